@@ -0,0 +1,86 @@
+//! Micro-benchmarks of the core structures of SGA operators, run with `cargo bench --bench core`
+use criterion::{BatchSize, black_box, Criterion, criterion_group, criterion_main};
+
+use sgraffito_query::test_support::model::ModelGenerator;
+use sgraffito_query::operator::MinPQIndex;
+use sgraffito_query::operator::rpq::ProductGraphHook;
+use sgraffito_query::query::automata::{determinize, minimize};
+use sgraffito_query::query::parser::RPQParser;
+use sgraffito_query::util::types::{HalfOpenTimeInterval, OperationType};
+
+const QUERIES: [(&str, &str); 3] = [("concatenation", "a/b/c"), ("kleene", "(a/b)+"), ("nested", "((a|b)/c*)+/(b|c)*")];
+
+/// priorities of `count` entries in a scrambled order
+fn priorities(count: u64) -> impl Iterator<Item=(u64, u64)> {
+    (0..count).map(move |key| (key, key.wrapping_mul(7919) % count))
+}
+
+fn filled_index(count: u64) -> MinPQIndex<u64, u64> {
+    let mut index = MinPQIndex::default();
+    priorities(count).for_each(|(key, priority)| { index.push(key, key, priority); });
+    index
+}
+
+fn min_pq_index(c: &mut Criterion) {
+    c.bench_function("min_pq_index/push", |b| b.iter(|| filled_index(black_box(10_000))));
+    c.bench_function("min_pq_index/pop", |b| b.iter_batched(|| filled_index(10_000), |mut index| {
+        while let Some(entry) = index.pop() {
+            black_box(entry);
+        }
+    }, BatchSize::SmallInput));
+    c.bench_function("min_pq_index/change_priority", |b| b.iter_batched(|| filled_index(10_000), |mut index| {
+        priorities(10_000).for_each(|(key, priority)| { index.change_priority(&key, 10_000 - priority); });
+        index
+    }, BatchSize::SmallInput));
+}
+
+/// random edges over 1000 vertices with labels of `QUERIES`, whose timestamps advance by at most 1
+fn random_edges(count: usize) -> Vec<(u64, String, u64, HalfOpenTimeInterval)> {
+    ModelGenerator::new(42).edges(count, 1000, &["a", "b", "c"], 1).into_iter()
+        .map(|sge| (sge.source, sge.label, sge.target, HalfOpenTimeInterval::new(sge.timestamp, sge.timestamp + 1000)))
+        .collect()
+}
+
+fn filled_graph(edges: &[(u64, String, u64, HalfOpenTimeInterval)], query: &str, approach: OperationType) -> ProductGraphHook {
+    let mut graph = ProductGraphHook::new(query, approach);
+    edges.iter().cloned().for_each(|(source, label, target, interval)| { graph.insert_edge(source, label, target, interval); });
+    graph
+}
+
+fn graph(c: &mut Criterion) {
+    let edges = random_edges(10_000);
+    c.bench_function("graph/insert_edge/direct", |b| b.iter(|| filled_graph(&edges, "(a/b)+", OperationType::Direct)));
+    c.bench_function("graph/insert_edge/negative_tuple", |b| b.iter(|| filled_graph(&edges, "(a/b)+", OperationType::NegativeTuple)));
+    c.bench_function("graph/remove_edges", |b| b.iter_batched(|| filled_graph(&edges, "(a/b)+", OperationType::Direct), |mut graph| {
+        // expire the window in 10 slides
+        for slide in 1..=10 {
+            black_box(graph.remove_edges(slide * 1_000));
+        }
+        graph
+    }, BatchSize::LargeInput));
+}
+
+fn automata(c: &mut Criterion) {
+    let parser = RPQParser::new();
+    for (name, query) in QUERIES.iter() {
+        c.bench_function(&format!("parse_rpq/{}", name), |b| b.iter(|| parser.parse_rpq(black_box(query)).unwrap()));
+
+        let tree = parser.parse_regex(query).unwrap();
+        c.bench_function(&format!("determinize/{}", name), |b| b.iter_batched(|| tree.thompson(), determinize, BatchSize::SmallInput));
+        c.bench_function(&format!("minimize/{}", name), |b| b.iter_batched(|| determinize(tree.thompson()), minimize, BatchSize::SmallInput));
+    }
+}
+
+/// spanning tree expansion, i.e., `tree_expand`, of a tree rooted at each vertex over the product graph of a random window
+fn tree_expand(c: &mut Criterion) {
+    let edges = random_edges(2_000);
+    let mut group = c.benchmark_group("tree_expand");
+    for (name, query) in QUERIES.iter() {
+        let graph = filled_graph(&edges, query, OperationType::Direct);
+        group.bench_function(*name, |b| b.iter(|| graph.expand_trees()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, min_pq_index, graph, automata, tree_expand);
+criterion_main!(benches);
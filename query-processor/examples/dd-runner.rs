@@ -1,22 +1,40 @@
-extern crate differential_dataflow;
 extern crate timely;
 
-use std::collections::VecDeque;
-use std::thread;
-use std::time::Duration;
-
+// memory samples report allocated bytes only if jemalloc is the global allocator, see `util::memory`
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
 
-use differential_dataflow::input::InputSession;
+use std::sync::Arc;
+use std::time::Duration;
 
-use log::{info, trace};
-use metrics_runtime::Receiver;
+use log::{trace, warn};
+use metrics_runtime::{Receiver, Sink};
 
+use timely::communication::Allocate;
 use timely::dataflow::operators::probe::Handle;
-
-use sgraffito_query::input::{GraphEdge, InputFileReader, LineFileReader, StreamingGraphEdge};
-use sgraffito_query::util::metrics::csv_builder::CSVBuilder;
+use timely::worker::Worker;
+
+use sgraffito_query::dd::{DistinctGranularity, SharedArrangements, WindowedInput, DD_DISTINCT_VARIABLE};
+use sgraffito_query::ingest::{ReplayDriver, ReplayHandler, Simulation, Slide, BACKPRESSURE_VARIABLE, SIMULATION_VARIABLE, SLIDE_COALESCING_VARIABLE};
+use sgraffito_query::input::{InputFileReader, InputPartitioner, InputStreamKind, LineFileReader, Partitioning, StreamingGraphEdge, VertexDictionary, VertexEncoding, VertexIds, DICTIONARY_FILE_VARIABLE, VERTEX_ENCODING_VARIABLE};
+use sgraffito_query::util::gauges;
+use sgraffito_query::util::memory::{self, MEMORY_STATS_VARIABLE};
+use sgraffito_query::util::metrics::{QUERY_LABEL, WORKER_LABEL};
+use sgraffito_query::util::metrics::summary::{RunSummary, SUMMARY_STATE_VARIABLE};
+use sgraffito_query::util::clock::{Clock, system_clock};
+use sgraffito_query::util::metrics::csv_builder::{CSVBuilder, HistogramMode, HISTOGRAM_MODE_VARIABLE};
 use sgraffito_query::util::metrics::csv_exporter::CSVExporter;
-use sgraffito_query::util::types::REPORTING_PERIOD_MILLISECONDS;
+use sgraffito_query::input::labels::{CASE_INSENSITIVE_LABELS_VARIABLE, LabelMapping, LABEL_MAP_VARIABLE, UNDIRECTED_LABELS_VARIABLE};
+use sgraffito_query::input::vertex_filter::VertexFilter;
+use sgraffito_query::input::vertex_types::{VertexTypes, VERTEX_TYPES_VARIABLE};
+use sgraffito_query::input::watermark::{WatermarkSource, WATERMARK_INTERVAL_VARIABLE};
+use sgraffito_query::input::dedup::{DedupMode, EdgeDeduplicator, DEDUP_VARIABLE};
+use sgraffito_query::util::types::{DETERMINISTIC_VARIABLE, REPORTING_PERIOD_MILLISECONDS, set_deterministic_mode};
+use sgraffito_query::util::phase::{PhaseSchedule, PHASE_LABEL};
+use sgraffito_query::util::shutdown::install_signal_handler;
+use sgraffito_query::util::distributed::DistributedOptions;
+use sgraffito_query::admin::{AdminState, RegisteredQuery};
 
 use sgraffito_query::query::query_library::DDQueryLibrary;
 
@@ -29,6 +47,7 @@ use sgraffito_query::query::query_library::DDQueryLibrary;
 /// 6. query name: name of the query to be executed
 /// 7. arg_count: # of edge predicates that are required by the `query`
 /// 8. space seperated list of edge predicates
+/// 9. optional execution options `[-w workers] [-n processes] [-p process] [-h hostfile] [--partition line|hash|range] [--vertices start..end|file]`
 fn main() {
     let mut args = std::env::args();
     args.next();
@@ -48,79 +67,167 @@ fn main() {
         edge_predicates.push(args.next().unwrap());
     }
 
+    // remaining arguments configure the number of workers and processes
+    let options = DistributedOptions::from_args(args).unwrap_or_else(|e| panic!("Invalid distributed execution options: {}", e));
+    let partitioning = options.partitioning;
+
+    let input_kind = InputStreamKind::from_name(&input_type_name).unwrap_or_else(|| panic!("Input type {} is not valid", input_type_name));
+
 
     // initialize env_logger
     env_logger::init();
 
+    // SIGINT/SIGTERM end the stream gracefully
+    install_signal_handler();
+
+    // deterministic execution mode for reproducible runs, enabled via environment variable
+    if std::env::var(DETERMINISTIC_VARIABLE).is_ok() {
+        set_deterministic_mode(true);
+    }
+
+    // samples of the memory usage of the process per slide, enabled via environment variable
+    let sample_memory = std::env::var(MEMORY_STATS_VARIABLE).is_ok();
+
+    // de-duplication of input edges, enabled via environment variable
+    let dedup_mode = std::env::var(DEDUP_VARIABLE).ok()
+        .map(|name| DedupMode::from_name(&name).unwrap_or_else(|| panic!("{} must be `exact` or a time horizon, found {}", DEDUP_VARIABLE, name)));
+    if dedup_mode.is_some() && partitioning == Partitioning::ByteRange {
+        warn!("Duplicates are dropped only within the byte range of each worker with byte range partitioning");
+    }
+
+    // throttling of the input once too many edges are in flight, enabled via environment variable
+    let max_in_flight: Option<u64> = std::env::var(BACKPRESSURE_VARIABLE).ok()
+        .map(|size| size.parse().unwrap_or_else(|_| panic!("{} must be a non-negative integer, found {}", BACKPRESSURE_VARIABLE, size)));
+
+    // processing budget of a slide, after which following slides are coalesced, enabled via environment variable
+    let slide_budget = std::env::var(SLIDE_COALESCING_VARIABLE).ok()
+        .map(|budget| Duration::from_millis(budget.parse().unwrap_or_else(|_| panic!("{} must be a non-negative integer, found {}", SLIDE_COALESCING_VARIABLE, budget))));
+
+    // virtual time of the replay and synthetic processing delays, enabled via environment variable
+    let simulation = std::env::var(SIMULATION_VARIABLE).ok()
+        .map(|spec| Simulation::from_spec(&spec).unwrap_or_else(|e| panic!("{}", e)));
+
+    // where queries deduplicate results of their joins, after every join by default
+    let distinct = std::env::var(DD_DISTINCT_VARIABLE).ok()
+        .map(|name| DistinctGranularity::from_name(&name).unwrap_or_else(|| panic!("{} must be `per-join`, `final-only` or `none`, found {}", DD_DISTINCT_VARIABLE, name)))
+        .unwrap_or_default();
+
+    // watermarks that trigger slides during input gaps, enabled via environment variable
+    let watermark_interval = std::env::var(WATERMARK_INTERVAL_VARIABLE).ok()
+        .map(|interval| Duration::from_millis(interval.parse().unwrap_or_else(|_| panic!("{} must be a non-negative integer, found {}", WATERMARK_INTERVAL_VARIABLE, interval))));
+
+    // rewriting of edge labels, enabled via environment variables
+    let mut label_mapping = std::env::var(LABEL_MAP_VARIABLE).ok()
+        .map(|path| LabelMapping::from_file(&path).unwrap_or_else(|e| panic!("{}", e)));
+    if std::env::var(CASE_INSENSITIVE_LABELS_VARIABLE).is_ok() {
+        label_mapping = Some(label_mapping.unwrap_or_default().case_insensitive(true));
+    }
+    if let Ok(labels) = std::env::var(UNDIRECTED_LABELS_VARIABLE) {
+        label_mapping = Some(labels.split(',').fold(label_mapping.unwrap_or_default(), |mapping, label| {
+            let label = mapping.canonicalize(label);
+            mapping.undirected(&label)
+        }));
+    }
+    // edge predicates are canonicalized as labels of edges, which are used for stream partitioning and automaton labels
+    let edge_predicates: Vec<String> = match &label_mapping {
+        Some(mapping) => edge_predicates.iter().map(|predicate| mapping.canonicalize(predicate)).collect(),
+        None => edge_predicates,
+    };
+    let label_mapping = label_mapping.map(|mapping| mapping.with_alphabet(edge_predicates.iter().cloned().collect()));
+
+    // encoding of string vertex identifiers, hashing by default
+    let vertex_encoding = std::env::var(VERTEX_ENCODING_VARIABLE).ok()
+        .map(|name| VertexEncoding::from_name(&name).unwrap_or_else(|| panic!("{} must be one of `hash`, `checked` or `dense`, found {}", VERTEX_ENCODING_VARIABLE, name)))
+        .unwrap_or(VertexEncoding::Hash);
+    // dictionary that is reloaded from and persisted to a file, so that vertex identifiers remain stable across runs
+    let dictionary_file = std::env::var(DICTIONARY_FILE_VARIABLE).ok();
+    let persisted_dictionary = dictionary_file.as_ref()
+        .map(|path| VertexDictionary::load_or_new(path).unwrap_or_else(|e| panic!("{}", e)));
+    let dictionary = persisted_dictionary.clone().or_else(|| if vertex_encoding.requires_dictionary() { Some(VertexDictionary::new()) } else { None });
+
+    // vertex files resolve identifiers as the readers, i.e., through the same dictionary for dense identifiers
+    let vertex_ids = match &dictionary {
+        Some(dictionary) => VertexIds::new(input_kind.has_integer_ids()).with_vertex_encoding(vertex_encoding, dictionary.clone()),
+        None => VertexIds::new(input_kind.has_integer_ids()),
+    };
+    // restriction of the input to a subgraph, if it is given
+    let vertex_filter = options.vertex_filter.as_ref()
+        .map(|spec| VertexFilter::from_spec(spec, &vertex_ids).unwrap_or_else(|e| panic!("Invalid vertex filter: {}", e)));
+    // endpoint types in edge labels, enabled via environment variable
+    let vertex_types = std::env::var(VERTEX_TYPES_VARIABLE).ok()
+        .map(|path| VertexTypes::from_file(&path, &vertex_ids).unwrap_or_else(|e| panic!("{}", e)));
+
+    // percentiles are reported over all values since the start unless another histogram mode is given
+    let histogram_mode = std::env::var(HISTOGRAM_MODE_VARIABLE).ok()
+        .map(|name| HistogramMode::from_name(&name).unwrap_or_else(|| panic!("{} must be one of `cumulative`, `interval` or `rolling:<periods>`, found {}", HISTOGRAM_MODE_VARIABLE, name)))
+        .unwrap_or(HistogramMode::Cumulative);
+
+    // processing time of latencies and metric reports
+    let clock: Arc<dyn Clock> = system_clock();
+
     // initialize runtime and metric logger
     let receiver = Receiver::builder().build().expect("failed to create receiver");
     let mut exporter = CSVExporter::new(
         receiver.controller(),
-        CSVBuilder::default(),
+        CSVBuilder::default().with_histogram_mode(histogram_mode).with_clock(clock.clone()),
         &reporting_file,
         Duration::from_millis(REPORTING_PERIOD_MILLISECONDS)
     );
 
-    // spawn a bakcground thread to run metric logger
-    thread::spawn(move || exporter.run());
+    // state exposed by the admin endpoint, which is served only if it is enabled and an address is given
+    let admin = Arc::new(AdminState::new());
+    admin.register_query(RegisteredQuery {
+        name: query_name.clone(),
+        engine: "dd".to_string(),
+        predicates: edge_predicates.clone(),
+        explain: None,
+    });
+    #[cfg(feature = "admin")]
+    {
+        if let Ok(address) = std::env::var(sgraffito_query::admin::ADMIN_ADDRESS_VARIABLE) {
+            sgraffito_query::admin::serve(admin.clone(), &address).unwrap_or_else(|e| panic!("{}", e));
+            gauges::enable();
+        }
+    }
+
+    // spawn a bakcground thread to run metric logger until the end of execution
+    let exporter_thread = exporter.spawn();
+    // metrics are flushed at the end of stream without waiting for the reporting period
+    let exporter_flusher = exporter_thread.flusher();
+
+    // totals of the run, and state sizes of operators for their peaks
+    let run_summary = RunSummary::new();
+    let final_summary = run_summary.clone();
+    let run_start = clock.now();
+    let run_clock = clock.clone();
+    // peak state sizes of the run summary are read from gauges, which are recorded only if they have a consumer
+    if std::env::var(SUMMARY_STATE_VARIABLE).is_ok() {
+        gauges::enable();
+    }
 
     // define a new computational scope, in which to run BFS
-    timely::execute_from_args(std::env::args().skip(7), move |worker| {
+    timely::execute_from_args(options.timely_args().into_iter(), move |worker| {
         // initialize sink
         let mut sink = receiver.sink();
+        // metrics of each worker are tagged with its index and the query
+        sink.add_default_labels(vec![(WORKER_LABEL, worker.index().to_string()), (QUERY_LABEL, query_name.clone())]);
 
 
-        let mut input1 = InputSession::new();
+        // expired edges are retracted as the input advances
+        let mut input1 = WindowedInput::sliding(window_size);
         let mut probe = Handle::new();
 
-        let mut batch_start = sink.now();
-
-        let timer = ::std::time::Instant::now();
+        let timer = clock.now();
 
         let query_arguments = edge_predicates.clone();
         // create a TC differential dataflow
         worker.dataflow::<u64,_,_>(|scope| {
 
             let input_stream = input1.to_collection(scope);
+            let mut shared = SharedArrangements::new(&input_stream, &query_arguments).with_distinct(distinct);
 
-            let result = match query_name.as_str() {
-                "join" => {
-                    DDQueryLibrary::hash_join(input_stream, query_arguments, "join".to_string())
-                },
-                "query1" => {
-                    DDQueryLibrary::query1(input_stream, query_arguments, "q1".to_string())
-                },
-                "query2" => {
-                    DDQueryLibrary::query2(input_stream, query_arguments, "q2".to_string())
-                },
-                "query3" => {
-                    DDQueryLibrary::query3(input_stream, query_arguments, "q3".to_string())
-                },
-                "query4" => {
-                    DDQueryLibrary::query4(input_stream, query_arguments, "q4".to_string())
-                },
-                "query5" => {
-                    DDQueryLibrary::query5(input_stream, query_arguments, "q5".to_string())
-                },
-                "query6" => {
-                    DDQueryLibrary::query6(input_stream, query_arguments, "q6".to_string())
-                },
-                "query6-cq" => {
-                    DDQueryLibrary::query6_cq(input_stream, query_arguments, "q6".to_string())
-                },
-                "query7" => {
-                    DDQueryLibrary::query7(input_stream, query_arguments, "q7".to_string())
-                },
-                "query7-cq" => {
-                    DDQueryLibrary::query7_cq(input_stream, query_arguments, "q7".to_string())
-                },
-                "query8" => {
-                    DDQueryLibrary::query8(input_stream, query_arguments, "q8".to_string())
-                },
-                _ => {
-                    panic!("Supplied query name is not defined: {}", &query_name);
-                }
-            };
+            let result = DDQueryLibrary::by_name_shared(&query_name, &mut shared, query_arguments)
+                .unwrap_or_else(|| panic!("Supplied query name is not defined: {}", &query_name));
 
             result.inspect(|x| trace!("Query result {:?}", x))
                 .probe_with(&mut probe);
@@ -128,106 +235,150 @@ fn main() {
         });
 
         // read graph data from file
-        let reader = match input_type_name.as_str() {
-            "i" => LineFileReader::open(&filename, false, true).expect("Cannot open input graph file"),
-            "it" => LineFileReader::open(&filename, true, true).expect("Cannot open input graph file"),
-            "s" => LineFileReader::open(&filename, false, false).expect("Cannot open input graph file"),
-            "st" => LineFileReader::open(&filename, true, false).expect("Cannot open input graph file"),
-            _ => panic!("Input type {} is not valid", input_type_name)
+        let reader = match partitioning {
+            // each worker reads only its own range of the input file
+            Partitioning::ByteRange => LineFileReader::open_partition(&filename, input_kind.is_timestamped(), input_kind.has_integer_ids(), worker.index(), worker.peers()),
+            _ => LineFileReader::open(&filename, input_kind.is_timestamped(), input_kind.has_integer_ids()),
+        }.expect("Cannot open input graph file");
+        let reader = match &dictionary {
+            Some(dictionary) => reader.with_vertex_encoding(vertex_encoding, dictionary.clone()),
+            None => reader,
         };
 
-        // Vector to store window content
-        let mut window_content1: VecDeque<StreamingGraphEdge> = VecDeque::new();
-
         let start_time = reader.get_start_timestamp();
+        // by default, the first slide is the warm-up phase and measurements continue until the end of the input
+        let schedule = PhaseSchedule::from_env(start_time, slide_size);
+
+        // each worker sends a disjoint subset of the input
+        let partitioner = InputPartitioner::new(partitioning, worker.index(), worker.peers());
+
+        let mut driver = ReplayDriver::new(start_time, slide_size, schedule, &edge_predicates, partitioner)
+            .with_label_mapping(label_mapping.as_ref())
+            .with_vertex_filter(vertex_filter.as_ref())
+            .with_vertex_types(vertex_types.as_ref())
+            .with_deduplicator(dedup_mode.map(EdgeDeduplicator::new))
+            .with_backpressure(max_in_flight)
+            .with_slide_coalescing(slide_budget)
+            .with_clock(clock.clone())
+            .with_simulation(simulation);
+
+        // latencies are measured in virtual time in simulation mode
+        let replay_clock = driver.clock();
+        let batch_start = replay_clock.nanos();
+        let mut replay = DdReplay { worker, summary: &run_summary, sample_memory, input: input1, probe, sink, admin: &admin, clock: replay_clock.as_ref(), batch_start };
+        let summary = match watermark_interval {
+            Some(interval) => driver.run_events(WatermarkSource::spawn(reader, interval), &mut replay),
+            None => driver.run(reader, &mut replay),
+        };
+        let DdReplay { worker, input: input1, probe, mut sink, .. } = replay;
 
-        let mut total_edge_counter = 0;
-        let mut processed_edge_counter = 0;
-        let mut last_batch_process = start_time;
-        let mut edge_ts = 0;
-
-        let mut first_window = true;
-
-        for sge in reader {
-            trace!("Next sgt from input stream {:?}", sge);
-            total_edge_counter += 1;
-
-            let edge_predicate = sge.get_label();
-            edge_ts = sge.timestamp;
-
-            // do not start computation and measurements until slide is full for the first time
-            if edge_ts - start_time >= slide_size {
-                // perform first flush
-                if first_window {
-                    first_window = false;
-
-                    // update batch process marker
-                    last_batch_process = edge_ts;
-                    // update edge_counter for accurate measuring
-                    total_edge_counter = 0;
-                    processed_edge_counter = 0;
-
-                    // advance input so that computation is performed
-                    input1.advance_to(edge_ts);
-                    input1.flush();
-                    worker.step_while(|| probe.less_than(input1.time()));
-                    info!("Window is fully populated at {} after {} secs", edge_ts, timer.elapsed().as_secs());
-                } else if edge_ts - last_batch_process >= slide_size {
-                    // perform window slide and measure elapsed time
-                    trace!("Slide at {}", edge_ts);
-                    last_batch_process = edge_ts;
-
-                    // determine expired tuples from the window content and push negative tuples
-                    while !window_content1.is_empty() {
-                        let sgt = window_content1.pop_front().unwrap();
-                        if sgt.timestamp + window_size <= edge_ts {
-                            input1.update(sgt.clone(), -1);
-                        } else {
-                            // place it back to window and break
-                            window_content1.push_front(sgt);
-                            break;
-                        }
-                    }
-
-                    // computation timer
-                    let start = sink.now();
-
-                    // advance time to trigger computation
-                    input1.advance_to(edge_ts);
-                    input1.flush();
-                    worker.step_while(|| probe.less_than(input1.time()));
-                    info!("Input advance to: {} after {} secs", edge_ts, timer.elapsed().as_secs());
-
-                    sink.record_timing("batch-latency", start, sink.now());
-                    sink.record_value("batch-size", processed_edge_counter);
-                    sink.record_timing("total-latency", batch_start, sink.now());
-                    sink.record_value("total-size", total_edge_counter);
-
-                    // reset edge-counter to count #of edges for next batch
-                    processed_edge_counter = 0;
-                    total_edge_counter = 0;
-
-                    // reset timer
-                    batch_start = sink.now();
-                }
+        // report the final window size
+        sink.record_value("final-window-size", input1.len() as u64);
+
+        // close input1 so that frontiers of all operators become empty and all standing tuples are processed
+        input1.close();
+        worker.step_while(|| !probe.done());
+        trace!("Input processing has ended {}", summary.last_timestamp);
+        run_summary.add_edges(summary.sent);
+
+        // measure total time to execute the entire input
+        sink.record_value("total-time", (clock.now() - timer).as_secs());
+        if let Some(dictionary) = &dictionary {
+            sink.record_value("vertex-collisions", dictionary.collisions());
+        }
+        if worker.index() == 0 {
+            exporter_flusher.flush_now();
+        }
+    }).unwrap();
+
+    // flush final metrics
+    exporter_thread.stop();
+
+    if let (Some(path), Some(dictionary)) = (&dictionary_file, &persisted_dictionary) {
+        dictionary.save(path).unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    final_summary.write(&reporting_file, run_clock.now() - run_start).unwrap_or_else(|e| panic!("{}", e));
+}
+
+
+/// Dataflow of a worker that is fed by the `ReplayDriver`
+struct DdReplay<'w, A: Allocate> {
+    worker: &'w mut Worker<A>,
+    summary: &'w RunSummary,
+    sample_memory: bool,
+    input: WindowedInput,
+    probe: Handle<u64>,
+    sink: Sink,
+    admin: &'w AdminState,
+    clock: &'w dyn Clock,
+    batch_start: u64,
+}
+
+impl<'w, A: Allocate> ReplayHandler for DdReplay<'w, A> {
+    fn observe(&mut self, event_time: u64) {
+        self.admin.set_event_time(event_time);
+    }
+
+    fn send(&mut self, sge: StreamingGraphEdge) {
+        // update input collection and the window content
+        self.input.insert(sge);
+    }
+
+    fn slide(&mut self, slide: &Slide) {
+        let labels = [(PHASE_LABEL, slide.phase.as_str())];
+
+        // computation timer
+        let start = self.clock.nanos();
+
+        // advance time to retract expired edges and trigger computation
+        self.input.advance_to(slide.time);
+        self.input.flush();
+        let (input, probe) = (&self.input, &self.probe);
+        self.worker.step_while(|| probe.less_than(input.time()));
+        if self.worker.index() == 0 {
+            self.admin.set_frontier(slide.time);
+            if gauges::is_enabled() {
+                self.summary.observe_state(&gauges::snapshot());
             }
+        }
 
-            // propagate update only if its used by the query
-            if edge_predicates.iter().any(|p| p == edge_predicate) {
-                // update input collection and the window content
-                input1.update(sge.clone(), 1);
-                window_content1.push_back(sge);
-                processed_edge_counter += 1;
+        let sink = &mut self.sink;
+        sink.record_timing_with_labels("batch-latency", start, self.clock.nanos(), &labels[..]);
+        sink.record_value_with_labels("batch-size", slide.batch_size, &labels[..]);
+        sink.record_timing_with_labels("total-latency", self.batch_start, self.clock.nanos(), &labels[..]);
+        sink.record_value_with_labels("total-size", slide.total_size, &labels[..]);
+        if let Some(duplicates_dropped) = slide.duplicates_dropped {
+            sink.record_value_with_labels("duplicates-dropped", duplicates_dropped, &labels[..]);
+        }
+        if let Some(backpressure) = slide.backpressure {
+            sink.record_value_with_labels("backpressure-throttles", backpressure.throttles, &labels[..]);
+            sink.record_value_with_labels("backpressure-stalled", backpressure.stalled.as_nanos() as u64, &labels[..]);
+        }
+        if let Some(coalesced) = slide.coalesced {
+            sink.record_value_with_labels("coalesced-slides", coalesced, &labels[..]);
+        }
+        if let Some(virtual_arrival) = slide.virtual_arrival {
+            sink.record_value_with_labels("virtual-latency", self.clock.now().checked_sub(virtual_arrival).unwrap_or_default().as_nanos() as u64, &labels[..]);
+        }
+        // memory is process-wide, so it is sampled once per process
+        if self.sample_memory && self.worker.index() == 0 {
+            if let Some(stats) = memory::sample() {
+                sink.record_value_with_labels("memory-resident", stats.resident, &labels[..]);
+                if let Some(allocated) = stats.allocated {
+                    sink.record_value_with_labels("memory-allocated", allocated, &labels[..]);
+                }
             }
         }
-        // advance input1 to last seen edge_ts to ensure all standing tuples are processed
-        input1.advance_to(edge_ts + 1);
-        input1.flush();
-        worker.step_while(|| probe.less_than(input1.time()));
-        trace!("Input processing has ended {}", edge_ts);
 
-        // measure total time to execute the entire input
-        sink.record_value("total-time", timer.elapsed().as_secs());
-    }).unwrap();
-    thread::sleep(Duration::from_millis(REPORTING_PERIOD_MILLISECONDS));
+        // reset timer
+        self.batch_start = self.clock.nanos();
+    }
+
+    fn catch_up(&mut self, time: u64) {
+        self.input.advance_to(time);
+        self.input.flush();
+        let (input, probe) = (&self.input, &self.probe);
+        self.worker.step_while(|| probe.less_than(input.time()));
+    }
 }
@@ -0,0 +1,44 @@
+use std::time::Instant;
+
+use sgraffito_query::query::automata::{determinize, minimize};
+use sgraffito_query::query::automata::regex::NFAConstruction;
+use sgraffito_query::query::parser::RPQParser;
+
+/// Compares NFA constructions by the time to construct, determinize and minimize the automata of complex queries. Arguments
+/// 1. optional # of repetitions for each query, default is 100
+/// 2. optional list of queries, queries with many alternations and closures are used by default
+fn main() {
+    let mut args = std::env::args();
+    args.next();
+
+    let repetitions: u32 = args.next().map_or(100, |r| r.parse().unwrap());
+    let mut queries: Vec<String> = args.collect();
+    if queries.is_empty() {
+        queries = vec![
+            "(a/b/c)+".to_string(),
+            "a/(b|c|d)*/e".to_string(),
+            "((a|b)/(c|d))+/(e|f)*".to_string(),
+            "(a*/b*/c*/d*)+".to_string(),
+            "((a/b)*|(c/d)+|(e/f/g)*)+/h".to_string(),
+        ];
+    }
+
+    let parser = RPQParser::new();
+    for query in queries {
+        let tree = parser.parse_regex(&query).unwrap_or_else(|e| panic!("Cannot parse {}: {}", query, e));
+
+        for construction in vec![NFAConstruction::Thompson, NFAConstruction::Glushkov] {
+            let nfa = tree.to_nfa(construction);
+            let nfa_states = nfa.num_states;
+
+            let start = Instant::now();
+            let mut dfa = None;
+            for _ in 0..repetitions {
+                dfa = Some(minimize(determinize(tree.to_nfa(construction))));
+            }
+            let elapsed = start.elapsed() / repetitions;
+
+            println!("{}\t{:?}\tnfa-states: {}\tdfa-states: {}\tdeterminization: {:?}", query, construction, nfa_states, dfa.unwrap().num_states, elapsed);
+        }
+    }
+}
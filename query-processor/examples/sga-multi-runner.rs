@@ -0,0 +1,214 @@
+extern crate timely;
+
+use std::iter::Iterator;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use timely::dataflow::*;
+use timely::dataflow::operators::{Input, Probe, Inspect};
+
+use sgraffito_query::operator::{window::SlidingWindow};
+use sgraffito_query::input::{SGE, StreamingGraphEdge, LineFileReader, InputFileReader, InputPartitioner, InputStreamKind, Partitioning};
+use sgraffito_query::input::multi_stream::AlignedStreams;
+
+use log::{info, trace};
+
+use metrics_runtime::Receiver;
+
+use sgraffito_query::util::types::{configure_from_env, REPORTING_PERIOD_MILLISECONDS};
+use sgraffito_query::util::phase::{PhaseSchedule, RunPhase, PHASE_LABEL};
+use sgraffito_query::util::shutdown::{install_signal_handler, is_shutdown_requested};
+use sgraffito_query::util::distributed::DistributedOptions;
+use sgraffito_query::util::metrics::csv_exporter::CSVExporter;
+use sgraffito_query::util::metrics::{QUERY_LABEL, WORKER_LABEL};
+use sgraffito_query::util::clock::{Clock, system_clock};
+use sgraffito_query::util::metrics::csv_builder::{CSVBuilder, HistogramMode, HISTOGRAM_MODE_VARIABLE};
+use sgraffito_query::query::query_library::SGAQueryLibrary;
+
+/// Utility to run StreamingGraphQueries over multiple independent input streams, one stream per edge predicate. Arguments
+/// 1. window size
+/// 2. slide size
+/// 3. Input type: allowed values are `{st, it}` where `s`, `i` represent string or integer vertex identifiers, input files must be timestamped
+/// 4. reporting file: Absolute path where metrics will be recorded
+/// 5. query name: name of the query to be executed
+/// 6. arg_count: # of edge predicates that are required by the `query`
+/// 7. space seperated list of edge predicate and input file pairs, where each file is the stream of the preceding predicate
+/// 8. optional distributed execution options `[-w workers] [-n processes] [-p process] [-h hostfile] [--partition line|hash]`
+///
+/// Each stream progresses independently, and a slide is triggered once all streams that are not exhausted have passed the slide boundary
+fn main() {
+    let mut args = std::env::args();
+    args.next();
+
+    let window_size: u64 = args.next().unwrap().parse().unwrap();
+    let slide_size: u64 = args.next().unwrap().parse().unwrap();
+    let input_type_name = args.next().unwrap();
+    let reporting_file = args.next().unwrap();
+    let query_name = args.next().unwrap();
+    let argument_count: usize = args.next().unwrap().parse().unwrap();
+
+    let mut edge_predicates = Vec::new();
+    let mut filenames = Vec::new();
+    // parse predicate and file pairs
+    for _i in 0..argument_count {
+        edge_predicates.push(args.next().unwrap());
+        filenames.push(args.next().unwrap());
+    }
+
+    // remaining arguments configure the number of workers and processes
+    let options = DistributedOptions::from_args(args).unwrap_or_else(|e| panic!("Invalid distributed execution options: {}", e));
+    let partitioning = options.partitioning;
+    if partitioning == Partitioning::ByteRange {
+        panic!("Byte range partitioning is not supported for multiple input streams");
+    }
+
+    let input_kind = InputStreamKind::from_name(&input_type_name).unwrap_or_else(|| panic!("Input type {} is not valid", input_type_name));
+    if !input_kind.is_timestamped() {
+        panic!("Multiple input streams require timestamped inputs to align their frontiers");
+    }
+
+    // initialize env_logger
+    env_logger::init();
+
+    // SIGINT/SIGTERM end the streams gracefully
+    install_signal_handler();
+
+    // modes and operator settings, e.g., deterministic execution or node caps of spanning trees, enabled via environment variables
+    configure_from_env(window_size, slide_size);
+
+    // percentiles are reported over all values since the start unless another histogram mode is given
+    let histogram_mode = std::env::var(HISTOGRAM_MODE_VARIABLE).ok()
+        .map(|name| HistogramMode::from_name(&name).unwrap_or_else(|| panic!("{} must be one of `cumulative`, `interval` or `rolling:<periods>`, found {}", HISTOGRAM_MODE_VARIABLE, name)))
+        .unwrap_or(HistogramMode::Cumulative);
+
+    // processing time of latencies and metric reports
+    let clock: Arc<dyn Clock> = system_clock();
+
+    // initialize runtime and metric logger
+    let receiver = Receiver::builder().build().expect("failed to create receiver");
+    let mut exporter = CSVExporter::new(
+        receiver.controller(),
+        CSVBuilder::default().with_histogram_mode(histogram_mode).with_clock(clock.clone()),
+        &reporting_file,
+        Duration::from_millis(REPORTING_PERIOD_MILLISECONDS)
+    );
+
+    // spawn a bakcground thread to run metric logger until the end of execution
+    let exporter_stop = Arc::new(AtomicBool::new(false));
+    let exporter_stop_flag = exporter_stop.clone();
+    let exporter_thread = thread::spawn(move || exporter.run_until(&exporter_stop_flag));
+
+    timely::execute_from_args(options.timely_args().into_iter(), move |worker| {
+
+        // one input per stream, so that each stream advances its own epochs
+        let mut inputs: Vec<Option<InputHandle<u64, StreamingGraphEdge>>> = filenames.iter().map(|_| Some(InputHandle::new())).collect();
+        let mut probe = ProbeHandle::new();
+
+        // initialize sink
+        let mut sink = receiver.sink();
+        // metrics of each worker are tagged with its index and the query
+        sink.add_default_labels(vec![(WORKER_LABEL, worker.index().to_string()), (QUERY_LABEL, query_name.clone())]);
+
+        let timer = clock.now();
+
+        let query_arguments = edge_predicates.clone();
+        worker.dataflow::<u64, _, _>(|scope| {
+            let windowed_streams = inputs.iter_mut()
+                .map(|input| scope.input_from(input.as_mut().unwrap()).sliding_window(window_size))
+                .collect();
+
+            let result = SGAQueryLibrary::by_streams(&query_name, windowed_streams, query_arguments)
+                .unwrap_or_else(|| panic!("Supplied query name is not defined: {}", &query_name));
+
+            result
+                .inspect(|x| trace!("Query result {:?}", x ))
+                .probe_with(&mut probe);
+        });
+
+        let readers: Vec<LineFileReader> = filenames.iter()
+            .map(|filename| LineFileReader::open(filename, input_kind.is_timestamped(), input_kind.has_integer_ids()).unwrap_or_else(|e| panic!("Cannot open input graph file {}: {}", filename, e)))
+            .collect();
+
+        // phases start at the earliest stream
+        let start_time = readers.iter().map(|reader| reader.get_start_timestamp()).min().unwrap();
+        let schedule = PhaseSchedule::from_env(start_time, slide_size);
+
+        let mut processed_edge_counter = 0;
+        let mut last_batch_process = start_time;
+        let mut batch_start = clock.nanos();
+
+        // each worker sends a disjoint subset of each stream
+        let partitioner = InputPartitioner::new(partitioning, worker.index(), worker.peers());
+        let mut positions = vec![0; filenames.len()];
+
+        let mut streams = AlignedStreams::new(readers);
+        while let Some((stream, sge)) = streams.next() {
+            if is_shutdown_requested() {
+                info!("Shutdown is requested after {} secs", (clock.now() - timer).as_secs());
+                break;
+            }
+
+            let position = positions[stream];
+            positions[stream] += 1;
+
+            // exhausted streams are closed so that they no longer hold back the frontier
+            for (index, input) in inputs.iter_mut().enumerate() {
+                if streams.aligner().is_closed(index) {
+                    if let Some(input) = input.take() {
+                        info!("Stream {} is exhausted at {:?}", index, streams.aligner().watermark(index));
+                        input.close();
+                    }
+                }
+            }
+
+            let input = inputs[stream].as_mut().unwrap();
+            if sge.get_timestamp() > *input.time() {
+                input.advance_to(sge.get_timestamp());
+            }
+
+            // a slide is completed once all open streams have passed its boundary
+            let frontier = streams.aligner().aligned_frontier().unwrap_or(sge.get_timestamp());
+            if schedule.phase_at(frontier) == RunPhase::Finished {
+                info!("Run is finished at {} after {} secs", frontier, (clock.now() - timer).as_secs());
+                break;
+            }
+
+            if frontier.saturating_sub(last_batch_process) >= slide_size {
+                let phase = schedule.phase_at(last_batch_process);
+                let labels = [(PHASE_LABEL, phase.as_str())];
+                last_batch_process = frontier;
+
+                let start = clock.nanos();
+                worker.step_while(|| probe.less_than(&frontier));
+                info!("Frontier aligned at {} in {:?} phase after {} secs", frontier, phase, (clock.now() - timer).as_secs());
+
+                sink.record_timing_with_labels("batch-latency", start, clock.nanos(), &labels[..]);
+                sink.record_value_with_labels("batch-size", processed_edge_counter, &labels[..]);
+                sink.record_timing_with_labels("total-latency", batch_start, clock.nanos(), &labels[..]);
+
+                processed_edge_counter = 0;
+                batch_start = clock.nanos();
+            }
+
+            if partitioner.owns(position, &sge) {
+                input.send(sge);
+                processed_edge_counter += 1;
+            }
+        }
+
+        // close remaining inputs so that all standing tuples are processed
+        for input in inputs.into_iter().flatten() {
+            input.close();
+        }
+        worker.step_while(|| !probe.done());
+
+        sink.record_value("total-time", (clock.now() - timer).as_secs());
+    }).unwrap(); // asserts error-free execution;
+
+    // flush final metrics
+    exporter_stop.store(true, Ordering::SeqCst);
+    exporter_thread.thread().unpark();
+    exporter_thread.join().expect("Metric logger has failed");
+}
@@ -1,33 +1,66 @@
 extern crate timely;
 
+// memory samples report allocated bytes only if jemalloc is the global allocator, see `util::memory`
+#[cfg(feature = "jemalloc")]
+#[global_allocator]
+static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
+
 use std::iter::Iterator;
-use std::thread;
+use std::sync::Arc;
 use std::time::Duration;
 
 use timely::dataflow::*;
 use timely::dataflow::operators::{Input, Probe, Inspect};
 
 use sgraffito_query::operator::{window::SlidingWindow};
-use sgraffito_query::input::{SGE, GraphEdge, StreamingGraphEdge, LineFileReader, InputFileReader};
-
-use log::{info, trace};
-
-use metrics_runtime::Receiver;
-
-use sgraffito_query::util::types::{REPORTING_PERIOD_MILLISECONDS};
+use timely::communication::Allocate;
+use timely::worker::Worker;
+
+use sgraffito_query::input::{StreamingGraphEdge, LineFileReader, InputFileReader, InputPartitioner, InputStreamKind, Partitioning, VertexDictionary, VertexEncoding, VertexIds, DICTIONARY_FILE_VARIABLE, VERTEX_ENCODING_VARIABLE};
+
+use log::{trace, warn};
+
+use metrics_runtime::{Receiver, Sink};
+
+use sgraffito_query::input::datasets::{DatasetReader, DatasetSchema};
+use sgraffito_query::input::labels::{CASE_INSENSITIVE_LABELS_VARIABLE, LabelMapping, LABEL_MAP_VARIABLE, UNDIRECTED_LABELS_VARIABLE};
+use sgraffito_query::input::vertex_filter::VertexFilter;
+use sgraffito_query::input::vertex_types::{VertexTypes, VERTEX_TYPES_VARIABLE};
+use sgraffito_query::input::watermark::{WatermarkSource, WATERMARK_INTERVAL_VARIABLE};
+use sgraffito_query::input::dedup::{DedupMode, EdgeDeduplicator, DEDUP_VARIABLE};
+use sgraffito_query::util::types::{configure_from_env, REPORTING_PERIOD_MILLISECONDS};
+use sgraffito_query::util::phase::{PhaseSchedule, PHASE_LABEL};
+use sgraffito_query::ingest::{ReplayDriver, ReplayHandler, Simulation, Slide, BACKPRESSURE_VARIABLE, SIMULATION_VARIABLE, SLIDE_COALESCING_VARIABLE};
+use sgraffito_query::sink::SinkTo;
+use sgraffito_query::sink::file::{CommitTo, EpochFileSink, RESULT_DIRECTORY_VARIABLE};
+use sgraffito_query::sink::cardinality::{CardinalitySink, RESULT_COUNT_DIRECTORY_VARIABLE};
+use sgraffito_query::sink::sampling::{SamplingSink, SAMPLE_SIZE_VARIABLE};
+use sgraffito_query::util::anomaly::{self, ANOMALY_DIRECTORY_VARIABLE, ANOMALY_THRESHOLD_VARIABLE};
+use sgraffito_query::util::state_counters::{self, STATE_COUNTERS_VARIABLE};
+use sgraffito_query::util::shutdown::install_signal_handler;
+use sgraffito_query::util::distributed::DistributedOptions;
+use sgraffito_query::admin::{AdminState, RegisteredQuery};
 use sgraffito_query::util::metrics::csv_exporter::CSVExporter;
-use sgraffito_query::util::metrics::csv_builder::CSVBuilder;
+use sgraffito_query::util::gauges;
+use sgraffito_query::util::hot_swap;
+use sgraffito_query::util::memory::{self, MEMORY_STATS_VARIABLE};
+use sgraffito_query::util::metrics::{QUERY_LABEL, WORKER_LABEL};
+use sgraffito_query::util::metrics::summary::{RunSummary, SUMMARY_STATE_VARIABLE};
+use sgraffito_query::util::clock::{Clock, system_clock};
+use sgraffito_query::util::metrics::csv_builder::{CSVBuilder, HistogramMode, HISTOGRAM_MODE_VARIABLE};
 use sgraffito_query::query::query_library::SGAQueryLibrary;
 
 /// Utility to run StreamingGraphQueries on SGA-based query processor prototype. Arguments
 /// 1. window size
 /// 2. slide size
-/// 3. Input type: allowed values are `{s, st, i, it}` where `s`, `i` represent string or integer vertex identifiers and `t` denotes a timestamped input file
+/// 3. Input type: allowed values are `{s, st, i, it}` where `s`, `i` represent string or integer vertex identifiers and `t` denotes a timestamped input file,
+///    or the name of a dataset preset, see `input::datasets`
 /// 4. filename: Absolute path for the input stream file
 /// 5. reporting file: Absolute path where metrics will be recorded
 /// 6. query name: name of the query to be executed
 /// 7. arg_count: # of edge predicates that are required by the `query`
 /// 8. space seperated list of edge predicates
+/// 9. optional execution options `[-w workers] [-n processes] [-p process] [-h hostfile] [--partition line|hash|range] [--vertices start..end|file]`
 fn main() {
     let mut args = std::env::args();
     args.next();
@@ -47,92 +80,241 @@ fn main() {
         edge_predicates.push(args.next().unwrap());
     }
 
+    // remaining arguments configure the number of workers and processes
+    let options = DistributedOptions::from_args(args).unwrap_or_else(|e| panic!("Invalid distributed execution options: {}", e));
+    let partitioning = options.partitioning;
+
+    // input type is either a line format or the name of a dataset preset
+    let dataset_schema = DatasetSchema::preset(&input_type_name);
+    let input_kind = match &dataset_schema {
+        Some(schema) => schema.input_kind(),
+        None => InputStreamKind::from_name(&input_type_name)
+            .unwrap_or_else(|| panic!("Input type {} is not valid, use one of `s`, `st`, `i`, `it` or a dataset preset {:?}", input_type_name, DatasetSchema::preset_names())),
+    };
+    if dataset_schema.is_some() && partitioning == Partitioning::ByteRange {
+        panic!("Byte range partitioning is not supported for dataset presets");
+    }
+
     // initialize env_logger
     env_logger::init();
 
+    // SIGINT/SIGTERM end the stream gracefully
+    install_signal_handler();
+
+    // modes and operator settings, e.g., deterministic execution or node caps of spanning trees, enabled via environment variables
+    configure_from_env(window_size, slide_size);
+
+    // automaton state visit counters of RPQ operators, enabled via environment variable
+    if std::env::var(STATE_COUNTERS_VARIABLE).is_ok() {
+        state_counters::enable();
+    }
+
+    // dumps of operator state once result counts deviate between windows, enabled via environment variables
+    let anomaly_threshold: Option<f64> = std::env::var(ANOMALY_DIRECTORY_VARIABLE).ok().map(|directory| {
+        anomaly::enable(&directory);
+        std::env::var(ANOMALY_THRESHOLD_VARIABLE).ok()
+            .map_or(1.0, |threshold| threshold.parse().unwrap_or_else(|_| panic!("{} must be a positive number, found {}", ANOMALY_THRESHOLD_VARIABLE, threshold)))
+    });
+
+    // samples of the memory usage of the process per slide, enabled via environment variable
+    let sample_memory = std::env::var(MEMORY_STATS_VARIABLE).is_ok();
+
+    // de-duplication of input edges, enabled via environment variable
+    let dedup_mode = std::env::var(DEDUP_VARIABLE).ok()
+        .map(|name| DedupMode::from_name(&name).unwrap_or_else(|| panic!("{} must be `exact` or a time horizon, found {}", DEDUP_VARIABLE, name)));
+    if dedup_mode.is_some() && partitioning == Partitioning::ByteRange {
+        warn!("Duplicates are dropped only within the byte range of each worker with byte range partitioning");
+    }
+
+    // throttling of the input once too many edges are in flight, enabled via environment variable
+    let max_in_flight: Option<u64> = std::env::var(BACKPRESSURE_VARIABLE).ok()
+        .map(|size| size.parse().unwrap_or_else(|_| panic!("{} must be a non-negative integer, found {}", BACKPRESSURE_VARIABLE, size)));
+
+    // processing budget of a slide, after which following slides are coalesced, enabled via environment variable
+    let slide_budget = std::env::var(SLIDE_COALESCING_VARIABLE).ok()
+        .map(|budget| Duration::from_millis(budget.parse().unwrap_or_else(|_| panic!("{} must be a non-negative integer, found {}", SLIDE_COALESCING_VARIABLE, budget))));
+
+    // virtual time of the replay and synthetic processing delays, enabled via environment variable
+    let simulation = std::env::var(SIMULATION_VARIABLE).ok()
+        .map(|spec| Simulation::from_spec(&spec).unwrap_or_else(|e| panic!("{}", e)));
+
+    // watermarks that trigger slides during input gaps, enabled via environment variable
+    let watermark_interval = std::env::var(WATERMARK_INTERVAL_VARIABLE).ok()
+        .map(|interval| Duration::from_millis(interval.parse().unwrap_or_else(|_| panic!("{} must be a non-negative integer, found {}", WATERMARK_INTERVAL_VARIABLE, interval))));
+
+    // rewriting of edge labels, enabled via environment variables
+    let mut label_mapping = std::env::var(LABEL_MAP_VARIABLE).ok()
+        .map(|path| LabelMapping::from_file(&path).unwrap_or_else(|e| panic!("{}", e)));
+    if std::env::var(CASE_INSENSITIVE_LABELS_VARIABLE).is_ok() {
+        label_mapping = Some(label_mapping.unwrap_or_default().case_insensitive(true));
+    }
+    if let Ok(labels) = std::env::var(UNDIRECTED_LABELS_VARIABLE) {
+        label_mapping = Some(labels.split(',').fold(label_mapping.unwrap_or_default(), |mapping, label| {
+            let label = mapping.canonicalize(label);
+            mapping.undirected(&label)
+        }));
+    }
+    // edge predicates are canonicalized as labels of edges, which are used for stream partitioning and automaton labels
+    let edge_predicates: Vec<String> = match &label_mapping {
+        Some(mapping) => edge_predicates.iter().map(|predicate| mapping.canonicalize(predicate)).collect(),
+        None => edge_predicates,
+    };
+    let label_mapping = label_mapping.map(|mapping| mapping.with_alphabet(SGAQueryLibrary::alphabet(&query_name, &edge_predicates).unwrap_or_else(|| panic!("Supplied query name is not defined: {}", &query_name))));
+
+    // exactly-once delivery of results into a directory, enabled via environment variable
+    let result_directory = std::env::var(RESULT_DIRECTORY_VARIABLE).ok();
+
+    // reservoir sample of results printed per slide, enabled via environment variable
+    let sample_size: Option<usize> = std::env::var(SAMPLE_SIZE_VARIABLE).ok()
+        .map(|size| size.parse().unwrap_or_else(|_| panic!("{} must be a non-negative integer, found {}", SAMPLE_SIZE_VARIABLE, size)));
+
+    // per-worker summary files of result counts per window, enabled via environment variable
+    let result_count_directory = std::env::var(RESULT_COUNT_DIRECTORY_VARIABLE).ok();
+    if let Some(directory) = &result_count_directory {
+        std::fs::create_dir_all(directory).unwrap_or_else(|e| panic!("Cannot create result count directory {}: {}", directory, e));
+    }
+
+    // percentiles are reported over all values since the start unless another histogram mode is given
+    let histogram_mode = std::env::var(HISTOGRAM_MODE_VARIABLE).ok()
+        .map(|name| HistogramMode::from_name(&name).unwrap_or_else(|| panic!("{} must be one of `cumulative`, `interval` or `rolling:<periods>`, found {}", HISTOGRAM_MODE_VARIABLE, name)))
+        .unwrap_or(HistogramMode::Cumulative);
+
+    // processing time of latencies and metric reports
+    let clock: Arc<dyn Clock> = system_clock();
+
     // initialize runtime and metric logger
     let receiver = Receiver::builder().build().expect("failed to create receiver");
     let mut exporter = CSVExporter::new(
         receiver.controller(),
-        CSVBuilder::default(),
+        CSVBuilder::default().with_histogram_mode(histogram_mode).with_clock(clock.clone()),
         &reporting_file,
         Duration::from_millis(REPORTING_PERIOD_MILLISECONDS)
     );
 
-    // spawn a bakcground thread to run metric logger
-    thread::spawn(move || exporter.run());
+    // state exposed by the admin endpoint, which is served only if it is enabled and an address is given
+    let admin = Arc::new(AdminState::new());
+    admin.register_query(RegisteredQuery {
+        name: query_name.clone(),
+        engine: "sga".to_string(),
+        predicates: edge_predicates.clone(),
+        explain: SGAQueryLibrary::explain(&query_name, &edge_predicates),
+    });
+    #[cfg(feature = "admin")]
+    {
+        if let Ok(address) = std::env::var(sgraffito_query::admin::ADMIN_ADDRESS_VARIABLE) {
+            sgraffito_query::admin::serve(admin.clone(), &address).unwrap_or_else(|e| panic!("{}", e));
+            gauges::enable();
+        }
+    }
+
+    // encoding of string vertex identifiers, hashing by default
+    let vertex_encoding = std::env::var(VERTEX_ENCODING_VARIABLE).ok()
+        .map(|name| VertexEncoding::from_name(&name).unwrap_or_else(|| panic!("{} must be one of `hash`, `checked` or `dense`, found {}", VERTEX_ENCODING_VARIABLE, name)))
+        .unwrap_or(VertexEncoding::Hash);
+
+    // dictionary that is reloaded from and persisted to a file, so that vertex identifiers remain stable across runs
+    let dictionary_file = std::env::var(DICTIONARY_FILE_VARIABLE).ok();
+    let persisted_dictionary = dictionary_file.as_ref()
+        .map(|path| VertexDictionary::load_or_new(path).unwrap_or_else(|e| panic!("{}", e)));
+
+    // vertex identifiers are recorded only if results are streamed to a sink that resolves them, the encoding requires them, or they are persisted
+    #[cfg(feature = "websocket")]
+    let websocket_sink = std::env::var(sgraffito_query::sink::websocket::WEBSOCKET_ADDRESS_VARIABLE).ok().map(|address| {
+        sgraffito_query::sink::websocket::WebSocketSink::bind(&address, persisted_dictionary.clone().unwrap_or_default())
+            .unwrap_or_else(|e| panic!("Cannot start WebSocket sink at {}: {}", address, e))
+    });
+    #[cfg(feature = "websocket")]
+    let dictionary = websocket_sink.as_ref().map(|websocket_sink| websocket_sink.dictionary());
+    #[cfg(not(feature = "websocket"))]
+    let dictionary: Option<VertexDictionary> = None;
+    let dictionary = dictionary.or_else(|| persisted_dictionary.clone()).or_else(|| if vertex_encoding.requires_dictionary() || sample_size.is_some() { Some(VertexDictionary::new()) } else { None });
+
+    // vertex files resolve identifiers as the readers, i.e., through the same dictionary for dense identifiers
+    let vertex_ids = match &dictionary {
+        Some(dictionary) => VertexIds::new(input_kind.has_integer_ids()).with_vertex_encoding(vertex_encoding, dictionary.clone()),
+        None => VertexIds::new(input_kind.has_integer_ids()),
+    };
+    // restriction of the input to a subgraph, if it is given
+    let vertex_filter = options.vertex_filter.as_ref()
+        .map(|spec| VertexFilter::from_spec(spec, &vertex_ids).unwrap_or_else(|e| panic!("Invalid vertex filter: {}", e)));
+    // endpoint types in edge labels, enabled via environment variable
+    let vertex_types = std::env::var(VERTEX_TYPES_VARIABLE).ok()
+        .map(|path| VertexTypes::from_file(&path, &vertex_ids).unwrap_or_else(|e| panic!("{}", e)));
+
+    // spawn a bakcground thread to run metric logger until the end of execution
+    let exporter_thread = exporter.spawn();
+    // metrics are flushed at the end of stream without waiting for the reporting period
+    let exporter_flusher = exporter_thread.flusher();
+
+    // totals of the run, and state sizes of operators for their peaks
+    let run_summary = RunSummary::new();
+    let final_summary = run_summary.clone();
+    let run_start = clock.now();
+    let run_clock = clock.clone();
+    // peak state sizes of the run summary are read from gauges, which are recorded only if they have a consumer
+    if std::env::var(SUMMARY_STATE_VARIABLE).is_ok() || anomaly::is_enabled() {
+        gauges::enable();
+    }
 
-    timely::execute_from_args(std::env::args().skip(6), move |worker| {
+    timely::execute_from_args(options.timely_args().into_iter(), move |worker| {
 
         let mut input: InputHandle<u64, StreamingGraphEdge> = InputHandle::new();
         let mut probe = ProbeHandle::new();
 
         // initialize sink
         let mut sink = receiver.sink();
-
-
-        let mut batch_start= sink.now();
-
-        let timer = ::std::time::Instant::now();
+        // metrics of each worker are tagged with its index and the query
+        sink.add_default_labels(vec![(WORKER_LABEL, worker.index().to_string()), (QUERY_LABEL, query_name.clone())]);
+
+        let timer = clock.now();
+
+        // results are committed per epoch if a result directory is given
+        let epoch_sink = result_directory.as_ref()
+            .map(|directory| EpochFileSink::open(directory, worker.index()).unwrap_or_else(|e| panic!("{}", e)));
+
+        // results are counted per window of each query only if counts are written or anomalies are detected on them,
+        // so that results are not routed through an extra sink otherwise
+        let cardinality_sink = if result_count_directory.is_some() || anomaly_threshold.is_some() {
+            let cardinality_sink = CardinalitySink::new(slide_size, sink.clone()).with_run_summary(run_summary.clone());
+            let cardinality_sink = match &result_count_directory {
+                Some(directory) => cardinality_sink.with_summary(&format!("{}/result-counts-{}.csv", directory, worker.index()))
+                    .unwrap_or_else(|e| panic!("{}", e)),
+                None => cardinality_sink,
+            };
+            Some(match anomaly_threshold {
+                Some(threshold) => cardinality_sink.with_anomaly_detection(threshold),
+                None => cardinality_sink,
+            })
+        } else {
+            None
+        };
 
         let query_arguments = edge_predicates.clone();
         worker.dataflow::<u64, _, _>(|scope| {
 
             let windowed_stream = scope.input_from(&mut input).sliding_window(window_size);
 
-            let result = match query_name.as_str() {
-                "join" => {
-                    SGAQueryLibrary::hash_join(windowed_stream, query_arguments, "join".to_string())
-                },
-                "query1" => {
-                    SGAQueryLibrary::query1(windowed_stream, query_arguments, "q1".to_string())
-                },
-                "query2" => {
-                    SGAQueryLibrary::query2(windowed_stream, query_arguments, "q2".to_string())
-                },
-                "query2-a" => {
-                    SGAQueryLibrary::query2_a(windowed_stream, query_arguments, "q2".to_string())
-                },
-                "query3" => {
-                    SGAQueryLibrary::query3(windowed_stream, query_arguments, "q3".to_string())
-                },
-                "query3-a" => {
-                    SGAQueryLibrary::query3_a(windowed_stream, query_arguments, "q3".to_string())
-                },
-                "query4" => {
-                    SGAQueryLibrary::query4(windowed_stream, query_arguments, "q4".to_string())
-                },
-                "query4-a" => {
-                    SGAQueryLibrary::query4_a(windowed_stream, query_arguments, "q4".to_string())
-                },
-                "query4-pc1" => {
-                    SGAQueryLibrary::query4_pc1(windowed_stream, query_arguments, "q4".to_string())
-                },
-                "query4-pc2" => {
-                    SGAQueryLibrary::query4_pc2(windowed_stream, query_arguments, "q4".to_string())
-                },
-                "query5" => {
-                    SGAQueryLibrary::query5(windowed_stream, query_arguments, "q5".to_string())
-                },
-                "query6" => {
-                    SGAQueryLibrary::query6(windowed_stream, query_arguments, "q6".to_string())
-                },
-                "query6-cq" => {
-                    SGAQueryLibrary::query6_cq(windowed_stream, query_arguments, "q6".to_string())
-                },
-                "query7" => {
-                    SGAQueryLibrary::query7(windowed_stream, query_arguments, "q7".to_string())
-                },
-                "query7-cq" => {
-                    SGAQueryLibrary::query7_cq(windowed_stream, query_arguments, "q7".to_string())
-                },
-                "query8" => {
-                    SGAQueryLibrary::query8(windowed_stream, query_arguments, "q8".to_string())
-                },
-                _ => {
-                    panic!("Supplied query name is not defined: {}", &query_name);
+            let result = SGAQueryLibrary::by_name(&query_name, windowed_stream, query_arguments)
+                .unwrap_or_else(|| panic!("Supplied query name is not defined: {}", &query_name));
+
+            if let Some(epoch_sink) = epoch_sink {
+                result.commit_to(epoch_sink);
+            }
+
+            if let Some(cardinality_sink) = cardinality_sink {
+                result.sink_to(cardinality_sink);
+            }
+
+            if let Some(sample_size) = sample_size {
+                result.sink_to(SamplingSink::new(sample_size, slide_size, dictionary.clone().unwrap()));
+            }
+
+            #[cfg(feature = "websocket")]
+            {
+                if let Some(websocket_sink) = &websocket_sink {
+                    result.sink_to(websocket_sink.clone());
                 }
-            };
+            }
 
             result
                 .inspect(|x| trace!("Query result {:?}", x ))
@@ -140,88 +322,171 @@ fn main() {
         });
 
 
-        let reader = match input_type_name.as_str() {
-            "i" => LineFileReader::open(&filename, false, true).expect("Cannot open input graph file"),
-            "it" => LineFileReader::open(&filename, true, true).expect("Cannot open input graph file"),
-            "s" => LineFileReader::open(&filename, false, false).expect("Cannot open input graph file"),
-            "st" => LineFileReader::open(&filename, true, false).expect("Cannot open input graph file"),
-            _ => panic!("Input type {} is not valid", input_type_name)
+        let (reader, start_time): (Box<dyn Iterator<Item=StreamingGraphEdge> + Send>, u64) = match &dataset_schema {
+            Some(schema) => {
+                let reader = DatasetReader::open(&filename, schema.clone()).expect("Cannot open input graph file");
+                let reader = match &dictionary {
+                    Some(dictionary) => reader.with_vertex_encoding(vertex_encoding, dictionary.clone()),
+                    None => reader,
+                };
+                let start_time = reader.get_start_timestamp();
+                (Box::new(reader), start_time)
+            }
+            None => {
+                let reader = match partitioning {
+                    // each worker reads only its own range of the input file
+                    Partitioning::ByteRange => LineFileReader::open_partition(&filename, input_kind.is_timestamped(), input_kind.has_integer_ids(), worker.index(), worker.peers()),
+                    _ => LineFileReader::open(&filename, input_kind.is_timestamped(), input_kind.has_integer_ids()),
+                }.expect("Cannot open input graph file");
+                let reader = match &dictionary {
+                    Some(dictionary) => reader.with_vertex_encoding(vertex_encoding, dictionary.clone()),
+                    None => reader,
+                };
+                let start_time = reader.get_start_timestamp();
+                (Box::new(reader), start_time)
+            }
         };
 
-        let start_time = reader.get_start_timestamp();
-
-        let mut total_edge_counter = 0;
-        let mut processed_edge_counter = 0;
-        let mut last_batch_process = start_time;
-        let mut edge_ts = 0;
-
-        let mut first_window = true;
-
-        for sge in reader {
-            trace!("Next sgt from input stream {:?}", sge);
-            total_edge_counter += 1;
-
-            let edge_predicate = sge.get_label();
-            edge_ts = sge.get_timestamp();
-
-            // do not computation and measurements until slide is full for the first time
-            if edge_ts - start_time >= slide_size {
-                // perform the first flush
-                if first_window {
-                    first_window = false;
-
-                    //update batch process marker
-                    last_batch_process = edge_ts;
-                    // update edge_counter for accurate measuring
-                    total_edge_counter = 0;
-                    processed_edge_counter = 0;
-
-                    // advance input so that computation is performed
-                    input.advance_to(edge_ts);
-                    worker.step_while(|| probe.less_than(input.time()));
-                    info!("Window is fully populated at {} after {}", edge_ts, timer.elapsed().as_secs());
-                } else if edge_ts - last_batch_process >= slide_size {
-                    // perform  slide and measure elapsed time
-                    trace!("Slide at {}", edge_ts);
-                    last_batch_process = edge_ts;
-
-                    // computation timer
-                    let start = sink.now();
-
-                    // advance time to trigger computation
-                    input.advance_to(edge_ts);
-                    worker.step_while(|| probe.less_than(input.time()));
-                    info!("Input advance to: {} after {} secs", edge_ts, timer.elapsed().as_secs());
-
-                    sink.record_timing("batch-latency", start, sink.now());
-                    sink.record_value("batch-size", processed_edge_counter);
-                    sink.record_timing("total-latency", batch_start, sink.now());
-                    sink.record_value("total-size", total_edge_counter);
-
-                    // reset edge-counter to count #of edges for next batch
-                    processed_edge_counter = 0;
-                    total_edge_counter = 0;
-
-                    // reset timer
-                    batch_start = sink.now();
-                }
-            }
+        // by default, the first slide is the warm-up phase and measurements continue until the end of the input
+        let schedule = PhaseSchedule::from_env(start_time, slide_size);
+
+        // each worker sends a disjoint subset of the input
+        let partitioner = InputPartitioner::new(partitioning, worker.index(), worker.peers());
+
+        let mut driver = ReplayDriver::new(start_time, slide_size, schedule, &edge_predicates, partitioner)
+            .with_label_mapping(label_mapping.as_ref())
+            .with_vertex_filter(vertex_filter.as_ref())
+            .with_vertex_types(vertex_types.as_ref())
+            .with_deduplicator(dedup_mode.map(EdgeDeduplicator::new))
+            .with_backpressure(max_in_flight)
+            .with_slide_coalescing(slide_budget)
+            .with_clock(clock.clone())
+            .with_simulation(simulation);
+
+        // latencies are measured in virtual time in simulation mode
+        let replay_clock = driver.clock();
+        let batch_start = replay_clock.nanos();
+        let mut replay = SgaReplay { worker, summary: &run_summary, sample_memory, input, probe, sink, admin: &admin, clock: replay_clock.as_ref(), batch_start };
+        let summary = match watermark_interval {
+            Some(interval) => driver.run_events(WatermarkSource::spawn(reader, interval), &mut replay),
+            None => driver.run(reader, &mut replay),
+        };
+        let SgaReplay { worker, input, probe, mut sink, .. } = replay;
+
+        // close the input so that frontiers of all operators become empty and all standing tuples are processed
+        input.close();
+        worker.step_while(|| !probe.done());
+        trace!("Input processing has ended {}", summary.last_timestamp);
+        run_summary.add_edges(summary.sent);
+
+        // measure total time to execute the entire input
+        sink.record_value("total-time", (clock.now() - timer).as_secs());
+        if let Some(dictionary) = &dictionary {
+            sink.record_value("vertex-collisions", dictionary.collisions());
+        }
+        if worker.index() == 0 {
+            exporter_flusher.flush_now();
+        }
+    }).unwrap(); // asserts error-free execution;
+
+    // flush final metrics
+    exporter_thread.stop();
+
+    if let (Some(path), Some(dictionary)) = (&dictionary_file, &persisted_dictionary) {
+        dictionary.save(path).unwrap_or_else(|e| panic!("{}", e));
+    }
+
+    final_summary.write(&reporting_file, run_clock.now() - run_start).unwrap_or_else(|e| panic!("{}", e));
+}
+
+
+/// Dataflow of a worker that is fed by the `ReplayDriver`
+struct SgaReplay<'w, A: Allocate> {
+    worker: &'w mut Worker<A>,
+    summary: &'w RunSummary,
+    sample_memory: bool,
+    input: InputHandle<u64, StreamingGraphEdge>,
+    probe: ProbeHandle<u64>,
+    sink: Sink,
+    admin: &'w AdminState,
+    clock: &'w dyn Clock,
+    batch_start: u64,
+}
+
+impl<'w, A: Allocate> ReplayHandler for SgaReplay<'w, A> {
+    fn observe(&mut self, event_time: u64) {
+        self.admin.set_event_time(event_time);
+    }
 
-            // check if the edge predicate matches input label
-            if edge_predicates.iter().any(|p| p == edge_predicate) {
-                // then update input stream and increment edge counter
-                input.send( sge);
-                processed_edge_counter += 1;
+    fn send(&mut self, sge: StreamingGraphEdge) {
+        self.input.send(sge);
+    }
+
+    fn slide(&mut self, slide: &Slide) {
+        let labels = [(PHASE_LABEL, slide.phase.as_str())];
+
+        // computation timer
+        let start = self.clock.nanos();
+
+        // advance time to trigger computation
+        self.input.advance_to(slide.time);
+        let (input, probe) = (&self.input, &self.probe);
+        self.worker.step_while(|| probe.less_than(input.time()));
+        if self.worker.index() == 0 {
+            self.admin.set_frontier(slide.time);
+            if gauges::is_enabled() {
+                self.summary.observe_state(&gauges::snapshot());
             }
+        }
 
+        let sink = &mut self.sink;
+        sink.record_timing_with_labels("batch-latency", start, self.clock.nanos(), &labels[..]);
+        sink.record_value_with_labels("batch-size", slide.batch_size, &labels[..]);
+        sink.record_timing_with_labels("total-latency", self.batch_start, self.clock.nanos(), &labels[..]);
+        sink.record_value_with_labels("total-size", slide.total_size, &labels[..]);
+        if let Some(duplicates_dropped) = slide.duplicates_dropped {
+            sink.record_value_with_labels("duplicates-dropped", duplicates_dropped, &labels[..]);
+        }
+        if let Some(backpressure) = slide.backpressure {
+            sink.record_value_with_labels("backpressure-throttles", backpressure.throttles, &labels[..]);
+            sink.record_value_with_labels("backpressure-stalled", backpressure.stalled.as_nanos() as u64, &labels[..]);
+        }
+        if let Some(coalesced) = slide.coalesced {
+            sink.record_value_with_labels("coalesced-slides", coalesced, &labels[..]);
+        }
+        if let Some(virtual_arrival) = slide.virtual_arrival {
+            sink.record_value_with_labels("virtual-latency", self.clock.now().checked_sub(virtual_arrival).unwrap_or_default().as_nanos() as u64, &labels[..]);
+        }
+        // windows may miss results while RPQ operators are warming after a hot swap
+        if hot_swap::is_enabled() {
+            sink.record_value_with_labels("warming", slide.is_warming() as u64, &labels[..]);
+        }
+        // memory is process-wide, so it is sampled once per process
+        if self.sample_memory && self.worker.index() == 0 {
+            if let Some(stats) = memory::sample() {
+                sink.record_value_with_labels("memory-resident", stats.resident, &labels[..]);
+                if let Some(allocated) = stats.allocated {
+                    sink.record_value_with_labels("memory-allocated", allocated, &labels[..]);
+                }
+            }
+        }
+        // counters are process-wide, so they are reported once per process
+        if self.worker.index() == 0 && state_counters::is_enabled() {
+            for (name, count) in state_counters::complete_window() {
+                sink.record_value_with_labels(name, count, &labels[..]);
+            }
         }
-        // advance input to last seen edge_ts to ensure all standing tuples are processed
-        input.advance_to(edge_ts + 1);
-        worker.step_while(|| probe.less_than(input.time()));
-        trace!("Input processing has ended {}", edge_ts);
 
-        // measure total time to execute the entire input
-        sink.record_value("total-time", timer.elapsed().as_secs());
-    }).unwrap(); // asserts error-free execution;
-    thread::sleep(Duration::from_millis(REPORTING_PERIOD_MILLISECONDS));
+        // reset timer
+        self.batch_start = self.clock.nanos();
+    }
+
+    fn catch_up(&mut self, time: u64) {
+        // out-of-order edges may be behind the input
+        if time > *self.input.time() {
+            self.input.advance_to(time);
+        }
+        let (input, probe) = (&self.input, &self.probe);
+        self.worker.step_while(|| probe.less_than(input.time()));
+    }
 }
@@ -9,10 +9,33 @@ TIMEOUT_IN_SECONDS = 600
 # period of reporting, i.e., actual execution time between each row
 REPORTING_PERIOD_IN_SECONDS = 5
 
+# phase whose metrics are aggregated, see `RunPhase`, metrics without a phase are reported in files without a phase suffix
+MEASUREMENT_PHASE = 'measurement'
+
 def get_immediate_subdirectories(a_dir):
     return [name for name in os.listdir(a_dir)
             if os.path.isdir(os.path.join(a_dir, name))]
 
+# rows of the given metric in the measurement phase, or without a phase, as dictionaries keyed by the csv header
+def read_metric(log_path, metric):
+    for file_name in ['{}-{}.csv'.format(metric, MEASUREMENT_PHASE), '{}.csv'.format(metric)]:
+        metric_file = os.path.join(log_path, file_name)
+        if os.path.exists(metric_file):
+            with open(metric_file, 'r') as f:
+                return list(csv.DictReader(f))
+    raise FileNotFoundError('No {} metric in {}'.format(metric, log_path))
+
+# rows of each worker in the order they are reported, rows of metrics without a worker column belong to a single worker
+def rows_by_worker(rows):
+    workers = {}
+    for row in rows:
+        workers.setdefault(row.get('worker', ''), []).append(row)
+    return workers
+
+# latest row of each worker, i.e., percentiles of the entire run as histograms are cumulative by default
+def last_rows(rows):
+    return [worker_rows[-1] for worker_rows in rows_by_worker(rows).values()]
+
 # read the command line arguments
 if len(sys.argv) != 3:
     print('Provide input folder and output files')
@@ -33,50 +56,37 @@ with open(aggregated_results_file, 'w') as csv_file:
     writer.writeheader()
 
     for log_folder in log_folders:
-        batch_size_histogram = os.path.join(results_folder, log_folder, 'batch-size.csv')
-        total_size_histogram = os.path.join(results_folder, log_folder, 'total-size.csv')
-        batch_latency_histogram = os.path.join(results_folder, log_folder, 'batch-latency.csv')
-        total_latency_histogram = os.path.join(results_folder, log_folder, 'total-latency.csv')
-        total_time_counter = os.path.join(results_folder, log_folder, 'total-time.csv')
-        memory_counter = os.path.join(results_folder, log_folder, 'memory.csv')
+        log_path = os.path.join(results_folder, log_folder)
+        memory_counter = os.path.join(log_path, 'memory.csv')
 
         print('Opening {}'.format(log_folder))
 
         try:
-            with open(batch_size_histogram, 'r') as f:
-                r_list = list(csv.reader(f))
-                row = next(reversed(r_list))
-                slide_count = row[1]
-                slide_size_mean = row[2]
-                slide_size_p99 = row[7]
-                # find the second of the last
-                last_slide_count = 1
-                for row in r_list:
-                    if row[1] == slide_count:
-                        # find the last slide that reported new numbers
-                        break
-                    else:
-                        last_slide_count += 1
-
-            with open(total_size_histogram, 'r') as f:
-                row = next(reversed(list(csv.reader(f))))
-                total_slide_size_mean = row[2]
-                total_slide_size_p99 = row[7]
-
-            with open(batch_latency_histogram, 'r') as f:
-                row = next(reversed(list(csv.reader(f))))
-                processed_mean = row[2]
-                processed_p25 = row[3]
-                processed_p50 = row[4]
-                processed_p75 = row[5]
-                processed_p90 = row[6]
-                processed_p99 = row[7]
-                processed_p999 = row[8]
-
-            with open(total_latency_histogram, 'r') as f:
-                row = next(reversed(list(csv.reader(f))))
-                total_mean = row[2]
-                total_p99 = row[7]
+            # sizes of workers add up, i.e., p99 of the sum is bounded by the sum of their p99
+            batch_size_rows = read_metric(log_path, 'batch-size')
+            rows = last_rows(batch_size_rows)
+            slide_count = max(int(row['count']) for row in rows)
+            slide_size_mean = sum(int(row['mean']) for row in rows)
+            slide_size_p99 = sum(int(row['p99']) for row in rows)
+            # find the first report of the last slide of the slowest worker, in reporting periods
+            last_slide_count = 1
+            for worker_rows in rows_by_worker(batch_size_rows).values():
+                if int(worker_rows[-1]['count']) == slide_count:
+                    last_slide_count = next(index for index, row in enumerate(worker_rows) if int(row['count']) == slide_count) + 1
+                    break
+
+            rows = last_rows(read_metric(log_path, 'total-size'))
+            total_slide_size_mean = sum(int(row['mean']) for row in rows)
+            total_slide_size_p99 = sum(int(row['p99']) for row in rows)
+
+            # latencies of a slide are those of the slowest worker
+            rows = last_rows(read_metric(log_path, 'batch-latency'))
+            processed_mean = max(int(row['mean']) for row in rows)
+            processed_p99 = max(int(row['p99']) for row in rows)
+
+            rows = last_rows(read_metric(log_path, 'total-latency'))
+            total_mean = max(int(row['mean']) for row in rows)
+            total_p99 = max(int(row['p99']) for row in rows)
 
             with open(memory_counter, 'r') as f:
                 row = next(reversed(list(csv.reader(f))))
@@ -84,9 +94,7 @@ with open(aggregated_results_file, 'w') as csv_file:
 
             # there won't be time counter for processes that are killed due to a timeout
             try:
-                with open(total_time_counter, 'r') as f:
-                    row = next(reversed(list(csv.reader(f))))
-                    time = row[2]
+                time = max(int(row['mean']) for row in last_rows(read_metric(log_path, 'total-time')))
             except:
                 time = TIMEOUT_IN_SECONDS
 
@@ -112,7 +120,7 @@ with open(aggregated_results_file, 'w') as csv_file:
                 'total-size-p99' : total_slide_size_p99,
                 'total-mean': total_mean,
                 'total-p99': total_p99,
-                'throughput' : str(int(slide_count) * int(slide_size_mean) / time),
+                'throughput' : str(slide_count * slide_size_mean / time),
                 'total-time' : time,
                 'last-slide-time' : str(last_slide_count * REPORTING_PERIOD_IN_SECONDS),
                 'memory': memory
@@ -0,0 +1,121 @@
+//! Embedded HTTP admin endpoint to inspect a live engine
+//!
+//! Runners share an `AdminState` with their workers, which update the event time and the frontier as they progress.
+//! With the `admin` feature, `serve` exposes the state over HTTP:
+//! - `/health`: liveness and uptime
+//! - `/frontier`: latest event time and completed frontier
+//! - `/queries`: registered queries
+//! - `/gauges`: state-size gauges of operators, see `util::gauges`
+//! - `/state-counters`: automaton state and transition visits of RPQ operators, see `util::state_counters`
+//! - `/explain/<query name>`: plan of a registered query
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+
+use serde::Serialize;
+
+use crate::util::{gauges, state_counters};
+
+/// environment variable that sets the address of the admin endpoint in runners, e.g., `127.0.0.1:8080`
+pub const ADMIN_ADDRESS_VARIABLE: &str = "SGRAFFITO_ADMIN_ADDRESS";
+
+/// a query running on the engine
+#[derive(Serialize, Clone, Debug)]
+pub struct RegisteredQuery {
+    pub name: String,
+    pub engine: String,
+    pub predicates: Vec<String>,
+    pub explain: Option<String>,
+}
+
+/// State of a live engine that is exposed by the admin endpoint
+pub struct AdminState {
+    started: Instant,
+    event_time: AtomicU64,
+    frontier: AtomicU64,
+    queries: RwLock<Vec<RegisteredQuery>>,
+}
+
+impl Default for AdminState {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            event_time: AtomicU64::new(0),
+            frontier: AtomicU64::new(0),
+            queries: RwLock::new(Vec::new()),
+        }
+    }
+}
+
+impl AdminState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// updates the latest event time, i.e., the max timestamp ingested by any worker
+    pub fn set_event_time(&self, event_time: u64) {
+        self.event_time.fetch_max(event_time, Ordering::Relaxed);
+    }
+
+    /// updates the frontier, i.e., the time up to which all computation is completed
+    pub fn set_frontier(&self, frontier: u64) {
+        self.frontier.store(frontier, Ordering::Relaxed);
+    }
+
+    pub fn register_query(&self, query: RegisteredQuery) {
+        self.queries.write().unwrap().push(query);
+    }
+
+    /// handles a request to the given url, and returns its status code, content type and body
+    pub fn handle(&self, url: &str) -> (u16, &'static str, String) {
+        let path = url.split('?').next().unwrap_or("");
+
+        match path {
+            "/health" => json_response(&serde_json::json!({
+                "status": "ok",
+                "uptime_secs": self.started.elapsed().as_secs(),
+            })),
+            "/frontier" => json_response(&serde_json::json!({
+                "event_time": self.event_time.load(Ordering::Relaxed),
+                "frontier": self.frontier.load(Ordering::Relaxed),
+            })),
+            "/queries" => json_response(&*self.queries.read().unwrap()),
+            "/gauges" => json_response(&gauges::snapshot()),
+            "/state-counters" => json_response(&state_counters::snapshot()),
+            _ if path.starts_with("/explain/") => {
+                let query_name = &path["/explain/".len()..];
+                let queries = self.queries.read().unwrap();
+                match queries.iter().find(|query| query.name == query_name) {
+                    Some(RegisteredQuery { explain: Some(explain), .. }) => (200, "text/plain", explain.clone()),
+                    Some(_) => (404, "text/plain", format!("Explain is not available for {}", query_name)),
+                    None => (404, "text/plain", format!("Query {} is not registered", query_name)),
+                }
+            }
+            _ => (404, "text/plain", format!("Unknown path {}", path))
+        }
+    }
+}
+
+fn json_response<T: Serialize + ?Sized>(value: &T) -> (u16, &'static str, String) {
+    match serde_json::to_string(value) {
+        Ok(body) => (200, "application/json", body),
+        Err(e) => (500, "text/plain", format!("Cannot serialize response: {}", e)),
+    }
+}
+
+/// serves the admin endpoint at the given address on a background thread
+#[cfg(feature = "admin")]
+pub fn serve(state: std::sync::Arc<AdminState>, address: &str) -> Result<std::thread::JoinHandle<()>, String> {
+    let server = tiny_http::Server::http(address).map_err(|e| format!("Cannot start admin endpoint at {}: {}", address, e))?;
+
+    Ok(std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let (status, content_type, body) = state.handle(request.url());
+            let header = tiny_http::Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes()).unwrap();
+            let response = tiny_http::Response::from_string(body).with_status_code(status).with_header(header);
+            if let Err(e) = request.respond(response) {
+                log::warn!("Cannot respond to admin request: {}", e);
+            }
+        }
+    }))
+}
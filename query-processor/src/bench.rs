@@ -0,0 +1,473 @@
+//! Benchmark runner that executes every combination of a scenario and produces a consolidated report
+//!
+//! A scenario is described in TOML, e.g.,
+//! ```toml
+//! name = "so-table1"
+//! dataset = "/data/so/sx-stackoverflow.txt"
+//! input_type = "it"
+//! engines = ["sga", "dd"]
+//! workers = [1, 2]
+//! report = "/tmp/so-table1.csv"
+//!
+//! [[queries]]
+//! name = "query1"
+//! predicates = ["a2q"]
+//!
+//! [[windows]]
+//! window_size = 2592000
+//! slide_size = 86400
+//!
+//! [phases]
+//! warm_up = 864000
+//! measurement = 8640000
+//! ```
+//! Each run replays the dataset with the given number of workers in this process, where each worker reads the
+//! file and sends its share of edges, and records the latency of every slide in the measurement phase.
+//! As runs share the process, memory of each run is reported as the growth of the resident set size over the run
+//! instead of the peak of the process, which would include earlier runs.
+//!
+//! If `verify_directory` is set, results of every slide are also written into per-window result files in the directory,
+//! and SGA results are diffed against DD results of the same combination, see `diff`.
+extern crate timely;
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Instant;
+
+use hdrhistogram::Histogram;
+use log::info;
+use serde::{Deserialize, Serialize};
+use timely::Configuration;
+use timely::dataflow::{InputHandle, ProbeHandle};
+use timely::dataflow::operators::{Input, Inspect, Probe};
+
+use crate::dd::WindowedInput;
+use crate::diff::{diff_files, write_window_outputs, WindowOutputs};
+use crate::input::{GraphEdge, InputFileReader, InputPartitioner, InputStreamKind, LineFileReader, Partitioning, StreamingGraphEdge};
+use crate::operator::window::SlidingWindow;
+use crate::query::query_library::{DDQueryLibrary, SGAQueryLibrary};
+use crate::util::memory;
+use crate::util::phase::{PhaseSchedule, RunPhase};
+use crate::util::types::VertexType;
+
+/// query processing engines a scenario can be executed on
+#[derive(Deserialize, Serialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    Sga,
+    Dd,
+}
+
+/// a query of the library together with its edge predicates
+#[derive(Deserialize, Clone, Debug)]
+pub struct QuerySpec {
+    pub name: String,
+    pub predicates: Vec<String>,
+}
+
+/// window and slide sizes of a run
+#[derive(Deserialize, Copy, Clone, Debug)]
+pub struct WindowSpec {
+    pub window_size: u64,
+    pub slide_size: u64,
+}
+
+/// durations of run phases in the unit of edge timestamps, see `PhaseSchedule`
+/// warm-up defaults to the slide size and measurement is unbounded if they are not set
+#[derive(Deserialize, Copy, Clone, Debug, Default)]
+pub struct PhaseSpec {
+    pub warm_up: Option<u64>,
+    pub measurement: Option<u64>,
+    #[serde(default)]
+    pub cool_down: u64,
+}
+
+/// benchmark scenario, every combination of engines, queries, windows and workers is executed
+#[derive(Deserialize, Clone, Debug)]
+pub struct Scenario {
+    pub name: String,
+    pub dataset: String,
+    pub input_type: String,
+    pub engines: Vec<Engine>,
+    pub queries: Vec<QuerySpec>,
+    pub windows: Vec<WindowSpec>,
+    pub workers: Vec<usize>,
+    #[serde(default)]
+    pub phases: PhaseSpec,
+    /// path of the consolidated report, written as JSON if it ends with `.json` and as CSV otherwise
+    pub report: String,
+    /// directory of per-window result files of each run, SGA results are verified against DD results if it is set
+    #[serde(default)]
+    pub verify_directory: Option<String>,
+}
+
+impl Scenario {
+    /// reads and validates a scenario from the given TOML file
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("Cannot read scenario file {}: {}", path, e))?;
+        let scenario: Scenario = toml::from_str(&content).map_err(|e| format!("Cannot parse scenario file {}: {}", path, e))?;
+
+        if InputStreamKind::from_name(&scenario.input_type).is_none() {
+            return Err(format!("Input type {} is not valid", scenario.input_type));
+        }
+        if scenario.workers.iter().any(|workers| *workers == 0) {
+            return Err("Number of workers must be positive".to_string());
+        }
+        if scenario.windows.iter().any(|window| window.slide_size == 0 || window.window_size < window.slide_size) {
+            return Err("Slide size must be positive and at most the window size".to_string());
+        }
+        // queries are constructed inside the workers of each run, which cannot recover from an unknown query
+        for query in &scenario.queries {
+            for engine in &scenario.engines {
+                let is_defined = match engine {
+                    Engine::Sga => SGAQueryLibrary::explain(&query.name, &query.predicates).is_some(),
+                    Engine::Dd => DDQueryLibrary::is_defined(&query.name),
+                };
+                if !is_defined {
+                    return Err(format!("Query {} is not defined for {:?}", query.name, engine));
+                }
+            }
+        }
+
+        Ok(scenario)
+    }
+}
+
+/// measurements of a single run of a scenario
+#[derive(Serialize, Clone, Debug)]
+pub struct BenchmarkReport {
+    pub scenario: String,
+    pub engine: Engine,
+    pub query: String,
+    pub window_size: u64,
+    pub slide_size: u64,
+    pub workers: usize,
+    /// number of edges sent to the dataflow by all workers
+    pub edges: u64,
+    /// number of slides in the measurement phase
+    pub slides: u64,
+    pub elapsed_ms: u64,
+    /// edges per second over the entire run
+    pub throughput: f64,
+    pub mean_latency_ms: f64,
+    pub p99_latency_ms: f64,
+    pub max_latency_ms: f64,
+    /// growth of the resident set size of the process over the run in KB, i.e., the max size sampled at slides minus the size before the run,
+    /// 0 if it is not available or memory that earlier runs released is reused
+    pub memory_growth_kb: u64,
+    /// # of differences from DD results of the same combination, only set for verified SGA runs
+    pub mismatches: Option<u64>,
+}
+
+/// edge count, slide latencies in microseconds, results of each slide if they are recorded and the max resident bytes sampled at slides,
+/// observed by a single worker
+type WorkerMeasurements = (u64, Vec<u64>, WindowOutputs, u64);
+
+/// executes every combination of the scenario in order
+pub fn run_scenario(scenario: &Scenario) -> Vec<BenchmarkReport> {
+    let mut reports = Vec::new();
+    // result files of verified runs, keyed by the combination and the engine
+    let mut result_files = HashMap::new();
+
+    for engine in &scenario.engines {
+        for query in &scenario.queries {
+            for (window_index, window) in scenario.windows.iter().enumerate() {
+                for workers in &scenario.workers {
+                    info!("Running {:?} {} window {} slide {} with {} workers", engine, query.name, window.window_size, window.slide_size, workers);
+                    let (report, outputs) = run_single(scenario, *engine, query, *window, *workers);
+
+                    if let Some(directory) = &scenario.verify_directory {
+                        let path = Path::new(directory).join(format!("{}-{:?}-{}-{}-{}-{}.csv", scenario.name, engine, query.name, window.window_size, window.slide_size, workers).to_lowercase());
+                        let path = path.to_string_lossy().into_owned();
+                        write_window_outputs(&path, &outputs).unwrap_or_else(|e| panic!("{}", e));
+                        result_files.insert((query.name.clone(), window_index, *workers, *engine), (reports.len(), path));
+                    }
+                    reports.push(report);
+                }
+            }
+        }
+    }
+
+    // SGA results are verified against DD results of the same combination
+    for ((query_name, window_index, workers, engine), (report_index, sga_file)) in &result_files {
+        if *engine != Engine::Sga {
+            continue;
+        }
+        if let Some((_, dd_file)) = result_files.get(&(query_name.clone(), *window_index, *workers, Engine::Dd)) {
+            let diff = diff_files(dd_file, sga_file).unwrap_or_else(|e| panic!("{}", e));
+            info!("Verified {} against {}: {:?}", sga_file, dd_file, diff.summary);
+
+            let diff_file = format!("{}-diff.json", sga_file.trim_end_matches(".csv"));
+            let content = serde_json::to_string_pretty(&diff).unwrap_or_else(|e| panic!("Cannot serialize diff: {}", e));
+            fs::write(&diff_file, content).unwrap_or_else(|e| panic!("Cannot write diff {}: {}", diff_file, e));
+            reports[*report_index].mismatches = Some(diff.summary.mismatches());
+        }
+    }
+
+    reports
+}
+
+/// executes a single combination of the scenario and consolidates measurements of all workers
+/// returns the report and results of each slide of all workers, which are recorded only if the scenario is verified
+fn run_single(scenario: &Scenario, engine: Engine, query: &QuerySpec, window: WindowSpec, workers: usize) -> (BenchmarkReport, WindowOutputs) {
+    let input_kind = InputStreamKind::from_name(&scenario.input_type).expect("Input type is validated on load");
+    let record_outputs = scenario.verify_directory.is_some();
+
+    let resident_before = resident_bytes();
+    let timer = Instant::now();
+    let measurements = match engine {
+        Engine::Sga => replay_sga(&scenario.dataset, input_kind, query, window, scenario.phases, workers, record_outputs),
+        Engine::Dd => replay_dd(&scenario.dataset, input_kind, query, window, scenario.phases, workers, record_outputs),
+    };
+    let elapsed = timer.elapsed();
+
+    // workers advance in lock step, so the latency of a slide is the latency of the slowest worker
+    let edges: u64 = measurements.iter().map(|(edges, _, _, _)| edges).sum();
+    let slides = measurements.iter().map(|(_, latencies, _, _)| latencies.len()).max().unwrap_or(0);
+    let max_resident = measurements.iter().map(|(_, _, _, resident)| *resident).max().unwrap_or(0);
+    let mut histogram = Histogram::<u64>::new(3).expect("Cannot create latency histogram");
+    for slide in 0..slides {
+        let latency = measurements.iter().filter_map(|(_, latencies, _, _)| latencies.get(slide)).max().cloned().unwrap_or(0);
+        histogram.record(latency).expect("Cannot record slide latency");
+    }
+
+    // each worker produces a disjoint subset of results of each slide
+    let mut outputs = WindowOutputs::new();
+    for (_, _, worker_outputs, _) in measurements {
+        for (window_time, results) in worker_outputs {
+            outputs.entry(window_time).or_insert_with(BTreeMap::new).extend(results);
+        }
+    }
+
+    let elapsed_secs = elapsed.as_secs_f64();
+    let report = BenchmarkReport {
+        scenario: scenario.name.clone(),
+        engine,
+        query: query.name.clone(),
+        window_size: window.window_size,
+        slide_size: window.slide_size,
+        workers,
+        edges,
+        slides: slides as u64,
+        elapsed_ms: elapsed.as_millis() as u64,
+        throughput: if elapsed_secs > 0.0 { edges as f64 / elapsed_secs } else { 0.0 },
+        mean_latency_ms: if slides > 0 { histogram.mean() / 1000.0 } else { 0.0 },
+        p99_latency_ms: histogram.value_at_quantile(0.99) as f64 / 1000.0,
+        max_latency_ms: histogram.max() as f64 / 1000.0,
+        memory_growth_kb: max_resident.max(resident_bytes()).saturating_sub(resident_before) / 1024,
+        mismatches: None,
+    };
+
+    (report, outputs)
+}
+
+fn timely_configuration(workers: usize) -> Configuration {
+    if workers > 1 { Configuration::Process(workers) } else { Configuration::Thread }
+}
+
+fn open_reader(dataset: &str, input_kind: InputStreamKind) -> LineFileReader {
+    LineFileReader::open(dataset, input_kind.is_timestamped(), input_kind.has_integer_ids()).expect("Cannot open input graph file")
+}
+
+/// replays the dataset over the SGA query
+/// if `record_outputs` is set, results valid at the end of each slide are recorded with their validity intervals
+fn replay_sga(dataset: &str, input_kind: InputStreamKind, query: &QuerySpec, window: WindowSpec, phases: PhaseSpec, workers: usize, record_outputs: bool) -> Vec<WorkerMeasurements> {
+    let dataset = dataset.to_string();
+    let query_name = query.name.clone();
+    let edge_predicates = query.predicates.clone();
+
+    let guards = timely::execute(timely_configuration(workers), move |worker| {
+        let partitioner = InputPartitioner::new(Partitioning::RoundRobin, worker.index(), worker.peers());
+
+        let mut input: InputHandle<u64, StreamingGraphEdge> = InputHandle::new();
+        let mut probe = ProbeHandle::new();
+
+        let results = Rc::new(RefCell::new(Vec::new()));
+        let result_sink = results.clone();
+
+        let query_arguments = edge_predicates.clone();
+        worker.dataflow::<u64, _, _>(|scope| {
+            let windowed_stream = scope.input_from(&mut input).sliding_window(window.window_size);
+
+            SGAQueryLibrary::by_name(&query_name, windowed_stream, query_arguments)
+                .unwrap_or_else(|| panic!("Supplied query name is not defined: {}", &query_name))
+                .inspect(move |sgt| if record_outputs { result_sink.borrow_mut().push((sgt.source, sgt.target, sgt.interval)) })
+                .probe_with(&mut probe);
+        });
+        let mut outputs = WindowOutputs::new();
+
+        let reader = open_reader(&dataset, input_kind);
+        let start_time = reader.get_start_timestamp();
+        let schedule = PhaseSchedule::new(start_time, phases.warm_up.unwrap_or(window.slide_size), phases.measurement, phases.cool_down);
+
+        let mut edge_counter = 0;
+        let mut latencies = Vec::new();
+        let mut max_resident = 0;
+        let mut last_batch_process = start_time;
+        let mut edge_ts = start_time;
+
+        for (line, sge) in reader.enumerate() {
+            edge_ts = sge.get_timestamp();
+            if schedule.phase_at(edge_ts) == RunPhase::Finished {
+                break;
+            }
+
+            if edge_ts - last_batch_process >= window.slide_size {
+                let phase = schedule.phase_at(last_batch_process);
+                last_batch_process = edge_ts;
+
+                let start = Instant::now();
+                input.advance_to(edge_ts);
+                worker.step_while(|| probe.less_than(input.time()));
+
+                // only slides that have started in the measurement phase are recorded
+                if phase == RunPhase::Measurement {
+                    latencies.push(start.elapsed().as_micros() as u64);
+                }
+                // memory is process-wide, so it is sampled by a single worker
+                if worker.index() == 0 {
+                    max_resident = max_resident.max(resident_bytes());
+                }
+
+                // all times before the edge are completed, so the slide is evaluated right before it
+                if record_outputs {
+                    let window_time = edge_ts - 1;
+                    let mut results = results.borrow_mut();
+                    results.retain(|(_, _, interval)| interval.end > window_time);
+                    outputs.insert(window_time, results.iter()
+                        .filter(|(_, _, interval)| interval.start <= window_time)
+                        .map(|(source, target, interval)| ((*source, *target), Some(*interval)))
+                        .collect());
+                }
+            }
+
+            if partitioner.owns(line, &sge) && edge_predicates.iter().any(|p| p == sge.get_label()) {
+                input.send(sge);
+                edge_counter += 1;
+            }
+        }
+        input.advance_to(edge_ts + 1);
+        worker.step_while(|| probe.less_than(input.time()));
+
+        (edge_counter, latencies, outputs, max_resident)
+    }).expect("Cannot execute the SGA dataflow");
+
+    guards.join().into_iter().map(|result| result.expect("SGA dataflow has failed")).collect()
+}
+
+/// replays the dataset over the DD query, expired edges are retracted at every slide by a `WindowedInput`
+/// if `record_outputs` is set, the collection at the end of each slide is recorded
+fn replay_dd(dataset: &str, input_kind: InputStreamKind, query: &QuerySpec, window: WindowSpec, phases: PhaseSpec, workers: usize, record_outputs: bool) -> Vec<WorkerMeasurements> {
+    let dataset = dataset.to_string();
+    let query_name = query.name.clone();
+    let edge_predicates = query.predicates.clone();
+
+    let guards = timely::execute(timely_configuration(workers), move |worker| {
+        let partitioner = InputPartitioner::new(Partitioning::RoundRobin, worker.index(), worker.peers());
+
+        let mut input = WindowedInput::sliding(window.window_size);
+        let mut probe = ProbeHandle::new();
+
+        let updates = Rc::new(RefCell::new(Vec::new()));
+        let update_sink = updates.clone();
+
+        let query_arguments = edge_predicates.clone();
+        worker.dataflow::<u64, _, _>(|scope| {
+            let input_stream = input.to_collection(scope);
+
+            DDQueryLibrary::by_name(&query_name, input_stream, query_arguments)
+                .unwrap_or_else(|| panic!("Supplied query name is not defined: {}", &query_name))
+                .inspect(move |(sgt, time, diff)| if record_outputs { update_sink.borrow_mut().push(((sgt.source, sgt.target), *time, *diff)) })
+                .probe_with(&mut probe);
+        });
+        let mut outputs = WindowOutputs::new();
+        // accumulated multiplicity of each result
+        let mut counts: BTreeMap<(VertexType, VertexType), isize> = BTreeMap::new();
+
+        let reader = open_reader(&dataset, input_kind);
+        let start_time = reader.get_start_timestamp();
+        let schedule = PhaseSchedule::new(start_time, phases.warm_up.unwrap_or(window.slide_size), phases.measurement, phases.cool_down);
+
+        let mut edge_counter = 0;
+        let mut latencies = Vec::new();
+        let mut max_resident = 0;
+        let mut last_batch_process = start_time;
+        let mut edge_ts = start_time;
+
+        for (line, sge) in reader.enumerate() {
+            edge_ts = sge.get_timestamp();
+            if schedule.phase_at(edge_ts) == RunPhase::Finished {
+                break;
+            }
+
+            if edge_ts - last_batch_process >= window.slide_size {
+                let phase = schedule.phase_at(last_batch_process);
+                last_batch_process = edge_ts;
+
+                let start = Instant::now();
+                input.advance_to(edge_ts);
+                input.flush();
+                worker.step_while(|| probe.less_than(input.time()));
+
+                // only slides that have started in the measurement phase are recorded
+                if phase == RunPhase::Measurement {
+                    latencies.push(start.elapsed().as_micros() as u64);
+                }
+                // memory is process-wide, so it is sampled by a single worker
+                if worker.index() == 0 {
+                    max_resident = max_resident.max(resident_bytes());
+                }
+
+                // all times before the edge are completed, so the slide is evaluated right before it
+                if record_outputs {
+                    let window_time = edge_ts - 1;
+                    updates.borrow_mut().retain(|(pair, time, diff)| {
+                        if *time > window_time {
+                            return true;
+                        }
+                        *counts.entry(*pair).or_insert(0) += *diff;
+                        false
+                    });
+                    counts.retain(|_, count| *count != 0);
+                    outputs.insert(window_time, counts.iter().filter(|(_, count)| **count > 0).map(|(pair, _)| (*pair, None)).collect());
+                }
+            }
+
+            if partitioner.owns(line, &sge) && edge_predicates.iter().any(|p| p == sge.get_label()) {
+                input.insert(sge);
+                edge_counter += 1;
+            }
+        }
+        input.advance_to(edge_ts + 1);
+        input.flush();
+        worker.step_while(|| probe.less_than(input.time()));
+
+        (edge_counter, latencies, outputs, max_resident)
+    }).expect("Cannot execute the DD dataflow");
+
+    guards.join().into_iter().map(|result| result.expect("DD dataflow has failed")).collect()
+}
+
+/// current resident bytes of this process, 0 if they are not available
+fn resident_bytes() -> u64 {
+    memory::sample().map_or(0, |stats| stats.resident)
+}
+
+/// writes the reports to the given path, as JSON if the path ends with `.json` and as CSV otherwise
+pub fn write_report(reports: &[BenchmarkReport], path: &str) -> Result<(), String> {
+    let is_json = Path::new(path).extension().map_or(false, |extension| extension == "json");
+
+    if is_json {
+        let content = serde_json::to_string_pretty(reports).map_err(|e| format!("Cannot serialize report: {}", e))?;
+        fs::write(path, content).map_err(|e| format!("Cannot write report {}: {}", path, e))
+    } else {
+        let mut writer = csv::Writer::from_path(path).map_err(|e| format!("Cannot create report {}: {}", path, e))?;
+        for report in reports {
+            writer.serialize(report).map_err(|e| format!("Cannot write report {}: {}", path, e))?;
+        }
+        writer.flush().map_err(|e| format!("Cannot write report {}: {}", path, e))
+    }
+}
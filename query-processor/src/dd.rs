@@ -0,0 +1,262 @@
+//! Windowed inputs of DD pipelines
+//!
+//! DD collections have no notion of validity intervals, so a window is maintained by retracting edges once they expire.
+//! `WindowedInput` wraps an `InputSession` and retracts expired edges as the input advances,
+//! using the same `WindowSemantics` as the `SlidingWindow` operator of SGA pipelines.
+//! `SharedArrangements` arranges the edges of each label once, so that DD queries of a dataflow share their indices,
+//! and carries the `DistinctGranularity` of the queries that are constructed with it.
+//! `transitive_closure` and `reachability` compose the transitive closure of the queries into user-written DD dataflows.
+extern crate differential_dataflow;
+extern crate timely;
+
+use std::collections::{HashMap, VecDeque};
+
+use differential_dataflow::Collection;
+use differential_dataflow::input::InputSession;
+use differential_dataflow::lattice::Lattice;
+use differential_dataflow::operators::{Iterate, JoinCore, Threshold};
+use differential_dataflow::operators::arrange::{ArrangeByKey, Arranged, TraceAgent};
+use differential_dataflow::trace::implementations::ord::OrdValSpine;
+use timely::dataflow::{Scope, ScopeParent};
+use timely::dataflow::operators::Input as TimelyInput;
+
+use crate::input::StreamingGraphEdge;
+use crate::operator::window::WindowSemantics;
+use crate::query::query_library::partition_by_label;
+use crate::util::types::VertexType;
+
+/// Input of a DD pipeline that retains edges as defined by the window semantics
+pub struct WindowedInput {
+    input: InputSession<u64, StreamingGraphEdge, isize>,
+    semantics: WindowSemantics,
+    // edges in the window with their expiry, in the order they are inserted
+    content: VecDeque<(u64, StreamingGraphEdge)>,
+}
+
+impl WindowedInput {
+    pub fn new(semantics: WindowSemantics) -> Self {
+        Self { input: InputSession::new(), semantics, content: VecDeque::new() }
+    }
+
+    /// input with sliding window semantics of the given size
+    pub fn sliding(window_size: u64) -> Self {
+        Self::new(WindowSemantics::Sliding { window_size })
+    }
+
+    /// input with tumbling window semantics of the given size
+    pub fn tumbling(window_size: u64) -> Self {
+        Self::new(WindowSemantics::Tumbling { window_size })
+    }
+
+    /// introduces the input as a collection into the given scope
+    pub fn to_collection<G: TimelyInput + Scope<Timestamp=u64>>(&mut self, scope: &mut G) -> Collection<G, StreamingGraphEdge, isize> {
+        self.input.to_collection(scope)
+    }
+
+    /// inserts the edge into the window at the current time of the input
+    /// edges are expected in timestamp order, so that they expire in the order they are inserted
+    pub fn insert(&mut self, sge: StreamingGraphEdge) {
+        self.input.update(sge.clone(), 1);
+        self.content.push_back((self.semantics.expiry(sge.timestamp), sge));
+    }
+
+    /// advances the input to the given time, and retracts edges that are expired at that time
+    pub fn advance_to(&mut self, time: u64) {
+        if time > *self.input.time() {
+            self.input.advance_to(time);
+        }
+
+        while let Some((expiry, _)) = self.content.front() {
+            if *expiry > time {
+                break;
+            }
+            let (_, sge) = self.content.pop_front().unwrap();
+            self.input.update(sge, -1);
+        }
+    }
+
+    pub fn flush(&mut self) {
+        self.input.flush();
+    }
+
+    /// current time of the input
+    pub fn time(&self) -> &u64 {
+        self.input.time()
+    }
+
+    pub fn semantics(&self) -> WindowSemantics {
+        self.semantics
+    }
+
+    /// # of edges in the window
+    pub fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+
+    /// closes the input, so that all standing updates are processed
+    pub fn close(self) {
+        self.input.close();
+    }
+}
+
+/// environment variable that sets where the DD runner deduplicates results of joins, i.e., `per-join`, `final-only` or `none`
+pub const DD_DISTINCT_VARIABLE: &str = "SGRAFFITO_DD_DISTINCT";
+
+/// Where DD queries deduplicate the (source, target) pairs of their joins, which trades duplicate work against the size of arrangements
+/// Pairs are always deduplicated within transitive closures, so that their iteration converges
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DistinctGranularity {
+    /// after every join
+    PerJoin,
+    /// only after the join that produces the results of a query
+    FinalOnly,
+    /// never, so that results are reported with the multiplicity of their derivations
+    Disabled,
+}
+
+impl DistinctGranularity {
+    /// parses granularities used by runners, i.e., `per-join`, `final-only` or `none`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "per-join" => Some(DistinctGranularity::PerJoin),
+            "final-only" => Some(DistinctGranularity::FinalOnly),
+            "none" => Some(DistinctGranularity::Disabled),
+            _ => None,
+        }
+    }
+
+    /// pairs of a join whose output is joined further
+    pub fn intermediate<G: Scope>(&self, pairs: &Collection<G, (VertexType, VertexType), isize>) -> Collection<G, (VertexType, VertexType), isize> where G::Timestamp: Lattice + Ord {
+        match self {
+            DistinctGranularity::PerJoin => pairs.distinct(),
+            _ => pairs.clone(),
+        }
+    }
+
+    /// pairs of the join that produces the results of a query
+    pub fn results<G: Scope>(&self, pairs: &Collection<G, (VertexType, VertexType), isize>) -> Collection<G, (VertexType, VertexType), isize> where G::Timestamp: Lattice + Ord {
+        match self {
+            DistinctGranularity::Disabled => pairs.clone(),
+            _ => pairs.distinct(),
+        }
+    }
+}
+
+impl Default for DistinctGranularity {
+    fn default() -> Self {
+        DistinctGranularity::PerJoin
+    }
+}
+
+/// Arrangement of (source, target) pairs of edges keyed by their first vertex
+pub type EdgeArrangement<G> = Arranged<G, TraceAgent<OrdValSpine<VertexType, VertexType, <G as ScopeParent>::Timestamp, isize>>>;
+
+/// Edges of each label of the input, where the arrangements of a label are constructed once on first use
+/// and shared by all queries that are constructed with the same `SharedArrangements`
+pub struct SharedArrangements<G: Scope> where G::Timestamp: Lattice + Ord {
+    scope: G,
+    edges: HashMap<String, Collection<G, (VertexType, VertexType), isize>>,
+    by_source: HashMap<String, EdgeArrangement<G>>,
+    by_target: HashMap<String, EdgeArrangement<G>>,
+    distinct: DistinctGranularity,
+}
+
+impl<G: Scope> SharedArrangements<G> where G::Timestamp: Lattice + Ord {
+    /// partitions the input into the edges of each of the given labels, edges of other labels are dropped
+    pub fn new(input: &Collection<G, StreamingGraphEdge, isize>, labels: &[String]) -> Self {
+        let mut distinct_labels: Vec<String> = Vec::new();
+        for label in labels {
+            if !distinct_labels.contains(label) {
+                distinct_labels.push(label.clone());
+            }
+        }
+        let edges = distinct_labels.iter().cloned().zip(partition_by_label(input, &distinct_labels)).collect();
+
+        Self { scope: input.scope(), edges, by_source: HashMap::new(), by_target: HashMap::new(), distinct: DistinctGranularity::default() }
+    }
+
+    /// queries constructed with the arrangements deduplicate the pairs of their joins with the given granularity, `PerJoin` by default
+    pub fn with_distinct(mut self, distinct: DistinctGranularity) -> Self {
+        self.distinct = distinct;
+        self
+    }
+
+    pub fn distinct(&self) -> DistinctGranularity {
+        self.distinct
+    }
+
+    /// scope of the input
+    pub fn scope(&self) -> G {
+        self.scope.clone()
+    }
+
+    /// (source, target) pairs of the edges with the given label
+    /// panics if the label is not one of the labels the arrangements are constructed with
+    pub fn edges(&self, label: &str) -> Collection<G, (VertexType, VertexType), isize> {
+        self.edges.get(label).unwrap_or_else(|| panic!("Label {} is not arranged", label)).clone()
+    }
+
+    /// (source, target) pairs of the edges with the given label, arranged by their source
+    pub fn by_source(&mut self, label: &str) -> EdgeArrangement<G> {
+        let edges = self.edges(label);
+        self.by_source.entry(label.to_string()).or_insert_with(|| edges.arrange_by_key()).clone()
+    }
+
+    /// (target, source) pairs of the edges with the given label, arranged by their target
+    pub fn by_target(&mut self, label: &str) -> EdgeArrangement<G> {
+        let edges = self.edges(label);
+        self.by_target.entry(label.to_string()).or_insert_with(|| edges.map(|(s, t)| (t, s)).arrange_by_key()).clone()
+    }
+
+    /// # of arrangements constructed so far
+    pub fn num_arrangements(&self) -> usize {
+        self.by_source.len() + self.by_target.len()
+    }
+}
+
+/// Direction of paths in a transitive closure
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// (source, target) pairs of paths, i.e., each vertex with the vertices it reaches
+    Forward,
+    /// (target, source) pairs of paths, i.e., each vertex with the vertices it is reached from
+    Backward,
+}
+
+/// transitive closure of the given (source, target) pairs in the given direction, i.e., the endpoints of paths of one or more pairs
+pub fn transitive_closure<G: Scope>(pairs: &Collection<G, (VertexType, VertexType), isize>, direction: Direction) -> Collection<G, (VertexType, VertexType), isize> where G::Timestamp: Lattice + Ord {
+    let oriented = match direction {
+        Direction::Forward => pairs.clone(),
+        Direction::Backward => pairs.map(|(s, t)| (t, s)),
+    };
+    transitive_closure_arranged(&oriented.arrange_by_key())
+}
+
+/// transitive closure of pairs that are arranged by their first vertex, e.g., by `SharedArrangements::by_source` for the forward closure
+/// of the edges of a label or `by_target` for the backward closure
+/// The arrangement is entered into the iteration, so that it is shared by each round
+pub fn transitive_closure_arranged<G: Scope>(edges: &EdgeArrangement<G>) -> Collection<G, (VertexType, VertexType), isize> where G::Timestamp: Lattice + Ord {
+    let pairs = edges.as_collection(|first, second| (*first, *second));
+    pairs.iterate(|transitive| {
+        let edges = edges.enter(&transitive.scope());
+        transitive
+            .map(|(s, t)| (t, s))
+            .join_core(&edges, |_key, s1, t2| Some((*s1, *t2)))
+            .concat(&pairs.enter(&transitive.scope()))
+            .distinct()
+    })
+}
+
+/// transitive closure of the edges whose label is one of the given labels in the given direction, i.e., endpoints of paths
+/// of one or more edges with any of the labels
+pub fn reachability<G: Scope>(input: &Collection<G, StreamingGraphEdge, isize>, labels: &[String], direction: Direction) -> Collection<G, (VertexType, VertexType), isize> where G::Timestamp: Lattice + Ord {
+    let labels = labels.to_vec();
+    let pairs = input
+        .filter(move |sge| labels.iter().any(|label| *label == sge.label))
+        .map(|sge| (sge.source, sge.target));
+    transitive_closure(&pairs, direction)
+}
@@ -0,0 +1,158 @@
+//! Differencing of per-window query results, e.g., to verify SGA results against DD results of the same query
+//!
+//! A per-window result file has one result per line as `window,source,target[,start,end]`,
+//! where `window` is the window evaluation time and the optional `start,end` is the validity interval of the result.
+//! Intervals are compared only if both files have them, as DD results do not carry validity intervals.
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+
+use serde::Serialize;
+
+use crate::util::types::{HalfOpenTimeInterval, VertexType};
+
+/// Results of each window keyed by the window evaluation time, with the validity interval of each result if it is known
+pub type WindowOutputs = BTreeMap<u64, BTreeMap<(VertexType, VertexType), Option<HalfOpenTimeInterval>>>;
+
+/// a result that is in both windows with different validity intervals
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct IntervalMismatch {
+    pub source: VertexType,
+    pub target: VertexType,
+    pub expected: HalfOpenTimeInterval,
+    pub actual: HalfOpenTimeInterval,
+}
+
+/// differences of a single window, a window that is in only one of the files has all its results missing or extra
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct WindowDiff {
+    pub window: u64,
+    /// results of the expected window that are not in the actual window
+    pub missing: Vec<(VertexType, VertexType)>,
+    /// results of the actual window that are not in the expected window
+    pub extra: Vec<(VertexType, VertexType)>,
+    pub interval_mismatches: Vec<IntervalMismatch>,
+}
+
+impl WindowDiff {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.interval_mismatches.is_empty()
+    }
+}
+
+/// summary statistics of a diff
+#[derive(Serialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DiffSummary {
+    pub windows: u64,
+    pub differing_windows: u64,
+    pub matching: u64,
+    pub missing: u64,
+    pub extra: u64,
+    pub interval_mismatches: u64,
+}
+
+impl DiffSummary {
+    /// total # of differences
+    pub fn mismatches(&self) -> u64 {
+        self.missing + self.extra + self.interval_mismatches
+    }
+}
+
+/// structured diff of two per-window results, only windows with differences are listed
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ResultDiff {
+    pub summary: DiffSummary,
+    pub windows: Vec<WindowDiff>,
+}
+
+impl ResultDiff {
+    pub fn is_identical(&self) -> bool {
+        self.windows.is_empty()
+    }
+}
+
+/// computes differences of the actual results with respect to the expected results
+pub fn diff_outputs(expected: &WindowOutputs, actual: &WindowOutputs) -> ResultDiff {
+    let empty = BTreeMap::new();
+    let windows: BTreeSet<u64> = expected.keys().chain(actual.keys()).cloned().collect();
+
+    let mut diff = ResultDiff::default();
+    for window in windows {
+        let expected_results = expected.get(&window).unwrap_or(&empty);
+        let actual_results = actual.get(&window).unwrap_or(&empty);
+
+        let mut window_diff = WindowDiff { window, ..WindowDiff::default() };
+        for (pair, expected_interval) in expected_results {
+            match actual_results.get(pair) {
+                None => window_diff.missing.push(*pair),
+                Some(actual_interval) => match (expected_interval, actual_interval) {
+                    (Some(expected), Some(actual)) if expected != actual => window_diff.interval_mismatches.push(IntervalMismatch {
+                        source: pair.0,
+                        target: pair.1,
+                        expected: *expected,
+                        actual: *actual,
+                    }),
+                    _ => diff.summary.matching += 1,
+                }
+            }
+        }
+        window_diff.extra = actual_results.keys().filter(|pair| !expected_results.contains_key(pair)).cloned().collect();
+
+        diff.summary.windows += 1;
+        diff.summary.missing += window_diff.missing.len() as u64;
+        diff.summary.extra += window_diff.extra.len() as u64;
+        diff.summary.interval_mismatches += window_diff.interval_mismatches.len() as u64;
+        if !window_diff.is_empty() {
+            diff.summary.differing_windows += 1;
+            diff.windows.push(window_diff);
+        }
+    }
+
+    diff
+}
+
+/// computes differences of the actual result file with respect to the expected result file
+pub fn diff_files(expected_file: &str, actual_file: &str) -> Result<ResultDiff, String> {
+    let expected = read_window_outputs(expected_file)?;
+    let actual = read_window_outputs(actual_file)?;
+    Ok(diff_outputs(&expected, &actual))
+}
+
+/// reads a per-window result file
+pub fn read_window_outputs(path: &str) -> Result<WindowOutputs, String> {
+    let content = fs::read_to_string(path).map_err(|e| format!("Cannot read result file {}: {}", path, e))?;
+
+    let mut outputs = WindowOutputs::new();
+    for (line_number, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let fields = line.split(',')
+            .map(|field| field.trim().parse::<u64>())
+            .collect::<Result<Vec<u64>, _>>()
+            .map_err(|e| format!("Invalid result at {}:{}: {}", path, line_number + 1, e))?;
+
+        let (window, pair, interval) = match fields.as_slice() {
+            [window, source, target] => (*window, (*source, *target), None),
+            [window, source, target, start, end] => (*window, (*source, *target), Some(HalfOpenTimeInterval::new(*start, *end))),
+            _ => return Err(format!("Invalid result at {}:{}: expected `window,source,target[,start,end]`", path, line_number + 1)),
+        };
+        outputs.entry(window).or_insert_with(BTreeMap::new).insert(pair, interval);
+    }
+
+    Ok(outputs)
+}
+
+/// writes results into a per-window result file
+pub fn write_window_outputs(path: &str, outputs: &WindowOutputs) -> Result<(), String> {
+    let file = File::create(path).map_err(|e| format!("Cannot create result file {}: {}", path, e))?;
+    let mut writer = BufWriter::new(file);
+
+    for (window, results) in outputs {
+        for ((source, target), interval) in results {
+            match interval {
+                Some(interval) => writeln!(writer, "{},{},{},{},{}", window, source, target, interval.start, interval.end),
+                None => writeln!(writer, "{},{},{}", window, source, target),
+            }.map_err(|e| format!("Cannot write result file {}: {}", path, e))?;
+        }
+    }
+
+    writer.flush().map_err(|e| format!("Cannot write result file {}: {}", path, e))
+}
@@ -1,34 +1,90 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Alphabet that is frozen and shared across operators, e.g., to translate labels of edges and automata transitions consistently
+pub type SharedAlphabet = Arc<Alphabet>;
 
 /// Dictionary mapping for String labels
 /// maps String to stringly increasing integers
+#[derive(Clone, Debug, Default)]
 pub struct Alphabet {
     labels: Vec<String>,
     label_mapping: HashMap<String, usize>,
+    frozen: bool,
 }
 
 impl Alphabet {
     /// initialize an empty alphabet
     pub fn new() -> Self {
-        Self { labels: Vec::new(), label_mapping: HashMap::new() }
+        Self::default()
+    }
+
+    /// initialize an alphabet with the given labels, where ids follow their order
+    pub fn from_labels<'a, I: IntoIterator<Item=&'a str>>(labels: I) -> Self {
+        let mut alphabet = Self::new();
+        for label in labels {
+            alphabet.get_or_insert(label);
+        }
+        alphabet
     }
 
     /// checks whether given label is part of the alphabet
     pub fn contains(&self, label: &str) -> bool {
-        self.label_mapping.contains()
+        self.label_mapping.contains_key(label)
+    }
+
+    /// Return the mapping for given label if it exists
+    pub fn get(&self, label: &str) -> Option<usize> {
+        self.label_mapping.get(label).copied()
     }
 
     /// Return the mapping for given label,
     /// and create new mapping if label does not exists
-    pub fn get_or_insert(&mut self, label: &str) -> usize {
+    /// Returns None for labels that do not exist once the alphabet is frozen
+    pub fn get_or_insert(&mut self, label: &str) -> Option<usize> {
         if let Some(id) = self.label_mapping.get(label) {
-            id
+            Some(*id)
+        } else if self.frozen {
+            None
         } else {
             self.labels.push(label.to_string());
             let id = self.labels.len() - 1;
             self.label_mapping.insert(label.to_string(), id);
-            id
+            Some(id)
         }
     }
-}
 
+    /// returns the label of the given id
+    /// panics if the id is not assigned by this alphabet
+    pub fn resolve(&self, id: usize) -> &str {
+        self.labels.get(id).unwrap_or_else(|| panic!("Label id {} is not part of the alphabet", id))
+    }
+
+    /// freezes the alphabet, so that no new label is inserted
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// freezes the alphabet and returns a handle to share it across operators
+    pub fn into_shared(mut self) -> SharedAlphabet {
+        self.freeze();
+        Arc::new(self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// iterates over (id, label) pairs in the order of their ids
+    pub fn iter(&self) -> impl Iterator<Item=(usize, &str)> + '_ {
+        self.labels.iter().enumerate().map(|(id, label)| (id, label.as_str()))
+    }
+}
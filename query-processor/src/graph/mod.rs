@@ -1,87 +1,126 @@
 use std::cmp::{min};
-use std::hash::BuildHasherDefault;
 
-use hashbrown::HashMap;
-use hashers::fx_hash::FxHasher;
+use crate::operator::{MinPQIndex};
+use crate::query::automata::dfa::DFA;
+use crate::query::automata::lazy::LazyDFA;
 
+use self::plan::TransitionPlan;
+use self::super::util::types::{HalfOpenInterval, HalfOpenTimeInterval, OperationType, StateType, VertexStatePair, VertexType};
 
+pub mod alphabet;
+pub mod plan;
+pub mod static_graph;
 
-use crate::operator::{MinPQIndex};
-use crate::query::automata::dfa::DFA;
+pub use self::static_graph::StaticGraph;
+
+/// start timestamp stored with each edge of the product graph, where the expiry timestamp is the priority
+/// With the `expiry-only-edges` feature only expiry timestamps are stored to shrink the per-edge footprint,
+/// and stored edges are considered valid from the beginning, which is exact for in-order streams
+/// but may report earlier start timestamps for results of out-of-order inputs
+#[cfg(not(feature = "expiry-only-edges"))]
+type EdgeStart = u64;
+#[cfg(feature = "expiry-only-edges")]
+type EdgeStart = ();
+
+#[cfg(not(feature = "expiry-only-edges"))]
+fn edge_start(start: u64) -> EdgeStart {
+    start
+}
 
-use self::super::util::types::{HalfOpenInterval, HalfOpenTimeInterval, StateType, VertexStatePair, VertexType};
+#[cfg(feature = "expiry-only-edges")]
+fn edge_start(_start: u64) -> EdgeStart {}
+
+#[cfg(not(feature = "expiry-only-edges"))]
+fn start_timestamp(start: &EdgeStart) -> u64 {
+    *start
+}
+
+#[cfg(feature = "expiry-only-edges")]
+fn start_timestamp(_start: &EdgeStart) -> u64 {
+    0
+}
+
+/// edge that expires from the graph as a `(source, label, target, interval)` tuple
+pub type ExpiredEdge = (VertexType, String, VertexType, HalfOpenTimeInterval);
+
+/// adjacency of a graph node as (label id, neighbours) entries, which is scanned linearly as a node has few distinct labels
+type Adjacency = Vec<(usize, MinPQIndex<VertexType, EdgeStart>)>;
 
 /// Helper struct to store forward/backward adjacency list of each graph node
 #[derive(Clone, Debug)]
 struct GraphNode {
     node: VertexType,
-    outgoing_edges: HashMap<String, MinPQIndex<VertexType, u64>, BuildHasherDefault<FxHasher>>,
-    incoming_edges: HashMap<String, MinPQIndex<VertexType, u64>, BuildHasherDefault<FxHasher>>,
+    outgoing_edges: Adjacency,
+    incoming_edges: Adjacency,
 }
 
+/// neighbours of the given label id in the adjacency
+fn neighbours(adjacency: &Adjacency, label_id: usize) -> Option<&MinPQIndex<VertexType, EdgeStart>> {
+    adjacency.iter().find(|(id, _)| *id == label_id).map(|(_, neighbours)| neighbours)
+}
+
+/// adds the neighbour to the adjacency and returns true if it is new or its expiry timestamp increases
+fn add_neighbour(adjacency: &mut Adjacency, label_id: usize, neighbour: VertexType, interval: HalfOpenTimeInterval) -> bool {
+    let position = match adjacency.iter().position(|(id, _)| *id == label_id) {
+        Some(position) => position,
+        None => {
+            adjacency.push((label_id, MinPQIndex::default()));
+            adjacency.len() - 1
+        }
+    };
+    let edges = &mut adjacency[position].1;
+
+    if let Some((_start, expiry)) = edges.get(&neighbour) {
+        if expiry >= interval.get_end() {
+            return false;
+        }
+    }
+    edges.push(neighbour, edge_start(interval.start), interval.end);
+    true
+}
+
+/// min expiry timestamp of the edges in the adjacency
+fn min_expiry(adjacency: &Adjacency) -> u64 {
+    adjacency.iter()
+        .map(|(_, values)| values.peek().map_or(u64::MAX, |(_, _, expiry_ts)| expiry_ts))
+        .min().unwrap_or(u64::MAX)
+}
 
 impl GraphNode {
     fn new(vertex: VertexType) -> Self {
-        Self { node: vertex, outgoing_edges: HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default()), incoming_edges: HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default()) }
+        Self { node: vertex, outgoing_edges: Vec::new(), incoming_edges: Vec::new() }
     }
 
-    fn get_outgoing_edges(&self, label: &str) -> impl Iterator<Item=(u64, u64, u64)> + '_ {
-        self.outgoing_edges.get(label)
+    fn get_outgoing_edges(&self, label_id: usize) -> impl Iterator<Item=(u64, u64, u64)> + '_ {
+        neighbours(&self.outgoing_edges, label_id)
             .into_iter()
             .flat_map(|t| t.iter())
-            .map(|(v, start, end)| (v, *start, end))
+            .map(|(v, start, end)| (v, start_timestamp(start), end))
     }
 
-    fn get_incoming_edges(&self, label: &str) -> impl Iterator<Item=(u64, u64, u64)> + '_ {
-        self.incoming_edges.get(label)
+    fn get_incoming_edges(&self, label_id: usize) -> impl Iterator<Item=(u64, u64, u64)> + '_ {
+        neighbours(&self.incoming_edges, label_id)
             .into_iter()
             .flat_map(|t| t.iter())
-            .map(|(v, start, end)| (v, *start, end))
+            .map(|(v, start, end)| (v, start_timestamp(start), end))
     }
 
-    fn get_outgoing_edges_larger_than(&self, label: &str, low_watermark: u64) -> impl Iterator<Item=(u64, u64, u64)> + '_ {
-        self.outgoing_edges.get(label)
-            .into_iter()
-            .flat_map(|t| t.iter())
+    fn get_outgoing_edges_larger_than(&self, label_id: usize, low_watermark: u64) -> impl Iterator<Item=(u64, u64, u64)> + '_ {
+        self.get_outgoing_edges(label_id)
             .filter(move |(_, _start_ts, expiry_ts)| *expiry_ts > low_watermark)
-            .map(|(v, start, end)| (v, *start, end))
     }
 
-    fn add_incoming_neighbour(&mut self, label: String, neighbour: VertexType, interval: HalfOpenTimeInterval) -> bool {
-        let edges = self.incoming_edges.entry(label).or_insert(MinPQIndex::default());
-        let mut has_larger_expiry = true;
-        if let Some((_start, expiry)) = edges.get(&neighbour) {
-            if expiry >= interval.get_end() {
-                has_larger_expiry = false;
-            } else {
-                edges.push(neighbour, interval.start, interval.end);
-            }
-        } else {
-            edges.push(neighbour, interval.start, interval.end);
-        }
-
-        has_larger_expiry
+    fn add_incoming_neighbour(&mut self, label_id: usize, neighbour: VertexType, interval: HalfOpenTimeInterval) -> bool {
+        add_neighbour(&mut self.incoming_edges, label_id, neighbour, interval)
     }
 
-    fn add_outgoing_neighbour(&mut self, label: String, neighbour: VertexType, interval: HalfOpenTimeInterval) -> bool {
-        let edges = self.outgoing_edges.entry(label).or_insert(MinPQIndex::default());
-        let mut has_larger_expiry = true;
-        if let Some((_start, expiry)) = edges.get(&neighbour) {
-            if expiry >= interval.get_end() {
-                has_larger_expiry = false;
-            } else {
-                edges.push(neighbour, interval.start, interval.end);
-            }
-        } else {
-            edges.push(neighbour, interval.start, interval.end);
-        }
-        has_larger_expiry
+    fn add_outgoing_neighbour(&mut self, label_id: usize, neighbour: VertexType, interval: HalfOpenTimeInterval) -> bool {
+        add_neighbour(&mut self.outgoing_edges, label_id, neighbour, interval)
     }
 
     /// removes all expired inedges of the given vertex
     fn remove_expired_inedges(&mut self, low_watermark: u64) -> u64 {
-        // retain an entry if there are still edges after expiry
-        self.incoming_edges.retain(move |_, sources| {
+        for (_, sources) in self.incoming_edges.iter_mut() {
             // find expired edges
             while let Some((_, _, expiry_ts)) = sources.peek() {
                 if expiry_ts > low_watermark {
@@ -89,37 +128,40 @@ impl GraphNode {
                 }
                 sources.pop();
             }
-
-            //return false if the edge list empty so that it will be deleted from the hashmap
-            !sources.is_empty()
-        });
+        }
+        // retain an entry if there are still edges after expiry
+        self.incoming_edges.retain(|(_, sources)| !sources.is_empty());
 
         // return the min timestamp
-        self.incoming_edges.iter()
-            .map(|(_, values)| values.peek().map_or(u64::MAX, |(_, _, expiry_ts)| expiry_ts))
-            .min().unwrap_or(u64::MAX)
+        min_expiry(&self.incoming_edges)
     }
 
-    /// removes all expired outedges of the vertex
-    fn remove_expired_outedges(&mut self, low_watermark: u64) -> u64 {
-        // retain an entry if there are still edges after expiry
-        self.outgoing_edges.retain(move |_, targets| {
+    /// removes all expired outedges of the vertex, and passes each of them to `on_removed` with the id of its label
+    fn remove_expired_outedges<F: FnMut(VertexType, usize, VertexType, HalfOpenTimeInterval)>(&mut self, low_watermark: u64, on_removed: &mut F) -> u64 {
+        let source = self.node;
+        for (label_id, targets) in self.outgoing_edges.iter_mut() {
             // find expired edges
             while let Some((_, _, expiry_ts)) = targets.peek() {
                 if expiry_ts > low_watermark {
                     break;
                 }
-                targets.pop();
+                let (target, start, expiry_ts) = targets.pop().unwrap();
+                on_removed(source, *label_id, target, HalfOpenTimeInterval::new(start_timestamp(&start), expiry_ts));
             }
-
-            //return false if the edge list empty so that it will be deleted from the hashmap
-            !targets.is_empty()
-        });
+        }
+        // retain an entry if there are still edges after expiry
+        self.outgoing_edges.retain(|(_, targets)| !targets.is_empty());
 
         // return the min timestamp
+        min_expiry(&self.outgoing_edges)
+    }
+
+    /// outedges of the vertex that are expired at the given low watermark, with the id of their label
+    fn expired_outedges(&self, low_watermark: u64) -> impl Iterator<Item=(VertexType, usize, VertexType, HalfOpenTimeInterval)> + '_ {
         self.outgoing_edges.iter()
-            .map(|(_, values)| values.peek().map_or(u64::MAX, |(_, _, expiry_ts)| expiry_ts))
-            .min().unwrap_or(u64::MAX)
+            .flat_map(move |(label_id, targets)| targets.iter()
+                .filter(move |(_, _, expiry_ts)| *expiry_ts <= low_watermark)
+                .map(move |(target, start, expiry_ts)| (self.node, *label_id, target, HalfOpenTimeInterval::new(start_timestamp(start), expiry_ts))))
     }
 
     fn is_isolated(&self) -> bool {
@@ -127,26 +169,103 @@ impl GraphNode {
     }
 }
 
+/// Automaton that the product graph is built on, either a DFA that is constructed upfront,
+/// or a `LazyDFA` that materializes its states for the labels of the edges as they are inserted
+#[derive(Clone, Debug)]
+pub enum QueryAutomaton {
+    Eager(DFA),
+    Lazy(LazyDFA),
+}
+
+impl QueryAutomaton {
+    /// DFA over the labels that are materialized so far
+    pub fn dfa(&self) -> &DFA {
+        match self {
+            QueryAutomaton::Eager(automaton) => automaton,
+            QueryAutomaton::Lazy(automaton) => automaton.dfa(),
+        }
+    }
+}
+
+impl From<DFA> for QueryAutomaton {
+    fn from(automaton: DFA) -> Self {
+        QueryAutomaton::Eager(automaton)
+    }
+}
+
+impl From<LazyDFA> for QueryAutomaton {
+    fn from(automaton: LazyDFA) -> Self {
+        QueryAutomaton::Lazy(automaton)
+    }
+}
+
 /// MinPQIndex backed adjacency list implementation to store the product graph
 /// It transparently stores the structure of the product graph based on the given DFA
 /// Each edge is associated with a validity interval, whose upper-end is used the priority in MinPQIndex
 /// It allows quick look-ups to retrieve all neighbours of a given node and to traversel all expired edges
+/// Adjacency is indexed by the label ids of a `TransitionPlan` compiled from the DFA, so that traversals iterate
+/// the transitions of a state as a slice and look up neighbours by id
 #[derive(Clone, Debug)]
 pub struct Graph {
     node_index: MinPQIndex<VertexType, GraphNode>,
-    query_automata: DFA,
+    query_automata: QueryAutomaton,
+    transition_plan: TransitionPlan,
+    // backward adjacency is only traversed by the NT approach
+    maintain_incoming_edges: bool,
+    // edges of labels outside the alphabet are stored as well, see `retain_all_labels`
+    retain_all_labels: bool,
 }
 
 impl Graph {
-    pub fn new(query_automata: DFA) -> Self {
+    /// creates a graph that maintains only the adjacency required by the given approach,
+    /// i.e., incoming edges are not indexed for the Direct approach, which roughly halves the graph size
+    pub fn for_operation(query_automata: DFA, approach: OperationType) -> Self {
+        Self::for_automaton(QueryAutomaton::Eager(query_automata), approach)
+    }
+
+    /// creates a graph as `for_operation` over the given automaton backend
+    pub fn for_automaton(query_automata: QueryAutomaton, approach: OperationType) -> Self {
         Self {
             node_index: MinPQIndex::default(),
+            transition_plan: TransitionPlan::compile(query_automata.dfa()),
             query_automata: query_automata,
+            maintain_incoming_edges: approach == OperationType::NegativeTuple,
+            retain_all_labels: false,
         }
     }
 
+    /// stores edges of all labels from now on, including labels that no transition of the automaton has,
+    /// so that an automaton that is swapped in later finds them in the graph, see `util::hot_swap`
+    pub fn retain_all_labels(&mut self) {
+        self.retain_all_labels = true;
+    }
+
+    /// returns true if edges of labels outside the alphabet of the automaton are stored
+    pub fn retains_all_labels(&self) -> bool {
+        self.retain_all_labels
+    }
+
+    /// replaces the automaton that the graph is built on, e.g., to evaluate an edited query over the window content
+    /// Edges are retained and labels keep their ids, where edges of labels that only the new automaton has are part of the graph
+    /// only if all labels are retained, and are stored as they arrive otherwise
+    pub fn swap_automaton(&mut self, query_automata: QueryAutomaton) {
+        self.transition_plan.recompile(query_automata.dfa());
+        self.query_automata = query_automata;
+    }
+
+    /// DFA of the query, i.e., the states and transitions that are materialized so far for a lazy automaton
     pub fn get_query_automata(&self) -> &DFA {
-        &self.query_automata
+        self.query_automata.dfa()
+    }
+
+    /// transitions of the query automata compiled into label ids
+    pub fn get_transition_plan(&self) -> &TransitionPlan {
+        &self.transition_plan
+    }
+
+    /// number of vertices with at least one valid indexed edge
+    pub fn node_count(&self) -> usize {
+        self.node_index.len()
     }
 
     fn get_node(&self, vertex: VertexType) -> Option<&GraphNode> {
@@ -155,81 +274,121 @@ impl Graph {
 
     /// Get outgoing edges of a given vertex as (vertex-state) pairs
     pub fn get_outgoing_edges(&self, vertex: VertexType, state: StateType) -> impl Iterator<Item=(VertexStatePair, HalfOpenTimeInterval)> + '_ {
+        let transitions = self.transition_plan.outgoing(state);
         // get all outdoing edges of given source state
-        self.query_automata.get_outgoing_transitions(state).into_iter()
-            .flat_map(move |(label, target_state)| {
-                self.get_node(vertex).into_iter()
-                    .flat_map(move |graph_node| graph_node.get_outgoing_edges(&label))
+        self.get_node(vertex).into_iter()
+            .flat_map(move |graph_node| transitions.iter().flat_map(move |&(label_id, target_state)| {
+                graph_node.get_outgoing_edges(label_id)
                     .map(move |(target_vertex, start, end)| ((target_vertex, target_state), HalfOpenTimeInterval::new(start, end)))
-            })
+            }))
     }
 
     /// get outgoing edges of a given vertex with expiry timestamp larger than the `low_watermark`
     pub fn get_outgoing_edges_larger_than(&self, vertex: VertexType, state: StateType, low_watermark: u64) -> impl Iterator<Item=(VertexStatePair, HalfOpenTimeInterval)> + '_ {
+        let transitions = self.transition_plan.outgoing(state);
         // get all outdoing edges of given source state
-        self.query_automata.get_outgoing_transitions(state).into_iter()
-            .flat_map(move |(label, target_state)| {
-                self.get_node(vertex).into_iter()
-                    .flat_map(move |graph_node| graph_node.get_outgoing_edges_larger_than(&label, low_watermark))
+        self.get_node(vertex).into_iter()
+            .flat_map(move |graph_node| transitions.iter().flat_map(move |&(label_id, target_state)| {
+                graph_node.get_outgoing_edges_larger_than(label_id, low_watermark)
                     .map(move |(target_vertex, start, end)| ((target_vertex, target_state), HalfOpenTimeInterval::new(start, end)))
-            })
+            }))
     }
 
     /// get incoming edges of a given vertex with expiry timestamp larger then the `low_watermark`
+    /// incoming edges are indexed only for the NT approach
     pub fn get_incoming_edges(&self, vertex: VertexType, state: StateType) -> impl Iterator<Item=(VertexStatePair, HalfOpenTimeInterval)> + '_ {
-        // get all outdoing edges of given source state
-        self.query_automata.get_incoming_transitions(state).into_iter()
-            .flat_map(move |(label, target_state)| {
-                self.get_node(vertex).into_iter()
-                    .flat_map(move |graph_node| graph_node.get_incoming_edges(&label))
-                    .map(move |(target_vertex, start, end)| ((target_vertex, target_state), HalfOpenTimeInterval::new(start, end)))
-            })
+        debug_assert!(self.maintain_incoming_edges, "Incoming edges are not maintained for the Direct approach");
+        let transitions = self.transition_plan.incoming(state);
+        // get all incoming edges of given target state
+        self.get_node(vertex).into_iter()
+            .flat_map(move |graph_node| transitions.iter().flat_map(move |&(label_id, source_state)| {
+                graph_node.get_incoming_edges(label_id)
+                    .map(move |(source_vertex, start, end)| ((source_vertex, source_state), HalfOpenTimeInterval::new(start, end)))
+            }))
     }
 
     /// update underlying graph with given graph edge
     /// return true if it replaces an existing edge with a lower timestamp
+    /// edges whose label is not in the alphabet of the query automata are not stored, and return false, unless all labels are retained
     pub fn insert_edge(&mut self, source: VertexType, label: String, target: VertexType, interval: HalfOpenTimeInterval) -> bool {
-        // set to false only if there is an existing edge with a lower existing timestamp
-        let mut has_larger_expiry = true;
-
-        // first obtain or create the source
+        let known_label_id = self.transition_plan.label_id(&label);
+        // a retained label may have an id before a lazy automaton observes it
+        let label_id = match known_label_id {
+            Some(label_id) if !self.retain_all_labels => Some(label_id),
+            _ => self.observe_label(&label).or(known_label_id),
+        };
+        let label_id = match label_id {
+            Some(label_id) => label_id,
+            None if self.retain_all_labels => self.transition_plan.insert_label(&label),
+            None => return false,
+        };
         let new_expiry_ts = interval.get_end();
 
-        // index lookup by source vertex
-        if let Some((entry, _)) = self.node_index.get_mut(&source) {
-            // simply return mutable entry if the node already exists
-            has_larger_expiry = entry.add_outgoing_neighbour(label.clone(), target, interval);
-            // update priority if new edge has a lower expiry timestamp
-        } else {
-            // otherwise create the entry
-            let mut source_node = GraphNode::new(source);
-            source_node.add_outgoing_neighbour(label.clone(), target, interval);
-            self.node_index.push(source, source_node, interval.get_end());
+        // obtain or create the source, and update its priority only if it gets smaller
+        // set to false only if there is an existing edge with a lower existing timestamp
+        let has_larger_expiry = self.node_index.upsert_with(source, || GraphNode::new(source),
+                                                            |entry| entry.add_outgoing_neighbour(label_id, target, interval), new_expiry_ts);
+
+        if !self.maintain_incoming_edges {
+            return has_larger_expiry;
         }
-        // update priority only if it gets smaller
-        self.node_index.try_decrease_priority(&source, new_expiry_ts);
-
-        // index lookup by target vertex
-        if let Some((entry, _)) = self.node_index.get_mut(&target) {
-            // simply return mutable entry if the node already exists
-            entry.add_incoming_neighbour(label.clone(), source, interval);
-            // update priority if new edge has a lower expiry timestamp
-        } else {
-            // otherwise create the entry
-            let mut target_node = GraphNode::new(target);
-            target_node.add_incoming_neighbour(label.clone(), source, interval);
-            self.node_index.push(target, target_node, interval.get_end());
-        };
-        // update priority only if it gets smaller
-        self.node_index.try_decrease_priority(&target, new_expiry_ts);
+
+        // obtain or create the target, and update its priority only if it gets smaller
+        self.node_index.upsert_with(target, || GraphNode::new(target),
+                                    |entry| entry.add_incoming_neighbour(label_id, source, interval), new_expiry_ts);
 
         // indicate whether incoming edge has increased expiry timestamp of an existing edge
         has_larger_expiry
     }
 
-    /// removes all edges that are older than the provided timestamp
+    /// materializes the transitions of a label that is not observed yet by a lazy automaton, and returns the id of the label
+    /// or None if the automaton is not lazy or the label is not in its alphabet
+    fn observe_label(&mut self, label: &str) -> Option<usize> {
+        if let QueryAutomaton::Lazy(automaton) = &mut self.query_automata {
+            if automaton.observe(label) {
+                self.transition_plan.recompile(automaton.dfa());
+                return self.transition_plan.label_id(label);
+            }
+        }
+        None
+    }
+
+    /// removes all edges that are older than the provided timestamp, and returns the removed edges
     /// it does not require linear scan due to underlying MinPQIndex
-    pub fn remove_edges(&mut self, low_watermark: u64) {
+    pub fn remove_edges(&mut self, low_watermark: u64) -> Vec<ExpiredEdge> {
+        let mut removed_edges = Vec::new();
+        self.remove_edges_with(low_watermark, |source, label, target, interval| removed_edges.push((source, label.to_string(), target, interval)));
+        removed_edges
+    }
+
+    /// returns the edges that `remove_edges` would remove at the given timestamp without removing them
+    /// it scans vertices whose earliest expiry is passed
+    pub fn expired_edges(&self, low_watermark: u64) -> Vec<ExpiredEdge> {
+        self.node_index.iter()
+            .filter(|(_, _, priority)| *priority <= low_watermark)
+            .flat_map(|(_, node, _)| node.expired_outedges(low_watermark))
+            .map(|(source, label_id, target, interval)| (source, self.transition_plan.label(label_id).to_string(), target, interval))
+            .collect()
+    }
+
+    /// returns the sorted labels whose edges are stored, i.e., the alphabet of the automaton, or None if edges of all labels are stored
+    pub fn stored_labels(&self) -> Option<Vec<String>> {
+        if self.retain_all_labels {
+            return None;
+        }
+        let mut labels: Vec<String> = self.query_automata.dfa().alphabet.iter().cloned().collect();
+        labels.sort();
+        Some(labels)
+    }
+
+    /// returns all edges of the graph, e.g., to dump a snapshot of the window
+    pub fn edges(&self) -> Vec<ExpiredEdge> {
+        self.expired_edges(u64::MAX)
+    }
+
+    /// removes all edges that are older than the provided timestamp as `remove_edges`, and passes each removed edge to `on_removed`
+    /// instead of collecting them, e.g., to emit retractions without allocating the removed edges
+    pub fn remove_edges_with<F: FnMut(VertexType, &str, VertexType, HalfOpenTimeInterval)>(&mut self, low_watermark: u64, mut on_removed: F) {
         // iterate over edges and update adjacency lists
 
         let mut expiry_candidates = Vec::new();
@@ -245,7 +404,8 @@ impl Graph {
 
         for (key, mut node) in expiry_candidates.into_iter() {
             let min_incoming_ts = node.remove_expired_inedges(low_watermark);
-            let min_outgoing_ts = node.remove_expired_outedges(low_watermark);
+            let plan = &self.transition_plan;
+            let min_outgoing_ts = node.remove_expired_outedges(low_watermark, &mut |source, label_id, target, interval| on_removed(source, plan.label(label_id), target, interval));
 
             // if node still has neighbours, update the node index
             if !node.is_isolated() {
@@ -254,3 +414,100 @@ impl Graph {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::{ExpiredEdge, Graph};
+    use crate::query::parser::RPQParser;
+    use crate::util::types::{HalfOpenInterval, HalfOpenTimeInterval, OperationType};
+
+    /// start timestamp that the graph reports for an edge inserted with the given start
+    fn reported_start(start: u64) -> u64 {
+        if cfg!(feature = "expiry-only-edges") { 0 } else { start }
+    }
+
+    #[test]
+    fn removed_edges() {
+        let automata = RPQParser::new().parse_rpq("a/b").unwrap();
+        let mut graph = Graph::for_operation(automata, OperationType::NegativeTuple);
+        graph.insert_edge(1, "a".to_string(), 2, HalfOpenTimeInterval::new(0, 10));
+        graph.insert_edge(2, "b".to_string(), 3, HalfOpenTimeInterval::new(5, 20));
+        graph.insert_edge(1, "b".to_string(), 3, HalfOpenTimeInterval::new(8, 15));
+
+        let endpoints = |edges: Vec<ExpiredEdge>| {
+            let mut endpoints: Vec<_> = edges.into_iter().map(|(s, label, t, interval)| (s, label, t, interval.get_end())).collect();
+            endpoints.sort();
+            endpoints
+        };
+
+        // dry-run does not remove any edge
+        assert_eq!(endpoints(graph.expired_edges(15)), vec![(1, "a".to_string(), 2, 10), (1, "b".to_string(), 3, 15)]);
+        assert_eq!(endpoints(graph.expired_edges(15)), endpoints(graph.remove_edges(15)));
+        assert!(graph.expired_edges(15).is_empty());
+        assert!(graph.remove_edges(15).is_empty());
+
+        assert_eq!(endpoints(graph.remove_edges(20)), vec![(2, "b".to_string(), 3, 20)]);
+        assert_eq!(graph.node_count(), 0);
+    }
+
+    #[test]
+    fn incoming_edges_only_for_negative_tuple() {
+        let graph = |approach: OperationType| {
+            let mut graph = Graph::for_operation(RPQParser::new().parse_rpq("a/b").unwrap(), approach);
+            graph.insert_edge(1, "a".to_string(), 2, HalfOpenTimeInterval::new(0, 10));
+            graph.insert_edge(2, "b".to_string(), 3, HalfOpenTimeInterval::new(5, 20));
+            graph
+        };
+
+        // the Direct approach indexes only sources of edges, so that 3 is not a node
+        let mut direct = graph(OperationType::Direct);
+        let mut negative_tuple = graph(OperationType::NegativeTuple);
+        assert_eq!(direct.node_count(), 2);
+        assert_eq!(negative_tuple.node_count(), 3);
+        assert_eq!(negative_tuple.get_incoming_edges(3, 2).collect::<Vec<_>>(), vec![((2, 1), HalfOpenTimeInterval::new(reported_start(5), 20))]);
+
+        // both traverse and expire the same outgoing edges
+        assert_eq!(direct.get_outgoing_edges(2, 1).collect::<Vec<_>>(), negative_tuple.get_outgoing_edges(2, 1).collect::<Vec<_>>());
+        assert_eq!(direct.remove_edges(20).len(), 2);
+        assert_eq!(negative_tuple.remove_edges(20).len(), 2);
+        assert_eq!(direct.node_count(), 0);
+        assert_eq!(negative_tuple.node_count(), 0);
+    }
+
+    #[test]
+    fn edges_report_their_start() {
+        let mut graph = Graph::for_operation(RPQParser::new().parse_rpq("a/b").unwrap(), OperationType::NegativeTuple);
+        graph.insert_edge(1, "a".to_string(), 2, HalfOpenTimeInterval::new(5, 10));
+        assert_eq!(graph.get_outgoing_edges(1, 0).collect::<Vec<_>>(), vec![((2, 1), HalfOpenTimeInterval::new(reported_start(5), 10))]);
+
+        // with `expiry-only-edges` edges are valid from the beginning, also when they expire
+        let removed = graph.remove_edges(10);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].3.get_start(), reported_start(5));
+        assert_eq!(removed[0].3.get_end(), 10);
+    }
+
+    #[test]
+    fn compiled_transitions() {
+        let automata = RPQParser::new().parse_rpq("a/b").unwrap();
+        let mut graph = Graph::for_operation(automata, OperationType::NegativeTuple);
+        assert!(graph.insert_edge(1, "a".to_string(), 2, HalfOpenTimeInterval::new(0, 10)));
+        assert!(graph.insert_edge(2, "b".to_string(), 3, HalfOpenTimeInterval::new(0, 10)));
+        // labels outside of the alphabet are not stored
+        assert!(!graph.insert_edge(2, "c".to_string(), 4, HalfOpenTimeInterval::new(0, 10)));
+
+        let after_a = graph.get_query_automata().state_move(0, "a").unwrap();
+        let after_b = graph.get_query_automata().state_move(after_a, "b").unwrap();
+        let plan = graph.get_transition_plan();
+        let a = plan.label_id("a").unwrap();
+        assert_eq!(plan.label(a), "a");
+        assert_eq!(plan.label_id("c"), None);
+        assert_eq!(plan.outgoing(0), &[(a, after_a)]);
+
+        let outgoing: Vec<_> = graph.get_outgoing_edges(2, after_a).map(|(pair, _)| pair).collect();
+        assert_eq!(outgoing, vec![(3, after_b)]);
+        let incoming: Vec<_> = graph.get_incoming_edges(2, after_a).map(|(pair, _)| pair).collect();
+        assert_eq!(incoming, vec![(1, 0)]);
+        assert_eq!(graph.get_outgoing_edges_larger_than(2, after_a, 10).count(), 0);
+    }
+}
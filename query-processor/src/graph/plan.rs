@@ -0,0 +1,78 @@
+use crate::graph::alphabet::Alphabet;
+use crate::query::automata::dfa::DFA;
+use crate::util::types::StateType;
+
+/// Transitions of a DFA compiled into label ids, so that the graph stores and traverses adjacency by id
+/// instead of hashing the label of each transition on every expansion
+/// Label ids follow the order of the alphabet of the automaton, which is frozen when the plan is compiled
+#[derive(Clone, Debug)]
+pub struct TransitionPlan {
+    alphabet: Alphabet,
+    // (label id, target state) of the transitions that originate from each state
+    outgoing: Vec<Vec<(usize, StateType)>>,
+    // (label id, source state) of the transitions that lead to each state
+    incoming: Vec<Vec<(usize, StateType)>>,
+}
+
+impl TransitionPlan {
+    pub fn compile(automata: &DFA) -> Self {
+        Self::compile_after(automata, &Alphabet::new())
+    }
+
+    /// compiles the transitions of the automaton again, e.g., after it grows, where labels keep their ids
+    pub fn recompile(&mut self, automata: &DFA) {
+        *self = Self::compile_after(automata, &self.alphabet);
+    }
+
+    /// compiles the transitions of the automaton, where labels of the given alphabet keep their ids and others follow them
+    fn compile_after(automata: &DFA, previous: &Alphabet) -> Self {
+        let mut labels: Vec<&str> = automata.alphabet.iter().map(|label| label.as_str()).filter(|label| !previous.contains(label)).collect();
+        // ids are assigned in a deterministic order regardless of the hash order of the alphabet
+        labels.sort_unstable();
+        let mut alphabet = Alphabet::from_labels(previous.iter().map(|(_, label)| label).chain(labels));
+        alphabet.freeze();
+
+        let compile_state = |transitions: Vec<(&str, StateType)>| -> Vec<(usize, StateType)> {
+            transitions.into_iter()
+                .map(|(label, state)| (alphabet.get(label).expect("Transition labels are part of the alphabet"), state))
+                .collect()
+        };
+        let outgoing = (0..automata.num_states).map(|state| compile_state(automata.iter_outgoing_transitions(state).collect())).collect();
+        let incoming = (0..automata.num_states).map(|state| compile_state(automata.iter_incoming_transitions(state).collect())).collect();
+
+        Self { alphabet, outgoing, incoming }
+    }
+
+    /// returns the id of the given label, or None if no transition has the label and it is not inserted
+    pub fn label_id(&self, label: &str) -> Option<usize> {
+        self.alphabet.get(label)
+    }
+
+    /// returns the id of the given label, where a label without transitions gets the next id,
+    /// e.g., to store edges of labels that a swapped automaton may have
+    pub fn insert_label(&mut self, label: &str) -> usize {
+        if let Some(id) = self.alphabet.get(label) {
+            return id;
+        }
+        let mut alphabet = Alphabet::from_labels(self.alphabet.iter().map(|(_, label)| label).chain(std::iter::once(label)));
+        alphabet.freeze();
+        self.alphabet = alphabet;
+        self.alphabet.get(label).expect("Inserted labels are part of the alphabet")
+    }
+
+    /// returns the label of the given id
+    /// panics if the id is not assigned by this plan
+    pub fn label(&self, id: usize) -> &str {
+        self.alphabet.resolve(id)
+    }
+
+    /// (label id, target state) pairs of the transitions that originate from the given state
+    pub fn outgoing(&self, state: StateType) -> &[(usize, StateType)] {
+        &self.outgoing[state as usize]
+    }
+
+    /// (label id, source state) pairs of the transitions that lead to the given state
+    pub fn incoming(&self, state: StateType) -> &[(usize, StateType)] {
+        &self.incoming[state as usize]
+    }
+}
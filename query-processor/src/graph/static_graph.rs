@@ -0,0 +1,160 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::graph::alphabet::Alphabet;
+use crate::query::automata::dfa::DFA;
+use crate::util::types::VertexType;
+
+/// Adjacency of the edges with a single label in compressed sparse row form, where the targets of vertex `v`
+/// are `targets[offsets[v]..offsets[v + 1]]` in terms of dense vertex ids
+#[derive(Clone, Debug, Default)]
+struct Csr {
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
+}
+
+impl Csr {
+    /// builds the adjacency of the given (source, target) pairs over `num_vertices` dense vertex ids, dropping duplicate edges
+    fn from_pairs(mut pairs: Vec<(usize, usize)>, num_vertices: usize) -> Self {
+        pairs.sort_unstable();
+        pairs.dedup();
+
+        let mut offsets = vec![0; num_vertices + 1];
+        pairs.iter().for_each(|(source, _)| offsets[source + 1] += 1);
+        for vertex in 0..num_vertices {
+            offsets[vertex + 1] += offsets[vertex];
+        }
+
+        Self { offsets, targets: pairs.into_iter().map(|(_, target)| target).collect() }
+    }
+
+    fn targets(&self, vertex: usize) -> &[usize] {
+        &self.targets[self.offsets[vertex]..self.offsets[vertex + 1]]
+    }
+}
+
+/// Immutable graph of a static edge set with one CSR adjacency per label id, for batch evaluation that does not need
+/// the expiry-ordered adjacency of the windowed `Graph`
+/// Vertices are mapped to dense ids in the order they first appear in the edges
+#[derive(Clone, Debug)]
+pub struct StaticGraph {
+    alphabet: Alphabet,
+    adjacency: Vec<Csr>,
+    vertices: Vec<VertexType>,
+    vertex_ids: HashMap<VertexType, usize>,
+    num_edges: usize,
+}
+
+impl StaticGraph {
+    /// builds the graph of the given (source, label, target) edges, where duplicate edges are stored once
+    pub fn from_edges<'a, I: IntoIterator<Item=(VertexType, &'a str, VertexType)>>(edges: I) -> Self {
+        let mut alphabet = Alphabet::new();
+        let mut vertices = Vec::new();
+        let mut vertex_ids = HashMap::new();
+        let mut pairs: Vec<Vec<(usize, usize)>> = Vec::new();
+
+        let mut vertex_id = |vertex: VertexType| *vertex_ids.entry(vertex).or_insert_with(|| {
+            vertices.push(vertex);
+            vertices.len() - 1
+        });
+        for (source, label, target) in edges {
+            let label_id = alphabet.get_or_insert(label).expect("Alphabet of a static graph is not frozen");
+            if label_id == pairs.len() {
+                pairs.push(Vec::new());
+            }
+            let (source_id, target_id) = (vertex_id(source), vertex_id(target));
+            pairs[label_id].push((source_id, target_id));
+        }
+        alphabet.freeze();
+
+        let adjacency: Vec<Csr> = pairs.into_iter().map(|label_pairs| Csr::from_pairs(label_pairs, vertices.len())).collect();
+        let num_edges = adjacency.iter().map(|csr| csr.targets.len()).sum();
+
+        Self { alphabet, adjacency, vertices, vertex_ids, num_edges }
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    /// # of distinct edges
+    pub fn edge_count(&self) -> usize {
+        self.num_edges
+    }
+
+    /// iterates over the targets of the edges with the given label that originate from the given vertex
+    pub fn neighbours<'a>(&'a self, vertex: VertexType, label: &str) -> impl Iterator<Item=VertexType> + 'a {
+        let targets: &[usize] = match (self.vertex_ids.get(&vertex), self.alphabet.get(label)) {
+            (Some(vertex_id), Some(label_id)) => self.adjacency[label_id].targets(*vertex_id),
+            _ => &[],
+        };
+        targets.iter().map(move |target| self.vertices[*target])
+    }
+
+    /// returns the sorted (source, target) pairs of paths of at least one edge whose labels form a word accepted by the automaton,
+    /// with a BFS over the product of the graph and the automaton from each vertex
+    pub fn reachability(&self, automaton: &DFA) -> Vec<(VertexType, VertexType)> {
+        // (label id, target state) of transitions from each state, where labels that are not in the graph are dropped
+        let transitions: Vec<Vec<(usize, u8)>> = (0..automaton.num_states)
+            .map(|state| automaton.iter_outgoing_transitions(state)
+                .filter_map(|(label, target)| self.alphabet.get(label).map(|label_id| (label_id, target)))
+                .collect())
+            .collect();
+        let num_states = automaton.num_states as usize;
+
+        // visited product nodes are reset after each source through the nodes that are set
+        let mut visited = vec![false; self.vertices.len() * num_states];
+        let mut visited_nodes = Vec::new();
+        let mut queue = VecDeque::new();
+        let mut results = Vec::new();
+        for source in 0..self.vertices.len() {
+            let mut targets = Vec::new();
+            // the start node is visited only if a path leads back to it
+            queue.push_back((source, 0u8));
+            while let Some((vertex, state)) = queue.pop_front() {
+                for (label_id, target_state) in transitions[state as usize].iter() {
+                    for target in self.adjacency[*label_id].targets(vertex) {
+                        let node = target * num_states + *target_state as usize;
+                        if !visited[node] {
+                            visited[node] = true;
+                            visited_nodes.push(node);
+                            if automaton.is_final_state(*target_state) {
+                                targets.push(self.vertices[*target]);
+                            }
+                            queue.push_back((*target, *target_state));
+                        }
+                    }
+                }
+            }
+            visited_nodes.drain(..).for_each(|node| visited[node] = false);
+
+            targets.sort_unstable();
+            targets.dedup();
+            results.extend(targets.into_iter().map(|target| (self.vertices[source], target)));
+        }
+
+        results.sort_unstable();
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::StaticGraph;
+    use crate::query::parser::RPQParser;
+
+    #[test]
+    fn csr_reachability() {
+        let graph = StaticGraph::from_edges(vec![(1, "a", 2), (2, "b", 3), (3, "b", 4), (2, "b", 3), (4, "a", 1)]);
+        assert_eq!(graph.vertex_count(), 4);
+        assert_eq!(graph.edge_count(), 4);
+        assert_eq!(graph.neighbours(2, "b").collect::<Vec<_>>(), vec![3]);
+        assert_eq!(graph.neighbours(2, "a").count(), 0);
+        assert_eq!(graph.neighbours(5, "a").count(), 0);
+
+        let automaton = RPQParser::new().parse_rpq("a/b+").unwrap();
+        assert_eq!(graph.reachability(&automaton), vec![(1, 3), (1, 4)]);
+        // paths return to their source only through a cycle
+        let automaton = RPQParser::new().parse_rpq("(a/b/b)+/a").unwrap();
+        assert_eq!(graph.reachability(&automaton), vec![(1, 1)]);
+    }
+}
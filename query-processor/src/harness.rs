@@ -0,0 +1,338 @@
+//! Helpers to run queries of the query library end-to-end over small inputs in a single worker
+//!
+//! Each run replays a list of edges, where every edge is sent at the epoch of its own timestamp,
+//! and collects results as a set of (source, target) pairs for each window.
+//! Windows are evaluated at every `slide_size` after the first edge until the last edge
+//! and a window evaluated at `T` contains edges with timestamps in `(T - window_size, T]`.
+//!
+//! Runs can be controlled through a `RunControl` handle to pause ingestion, advance one window at a time and resume,
+//! and to observe results of each window as soon as it is completed, as well as the time it took to complete,
+//! measured with the `Clock` of the handle, e.g., a `ManualClock` in deterministic tests.
+extern crate timely;
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::rc::Rc;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use timely::Configuration;
+use timely::communication::allocator::Generic;
+use timely::dataflow::{InputHandle, ProbeHandle, Stream};
+use timely::dataflow::operators::{Input, Inspect, Probe};
+use timely::dataflow::scopes::Child;
+use timely::worker::Worker;
+
+#[cfg(feature = "dd")]
+use crate::dd::{DistinctGranularity, SharedArrangements, WindowedInput};
+use crate::input::{GraphEdge, InputFileReader, LineFileReader, StreamingGraphEdge};
+use crate::input::tuple::StreamingGraphTuple;
+use crate::operator::window::SlidingWindow;
+use crate::query::query_library::SGAQueryLibrary;
+#[cfg(feature = "dd")]
+use crate::query::query_library::DDQueryLibrary;
+use crate::util::clock::{Clock, system_clock};
+use crate::util::types::VertexType;
+
+/// Results of a query as the set of (source, target) pairs for each window, keyed by the window evaluation time
+pub type WindowResults = BTreeMap<u64, BTreeSet<(VertexType, VertexType)>>;
+
+/// Control handle of a harness run that is shared between the run and its controller
+/// A controlled run blocks while it is paused, so it has to be executed on a separate thread than its controller
+#[derive(Clone)]
+pub struct RunControl {
+    state: Arc<(Mutex<ControlState>, Condvar)>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for RunControl {
+    fn default() -> Self {
+        Self { state: Arc::default(), clock: system_clock() }
+    }
+}
+
+#[derive(Default)]
+struct ControlState {
+    paused: bool,
+    // # of windows that can be completed while paused
+    steps: usize,
+    snapshots: WindowResults,
+    // time to complete each window, excluding the time it is paused
+    latencies: BTreeMap<u64, Duration>,
+    finished: bool,
+}
+
+impl RunControl {
+    /// creates a handle for a run that starts running
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// creates a handle for a run that starts paused, i.e., no window is completed until `step` or `resume`
+    pub fn new_paused() -> Self {
+        let control = Self::default();
+        control.pause();
+        control
+    }
+
+    /// measures the time to complete windows with the given clock instead of the system clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// pauses the run before completing the next window
+    pub fn pause(&self) {
+        let (state, _) = &*self.state;
+        state.lock().unwrap().paused = true;
+    }
+
+    /// resumes the run
+    pub fn resume(&self) {
+        let (state, condvar) = &*self.state;
+        let mut state = state.lock().unwrap();
+        state.paused = false;
+        state.steps = 0;
+        condvar.notify_all();
+    }
+
+    /// allows a paused run to complete one more window
+    pub fn step(&self) {
+        let (state, condvar) = &*self.state;
+        state.lock().unwrap().steps += 1;
+        condvar.notify_all();
+    }
+
+    /// blocks until the given number of windows are completed, or the run is finished, and returns results of completed windows
+    pub fn wait_for_windows(&self, window_count: usize) -> WindowResults {
+        let (state, condvar) = &*self.state;
+        let state = condvar.wait_while(state.lock().unwrap(), |state| state.snapshots.len() < window_count && !state.finished).unwrap();
+        state.snapshots.clone()
+    }
+
+    /// returns results of windows that are completed so far
+    pub fn snapshots(&self) -> WindowResults {
+        let (state, _) = &*self.state;
+        state.lock().unwrap().snapshots.clone()
+    }
+
+    /// returns the time to complete each window that is completed so far, keyed by the window evaluation time
+    pub fn latencies(&self) -> BTreeMap<u64, Duration> {
+        let (state, _) = &*self.state;
+        state.lock().unwrap().latencies.clone()
+    }
+
+    /// blocks the run while it is paused and there is no pending step
+    fn await_window(&self) {
+        let (state, condvar) = &*self.state;
+        let mut state = condvar.wait_while(state.lock().unwrap(), |state| state.paused && state.steps == 0).unwrap();
+        if state.paused {
+            state.steps -= 1;
+        }
+    }
+
+    /// publishes results of a completed window and the time it took to complete since the given time of the clock
+    fn publish(&self, window_time: u64, results: BTreeSet<(VertexType, VertexType)>, start: Duration) {
+        let latency = self.clock.now() - start;
+        let (state, condvar) = &*self.state;
+        let mut state = state.lock().unwrap();
+        state.snapshots.insert(window_time, results);
+        state.latencies.insert(window_time, latency);
+        condvar.notify_all();
+    }
+
+    /// marks the run as finished and returns results of all windows
+    fn finish(&self) -> WindowResults {
+        let (state, condvar) = &*self.state;
+        let mut state = state.lock().unwrap();
+        state.finished = true;
+        condvar.notify_all();
+        state.snapshots.clone()
+    }
+}
+
+/// reads all edges of the given file
+pub fn read_edges(input_file: &str, has_timestamp: bool, integer_ids: bool) -> Vec<StreamingGraphEdge> {
+    let reader = LineFileReader::open(input_file, has_timestamp, integer_ids).expect("Cannot open input graph file");
+    reader.collect()
+}
+
+/// returns the times windows are evaluated at for the given edges
+pub fn window_times(edges: &[StreamingGraphEdge], slide_size: u64) -> Vec<u64> {
+    let mut windows = Vec::new();
+
+    if let (Some(first), Some(last)) = (edges.first(), edges.last()) {
+        let mut window_time = first.timestamp + slide_size;
+        while window_time <= last.timestamp {
+            windows.push(window_time);
+            window_time += slide_size;
+        }
+    }
+
+    windows
+}
+
+/// runs the SGA query with the given name over the edges and returns the results valid at each window
+/// only edges whose label is one of the `edge_predicates` are sent to the dataflow
+pub fn run_sga_query(edges: Vec<StreamingGraphEdge>, window_size: u64, slide_size: u64, query_name: &str, edge_predicates: Vec<String>) -> WindowResults {
+    run_sga_query_with_control(edges, window_size, slide_size, query_name, edge_predicates, RunControl::new())
+}
+
+/// runs the SGA query as `run_sga_query`, where windows are completed as allowed by the given control handle
+pub fn run_sga_query_with_control(edges: Vec<StreamingGraphEdge>, window_size: u64, slide_size: u64, query_name: &str, edge_predicates: Vec<String>, control: RunControl) -> WindowResults {
+    let query_name = query_name.to_string();
+    let windows = window_times(&edges, slide_size);
+    let edges = edges.into_iter().filter(|sge| edge_predicates.iter().any(|p| p == sge.get_label())).collect();
+
+    run_sga_dataflow(edges, windows, window_size, control, move |windowed_stream| {
+        SGAQueryLibrary::by_name(&query_name, windowed_stream, edge_predicates.clone())
+            .unwrap_or_else(|| panic!("Supplied query name is not defined: {}", &query_name))
+    })
+}
+
+/// runs the dataflow built by `query` over the windowed stream of the given edges, and returns the results valid at each of the given windows
+pub(crate) fn run_sga_dataflow<F>(edges: Vec<StreamingGraphEdge>, windows: Vec<u64>, window_size: u64, control: RunControl, query: F) -> WindowResults
+    where F: for<'a> Fn(Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> + Send + Sync + 'static {
+    let guards = timely::execute(Configuration::Thread, move |worker| {
+        let mut input: InputHandle<u64, StreamingGraphEdge> = InputHandle::new();
+        let mut probe = ProbeHandle::new();
+
+        let results = Rc::new(RefCell::new(Vec::new()));
+        let result_sink = results.clone();
+
+        worker.dataflow::<u64, _, _>(|scope| {
+            let windowed_stream = scope.input_from(&mut input).sliding_window(window_size);
+
+            query(windowed_stream)
+                .inspect(move |sgt| result_sink.borrow_mut().push((sgt.source, sgt.target, sgt.interval)))
+                .probe_with(&mut probe);
+        });
+
+        // a window is completed once all edges up to the window time are processed
+        // a result belongs to a window if its validity interval contains the window evaluation time,
+        // and results produced by later edges start after the window time
+        let mut complete_window = |input: &mut InputHandle<u64, StreamingGraphEdge>, window_time: u64| {
+            control.await_window();
+            let start = control.clock.now();
+            input.advance_to(window_time + 1);
+            worker.step_while(|| probe.less_than(input.time()));
+
+            let valid_results = results.borrow().iter()
+                .filter(|(_, _, interval)| interval.start <= window_time && window_time < interval.end)
+                .map(|(source, target, _)| (*source, *target))
+                .collect();
+            control.publish(window_time, valid_results, start);
+        };
+
+        let mut pending_windows = windows.iter().peekable();
+
+        // each edge is sent at the epoch of its timestamp
+        for sge in edges.iter().cloned() {
+            // windows before the edge timestamp are completed without the edge
+            while let Some(window_time) = pending_windows.peek() {
+                if **window_time >= sge.timestamp {
+                    break;
+                }
+                complete_window(&mut input, **window_time);
+                pending_windows.next();
+            }
+
+            if sge.timestamp > *input.time() {
+                input.advance_to(sge.timestamp);
+            }
+            input.send(sge);
+        }
+        for window_time in pending_windows {
+            complete_window(&mut input, *window_time);
+        }
+
+        control.finish()
+    }).expect("Cannot execute the SGA dataflow");
+
+    guards.join().into_iter().next().unwrap().expect("SGA dataflow has failed")
+}
+
+/// runs the DD query with the given name over the edges and returns the collection at each window
+/// only edges whose label is one of the `edge_predicates` are sent to the dataflow
+#[cfg(feature = "dd")]
+pub fn run_dd_query(edges: Vec<StreamingGraphEdge>, window_size: u64, slide_size: u64, query_name: &str, edge_predicates: Vec<String>) -> WindowResults {
+    run_dd_query_with_control(edges, window_size, slide_size, query_name, edge_predicates, RunControl::new())
+}
+
+/// runs the DD query as `run_dd_query`, where windows are completed as allowed by the given control handle
+#[cfg(feature = "dd")]
+pub fn run_dd_query_with_control(edges: Vec<StreamingGraphEdge>, window_size: u64, slide_size: u64, query_name: &str, edge_predicates: Vec<String>, control: RunControl) -> WindowResults {
+    run_dd(edges, window_size, slide_size, query_name, edge_predicates, DistinctGranularity::default(), control)
+}
+
+/// runs the DD query as `run_dd_query`, where joins of the query are deduplicated with the given granularity
+#[cfg(feature = "dd")]
+pub fn run_dd_query_with_distinct(edges: Vec<StreamingGraphEdge>, window_size: u64, slide_size: u64, query_name: &str, edge_predicates: Vec<String>, distinct: DistinctGranularity) -> WindowResults {
+    run_dd(edges, window_size, slide_size, query_name, edge_predicates, distinct, RunControl::new())
+}
+
+#[cfg(feature = "dd")]
+fn run_dd(edges: Vec<StreamingGraphEdge>, window_size: u64, slide_size: u64, query_name: &str, edge_predicates: Vec<String>, distinct: DistinctGranularity, control: RunControl) -> WindowResults {
+    let query_name = query_name.to_string();
+    let windows = window_times(&edges, slide_size);
+
+    let guards = timely::execute(Configuration::Thread, move |worker| {
+        let mut input = WindowedInput::sliding(window_size);
+        let mut probe = ProbeHandle::new();
+
+        let results = Rc::new(RefCell::new(Vec::new()));
+        let result_sink = results.clone();
+
+        let query_arguments = edge_predicates.clone();
+        worker.dataflow::<u64, _, _>(|scope| {
+            let input_stream = input.to_collection(scope);
+            let mut shared = SharedArrangements::new(&input_stream, &query_arguments).with_distinct(distinct);
+
+            DDQueryLibrary::by_name_shared(&query_name, &mut shared, query_arguments)
+                .unwrap_or_else(|| panic!("Supplied query name is not defined: {}", &query_name))
+                .inspect(move |(sgt, time, diff)| result_sink.borrow_mut().push(((sgt.source, sgt.target), *time, *diff)))
+                .probe_with(&mut probe);
+        });
+
+        // a window is completed once the collection at the window time is computed
+        // a result belongs to a window if its accumulated multiplicity at the window evaluation time is positive
+        let mut complete_window = |input: &mut WindowedInput, window_time: u64| {
+            control.await_window();
+            let start = control.clock.now();
+            input.advance_to(window_time);
+            input.advance_to(window_time + 1);
+            input.flush();
+            worker.step_while(|| probe.less_than(input.time()));
+
+            let mut counts = BTreeMap::new();
+            results.borrow().iter()
+                .filter(|(_, time, _)| *time <= window_time)
+                .for_each(|(pair, _, diff)| *counts.entry(*pair).or_insert(0) += *diff);
+            let valid_results = counts.into_iter().filter(|(_, count)| *count > 0).map(|(pair, _)| pair).collect();
+            control.publish(window_time, valid_results, start);
+        };
+
+        let mut pending_windows = windows.iter().peekable();
+
+        for sge in edges.iter().filter(|sge| edge_predicates.iter().any(|p| p == sge.get_label())).cloned() {
+            // windows before the edge timestamp are completed without the edge
+            while let Some(window_time) = pending_windows.peek() {
+                if **window_time >= sge.timestamp {
+                    break;
+                }
+                complete_window(&mut input, **window_time);
+                pending_windows.next();
+            }
+
+            input.advance_to(sge.timestamp);
+            input.insert(sge);
+        }
+        for window_time in pending_windows {
+            complete_window(&mut input, *window_time);
+        }
+
+        control.finish()
+    }).expect("Cannot execute the DD dataflow");
+
+    guards.join().into_iter().next().unwrap().expect("DD dataflow has failed")
+}
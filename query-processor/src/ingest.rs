@@ -0,0 +1,403 @@
+//! Replay of an input source into a dataflow, shared by runners
+//!
+//! `ReplayDriver` consumes a source edge by edge until the end of the stream or the end of the run:
+//! it rewrites labels, restricts the input to a subgraph, drops duplicates and edges that are not used by the query
+//! or are owned by other workers, and triggers a slide whenever the event time advances by the slide size.
+//! Runners plug in their dataflow through `ReplayHandler`, which sends edges and completes slides.
+//!
+//! Operators such as RPQ stash their input until the frontier passes its time, so stashes grow with the # of edges of a slide
+//! if the input is read faster than the dataflow progresses. With backpressure, the driver throttles the input once too many edges
+//! are sent since the dataflow last caught up, and lets the dataflow catch up to the timestamp of the current edge before it sends more.
+//!
+//! Slides are processed one at a time, so a spike that slows down processing delays every following slide as well.
+//! With slide coalescing, each slide has a processing budget, and the time a slide exceeds its budget by is recovered by coalescing
+//! the following slides into one, so that expiry and processing of several slides take a single step.
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{info, trace};
+
+use crate::input::{GraphEdge, InputPartitioner, Punctuate, SGE, StreamEvent, StreamingGraphEdge};
+use crate::input::dedup::EdgeDeduplicator;
+use crate::input::labels::LabelMapping;
+use crate::input::vertex_filter::VertexFilter;
+use crate::input::vertex_types::VertexTypes;
+use crate::util::clock::{Clock, system_clock, VirtualClock};
+use crate::util::hot_swap;
+use crate::util::phase::{PhaseSchedule, RunPhase};
+
+/// A slide triggered by the driver
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slide {
+    /// event time the dataflow is advanced to
+    pub time: u64,
+    /// phase the slide has started in
+    pub phase: RunPhase,
+    /// # of edges sent to the dataflow by this worker since the previous slide
+    pub batch_size: u64,
+    /// # of edges read from the source since the previous slide
+    pub total_size: u64,
+    /// # of duplicates dropped since the previous slide, if de-duplication is enabled
+    pub duplicates_dropped: Option<u64>,
+    /// backpressure applied since the previous slide, if backpressure is enabled
+    pub backpressure: Option<Backpressure>,
+    /// # of slides coalesced into this slide besides itself, if slide coalescing is enabled
+    pub coalesced: Option<u64>,
+    /// virtual time of the arrival of the slide in simulation mode, where handlers measure the virtual time until it is processed
+    /// with the clock of the driver, see `ReplayDriver::clock`
+    pub virtual_arrival: Option<Duration>,
+}
+
+impl Slide {
+    /// returns true if the window of any RPQ operator at the time of the slide is warming after a hot swap,
+    /// i.e., it may miss results, which is known once the slide is processed, see `util::hot_swap`
+    pub fn is_warming(&self) -> bool {
+        hot_swap::is_any_warming(self.time)
+    }
+}
+
+/// Environment variable for the max # of edges a worker sends before the dataflow catches up, backpressure is disabled if it is not set
+pub const BACKPRESSURE_VARIABLE: &str = "SGRAFFITO_BACKPRESSURE";
+
+/// Environment variable for the processing budget of a slide in milliseconds, slide coalescing is disabled if it is not set
+pub const SLIDE_COALESCING_VARIABLE: &str = "SGRAFFITO_SLIDE_COALESCING";
+
+/// Environment variable for simulation mode, i.e., `<virtual time of an event time unit in ms>[,<delay per slide in ms>[,<delay per edge in us>]]`
+pub const SIMULATION_VARIABLE: &str = "SGRAFFITO_SIMULATION";
+
+/// Virtual time of a replay in simulation mode, which replays the source as fast as processing allows,
+/// whereas edges arrive at the virtual time of their event time and slides take their measured processing time
+/// plus a synthetic processing delay of virtual time
+/// A slide that arrives while the previous one is processed waits for it, so that its virtual latency, i.e., the time from its arrival
+/// until it is processed, shows whether processing with the given delays keeps up with the source, e.g., for capacity studies
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Simulation {
+    /// virtual time of a unit of event time, i.e., an edge arrives `(timestamp - start) * time_unit` after the start of the replay
+    pub time_unit: Duration,
+    /// processing delay of each processed slide
+    pub slide_delay: Duration,
+    /// processing delay of each edge that a processed slide sends
+    pub edge_delay: Duration,
+}
+
+impl Simulation {
+    /// simulation without processing delays, i.e., slides are processed at their arrival
+    pub fn new(time_unit: Duration) -> Self {
+        Self { time_unit, slide_delay: Duration::default(), edge_delay: Duration::default() }
+    }
+
+    pub fn with_delays(mut self, slide_delay: Duration, edge_delay: Duration) -> Self {
+        self.slide_delay = slide_delay;
+        self.edge_delay = edge_delay;
+        self
+    }
+
+    /// parses `<time unit in ms>[,<slide delay in ms>[,<edge delay in us>]]`
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        let values = spec.split(',')
+            .map(|value| value.trim().parse::<u64>().map_err(|_| format!("Invalid simulation {}, expected `<time unit in ms>[,<slide delay in ms>[,<edge delay in us>]]`", spec)))
+            .collect::<Result<Vec<u64>, String>>()?;
+        match values[..] {
+            [time_unit] => Ok(Self::new(Duration::from_millis(time_unit))),
+            [time_unit, slide_delay] => Ok(Self::new(Duration::from_millis(time_unit)).with_delays(Duration::from_millis(slide_delay), Duration::default())),
+            [time_unit, slide_delay, edge_delay] => Ok(Self::new(Duration::from_millis(time_unit)).with_delays(Duration::from_millis(slide_delay), Duration::from_micros(edge_delay))),
+            _ => Err(format!("Invalid simulation {}, expected `<time unit in ms>[,<slide delay in ms>[,<edge delay in us>]]`", spec)),
+        }
+    }
+
+    /// virtual time of the given event time since the start of the replay
+    fn arrival(&self, event_time: u64) -> Duration {
+        Duration::from_nanos((self.time_unit.as_nanos() as u64).saturating_mul(event_time))
+    }
+
+    /// processing delay of a slide that sends the given # of edges
+    fn processing_delay(&self, edges: u64) -> Duration {
+        self.slide_delay + Duration::from_nanos((self.edge_delay.as_nanos() as u64).saturating_mul(edges))
+    }
+}
+
+/// Backpressure applied to the input during a slide
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Backpressure {
+    /// # of times the input is throttled
+    pub throttles: u64,
+    /// time spent waiting for the dataflow to catch up
+    pub stalled: Duration,
+}
+
+/// Callbacks of a replay, implemented by runners for their dataflow
+pub trait ReplayHandler {
+    /// observes the event time of each edge read from the source
+    fn observe(&mut self, _event_time: u64) {}
+
+    /// sends an edge that is used by the query and owned by this worker into the dataflow
+    fn send(&mut self, sge: StreamingGraphEdge);
+
+    /// advances the dataflow to the time of the slide and completes it
+    fn slide(&mut self, slide: &Slide);
+
+    /// advances the dataflow to the given time within a slide and waits until it catches up,
+    /// called to throttle the input once too many edges are in flight
+    fn catch_up(&mut self, _time: u64) {}
+}
+
+/// Reason a replay has ended
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ReplayEnd {
+    /// the source is exhausted or a shutdown is requested
+    EndOfStream,
+    /// the cool-down phase of the run is over
+    Finished,
+}
+
+/// Outcome of a replay
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ReplaySummary {
+    pub end: ReplayEnd,
+    /// timestamp of the last edge read from the source
+    pub last_timestamp: u64,
+    /// # of edges sent into the dataflow
+    pub sent: u64,
+}
+
+/// Replays a source into a dataflow through a `ReplayHandler`
+pub struct ReplayDriver<'a> {
+    start_timestamp: u64,
+    slide_size: u64,
+    schedule: PhaseSchedule,
+    edge_predicates: &'a [String],
+    partitioner: InputPartitioner,
+    label_mapping: Option<&'a LabelMapping>,
+    vertex_filter: Option<&'a VertexFilter>,
+    vertex_types: Option<&'a VertexTypes>,
+    deduplicator: Option<EdgeDeduplicator>,
+    max_in_flight: Option<u64>,
+    slide_budget: Option<Duration>,
+    clock: Arc<dyn Clock>,
+    // simulation and its virtual clock, in simulation mode
+    simulation: Option<(Simulation, VirtualClock)>,
+}
+
+impl<'a> ReplayDriver<'a> {
+    /// creates a driver for a source starting at the given timestamp, the first slide is triggered once
+    /// the event time passes `start_timestamp + slide_size`
+    pub fn new(start_timestamp: u64, slide_size: u64, schedule: PhaseSchedule, edge_predicates: &'a [String], partitioner: InputPartitioner) -> Self {
+        Self { start_timestamp, slide_size, schedule, edge_predicates, partitioner, label_mapping: None, vertex_filter: None, vertex_types: None, deduplicator: None, max_in_flight: None, slide_budget: None, clock: system_clock(), simulation: None }
+    }
+
+    /// rewrites labels before they are matched against edge predicates
+    pub fn with_label_mapping(mut self, label_mapping: Option<&'a LabelMapping>) -> Self {
+        self.label_mapping = label_mapping;
+        self
+    }
+
+    /// restricts the input to the subgraph of the filter
+    pub fn with_vertex_filter(mut self, vertex_filter: Option<&'a VertexFilter>) -> Self {
+        self.vertex_filter = vertex_filter;
+        self
+    }
+
+    /// rewrites labels to include the types of their endpoints before labels are mapped
+    pub fn with_vertex_types(mut self, vertex_types: Option<&'a VertexTypes>) -> Self {
+        self.vertex_types = vertex_types;
+        self
+    }
+
+    /// drops duplicates before they reach the window
+    /// Duplicates are dropped before edges are assigned to workers, so workers that read the entire stream drop them consistently,
+    /// whereas a worker of a reader partitioned by byte ranges only drops duplicates within its range, see `InputPartitioner`
+    pub fn with_deduplicator(mut self, deduplicator: Option<EdgeDeduplicator>) -> Self {
+        self.deduplicator = deduplicator;
+        self
+    }
+
+    /// throttles the input once the given # of edges are sent since the dataflow last caught up
+    pub fn with_backpressure(mut self, max_in_flight: Option<u64>) -> Self {
+        self.max_in_flight = max_in_flight;
+        self
+    }
+
+    /// coalesces slides into the next one while processing is behind, i.e., the time that slides exceed the given budget by
+    /// is not recovered yet, where each coalesced slide recovers its budget
+    /// Slides of different phases are not coalesced
+    pub fn with_slide_coalescing(mut self, slide_budget: Option<Duration>) -> Self {
+        self.slide_budget = slide_budget;
+        self
+    }
+
+    /// measures processing time, i.e., the time of slides against their budget and stalls of backpressure, with the given clock
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// replays the source in simulation mode, where the clock of the driver is replaced by the virtual clock of the simulation,
+    /// which measures the processing time of slides with the clock of the driver, i.e., it is set by `with_clock` before
+    pub fn with_simulation(mut self, simulation: Option<Simulation>) -> Self {
+        if let Some(simulation) = simulation {
+            let clock = VirtualClock::new(self.clock.clone());
+            self.clock = Arc::new(clock.clone());
+            self.simulation = Some((simulation, clock));
+        }
+        self
+    }
+
+    /// clock that handlers measure latencies with, i.e., the virtual clock in simulation mode
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    /// replays the source until it is exhausted, a shutdown is requested or the run is finished
+    pub fn run<I: Iterator<Item=StreamingGraphEdge>, H: ReplayHandler>(&mut self, source: I, handler: &mut H) -> ReplaySummary {
+        self.run_events(source.punctuate(), handler)
+    }
+
+    /// replays a punctuated source, e.g., a `WatermarkSource`, where watermarks trigger slides aligned to the slide size
+    /// edges that arrive later than a watermark are sent in the next slide
+    pub fn run_events<I: Iterator<Item=StreamEvent>, H: ReplayHandler>(&mut self, source: I, handler: &mut H) -> ReplaySummary {
+        let timer = self.clock.now();
+
+        let mut total_edge_counter = 0;
+        let mut processed_edge_counter = 0;
+        let mut in_flight = 0;
+        let mut backpressure = Backpressure::default();
+        let mut last_batch_process = self.start_timestamp;
+        // start of the batch of pending slides, and the processing time that is not recovered yet
+        let mut batch_start = self.start_timestamp;
+        let mut behind = Duration::default();
+        let mut coalesced = 0;
+        let mut edge_ts = 0;
+        let mut position = 0;
+        let mut sent = 0;
+
+        for event in source {
+            let (event_time, mut edge) = match event {
+                StreamEvent::Edge(sge) => (sge.get_timestamp(), Some(sge)),
+                StreamEvent::Watermark(watermark) => (watermark, None),
+                StreamEvent::EndOfStream => {
+                    info!("End of stream at {} after {} secs", edge_ts, (self.clock.now() - timer).as_secs());
+                    break;
+                }
+            };
+            handler.observe(event_time);
+            // virtual time follows the arrival of events unless processing is behind
+            if let Some((simulation, clock)) = &self.simulation {
+                clock.advance_to(simulation.arrival(event_time.saturating_sub(self.start_timestamp)));
+            }
+
+            // stop consuming the input once the cool-down phase is over
+            if self.schedule.phase_at(event_time) == RunPhase::Finished {
+                info!("Run is finished at {} after {} secs", event_time, (self.clock.now() - timer).as_secs());
+                return ReplaySummary { end: ReplayEnd::Finished, last_timestamp: edge_ts, sent };
+            }
+            if let Some(sge) = &edge {
+                trace!("Next sgt from input stream {:?}", sge);
+                edge_ts = event_time;
+                total_edge_counter += 1;
+            }
+
+            // out-of-order edges may be behind the last slide
+            if event_time.saturating_sub(last_batch_process) >= self.slide_size {
+                // edges slide to their timestamp, whereas watermarks slide to the last slide boundary they pass
+                let slide_time = match edge {
+                    Some(_) => event_time,
+                    None => last_batch_process + (event_time - last_batch_process) / self.slide_size * self.slide_size,
+                };
+                // the batch belongs to the phase it has started in
+                let phase = self.schedule.phase_at(batch_start);
+                last_batch_process = slide_time;
+
+                // the slide is processed with the next one while processing is behind, which recovers its budget
+                let coalescing_budget = self.slide_budget.filter(|_| behind > Duration::default() && self.schedule.phase_at(slide_time) == phase);
+                if let Some(budget) = coalescing_budget {
+                    trace!("Slide at {} is coalesced", slide_time);
+                    behind = behind.checked_sub(budget).unwrap_or_default();
+                    coalesced += 1;
+                } else {
+                    trace!("Slide at {}", slide_time);
+                    let processing = self.clock.now();
+                    // virtual time advances with the processing of the slide, after its synthetic delay
+                    let virtual_arrival = self.simulation.as_ref().map(|(simulation, clock)| {
+                        clock.advance(simulation.processing_delay(processed_edge_counter));
+                        clock.start_processing();
+                        simulation.arrival(slide_time.saturating_sub(self.start_timestamp))
+                    });
+                    handler.slide(&Slide {
+                        time: slide_time,
+                        phase,
+                        batch_size: processed_edge_counter,
+                        total_size: total_edge_counter,
+                        duplicates_dropped: self.deduplicator.as_mut().map(|deduplicator| deduplicator.take_dropped()),
+                        backpressure: self.max_in_flight.map(|_| backpressure),
+                        coalesced: self.slide_budget.map(|_| coalesced),
+                        virtual_arrival,
+                    });
+                    if let Some((_, clock)) = &self.simulation {
+                        clock.stop_processing();
+                    }
+                    info!("Input advance to: {} in {:?} phase after {} secs", slide_time, phase, (self.clock.now() - timer).as_secs());
+
+                    // coalesced slides share the budget of the slide they are processed with
+                    if let Some(budget) = self.slide_budget {
+                        behind = (self.clock.now() - processing).checked_sub(budget * (coalesced as u32 + 1)).unwrap_or_default();
+                    }
+                    batch_start = slide_time;
+                    coalesced = 0;
+                    processed_edge_counter = 0;
+                    total_edge_counter = 0;
+                    in_flight = 0;
+                    backpressure = Backpressure::default();
+                }
+            }
+
+            let mut sge = match edge.take() {
+                Some(sge) => sge,
+                None => continue,
+            };
+            let index = position;
+            position += 1;
+
+            // labels are rewritten before they are matched against edge predicates
+            if let Some(vertex_types) = self.vertex_types {
+                vertex_types.apply(&mut sge);
+            }
+            let retained = self.label_mapping.map_or(true, |label_mapping| label_mapping.apply(&mut sge))
+                && self.vertex_filter.map_or(true, |vertex_filter| vertex_filter.retains(&sge));
+
+            if self.deduplicator.as_mut().map_or(false, |deduplicator| deduplicator.is_duplicate(&sge)) {
+                continue;
+            }
+
+            if !retained || !self.edge_predicates.iter().any(|p| p == sge.get_label()) {
+                continue;
+            }
+
+            // edges of undirected labels are sent in both directions with the same timestamp, so that both expire together
+            let reverse = if sge.source != sge.target && self.label_mapping.map_or(false, |label_mapping| label_mapping.is_undirected(&sge.label)) {
+                Some(StreamingGraphEdge::new(sge.target, sge.source, sge.label.clone(), sge.timestamp))
+            } else {
+                None
+            };
+
+            // send the edge only if it is assigned to this worker
+            for sge in std::iter::once(sge).chain(reverse).filter(|sge| self.partitioner.owns(index, sge)) {
+                // throttle the input until the dataflow catches up to the current edge
+                if self.max_in_flight.map_or(false, |max_in_flight| in_flight >= max_in_flight) {
+                    trace!("Backpressure at {} with {} edges in flight", edge_ts, in_flight);
+                    let stall = self.clock.now();
+                    handler.catch_up(edge_ts);
+                    backpressure.throttles += 1;
+                    backpressure.stalled += self.clock.now() - stall;
+                    in_flight = 0;
+                }
+
+                handler.send(sge);
+                processed_edge_counter += 1;
+                in_flight += 1;
+                sent += 1;
+            }
+        }
+
+        ReplaySummary { end: ReplayEnd::EndOfStream, last_timestamp: edge_ts, sent }
+    }
+}
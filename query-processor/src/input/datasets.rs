@@ -0,0 +1,222 @@
+//! Adapters for public temporal graph datasets, which map their column layouts and time units to `StreamingGraphEdge`
+//!
+//! Presets are available for
+//! - `sx-stackoverflow`, `sx-stackoverflow-a2q`, `sx-stackoverflow-c2q`, `sx-stackoverflow-c2a`: SNAP Stack Overflow interactions `SRC TGT UNIXTS`,
+//!   labeled by the interaction type of the file, or `interaction` for the combined file
+//! - `wiki-talk`: SNAP wiki-talk-temporal `SRC TGT UNIXTS`, labeled `talk`
+//! - `reddit-hyperlinks`: SNAP soc-redditHyperlinks tab-separated files with a header, subreddit names as vertex identifiers
+//!   and `YYYY-MM-DD HH:MM:SS` timestamps, labeled `hyperlink`
+//!
+//! Timestamps are converted to seconds, and edges are expected in timestamp order as in the original files.
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error};
+
+use log::warn;
+
+use crate::input::{vertex_id, StreamingGraphEdge, SGE, InputStreamKind, VertexDictionary, VertexEncoding};
+use crate::input::labels::canonical_label;
+
+/// Unit of the timestamp column of a dataset
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TimeUnit {
+    Seconds,
+    Milliseconds,
+    /// UTC date and time of the form `YYYY-MM-DD HH:MM:SS`
+    DateTime,
+}
+
+/// Label of the edges of a dataset
+#[derive(Clone, Debug, PartialEq)]
+pub enum LabelSource {
+    /// all edges have the same label
+    Fixed(String),
+    /// label is the value of the given column
+    Column(usize),
+}
+
+/// Column layout of a dataset
+#[derive(Clone, Debug, PartialEq)]
+pub struct DatasetSchema {
+    /// column delimiter, columns are separated by whitespace if it is not set
+    pub delimiter: Option<char>,
+    pub source_column: usize,
+    pub target_column: usize,
+    pub timestamp_column: usize,
+    pub label: LabelSource,
+    pub time_unit: TimeUnit,
+    /// # of header lines that are skipped
+    pub header_lines: usize,
+    /// lines starting with the prefix are skipped
+    pub comment_prefix: Option<String>,
+    pub integer_ids: bool,
+}
+
+impl DatasetSchema {
+    /// returns the schema of the dataset with the given preset name
+    pub fn preset(name: &str) -> Option<Self> {
+        let snap = |label: &str| Self {
+            delimiter: None,
+            source_column: 0,
+            target_column: 1,
+            timestamp_column: 2,
+            label: LabelSource::Fixed(label.to_string()),
+            time_unit: TimeUnit::Seconds,
+            header_lines: 0,
+            comment_prefix: Some("#".to_string()),
+            integer_ids: true,
+        };
+
+        match name {
+            "sx-stackoverflow" => Some(snap("interaction")),
+            "sx-stackoverflow-a2q" => Some(snap("a2q")),
+            "sx-stackoverflow-c2q" => Some(snap("c2q")),
+            "sx-stackoverflow-c2a" => Some(snap("c2a")),
+            "wiki-talk" => Some(snap("talk")),
+            "reddit-hyperlinks" => Some(Self {
+                delimiter: Some('\t'),
+                source_column: 0,
+                target_column: 1,
+                timestamp_column: 3,
+                label: LabelSource::Fixed("hyperlink".to_string()),
+                time_unit: TimeUnit::DateTime,
+                header_lines: 1,
+                comment_prefix: None,
+                integer_ids: false,
+            }),
+            _ => None
+        }
+    }
+
+    /// names of the available presets
+    pub fn preset_names() -> &'static [&'static str] {
+        &["sx-stackoverflow", "sx-stackoverflow-a2q", "sx-stackoverflow-c2q", "sx-stackoverflow-c2a", "wiki-talk", "reddit-hyperlinks"]
+    }
+
+    /// input stream kind of the dataset, datasets are always timestamped
+    pub fn input_kind(&self) -> InputStreamKind {
+        if self.integer_ids { InputStreamKind::IntegerTimestamped } else { InputStreamKind::StringTimestampted }
+    }
+
+    /// parses a line of the dataset into its source, target, label and timestamp in seconds
+    fn parse_line<'a>(&self, line: &'a str) -> Result<(&'a str, &'a str, &'a str, u64), String> {
+        let fields: Vec<&str> = match self.delimiter {
+            Some(delimiter) => line.split(delimiter).map(|field| field.trim()).collect(),
+            None => line.split_whitespace().collect(),
+        };
+        let field = |column: usize| fields.get(column).cloned().ok_or_else(|| format!("Column {} is missing", column));
+
+        let label = match &self.label {
+            LabelSource::Fixed(label) => label.as_str(),
+            LabelSource::Column(column) => field(*column)?,
+        };
+        let timestamp = field(self.timestamp_column)?;
+        let timestamp = match self.time_unit {
+            TimeUnit::Seconds => timestamp.parse().ok(),
+            TimeUnit::Milliseconds => timestamp.parse::<u64>().ok().map(|milliseconds| milliseconds / 1000),
+            TimeUnit::DateTime => parse_datetime(timestamp),
+        }.ok_or_else(|| format!("Invalid timestamp {}", timestamp))?;
+
+        Ok((field(self.source_column)?, field(self.target_column)?, label, timestamp))
+    }
+}
+
+/// seconds since the Unix epoch of a UTC date and time of the form `YYYY-MM-DD HH:MM:SS`
+pub fn parse_datetime(datetime: &str) -> Option<u64> {
+    let mut parts = datetime.split(|c| c == '-' || c == ' ' || c == 'T' || c == ':');
+    let mut next = || parts.next().and_then(|part| part.parse::<u64>().ok());
+    let (year, month, day) = (next()?, next()?, next()?);
+    let (hour, minute, second) = (next()?, next()?, next()?);
+    if year < 1970 || !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    // days since the epoch of the proleptic Gregorian calendar, years start in March so that leap days are last
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let shifted_month = (month + 9) % 12;
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+
+    Some(days * 86_400 + hour * 3_600 + minute * 60 + second)
+}
+
+/// Reader of a dataset file with the given schema
+pub struct DatasetReader {
+    reader: BufReader<File>,
+    schema: DatasetSchema,
+    start_timestamp: u64,
+    // first line, which is read while opening to obtain the start timestamp
+    first_line: Option<String>,
+    // records string identifiers of vertices if it is set
+    dictionary: Option<VertexDictionary>,
+    vertex_encoding: VertexEncoding,
+}
+
+impl DatasetReader {
+    pub fn open(input_file: &str, schema: DatasetSchema) -> Result<Self, Error> {
+        let mut reader = BufReader::new(File::open(input_file)?);
+        for _ in 0..schema.header_lines {
+            reader.read_line(&mut String::new())?;
+        }
+
+        let mut dataset_reader = Self { reader, schema, start_timestamp: 0, first_line: None, dictionary: None, vertex_encoding: VertexEncoding::Hash };
+        if let Some((line, timestamp)) = dataset_reader.read_valid_line() {
+            dataset_reader.first_line = Some(line);
+            dataset_reader.start_timestamp = timestamp;
+        }
+
+        Ok(dataset_reader)
+    }
+
+    /// encodes string identifiers of vertices with the given encoding, where they are recorded into the given dictionary
+    pub fn with_vertex_encoding(mut self, vertex_encoding: VertexEncoding, dictionary: VertexDictionary) -> Self {
+        if !self.schema.integer_ids {
+            self.vertex_encoding = vertex_encoding;
+            self.dictionary = Some(dictionary);
+        }
+        self
+    }
+
+    pub fn get_start_timestamp(&self) -> u64 {
+        self.start_timestamp
+    }
+
+    /// reads the next line that can be parsed and its timestamp, other lines are skipped
+    fn read_valid_line(&mut self) -> Option<(String, u64)> {
+        loop {
+            let mut line = String::new();
+            let len = self.reader.read_line(&mut line).expect("Error reading the next line from input stream");
+            if len == 0 {
+                return None;
+            }
+
+            let line = line.trim_end_matches(|c| c == '\n' || c == '\r').to_string();
+            if line.trim().is_empty() || self.schema.comment_prefix.as_ref().map_or(false, |prefix| line.starts_with(prefix.as_str())) {
+                continue;
+            }
+
+            match self.schema.parse_line(&line) {
+                Ok((_, _, _, timestamp)) => return Some((line, timestamp)),
+                Err(e) => warn!("Skipping line {}: {}", line, e),
+            }
+        }
+    }
+}
+
+impl Iterator for DatasetReader {
+    type Item = StreamingGraphEdge;
+
+    fn next(&mut self) -> Option<StreamingGraphEdge> {
+        let line = match self.first_line.take() {
+            Some(line) => line,
+            None => self.read_valid_line()?.0,
+        };
+
+        let (source, target, label, timestamp) = self.schema.parse_line(&line).expect("Line is validated while reading");
+        let source = vertex_id(source, self.schema.integer_ids, self.vertex_encoding, self.dictionary.as_ref());
+        let target = vertex_id(target, self.schema.integer_ids, self.vertex_encoding, self.dictionary.as_ref());
+        Some(StreamingGraphEdge::new(source, target, canonical_label(label, false), timestamp))
+    }
+}
@@ -0,0 +1,113 @@
+//! De-duplication of input edges before they are sent to the dataflow
+//!
+//! Edges are identified by the hash of their (source, label, target) triple, plus their timestamp in exact mode,
+//! so the set of recently seen edges stores a single `u64` per edge. Hash collisions may drop distinct edges,
+//! which is negligible with 64-bit hashes for realistic window contents.
+//! Each worker drops the duplicates of the edges it reads, i.e., of the entire stream unless the reader is partitioned by byte ranges,
+//! where a duplicate in the byte range of another worker is not dropped.
+use std::collections::VecDeque;
+use std::hash::{BuildHasherDefault, Hash, Hasher};
+
+use hashbrown::HashMap;
+use hashers::fx_hash::FxHasher;
+
+use crate::input::StreamingGraphEdge;
+
+/// environment variable that enables de-duplication in runners, either `exact` or a time horizon, e.g., `10`
+pub const DEDUP_VARIABLE: &str = "SGRAFFITO_DEDUP";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DedupMode {
+    /// drops edges that are identical to an earlier edge including their timestamp, i.e., duplicate lines
+    Exact,
+    /// drops edges whose (source, label, target) is seen within the given time horizon of an edge that is not dropped
+    Horizon(u64),
+}
+
+impl DedupMode {
+    /// parses de-duplication modes used by runners, i.e., `exact` or a time horizon
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "exact" => Some(DedupMode::Exact),
+            _ => name.parse().ok().map(DedupMode::Horizon),
+        }
+    }
+}
+
+/// Compact hash set of recently seen edges with timestamp-based expiry
+/// It assumes that edges arrive in timestamp order, duplicates of out-of-order edges may not be detected
+pub struct EdgeDeduplicator {
+    mode: DedupMode,
+    // hash of each seen edge and its timestamp
+    seen: HashMap<u64, u64, BuildHasherDefault<FxHasher>>,
+    // seen edges in the order of their timestamps for expiry
+    expiry_queue: VecDeque<(u64, u64)>,
+    // # of dropped duplicates since the last `take_dropped`
+    dropped: u64,
+}
+
+impl EdgeDeduplicator {
+    pub fn new(mode: DedupMode) -> Self {
+        Self {
+            mode,
+            seen: HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default()),
+            expiry_queue: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    /// returns true if the edge is a duplicate of a recently seen edge and has to be dropped
+    pub fn is_duplicate(&mut self, edge: &StreamingGraphEdge) -> bool {
+        // an exact duplicate has the same timestamp, so entries expire as soon as the timestamp advances
+        let horizon = match self.mode {
+            DedupMode::Exact => 1,
+            DedupMode::Horizon(horizon) => horizon,
+        };
+
+        // expire entries outside of the horizon, unless they are refreshed by a later edge
+        while let Some((timestamp, hash)) = self.expiry_queue.front().cloned() {
+            if timestamp + horizon > edge.timestamp {
+                break;
+            }
+            self.expiry_queue.pop_front();
+            if self.seen.get(&hash) == Some(&timestamp) {
+                self.seen.remove(&hash);
+            }
+        }
+
+        let hash = self.edge_hash(edge);
+        if self.seen.contains_key(&hash) {
+            self.dropped += 1;
+            return true;
+        }
+
+        self.seen.insert(hash, edge.timestamp);
+        self.expiry_queue.push_back((edge.timestamp, hash));
+        false
+    }
+
+    /// returns the # of dropped duplicates since the last call and resets it
+    pub fn take_dropped(&mut self) -> u64 {
+        std::mem::replace(&mut self.dropped, 0)
+    }
+
+    /// returns the # of edges that are currently tracked
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    fn edge_hash(&self, edge: &StreamingGraphEdge) -> u64 {
+        let mut hasher = FxHasher::default();
+        edge.source.hash(&mut hasher);
+        edge.label.hash(&mut hasher);
+        edge.target.hash(&mut hasher);
+        if self.mode == DedupMode::Exact {
+            edge.timestamp.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+}
@@ -0,0 +1,196 @@
+//! Rewriting of edge labels before they are sent to the dataflow
+//!
+//! A label mapping is read from a TOML file of the form
+//! ```toml
+//! # drop edges whose label is not used by the query after rewriting
+//! drop_unused = true
+//! # edges with these labels are ingested in both directions
+//! undirected = ["knows"]
+//!
+//! [aliases]
+//! likes_post = "likes"
+//! likes_comment = "likes"
+//! ```
+//! where labels without an alias are kept as they are, and `undirected` refers to labels after rewriting.
+//! Input readers canonicalize labels by `canonical_label`, so that IRIs match predicates of RPQs without a mapping.
+//! Labels are canonicalized again before they are rewritten, with `case_insensitive = true` they are also lowercased,
+//! so that aliases have to be given in their canonical form. Runners canonicalize edge predicates of queries with the same mapping.
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use serde::Deserialize;
+
+use crate::input::StreamingGraphEdge;
+
+/// environment variable that sets the label mapping file in runners
+pub const LABEL_MAP_VARIABLE: &str = "SGRAFFITO_LABEL_MAP";
+/// environment variable that enables case-insensitive labels in runners, i.e., labels of edges and queries are lowercased
+pub const CASE_INSENSITIVE_LABELS_VARIABLE: &str = "SGRAFFITO_CASE_INSENSITIVE_LABELS";
+/// environment variable that sets a comma separated list of undirected labels in runners
+pub const UNDIRECTED_LABELS_VARIABLE: &str = "SGRAFFITO_UNDIRECTED_LABELS";
+
+/// Fields of a line of an input file, which are separated by whitespace
+/// A field with whitespace is enclosed in double quotes, e.g., `1 "works at" 2 10`, where `\"` and `\\` escape
+/// quotes and backslashes inside quotes. Returns an error if a quote is not terminated
+pub fn split_fields(line: &str) -> Result<Vec<String>, String> {
+    if !line.contains('"') {
+        return Ok(line.split_whitespace().map(|field| field.to_string()).collect());
+    }
+
+    let mut fields = Vec::new();
+    let mut chars = line.chars().peekable();
+    loop {
+        while chars.peek().map_or(false, |c| c.is_whitespace()) {
+            chars.next();
+        }
+        let mut field = String::new();
+        match chars.peek() {
+            None => return Ok(fields),
+            Some('"') => {
+                chars.next();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => field.push(chars.next().ok_or_else(|| format!("Escape is not terminated in {}", line.trim_end()))?),
+                        Some(c) => field.push(c),
+                        None => return Err(format!("Quote is not terminated in {}", line.trim_end())),
+                    }
+                }
+                if chars.peek().map_or(false, |c| !c.is_whitespace()) {
+                    return Err(format!("Quoted field is not followed by whitespace in {}", line.trim_end()));
+                }
+            }
+            Some(_) => {
+                while let Some(c) = chars.peek().filter(|c| !c.is_whitespace()) {
+                    field.push(*c);
+                    chars.next();
+                }
+            }
+        }
+        fields.push(field);
+    }
+}
+
+/// label of a single quoted field, e.g., a quoted label of an RPQ
+pub fn unquote_label(quoted: &str) -> Result<String, String> {
+    let mut fields = split_fields(quoted)?;
+    if fields.len() != 1 {
+        return Err(format!("{} is not a single label", quoted));
+    }
+    Ok(fields.remove(0))
+}
+
+/// quotes and escapes the label unless it is a plain predicate of RPQs, e.g., if it contains whitespace or quotes or is empty,
+/// so that `split_fields` and RPQs read it back as it is
+pub fn quote_label(label: &str) -> String {
+    if label.is_empty() || !label.chars().all(|c| c.is_ascii_alphanumeric() || ".:_-#".contains(c)) {
+        format!("\"{}\"", label.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        label.to_string()
+    }
+}
+
+/// canonical form of a label that is used for stream partitioning and automaton labels
+/// Surrounding whitespace and angle brackets of IRIs are removed, e.g., `<http://xmlns.com/foaf/0.1/knows>`,
+/// and labels are lowercased if `case_insensitive` is set
+pub fn canonical_label(label: &str, case_insensitive: bool) -> String {
+    let label = label.trim();
+    let label = if label.len() > 2 && label.starts_with('<') && label.ends_with('>') {
+        &label[1..label.len() - 1]
+    } else {
+        label
+    };
+
+    if case_insensitive {
+        label.to_lowercase()
+    } else {
+        label.to_string()
+    }
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct LabelMapping {
+    #[serde(default)]
+    aliases: HashMap<String, String>,
+    #[serde(default)]
+    drop_unused: bool,
+    #[serde(default)]
+    case_insensitive: bool,
+    #[serde(default)]
+    undirected: HashSet<String>,
+    // labels used by the query, provided by the planner
+    #[serde(skip)]
+    alphabet: Option<HashSet<String>>,
+}
+
+impl LabelMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("Cannot read label mapping file {}: {}", path, e))?;
+        let mapping: Self = toml::from_str(&content).map_err(|e| format!("Cannot parse label mapping file {}: {}", path, e))?;
+        Ok(mapping.canonicalize_entries())
+    }
+
+    /// rewrites `label` into `alias`
+    pub fn alias(mut self, label: &str, alias: &str) -> Self {
+        self.aliases.insert(self.canonicalize(label), self.canonicalize(alias));
+        self
+    }
+
+    /// drops edges whose rewritten label is not in the alphabet of the query
+    pub fn drop_unused(mut self, drop_unused: bool) -> Self {
+        self.drop_unused = drop_unused;
+        self
+    }
+
+    /// interprets edges with the given rewritten label as undirected edges
+    pub fn undirected(mut self, label: &str) -> Self {
+        self.undirected.insert(self.canonicalize(label));
+        self
+    }
+
+    /// returns true if edges with the given rewritten label are undirected
+    pub fn is_undirected(&self, label: &str) -> bool {
+        self.undirected.contains(label)
+    }
+
+    /// lowercases labels before they are rewritten
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self.canonicalize_entries()
+    }
+
+    /// returns the canonical form of the given label, e.g., to canonicalize edge predicates of queries consistently with edges
+    pub fn canonicalize(&self, label: &str) -> String {
+        canonical_label(label, self.case_insensitive)
+    }
+
+    /// sets the labels used by the query, see `SGAQueryLibrary::alphabet`
+    pub fn with_alphabet(mut self, alphabet: HashSet<String>) -> Self {
+        self.alphabet = Some(alphabet.iter().map(|label| self.canonicalize(label)).collect());
+        self
+    }
+
+    /// canonicalizes the labels of aliases, undirected labels and the alphabet as the labels of edges that they are matched against,
+    /// e.g., IRIs in angle brackets of mapping files or uppercase labels of case-insensitive mappings
+    fn canonicalize_entries(mut self) -> Self {
+        self.aliases = self.aliases.iter().map(|(label, alias)| (self.canonicalize(label), self.canonicalize(alias))).collect();
+        self.undirected = self.undirected.iter().map(|label| self.canonicalize(label)).collect();
+        self.alphabet = self.alphabet.as_ref().map(|alphabet| alphabet.iter().map(|label| self.canonicalize(label)).collect());
+        self
+    }
+
+    /// rewrites the label of the edge in place, and returns false if the edge has to be dropped,
+    /// i.e., if unused labels are dropped and its rewritten label is not in the alphabet, where no label is dropped until the alphabet is set
+    pub fn apply(&self, edge: &mut StreamingGraphEdge) -> bool {
+        edge.label = self.canonicalize(&edge.label);
+        if let Some(alias) = self.aliases.get(&edge.label) {
+            edge.label = alias.clone();
+        }
+
+        !self.drop_unused || self.alphabet.as_ref().map_or(true, |alphabet| alphabet.contains(&edge.label))
+    }
+}
@@ -1,24 +1,36 @@
 use std::collections::hash_map::DefaultHasher;
 use std::fs::File;
 use std::hash::{Hash, Hasher};
-use std::io::{BufReader, prelude::*};
+use std::io::{BufReader, BufWriter, Error, ErrorKind, SeekFrom, prelude::*};
 use std::iter::Iterator;
 use std::marker::Sized;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock};
 
-use log::trace;
+use log::{trace, warn};
 
+use crate::input::labels::{canonical_label, split_fields};
+use crate::util::shutdown::is_shutdown_requested;
 use crate::util::types::{HalfOpenInterval, VertexType};
 
+pub mod datasets;
+pub mod dedup;
+pub mod labels;
+pub mod multi_stream;
 pub mod tuple;
+pub mod vertex_filter;
+pub mod vertex_types;
+pub mod watermark;
 
 // helper function to calculate hash values
-fn calculate_hash<T: Hash + ?Sized>(t: &T) -> u64 {
+pub(crate) fn calculate_hash<T: Hash + ?Sized>(t: &T) -> u64 {
     let mut s = DefaultHasher::new();
     t.hash(&mut s);
     s.finish()
 }
 
 // enum to define various input formats
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum InputStreamKind {
     String, // vertices have string identifiers and edges do not carry sourcetimestamp
     StringTimestampted, // vertices have string identifiers and edges are timestamped by the source
@@ -26,6 +38,108 @@ pub enum InputStreamKind {
     IntegerTimestamped, // vertices have integer identifiers and edges are timestamped by the source
 }
 
+impl InputStreamKind {
+    /// parses the input type names used by runners, i.e., `{s, st, i, it}`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "s" => Some(InputStreamKind::String),
+            "st" => Some(InputStreamKind::StringTimestampted),
+            "i" => Some(InputStreamKind::Integer),
+            "it" => Some(InputStreamKind::IntegerTimestamped),
+            _ => None
+        }
+    }
+
+    /// returns true if edges are timestamped by the source
+    pub fn is_timestamped(&self) -> bool {
+        *self == InputStreamKind::StringTimestampted || *self == InputStreamKind::IntegerTimestamped
+    }
+
+    /// returns true if vertices have integer identifiers
+    pub fn has_integer_ids(&self) -> bool {
+        *self == InputStreamKind::Integer || *self == InputStreamKind::IntegerTimestamped
+    }
+}
+
+/// environment variable that sets the encoding of string vertex identifiers in runners, i.e., `{hash, checked, dense}`
+pub const VERTEX_ENCODING_VARIABLE: &str = "SGRAFFITO_VERTEX_ENCODING";
+
+/// environment variable for the file that runners reload the `VertexDictionary` from at startup, if it exists, and persist it to at shutdown,
+/// so that string identifiers keep their vertex across runs and results of different runs can be joined
+pub const DICTIONARY_FILE_VARIABLE: &str = "SGRAFFITO_DICTIONARY_FILE";
+
+/// Encoding of string vertex identifiers into `VertexType`
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VertexEncoding {
+    Hash, // 64-bit hash of the identifier, distinct identifiers may silently collide
+    CheckedHash, // 64-bit hash of the identifier, collisions are detected through a `VertexDictionary`, logged and counted
+    Dense, // sequential identifiers assigned by a `VertexDictionary`, collision-free but only consistent among workers of a process
+}
+
+impl VertexEncoding {
+    /// parses vertex encoding names used by runners, i.e., `{hash, checked, dense}`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "hash" => Some(VertexEncoding::Hash),
+            "checked" => Some(VertexEncoding::CheckedHash),
+            "dense" => Some(VertexEncoding::Dense),
+            _ => None
+        }
+    }
+
+    /// returns true if the encoding requires a `VertexDictionary`
+    pub fn requires_dictionary(&self) -> bool {
+        *self != VertexEncoding::Hash
+    }
+}
+
+/// Partitioning of an input stream among workers
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Partitioning {
+    RoundRobin, // edges are assigned to workers round-robin by their position in the stream
+    SourceHash, // edges are assigned to workers by their source vertex, following the routing of `Exchange` pacts
+    ByteRange, // each worker reads a disjoint byte range of the file aligned to line boundaries, see `LineFileReader::open_partition`
+}
+
+impl Partitioning {
+    /// parses partitioning names used by runners, i.e., `{line, hash}`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "line" => Some(Partitioning::RoundRobin),
+            "hash" => Some(Partitioning::SourceHash),
+            "range" => Some(Partitioning::ByteRange),
+            _ => None
+        }
+    }
+}
+
+/// Assigns each edge of an input stream to exactly one worker, so that each worker sends a disjoint subset
+/// Every worker reads the entire stream and advances its input with all edges, but only sends the edges it owns
+/// except for `ByteRange`, where the reader itself is partitioned and a worker owns every edge it reads
+#[derive(Copy, Clone, Debug)]
+pub struct InputPartitioner {
+    partitioning: Partitioning,
+    index: usize,
+    peers: usize,
+}
+
+impl InputPartitioner {
+    pub fn new(partitioning: Partitioning, index: usize, peers: usize) -> Self {
+        Self { partitioning, index, peers }
+    }
+
+    /// returns true if the edge at the given position of the stream is assigned to this worker
+    pub fn owns<E: GraphEdge>(&self, position: usize, edge: &E) -> bool {
+        match self.partitioning {
+            Partitioning::RoundRobin => position % self.peers == self.index,
+            // vertex identifiers are already hashed for string inputs, and `Exchange` pacts keyed by vertices route by modulo,
+            // so edges arrive at the worker that owns their source in operators partitioned by the source vertex
+            Partitioning::SourceHash => (edge.get_source() % self.peers as u64) as usize == self.index,
+            Partitioning::ByteRange => true,
+        }
+    }
+}
+
 /// Trait for Static graph edges
 pub trait GraphEdge {
     fn get_source(&self) -> VertexType;
@@ -70,6 +184,13 @@ impl GraphEdge for StreamingGraphEdge {
     }
 }
 
+impl StreamingGraphEdge {
+    /// identifier of the edge for provenance, i.e., a hash of its endpoints, label and timestamp
+    pub fn edge_id(&self) -> u64 {
+        calculate_hash(&(self.source, &self.label, self.target, self.timestamp))
+    }
+}
+
 impl SGE for StreamingGraphEdge {
     fn new(s: u64, t: u64, l: String, ts: u64) -> Self {
         Self { source: s, target: t, label: l, timestamp: ts, append: true }
@@ -79,6 +200,206 @@ impl SGE for StreamingGraphEdge {
     }
 }
 
+/// Events emitted by punctuated sources, `EndOfStream` is emitted exactly once after the last edge
+#[derive(Clone, Debug, PartialEq)]
+pub enum StreamEvent {
+    Edge(StreamingGraphEdge),
+    /// event time has advanced to the given time without an edge, see `watermark::WatermarkSource`
+    Watermark(u64),
+    EndOfStream,
+}
+
+/// Source wrapper that punctuates the stream with an explicit `EndOfStream` marker
+/// the marker is emitted once the underlying source is exhausted or a graceful shutdown is requested
+pub struct PunctuatedSource<I> {
+    source: I,
+    finished: bool,
+}
+
+impl<I: Iterator<Item=StreamingGraphEdge>> Iterator for PunctuatedSource<I> {
+    type Item = StreamEvent;
+
+    fn next(&mut self) -> Option<StreamEvent> {
+        if self.finished {
+            return None;
+        }
+
+        match self.source.next() {
+            Some(sge) if !is_shutdown_requested() => Some(StreamEvent::Edge(sge)),
+            _ => {
+                self.finished = true;
+                Some(StreamEvent::EndOfStream)
+            }
+        }
+    }
+}
+
+/// Extension trait to punctuate sources with the end-of-stream marker
+pub trait Punctuate: Iterator<Item=StreamingGraphEdge> + Sized {
+    fn punctuate(self) -> PunctuatedSource<Self> {
+        PunctuatedSource { source: self, finished: false }
+    }
+}
+
+impl<I: Iterator<Item=StreamingGraphEdge>> Punctuate for I {}
+
+/// Maps hashed vertex identifiers back to the string identifiers of the input, shared among readers and sinks
+/// It detects hash collisions, i.e., distinct string identifiers that are mapped to the same vertex,
+/// and assigns dense identifiers for `VertexEncoding::Dense`
+#[derive(Clone, Debug, Default)]
+pub struct VertexDictionary {
+    names: Arc<RwLock<HashMap<VertexType, String>>>,
+    // dense identifiers of string identifiers
+    ids: Arc<RwLock<HashMap<String, VertexType>>>,
+    // string identifiers that collide with the identifier of another vertex
+    collisions: Arc<Mutex<HashSet<String>>>,
+}
+
+impl VertexDictionary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// records the string identifier of the vertex, a collision is logged and counted if the vertex has another identifier
+    pub fn insert(&self, vertex: VertexType, name: &str) {
+        if let Some(existing_name) = self.names.read().unwrap().get(&vertex) {
+            if existing_name != name && self.collisions.lock().unwrap().insert(name.to_string()) {
+                warn!("Vertex identifiers {} and {} collide on {}", existing_name, name, vertex);
+            }
+            return;
+        }
+        self.names.write().unwrap().entry(vertex).or_insert_with(|| name.to_string());
+    }
+
+    /// returns the dense identifier of the string identifier, which is assigned if it is not seen before
+    pub fn get_or_assign(&self, name: &str) -> VertexType {
+        if let Some(vertex) = self.ids.read().unwrap().get(name) {
+            return *vertex;
+        }
+
+        let mut ids = self.ids.write().unwrap();
+        let next_id = ids.len() as VertexType;
+        let vertex = *ids.entry(name.to_string()).or_insert(next_id);
+        self.names.write().unwrap().entry(vertex).or_insert_with(|| name.to_string());
+        vertex
+    }
+
+    /// returns the # of string identifiers that collide with another one
+    pub fn collisions(&self) -> u64 {
+        self.collisions.lock().unwrap().len() as u64
+    }
+
+    /// returns the string identifier of the vertex, or the identifier itself if it is not in the dictionary
+    pub fn resolve(&self, vertex: VertexType) -> String {
+        self.names.read().unwrap().get(&vertex).cloned().unwrap_or_else(|| vertex.to_string())
+    }
+
+    /// writes the dictionary into the file as one `<dense|hashed> <vertex> <identifier>` line for each vertex, sorted by vertex,
+    /// where dense vertices are the ones assigned by `get_or_assign`. Collisions are not persisted
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let names = self.names.read().unwrap();
+        let ids = self.ids.read().unwrap();
+        let mut vertices: Vec<(&VertexType, &String)> = names.iter().collect();
+        vertices.sort();
+
+        let file = File::create(path).map_err(|e| format!("Cannot create dictionary file {}: {}", path, e))?;
+        let mut writer = BufWriter::new(file);
+        for (vertex, name) in vertices {
+            let kind = if ids.get(name) == Some(vertex) { "dense" } else { "hashed" };
+            writeln!(writer, "{} {} {}", kind, vertex, name).map_err(|e| format!("Cannot write dictionary file {}: {}", path, e))?;
+        }
+        writer.flush().map_err(|e| format!("Cannot write dictionary file {}: {}", path, e))
+    }
+
+    /// reads a dictionary that is written by `save`, where dense vertices that are assigned afterwards follow the loaded ones
+    pub fn load(path: &str) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Cannot open dictionary file {}: {}", path, e))?;
+        let dictionary = Self::new();
+        {
+            let mut names = dictionary.names.write().unwrap();
+            let mut ids = dictionary.ids.write().unwrap();
+            for (line_number, line) in BufReader::new(file).lines().enumerate() {
+                let line = line.map_err(|e| format!("Cannot read dictionary file {}: {}", path, e))?;
+                let fields: Vec<&str> = line.splitn(3, ' ').collect();
+                let vertex = match fields.get(1).map(|vertex| vertex.parse::<VertexType>()) {
+                    Some(Ok(vertex)) if fields.len() == 3 => vertex,
+                    _ => return Err(format!("Invalid dictionary entry at line {} of {}: {}", line_number + 1, path, line)),
+                };
+                match fields[0] {
+                    "dense" => { ids.insert(fields[2].to_string(), vertex); }
+                    "hashed" => {}
+                    kind => return Err(format!("Invalid dictionary entry kind {} at line {} of {}", kind, line_number + 1, path)),
+                }
+                names.insert(vertex, fields[2].to_string());
+            }
+        }
+        Ok(dictionary)
+    }
+
+    /// reloads the dictionary from the file if it exists, or returns an empty dictionary otherwise
+    pub fn load_or_new(path: &str) -> Result<Self, String> {
+        if std::path::Path::new(path).exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::new())
+        }
+    }
+}
+
+/// returns the vertex of the given identifier, string identifiers are encoded with the given encoding and recorded into the dictionary
+pub(crate) fn vertex_id(name: &str, integer_ids: bool, vertex_encoding: VertexEncoding, dictionary: Option<&VertexDictionary>) -> VertexType {
+    if integer_ids {
+        return name.parse().unwrap();
+    }
+
+    match (vertex_encoding, dictionary) {
+        (VertexEncoding::Dense, Some(dictionary)) => dictionary.get_or_assign(name),
+        (_, Some(dictionary)) => {
+            let vertex = calculate_hash(name);
+            dictionary.insert(vertex, name);
+            vertex
+        }
+        (_, None) => calculate_hash(name),
+    }
+}
+
+/// Encoding of the vertex identifiers of an input, so that vertex files, e.g., of `VertexFilter` and `VertexTypes`,
+/// resolve identifiers to the same vertices as the readers of the input
+#[derive(Clone, Debug)]
+pub struct VertexIds {
+    integer_ids: bool,
+    vertex_encoding: VertexEncoding,
+    dictionary: Option<VertexDictionary>,
+}
+
+impl VertexIds {
+    /// integer identifiers if `integer_ids` is set, hashed string identifiers otherwise
+    pub fn new(integer_ids: bool) -> Self {
+        Self { integer_ids, vertex_encoding: VertexEncoding::Hash, dictionary: None }
+    }
+
+    /// encodes string identifiers with the given encoding through the dictionary of the readers, see `LineFileReader::with_vertex_encoding`
+    pub fn with_vertex_encoding(mut self, vertex_encoding: VertexEncoding, dictionary: VertexDictionary) -> Self {
+        if !self.integer_ids {
+            self.vertex_encoding = vertex_encoding;
+            self.dictionary = Some(dictionary);
+        }
+        self
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.integer_ids
+    }
+
+    /// returns the vertex of the given identifier, or an error if it is not a valid integer identifier
+    pub fn resolve(&self, name: &str) -> Result<VertexType, String> {
+        if self.integer_ids {
+            return name.parse().map_err(|_| format!("Invalid vertex identifier {}", name));
+        }
+        Ok(vertex_id(name, false, self.vertex_encoding, self.dictionary.as_ref()))
+    }
+}
+
 /// Trait for FileBased input streams
 pub trait InputFileReader: Iterator {
     fn open(input_file: &str, has_timestamp: bool, integer_ids: bool) -> Result<Self, std::io::Error> where Self: Sized;
@@ -94,6 +415,11 @@ pub struct LineFileReader {
     integer_ids: bool,
     current_timestamp: u64,
     first_line: Option<String>,
+    // remaining # of bytes of the partition, unbounded if it is not a partitioned reader
+    remaining_bytes: Option<u64>,
+    // records string identifiers of vertices if it is set
+    dictionary: Option<VertexDictionary>,
+    vertex_encoding: VertexEncoding,
 }
 
 impl Iterator for LineFileReader {
@@ -104,19 +430,27 @@ impl Iterator for LineFileReader {
 
         if self.first_line.is_some() && self.is_timestamped {
             let line = self.first_line.as_ref().unwrap().to_string();
-            line_fields = line.split_whitespace().map(|s| s.to_string()).collect();
+            line_fields = Self::fields(&line);
             self.first_line = None;
         } else {
             while self.is_timestamped && line_fields.len() < 4 || line_fields.len() < 3 {
                 let mut line = String::new();
 
+                // a line belongs to the partition its first byte is in
+                if self.remaining_bytes == Some(0) {
+                    return None;
+                }
+
                 let len = self.reader.read_line(&mut line).expect("Error reading the next line from input stream");
 
                 if len == 0 {
                     return None;
                 }
+                if let Some(remaining_bytes) = self.remaining_bytes.as_mut() {
+                    *remaining_bytes = remaining_bytes.saturating_sub(len as u64);
+                }
 
-                line_fields = line.split_whitespace().map(|s| s.to_string()).collect();
+                line_fields = Self::fields(&line);
                 if self.is_timestamped && line_fields.len() < 4 {
                     continue;
                 } else if line_fields.len() < 3 {
@@ -127,18 +461,10 @@ impl Iterator for LineFileReader {
             }
         }
 
-        let source = if self.integer_ids {
-            line_fields[0].parse().unwrap()
-        } else {
-            calculate_hash(&line_fields[0])
-        };
+        let source = self.vertex_id(&line_fields[0]);
         let edge_predicate = &line_fields[1];
 
-        let target = if self.integer_ids {
-            line_fields[2].parse().unwrap()
-        } else {
-            calculate_hash(&line_fields[2])
-        };
+        let target = self.vertex_id(&line_fields[2]);
         let edge_ts: u64 = if self.is_timestamped {
             line_fields[3].parse().unwrap()
         } else {
@@ -148,7 +474,69 @@ impl Iterator for LineFileReader {
         // update the current timestamp
         self.current_timestamp = edge_ts;
 
-        Some(StreamingGraphEdge::new(source, target, edge_predicate.to_string(), edge_ts))
+        // labels are canonicalized as predicates of RPQs, e.g., IRIs without angle brackets
+        Some(StreamingGraphEdge::new(source, target, canonical_label(edge_predicate, false), edge_ts))
+    }
+}
+
+impl LineFileReader {
+    /// records string identifiers of vertices into the given dictionary while reading
+    pub fn with_dictionary(self, dictionary: VertexDictionary) -> Self {
+        let vertex_encoding = self.vertex_encoding;
+        self.with_vertex_encoding(vertex_encoding, dictionary)
+    }
+
+    /// encodes string identifiers of vertices with the given encoding, where they are recorded into the given dictionary
+    pub fn with_vertex_encoding(mut self, vertex_encoding: VertexEncoding, dictionary: VertexDictionary) -> Self {
+        if !self.integer_ids {
+            self.vertex_encoding = vertex_encoding;
+            self.dictionary = Some(dictionary);
+        }
+        self
+    }
+
+    fn vertex_id(&self, name: &str) -> VertexType {
+        vertex_id(name, self.integer_ids, self.vertex_encoding, self.dictionary.as_ref())
+    }
+
+    /// fields of the line with quoted labels, malformed lines are skipped as lines with missing fields
+    fn fields(line: &str) -> Vec<String> {
+        split_fields(line).unwrap_or_else(|e| {
+            warn!("Skipping line: {}", e);
+            Vec::new()
+        })
+    }
+
+    /// initialize a reader over the `index`th of `peers` disjoint byte ranges of the file
+    /// ranges are aligned to line boundaries, so that each line is read by exactly one reader
+    /// only timestamped inputs can be partitioned, as timestamps of other inputs depend on line numbers
+    /// the start timestamp is the timestamp of the first line of the file for all partitions
+    pub fn open_partition(input_file: &str, has_timestamp: bool, integer_ids: bool, index: usize, peers: usize) -> Result<Self, Error> {
+        if !has_timestamp {
+            return Err(Error::new(ErrorKind::InvalidInput, "Only timestamped inputs can be partitioned by byte ranges"));
+        }
+
+        let mut reader = Self::open(input_file, has_timestamp, integer_ids)?;
+        let file_size = reader.reader.get_ref().metadata()?.len();
+        let range_start = file_size * index as u64 / peers as u64;
+        let range_end = file_size * (index as u64 + 1) / peers as u64;
+
+        if range_start > 0 {
+            // skip the line that starts in the previous range, or the line break right before this range
+            reader.first_line = None;
+            reader.reader.seek(SeekFrom::Start(range_start - 1))?;
+            let mut partial_line = String::new();
+            let skipped = reader.reader.read_line(&mut partial_line)? as u64;
+            reader.remaining_bytes = Some((range_end + 1).saturating_sub(range_start + skipped));
+        } else if range_end == 0 {
+            reader.first_line = None;
+            reader.remaining_bytes = Some(0);
+        } else {
+            let first_line_length = reader.first_line.as_ref().map_or(0, |line| line.len() as u64);
+            reader.remaining_bytes = Some(range_end.saturating_sub(first_line_length));
+        }
+
+        Ok(reader)
     }
 }
 
@@ -165,14 +553,14 @@ impl InputFileReader for LineFileReader {
         if has_timestamp {
             let mut line = String::new();
             file_reader.read_line(&mut line).expect("Cannot open input graph file");
-            first_ts = line.split_whitespace().nth(3).unwrap().parse().unwrap();
+            first_ts = Self::fields(&line)[3].parse().unwrap();
             first_line = Some(line);
 
             trace!("First line read while opening -- {:?}", first_line);
         }
 
         // create the file reader object
-        Ok(Self { reader: file_reader, start_timestamp: first_ts, is_timestamped: has_timestamp, integer_ids: integer_ids, current_timestamp: first_ts, first_line: first_line })
+        Ok(Self { reader: file_reader, start_timestamp: first_ts, is_timestamped: has_timestamp, integer_ids: integer_ids, current_timestamp: first_ts, first_line: first_line, remaining_bytes: None, dictionary: None, vertex_encoding: VertexEncoding::Hash })
     }
 
     fn close(&self) {
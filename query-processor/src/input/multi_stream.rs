@@ -0,0 +1,107 @@
+//! Multiple independent physical input streams that feed a single dataflow, e.g., one stream per relation
+//!
+//! Each stream has its own watermark, i.e., the timestamp of its latest edge, and edges of a stream arrive in timestamp order
+//! independently of other streams. The dataflow can only complete times up to the aligned frontier,
+//! i.e., the minimum watermark of streams that are not exhausted yet.
+use std::iter::Iterator;
+
+use log::trace;
+
+use crate::input::{SGE, StreamingGraphEdge};
+
+/// Tracks watermarks of multiple input streams and their aligned frontier
+#[derive(Clone, Debug)]
+pub struct FrontierAligner {
+    // watermark of each stream, `None` until the first edge of the stream
+    watermarks: Vec<Option<u64>>,
+    closed: Vec<bool>,
+}
+
+impl FrontierAligner {
+    pub fn new(stream_count: usize) -> Self {
+        Self {
+            watermarks: vec![None; stream_count],
+            closed: vec![false; stream_count],
+        }
+    }
+
+    pub fn stream_count(&self) -> usize {
+        self.watermarks.len()
+    }
+
+    /// advances the watermark of the given stream, watermarks never regress
+    pub fn advance(&mut self, stream: usize, timestamp: u64) {
+        let watermark = &mut self.watermarks[stream];
+        if watermark.map_or(true, |current| current < timestamp) {
+            *watermark = Some(timestamp);
+        }
+    }
+
+    /// marks the given stream as exhausted, so that it no longer holds back the aligned frontier
+    pub fn close(&mut self, stream: usize) {
+        trace!("Stream {} is closed at {:?}", stream, self.watermarks[stream]);
+        self.closed[stream] = true;
+    }
+
+    pub fn is_closed(&self, stream: usize) -> bool {
+        self.closed[stream]
+    }
+
+    pub fn watermark(&self, stream: usize) -> Option<u64> {
+        self.watermarks[stream]
+    }
+
+    /// returns the time up to which all open streams have progressed, or `None` if all streams are closed
+    /// a stream that has not produced any edge yet holds the aligned frontier at 0
+    pub fn aligned_frontier(&self) -> Option<u64> {
+        self.open_streams().map(|stream| self.watermarks[stream].unwrap_or(0)).min()
+    }
+
+    /// returns the open stream with the smallest watermark, streams without any edge first
+    /// reading from the lagging stream keeps watermarks of streams close to each other
+    pub fn lagging_stream(&self) -> Option<usize> {
+        self.open_streams().min_by_key(|stream| self.watermarks[*stream])
+    }
+
+    fn open_streams<'a>(&'a self) -> impl Iterator<Item=usize> + 'a {
+        (0..self.stream_count()).filter(move |stream| !self.closed[*stream])
+    }
+}
+
+/// Reads multiple streams of edges into a single sequence of (stream index, edge) pairs
+/// Each edge is read from the lagging stream, so a slow stream is never left behind by others
+/// but edges of distinct streams are not necessarily in timestamp order
+pub struct AlignedStreams<I> {
+    readers: Vec<I>,
+    aligner: FrontierAligner,
+}
+
+impl<I: Iterator<Item=StreamingGraphEdge>> AlignedStreams<I> {
+    pub fn new(readers: Vec<I>) -> Self {
+        let aligner = FrontierAligner::new(readers.len());
+        Self { readers, aligner }
+    }
+
+    /// returns watermarks of streams read so far
+    pub fn aligner(&self) -> &FrontierAligner {
+        &self.aligner
+    }
+}
+
+impl<I: Iterator<Item=StreamingGraphEdge>> Iterator for AlignedStreams<I> {
+    type Item = (usize, StreamingGraphEdge);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(stream) = self.aligner.lagging_stream() {
+            match self.readers[stream].next() {
+                Some(sge) => {
+                    self.aligner.advance(stream, sge.get_timestamp());
+                    return Some((stream, sge));
+                }
+                None => self.aligner.close(stream),
+            }
+        }
+
+        None
+    }
+}
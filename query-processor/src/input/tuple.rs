@@ -2,21 +2,181 @@ extern crate abomonation;
 extern crate abomonation_derive;
 extern crate timely;
 
+use std::fmt;
+
+#[cfg(not(feature = "bincode"))]
 use abomonation_derive::Abomonation;
+#[cfg(feature = "bincode")]
+use serde::{Deserialize, Serialize};
 
 use crate::input::{GraphEdge, SGT, StreamingGraphEdge};
 
-use self::super::super::util::types::{HalfOpenTimeInterval, VertexType};
+use self::super::super::util::types::{HalfOpenTimeInterval, is_provenance_mode, VertexType};
+
+/// ids of the base edges that a tuple is derived from, sorted and without duplicates, see `set_provenance_mode`
+/// Ids are boxed so that tuples outside of provenance mode only carry an empty pointer instead of an empty vector
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(not(feature = "bincode"), derive(Abomonation))]
+#[cfg_attr(feature = "bincode", derive(Serialize, Deserialize))]
+#[allow(clippy::box_collection)]
+pub struct Provenance(Option<Box<Vec<u64>>>);
+
+impl Provenance {
+    /// provenance of a tuple of the edge with the given id
+    pub fn of_edge(edge_id: u64) -> Self {
+        Provenance(Some(Box::new(vec![edge_id])))
+    }
+
+    /// sorted ids of the base edges, empty outside of provenance mode
+    pub fn ids(&self) -> &[u64] {
+        self.0.as_deref().map_or(&[][..], |ids| ids.as_slice())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids().is_empty()
+    }
+
+    /// provenance of a result derived from tuples with the given provenances, i.e., the union of their edge ids
+    pub fn merge(&self, other: &Self) -> Self {
+        match (&self.0, &other.0) {
+            (None, None) => Provenance(None),
+            (Some(_), None) => self.clone(),
+            (None, Some(_)) => other.clone(),
+            (Some(lhs), Some(rhs)) => {
+                let mut ids: Vec<u64> = lhs.iter().chain(rhs.iter()).cloned().collect();
+                ids.sort_unstable();
+                ids.dedup();
+                Provenance(Some(Box::new(ids)))
+            }
+        }
+    }
+}
 
 /// StreamingGraphTuple implementation
-#[derive(Clone, Debug, Abomonation, PartialEq, Hash,
-Eq)]
+/// Tuples are exchanged between workers with Abomonation, or with serde and bincode with the `bincode` feature,
+/// where fields are not limited to types that Abomonation supports, e.g., `HashMap` properties
+#[derive(Clone, Debug, PartialEq, Hash,
+Eq, PartialOrd, Ord)]
+#[cfg_attr(not(feature = "bincode"), derive(Abomonation))]
+#[cfg_attr(feature = "bincode", derive(Serialize, Deserialize))]
 pub struct StreamingGraphTuple {
     pub source: u64,
     pub target: u64,
     pub label: String,
     pub interval: HalfOpenTimeInterval,
     pub append: bool,
+    /// id of the window that produced this tuple, set by operators emitting per window batches
+    pub window_id: u64,
+    /// ids of the slides of the base edges of the tuple if they are enabled with `set_window_ids`, zero otherwise
+    pub slide_ids: SlideIds,
+    /// ids of the base edges of the tuple in provenance mode, which joins carry over to their results, empty otherwise
+    pub provenance: Provenance,
+}
+
+impl StreamingGraphTuple {
+    /// returns the structured form of the label of this tuple
+    pub fn result_label(&self) -> ResultLabel {
+        ResultLabel::parse(&self.label)
+    }
+
+    /// slide ids and provenance of this tuple
+    pub fn annotations(&self) -> Annotations {
+        Annotations { slide_ids: self.slide_ids, provenance: self.provenance.clone() }
+    }
+
+    pub fn with_annotations(mut self, annotations: Annotations) -> Self {
+        self.slide_ids = annotations.slide_ids;
+        self.provenance = annotations.provenance;
+        self
+    }
+}
+
+/// Ids of the earliest and the latest slide of the base edges of a tuple, see `set_window_ids`
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(not(feature = "bincode"), derive(Abomonation))]
+#[cfg_attr(feature = "bincode", derive(Serialize, Deserialize))]
+pub struct SlideIds {
+    pub first: u64,
+    pub last: u64,
+}
+
+impl SlideIds {
+    /// slide ids of a tuple of an edge in the given slide
+    pub fn of_slide(slide_id: u64) -> Self {
+        Self { first: slide_id, last: slide_id }
+    }
+
+    /// slide ids of a result derived from tuples with the given slide ids, i.e., the min of their first and the max of their last ids
+    pub fn merge(&self, other: &Self) -> Self {
+        Self { first: self.first.min(other.first), last: self.last.max(other.last) }
+    }
+}
+
+/// Slide ids and provenance of a tuple, which operators carry over from their inputs to their results
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Annotations {
+    pub slide_ids: SlideIds,
+    pub provenance: Provenance,
+}
+
+impl Annotations {
+    /// annotations of a result derived from tuples with the given annotations,
+    /// i.e., the merge of their slide ids and the union of their edge ids
+    pub fn merge(&self, other: &Self) -> Self {
+        Self { slide_ids: self.slide_ids.merge(&other.slide_ids), provenance: self.provenance.merge(&other.provenance) }
+    }
+}
+
+/// Structured label of result tuples, i.e., the query that produced a tuple and its stage within the query plan
+/// Its string form is `<query_id>` for final results and `<query_id>.<stage>` for intermediate results,
+/// so that intermediate labels of distinct queries, e.g., `cq`, do not collide in multi-query pipelines
+/// and free-form labels without a stage remain valid query results
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ResultLabel {
+    pub query_id: String,
+    pub stage: Option<String>,
+}
+
+impl ResultLabel {
+    pub const SEPARATOR: char = '.';
+
+    /// label of final results of a query
+    pub fn query(query_id: &str) -> Self {
+        Self { query_id: query_id.to_string(), stage: None }
+    }
+
+    /// label of intermediate results of the given stage of a query
+    pub fn stage(query_id: &str, stage: &str) -> Self {
+        Self { query_id: query_id.to_string(), stage: Some(stage.to_string()) }
+    }
+
+    /// parses the string form of a label, where the stage follows the first separator
+    pub fn parse(label: &str) -> Self {
+        match label.find(Self::SEPARATOR) {
+            Some(position) => Self::stage(&label[..position], &label[position + 1..]),
+            None => Self::query(label),
+        }
+    }
+
+    /// returns true if the label belongs to final results of a query
+    pub fn is_final(&self) -> bool {
+        self.stage.is_none()
+    }
+}
+
+impl fmt::Display for ResultLabel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.stage {
+            Some(stage) => write!(f, "{}{}{}", self.query_id, Self::SEPARATOR, stage),
+            None => write!(f, "{}", self.query_id),
+        }
+    }
+}
+
+impl From<ResultLabel> for String {
+    fn from(label: ResultLabel) -> Self {
+        label.to_string()
+    }
 }
 
 impl GraphEdge for StreamingGraphTuple {
@@ -41,14 +201,65 @@ impl SGT<HalfOpenTimeInterval, StreamingGraphEdge> for StreamingGraphTuple {
             label: edge.get_label().to_string(),
             interval: interval,
             append: edge.append,
+            window_id: 0,
+            slide_ids: SlideIds::default(),
+            provenance: if is_provenance_mode() { Provenance::of_edge(edge.edge_id()) } else { Provenance::default() },
         }
     }
 
     fn new(source: u64, target: u64, label: String, interval: HalfOpenTimeInterval) -> Self {
-        Self { source: source, target: target, label: label, interval: interval, append: true }
+        Self { source: source, target: target, label: label, interval: interval, append: true, window_id: 0, slide_ids: SlideIds::default(), provenance: Provenance::default() }
     }
 
     fn get_interval(&self) -> HalfOpenTimeInterval {
         self.interval
     }
 }
+
+/// Result tuple tagged with the standing query it originates from, see `UnionWithTag`
+#[derive(Clone, Debug, PartialEq, Hash, Eq, PartialOrd, Ord)]
+#[cfg_attr(not(feature = "bincode"), derive(Abomonation))]
+#[cfg_attr(feature = "bincode", derive(Serialize, Deserialize))]
+pub struct TaggedTuple {
+    pub tag: String,
+    pub tuple: StreamingGraphTuple,
+}
+
+impl TaggedTuple {
+    pub fn new(tag: String, tuple: StreamingGraphTuple) -> Self {
+        Self { tag, tuple }
+    }
+}
+
+/// Result of a star join, i.e., the targets of one tuple of each joined stream that share their source as the center of the star,
+/// valid during the intersection of the intervals of the tuples, see `StarJoin`
+#[derive(Clone, Debug, PartialEq, Hash, Eq, PartialOrd, Ord)]
+#[cfg_attr(not(feature = "bincode"), derive(Abomonation))]
+#[cfg_attr(feature = "bincode", derive(Serialize, Deserialize))]
+pub struct StarTuple {
+    pub center: u64,
+    // target of the tuple of each stream, in the order of the streams
+    pub leaves: Vec<u64>,
+    pub label: String,
+    pub interval: HalfOpenTimeInterval,
+}
+
+impl StarTuple {
+    pub fn new(center: u64, leaves: Vec<u64>, label: String, interval: HalfOpenTimeInterval) -> Self {
+        Self { center, leaves, label, interval }
+    }
+}
+
+/// Results of a window that share their source, label and interval, see `GroupBySource`
+#[derive(Clone, Debug, PartialEq, Hash, Eq, PartialOrd, Ord)]
+#[cfg_attr(not(feature = "bincode"), derive(Abomonation))]
+#[cfg_attr(feature = "bincode", derive(Serialize, Deserialize))]
+pub struct GroupedResult {
+    pub source: u64,
+    // targets of the results, sorted and without duplicates
+    pub targets: Vec<u64>,
+    pub label: String,
+    pub interval: HalfOpenTimeInterval,
+    pub append: bool,
+    pub window_id: u64,
+}
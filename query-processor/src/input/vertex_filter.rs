@@ -0,0 +1,62 @@
+//! Restriction of the input stream to a subgraph induced by a set of vertices
+//!
+//! Vertices are given either as a range of integer identifiers `<start>..<end>` (end exclusive),
+//! or as a file with one vertex identifier per line, which are resolved with the `VertexIds` of the input, e.g., through the
+//! `VertexDictionary` of its readers for dense string identifiers.
+use std::collections::HashSet;
+use std::fs;
+use std::ops::Range;
+
+use crate::input::{GraphEdge, VertexIds};
+use crate::util::types::VertexType;
+
+#[derive(Clone, Debug)]
+pub enum VertexFilter {
+    Set(HashSet<VertexType>),
+    Range(Range<VertexType>),
+}
+
+impl VertexFilter {
+    /// parses a vertex range `<start>..<end>`, or reads vertices from the given file otherwise
+    pub fn from_spec(spec: &str, vertex_ids: &VertexIds) -> Result<Self, String> {
+        if let Some((start, end)) = parse_range(spec) {
+            if !vertex_ids.is_integer() {
+                return Err("Vertex ranges require integer vertex identifiers".to_string());
+            }
+            return Ok(VertexFilter::Range(start..end));
+        }
+
+        Self::from_file(spec, vertex_ids)
+    }
+
+    /// reads vertices from the given file with one vertex identifier per line
+    pub fn from_file(path: &str, vertex_ids: &VertexIds) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("Cannot read vertex file {}: {}", path, e))?;
+
+        let mut vertices = HashSet::new();
+        for line in content.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            vertices.insert(vertex_ids.resolve(line).map_err(|e| format!("{} in {}", e, path))?);
+        }
+
+        Ok(VertexFilter::Set(vertices))
+    }
+
+    pub fn contains(&self, vertex: VertexType) -> bool {
+        match self {
+            VertexFilter::Set(vertices) => vertices.contains(&vertex),
+            VertexFilter::Range(range) => range.contains(&vertex),
+        }
+    }
+
+    /// returns true if both endpoints of the edge are in the subgraph
+    pub fn retains<E: GraphEdge>(&self, edge: &E) -> bool {
+        self.contains(edge.get_source()) && self.contains(edge.get_target())
+    }
+}
+
+fn parse_range(spec: &str) -> Option<(VertexType, VertexType)> {
+    let mut bounds = spec.splitn(2, "..");
+    let start = bounds.next()?.parse().ok()?;
+    let end = bounds.next()?.parse().ok()?;
+    Some((start, end))
+}
@@ -0,0 +1,77 @@
+//! Projection of typed vertices of a property graph onto edge labels
+//!
+//! Vertex types are read from a file with one `<vertex> <type>` record per line, e.g., `42 Person`, where identifiers
+//! are resolved as in `VertexFilter`. The label of an edge between typed vertices is rewritten into
+//! `<source type>:<label>:<target type>`, e.g., `Person:knows:Organisation`, so that RPQs constrain the types of endpoints
+//! with plain predicates. Labels of edges with an untyped endpoint are kept as they are.
+//! Types cannot contain the separator, whereas labels can, e.g., IRIs, so typed labels are split at the first and the last separator.
+use std::collections::HashMap;
+use std::fs;
+
+use crate::input::{StreamingGraphEdge, VertexIds};
+use crate::input::labels::split_fields;
+use crate::util::types::VertexType;
+
+/// environment variable that sets the vertex type file in runners
+pub const VERTEX_TYPES_VARIABLE: &str = "SGRAFFITO_VERTEX_TYPES";
+
+/// separator of endpoint types and the label in typed labels
+pub const TYPE_SEPARATOR: char = ':';
+
+#[derive(Clone, Debug, Default)]
+pub struct VertexTypes {
+    types: HashMap<VertexType, String>,
+}
+
+impl VertexTypes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// reads vertex types from the given file with one `<vertex> <type>` record per line
+    pub fn from_file(path: &str, vertex_ids: &VertexIds) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| format!("Cannot read vertex type file {}: {}", path, e))?;
+
+        let mut vertex_types = Self::new();
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let fields = split_fields(line).map_err(|e| format!("Invalid vertex type record in {}: {}", path, e))?;
+            if fields.len() != 2 {
+                return Err(format!("Vertex type record {} in {} should have a vertex and a type", line, path));
+            }
+            if fields[1].contains(TYPE_SEPARATOR) {
+                return Err(format!("Vertex type {} in {} should not contain {}", fields[1], path, TYPE_SEPARATOR));
+            }
+            let vertex = vertex_ids.resolve(&fields[0]).map_err(|e| format!("{} in {}", e, path))?;
+            vertex_types.insert(vertex, &fields[1]);
+        }
+
+        Ok(vertex_types)
+    }
+
+    pub fn insert(&mut self, vertex: VertexType, vertex_type: &str) {
+        self.types.insert(vertex, vertex_type.to_string());
+    }
+
+    pub fn get(&self, vertex: VertexType) -> Option<&str> {
+        self.types.get(&vertex).map(String::as_str)
+    }
+
+    /// rewrites the label of the edge in place if both of its endpoints are typed
+    pub fn apply(&self, edge: &mut StreamingGraphEdge) {
+        if let (Some(source_type), Some(target_type)) = (self.get(edge.source), self.get(edge.target)) {
+            edge.label = typed_label(source_type, &edge.label, target_type);
+        }
+    }
+}
+
+/// label of edges with the given label between vertices of the given types, e.g., to build edge predicates of queries
+pub fn typed_label(source_type: &str, label: &str, target_type: &str) -> String {
+    format!("{}{}{}{}{}", source_type, TYPE_SEPARATOR, label, TYPE_SEPARATOR, target_type)
+}
+
+/// splits a typed label into the source type, the label and the target type, or returns `None` if it is not typed
+pub fn split_typed_label(typed_label: &str) -> Option<(&str, &str, &str)> {
+    let (source_type, rest) = typed_label.split_once(TYPE_SEPARATOR)?;
+    let (label, target_type) = rest.rsplit_once(TYPE_SEPARATOR)?;
+    Some((source_type, label, target_type))
+}
@@ -0,0 +1,84 @@
+//! Timer-based watermarks that advance event time on a wall-clock schedule during input gaps
+//!
+//! Slides are triggered by edges, so a stalled stream, e.g., a live feed read from a pipe, never closes its last windows.
+//! `WatermarkSource` reads the underlying source on a background thread, and emits a `StreamEvent::Watermark` whenever no edge
+//! arrives within the interval. The watermark assumes that event time, in seconds, advances with wall-clock time
+//! since the arrival of the last edge.
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::trace;
+
+use crate::input::{SGE, StreamEvent, StreamingGraphEdge};
+use crate::util::shutdown::is_shutdown_requested;
+
+/// Environment variable for the wall-clock interval of watermarks in milliseconds, watermarks are disabled if it is not set
+pub const WATERMARK_INTERVAL_VARIABLE: &str = "SGRAFFITO_WATERMARK_INTERVAL";
+
+// # of edges read ahead of the consumer
+const READ_AHEAD: usize = 1024;
+
+/// Punctuated source that emits watermarks during input gaps, the stream ends with `EndOfStream` as `PunctuatedSource`
+pub struct WatermarkSource {
+    receiver: Receiver<StreamingGraphEdge>,
+    interval: Duration,
+    // timestamp and arrival of the last edge
+    last_edge: Option<(u64, Instant)>,
+    finished: bool,
+}
+
+impl WatermarkSource {
+    /// reads the given source on a background thread, and emits a watermark after each `interval` without an edge
+    pub fn spawn<I: Iterator<Item=StreamingGraphEdge> + Send + 'static>(source: I, interval: Duration) -> Self {
+        let (sender, receiver) = sync_channel(READ_AHEAD);
+        thread::spawn(move || {
+            for sge in source {
+                // the consumer has stopped reading
+                if sender.send(sge).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { receiver, interval, last_edge: None, finished: false }
+    }
+
+    fn end_of_stream(&mut self) -> Option<StreamEvent> {
+        self.finished = true;
+        Some(StreamEvent::EndOfStream)
+    }
+}
+
+impl Iterator for WatermarkSource {
+    type Item = StreamEvent;
+
+    fn next(&mut self) -> Option<StreamEvent> {
+        if self.finished {
+            return None;
+        }
+
+        loop {
+            let event = self.receiver.recv_timeout(self.interval);
+            if is_shutdown_requested() {
+                return self.end_of_stream();
+            }
+
+            match event {
+                Ok(sge) => {
+                    self.last_edge = Some((sge.get_timestamp(), Instant::now()));
+                    return Some(StreamEvent::Edge(sge));
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    // event time is unknown until the first edge
+                    if let Some((timestamp, arrival)) = self.last_edge {
+                        let watermark = timestamp + arrival.elapsed().as_secs();
+                        trace!("No input for {:?}, watermark at {}", arrival.elapsed(), watermark);
+                        return Some(StreamEvent::Watermark(watermark));
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => return self.end_of_stream(),
+            }
+        }
+    }
+}
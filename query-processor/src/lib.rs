@@ -1,8 +1,20 @@
-pub use self::graph::Graph;
-pub use self::operator::{delta::Delta, spanning_tree::SpanningTree, tree_node::TreeNode};
+pub use self::graph::alphabet::{Alphabet, SharedAlphabet};
 
-pub mod graph;
+pub mod admin;
+#[cfg(all(feature = "dd", feature = "metrics"))]
+pub mod bench;
+#[cfg(feature = "dd")]
+pub mod dd;
+pub mod diff;
+pub(crate) mod graph;
+pub mod harness;
+pub mod ingest;
 pub mod operator;
+pub mod prelude;
 pub mod util;
 pub mod input;
-pub mod query;
\ No newline at end of file
+pub mod query;
+pub mod replica;
+pub mod sink;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
@@ -1,4 +1,94 @@
+use std::fs;
+use std::process;
+
+use sgraffito_query::bench::{Scenario, run_scenario, write_report};
+use sgraffito_query::query::parser::RPQParser;
+use sgraffito_query::replica::Replica;
+
+/// Command-line entry point of the query processor. Subcommands
+/// - `bench <scenario.toml>`: runs every combination of the scenario and writes a consolidated report
+/// - `check <queries>`: validates a file with one RPQ per line, where empty lines and lines starting with `#` are skipped
+/// - `replica <state file> <rpq> [<time>]`: evaluates the RPQ over the graph of a state file of an anomaly dump without consuming any stream
 fn main() {
-    println!("Hello, world!");
+    let mut args = std::env::args();
+    args.next();
+
+    // initialize env_logger
+    env_logger::init();
+
+    match args.next().as_deref() {
+        Some("bench") => {
+            let scenario_file = args.next().unwrap_or_else(|| usage());
+            let scenario = Scenario::from_file(&scenario_file).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                process::exit(1);
+            });
+
+            let reports = run_scenario(&scenario);
+            if let Err(e) = write_report(&reports, &scenario.report) {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+            println!("{} runs of scenario {} are reported at {}", reports.len(), scenario.name, scenario.report);
+        }
+        Some("check") => {
+            let query_file = args.next().unwrap_or_else(|| usage());
+            if !check_queries(&query_file) {
+                process::exit(1);
+            }
+        }
+        Some("replica") => {
+            let (state_file, query) = match (args.next(), args.next()) {
+                (Some(state_file), Some(query)) => (state_file, query),
+                _ => usage(),
+            };
+            let at = args.next().map(|time| time.parse::<u64>().unwrap_or_else(|_| usage()));
+            let view = Replica::load(&state_file).and_then(|replica| replica.evaluate(&query, at)).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                process::exit(1);
+            });
+            for (source, target) in view.pairs() {
+                println!("{} {}", source, target);
+            }
+            eprintln!("{} results of {} over {}", view.len(), query, state_file);
+        }
+        _ => usage()
+    }
 }
 
+/// prints a diagnostic for each invalid query of the file, and returns true if all queries are valid
+fn check_queries(query_file: &str) -> bool {
+    let content = fs::read_to_string(query_file).unwrap_or_else(|e| {
+        eprintln!("Cannot read query file {}: {}", query_file, e);
+        process::exit(1);
+    });
+
+    let parser = RPQParser::new();
+    let mut queries = 0;
+    let mut errors = 0;
+    for (line_number, query) in content.lines().enumerate() {
+        if query.trim().is_empty() || query.trim_start().starts_with('#') {
+            continue;
+        }
+        queries += 1;
+
+        if let Err(e) = parser.parse_regex(query) {
+            errors += 1;
+            eprintln!("{}:{}:{}: {}", query_file, line_number + e.line, e.column, e.message);
+            eprintln!("    {}", query);
+            eprintln!("    {}^ {}", " ".repeat(e.column - 1), e.token.as_ref().map_or("end of query".to_string(), |token| format!("found `{}`", token)));
+        }
+    }
+
+    if errors == 0 {
+        println!("{} queries of {} are valid", queries, query_file);
+    } else {
+        eprintln!("{} of {} queries of {} are invalid", errors, queries, query_file);
+    }
+    errors == 0
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: sgraffito-query bench <scenario.toml> | check <queries> | replica <state file> <rpq> [<time>]");
+    process::exit(2);
+}
@@ -0,0 +1,128 @@
+extern crate timely;
+
+use std::cmp::{max, min};
+use std::collections::{BTreeMap, HashMap};
+
+use timely::dataflow::{Scope, Stream};
+use timely::dataflow::channels::pact::Exchange;
+use timely::dataflow::operators::Capability;
+use timely::dataflow::operators::generic::operator::Operator;
+
+use crate::input::tuple::StreamingGraphTuple;
+use crate::util::types::{HalfOpenTimeInterval, VertexType};
+
+/// Compares the results that are valid at consecutive windows, evaluated at every multiple of the slide size,
+/// and emits only results that appear or disappear between them, e.g., to alert on new fraud paths
+/// A rising result is emitted as an append with its validity interval, and a falling result as a tuple that is not an append
+/// with the interval it was last valid for. Alerts carry the window time in their `window_id` and are emitted at the window time
+/// Retractions in the input, i.e., tuples that are not appends, end the validity of their result at their start
+pub trait ChangeAlerts<G: Scope<Timestamp=u64>> {
+    /// panics if `slide_size` is zero
+    fn change_alerts(&self, slide_size: u64) -> Stream<G, StreamingGraphTuple>;
+}
+
+impl<G: Scope<Timestamp=u64>> ChangeAlerts<G> for Stream<G, StreamingGraphTuple> {
+    fn change_alerts(&self, slide_size: u64) -> Stream<G, StreamingGraphTuple> {
+        assert!(slide_size > 0, "Slide size of change alerts has to be positive");
+        let first_window = move |time: u64| (time + slide_size - 1) / slide_size * slide_size;
+        let mut vector = Vec::new();
+
+        // stash to collect tuples until the window they belong to is complete
+        let mut stash: BTreeMap<u64, Vec<StreamingGraphTuple>> = BTreeMap::new();
+        // interval of each result that is received and has not expired, keep max expiry for each value equivalent result
+        let mut results: HashMap<(VertexType, VertexType, String), HalfOpenTimeInterval> = HashMap::new();
+        // results that are valid at the last evaluated window
+        let mut previous: HashMap<(VertexType, VertexType, String), HalfOpenTimeInterval> = HashMap::new();
+
+        let mut next_window: Option<u64> = None;
+        // the latest time of the input, windows after it are not evaluated once the input is closed
+        let mut last_time = 0;
+        // capability to emit alerts of windows that complete without input at their time
+        let mut capability: Option<Capability<u64>> = None;
+
+        // a result is compared across windows by the same worker
+        let exchange = Exchange::new(|sgt: &StreamingGraphTuple| sgt.source);
+
+        self.unary_frontier(exchange, "ChangeAlerts", move |_, _| move |input, output| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                stash.entry(*time.time()).or_insert_with(Vec::new).extend(vector.drain(..));
+                last_time = max(last_time, *time.time());
+                if capability.as_ref().map_or(true, |current| time.time() < current.time()) {
+                    capability = Some(time.retain());
+                }
+                if next_window.is_none() {
+                    next_window = Some(first_window(*time.time()));
+                }
+            });
+
+            let end_of_stream = input.frontier().frontier().is_empty();
+            while let Some(window) = next_window {
+                if input.frontier().less_equal(&window) || (end_of_stream && window > first_window(last_time)) {
+                    break;
+                }
+
+                let due_times: Vec<u64> = stash.range(..=window).map(|(time, _)| *time).collect();
+                for sgt in due_times.into_iter().flat_map(|time| stash.remove(&time).unwrap()) {
+                    let key = (sgt.source, sgt.target, sgt.label);
+                    if sgt.append {
+                        let interval = sgt.interval;
+                        results.entry(key).and_modify(|current_interval| {
+                            if current_interval.end < interval.end {
+                                *current_interval = interval;
+                            }
+                        }).or_insert(interval);
+                    } else if let Some(current_interval) = results.get_mut(&key) {
+                        current_interval.end = min(current_interval.end, sgt.interval.start);
+                    }
+                }
+
+                results.retain(|_, interval| interval.end > window);
+                let current: HashMap<_, _> = results.iter()
+                    .filter(|(_, interval)| interval.start <= window)
+                    .map(|(key, interval)| (key.clone(), *interval))
+                    .collect();
+
+                let mut alerts: Vec<StreamingGraphTuple> = current.iter()
+                    .filter(|(key, _)| !previous.contains_key(*key))
+                    .map(|((source, target, label), interval)| StreamingGraphTuple::new(*source, *target, label.clone(), *interval))
+                    .chain(previous.iter()
+                        .filter(|(key, _)| !current.contains_key(*key))
+                        .map(|((source, target, label), interval)| {
+                            let mut sgt = StreamingGraphTuple::new(*source, *target, label.clone(), *interval);
+                            sgt.append = false;
+                            sgt
+                        }))
+                    .collect();
+                if !alerts.is_empty() {
+                    alerts.sort();
+                    let current_capability = capability.as_ref().expect("Change alerts have no capability for a window");
+                    output.session(&current_capability.delayed(&max(window, *current_capability.time()))).give_iterator(alerts.into_iter().map(|mut sgt| {
+                        sgt.window_id = window;
+                        sgt
+                    }));
+                }
+                previous = current;
+
+                // windows without any result are skipped until the next stashed tuple
+                next_window = if results.is_empty() && previous.is_empty() {
+                    stash.keys().next().map(|time| max(first_window(*time), window + slide_size))
+                } else {
+                    Some(window + slide_size)
+                };
+            }
+
+            // the capability follows the earliest time that an alert can be emitted at
+            if end_of_stream && next_window.map_or(true, |window| window > first_window(last_time)) {
+                capability = None;
+            } else if let Some(current_capability) = capability.as_mut() {
+                let earliest = input.frontier().frontier().iter().cloned().chain(next_window).min();
+                if let Some(earliest) = earliest {
+                    if earliest > *current_capability.time() {
+                        current_capability.downgrade(&earliest);
+                    }
+                }
+            }
+        })
+    }
+}
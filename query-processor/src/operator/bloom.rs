@@ -0,0 +1,98 @@
+use std::hash::{Hash, Hasher};
+
+use hashers::fx_hash::FxHasher;
+
+/// min # of keys that a filter is sized for, so that small indexes are not rebuilt on every few insertions
+const MIN_CAPACITY: usize = 1024;
+
+/// Bloom filter over the keys of a join index, so that probes for keys that are definitely not in the index skip the index lookup
+/// Keys cannot be removed from a Bloom filter, so keys that expire from the index remain in the filter until it is rebuilt
+/// from the keys of the index, which is due once insertions since the last rebuild exceed the capacity the filter is sized for
+#[derive(Clone, Debug)]
+pub struct KeyFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+    false_positive_rate: f64,
+    capacity: usize,
+    insertions: usize,
+}
+
+impl KeyFilter {
+    /// creates an empty filter whose false-positive rate is at most `false_positive_rate` up to its capacity
+    /// panics if the rate is not in (0, 1)
+    pub fn new(false_positive_rate: f64) -> Self {
+        Self::with_capacity(MIN_CAPACITY, false_positive_rate)
+    }
+
+    fn with_capacity(capacity: usize, false_positive_rate: f64) -> Self {
+        assert!(false_positive_rate > 0.0 && false_positive_rate < 1.0, "False-positive rate must be in (0, 1), found {}", false_positive_rate);
+        let ln2 = std::f64::consts::LN_2;
+        let num_bits = ((-(capacity as f64) * false_positive_rate.ln()) / (ln2 * ln2)).ceil().max(64.0) as u64;
+        let num_hashes = ((num_bits as f64 / capacity as f64) * ln2).round().max(1.0) as u32;
+
+        Self {
+            bits: vec![0; ((num_bits + 63) / 64) as usize],
+            num_bits,
+            num_hashes,
+            false_positive_rate,
+            capacity,
+            insertions: 0,
+        }
+    }
+
+    /// positions of the key in the bit vector, derived from a single hash by double hashing
+    fn positions<K: Hash>(&self, key: &K) -> impl Iterator<Item=u64> {
+        let mut hasher = FxHasher::default();
+        key.hash(&mut hasher);
+        let hash = hasher.finish();
+        let (h1, h2, num_bits) = (hash, hash.rotate_left(32) | 1, self.num_bits);
+
+        (0..self.num_hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+    }
+
+    pub fn insert<K: Hash>(&mut self, key: &K) {
+        for position in self.positions(key) {
+            self.bits[(position / 64) as usize] |= 1 << (position % 64);
+        }
+        self.insertions += 1;
+    }
+
+    /// returns false only if the key is definitely not inserted since the last rebuild
+    pub fn may_contain<K: Hash>(&self, key: &K) -> bool {
+        self.positions(key).all(|position| self.bits[(position / 64) as usize] & (1 << (position % 64)) != 0)
+    }
+
+    /// returns true if the filter exceeds its capacity, so that it has to be rebuilt to keep its false-positive rate
+    pub fn needs_rebuild(&self) -> bool {
+        self.insertions > self.capacity
+    }
+
+    /// clears the filter and inserts the given `len` keys, where the filter is sized for twice as many keys to leave room for insertions
+    pub fn rebuild<K: Hash, I: Iterator<Item=K>>(&mut self, keys: I, len: usize) {
+        *self = Self::with_capacity(MIN_CAPACITY.max(2 * len), self.false_positive_rate);
+        keys.for_each(|key| self.insert(&key));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::operator::bloom::KeyFilter;
+
+    #[test]
+    fn filter_keys() {
+        let mut filter = KeyFilter::new(0.01);
+        (0..1000u64).for_each(|key| filter.insert(&key));
+        // inserted keys are never ruled out, and few others pass
+        assert!((0..1000u64).all(|key| filter.may_contain(&key)));
+        assert!((1000..11000u64).filter(|key| filter.may_contain(key)).count() < 300);
+
+        (1000..1100u64).for_each(|key| filter.insert(&key));
+        assert!(filter.needs_rebuild());
+        // keys that are not in the index anymore are dropped by a rebuild
+        filter.rebuild(1050..1100u64, 50);
+        assert!(!filter.needs_rebuild());
+        assert!((1050..1100u64).all(|key| filter.may_contain(&key)));
+        assert!((0..1000u64).filter(|key| filter.may_contain(key)).count() < 100);
+    }
+}
@@ -0,0 +1,142 @@
+extern crate timely;
+
+use std::collections::HashMap;
+
+use timely::dataflow::{Scope, Stream};
+use timely::dataflow::channels::pact::Exchange;
+use timely::dataflow::operators::generic::operator::Operator;
+
+use crate::input::tuple::StreamingGraphTuple;
+use crate::util::types::{EventTime, VertexType};
+
+/// Bounds of the degree of a vertex, i.e., the # of distinct edges in the window that it is the source or the target of
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DegreeThresholds {
+    /// vertices with a smaller degree are outside the thresholds
+    pub min: Option<u64>,
+    /// vertices with a larger degree are outside the thresholds
+    pub max: Option<u64>,
+}
+
+impl DegreeThresholds {
+    /// thresholds that only bound the degree from above, e.g., to tame super-nodes
+    pub fn at_most(max: u64) -> Self {
+        Self { min: None, max: Some(max) }
+    }
+
+    /// returns true if the degree is within the thresholds
+    pub fn contains(&self, degree: u64) -> bool {
+        self.min.map_or(true, |min| degree >= min) && self.max.map_or(true, |max| degree <= max)
+    }
+}
+
+/// What happens to an edge that is incident to a vertex whose degree is outside the thresholds
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DegreeAction {
+    /// the edge is dropped
+    Drop,
+    /// the edge is forwarded with the given suffix appended to its label, so that queries can treat it separately
+    Flag(String),
+}
+
+impl DegreeAction {
+    /// parses `drop`, or `flag:<suffix>`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "drop" => Some(DegreeAction::Drop),
+            _ => name.strip_prefix("flag:").map(|suffix| DegreeAction::Flag(suffix.to_string())),
+        }
+    }
+}
+
+/// Tracks the degree of each vertex in the window, and drops or flags edges incident to a vertex whose degree is outside the thresholds
+/// at the time the edge arrives, counting the edges of the same timestamp, e.g., to tame super-nodes before RPQ evaluation
+/// Retractions, i.e., tuples that are not appends, remove their edge from the window and are forwarded like the edge they retract,
+/// or dropped if it has been dropped. Degrees span both endpoints of each edge, so that all tuples are routed to the first worker
+pub trait DegreeFilter<G: Scope> where G::Timestamp: EventTime {
+    fn degree_filter(&self, thresholds: DegreeThresholds, action: DegreeAction) -> Stream<G, StreamingGraphTuple>;
+}
+
+impl<G: Scope> DegreeFilter<G> for Stream<G, StreamingGraphTuple> where G::Timestamp: EventTime {
+    fn degree_filter(&self, thresholds: DegreeThresholds, action: DegreeAction) -> Stream<G, StreamingGraphTuple> {
+        let mut vector = Vec::new();
+
+        // stash to collect tuples of a timestamp until progress notification
+        let mut stash: HashMap<G::Timestamp, Vec<StreamingGraphTuple>> = HashMap::new();
+        // expiry of each edge in the window and the label it is forwarded with, if any
+        let mut edges: HashMap<(VertexType, VertexType, String), (u64, Option<String>)> = HashMap::new();
+        let mut degrees: HashMap<VertexType, u64> = HashMap::new();
+
+        self.unary_notify(Exchange::new(|_| 0), "DegreeFilter", vec![], move |input, output, notificator| {
+            while let Some((time, data)) = input.next() {
+                data.swap(&mut vector);
+                stash.entry(time.time().clone()).or_insert_with(Vec::new).extend(vector.drain(..));
+                notificator.notify_at(time.retain());
+            }
+
+            notificator.for_each(|time, _, _| {
+                let event_time = time.time().event_time();
+                let decrement = |degrees: &mut HashMap<VertexType, u64>, vertex: VertexType| {
+                    if let Some(degree) = degrees.get_mut(&vertex) {
+                        *degree -= 1;
+                        if *degree == 0 {
+                            degrees.remove(&vertex);
+                        }
+                    }
+                };
+
+                edges.retain(|(source, target, _), (end, _)| {
+                    let valid = *end > event_time;
+                    if !valid {
+                        decrement(&mut degrees, *source);
+                        decrement(&mut degrees, *target);
+                    }
+                    valid
+                });
+
+                let batch = stash.remove(time.time()).unwrap_or_default();
+                let (appends, retractions): (Vec<_>, Vec<_>) = batch.into_iter().partition(|sgt| sgt.append);
+                // edges of a timestamp count towards the degrees before any of them is filtered
+                for sgt in appends.iter() {
+                    let key = (sgt.source, sgt.target, sgt.label.clone());
+                    match edges.get_mut(&key) {
+                        Some((end, _)) => *end = (*end).max(sgt.interval.end),
+                        None => {
+                            edges.insert(key, (sgt.interval.end, None));
+                            *degrees.entry(sgt.source).or_insert(0) += 1;
+                            *degrees.entry(sgt.target).or_insert(0) += 1;
+                        }
+                    }
+                }
+
+                let mut session = output.session(&time);
+                for mut sgt in appends {
+                    let within = [sgt.source, sgt.target].iter().all(|vertex| thresholds.contains(degrees.get(vertex).cloned().unwrap_or(0)));
+                    let label = match (&action, within) {
+                        (_, true) => Some(sgt.label.clone()),
+                        (DegreeAction::Drop, false) => None,
+                        (DegreeAction::Flag(suffix), false) => Some(format!("{}{}", sgt.label, suffix)),
+                    };
+                    if let Some((_, forwarded)) = edges.get_mut(&(sgt.source, sgt.target, sgt.label.clone())) {
+                        *forwarded = label.clone();
+                    }
+                    if let Some(label) = label {
+                        sgt.label = label;
+                        session.give(sgt);
+                    }
+                }
+
+                for mut sgt in retractions {
+                    if let Some((_, forwarded)) = edges.remove(&(sgt.source, sgt.target, sgt.label.clone())) {
+                        decrement(&mut degrees, sgt.source);
+                        decrement(&mut degrees, sgt.target);
+                        if let Some(label) = forwarded {
+                            sgt.label = label;
+                            session.give(sgt);
+                        }
+                    }
+                }
+            });
+        })
+    }
+}
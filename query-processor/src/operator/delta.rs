@@ -1,18 +1,102 @@
 
 
 use std::hash::BuildHasherDefault;
+use std::sync::{Arc, Mutex};
 
 use hashbrown::{HashMap, HashSet};
 use hashers::fx_hash::FxHasher;
-use log::trace;
+use log::{trace, warn};
+use once_cell::sync::Lazy;
 
 
 use crate::operator::MinPQIndex;
 use crate::operator::spanning_tree::SpanningTree;
-use crate::util::types::VertexType;
+use crate::util::types::{delta_threads, HalfOpenTimeInterval, VertexType};
 
 use self::super::super::util::types::VertexStatePair;
 
+/// spanning tree after expiry with its removed nodes
+pub type ExpiredTree = (SpanningTree, Vec<(VertexStatePair, HalfOpenTimeInterval)>);
+
+/// # of shards per thread of a `DeltaPool`, so that idle threads can steal shards from busy ones
+#[cfg(feature = "parallel-delta")]
+const SHARDS_PER_THREAD: usize = 4;
+
+// pool shared by the RPQ operators of all workers of the process, together with its # of threads
+static SHARED_POOL: Lazy<Mutex<Option<(usize, Arc<DeltaPool>)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Small thread pool that expires spanning trees in parallel during window-close bursts, see `set_delta_threads`
+/// Expired trees are partitioned into shards by the hash of their root vertex, and threads steal shards from each other,
+/// so that the operator remains single-threaded towards the dataflow while its expiry is parallel internally
+/// Expansion of trees by new edges is not parallel, it runs on the worker thread of the operator
+/// A single pool is shared by all operators of the process, so that the # of threads does not grow with operators and workers
+#[cfg_attr(not(feature = "parallel-delta"), allow(dead_code))]
+pub struct DeltaPool {
+    #[cfg(feature = "parallel-delta")]
+    pool: rayon::ThreadPool,
+    shards: usize,
+}
+
+impl DeltaPool {
+    /// returns the pool of the process with the configured # of threads, which is created by the first operator that asks for it,
+    /// or None if parallel expiry is not configured or the crate is built without the `parallel-delta` feature
+    pub fn from_config() -> Option<Arc<Self>> {
+        let threads = delta_threads()?;
+        let mut shared_pool = SHARED_POOL.lock().unwrap();
+        match &*shared_pool {
+            Some((pool_threads, pool)) if *pool_threads == threads => Some(pool.clone()),
+            _ => {
+                let pool = Arc::new(Self::with_threads(threads)?);
+                *shared_pool = Some((threads, pool.clone()));
+                Some(pool)
+            }
+        }
+    }
+
+    #[cfg(feature = "parallel-delta")]
+    fn with_threads(threads: usize) -> Option<Self> {
+        match rayon::ThreadPoolBuilder::new().num_threads(threads).thread_name(|index| format!("delta-{}", index)).build() {
+            Ok(pool) => Some(Self { pool, shards: threads * SHARDS_PER_THREAD }),
+            Err(e) => {
+                warn!("Spanning trees are expired sequentially as the delta pool cannot be created: {}", e);
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "parallel-delta"))]
+    fn with_threads(_threads: usize) -> Option<Self> {
+        warn!("Spanning trees are expired sequentially as the crate is built without the `parallel-delta` feature");
+        None
+    }
+
+    #[cfg(feature = "parallel-delta")]
+    fn expire(&self, trees: Vec<SpanningTree>, low_watermark: u64) -> Vec<ExpiredTree> {
+        use std::hash::{Hash, Hasher};
+
+        use rayon::prelude::*;
+
+        // partition trees into shards by the hash of their root vertex
+        let mut shards: Vec<Vec<SpanningTree>> = (0..self.shards).map(|_| Vec::new()).collect();
+        for tree in trees {
+            let mut hasher = FxHasher::default();
+            tree.get_root_vertex().hash(&mut hasher);
+            shards[(hasher.finish() % self.shards as u64) as usize].push(tree);
+        }
+
+        let expired_shards: Vec<Vec<ExpiredTree>> = self.pool.install(|| shards.into_par_iter()
+            .map(|shard| shard.into_iter().map(|tree| expire_tree(tree, low_watermark)).collect())
+            .collect());
+        expired_shards.into_iter().flatten().collect()
+    }
+}
+
+/// deletes expired nodes of the tree, and returns the tree with its removed nodes
+fn expire_tree(mut tree: SpanningTree, low_watermark: u64) -> ExpiredTree {
+    let removed_nodes = tree.expiry(low_watermark);
+    (tree, removed_nodes)
+}
+
 /// Implementation of Delta Index from PVLDB Submission
 /// It organizes a collection of spanning trees in a MinPQIndex based on
 /// the lowest expiry timestamp of modes in a given tree.
@@ -27,17 +111,13 @@ impl Delta {
         tree_queue.get(vertex).is_some()
     }
 
-    /// inserts a new spanning tree rooted at the given vertex
-    pub fn add_spanning_tree(node_index: &mut HashMap<VertexStatePair, HashSet<u64, BuildHasherDefault<FxHasher>>, BuildHasherDefault<FxHasher>>, tree_queue: &mut MinPQIndex<VertexType, SpanningTree>, vertex: u64) {
-        tree_queue.push(vertex, SpanningTree::new(vertex), u64::MAX);
+    /// inserts a new spanning tree that only has its root vertex
+    pub fn add_spanning_tree(node_index: &mut HashMap<VertexStatePair, HashSet<u64, BuildHasherDefault<FxHasher>>, BuildHasherDefault<FxHasher>>, tree_queue: &mut MinPQIndex<VertexType, SpanningTree>, tree: SpanningTree) {
+        let vertex = tree.get_root_vertex();
+        tree_queue.push(vertex, tree, u64::MAX);
         Delta::insert_into_node_index(node_index, vertex, 0, vertex);
     }
 
-    /// return a reference to the spanning tree rooted at the given vertex
-    pub fn get_tree<'a>(tree_queue: &'a MinPQIndex<VertexType, SpanningTree>, vertex: &VertexType) -> Option<&'a SpanningTree> {
-        tree_queue.get(vertex).map(|(entry, _)| entry)
-    }
-
     /// returns a mutable refernce to the spanning tree rooted at the given vertex
     pub fn get_tree_mut<'a>(tree_queue: &'a mut MinPQIndex<VertexType, SpanningTree>, vertex: &VertexType) -> Option<&'a mut SpanningTree> {
         tree_queue.get_mut(vertex).map(|(entry, _)| entry)
@@ -90,6 +170,16 @@ impl Delta {
         expired_trees.into_iter()
     }
 
+    /// deletes expired nodes of the given trees, in parallel by the pool if it is given, and returns each tree with its removed nodes
+    /// trees are returned in an arbitrary order if they are expired in parallel
+    pub fn expire_trees(trees: Vec<SpanningTree>, low_watermark: u64, pool: Option<&DeltaPool>) -> Vec<ExpiredTree> {
+        match pool {
+            #[cfg(feature = "parallel-delta")]
+            Some(pool) if trees.len() > 1 => pool.expire(trees, low_watermark),
+            _ => trees.into_iter().map(|tree| expire_tree(tree, low_watermark)).collect(),
+        }
+    }
+
     /// Uses the inverted index to look-up trees that contains the given vertex-state pair
     /// Returns an iterator of root vertices
     fn get_containing_trees(node_index: &HashMap<VertexStatePair, HashSet<u64, BuildHasherDefault<FxHasher>>, BuildHasherDefault<FxHasher>>, vertex: u64, state: u8) -> impl Iterator<Item=u64> + '_ {
@@ -113,3 +203,20 @@ impl Delta {
         }
     }
 }
+
+#[cfg(all(test, feature = "parallel-delta"))]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::operator::delta::DeltaPool;
+    use crate::util::types::set_delta_threads;
+
+    #[test]
+    fn operators_share_the_pool_of_the_process() {
+        set_delta_threads(Some(2));
+        let (first, second) = (DeltaPool::from_config().unwrap(), DeltaPool::from_config().unwrap());
+        set_delta_threads(None);
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(DeltaPool::from_config().is_none());
+    }
+}
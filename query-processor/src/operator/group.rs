@@ -0,0 +1,49 @@
+extern crate timely;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use timely::dataflow::{Scope, Stream};
+use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::operators::generic::operator::Operator;
+
+use crate::input::tuple::{GroupedResult, StreamingGraphTuple};
+use crate::util::types::{EventTime, HalfOpenTimeInterval, VertexType};
+
+/// Groups results of each window by their source, i.e., the root vertex of RPQ results, and emits a `GroupedResult`
+/// with the list of targets for each source, label and interval once the window closes, instead of one tuple for each result
+/// Results of the same tree that are derived at the same time share their interval, so that a group typically spans the results
+/// a root gains in a window. Retractions, i.e., tuples that are not appends, are grouped separately from appends
+/// Groups carry the id of the window, i.e., the event time of the completed timestamp, and are emitted in sorted order
+pub trait GroupBySource<G: Scope> where G::Timestamp: EventTime {
+    fn group_by_source(&self) -> Stream<G, GroupedResult>;
+}
+
+impl<G: Scope> GroupBySource<G> for Stream<G, StreamingGraphTuple> where G::Timestamp: EventTime {
+    fn group_by_source(&self) -> Stream<G, GroupedResult> {
+        let mut vector = Vec::new();
+
+        // stash to collect tuples of a window until progress notification
+        let mut stash: HashMap<G::Timestamp, Vec<StreamingGraphTuple>> = HashMap::new();
+
+        self.unary_notify(Pipeline, "GroupBySource", vec![], move |input, output, notificator| {
+            while let Some((time, data)) = input.next() {
+                data.swap(&mut vector);
+                stash.entry(time.time().clone()).or_insert_with(Vec::new).extend(vector.drain(..));
+                notificator.notify_at(time.retain());
+            }
+
+            notificator.for_each(|time, _, _| {
+                if let Some(batch) = stash.remove(time.time()) {
+                    let window_id = time.time().event_time();
+                    let mut groups: BTreeMap<(VertexType, String, HalfOpenTimeInterval, bool), BTreeSet<VertexType>> = BTreeMap::new();
+                    for sgt in batch {
+                        groups.entry((sgt.source, sgt.label, sgt.interval, sgt.append)).or_insert_with(BTreeSet::new).insert(sgt.target);
+                    }
+                    output.session(&time).give_iterator(groups.into_iter().map(|((source, label, interval, append), targets)| {
+                        GroupedResult { source, targets: targets.into_iter().collect(), label, interval, append, window_id }
+                    }));
+                }
+            });
+        })
+    }
+}
@@ -1,38 +1,76 @@
 extern crate timely;
 
 use std::cmp::{max, min};
-
-use std::hash::BuildHasherDefault;
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::hash::{BuildHasherDefault, Hash};
+use std::sync::Arc;
+use std::time::Duration;
 
 use hashbrown::HashMap;
 use hashers::fx_hash::FxHasher;
-use log::{trace};
+use log::{info, trace};
 
 use timely::Data;
 use timely::dataflow::{Scope, Stream};
 use timely::dataflow::operators::generic::operator::Operator;
+use timely::worker::AsWorker;
 
+use crate::graph::alphabet::Alphabet;
 use crate::input::{GraphEdge, SGT, StreamingGraphEdge};
-use crate::input::tuple::StreamingGraphTuple;
-use crate::operator::MinPQIndex;
-
-use self::super::super::util::types::{HalfOpenInterval, HalfOpenTimeInterval, VertexType};
+use crate::input::tuple::{Annotations, StreamingGraphTuple};
+use crate::operator::{MinPQIndex, SelfLoops};
+use crate::operator::bloom::KeyFilter;
+use crate::operator::pool::StashPool;
+use crate::query::parser::Endpoints;
+use crate::util::clock::{Clock, system_clock};
+use crate::util::gauges;
+
+use self::super::super::util::types::{EventTime, HalfOpenInterval, HalfOpenTimeInterval, is_deterministic_mode, JOIN_COMPACTION_PERIOD, join_bloom_filter, REPORTING_PERIOD_MILLISECONDS, VertexType};
 use self::timely::dataflow::channels::pact::Exchange;
 
 /// Symmetric hash join implementation based on the direct approach as described in PVLDB submssion
 /// It takes two streams of sgts as inputs and produces a stream of sgts as output
-pub trait SymmetricHashJoin<G: Scope<Timestamp=u64>, D: Data + SGT<HalfOpenTimeInterval, StreamingGraphEdge>> {
+pub trait SymmetricHashJoin<G: Scope, D: Data + SGT<HalfOpenTimeInterval, StreamingGraphEdge>> {
 
     /// joins two streams based on the `join_predicate` and projects the join result based on the `join_output`
     /// `join_predicate` controls the endpoints of sgts that will be used for join
     /// `join_output` controls the endpoints that will be prohect in the resulting sgts
     fn hash_join<>(&self, other: &Stream<G, StreamingGraphTuple>, join_predicate: HashJoinAttributePair, join_output: HashJoinAttributePair, output_label: String) -> Stream<G, StreamingGraphTuple>;
+    /// joins two streams as `hash_join`, where results from a vertex to itself are dropped or kept based on `self_loops`
+    fn hash_join_with_loops<>(&self, other: &Stream<G, StreamingGraphTuple>, join_predicate: HashJoinAttributePair, join_output: HashJoinAttributePair, output_label: String, self_loops: SelfLoops) -> Stream<G, StreamingGraphTuple>;
+    /// joins two streams as `hash_join_with_loops`, where results are labeled based on `result_label`, e.g., by the label of the lhs tuple,
+    /// and `output_label` names the gauges of the join
+    fn hash_join_with_label<>(&self, other: &Stream<G, StreamingGraphTuple>, join_predicate: HashJoinAttributePair, join_output: HashJoinAttributePair, output_label: String, self_loops: SelfLoops, result_label: JoinLabel) -> Stream<G, StreamingGraphTuple>;
+    /// joins two streams as `hash_join_with_label`, where only results with the constant endpoints of `endpoints` are emitted, e.g., of `<42> ?x`,
+    /// and tuples whose output attribute does not match the constant of their side are dropped before they are stashed
+    #[allow(clippy::too_many_arguments)]
+    fn hash_join_with_endpoints<>(&self, other: &Stream<G, StreamingGraphTuple>, join_predicate: HashJoinAttributePair, join_output: HashJoinAttributePair, output_label: String, self_loops: SelfLoops, result_label: JoinLabel, endpoints: Endpoints) -> Stream<G, StreamingGraphTuple>;
     /// joins two streams based on the entire tuple, i.e., (source, target) pairs
     fn hash_join_tuple<>(&self, other: &Stream<G, StreamingGraphTuple>, rhs_reverse: bool, output_reverse: bool, output_label: String) -> Stream<G, StreamingGraphTuple>;
+    /// joins two streams as `hash_join_tuple`, where results from a vertex to itself are dropped or kept based on `self_loops`
+    fn hash_join_tuple_with_loops<>(&self, other: &Stream<G, StreamingGraphTuple>, rhs_reverse: bool, output_reverse: bool, output_label: String, self_loops: SelfLoops) -> Stream<G, StreamingGraphTuple>;
+    /// joins two streams as `hash_join_tuple_with_loops`, where results are labeled based on `result_label`
+    fn hash_join_tuple_with_label<>(&self, other: &Stream<G, StreamingGraphTuple>, rhs_reverse: bool, output_reverse: bool, output_label: String, self_loops: SelfLoops, result_label: JoinLabel) -> Stream<G, StreamingGraphTuple>;
+    /// joins two streams as `hash_join_tuple_with_label`, where tuples whose output pair does not match `endpoints` are dropped before they are stashed
+    #[allow(clippy::too_many_arguments)]
+    fn hash_join_tuple_with_endpoints<>(&self, other: &Stream<G, StreamingGraphTuple>, rhs_reverse: bool, output_reverse: bool, output_label: String, self_loops: SelfLoops, result_label: JoinLabel, endpoints: Endpoints) -> Stream<G, StreamingGraphTuple>;
 }
 
-impl<G: Scope<Timestamp=u64>> SymmetricHashJoin<G, StreamingGraphTuple> for Stream<G, StreamingGraphTuple> {
+impl<G: Scope> SymmetricHashJoin<G, StreamingGraphTuple> for Stream<G, StreamingGraphTuple> where G::Timestamp: EventTime {
     fn hash_join(&self, other: &Stream<G, StreamingGraphTuple>, join_predicate: HashJoinAttributePair, join_output: HashJoinAttributePair, output_label: String) -> Stream<G, StreamingGraphTuple> {
+        self.hash_join_with_loops(other, join_predicate, join_output, output_label, SelfLoops::Keep)
+    }
+
+    fn hash_join_with_loops(&self, other: &Stream<G, StreamingGraphTuple>, join_predicate: HashJoinAttributePair, join_output: HashJoinAttributePair, output_label: String, self_loops: SelfLoops) -> Stream<G, StreamingGraphTuple> {
+        self.hash_join_with_label(other, join_predicate, join_output, output_label, self_loops, JoinLabel::Output)
+    }
+
+    fn hash_join_with_label(&self, other: &Stream<G, StreamingGraphTuple>, join_predicate: HashJoinAttributePair, join_output: HashJoinAttributePair, output_label: String, self_loops: SelfLoops, result_label: JoinLabel) -> Stream<G, StreamingGraphTuple> {
+        self.hash_join_with_endpoints(other, join_predicate, join_output, output_label, self_loops, result_label, Endpoints::default())
+    }
+
+    fn hash_join_with_endpoints(&self, other: &Stream<G, StreamingGraphTuple>, join_predicate: HashJoinAttributePair, join_output: HashJoinAttributePair, output_label: String, self_loops: SelfLoops, result_label: JoinLabel, endpoints: Endpoints) -> Stream<G, StreamingGraphTuple> {
         let mut vector = Vec::new();
 
         let (key_selector1, key_selector2) = get_key_selector(&join_predicate);
@@ -44,66 +82,114 @@ impl<G: Scope<Timestamp=u64>> SymmetricHashJoin<G, StreamingGraphTuple> for Stre
         let exchange_source = Exchange::new(move |x: &StreamingGraphTuple| exchange_selector1(x));
         let exchange_target = Exchange::new(move |x: &StreamingGraphTuple| exchange_selector2(x));
 
+        // process stashed tuples in a sorted order for reproducible runs
+        let deterministic = is_deterministic_mode();
+        // false-positive rate of the filters that guard probes of join indexes, if they are enabled
+        let filter_rate = join_bloom_filter();
+        // labels of input tuples are kept in join indexes only if results are labeled by them
+        let keep_labels = result_label.keeps_input_labels();
+
+        // name of the state-size gauge of this operator instance
+        let index_gauge = format!("{}.join-keys.{}", output_label, self.scope().index());
+        let batch_gauge = format!("{}.join-batch-size.{}", output_label, self.scope().index());
+        let mut input_lag1 = gauges::InputLag::new(&format!("{}.input1", output_label), self.scope().index());
+        let mut input_lag2 = gauges::InputLag::new(&format!("{}.input2", output_label), self.scope().index());
+
         self.binary_frontier(other, exchange_source, exchange_target, "SymmetricHashJoin", move |_capability, _info| {
             // construct operator state
 
-            // stash incoming input, key is a pair of (join_attribute, output_attribute) and value is the expiry timestamp
+            // stash incoming input, key is a triple of (join_attribute, output_attribute, label id) and value is the interval and the annotations of the tuple
             // priority is the start timestamp
-            let mut stash1 = HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default());
-            let mut stash2 = HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default());
+            // stash is ordered by time so that completed times are drained in order without scanning all entries
+            let mut stash1 = BTreeMap::new();
+            let mut stash2 = BTreeMap::new();
+            // stash maps and buffers of drained tuples are recycled across times instead of being allocated for each of them
+            let mut stash_pool: StashPool<HashMap<_, (HalfOpenTimeInterval, Annotations), BuildHasherDefault<FxHasher>>> = StashPool::new();
+            let mut buffer_pool = StashPool::new();
+            // ids of labels of input tuples if results are labeled by them, so that tuples of the same endpoints with different labels
+            // are kept apart, and id 0 otherwise
+            let mut labels = Alphabet::from_labels(std::iter::once(""));
 
             // use a single source of truth. PQ enables look-up by keys with a custom key type
-            let mut index1: MinPQIndex<VertexType, MinPQIndex<VertexType, u64>> = MinPQIndex::default();
-            let mut index2: MinPQIndex<VertexType, MinPQIndex<VertexType, u64>> = MinPQIndex::default();
+            let mut index1: MinPQIndex<VertexType, MinPQIndex<LabeledVertex, JoinValue>> = MinPQIndex::default();
+            let mut index2: MinPQIndex<VertexType, MinPQIndex<LabeledVertex, JoinValue>> = MinPQIndex::default();
+
+            // filters over the keys of index1 and index2, respectively
+            let mut filter1 = filter_rate.map(KeyFilter::new);
+            let mut filter2 = filter_rate.map(KeyFilter::new);
+
+            let mut expired_keys = Vec::<(u64, MinPQIndex<LabeledVertex, JoinValue>)>::new();
 
-            let mut expired_keys = Vec::<(u64, MinPQIndex<VertexType, u64>)>::new();
+            // number of invocations since the last compaction of join indexes
+            let mut invocations_since_compaction = 0;
+
+            // buffer of results that are emitted as a batch, and # of emitted results and batches of the reporting period
+            let mut results = Vec::new();
+            let mut batches = BatchStats::new(system_clock());
+
+            // whether the final state is reported after both input frontiers become empty, i.e., at the end of stream
+            let mut end_of_stream = false;
 
             // finally create the closure to perform computation
             move |input1, input2, output| {
                 // stash incoming tuples from both streams
                 input1.for_each(|time, data| {
                     data.swap(&mut vector);
-                    let time_index = stash1.entry(time.retain()).or_insert(HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default()));
+                    input_lag1.observe(time.time().event_time());
+                    let (_, time_index) = stash1.entry(time.time().clone()).or_insert_with(|| (time.retain(), stash_pool.take()));
                     //stash incoming tuple
                     for sgt1 in vector.drain(..) {
                         let tuple_key = key_selector1(&sgt1);
                         let inner_value = output_selector1(&sgt1);
+                        // the output attribute of input 1 is the source of its results
+                        if !endpoints.matches_source(inner_value) {
+                            continue;
+                        }
                         let tuple_expiry = sgt1.interval.end;
                         trace!("Sgt {:?} at input 1", sgt1);
+                        let label1 = if keep_labels { labels.get_or_insert(&sgt1.label).expect("Label ids are not frozen") } else { 0 };
 
                         // simply stash the tuple and move on
-                        time_index.entry((tuple_key, inner_value)).and_modify(|current_interval: &mut HalfOpenTimeInterval| {
+                        time_index.entry((tuple_key, inner_value, label1)).and_modify(|(current_interval, current_annotations): &mut (HalfOpenTimeInterval, Annotations)| {
                             // maintain the larger expiry per tuple
                             if current_interval.get_end() < tuple_expiry {
                                 *current_interval = sgt1.interval;
+                                *current_annotations = sgt1.annotations();
                             }
-                        }).or_insert(sgt1.interval);
+                        }).or_insert_with(|| (sgt1.interval, sgt1.annotations()));
                     }
                 });
 
                 input2.for_each(|time, data| {
                     data.swap(&mut vector);
-                    let time_index = stash2.entry(time.retain()).or_insert(HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default()));
+                    input_lag2.observe(time.time().event_time());
+                    let (_, time_index) = stash2.entry(time.time().clone()).or_insert_with(|| (time.retain(), stash_pool.take()));
                     //stash incoming tuple
                     for sgt2 in vector.drain(..) {
                         let tuple_key = key_selector2(&sgt2);
                         let inner_value = output_selector2(&sgt2);
+                        // the output attribute of input 2 is the target of its results
+                        if !endpoints.matches_target(inner_value) {
+                            continue;
+                        }
                         let tuple_expiry = sgt2.interval.end;
                         trace!("Sgt {:?} at input 2", sgt2);
+                        let label2 = if keep_labels { labels.get_or_insert(&sgt2.label).expect("Label ids are not frozen") } else { 0 };
 
                         // simply stash the tuple and move on
-                        time_index.entry((tuple_key, inner_value)).and_modify(|current_interval: &mut HalfOpenTimeInterval| {
+                        time_index.entry((tuple_key, inner_value, label2)).and_modify(|(current_interval, current_annotations): &mut (HalfOpenTimeInterval, Annotations)| {
                             if current_interval.get_end() < tuple_expiry {
                                 *current_interval = sgt2.interval;
+                                *current_annotations = sgt2.annotations();
                             }
-                        }).or_insert(sgt2.interval);
+                        }).or_insert_with(|| (sgt2.interval, sgt2.annotations()));
                     }
                 });
 
                 // purge elements from the index2 based on input 1 frontier
                 // pop expired keys from index2
                 while let Some((_, _, expiry_ts)) = index2.peek() {
-                    if input1.frontier().less_equal(&expiry_ts) {
+                    if input1.frontier().less_equal(&G::Timestamp::upper_bound(expiry_ts)) {
                         break;
                     }
                     // otherwise pop the element from the state as it has expired entries
@@ -116,7 +202,7 @@ impl<G: Scope<Timestamp=u64>> SymmetricHashJoin<G, StreamingGraphTuple> for Stre
                     let mut min_valid_timestamp = u64::MAX;
                     // remove all entries that are expired
                     while let Some((_key, _, expiry_ts)) = expired_entry.peek() {
-                        if input1.frontier().less_equal(&expiry_ts) {
+                        if input1.frontier().less_equal(&G::Timestamp::upper_bound(expiry_ts)) {
                             min_valid_timestamp = expiry_ts;
                             break;
                         }
@@ -132,7 +218,7 @@ impl<G: Scope<Timestamp=u64>> SymmetricHashJoin<G, StreamingGraphTuple> for Stre
 
                 // purge elements from the index1 based on input2 frontier
                 while let Some((_, _, expiry_ts)) = index1.peek() {
-                    if input2.frontier().less_equal(&expiry_ts) {
+                    if input2.frontier().less_equal(&G::Timestamp::upper_bound(expiry_ts)) {
                         break;
                     }
                     // otherwise pop the element from the state as it has expired entries
@@ -144,7 +230,7 @@ impl<G: Scope<Timestamp=u64>> SymmetricHashJoin<G, StreamingGraphTuple> for Stre
                     let mut min_valid_timestamp = u64::MAX;
                     // remove all entries that are expired
                     while let Some((_key, _, expiry_ts)) = expired_entry.peek() {
-                        if input2.frontier().less_equal(&expiry_ts) {
+                        if input2.frontier().less_equal(&G::Timestamp::upper_bound(expiry_ts)) {
                             min_valid_timestamp = expiry_ts;
                             break;
                         }
@@ -158,123 +244,167 @@ impl<G: Scope<Timestamp=u64>> SymmetricHashJoin<G, StreamingGraphTuple> for Stre
                     }
                 }
 
+                // periodically compact join indexes so that inner values expired by the frontier are dropped
+                // and priorities of outer keys reflect the actual min expiry of their inner index
+                invocations_since_compaction += 1;
+                if invocations_since_compaction >= JOIN_COMPACTION_PERIOD {
+                    invocations_since_compaction = 0;
+                    compact_join_index(&mut index1, |expiry_ts| input2.frontier().less_equal(&G::Timestamp::upper_bound(expiry_ts)));
+                    compact_join_index(&mut index2, |expiry_ts| input1.frontier().less_equal(&G::Timestamp::upper_bound(expiry_ts)));
+                }
+
                 // finally safely perform join for items in the stash
                 // all expired tuples are purged from the state, and join will only consider tuples that can safely be extracted from the stash, i.e.,
                 // all input for that particular time has arrived (as the frontier guarentees) and we are keeping the one with max interval
 
                 // consider sending everything in `stash1`.
-                for (time, tuples) in stash1.iter_mut() {
-                    // if input1 cannot produce data at `time`, process the stash
-                    if !input1.frontier().less_equal(time.time()) {
-                        let mut session = output.session(&time);
-                        // update index1 and perform join
-                        for ((join_key, join_attribute1), tuple_interval1) in tuples.drain() {
-                            let start_ts1: u64 = tuple_interval1.get_start();
-                            let expiry_ts1: u64 = tuple_interval1.get_end();
-                            let mut has_larger_expiry: bool = true;
-
-                            // place tuples into the index1
-                            if let Some((inner_index, _b)) = index1.get_mut(&join_key) {
-                                // check whether same value already exists with a larger timestamp
-                                if let Some((_start_ts, current_expiry_ts)) = inner_index.get(&join_attribute1) {
-                                    // if value has already larger expiry ts, do not process
-                                    if current_expiry_ts >= expiry_ts1 {
-                                        // set the has_largeR_expiry flag to signal join will NOT process a new result with a larger expiry
-                                        has_larger_expiry = false;
-                                    } else {
-                                        // update the entry with larger expiry
-                                        inner_index.push(join_attribute1, start_ts1, expiry_ts1);
-                                    }
+                while let Some(time) = stash1.keys().next().cloned() {
+                    // if input1 can still produce data at `time`, later times in the stash are not complete either
+                    if input1.frontier().less_equal(&time) {
+                        break;
+                    }
+                    let (capability, mut tuples) = stash1.remove(&time).unwrap();
+                    let mut stashed_tuples: Vec<_> = buffer_pool.take();
+                    stashed_tuples.extend(tuples.drain());
+                    stash_pool.recycle(tuples);
+                    if deterministic {
+                        stashed_tuples.sort();
+                    }
+                    let mut session = output.session(&capability);
+                    // update index1 and perform join
+                    for ((join_key, join_attribute1, label1), (tuple_interval1, annotations1)) in stashed_tuples.drain(..) {
+                        let start_ts1: u64 = tuple_interval1.get_start();
+                        let expiry_ts1: u64 = tuple_interval1.get_end();
+                        let mut has_larger_expiry: bool = true;
+
+                        // place tuples into the index1
+                        if let Some((inner_index, _b)) = index1.get_mut(&join_key) {
+                            // check whether same value already exists with a larger timestamp
+                            if let Some((_start_ts, current_expiry_ts)) = inner_index.get(&(join_attribute1, label1)) {
+                                // if value has already larger expiry ts, do not process
+                                if current_expiry_ts >= expiry_ts1 {
+                                    // set the has_largeR_expiry flag to signal join will NOT process a new result with a larger expiry
+                                    has_larger_expiry = false;
                                 } else {
-                                    // it does not exist, push new value
-                                    inner_index.push(join_attribute1, start_ts1, expiry_ts1);
+                                    // update the entry with larger expiry
+                                    inner_index.push((join_attribute1, label1), JoinValue::new(start_ts1, &annotations1), expiry_ts1);
                                 }
                             } else {
-                                let mut new_inner_index = MinPQIndex::default();
-                                new_inner_index.push(join_attribute1, start_ts1, expiry_ts1);
-                                index1.push(join_key, new_inner_index, expiry_ts1);
+                                // it does not exist, push new value
+                                inner_index.push((join_attribute1, label1), JoinValue::new(start_ts1, &annotations1), expiry_ts1);
                             }
-                            // decrease priority in index 1
-                            index1.try_decrease_priority(&join_key, expiry_ts1);
-
-                            // perform join if incoming tuple is new or has larger expiry
-                            if has_larger_expiry {
-                                if let Some((inner_index, _)) = index2.get(&join_key) {
-                                    for (join_attribute2, start_ts2, expiry_ts2) in inner_index.iter() {
-                                        session.give(
-                                            StreamingGraphTuple::new(
-                                                join_attribute1,
-                                                join_attribute2,
-                                                output_label.clone(),
-                                                HalfOpenTimeInterval::new(max(start_ts1, *start_ts2), min(expiry_ts1, expiry_ts2)),
-                                            )
-                                        );
-                                    }
-                                }
+                        } else {
+                            let mut new_inner_index = MinPQIndex::default();
+                            new_inner_index.push((join_attribute1, label1), JoinValue::new(start_ts1, &annotations1), expiry_ts1);
+                            index1.push(join_key, new_inner_index, expiry_ts1);
+                            insert_key(&mut filter1, &join_key);
+                        }
+                        // decrease priority in index 1
+                        index1.try_decrease_priority(&join_key, expiry_ts1);
+
+                        // perform join if incoming tuple is new or has larger expiry
+                        if has_larger_expiry && may_contain(&filter2, &join_key) {
+                            if let Some((inner_index, _)) = index2.get(&join_key) {
+                                results.extend(inner_index.iter().filter(|((join_attribute2, _), _, _)| self_loops.retains(join_attribute1, *join_attribute2)).map(|((join_attribute2, label2), value2, expiry_ts2)| {
+                                    StreamingGraphTuple::new(
+                                        join_attribute1,
+                                        join_attribute2,
+                                        result_label.format(&output_label, labels.resolve(label1), labels.resolve(label2)),
+                                        HalfOpenTimeInterval::new(max(start_ts1, value2.start_ts), min(expiry_ts1, expiry_ts2)),
+                                    ).with_annotations(annotations1.merge(&value2.annotations))
+                                }));
+                                // emit results of the key as a single batch
+                                batches.record(results.len());
+                                session.give_vec(&mut results);
                             }
                         }
                     }
+                    buffer_pool.recycle(stashed_tuples);
                 }
 
-                // discard `time` entries with empty `list`.
-                stash1.retain(|_time, list| list.len() > 0);
-
                 // consider sending everything in `stash2`.
-                for (time, tuples) in stash2.iter_mut() {
-                    // if input2 cannot produce data at `time`, process the stash
-                    if !input2.frontier().less_equal(time.time()) {
-                        let mut session = output.session(&time);
-                        // update index1 and perform join
-                        for ((join_key, join_attribute2), tuple_interval2) in tuples.drain() {
-                            let start_ts2: u64 = tuple_interval2.get_start();
-                            let expiry_ts2: u64 = tuple_interval2.get_end();
-                            let mut has_larger_expiry: bool = true;
-
-                            // place tuples into the index2
-                            if let Some((inner_index, _b)) = index2.get_mut(&join_key) {
-                                // check whether same value already exists with a larger timestamp
-                                if let Some((_start_ts, current_expiry_ts)) = inner_index.get(&join_attribute2) {
-                                    // if value has already larger expiry ts, do not process
-                                    if current_expiry_ts >= expiry_ts2 {
-                                        // set the has_largeR_expiry flag to signal join will process a new result with a larger expiry
-                                        has_larger_expiry = false;
-                                    } else {
-                                        // update the entry with larger expiry
-                                        inner_index.push(join_attribute2, start_ts2, expiry_ts2);
-                                    }
+                while let Some(time) = stash2.keys().next().cloned() {
+                    // if input2 can still produce data at `time`, later times in the stash are not complete either
+                    if input2.frontier().less_equal(&time) {
+                        break;
+                    }
+                    let (capability, mut tuples) = stash2.remove(&time).unwrap();
+                    let mut stashed_tuples: Vec<_> = buffer_pool.take();
+                    stashed_tuples.extend(tuples.drain());
+                    stash_pool.recycle(tuples);
+                    if deterministic {
+                        stashed_tuples.sort();
+                    }
+                    let mut session = output.session(&capability);
+                    // update index1 and perform join
+                    for ((join_key, join_attribute2, label2), (tuple_interval2, annotations2)) in stashed_tuples.drain(..) {
+                        let start_ts2: u64 = tuple_interval2.get_start();
+                        let expiry_ts2: u64 = tuple_interval2.get_end();
+                        let mut has_larger_expiry: bool = true;
+
+                        // place tuples into the index2
+                        if let Some((inner_index, _b)) = index2.get_mut(&join_key) {
+                            // check whether same value already exists with a larger timestamp
+                            if let Some((_start_ts, current_expiry_ts)) = inner_index.get(&(join_attribute2, label2)) {
+                                // if value has already larger expiry ts, do not process
+                                if current_expiry_ts >= expiry_ts2 {
+                                    // set the has_largeR_expiry flag to signal join will process a new result with a larger expiry
+                                    has_larger_expiry = false;
                                 } else {
-                                    // it does not exist, push new value
-                                    inner_index.push(join_attribute2, start_ts2, expiry_ts2);
+                                    // update the entry with larger expiry
+                                    inner_index.push((join_attribute2, label2), JoinValue::new(start_ts2, &annotations2), expiry_ts2);
                                 }
                             } else {
-                                let mut new_inner_index = MinPQIndex::default();
-                                new_inner_index.push(join_attribute2, start_ts2, expiry_ts2);
-                                index2.push(join_key, new_inner_index, expiry_ts2);
+                                // it does not exist, push new value
+                                inner_index.push((join_attribute2, label2), JoinValue::new(start_ts2, &annotations2), expiry_ts2);
                             }
-                            // decrease priority in index 1
-                            index2.try_decrease_priority(&join_key, expiry_ts2);
-
-                            // perform join if incoming tuple is new or has larger expiry
-                            if has_larger_expiry {
-                                if let Some((inner_index, _)) = index1.get(&join_key) {
-                                    for (join_attribute1, start_ts1, expiry_ts1) in inner_index.iter() {
-                                        session.give(
-                                            StreamingGraphTuple::new(
-                                                join_attribute1,
-                                                join_attribute2,
-                                                output_label.clone(),
-                                                HalfOpenTimeInterval::new(max(*start_ts1, start_ts2), min(expiry_ts1, expiry_ts2)),
-                                            )
-                                        );
-                                    }
-                                }
+                        } else {
+                            let mut new_inner_index = MinPQIndex::default();
+                            new_inner_index.push((join_attribute2, label2), JoinValue::new(start_ts2, &annotations2), expiry_ts2);
+                            index2.push(join_key, new_inner_index, expiry_ts2);
+                            insert_key(&mut filter2, &join_key);
+                        }
+                        // decrease priority in index 1
+                        index2.try_decrease_priority(&join_key, expiry_ts2);
+
+                        // perform join if incoming tuple is new or has larger expiry
+                        if has_larger_expiry && may_contain(&filter1, &join_key) {
+                            if let Some((inner_index, _)) = index1.get(&join_key) {
+                                results.extend(inner_index.iter().filter(|((join_attribute1, _), _, _)| self_loops.retains(*join_attribute1, join_attribute2)).map(|((join_attribute1, label1), value1, expiry_ts1)| {
+                                    StreamingGraphTuple::new(
+                                        join_attribute1,
+                                        join_attribute2,
+                                        result_label.format(&output_label, labels.resolve(label1), labels.resolve(label2)),
+                                        HalfOpenTimeInterval::new(max(value1.start_ts, start_ts2), min(expiry_ts1, expiry_ts2)),
+                                    ).with_annotations(value1.annotations.merge(&annotations2))
+                                }));
+                                // emit results of the key as a single batch
+                                batches.record(results.len());
+                                session.give_vec(&mut results);
                             }
                         }
                     }
+                    buffer_pool.recycle(stashed_tuples);
+                }
+
+                // drop expired keys from the filters once they exceed their capacity
+                refresh_filter(&mut filter1, &index1);
+                refresh_filter(&mut filter2, &index2);
+
+                if gauges::is_enabled() {
+                    gauges::set(index_gauge.clone(), (index1.len() + index2.len()) as u64);
+                    if let Some(average) = batches.period_average() {
+                        gauges::set(batch_gauge.clone(), average);
+                    }
+                    input_lag1.record(input1.frontier().frontier().iter().map(|time| time.event_time()).min(), stash1.values().map(|(_, tuples)| tuples.len()).sum());
+                    input_lag2.record(input2.frontier().frontier().iter().map(|time| time.event_time()).min(), stash2.values().map(|(_, tuples)| tuples.len()).sum());
                 }
 
-                // discard `time` entries with empty `list`.
-                stash2.retain(|_time, list| list.len() > 0);
+                // report the final state size once both inputs are closed
+                if !end_of_stream && input1.frontier().frontier().is_empty() && input2.frontier().frontier().is_empty() {
+                    end_of_stream = true;
+                    info!("{} reached the end of stream with {} and {} keys in join indexes", output_label, index1.len(), index2.len());
+                }
             }
         })
     }
@@ -282,8 +412,22 @@ impl<G: Scope<Timestamp=u64>> SymmetricHashJoin<G, StreamingGraphTuple> for Stre
     // rhs_reverse controls whether sgts in the second input should be reversed, i.e., (trg, src) instead of (src, trg)
     // output_reverse controls the order of enpoints in resulting sgts
     fn hash_join_tuple<>(&self, other: &Stream<G, StreamingGraphTuple>, rhs_reverse: bool, output_reverse: bool, output_label: String) -> Stream<G, StreamingGraphTuple> {
+        self.hash_join_tuple_with_loops(other, rhs_reverse, output_reverse, output_label, SelfLoops::Keep)
+    }
+
+    fn hash_join_tuple_with_loops<>(&self, other: &Stream<G, StreamingGraphTuple>, rhs_reverse: bool, output_reverse: bool, output_label: String, self_loops: SelfLoops) -> Stream<G, StreamingGraphTuple> {
+        self.hash_join_tuple_with_label(other, rhs_reverse, output_reverse, output_label, self_loops, JoinLabel::Output)
+    }
+
+    fn hash_join_tuple_with_label<>(&self, other: &Stream<G, StreamingGraphTuple>, rhs_reverse: bool, output_reverse: bool, output_label: String, self_loops: SelfLoops, result_label: JoinLabel) -> Stream<G, StreamingGraphTuple> {
+        self.hash_join_tuple_with_endpoints(other, rhs_reverse, output_reverse, output_label, self_loops, result_label, Endpoints::default())
+    }
+
+    fn hash_join_tuple_with_endpoints<>(&self, other: &Stream<G, StreamingGraphTuple>, rhs_reverse: bool, output_reverse: bool, output_label: String, self_loops: SelfLoops, result_label: JoinLabel, endpoints: Endpoints) -> Stream<G, StreamingGraphTuple> {
         // tuple to be stored as the join state
         type JoinKey = (VertexType, VertexType);
+        // tuple and the id of its label, which tells tuples with different labels apart if results are labeled by them
+        type LabeledKey = (JoinKey, usize);
 
         let mut vector = Vec::new();
 
@@ -314,71 +458,115 @@ impl<G: Scope<Timestamp=u64>> SymmetricHashJoin<G, StreamingGraphTuple> for Stre
         let exchange_source = Exchange::new(move |x: &StreamingGraphTuple| exchange_selector1(x).0);
         let exchange_target = Exchange::new(move |x: &StreamingGraphTuple| exchange_selector2(x).0);
 
+        // process stashed tuples in a sorted order for reproducible runs
+        let deterministic = is_deterministic_mode();
+        // false-positive rate of the filters that guard probes of join indexes, if they are enabled
+        let filter_rate = join_bloom_filter();
+        // labels of input tuples are kept in join indexes only if results are labeled by them
+        let keep_labels = result_label.keeps_input_labels();
+
+        // name of the state-size gauge of this operator instance
+        let index_gauge = format!("{}.join-keys.{}", output_label, self.scope().index());
+        let batch_gauge = format!("{}.join-batch-size.{}", output_label, self.scope().index());
+        let mut input_lag1 = gauges::InputLag::new(&format!("{}.input1", output_label), self.scope().index());
+        let mut input_lag2 = gauges::InputLag::new(&format!("{}.input2", output_label), self.scope().index());
+
         self.binary_frontier(other, exchange_source, exchange_target, "SymmetricHashJoinTuple", move |_capability, _info| {
             // construct operator state
 
-            // stash incoming input, key is a pair of (join_attribute, output_attribute) and value is the expiry timestamp
+            // stash incoming input, key is a triple of (join_attribute, output_attribute, label id) and value is the interval and the annotations of the tuple
             // priority is the start timestamp
-            let mut stash1 = HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default());
-            let mut stash2 = HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default());
+            // stash is ordered by time so that completed times are drained in order without scanning all entries
+            let mut stash1 = BTreeMap::new();
+            let mut stash2 = BTreeMap::new();
+            // stash maps and buffers of drained tuples are recycled across times instead of being allocated for each of them
+            let mut stash_pool: StashPool<HashMap<_, (HalfOpenTimeInterval, Annotations), BuildHasherDefault<FxHasher>>> = StashPool::new();
+            let mut buffer_pool = StashPool::new();
+            // ids of labels of input tuples if results are labeled by them, so that tuples of the same endpoints with different labels
+            // are kept apart, and id 0 otherwise
+            let mut labels = Alphabet::from_labels(std::iter::once(""));
 
             // use a single source of truth. PQ enables look-up by keys with a custom key type
-            // in this key, for every join key, we store its start_ts as value and its expiry is the priority in the MinPQIndex
-            let mut index1: MinPQIndex<JoinKey, u64> = MinPQIndex::<JoinKey, u64>::default();
-            let mut index2: MinPQIndex<JoinKey, u64> = MinPQIndex::<JoinKey, u64>::default();
+            // in this key, for every join key, we store its start_ts and annotations as value and its expiry is the priority in the MinPQIndex
+            let mut index1: MinPQIndex<LabeledKey, JoinValue> = MinPQIndex::<LabeledKey, JoinValue>::default();
+            let mut index2: MinPQIndex<LabeledKey, JoinValue> = MinPQIndex::<LabeledKey, JoinValue>::default();
+
+            // filters over the keys of index1 and index2, respectively
+            let mut filter1 = filter_rate.map(KeyFilter::new);
+            let mut filter2 = filter_rate.map(KeyFilter::new);
+
+            // buffer of results that are emitted as a batch, and # of emitted results and batches of the reporting period
+            let mut results = Vec::new();
+            let mut batches = BatchStats::new(system_clock());
+
+            // whether the final state is reported after both input frontiers become empty, i.e., at the end of stream
+            let mut end_of_stream = false;
 
             // finally create the closure to perform computation
             move |input1, input2, output| {
                 // stash incoming tuples from both streams
                 input1.for_each(|time, data| {
                     data.swap(&mut vector);
-                    let time_index = stash1.entry(time.retain()).or_insert(HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default()));
+                    input_lag1.observe(time.time().event_time());
+                    let (_, time_index) = stash1.entry(time.time().clone()).or_insert_with(|| (time.retain(), stash_pool.take()));
 
                     //stash incoming tuples
                     for sgt1 in vector.drain(..) {
                         let tuple_key = key_selector1(&sgt1);
                         let inner_value = output_selector1(&sgt1);
+                        // the output pair of a tuple is the pair of its results
+                        if !endpoints.matches(inner_value.0, inner_value.1) {
+                            continue;
+                        }
                         let tuple_expiry = sgt1.interval.get_end();
                         trace!("SGT {:?} at input 1", sgt1);
+                        let label1 = if keep_labels { labels.get_or_insert(&sgt1.label).expect("Label ids are not frozen") } else { 0 };
 
                         // sinply stash the tuple and move in
-                        time_index.entry((tuple_key, inner_value))
-                            .and_modify(|current_interval: &mut HalfOpenTimeInterval| {
+                        time_index.entry((tuple_key, inner_value, label1))
+                            .and_modify(|(current_interval, current_annotations): &mut (HalfOpenTimeInterval, Annotations)| {
                                 // maintain the larger expiry per tuple
                                 if current_interval.get_end() < tuple_expiry {
-                                    *current_interval = sgt1.interval
+                                    *current_interval = sgt1.interval;
+                                    *current_annotations = sgt1.annotations();
                                 }
                             })
-                            .or_insert(sgt1.interval);
+                            .or_insert_with(|| (sgt1.interval, sgt1.annotations()));
                     }
                 });
 
                 input2.for_each(|time, data| {
                     data.swap(&mut vector);
-                    let time_index = stash2.entry(time.retain()).or_insert(HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default()));
+                    input_lag2.observe(time.time().event_time());
+                    let (_, time_index) = stash2.entry(time.time().clone()).or_insert_with(|| (time.retain(), stash_pool.take()));
 
                     //stash incoming tuples
                     for sgt2 in vector.drain(..) {
                         let tuple_key = key_selector2(&sgt2);
                         let inner_value = output_selector2(&sgt2);
+                        if !endpoints.matches(inner_value.0, inner_value.1) {
+                            continue;
+                        }
                         let tuple_expiry = sgt2.interval.get_end();
                         trace!("SGT {:?} at input 2", sgt2);
+                        let label2 = if keep_labels { labels.get_or_insert(&sgt2.label).expect("Label ids are not frozen") } else { 0 };
 
                         // sinply stash the tuple and move in
-                        time_index.entry((tuple_key, inner_value))
-                            .and_modify(|current_interval: &mut HalfOpenTimeInterval| {
+                        time_index.entry((tuple_key, inner_value, label2))
+                            .and_modify(|(current_interval, current_annotations): &mut (HalfOpenTimeInterval, Annotations)| {
                                 // maintain the larger expiry per tuple
                                 if current_interval.get_end() < tuple_expiry {
-                                    *current_interval = sgt2.interval
+                                    *current_interval = sgt2.interval;
+                                    *current_annotations = sgt2.annotations();
                                 }
                             })
-                            .or_insert(sgt2.interval);
+                            .or_insert_with(|| (sgt2.interval, sgt2.annotations()));
                     }
                 });
 
                 // purge elements from the index2 based on input 1 frontier
                 while let Some((_, _, expiry_ts)) = index2.peek() {
-                    if input1.frontier().less_equal(&expiry_ts) {
+                    if input1.frontier().less_equal(&G::Timestamp::upper_bound(expiry_ts)) {
                         break;
                     }
                     // otherwise pop the element from the state as its expiry has passed
@@ -387,7 +575,7 @@ impl<G: Scope<Timestamp=u64>> SymmetricHashJoin<G, StreamingGraphTuple> for Stre
 
                 // purge elements from the index1 based on input 2 frontier
                 while let Some((_, _, expiry_ts)) = index1.peek() {
-                    if input2.frontier().less_equal(&expiry_ts) {
+                    if input2.frontier().less_equal(&G::Timestamp::upper_bound(expiry_ts)) {
                         break;
                     }
                     // otherwise pop the element from the state as its expiry has passed
@@ -399,101 +587,211 @@ impl<G: Scope<Timestamp=u64>> SymmetricHashJoin<G, StreamingGraphTuple> for Stre
                 // all input for that particular time has arrived (as the frontier guarentees) and we are keeping the one with max interval
 
                 // consider processing tuples whose time has been completed based on the frontier in stash1
-                for (time, tuples) in stash1.iter_mut() {
-                    // if input1 cannot produce data at `time`, process the stash
-                    if !input1.frontier().less_equal(time.time()) {
-                        let mut session = output.session(&time);
-                        // update index 1, then perform the join
-                        for ((join_key, join_value), tuple_interval1) in tuples.drain() {
-                            let start_ts1 = tuple_interval1.get_start();
-                            let expiry_ts1 = tuple_interval1.get_end();
-                            let mut has_larger_expiry = true;
-
-                            // place tuples int the index1
-                            // check whether there is already an entry for the same key
-                            if let Some((_start_ts, current_expiry_ts)) = index1.get(&join_key) {
-                                // check whether existing entry already has larger expiry
-                                if current_expiry_ts >= expiry_ts1 {
-                                    // set the flag to skip join processing, same key already exists with a larger key
-                                    has_larger_expiry = false;
-                                } else {
-                                    index1.push(join_key, start_ts1, expiry_ts1);
-                                }
+                while let Some(time) = stash1.keys().next().cloned() {
+                    // if input1 can still produce data at `time`, later times in the stash are not complete either
+                    if input1.frontier().less_equal(&time) {
+                        break;
+                    }
+                    let (capability, mut tuples) = stash1.remove(&time).unwrap();
+                    let mut stashed_tuples: Vec<_> = buffer_pool.take();
+                    stashed_tuples.extend(tuples.drain());
+                    stash_pool.recycle(tuples);
+                    if deterministic {
+                        stashed_tuples.sort();
+                    }
+                    let mut session = output.session(&capability);
+                    // update index 1, then perform the join
+                    for ((join_key, join_value, label1), (tuple_interval1, annotations1)) in stashed_tuples.drain(..) {
+                        let start_ts1 = tuple_interval1.get_start();
+                        let expiry_ts1 = tuple_interval1.get_end();
+                        let mut has_larger_expiry = true;
+                        let key1 = (join_key, label1);
+
+                        // place tuples int the index1
+                        // check whether there is already an entry for the same key
+                        if let Some((_start_ts, current_expiry_ts)) = index1.get(&key1) {
+                            // check whether existing entry already has larger expiry
+                            if current_expiry_ts >= expiry_ts1 {
+                                // set the flag to skip join processing, same key already exists with a larger key
+                                has_larger_expiry = false;
                             } else {
-                                index1.push(join_key, start_ts1, expiry_ts1);
+                                index1.push(key1, JoinValue::new(start_ts1, &annotations1), expiry_ts1);
                             }
+                        } else {
+                            index1.push(key1, JoinValue::new(start_ts1, &annotations1), expiry_ts1);
+                            insert_key(&mut filter1, &key1);
+                        }
 
-                            // get mathcing tuple from rhs has table
-                            // perform join only if incoming tuple can produce new results with larger expiry
-                            if has_larger_expiry {
-                                if let Some((start_ts2, expiry_ts2)) = index2.get(&join_key) {
-                                    session.give(
+                        // get mathcing tuple from rhs has table
+                        // perform join only if incoming tuple can produce new results with larger expiry
+                        // tuples of each label of input 2 match, where all tuples have label id 0 unless results are labeled by them
+                        if has_larger_expiry && self_loops.retains(join_value.0, join_value.1) {
+                            for label2 in 0..labels.len() {
+                                let key2 = (join_key, label2);
+                                if !may_contain(&filter2, &key2) {
+                                    continue;
+                                }
+                                if let Some((value2, expiry_ts2)) = index2.get(&key2) {
+                                    results.push(
                                         StreamingGraphTuple::new(
                                             join_value.0,
                                             join_value.1,
-                                            output_label.clone(),
-                                            HalfOpenTimeInterval::new(max(start_ts1, *start_ts2), min(expiry_ts1, expiry_ts2)),
-                                        )
+                                            result_label.format(&output_label, labels.resolve(label1), labels.resolve(label2)),
+                                            HalfOpenTimeInterval::new(max(start_ts1, value2.start_ts), min(expiry_ts1, expiry_ts2)),
+                                        ).with_annotations(annotations1.merge(&value2.annotations))
                                     );
                                 }
                             }
                         }
                     }
+                    buffer_pool.recycle(stashed_tuples);
+                    // each key has at most one match per label, so results of the time are emitted as a single batch
+                    batches.record(results.len());
+                    session.give_vec(&mut results);
                 }
 
-                // discard `time` entries with empty `list`.
-                stash1.retain(|_time, list| list.len() > 0);
-
                 // consider processing tuples whose time has been completed based on the frontier in stash2
-                for (time, tuples) in stash2.iter_mut() {
-                    // if input2 cannot produce data at `time`, process the stash
-                    if !input2.frontier().less_equal(time.time()) {
-                        let mut session = output.session(&time);
-                        // update index 2, then perform the join
-                        for ((join_key, join_value), tuple_interval2) in tuples.drain() {
-                            let start_ts2 = tuple_interval2.get_start();
-                            let expiry_ts2 = tuple_interval2.get_end();
-                            let mut has_larger_expiry = true;
-
-                            // place tuples int the index2
-                            // check whether there is already an entry for the same key
-                            if let Some((_start_ts, current_expiry_ts)) = index2.get(&join_key) {
-                                // check whether existing entry already has larger expiry
-                                if current_expiry_ts >= expiry_ts2 {
-                                    // set the flag to skip join processing, same key already exists with a larger key
-                                    has_larger_expiry = false;
-                                } else {
-                                    index2.push(join_key, start_ts2, expiry_ts2);
-                                }
+                while let Some(time) = stash2.keys().next().cloned() {
+                    // if input2 can still produce data at `time`, later times in the stash are not complete either
+                    if input2.frontier().less_equal(&time) {
+                        break;
+                    }
+                    let (capability, mut tuples) = stash2.remove(&time).unwrap();
+                    let mut stashed_tuples: Vec<_> = buffer_pool.take();
+                    stashed_tuples.extend(tuples.drain());
+                    stash_pool.recycle(tuples);
+                    if deterministic {
+                        stashed_tuples.sort();
+                    }
+                    let mut session = output.session(&capability);
+                    // update index 2, then perform the join
+                    for ((join_key, join_value, label2), (tuple_interval2, annotations2)) in stashed_tuples.drain(..) {
+                        let start_ts2 = tuple_interval2.get_start();
+                        let expiry_ts2 = tuple_interval2.get_end();
+                        let mut has_larger_expiry = true;
+                        let key2 = (join_key, label2);
+
+                        // place tuples int the index2
+                        // check whether there is already an entry for the same key
+                        if let Some((_start_ts, current_expiry_ts)) = index2.get(&key2) {
+                            // check whether existing entry already has larger expiry
+                            if current_expiry_ts >= expiry_ts2 {
+                                // set the flag to skip join processing, same key already exists with a larger key
+                                has_larger_expiry = false;
                             } else {
-                                index2.push(join_key, start_ts2, expiry_ts2);
+                                index2.push(key2, JoinValue::new(start_ts2, &annotations2), expiry_ts2);
                             }
+                        } else {
+                            index2.push(key2, JoinValue::new(start_ts2, &annotations2), expiry_ts2);
+                            insert_key(&mut filter2, &key2);
+                        }
 
-                            // get mathcing tuple from lhs has table
-                            // perform join only if incoming tuple can produce new results with larger expiry
-                            if has_larger_expiry {
-                                if let Some((start_ts1, expiry_ts1)) = index1.get(&join_key) {
-                                    session.give(
+                        // get mathcing tuple from lhs has table
+                        // perform join only if incoming tuple can produce new results with larger expiry
+                        // tuples of each label of input 1 match, where all tuples have label id 0 unless results are labeled by them
+                        if has_larger_expiry && self_loops.retains(join_value.0, join_value.1) {
+                            for label1 in 0..labels.len() {
+                                let key1 = (join_key, label1);
+                                if !may_contain(&filter1, &key1) {
+                                    continue;
+                                }
+                                if let Some((value1, expiry_ts1)) = index1.get(&key1) {
+                                    results.push(
                                         StreamingGraphTuple::new(
                                             join_value.0,
                                             join_value.1,
-                                            output_label.clone(),
-                                            HalfOpenTimeInterval::new(max(*start_ts1, start_ts2), min(expiry_ts1, expiry_ts2)),
-                                        )
+                                            result_label.format(&output_label, labels.resolve(label1), labels.resolve(label2)),
+                                            HalfOpenTimeInterval::new(max(value1.start_ts, start_ts2), min(expiry_ts1, expiry_ts2)),
+                                        ).with_annotations(value1.annotations.merge(&annotations2))
                                     );
                                 }
                             }
                         }
                     }
+                    buffer_pool.recycle(stashed_tuples);
+                    // each key has at most one match per label, so results of the time are emitted as a single batch
+                    batches.record(results.len());
+                    session.give_vec(&mut results);
                 }
 
-                // discard `time` entries with empty `list`.
-                stash2.retain(|_time, list| list.len() > 0);
+                // drop expired keys from the filters once they exceed their capacity
+                refresh_filter(&mut filter1, &index1);
+                refresh_filter(&mut filter2, &index2);
+
+                if gauges::is_enabled() {
+                    gauges::set(index_gauge.clone(), (index1.len() + index2.len()) as u64);
+                    if let Some(average) = batches.period_average() {
+                        gauges::set(batch_gauge.clone(), average);
+                    }
+                    input_lag1.record(input1.frontier().frontier().iter().map(|time| time.event_time()).min(), stash1.values().map(|(_, tuples)| tuples.len()).sum());
+                    input_lag2.record(input2.frontier().frontier().iter().map(|time| time.event_time()).min(), stash2.values().map(|(_, tuples)| tuples.len()).sum());
+                }
+
+                // report the final state size once both inputs are closed
+                if !end_of_stream && input1.frontier().frontier().is_empty() && input2.frontier().frontier().is_empty() {
+                    end_of_stream = true;
+                    info!("{} reached the end of stream with {} and {} keys in join indexes", output_label, index1.len(), index2.len());
+                }
             }
         })
     }
 }
 
+/// vertex of an entry of an attribute join index and the id of the label of its tuple, see `JoinLabel::keeps_input_labels`
+type LabeledVertex = (VertexType, usize);
+
+/// value of an entry of a join index, i.e., the start timestamp of the stored tuple and its annotations,
+/// which results of the entry merge with the annotations of the other side
+#[derive(Clone, Debug)]
+struct JoinValue {
+    start_ts: u64,
+    annotations: Annotations,
+}
+
+impl JoinValue {
+    fn new(start_ts: u64, annotations: &Annotations) -> Self {
+        Self { start_ts, annotations: annotations.clone() }
+    }
+}
+
+/// # of results and batches that a join operator emits in the current reporting period, to report the average batch size of each period
+#[derive(Debug)]
+struct BatchStats {
+    results: u64,
+    batches: u64,
+    clock: Arc<dyn Clock>,
+    period_start: Duration,
+}
+
+impl BatchStats {
+    fn new(clock: Arc<dyn Clock>) -> Self {
+        let period_start = clock.now();
+        Self { results: 0, batches: 0, clock, period_start }
+    }
+
+    /// records a batch of the given size, empty batches are not emitted
+    fn record(&mut self, size: usize) {
+        if size > 0 {
+            self.results += size as u64;
+            self.batches += 1;
+        }
+    }
+
+    /// returns the average # of results per batch of the reporting period, rounded to the nearest integer, once the period is over
+    /// and starts the next period, where periods without batches have no average so that the gauge keeps the latest average
+    fn period_average(&mut self) -> Option<u64> {
+        let now = self.clock.now();
+        if now - self.period_start < Duration::from_millis(REPORTING_PERIOD_MILLISECONDS) {
+            return None;
+        }
+        self.period_start = now;
+        let average = if self.batches == 0 { None } else { Some((self.results + self.batches / 2) / self.batches) };
+        self.results = 0;
+        self.batches = 0;
+        average
+    }
+}
+
 /// Pair of sgt attributes for join:
 /// SS: Join by source of both sgts
 /// ST: Join the source of lhs with target of rhs
@@ -506,6 +804,80 @@ pub enum HashJoinAttributePair {
     TT,
 }
 
+/// Label of join results
+#[derive(Clone)]
+pub enum JoinLabel {
+    /// the output label of the join
+    Output,
+    /// the label of the lhs tuple of each result
+    Lhs,
+    /// the label of the rhs tuple of each result
+    Rhs,
+    /// the labels of the lhs and rhs tuples joined by the given separator, e.g., `a/b` for `/`
+    Concatenated(String),
+    /// the label that the closure formats from the labels of the lhs and rhs tuples
+    Custom(Arc<dyn Fn(&str, &str) -> String + Send + Sync>),
+}
+
+impl JoinLabel {
+    /// returns true if results are labeled by the labels of their tuples
+    fn keeps_input_labels(&self) -> bool {
+        !matches!(self, JoinLabel::Output)
+    }
+
+    /// label of a result of the join with the given output label from tuples with the given labels
+    fn format(&self, output_label: &str, lhs: &str, rhs: &str) -> String {
+        match self {
+            JoinLabel::Output => output_label.to_string(),
+            JoinLabel::Lhs => lhs.to_string(),
+            JoinLabel::Rhs => rhs.to_string(),
+            JoinLabel::Concatenated(separator) => format!("{}{}{}", lhs, separator, rhs),
+            JoinLabel::Custom(format) => format(lhs, rhs),
+        }
+    }
+}
+
+/// rebuilds the given join index by dropping inner values that are expired, i.e., `is_valid` returns false for their expiry
+/// keys with no remaining values are removed, and priority of each remaining key is reset to the min expiry of its inner index
+fn compact_join_index<F>(index: &mut MinPQIndex<VertexType, MinPQIndex<LabeledVertex, JoinValue>>, is_valid: F) where F: Fn(u64) -> bool {
+    let old_index = std::mem::take(index);
+
+    for (key, mut inner_index, _) in old_index.into_entries() {
+        // remove all inner values that are expired
+        while let Some((_, _, expiry_ts)) = inner_index.peek() {
+            if is_valid(expiry_ts) {
+                break;
+            }
+            inner_index.pop();
+        }
+
+        // re-insert the key with the actual min expiry of its values
+        if let Some((_, _, min_expiry_ts)) = inner_index.peek() {
+            index.push(key, inner_index, min_expiry_ts);
+        }
+    }
+}
+
+/// adds a key that is new to a join index to the filter of the index, if it is enabled
+fn insert_key<K: Hash>(filter: &mut Option<KeyFilter>, key: &K) {
+    if let Some(filter) = filter {
+        filter.insert(key);
+    }
+}
+
+/// returns false only if the filter of a join index rules out the key, so that the index lookup can be skipped
+fn may_contain<K: Hash>(filter: &Option<KeyFilter>, key: &K) -> bool {
+    filter.as_ref().map_or(true, |filter| filter.may_contain(key))
+}
+
+/// rebuilds the filter from the keys of its join index once it exceeds its capacity
+fn refresh_filter<K, V>(filter: &mut Option<KeyFilter>, index: &MinPQIndex<K, V>)
+    where K: Copy + PartialEq + Clone + Debug + Hash + Default, V: Clone + Debug {
+    if let Some(filter) = filter.as_mut().filter(|filter| filter.needs_rebuild()) {
+        filter.rebuild(index.iter().map(|(key, _, _)| key), index.len());
+    }
+}
+
 fn forward_tuple_selector(tuple: &StreamingGraphTuple) -> (u64, u64) {
     (tuple.get_source(), tuple.get_target())
 }
@@ -540,3 +912,36 @@ fn get_key_selector(predicate: &HashJoinAttributePair) -> (fn(&StreamingGraphTup
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::operator::hash_join::BatchStats;
+    use crate::util::clock::ManualClock;
+    use crate::util::types::REPORTING_PERIOD_MILLISECONDS;
+
+    #[test]
+    fn batch_sizes_are_averaged_per_period() {
+        let clock = ManualClock::new();
+        let period = Duration::from_millis(REPORTING_PERIOD_MILLISECONDS);
+        let mut batches = BatchStats::new(Arc::new(clock.clone()));
+
+        batches.record(1);
+        batches.record(2);
+        batches.record(0);
+        assert_eq!(batches.period_average(), None);
+        clock.advance(period);
+        // 3 results in 2 batches
+        assert_eq!(batches.period_average(), Some(2));
+
+        // batches of earlier periods do not dilute the average of a later period
+        batches.record(10);
+        clock.advance(period);
+        assert_eq!(batches.period_average(), Some(10));
+
+        clock.advance(period);
+        assert_eq!(batches.period_average(), None);
+    }
+}
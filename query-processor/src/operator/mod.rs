@@ -3,16 +3,35 @@ use std::cmp::Reverse;
 use std::fmt::Debug;
 use std::hash::{Hash, Hasher};
 use std::hash::BuildHasherDefault;
+use std::iter::once;
+use std::sync::Arc;
 
 use hashers::fx_hash::FxHasher;
 use priority_queue::PriorityQueue;
 
-pub mod delta;
-pub mod tree_node;
-pub mod spanning_tree;
+use crate::input::VertexIds;
+use crate::input::vertex_filter::VertexFilter;
+use crate::query::parser::Endpoints;
+use crate::util::types::VertexType;
+
+pub(crate) mod bloom;
+pub(crate) mod delta;
+pub(crate) mod pool;
+pub(crate) mod tree_node;
+pub(crate) mod spanning_tree;
 pub mod window;
 pub mod rpq;
 pub mod hash_join;
+pub mod window_batch;
+pub mod snapshot;
+pub mod union;
+pub mod ttl;
+pub mod alerts;
+pub mod degree;
+pub mod group;
+pub mod neighborhood;
+pub mod sequence;
+pub mod star_join;
 
 
 /// custom struct to store entries in PriorityQueue
@@ -106,6 +125,72 @@ impl<K, V> Hash for PQEntry<K, V> where
     }
 }
 
+/// Filter of results by whether their source is their target, i.e., self-loops in the result graph
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SelfLoops {
+    /// all results are emitted
+    Keep,
+    /// results from a vertex to itself are dropped, e.g., trivial matches of `knows*`
+    Drop,
+    /// only results from a vertex to itself are emitted, e.g., cycles
+    Only,
+}
+
+impl Default for SelfLoops {
+    fn default() -> Self {
+        SelfLoops::Keep
+    }
+}
+
+impl SelfLoops {
+    /// returns true if a result from `source` to `target` is emitted
+    pub fn retains(&self, source: VertexType, target: VertexType) -> bool {
+        match self {
+            SelfLoops::Keep => true,
+            SelfLoops::Drop => source != target,
+            SelfLoops::Only => source == target,
+        }
+    }
+}
+
+/// Sets of vertices that spanning trees are rooted at and that results reach, e.g., the constants of an anchored RPQ
+/// or vertex sets read from files, where an endpoint without a set is not restricted
+/// Sets are shared by clones, so that the filter is cheap to move into operators
+#[derive(Clone, Debug, Default)]
+pub struct EndpointSets {
+    sources: Option<Arc<VertexFilter>>,
+    targets: Option<Arc<VertexFilter>>,
+}
+
+impl EndpointSets {
+    /// restricts trees to roots in `sources` and results to targets in `targets`
+    pub fn new(sources: VertexFilter, targets: VertexFilter) -> Self {
+        Self { sources: Some(Arc::new(sources)), targets: Some(Arc::new(targets)) }
+    }
+
+    /// reads both vertex sets from files with one vertex identifier per line, resolved as the input, see `VertexFilter::from_file`
+    pub fn from_files(sources_path: &str, targets_path: &str, vertex_ids: &VertexIds) -> Result<Self, String> {
+        Ok(Self::new(VertexFilter::from_file(sources_path, vertex_ids)?, VertexFilter::from_file(targets_path, vertex_ids)?))
+    }
+
+    /// returns true if a tree may be rooted at the vertex
+    pub fn matches_source(&self, source: VertexType) -> bool {
+        self.sources.as_ref().map_or(true, |sources| sources.contains(source))
+    }
+
+    /// returns true if a result may reach the vertex
+    pub fn matches_target(&self, target: VertexType) -> bool {
+        self.targets.as_ref().map_or(true, |targets| targets.contains(target))
+    }
+}
+
+impl From<Endpoints> for EndpointSets {
+    fn from(endpoints: Endpoints) -> Self {
+        let constant = |vertex: Option<VertexType>| vertex.map(|vertex| Arc::new(VertexFilter::Set(once(vertex).collect())));
+        Self { sources: constant(endpoints.source), targets: constant(endpoints.target) }
+    }
+}
+
 /// Helper struct for PQIndex used in SGA Operator implementations
 /// it is a min priority-queue backed by an indexmap that provides efficient key-lookups
 /// Being backed by PQ, it supports efficient (logn) operations to pop, push and change priority
@@ -167,6 +252,41 @@ impl<K, V> MinPQIndex<K, V>
         }
     }
 
+    /// updates the value of the given key with `f`, or inserts the value created by `default` and updates it with `f`,
+    /// then decreases the priority of the key to `priority` only if it is larger, i.e., `get_mut`, `push` and `try_decrease_priority`
+    /// with a single lookup unless the priority decreases
+    /// returns the result of `f`
+    pub fn upsert_with<D, F, R>(&mut self, key: K, default: D, f: F, priority: u64) -> R
+        where
+            D: FnOnce() -> V,
+            F: FnOnce(&mut V) -> R {
+        self.index_key.swap_key(key);
+        let (result, decrease) = match self.index.get_mut(&self.index_key) {
+            Some((entry, Reverse(current))) => {
+                let decrease = *current > priority;
+                (f(entry.get_entry_mut()), decrease)
+            }
+            None => {
+                let mut value = default();
+                let result = f(&mut value);
+                self.index.push(PQEntry::create_entry(key, value), Reverse(priority));
+                return result;
+            }
+        };
+
+        if decrease {
+            self.index.change_priority(&self.index_key, Reverse(priority));
+        }
+        result
+    }
+
+    /// retrieve mutable reference to entry with the min priority
+    /// the priority of the entry does not change, so that its position in the index remains valid
+    pub fn peek_mut(&mut self) -> Option<(K, &mut V, u64)> {
+        let (key, priority) = self.index.peek().map(|(val, Reverse(ts))| (val.get_key(), *ts))?;
+        self.get_mut(&key).map(|(entry, _)| (key, entry, priority))
+    }
+
     /// iterate over (key ,value, priority) triples in an arbitrary order
     pub fn iter<'a>(&'a self) -> impl Iterator<Item=(K, &'a V, u64)> {
         self.index.iter().map(|(entry, Reverse(p))| (entry.get_key(), entry.get_entry(), *p))
@@ -193,15 +313,26 @@ impl<K, V> MinPQIndex<K, V>
         self.index.remove(&self.index_key).map(|(val, Reverse(ts))| (val.drain(), ts))
     }
 
+    /// consumes the index and iterate over (key, value, priority) triples in an arbitrary order
+    pub fn into_entries(self) -> impl Iterator<Item=(K, V, u64)> {
+        self.index.into_iter().map(|(entry, Reverse(p))| (entry.get_key(), entry.drain(), p))
+    }
+
     pub fn is_empty(&self) -> bool {
         self.index.is_empty()
     }
+
+    /// number of entries in the index
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
 }
 
 /// Tests for IntervalSet related functionality
 #[cfg(test)]
 mod tests {
     use crate::util::types::{HalfOpenTimeInterval, HalfOpenInterval};
+    use crate::operator::MinPQIndex;
     use crate::operator::tests::IntervalSetContent::{Single, Set};
 
 
@@ -445,4 +576,25 @@ mod tests {
 
         assert_eq!(set.get_min_expiry().unwrap(), 6);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn upsert_and_peek_mut() {
+        let mut index: MinPQIndex<u64, Vec<u64>> = MinPQIndex::default();
+
+        assert_eq!(index.upsert_with(1, Vec::new, |values| { values.push(10); values.len() }, 10), 1);
+        assert_eq!(index.upsert_with(2, Vec::new, |values| { values.push(5); values.len() }, 5), 1);
+        // priority is decreased only if it gets smaller
+        assert_eq!(index.upsert_with(1, Vec::new, |values| { values.push(20); values.len() }, 20), 2);
+        assert_eq!(index.get(&1).map(|(values, priority)| (values.clone(), priority)), Some((vec![10, 20], 10)));
+        index.upsert_with(1, Vec::new, |values| values.push(3), 3);
+        assert_eq!(index.get(&1).map(|(_, priority)| priority), Some(3));
+
+        if let Some((key, values, priority)) = index.peek_mut() {
+            assert_eq!((key, priority), (1, 3));
+            values.clear();
+        }
+        assert_eq!(index.pop(), Some((1, vec![], 3)));
+        assert_eq!(index.pop(), Some((2, vec![5], 5)));
+        assert!(index.peek_mut().is_none());
+    }
+}
@@ -0,0 +1,151 @@
+extern crate timely;
+
+use std::cmp::{max, min};
+use std::collections::{HashMap, HashSet};
+
+use timely::dataflow::{Scope, Stream};
+use timely::dataflow::channels::pact::Exchange;
+use timely::dataflow::operators::generic::operator::Operator;
+
+use crate::input::tuple::StreamingGraphTuple;
+use crate::util::types::{EventTime, HalfOpenTimeInterval, VertexType};
+
+/// Extracts the k-hop neighborhood of a set of seed vertices from a windowed stream of edges, i.e., edges with a label of the given set
+/// on a path of at most `k` such edges that starts at a seed, as a sub-stream of sgts that downstream queries consume as their input
+/// An edge is emitted with the interval of its longest-lived path from a seed, i.e., the intersection of the intervals of the path,
+/// once it enters the neighborhood and again whenever its path is extended to a later expiry
+/// The longest-lived path to each vertex by its # of hops is maintained incrementally, so that each new edge only extends the paths it is on,
+/// and all tuples are routed to the first worker
+pub trait KHopNeighborhood<G: Scope> where G::Timestamp: EventTime {
+    /// panics if `k` is zero
+    fn k_hop_neighborhood(&self, seeds: HashSet<VertexType>, labels: HashSet<String>, k: usize) -> Stream<G, StreamingGraphTuple>;
+}
+
+/// key of an edge, i.e., its source, target and label
+type EdgeKey = (VertexType, VertexType, String);
+
+/// Window content of edges with a label of the set, along with the longest-lived paths from the seeds over them
+struct Neighborhood {
+    k: usize,
+    // interval of each edge by its source, keep max expiry for each value equivalent edge
+    edges: HashMap<VertexType, HashMap<(VertexType, String), HalfOpenTimeInterval>>,
+    // longest-lived path of exactly `hop` edges from a seed to each vertex, for each `hop` less than `k`
+    paths: HashMap<VertexType, Vec<Option<HalfOpenTimeInterval>>>,
+}
+
+impl Neighborhood {
+    fn new(seeds: &HashSet<VertexType>, k: usize) -> Self {
+        let paths = seeds.iter().map(|seed| {
+            let mut seed_paths = vec![None; k];
+            seed_paths[0] = Some(HalfOpenTimeInterval::new(0, u64::MAX));
+            (*seed, seed_paths)
+        }).collect();
+        Self { k, edges: HashMap::new(), paths }
+    }
+
+    /// removes edges and paths that expire at or before the given event time
+    /// A path expires no later than any of its edges, so that the paths over expired edges are expired as well
+    fn expire(&mut self, event_time: u64) {
+        self.edges.values_mut().for_each(|targets| targets.retain(|_, interval| interval.end > event_time));
+        self.edges.retain(|_, targets| !targets.is_empty());
+        for vertex_paths in self.paths.values_mut() {
+            for path in vertex_paths.iter_mut() {
+                if path.map_or(false, |path| path.end <= event_time) {
+                    *path = None;
+                }
+            }
+        }
+        self.paths.retain(|_, vertex_paths| vertex_paths.iter().any(Option::is_some));
+    }
+
+    /// adds an edge and extends the paths to its source over it, and records the neighborhood edges whose path is extended in `extended_edges`
+    fn insert(&mut self, source: VertexType, target: VertexType, label: String, interval: HalfOpenTimeInterval, extended_edges: &mut HashMap<EdgeKey, HalfOpenTimeInterval>) {
+        let current_interval = self.edges.entry(source).or_insert_with(HashMap::new).entry((target, label.clone())).or_insert(interval);
+        if current_interval.end > interval.end {
+            return;
+        }
+        *current_interval = interval;
+
+        let mut updated_paths = Vec::new();
+        let source_paths: Vec<(usize, HalfOpenTimeInterval)> = self.paths.get(&source).into_iter()
+            .flat_map(|source_paths| source_paths.iter().enumerate().filter_map(|(hop, path)| path.map(|path| (hop, path))))
+            .collect();
+        for (hop, path) in source_paths {
+            Self::extend(&mut self.paths, self.k, (source, hop, path), (target, label.as_str(), interval), extended_edges, &mut updated_paths);
+        }
+
+        // then extend paths over the outgoing edges of each vertex whose path is updated, until no path is updated
+        while let Some((vertex, hop)) = updated_paths.pop() {
+            let path = self.paths[&vertex][hop].unwrap();
+            for ((target, label), interval) in self.edges.get(&vertex).into_iter().flatten() {
+                Self::extend(&mut self.paths, self.k, (vertex, hop, path), (*target, label.as_str(), *interval), extended_edges, &mut updated_paths);
+            }
+        }
+    }
+
+    /// extends the path of `hop` edges to a vertex over one of its outgoing edges, and records the (vertex, hop) pair of the target if its path is updated
+    fn extend(paths: &mut HashMap<VertexType, Vec<Option<HalfOpenTimeInterval>>>, k: usize, (source, hop, path): (VertexType, usize, HalfOpenTimeInterval),
+              (target, label, interval): (VertexType, &str, HalfOpenTimeInterval), extended_edges: &mut HashMap<EdgeKey, HalfOpenTimeInterval>, updated_paths: &mut Vec<(VertexType, usize)>) {
+        let extended = HalfOpenTimeInterval::new(max(path.start, interval.start), min(path.end, interval.end));
+        if extended.start >= extended.end {
+            return;
+        }
+        let edge_interval = extended_edges.entry((source, target, label.to_string())).or_insert(extended);
+        if edge_interval.end < extended.end {
+            *edge_interval = extended;
+        }
+
+        if hop + 1 < k {
+            let target_path = &mut paths.entry(target).or_insert_with(|| vec![None; k])[hop + 1];
+            if target_path.map_or(true, |current| current.end < extended.end) {
+                *target_path = Some(extended);
+                updated_paths.push((target, hop + 1));
+            }
+        }
+    }
+}
+
+impl<G: Scope> KHopNeighborhood<G> for Stream<G, StreamingGraphTuple> where G::Timestamp: EventTime {
+    fn k_hop_neighborhood(&self, seeds: HashSet<VertexType>, labels: HashSet<String>, k: usize) -> Stream<G, StreamingGraphTuple> {
+        assert!(k > 0, "Neighborhood has to span at least one hop");
+        let mut vector = Vec::new();
+
+        // stash to collect tuples of a timestamp until progress notification
+        let mut stash: HashMap<G::Timestamp, Vec<StreamingGraphTuple>> = HashMap::new();
+        let mut neighborhood = Neighborhood::new(&seeds, k);
+        // end of the interval each edge of the neighborhood is emitted with
+        let mut advertised: HashMap<EdgeKey, u64> = HashMap::new();
+
+        self.unary_notify(Exchange::new(|_| 0), "KHopNeighborhood", vec![], move |input, output, notificator| {
+            while let Some((time, data)) = input.next() {
+                data.swap(&mut vector);
+                stash.entry(time.time().clone()).or_insert_with(Vec::new).extend(vector.drain(..).filter(|sgt| sgt.append && labels.contains(&sgt.label)));
+                notificator.notify_at(time.retain());
+            }
+
+            notificator.for_each(|time, _, _| {
+                let event_time = time.time().event_time();
+                neighborhood.expire(event_time);
+                advertised.retain(|_, end| *end > event_time);
+
+                let mut extended_edges = HashMap::new();
+                for sgt in stash.remove(time.time()).unwrap_or_default() {
+                    if sgt.interval.end > event_time {
+                        neighborhood.insert(sgt.source, sgt.target, sgt.label, sgt.interval, &mut extended_edges);
+                    }
+                }
+
+                let mut session = output.session(&time);
+                let mut extended_edges: Vec<StreamingGraphTuple> = extended_edges.into_iter()
+                    .filter(|(key, interval)| advertised.get(key).map_or(true, |end| *end < interval.end))
+                    .map(|((source, target, label), interval)| {
+                        advertised.insert((source, target, label.clone()), interval.end);
+                        StreamingGraphTuple::new(source, target, label, interval)
+                    })
+                    .collect();
+                extended_edges.sort();
+                session.give_iterator(extended_edges.into_iter());
+            });
+        })
+    }
+}
@@ -0,0 +1,75 @@
+use std::hash::{BuildHasher, Hash};
+
+use hashbrown::HashMap;
+
+/// max # of containers that a pool keeps for reuse
+const MAX_POOLED: usize = 4;
+
+/// Containers that are cleared for reuse while keeping their allocated capacity
+pub trait Recycle: Default {
+    fn clear(&mut self);
+}
+
+impl<T> Recycle for Vec<T> {
+    fn clear(&mut self) {
+        Vec::clear(self)
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Default> Recycle for HashMap<K, V, S> {
+    fn clear(&mut self) {
+        HashMap::clear(self)
+    }
+}
+
+/// Pool of stash containers that operators recycle across times instead of allocating a new one for each time and dropping it once the time is processed
+/// The pool keeps at most `MAX_POOLED` containers, so that the capacity it retains is bounded by the few largest times in flight
+#[derive(Debug)]
+pub struct StashPool<T: Recycle> {
+    free: Vec<T>,
+}
+
+impl<T: Recycle> StashPool<T> {
+    pub fn new() -> Self {
+        Self { free: Vec::with_capacity(MAX_POOLED) }
+    }
+
+    /// returns an empty container, reusing a recycled one if there is any
+    pub fn take(&mut self) -> T {
+        self.free.pop().unwrap_or_default()
+    }
+
+    /// clears the given container and keeps it for reuse unless the pool is full
+    pub fn recycle(&mut self, mut container: T) {
+        if self.free.len() < MAX_POOLED {
+            container.clear();
+            self.free.push(container);
+        }
+    }
+}
+
+impl<T: Recycle> Default for StashPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::operator::pool::StashPool;
+
+    #[test]
+    fn recycle_containers() {
+        let mut pool: StashPool<Vec<u64>> = StashPool::new();
+        let mut buffer = pool.take();
+        buffer.extend(0..100);
+        let capacity = buffer.capacity();
+        pool.recycle(buffer);
+
+        // recycled containers are empty but keep their capacity
+        let buffer = pool.take();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.capacity(), capacity);
+        assert_eq!(pool.take().capacity(), 0);
+    }
+}
@@ -1,92 +1,339 @@
 extern crate timely;
 
-use std::collections::VecDeque;
+use std::cmp::max;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
 use std::hash::BuildHasherDefault;
+use std::io::{BufWriter, Write};
+use std::path::Path;
 
 use hashbrown::{HashMap, HashSet};
 use hashers::fx_hash::FxHasher;
-use log::{debug, trace};
+use log::{debug, info, trace, warn};
 
 use timely::Data;
 use timely::dataflow::{Scope, Stream};
 use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::operators::{Concatenate, Exchange, Map};
+use timely::worker::AsWorker;
 use timely::dataflow::operators::generic::operator::Operator;
 
-use crate::graph::Graph;
+use crate::graph::{Graph, QueryAutomaton, StaticGraph};
 use crate::input::{GraphEdge, SGT, StreamingGraphEdge};
-use crate::input::tuple::StreamingGraphTuple;
-use crate::operator::{delta::Delta, MinPQIndex, spanning_tree::SpanningTree};
+use crate::input::tuple::{SlideIds, StreamingGraphTuple};
+use crate::operator::{delta::{Delta, DeltaPool}, EndpointSets, MinPQIndex, pool::StashPool, SelfLoops, spanning_tree::SpanningTree};
 
+use crate::query::automata::dfa::DFA;
+use crate::query::automata::specialize;
+use crate::query::containment::share_contained;
 use crate::query::parser::RPQParser;
+use crate::util::{anomaly, gauges, hot_swap, state_counters};
 
-use self::super::super::util::types::{HalfOpenInterval, HalfOpenTimeInterval, VertexStatePair, VertexType};
+use self::super::super::util::types::{EventTime, EvictionPolicy, HalfOpenInterval, HalfOpenTimeInterval, is_deterministic_mode, OperationType, reordering_slack, StateType, tree_node_cap, VertexStatePair, VertexType, window_ids};
 
 /// Implementation of the `S-PATH` algorithm from PVLDB submission asa TD operator
 /// It creates the minimal DFA for the given RPQ
 /// It uses TD progress tracking mechanism to be notified about completed timestamps
-pub trait RegularPathQuery<G: Scope<Timestamp=u64>, D: Data + SGT<HalfOpenTimeInterval, StreamingGraphEdge>> {
+pub trait RegularPathQuery<G: Scope, D: Data + SGT<HalfOpenTimeInterval, StreamingGraphEdge>> {
     /// Incremental RPQ evaluation on the given streams based on the provided RPQ `query_str`
     /// Resulting tuples carry the provided label `output_label`
+    /// It uses a reordering buffer if a slack is set via `set_reordering_slack`
     fn regular_path_query(&self, query_str: &str, output_label: String) -> Stream<G, StreamingGraphTuple>;
+    /// Incremental RPQ evaluation as `regular_path_query` that tolerates sources with slight disorder
+    /// Tuples are stashed by their event time, i.e., the start of their validity interval, and are processed in event time order
+    /// once the input frontier passes their event time by `slack`. Tuples that arrive later than `slack` are not dropped,
+    /// but merged into the earliest event time that is not processed yet
+    fn regular_path_query_with_slack(&self, query_str: &str, output_label: String, slack: u64) -> Stream<G, StreamingGraphTuple>;
+    /// Incremental RPQ evaluation as `regular_path_query`, where results from a vertex to itself are dropped or kept based on `self_loops`
+    fn regular_path_query_with_loops(&self, query_str: &str, output_label: String, self_loops: SelfLoops) -> Stream<G, StreamingGraphTuple>;
+    /// Incremental RPQ evaluation as `regular_path_query` over paths of at most `max_depth` edges, e.g., `knows+` up to 3 hops
+    /// Spanning trees are not expanded beyond `max_depth`, and a node is re-parented to a longer-lived path only if it is not longer,
+    /// so that a result that is reachable only through a shorter path than its longest-lived one may expire earlier than without the bound
+    fn regular_path_query_with_max_depth(&self, query_str: &str, output_label: String, max_depth: usize) -> Stream<G, StreamingGraphTuple>;
+    /// Incremental RPQ evaluation as `regular_path_query`, where matches of a known prefix of the query, e.g., a selective first predicate,
+    /// are computed upstream and carry the `anchor_label`. Trees start directly at the automaton state reached after the prefix,
+    /// so that edges of the prefix are not expanded again
+    /// panics if no word of the query starts with the prefix
+    fn regular_path_query_after_prefix(&self, query_str: &str, prefix: &[&str], anchor_label: &str, output_label: String) -> Stream<G, StreamingGraphTuple>;
+    /// Incremental RPQ evaluation as `regular_path_query` for queries with constant endpoints, e.g., `<42> knows+ ?x`
+    /// Trees are created only for the source constant and only results that reach the target constant are emitted,
+    /// so that point queries do not construct and post-filter full result sets
+    fn anchored_regular_path_query(&self, query_str: &str, output_label: String) -> Stream<G, StreamingGraphTuple>;
+    /// Incremental RPQ evaluation as `regular_path_query` between fixed vertex sets, e.g., read with `EndpointSets::from_files`,
+    /// where trees are created only for sources in the source set and only results that reach the target set are emitted
+    fn regular_path_query_between(&self, query_str: &str, endpoints: EndpointSets, output_label: String) -> Stream<G, StreamingGraphTuple>;
+    /// Incremental RPQ evaluation as `regular_path_query` over the given automaton, e.g., a combined automaton of several queries,
+    /// where results carry the tag of the final state they reach, and `output_label` if the final state is not tagged
+    fn tagged_regular_path_query(&self, automaton: DFA, output_label: String) -> Stream<G, StreamingGraphTuple>;
+    /// Incremental RPQ evaluation as `regular_path_query` over a `LazyDFA` of the query, which materializes automaton states
+    /// only for the labels observed in the stream instead of determinizing the query upfront, e.g., for queries over large alphabets
+    fn lazy_regular_path_query(&self, query_str: &str, output_label: String) -> Stream<G, StreamingGraphTuple>;
+    /// Incremental evaluation of several RPQs, given as query strings with their output labels, into a single stream of their results
+    /// Queries whose language is contained in the language of another query are derived from its results by their final state,
+    /// instead of maintaining their own spanning trees, see `query::containment`
+    fn shared_regular_path_queries(&self, queries: &[(&str, String)]) -> Stream<G, StreamingGraphTuple>;
+    /// Incremental evaluation of the RPQ as `regular_path_query` that emits, instead of results, the # of distinct targets
+    /// that each source reaches as (source, count, interval) tuples, counted over its spanning tree
+    /// A count is emitted whenever the results of a source change, and is valid until the first of its targets expires,
+    /// so that a source whose count is not renewed at the end of the interval of its last count reaches no target
+    fn reachable_target_counts(&self, query_str: &str, output_label: String) -> Stream<G, (VertexType, u64, HalfOpenTimeInterval)>;
 }
 
-impl<G: Scope<Timestamp=u64>> RegularPathQuery<G, StreamingGraphTuple> for Stream<G, StreamingGraphTuple> {
+impl<G: Scope> RegularPathQuery<G, StreamingGraphTuple> for Stream<G, StreamingGraphTuple> where G::Timestamp: EventTime {
     fn regular_path_query(&self, query_str: &str, output_label: String) -> Stream<G, StreamingGraphTuple> {
-        let mut vector = Vec::new();
+        windowed_reachability(self, parse_query(query_str), ReachabilityOutput::Pairs(output_label), reordering_slack(), EndpointSets::default(), SelfLoops::Keep, None)
+    }
 
-        // Min PQ based index to store spanning trees organized by their expiry timestamp
-        let mut delta_node_index: HashMap<VertexStatePair, HashSet<u64, BuildHasherDefault<FxHasher>>, BuildHasherDefault<FxHasher>> = HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default());
-        // invertex index for fast lookups
-        let mut delta_tree_queue: MinPQIndex<VertexType, SpanningTree> = MinPQIndex::default();
+    fn regular_path_query_with_slack(&self, query_str: &str, output_label: String, slack: u64) -> Stream<G, StreamingGraphTuple> {
+        windowed_reachability(self, parse_query(query_str), ReachabilityOutput::Pairs(output_label), Some(slack), EndpointSets::default(), SelfLoops::Keep, None)
+    }
 
-        //create minimal DFA for the given regular expression
-        let rpq_parser = RPQParser::new();
-        let minimized_dfa = rpq_parser.parse_rpq(query_str);
+    fn regular_path_query_with_loops(&self, query_str: &str, output_label: String, self_loops: SelfLoops) -> Stream<G, StreamingGraphTuple> {
+        windowed_reachability(self, parse_query(query_str), ReachabilityOutput::Pairs(output_label), reordering_slack(), EndpointSets::default(), self_loops, None)
+    }
 
-        if minimized_dfa.is_err() {
-            panic!("CANNOT create DFA from given RPQ {}", query_str);
-        }
+    fn regular_path_query_with_max_depth(&self, query_str: &str, output_label: String, max_depth: usize) -> Stream<G, StreamingGraphTuple> {
+        windowed_reachability(self, parse_query(query_str), ReachabilityOutput::Pairs(output_label), reordering_slack(), EndpointSets::default(), SelfLoops::Keep, Some(max_depth))
+    }
+
+    fn regular_path_query_after_prefix(&self, query_str: &str, prefix: &[&str], anchor_label: &str, output_label: String) -> Stream<G, StreamingGraphTuple> {
+        let specialized_dfa = specialize(&parse_query(query_str), prefix, anchor_label)
+            .unwrap_or_else(|| panic!("RPQ {} has no match starting with {:?}", query_str, prefix));
+        windowed_reachability(self, specialized_dfa, ReachabilityOutput::Pairs(output_label), reordering_slack(), EndpointSets::default(), SelfLoops::Keep, None)
+    }
 
-        // adjacency list index to store tuples in the window (i.e., snapshot graph)
-        let mut graph = Graph::new(minimized_dfa.unwrap());
+    fn anchored_regular_path_query(&self, query_str: &str, output_label: String) -> Stream<G, StreamingGraphTuple> {
+        let (endpoints, minimized_dfa) = RPQParser::new().parse_anchored_rpq(query_str).unwrap_or_else(|e| panic!("{}", e));
+        windowed_reachability(self, minimized_dfa, ReachabilityOutput::Pairs(output_label), reordering_slack(), endpoints.into(), SelfLoops::Keep, None)
+    }
 
-        // stash to collect tuples until progress notification
-        let mut stash = HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default());
+    fn regular_path_query_between(&self, query_str: &str, endpoints: EndpointSets, output_label: String) -> Stream<G, StreamingGraphTuple> {
+        windowed_reachability(self, parse_query(query_str), ReachabilityOutput::Pairs(output_label), reordering_slack(), endpoints, SelfLoops::Keep, None)
+    }
 
-        // TODO: change communication pact for distributed setup
-        self.unary_notify(Pipeline, "WindowedReachability", vec![], move |input, output, notificator| {
-            // stash incoming tuples for processing after expiry
+    fn tagged_regular_path_query(&self, automaton: DFA, output_label: String) -> Stream<G, StreamingGraphTuple> {
+        windowed_reachability(self, automaton, ReachabilityOutput::Pairs(output_label), reordering_slack(), EndpointSets::default(), SelfLoops::Keep, None)
+    }
 
-            while let Some((time, data)) = input.next() {
+    fn lazy_regular_path_query(&self, query_str: &str, output_label: String) -> Stream<G, StreamingGraphTuple> {
+        let lazy_dfa = RPQParser::new().parse_lazy_rpq(query_str).unwrap_or_else(|e| panic!("CANNOT create DFA from given RPQ {}: {}", query_str, e));
+        windowed_reachability(self, lazy_dfa, ReachabilityOutput::Pairs(output_label), reordering_slack(), EndpointSets::default(), SelfLoops::Keep, None)
+    }
+
+    fn shared_regular_path_queries(&self, queries: &[(&str, String)]) -> Stream<G, StreamingGraphTuple> {
+        let automata = queries.iter().map(|(query_str, output_label)| (parse_query(query_str), output_label.clone())).collect();
+        let outputs: Vec<_> = share_contained(automata).into_iter().map(|shared| {
+            info!("{} evaluates {} queries", shared.output_label, shared.len());
+            self.tagged_regular_path_query(shared.automaton.clone(), shared.output_label.clone())
+                .flat_map(move |sgt| shared.output_labels(&sgt.label).iter().map(|label| {
+                    let mut result = sgt.clone();
+                    result.label = label.clone();
+                    result
+                }).collect::<Vec<_>>())
+        }).collect();
+
+        // a result reaches several final states of the product automaton with the same tag, e.g., through a cycle, so that a result
+        // of a query is emitted only if it extends the expiry of its previous result, as the spanning trees of the query would
+        let mut vector = Vec::new();
+        let mut emitted: HashMap<(VertexType, VertexType, String), u64> = HashMap::new();
+        let mut expired_until = 0;
+        self.scope().concatenate(outputs).unary(Pipeline, "SharedResults", move |_, _| move |input, output| {
+            input.for_each(|time, data| {
                 data.swap(&mut vector);
-                let time_index = stash.entry(time.time().clone()).or_insert_with(|| HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default()));
+                let event_time = time.time().event_time();
+                if event_time > expired_until {
+                    emitted.retain(|_, end| *end > event_time);
+                    expired_until = event_time;
+                }
 
+                let mut session = output.session(&time);
                 for sgt in vector.drain(..) {
-                    let tuple_key = (sgt.get_source(), sgt.get_target(), sgt.get_label().to_string());
-                    let tuple_interval = sgt.get_interval();
-
-                    // simply stash the tuple, keep max expiry for each value equivelant tuple
-                    time_index.entry(tuple_key).and_modify(|current_interval: &mut HalfOpenTimeInterval| {
-                        if current_interval.get_end() < tuple_interval.get_end() {
-                            *current_interval = tuple_interval;
+                    if sgt.append {
+                        let end = emitted.entry((sgt.source, sgt.target, sgt.label.clone())).or_insert(0);
+                        if *end >= sgt.interval.end {
+                            continue;
                         }
-                    }).or_insert(tuple_interval);
+                        *end = sgt.interval.end;
+                    }
+                    session.give(sgt);
                 }
+            });
+        })
+    }
 
+    fn reachable_target_counts(&self, query_str: &str, output_label: String) -> Stream<G, (VertexType, u64, HalfOpenTimeInterval)> {
+        windowed_reachability(self, parse_query(query_str), ReachabilityOutput::TargetCounts(output_label), reordering_slack(), EndpointSets::default(), SelfLoops::Keep, None)
+            .map(|sgt| (sgt.source, sgt.target, sgt.interval))
+    }
+}
+
+/// Output of the `S-PATH` operator, labeled with the given output label
+enum ReachabilityOutput {
+    /// a tuple for each result
+    Pairs(String),
+    /// the # of distinct targets of each spanning tree whose results change, where the target of each tuple is the count
+    /// and its interval ends at the first expiry of a target
+    TargetCounts(String),
+}
+
+/// Constructs the `S-PATH` operator, where tuples are stashed by the time they arrive at if `slack` is `None`,
+/// and by their event time in a reordering buffer otherwise
+/// Spanning trees are rooted only at vertices of the source set of `endpoints`, and results are restricted to its target set
+/// and to results from a vertex to itself, or other results, based on `self_loops`
+/// Trees are expanded only along paths of at most `max_depth` edges if it is set
+fn windowed_reachability<G: Scope, A: Into<QueryAutomaton>>(stream: &Stream<G, StreamingGraphTuple>, automaton: A, output: ReachabilityOutput, slack: Option<u64>, endpoints: EndpointSets, self_loops: SelfLoops, max_depth: Option<usize>) -> Stream<G, StreamingGraphTuple> where G::Timestamp: EventTime {
+    let mut vector = Vec::new();
+
+    let (output_label, count_targets) = match output {
+        ReachabilityOutput::Pairs(output_label) => (output_label, false),
+        ReachabilityOutput::TargetCounts(output_label) => (output_label, true),
+    };
+    // roots of trees whose results change at the event time that is processed, if targets are counted
+    let mut changed_trees: HashSet<VertexType, BuildHasherDefault<FxHasher>> = HashSet::default();
+
+    // Min PQ based index to store spanning trees organized by their expiry timestamp
+    let mut delta_node_index: HashMap<VertexStatePair, HashSet<u64, BuildHasherDefault<FxHasher>>, BuildHasherDefault<FxHasher>> = HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default());
+    // invertex index for fast lookups
+    let mut delta_tree_queue: MinPQIndex<VertexType, SpanningTree> = MinPQIndex::default();
+
+    // adjacency list index to store tuples in the window (i.e., snapshot graph)
+    // Direct approach only traverses outgoing edges, so backward adjacency is not maintained
+    let mut graph = Graph::for_automaton(automaton.into(), OperationType::Direct);
+
+    // stash to collect tuples until progress notification, ordered by the event time they are processed at
+    let mut stash: Stash = BTreeMap::new();
+    // stash maps and buffers of drained tuples are recycled across event times instead of being allocated for each of them
+    let mut stash_pool = StashPool::new();
+    let mut buffer_pool = StashPool::new();
+
+    // the latest event time that is processed, tuples of earlier event times are merged into later ones
+    let mut processed_watermark: Option<u64> = None;
+    // latest expiry of tuples that are inserted while edges of labels outside the alphabet are dropped
+    let mut unretained_until: u64 = 0;
+
+    // process tuples and trees in a sorted order for reproducible runs
+    let deterministic = is_deterministic_mode();
+    // results are stamped with slide ids if they are enabled
+    let window_slides = window_ids();
+
+    // max # of nodes of each spanning tree and the order nodes are evicted in once a tree exceeds it
+    let node_cap = tree_node_cap();
+    let track_extensions = node_cap.map_or(false, |(_, policy)| policy == EvictionPolicy::LeastRecentlyExtended);
+    // # of nodes that are evicted from trees due to the node cap
+    let mut evicted_node_count: u64 = 0;
+
+    // threads of the pool of the process that expire spanning trees in parallel, if they are enabled
+    let delta_pool = DeltaPool::from_config();
+
+    // whether the final state is reported after the input frontier becomes empty, i.e., at the end of stream
+    let mut end_of_stream = false;
+
+    // names of state-size gauges of this operator instance
+    let worker_index = stream.scope().index();
+    let tree_gauge = format!("{}.spanning-trees.{}", output_label, worker_index);
+    let vertex_gauge = format!("{}.vertices.{}", output_label, worker_index);
+    let eviction_gauge = format!("{}.tree-evictions.{}", output_label, worker_index);
+    let mut input_lag = gauges::InputLag::new(&output_label, worker_index);
+    // # of anomaly dumps that the state of this operator instance is dumped for
+    let mut dumped_requests = 0;
+
+    // # of times each transition is traversed, collected only if state counters are enabled
+    let mut transition_visits: Option<TransitionVisits> = if state_counters::is_enabled() {
+        Some(HashMap::with_hasher(BuildHasherDefault::<FxHasher>::default()))
+    } else {
+        None
+    };
+
+    // operator state is not partitioned, so the entire stream is routed to the first worker in multi-worker executions
+    let input_stream = if stream.scope().peers() > 1 {
+        warn!("{} is evaluated on a single worker out of {}", output_label, stream.scope().peers());
+        stream.exchange(|_| 0)
+    } else {
+        stream.clone()
+    };
+
+    // TODO: change communication pact for distributed setup
+    input_stream.unary_notify(Pipeline, "WindowedReachability", vec![], move |input, output, notificator| {
+        // stash incoming tuples for processing after expiry
+
+        while let Some((time, data)) = input.next() {
+            data.swap(&mut vector);
+            input_lag.observe(time.time().event_time());
+
+            for sgt in vector.drain(..) {
+                let tuple_key = (sgt.get_source(), sgt.get_target(), sgt.get_label().to_string());
+                let tuple_interval = sgt.get_interval();
+
+                // tuples are processed at their arrival time, or at their event time in the reordering buffer
+                // a tuple of an already processed event time is merged into the next event time to be processed
+                let event_time = match slack {
+                    None => time.time().event_time(),
+                    Some(slack) => {
+                        let event_time = processed_watermark.map_or(tuple_interval.get_start(), |watermark| max(tuple_interval.get_start(), watermark.saturating_add(1)));
+                        if !stash.contains_key(&event_time) {
+                            // request a notification once the frontier passes the event time by slack
+                            let due_time = max(time.time().clone(), G::Timestamp::upper_bound(event_time.saturating_add(slack)));
+                            notificator.notify_at(time.delayed(&due_time));
+                        }
+                        event_time
+                    }
+                };
+                let time_index = stash.entry(event_time).or_insert_with(|| stash_pool.take());
+
+                // simply stash the tuple, keep max expiry for each value equivelant tuple
+                time_index.entry(tuple_key).and_modify(|current_interval: &mut HalfOpenTimeInterval| {
+                    if current_interval.get_end() < tuple_interval.get_end() {
+                        *current_interval = tuple_interval;
+                    }
+                }).or_insert(tuple_interval);
+            }
+
+            if slack.is_none() {
                 notificator.notify_at(time.retain());
             }
+        }
 
-            // process tuples once TD notifies about a completion of a timestamp
-            // first clean-up the expired state based on the completed time
-            // then retrieve the data from stash, update graph and perform expansion
-            notificator.for_each(|time, _, _| {
-                let mut session = output.session(&time);
-                // perform expiry based on the completed timestamp
-                let low_watermark = *time.time();
+        // process tuples once TD notifies about a completion of a timestamp
+        // for each event time that is due, in order,
+        // first clean-up the expired state based on the event time
+        // then retrieve the data from stash, update graph and perform expansion
+        notificator.for_each(|time, _, _| {
+            let mut session = output.session(&time);
+            let completed_time = time.time().event_time();
+
+            while let Some(event_time) = stash.keys().next().cloned() {
+                if event_time.saturating_add(slack.unwrap_or(0)) > completed_time {
+                    break;
+                }
+                let mut time_index = stash.remove(&event_time).unwrap();
+                processed_watermark = Some(event_time);
+
+                // once swaps are requested, edges of all labels are retained for automata that are swapped in later
+                // a swapped automaton is evaluated over the retained graph, where trees of the previous automaton are dropped
+                // and rebuilt from the retained edges, so windows are warming only until edges that are dropped before retention expire
+                let mut rebuild_trees = false;
+                if hot_swap::is_enabled() {
+                    if !graph.retains_all_labels() {
+                        graph.retain_all_labels();
+                    }
+                    if let Some(automaton) = hot_swap::due_swap(&output_label, worker_index, event_time) {
+                        info!("{} swaps its automaton at {} with {} spanning trees, windows are warming until {}", output_label, event_time, delta_tree_queue.len(), unretained_until);
+                        graph.swap_automaton(automaton.into());
+                        delta_node_index.clear();
+                        delta_tree_queue = MinPQIndex::default();
+                        rebuild_trees = true;
+                        hot_swap::set_warming(&output_label, event_time, unretained_until);
+                    }
+                }
+
+                // perform expiry based on the event time
+                let low_watermark = event_time;
                 debug!("Expiry for timestamp <= {:?}", low_watermark);
 
                 // update the graph
-                graph.remove_edges(low_watermark);
+                let mut expired_edges = 0;
+                graph.remove_edges_with(low_watermark, |_, _, _, _| expired_edges += 1);
+                debug!("{} edges expired at {}", expired_edges, low_watermark);
 
                 // collect all expired tree based on the low watermark
                 let expired_trees: Vec<SpanningTree> = Delta::get_expired_trees(&mut delta_tree_queue, low_watermark).collect();
@@ -95,9 +342,10 @@ impl<G: Scope<Timestamp=u64>> RegularPathQuery<G, StreamingGraphTuple> for Strea
                 // 1. delete all expired nodes
                 // 2. compute the min expiry timestamp of remaining nodes
                 // 3. update trees expiry timestamp, or remove if there is no node remaining
-                expired_trees.into_iter().for_each(|mut tree| {
+                // expired nodes of each tree are deleted in parallel by the delta pool if it is enabled
+                Delta::expire_trees(expired_trees, low_watermark, delta_pool.as_deref()).into_iter().for_each(|(tree, removed_nodes)| {
                     let tree_root = tree.get_root_vertex();
-                    let removed_nodes = tree.expiry(low_watermark);
+                    debug_assert!(tree.validate_invariants().is_empty(), "Spanning tree {} is not valid after expiry: {:?}", tree_root, tree.validate_invariants());
                     // expiry requires differentiated treatment for NT approach
                     //     match approach {
                     //     OperationType::Direct => tree.expiry(low_watermark),
@@ -110,6 +358,9 @@ impl<G: Scope<Timestamp=u64>> RegularPathQuery<G, StreamingGraphTuple> for Strea
                     //     }
                     // };
 
+                    if count_targets && !removed_nodes.is_empty() {
+                        changed_trees.insert(tree_root);
+                    }
                     removed_nodes.iter().for_each(|(to, _expiry_ts)| {
                         // clear up node index
                         Delta::remove_from_node_index(&mut delta_node_index, to.0, to.1, tree_root);
@@ -128,16 +379,30 @@ impl<G: Scope<Timestamp=u64>> RegularPathQuery<G, StreamingGraphTuple> for Strea
                 });
 
                 // temp data structure to maintain tuples that will be used for expansion
-                let mut tuple_to_process = Vec::new();
-                // get input data from stash based on completed timestamp
-                if let Some(mut time_index) = stash.remove(&time.time()) {
-                    // update the graph and flag it for processing in they create larger expiry
-                    for ((source, target, label), interval) in time_index.drain() {
-                        let has_larger_expiry = graph.insert_edge(source, label.clone(), target, interval);
-                        // no need to process the tuple it maps to an existing tuple with already higher expiry timestamp
-                        if has_larger_expiry {
-                            tuple_to_process.push(((source, target, label), interval));
-                        }
+                let mut tuple_to_process: Vec<_> = buffer_pool.take();
+                // trees of a swapped automaton are rebuilt by expanding all edges of the window again
+                if rebuild_trees {
+                    tuple_to_process.extend(graph.edges().into_iter().map(|(source, label, target, interval)| ((source, target, label), interval)));
+                    if deterministic {
+                        tuple_to_process.sort();
+                    }
+                }
+                // get input data of the event time from stash
+                let mut stashed_tuples: Vec<_> = buffer_pool.take();
+                stashed_tuples.extend(time_index.drain());
+                stash_pool.recycle(time_index);
+                if deterministic {
+                    stashed_tuples.sort();
+                }
+                // update the graph and flag it for processing in they create larger expiry
+                for ((source, target, label), interval) in stashed_tuples.drain(..) {
+                    if !graph.retains_all_labels() {
+                        unretained_until = max(unretained_until, interval.get_end());
+                    }
+                    let has_larger_expiry = graph.insert_edge(source, label.clone(), target, interval);
+                    // no need to process the tuple it maps to an existing tuple with already higher expiry timestamp
+                    if has_larger_expiry {
+                        tuple_to_process.push(((source, target, label), interval));
                     }
                 }
 
@@ -145,34 +410,60 @@ impl<G: Scope<Timestamp=u64>> RegularPathQuery<G, StreamingGraphTuple> for Strea
                 for ((source, target, label), interval) in tuple_to_process.drain(..) {
                     debug!("Processing sgt {:?}", (source, target, &label, interval));
                     // iterate over each transition with the given label
-                    let transitions: Vec<(u8, u8)> = graph.get_query_automata().get_transitions(&label);
-                    transitions.into_iter().for_each(|(source_state, target_state)| {
+                    graph.get_query_automata().iter_transitions(&label).for_each(|(source_state, target_state)| {
                         debug!("Transition from {}-{} to {}-{} @ {}", source, source_state, target, target_state, interval);
 
                         // create a spanning tree rooted at source if it does not exists
-                        if source_state == 0 && !Delta::contains(&delta_tree_queue, &source) {
-                            Delta::add_spanning_tree(&mut delta_node_index, &mut delta_tree_queue, source);
+                        if source_state == 0 && endpoints.matches_source(source) && !Delta::contains(&delta_tree_queue, &source) {
+                            let tree = if track_extensions { SpanningTree::with_extension_tracking(source) } else { SpanningTree::new(source) };
+                            Delta::add_spanning_tree(&mut delta_node_index, &mut delta_tree_queue, tree);
                             debug!("Adding spanning tree rooted @ {:?}", source)
                         }
 
                         // invertex-index look-up to find trees that contains the source target-state pair
-                        let updateable_trees: Vec<u64> = Delta::get_updatable_trees(&delta_node_index, source, source_state).collect();
+                        let mut updateable_trees: Vec<u64> = Delta::get_updatable_trees(&delta_node_index, source, source_state).collect();
+                        if deterministic {
+                            updateable_trees.sort();
+                        }
 
                         // expand trees that have the source vertex,  but not the target vertex
                         updateable_trees.into_iter().for_each(|tree_root| {
                             // then insert the target node as a new leaf
                             let mut tree = Delta::get_tree_mut(&mut delta_tree_queue, &tree_root).unwrap();
 
-                            let reachability_results = tree_expand(&mut tree, &mut graph, source, source_state, target, target_state, interval);
+                            let reachability_results = tree_expand(&mut tree, &graph, (source, source_state), (target, target_state), interval, max_depth, transition_visits.as_mut());
                             for (to, node_interval) in reachability_results {
-                                if graph.get_query_automata().is_final_state(to.1) {
-                                    // construct a resulting sgt
-                                    session.give(
-                                        StreamingGraphTuple::new(tree_root, to.0, output_label.clone(), node_interval)
-                                    );
+                                if count_targets && graph.get_query_automata().is_final_state(to.1) {
+                                    changed_trees.insert(tree_root);
+                                } else if graph.get_query_automata().is_final_state(to.1) && endpoints.matches_target(to.0) && self_loops.retains(tree_root, to.0) {
+                                    // construct a resulting sgt, labeled by the tag of its final state if it is tagged
+                                    let label = graph.get_query_automata().final_tag(to.1).map_or_else(|| output_label.clone(), |tag| tag.to_string());
+                                    let mut result = StreamingGraphTuple::new(tree_root, to.0, label, node_interval);
+                                    // the latest edge of the path starts the interval of the result, and the earliest edge ends it as it expires first
+                                    if let Some((window_size, slide_size)) = window_slides {
+                                        result.slide_ids = SlideIds {
+                                            first: node_interval.end.saturating_sub(window_size) / slide_size,
+                                            last: node_interval.start / slide_size,
+                                        };
+                                    }
+                                    session.give(result);
                                 }
                                 Delta::insert_into_node_index(&mut delta_node_index, to.0, to.1, tree_root);
                             }
+                            // bound the size of the tree, nodes are evicted after their results are emitted
+                            if let Some((max_nodes, policy)) = node_cap {
+                                let evicted_nodes = tree.evict(max_nodes, policy);
+                                if !evicted_nodes.is_empty() {
+                                    debug!("{} nodes are evicted from tree {}, {} nodes remain", evicted_nodes.len(), tree_root, tree.len());
+                                }
+                                if count_targets && !evicted_nodes.is_empty() {
+                                    changed_trees.insert(tree_root);
+                                }
+                                for node in evicted_nodes.iter() {
+                                    Delta::remove_from_node_index(&mut delta_node_index, node.0, node.1, tree_root);
+                                }
+                                evicted_node_count += evicted_nodes.len() as u64;
+                            }
                             // get trees updated min timestamp
                             let tree_min_ts = tree.get_min_timestamp();
                             // update tree's priority based on the new timestamp
@@ -180,42 +471,212 @@ impl<G: Scope<Timestamp=u64>> RegularPathQuery<G, StreamingGraphTuple> for Strea
                         });
                     });
                 }
-            });
-        })
+                buffer_pool.recycle(stashed_tuples);
+                buffer_pool.recycle(tuple_to_process);
+
+                // counts of trees whose results change are emitted once all tuples of the event time are processed
+                if count_targets {
+                    let mut roots: Vec<VertexType> = changed_trees.drain().collect();
+                    roots.sort_unstable();
+                    for root in roots {
+                        if let Some((tree, _)) = delta_tree_queue.get(&root) {
+                            let (count, end) = count_reachable_targets(tree, graph.get_query_automata(), &endpoints, self_loops);
+                            if count > 0 {
+                                session.give(StreamingGraphTuple::new(root, count, output_label.clone(), HalfOpenTimeInterval::new(event_time, end)));
+                            }
+                        }
+                    }
+                }
+            }
+        });
+
+        if let Some(transition_visits) = transition_visits.as_mut() {
+            if !transition_visits.is_empty() {
+                state_counters::add(&output_label, transition_visits.drain());
+            }
+        }
+
+        if gauges::is_enabled() {
+            gauges::set(tree_gauge.clone(), delta_tree_queue.len() as u64);
+            gauges::set(vertex_gauge.clone(), graph.node_count() as u64);
+            if node_cap.is_some() {
+                gauges::set(eviction_gauge.clone(), evicted_node_count);
+            }
+            let frontier = notificator.frontier(0).frontier().iter().map(|time| time.event_time()).min();
+            input_lag.record(frontier, stash.values().map(|tuples| tuples.len()).sum());
+        }
+
+        // state is dumped once for each dump that is requested after an anomaly
+        if anomaly::is_enabled() {
+            if let Some(directory) = anomaly::pending_dump(&mut dumped_requests) {
+                let path = directory.join(format!("{}-{}.txt", output_label, worker_index));
+                if let Err(e) = dump_state(&path, &graph, &delta_tree_queue, &stash) {
+                    warn!("Cannot dump state of {} into {}: {}", output_label, path.display(), e);
+                }
+            }
+        }
+
+        // report the final state size once the input is closed
+        if !end_of_stream && notificator.frontier(0).frontier().is_empty() {
+            end_of_stream = true;
+            info!("{} reached the end of stream with {} spanning trees and {} vertices", output_label, delta_tree_queue.len(), graph.node_count());
+            if evicted_node_count > 0 {
+                warn!("{} evicted {} spanning tree nodes due to the node cap, results through evicted nodes are missed", output_label, evicted_node_count);
+            }
+        }
+    })
+}
+
+
+/// returns the # of distinct target vertices of results of the tree that are retained by the endpoints and self-loops,
+/// and the first expiry of a target, i.e., the end of the interval the count is valid for
+fn count_reachable_targets(tree: &SpanningTree, automaton: &DFA, endpoints: &EndpointSets, self_loops: SelfLoops) -> (u64, u64) {
+    let root = tree.get_root_vertex();
+    // a target is reachable until the latest expiry of its nodes in final states
+    let mut target_expiry: HashMap<VertexType, u64, BuildHasherDefault<FxHasher>> = HashMap::default();
+    for ((vertex, state), interval) in tree.nodes() {
+        if automaton.is_final_state(state) && endpoints.matches_target(vertex) && self_loops.retains(root, vertex) {
+            let expiry = target_expiry.entry(vertex).or_insert(0);
+            *expiry = max(*expiry, interval.get_end());
+        }
+    }
+    (target_expiry.len() as u64, target_expiry.values().cloned().min().unwrap_or(u64::MAX))
+}
+
+/// tuples stashed for each event time, keyed by their (source, target, label) with the max validity interval
+type Stash = BTreeMap<u64, HashMap<(VertexType, VertexType, String), HalfOpenTimeInterval, BuildHasherDefault<FxHasher>>>;
+
+/// writes the labels that the graph stores edges of, a snapshot of the graph, a summary of the spanning trees
+/// and the sizes of the stash of an RPQ operator into the given file
+fn dump_state(path: &Path, graph: &Graph, tree_queue: &MinPQIndex<VertexType, SpanningTree>, stash: &Stash) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+
+    match graph.stored_labels() {
+        Some(labels) => {
+            writeln!(file, "# labels: {} labels", labels.len())?;
+            for label in labels {
+                writeln!(file, "{}", label)?;
+            }
+        }
+        None => writeln!(file, "# labels: all")?,
     }
+
+    let mut edges = graph.edges();
+    edges.sort();
+    writeln!(file, "# graph: {} vertices, {} edges", graph.node_count(), edges.len())?;
+    for (source, label, target, interval) in edges {
+        writeln!(file, "{} {} {} {}", source, label, target, interval)?;
+    }
+
+    let mut trees: Vec<(VertexType, usize, u64)> = tree_queue.iter().map(|(root, tree, expiry)| (root, tree.len(), expiry)).collect();
+    trees.sort();
+    writeln!(file, "# delta: {} spanning trees, {} nodes", trees.len(), trees.iter().map(|(_, nodes, _)| nodes).sum::<usize>())?;
+    for (root, nodes, expiry) in trees {
+        writeln!(file, "tree {}: {} nodes, min expiry {}", root, nodes, expiry)?;
+    }
+
+    writeln!(file, "# stash: {} event times, {} tuples", stash.len(), stash.values().map(|tuples| tuples.len()).sum::<usize>())?;
+    for (event_time, tuples) in stash.iter() {
+        writeln!(file, "{}: {} tuples", event_time, tuples.len())?;
+    }
+    file.flush()
+}
+
+/// create minimal DFA for the given regular expression
+pub(crate) fn parse_query(query_str: &str) -> DFA {
+    let rpq_parser = RPQParser::new();
+    rpq_parser.parse_rpq(query_str).unwrap_or_else(|e| panic!("CANNOT create DFA from given RPQ {}: {}", query_str, e))
+}
+
+/// evaluates the RPQ over a static graph of (source, label, target) edges, and returns the sorted (source, target) pairs of paths of at least one edge
+/// The minimal DFA of the query is evaluated over a `StaticGraph` of the edges without a dataflow, e.g., for validation or batch evaluation
+/// panics if the RPQ cannot be parsed
+pub fn evaluate_rpq<'a, I: Iterator<Item=(VertexType, &'a str, VertexType)>>(graph_edges: I, query: &str) -> Vec<(VertexType, VertexType)> {
+    StaticGraph::from_edges(graph_edges).reachability(&parse_query(query))
 }
 
+/// Product graph of an RPQ over a set of edges, to micro-benchmark the graph and `tree_expand` while both remain crate-private
+/// It is not part of the API of the crate, see `benches/core.rs`
+#[doc(hidden)]
+pub struct ProductGraphHook {
+    graph: Graph,
+}
+
+#[doc(hidden)]
+impl ProductGraphHook {
+    pub fn new(query_str: &str, approach: OperationType) -> Self {
+        Self { graph: Graph::for_operation(parse_query(query_str), approach) }
+    }
+
+    pub fn insert_edge(&mut self, source: VertexType, label: String, target: VertexType, interval: HalfOpenTimeInterval) -> bool {
+        self.graph.insert_edge(source, label, target, interval)
+    }
+
+    /// removes edges older than the given timestamp and returns the # of removed edges
+    pub fn remove_edges(&mut self, low_watermark: u64) -> usize {
+        self.graph.remove_edges(low_watermark).len()
+    }
+
+    /// expands a spanning tree rooted at the source of each edge with a transition from the initial state, as the RPQ operator does
+    /// for a window that arrives at once, and returns the # of reachability results
+    pub fn expand_trees(&self) -> usize {
+        let mut trees: HashMap<VertexType, SpanningTree> = HashMap::new();
+        let mut result_count = 0;
+        for (source, label, target, interval) in self.graph.edges() {
+            for (source_state, target_state) in self.graph.get_query_automata().iter_transitions(&label).filter(|(source_state, _)| *source_state == 0) {
+                let tree = trees.entry(source).or_insert_with(|| SpanningTree::new(source));
+                result_count += tree_expand(tree, &self.graph, (source, source_state), (target, target_state), interval, None, None).len();
+            }
+        }
+        result_count
+    }
+}
+
+/// # of times each (source state, target state) transition of the automaton is traversed
+type TransitionVisits = HashMap<(StateType, StateType), u64, BuildHasherDefault<FxHasher>>;
 
 /// Performs expansion on a given SpanningTree by traversing the graph
 /// returns new reachability results in form of a vector of triples (to, from, ts)
 /// If the target node (vertex-state pair) is not in the tree, create new leaf
 /// If the target node already exists, check its expiry timestamp. If the new path leading to larger expiry
 /// propagate changes. Otherwise, stop traversal
-fn tree_expand(tree: &mut SpanningTree, graph: &mut Graph, source_vertex: u64, source_state: u8, target_vertex: u64, target_state: u8, edge_ts: HalfOpenTimeInterval) -> Vec<(VertexStatePair, HalfOpenTimeInterval)> {
+/// If `max_depth` is set, nodes are not attached deeper than `max_depth`, and are re-parented only to paths that are not longer
+fn tree_expand(tree: &mut SpanningTree, graph: &Graph, source: VertexStatePair, target: VertexStatePair, edge_ts: HalfOpenTimeInterval, max_depth: Option<usize>, mut transition_visits: Option<&mut TransitionVisits>) -> Vec<(VertexStatePair, HalfOpenTimeInterval)> {
     // collect results
     let mut reachability_results = Vec::new();
 
     let root_vertex = tree.get_root_vertex();
 
     let mut queue = VecDeque::new();
-    queue.push_back(((source_vertex, source_state), (target_vertex, target_state), edge_ts));
+    queue.push_back((source, target, edge_ts));
 
     while !queue.is_empty() {
         let (node, child, child_ts) = queue.pop_front().unwrap();
         // expand only of parent interval overlaps with incoming edge, or it is a root
         // interval of a node that is direct child of the root is simply the edge (sgt) interval
         if ((tree.get_root_vertex(), 0) == node) | tree.get_vertex(node).map_or(false, |v| v.get_interval().overlaps(&child_ts)) {
+            // length of the path to the child through the node
+            let child_depth = tree.get_depth(node) + 1;
+            if max_depth.map_or(false, |max_depth| child_depth > max_depth) {
+                continue;
+            }
             // expand child nodes that are not reachable and have overlapping timestamp
             if !tree.contains(child) {
                 // it does not exists, so add it to the tree
                 // decide expiry timestamp for the new node
                 let child_node = tree.add_vertex(child.0, child.1, child_ts, node);
                 reachability_results.push((child, child_node.get_interval()));
+                if let Some(visits) = &mut transition_visits {
+                    *visits.entry((node.1, child.1)).or_insert(0) += 1;
+                }
 
                 trace!("Node {:?} created at tree {} with parent {:?} @ {}", child, root_vertex, node, child_node.get_interval());
 
 
-                // add children of this node as potential extensions
+                // add children of this node as potential extensions, unless they would exceed the max depth
+                if max_depth == Some(child_depth) {
+                    continue;
+                }
                 trace!("Check all outgoing edges of {:?} for expansion of tree {}", child, root_vertex);
                 let neighbours = graph.get_outgoing_edges(child.0, child.1);
                 neighbours.filter(|(_, interval)| child_node.get_interval().overlaps(interval)).for_each(|((v, s), interval)| queue.push_back((child, (v, s), interval)));
@@ -235,7 +696,8 @@ fn tree_expand(tree: &mut SpanningTree, graph: &mut Graph, source_vertex: u64, s
                 trace!("Tree {} node {:?} is alternative parent for {:?} with timestamp {} compared to existing one {}", root_vertex, node, child, new_interval, child_node.get_interval());
 
                 // propagate if the the new path has a larger expiry timestamp, and the target is not root
-                if child_node.get_expiry_timestamp() < new_interval.end {
+                // with a max depth, the new path must not be longer so that depths of descendants remain within the bound
+                if child_node.get_expiry_timestamp() < new_interval.end && (max_depth.is_none() || child_depth <= child_node.get_depth()) {
                     trace!("Tree {} node {} is set as parent for {}", root_vertex, node.0, child.0);
 
                     let old_expiry_timestamp = child_node.get_expiry_timestamp();
@@ -252,6 +714,9 @@ fn tree_expand(tree: &mut SpanningTree, graph: &mut Graph, source_vertex: u64, s
 
                     // finally push back to queue so that its children are processed as well
                     reachability_results.push((child, new_interval));
+                    if let Some(visits) = &mut transition_visits {
+                        *visits.entry((node.1, child.1)).or_insert(0) += 1;
+                    }
 
                     // add children of this node as potential extensions
                     //                let neighbours = graph.get_outgoing_edges(child.0, child.1);
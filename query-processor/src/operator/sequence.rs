@@ -0,0 +1,103 @@
+extern crate timely;
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use timely::dataflow::{Scope, Stream};
+use timely::dataflow::channels::pact::Exchange;
+use timely::dataflow::operators::generic::operator::Operator;
+
+use crate::input::tuple::StreamingGraphTuple;
+use crate::operator::rpq::parse_query;
+use crate::util::gauges;
+use crate::util::types::{EventTime, HalfOpenTimeInterval, StateType, VertexType};
+
+/// prefix of a match that ends at a vertex, i.e., a path from `origin` whose labels lead the automaton to `state`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct PartialMatch {
+    origin: VertexType,
+    state: StateType,
+    // start of the first and of the last edge of the path
+    first: u64,
+    last: u64,
+    // earliest expiry of the edges of the path
+    end: u64,
+}
+
+/// Matches temporal sequences of labels, e.g., `a/b/c` for an `a` edge followed by a `b` edge followed by a `c` edge,
+/// where the edges of a path start in strictly increasing order and the last edge starts at most `within` after the first
+/// Unlike RPQ evaluation, where a path is valid as long as the intervals of its edges overlap, the order of edges constrains the match,
+/// so that `a/b` does not match a `b` edge that starts before the `a` edge it follows
+/// A result from the first to the last vertex of a path is valid from the start of its last edge until the earliest expiry of its edges,
+/// and is emitted again only if a later match extends its expiry. Retractions, i.e., tuples that are not appends, are ignored
+/// and all tuples are routed to the first worker
+/// Only the earliest starting partial match is kept for each origin, state and vertex, so that the state is bounded by the # of such triples,
+/// and a later starting one is dropped even if it would stay within the duration longer. `<output_label>.partial-matches.<worker>` reports their #
+pub trait TemporalSequence<G: Scope> where G::Timestamp: EventTime {
+    /// panics if the query cannot be parsed
+    fn temporal_sequence(&self, query_str: &str, within: u64, output_label: String) -> Stream<G, StreamingGraphTuple>;
+}
+
+impl<G: Scope> TemporalSequence<G> for Stream<G, StreamingGraphTuple> where G::Timestamp: EventTime {
+    fn temporal_sequence(&self, query_str: &str, within: u64, output_label: String) -> Stream<G, StreamingGraphTuple> {
+        let automaton = parse_query(query_str);
+        let mut vector = Vec::new();
+
+        // stash to collect tuples of a timestamp until progress notification
+        let mut stash: HashMap<G::Timestamp, Vec<StreamingGraphTuple>> = HashMap::new();
+        // partial matches by the vertex they end at, and by their origin and state
+        let mut partial_matches: HashMap<VertexType, HashMap<(VertexType, StateType), PartialMatch>> = HashMap::new();
+        let partial_match_gauge = format!("{}.partial-matches.{}", output_label, self.scope().index());
+        // end of the interval each result is emitted with
+        let mut advertised: HashMap<(VertexType, VertexType), u64> = HashMap::new();
+
+        self.unary_notify(Exchange::new(|_| 0), "TemporalSequence", vec![], move |input, output, notificator| {
+            while let Some((time, data)) = input.next() {
+                data.swap(&mut vector);
+                stash.entry(time.time().clone()).or_insert_with(Vec::new).extend(vector.drain(..).filter(|sgt| sgt.append && automaton.contains_label(&sgt.label)));
+                notificator.notify_at(time.retain());
+            }
+
+            notificator.for_each(|time, _, _| {
+                let event_time = time.time().event_time();
+                // a partial match cannot be extended once an edge of it expires or its duration is exhausted
+                partial_matches.values_mut().for_each(|matches| matches.retain(|_, partial| partial.end > event_time && partial.first.saturating_add(within) >= event_time));
+                partial_matches.retain(|_, matches| !matches.is_empty());
+                advertised.retain(|_, end| *end > event_time);
+
+                let mut edges = stash.remove(time.time()).unwrap_or_default();
+                // edges extend matches in the order of their start, so that matches of earlier edges of a timestamp are extended by later ones
+                edges.sort_by_key(|sgt| sgt.interval.start);
+
+                let mut results = Vec::new();
+                for sgt in edges {
+                    let start = sgt.interval.start;
+                    let seed = automaton.state_move(0, &sgt.label).map(|state| PartialMatch { origin: sgt.source, state, first: start, last: start, end: sgt.interval.end });
+                    let extended: Vec<PartialMatch> = partial_matches.get(&sgt.source).into_iter().flat_map(|matches| matches.values())
+                        .filter(|partial| partial.last < start && start - partial.first <= within)
+                        .filter_map(|partial| automaton.state_move(partial.state, &sgt.label).map(|state| PartialMatch { origin: partial.origin, state, first: partial.first, last: start, end: partial.end.min(sgt.interval.end) }))
+                        .chain(seed)
+                        .filter(|partial| partial.end > start)
+                        .collect();
+
+                    for partial in extended {
+                        if automaton.is_final_state(partial.state) && advertised.get(&(partial.origin, sgt.target)).map_or(true, |end| *end < partial.end) {
+                            advertised.insert((partial.origin, sgt.target), partial.end);
+                            results.push(StreamingGraphTuple::new(partial.origin, sgt.target, output_label.clone(), HalfOpenTimeInterval::new(start, partial.end)));
+                        }
+                        // keep the earliest starting partial match, then the one whose last edge starts earliest, then the longest-lived one
+                        let current = partial_matches.entry(sgt.target).or_insert_with(HashMap::new).entry((partial.origin, partial.state)).or_insert(partial);
+                        if (partial.first, partial.last, Reverse(partial.end)) < (current.first, current.last, Reverse(current.end)) {
+                            *current = partial;
+                        }
+                    }
+                }
+
+                if gauges::is_enabled() {
+                    gauges::set(partial_match_gauge.clone(), partial_matches.values().map(|matches| matches.len() as u64).sum());
+                }
+                output.session(&time).give_iterator(results.into_iter());
+            });
+        })
+    }
+}
@@ -0,0 +1,125 @@
+//! Per-epoch snapshots of streams for external readers, e.g., monitoring dashboards
+//!
+//! A snapshot captures the tuples of a stream that are valid at a completed time, i.e., the results of a query,
+//! or the content of the window if it is applied to the output of `SlidingWindow`. Snapshots do not capture the internal
+//! state of operators, e.g., the spanning trees of RPQ operators, which anomaly dumps capture instead, see `util::anomaly`.
+extern crate timely;
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use arc_swap::ArcSwap;
+use timely::dataflow::{Scope, Stream};
+use timely::dataflow::channels::pact::Exchange;
+use timely::dataflow::operators::generic::operator::Operator;
+
+use crate::input::SGT;
+use crate::input::tuple::StreamingGraphTuple;
+use crate::util::types::{HalfOpenTimeInterval, VertexType};
+
+/// Consistent view of a stream at a completed time, i.e., tuples whose validity interval contains the `epoch`
+#[derive(Clone, Debug, Default)]
+pub struct Snapshot {
+    pub epoch: u64,
+    pub tuples: Vec<StreamingGraphTuple>,
+}
+
+/// Shared cell holding the latest snapshot of a stream
+/// Snapshots are swapped atomically, so external readers (e.g., monitoring) never observe a partially updated state
+/// and never block the worker thread
+#[derive(Clone)]
+pub struct SnapshotCell {
+    current: Arc<ArcSwap<Snapshot>>,
+}
+
+impl Default for SnapshotCell {
+    fn default() -> Self {
+        Self { current: Arc::new(ArcSwap::from_pointee(Snapshot::default())) }
+    }
+}
+
+impl SnapshotCell {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// returns the latest published snapshot, which remains valid while newer snapshots are published
+    pub fn load(&self) -> Arc<Snapshot> {
+        self.current.load_full()
+    }
+
+    /// returns the epoch of the latest published snapshot
+    pub fn epoch(&self) -> u64 {
+        self.current.load().epoch
+    }
+
+    fn publish(&self, snapshot: Snapshot) {
+        self.current.store(Arc::new(snapshot));
+    }
+}
+
+/// Publishes a snapshot of the stream for each completed time into the given cell
+/// Applied to query results, it exposes results valid at the latest completed slide;
+/// applied to the output of `SlidingWindow`, it exposes the edges of the window, but not the state operators derive from them
+/// All tuples are routed to the first worker, which maintains and publishes snapshots
+pub trait PublishSnapshot<G: Scope<Timestamp=u64>> {
+    fn publish_snapshots(&self, cell: SnapshotCell);
+}
+
+impl<G: Scope<Timestamp=u64>> PublishSnapshot<G> for Stream<G, StreamingGraphTuple> {
+    fn publish_snapshots(&self, cell: SnapshotCell) {
+        let mut vector = Vec::new();
+
+        // stash to collect tuples until their time is completed
+        let mut stash: BTreeMap<u64, Vec<StreamingGraphTuple>> = BTreeMap::new();
+
+        // valid tuples, keep max expiry for each value equivalent tuple
+        let mut valid_tuples: HashMap<(VertexType, VertexType, String), HalfOpenTimeInterval> = HashMap::new();
+
+        let mut last_epoch = None;
+
+        self.sink(Exchange::new(|_| 0), "PublishSnapshot", move |input| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                stash.entry(*time.time()).or_insert_with(Vec::new).extend(vector.drain(..));
+            });
+
+            // completed times are applied in order, each with its own snapshot
+            let mut completed_epochs = Vec::new();
+            while let Some(time) = stash.keys().next().cloned() {
+                if input.frontier().less_equal(&time) {
+                    break;
+                }
+                for sgt in stash.remove(&time).unwrap() {
+                    let interval = sgt.interval;
+                    valid_tuples.entry((sgt.source, sgt.target, sgt.label)).and_modify(|current_interval| {
+                        if current_interval.end < interval.end {
+                            *current_interval = interval;
+                        }
+                    }).or_insert(interval);
+                }
+                completed_epochs.push(time);
+            }
+
+            // times without tuples are completed as the frontier advances, so that expired tuples are dropped
+            if let Some(frontier_time) = input.frontier().frontier().iter().next() {
+                if *frontier_time > 0 && last_epoch.map_or(true, |epoch| epoch < *frontier_time - 1) && completed_epochs.last() != Some(&(*frontier_time - 1)) {
+                    completed_epochs.push(*frontier_time - 1);
+                }
+            }
+
+            for epoch in completed_epochs {
+                valid_tuples.retain(|_, interval| interval.end > epoch);
+
+                let mut tuples: Vec<StreamingGraphTuple> = valid_tuples.iter()
+                    .filter(|(_, interval)| interval.start <= epoch)
+                    .map(|((source, target, label), interval)| StreamingGraphTuple::new(*source, *target, label.clone(), *interval))
+                    .collect();
+                tuples.sort();
+
+                last_epoch = Some(epoch);
+                cell.publish(Snapshot { epoch, tuples });
+            }
+        });
+    }
+}
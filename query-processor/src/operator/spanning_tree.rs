@@ -1,12 +1,28 @@
+use std::collections::VecDeque;
 use std::fmt::Debug;
 use hashbrown::HashMap;
 
 use crate::operator::{MinPQIndex};
 
-use super::super::util::types::{HalfOpenInterval, HalfOpenTimeInterval, VertexStatePair};
+use super::super::util::types::{EvictionPolicy, HalfOpenInterval, HalfOpenTimeInterval, VertexStatePair};
 
 use self::super::tree_node::TreeNode;
 
+/// Violation of a structural invariant of a `SpanningTree`, see `SpanningTree::validate_invariants`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TreeViolation {
+    /// parent of the node is not in the tree, i.e., the node is orphaned
+    MissingParent { node: VertexStatePair, parent: VertexStatePair },
+    /// the node is not among the children of its parent
+    MissingChildLink { node: VertexStatePair, parent: VertexStatePair },
+    /// a child of the node is not in the tree, or its parent is another node
+    DanglingChild { node: VertexStatePair, child: VertexStatePair },
+    /// interval of the node ends after the interval of its parent, so that the node would outlive its only path
+    ExpiresAfterParent { node: VertexStatePair, expiry: u64, parent_expiry: u64 },
+    /// priority of the node in the expiry queue is not the expiry timestamp of its interval
+    PriorityMismatch { node: VertexStatePair, priority: u64, expiry: u64 },
+}
+
 /// SpanningTree implementation based on the `S-PATH` algorithm in PVLDB Submission
 /// Each tree stores all reachable vertices from a given root vertex and the associated automata state
 /// It is backed by a MinPQIndex that stores each node and their expiry timestamp for efficient expiry processing
@@ -15,6 +31,9 @@ pub struct SpanningTree {
     root_vertex: VertexStatePair,
     root_node: TreeNode,
     node_queue: MinPQIndex<VertexStatePair, TreeNode>,
+    // nodes organized by the last time they are extended, maintained only for `EvictionPolicy::LeastRecentlyExtended`
+    extension_queue: Option<MinPQIndex<VertexStatePair, ()>>,
+    extension_clock: u64,
 }
 
 impl SpanningTree {
@@ -23,6 +42,28 @@ impl SpanningTree {
             root_vertex: (root, 0),
             root_node: TreeNode::new(root, 0, HalfOpenTimeInterval::ZERO, HalfOpenTimeInterval::ZERO, None),
             node_queue: MinPQIndex::default(),
+            extension_queue: None,
+            extension_clock: 0,
+        }
+    }
+
+    /// creates a tree that additionally tracks when each node is last extended, for `EvictionPolicy::LeastRecentlyExtended`
+    pub fn with_extension_tracking(root: u64) -> Self {
+        Self { extension_queue: Some(MinPQIndex::default()), ..Self::new(root) }
+    }
+
+    /// marks a node as the most recently extended node, if extensions are tracked
+    fn touch(&mut self, node: VertexStatePair) {
+        if let Some(extension_queue) = self.extension_queue.as_mut() {
+            if node == self.root_vertex {
+                return;
+            }
+            self.extension_clock += 1;
+            if extension_queue.get(&node).is_some() {
+                extension_queue.change_priority(&node, self.extension_clock);
+            } else {
+                extension_queue.push(node, (), self.extension_clock);
+            }
         }
     }
 
@@ -35,13 +76,16 @@ impl SpanningTree {
         };
 
         // timestamp of the new vertex is the min between edge timestamp and parent timestamp
-        let new_vertex = TreeNode::new(vertex, state, node_timestamp, timestamp, Some(parent));
+        let mut new_vertex = TreeNode::new(vertex, state, node_timestamp, timestamp, Some(parent));
+        new_vertex.set_depth(self.get_depth(parent) + 1);
 
         // insert into priority queue
         self.node_queue.push((vertex, state), new_vertex, node_timestamp.end);
 
         // update
         self.add_chilren(parent, (vertex, state));
+        self.touch(parent);
+        self.touch((vertex, state));
 
         // return newly create node
         self.node_queue.get(&(vertex, state)).map(|(entry, _)| entry).unwrap()
@@ -75,7 +119,34 @@ impl SpanningTree {
         };
 
         new_parent_node.add_child(node);
-        self.node_queue.get_mut(&node).map(|(entry, _)| entry).unwrap().set_parent(new_parent, edge_ts);
+        let new_depth = new_parent_node.get_depth() + 1;
+        let tree_node = self.node_queue.get_mut(&node).map(|(entry, _)| entry).unwrap();
+        tree_node.set_parent(new_parent, edge_ts);
+        tree_node.set_depth(new_depth);
+        self.propagate_depth(node);
+        self.touch(new_parent);
+        self.touch(node);
+    }
+
+    /// updates depths of the descendants of the given node after it is re-parented
+    /// subtrees whose root keeps its depth are not traversed, as their depths are already consistent
+    fn propagate_depth(&mut self, node: VertexStatePair) {
+        let mut queue = VecDeque::new();
+        queue.push_back(node);
+        while let Some(parent) = queue.pop_front() {
+            let (child_depth, children): (usize, Vec<VertexStatePair>) = match self.get_vertex(parent) {
+                Some(parent_node) => (parent_node.get_depth() + 1, parent_node.get_children().cloned().collect()),
+                None => continue,
+            };
+            for child in children {
+                if let Some(child_node) = self.get_vertex_mut(child) {
+                    if child_node.get_depth() != child_depth {
+                        child_node.set_depth(child_depth);
+                        queue.push_back(child);
+                    }
+                }
+            }
+        }
     }
 
     /// returns a reference to tree node for the given vertex-state pair
@@ -88,6 +159,15 @@ impl SpanningTree {
         self.node_queue.get_mut(&pair).map(|(entry, _)| entry)
     }
 
+    /// returns the length of the path from the root to the given node, where depths of descendants are updated when a node is re-parented
+    pub fn get_depth(&self, pair: VertexStatePair) -> usize {
+        if pair == self.root_vertex {
+            0
+        } else {
+            self.get_vertex(pair).map_or(0, |node| node.get_depth())
+        }
+    }
+
     /// return the root vertex of the tree
     pub fn get_root_vertex(&self) -> u64 {
         self.root_vertex.0
@@ -108,31 +188,65 @@ impl SpanningTree {
         self.node_queue.is_empty()
     }
 
+    /// returns the # of nodes of the tree, excluding the root
+    pub fn len(&self) -> usize {
+        self.node_queue.len()
+    }
+
 
     /// update the priority of a node in the expiry queue
     pub fn update_node_expiry(&mut self, node: VertexStatePair, new_timestamp: u64) {
         self.node_queue.change_priority(&node, new_timestamp);
     }
 
+    /// iterates over the nodes of the tree except its root with their validity intervals
+    pub fn nodes(&self) -> impl Iterator<Item=(VertexStatePair, HalfOpenTimeInterval)> + '_ {
+        self.node_queue.iter().map(|(node, tree_node, _)| (node, tree_node.get_interval()))
+    }
+
     /// return the current minimum timestamp living on this tree
     pub fn get_min_timestamp(&self) -> u64 {
         self.node_queue.peek().map_or(u64::MAX, |(_, _, expiry_timestamp)| expiry_timestamp)
     }
 
-    /// removes a single node from the tree
-    pub fn remove_node(&mut self, node: VertexStatePair) -> (VertexStatePair, HalfOpenTimeInterval) {
-        // remove the node from the tree
-        let tree_node = self.node_queue.remove(&node).map(|(entry, _)| entry).unwrap();
+    /// checks the structure of the tree, i.e., parent and children pointers, intervals and expiry queue priorities, and returns all violations
+    /// Starts of intervals are not compared, as re-parenting a node may move its start forward without updating its descendants
+    /// It traverses the entire tree, so that it is meant for debug builds and tests
+    pub fn validate_invariants(&self) -> Vec<TreeViolation> {
+        let mut violations = Vec::new();
+
+        self.root_node.get_children().for_each(|child| match self.get_vertex(*child) {
+            Some(child_node) if child_node.get_parent() == Some(self.root_vertex) => {}
+            _ => violations.push(TreeViolation::DanglingChild { node: self.root_vertex, child: *child }),
+        });
+
+        for (node, tree_node, priority) in self.node_queue.iter() {
+            let expiry = tree_node.get_expiry_timestamp();
+            if priority != expiry {
+                violations.push(TreeViolation::PriorityMismatch { node, priority, expiry });
+            }
 
-        // remove the node from its parent's children
-        let parent_pair = tree_node.get_parent().unwrap();
-        if let Some(parent) = self.node_queue.get_mut(&parent_pair).map(|(entry, _)| entry) {
-            parent.remove_child(node);
-        } else {
-            self.root_node.remove_child(node);
+            let parent = tree_node.get_parent().unwrap();
+            let parent_node = if parent == self.root_vertex { Some(&self.root_node) } else { self.get_vertex(parent) };
+            match parent_node {
+                None => violations.push(TreeViolation::MissingParent { node, parent }),
+                Some(parent_node) => {
+                    if !parent_node.get_children().any(|child| *child == node) {
+                        violations.push(TreeViolation::MissingChildLink { node, parent });
+                    }
+                    if parent != self.root_vertex && expiry > parent_node.get_expiry_timestamp() {
+                        violations.push(TreeViolation::ExpiresAfterParent { node, expiry, parent_expiry: parent_node.get_expiry_timestamp() });
+                    }
+                }
+            }
+
+            tree_node.get_children().for_each(|child| match self.get_vertex(*child) {
+                Some(child_node) if child_node.get_parent() == Some(node) => {}
+                _ => violations.push(TreeViolation::DanglingChild { node, child: *child }),
+            });
         }
 
-        (node, tree_node.get_interval())
+        violations
     }
 
     /// performs expiry given a low_watermark
@@ -167,6 +281,9 @@ impl SpanningTree {
 
             // finally remove the tree node from node_queue
             if let Some((_, expired_node, _timestamp)) = self.node_queue.pop() {
+                if let Some(extension_queue) = self.extension_queue.as_mut() {
+                    extension_queue.remove(&node);
+                }
                 expiry_candidates.insert((expired_node.get_vertex(), expired_node.get_state()), expired_node);
             }
         }
@@ -179,5 +296,154 @@ impl SpanningTree {
 
         removed_results
     }
+
+    /// evicts nodes along with their subtrees in the order of the given policy until the tree has at most `max_nodes` nodes
+    /// returns evicted nodes, whose descendants are evicted as well since their paths go through the evicted node
+    /// `LeastRecentlyExtended` falls back to `LowestExpiry` if the tree does not track extensions
+    pub fn evict(&mut self, max_nodes: usize, policy: EvictionPolicy) -> Vec<VertexStatePair> {
+        let mut evicted_nodes = Vec::new();
+
+        while self.node_queue.len() > max_nodes {
+            let candidate = match (policy, &self.extension_queue) {
+                (EvictionPolicy::LeastRecentlyExtended, Some(extension_queue)) => extension_queue.peek().map(|(node, _, _)| node),
+                _ => self.node_queue.peek().map(|(node, _, _)| node),
+            };
+            match candidate {
+                Some(node) => self.remove_subtree(node, &mut evicted_nodes),
+                None => break,
+            }
+        }
+
+        evicted_nodes
+    }
+
+    /// removes a node and all of its descendants from the tree, and appends them to `removed_nodes`
+    fn remove_subtree(&mut self, node: VertexStatePair, removed_nodes: &mut Vec<VertexStatePair>) {
+        // detach the subtree from the parent of its root
+        let parent_pair = self.node_queue.get(&node).map(|(entry, _)| entry).unwrap().get_parent().unwrap();
+        if parent_pair == self.root_vertex {
+            self.root_node.remove_child(node);
+        } else {
+            self.node_queue.get_mut(&parent_pair).map(|(entry, _)| entry).unwrap().remove_child(node);
+        }
+
+        let mut queue = VecDeque::new();
+        queue.push_back(node);
+        while let Some(node) = queue.pop_front() {
+            if let Some((tree_node, _)) = self.node_queue.remove(&node) {
+                tree_node.get_children().for_each(|child| queue.push_back(*child));
+                if let Some(extension_queue) = self.extension_queue.as_mut() {
+                    extension_queue.remove(&node);
+                }
+                removed_nodes.push(node);
+            }
+        }
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng, XorShiftRng};
+
+    use crate::operator::spanning_tree::SpanningTree;
+    use crate::util::types::{EvictionPolicy, HalfOpenInterval, HalfOpenTimeInterval, VertexStatePair};
+
+    #[test]
+    fn evict_subtrees() {
+        // 0 -> 1 -> 2, 0 -> 3, where 1 expires first but 3 is extended first
+        let mut tree = SpanningTree::with_extension_tracking(0);
+        tree.add_vertex(3, 1, HalfOpenTimeInterval::new(0, 30), (0, 0));
+        tree.add_vertex(1, 1, HalfOpenTimeInterval::new(0, 10), (0, 0));
+        tree.add_vertex(2, 1, HalfOpenTimeInterval::new(0, 20), (1, 1));
+
+        let mut lowest_expiry = tree.clone();
+        let mut evicted = lowest_expiry.evict(1, EvictionPolicy::LowestExpiry);
+        evicted.sort();
+        assert_eq!(evicted, vec![(1, 1), (2, 1)]);
+        assert!(lowest_expiry.contains((3, 1)));
+        assert_eq!(lowest_expiry.get_root_node().get_children().count(), 1);
+
+        let evicted = tree.evict(2, EvictionPolicy::LeastRecentlyExtended);
+        assert_eq!(evicted, vec![(3, 1)]);
+        assert_eq!(tree.len(), 2);
+        assert!(tree.evict(2, EvictionPolicy::LeastRecentlyExtended).is_empty());
+    }
+
+    #[test]
+    fn depths_follow_shorter_paths() {
+        // 0 -> 1 -> 2 -> 3 -> 4, where a shorter path 0 -> 2 arrives later
+        let mut tree = SpanningTree::new(0);
+        tree.add_vertex(1, 1, HalfOpenTimeInterval::new(0, 10), (0, 0));
+        tree.add_vertex(2, 1, HalfOpenTimeInterval::new(0, 10), (1, 1));
+        tree.add_vertex(3, 1, HalfOpenTimeInterval::new(0, 10), (2, 1));
+        tree.add_vertex(4, 1, HalfOpenTimeInterval::new(0, 10), (3, 1));
+        assert_eq!(tree.get_depth((4, 1)), 4);
+
+        tree.update_parent((2, 1), (0, 0), HalfOpenTimeInterval::new(1, 20));
+        assert_eq!((tree.get_depth((2, 1)), tree.get_depth((3, 1)), tree.get_depth((4, 1))), (1, 2, 3));
+        assert_eq!(tree.get_depth((1, 1)), 1);
+
+        // re-parenting onto a longer path deepens the subtree again
+        tree.update_parent((2, 1), (1, 1), HalfOpenTimeInterval::new(1, 20));
+        assert_eq!((tree.get_depth((2, 1)), tree.get_depth((3, 1)), tree.get_depth((4, 1))), (2, 3, 4));
+        assert!(tree.validate_invariants().is_empty());
+    }
+
+    /// returns true if `ancestor` is on the path from the root to `node`
+    fn is_ancestor(tree: &SpanningTree, ancestor: VertexStatePair, node: VertexStatePair) -> bool {
+        let mut current = Some(node);
+        while let Some(pair) = current {
+            if pair == ancestor {
+                return true;
+            }
+            current = tree.get_vertex(pair).and_then(|tree_node| tree_node.get_parent());
+        }
+        false
+    }
+
+    #[test]
+    fn random_insert_expire_sequences() {
+        for seed in 1..20 {
+            let mut rng = XorShiftRng::from_seed([seed, 7, 13, 31]);
+            let mut tree = SpanningTree::with_extension_tracking(0);
+            let mut low_watermark = 0;
+
+            for _ in 0..200 {
+                let nodes: Vec<VertexStatePair> = std::iter::once((0, 0)).chain(tree.node_queue.iter().map(|(node, _, _)| node)).collect();
+                let parent = nodes[rng.gen_range(0, nodes.len())];
+                let child = (rng.gen_range(1, 30), rng.gen_range(0, 3));
+                let start = low_watermark + rng.gen_range(0, 5);
+                let edge_ts = HalfOpenTimeInterval::new(start, start + rng.gen_range(1, 20));
+
+                match rng.gen_range(0, 10) {
+                    // expand the tree as `tree_expand` does, either with a new leaf or a longer-lived parent
+                    0..=6 => {
+                        if child == (0, 0) || (parent != (0, 0) && !tree.get_vertex(parent).unwrap().get_interval().overlaps(&edge_ts)) {
+                            continue;
+                        }
+                        if !tree.contains(child) {
+                            tree.add_vertex(child.0, child.1, edge_ts, parent);
+                        } else if !is_ancestor(&tree, child, parent) {
+                            let new_interval = if parent == (0, 0) { edge_ts } else { HalfOpenTimeInterval::intersect(&edge_ts, &tree.get_vertex(parent).unwrap().get_interval()) };
+                            if tree.get_vertex(child).unwrap().get_expiry_timestamp() < new_interval.end {
+                                tree.update_parent(child, parent, edge_ts);
+                                tree.get_vertex_mut(child).unwrap().set_interval(new_interval);
+                                tree.update_node_expiry(child, new_interval.end);
+                            }
+                        }
+                    }
+                    7..=8 => {
+                        low_watermark += rng.gen_range(1, 4);
+                        let removed_nodes = tree.expiry(low_watermark);
+                        assert!(removed_nodes.iter().all(|(node, interval)| !tree.contains(*node) && interval.get_end() <= low_watermark));
+                    }
+                    _ => {
+                        tree.evict(rng.gen_range(1, 10), EvictionPolicy::LeastRecentlyExtended);
+                    }
+                }
+
+                assert_eq!(tree.validate_invariants(), vec![], "Invalid tree with seed {}", seed);
+            }
+        }
+    }
+}
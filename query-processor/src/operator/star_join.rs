@@ -0,0 +1,90 @@
+extern crate timely;
+
+use std::collections::HashMap;
+
+use timely::dataflow::{Scope, Stream};
+use timely::dataflow::channels::pact::Exchange;
+use timely::dataflow::operators::{Concatenate, Map};
+use timely::dataflow::operators::generic::operator::Operator;
+
+use crate::input::tuple::{StarTuple, StreamingGraphTuple};
+use crate::util::types::{EventTime, HalfOpenInterval, HalfOpenTimeInterval, VertexType};
+
+/// Joins k streams on the source of their tuples, i.e., the center of a star-shaped pattern, in a single operator
+/// instead of k-1 chained binary joins, and emits one `StarTuple` for each combination of one tuple of each stream
+/// whose intervals overlap, with the targets of the tuples as leaves in the order of the streams and the intersection of their intervals
+/// A combination is emitted once its last tuple arrives, and again if a value equivalent tuple extends its expiry
+/// Retractions, i.e., tuples that are not appends, are ignored
+pub trait StarJoin<G: Scope> where G::Timestamp: EventTime {
+    /// panics if no stream is given
+    fn star_join(&self, streams: Vec<Stream<G, StreamingGraphTuple>>, output_label: String) -> Stream<G, StarTuple>;
+}
+
+impl<G: Scope> StarJoin<G> for G where G::Timestamp: EventTime {
+    fn star_join(&self, streams: Vec<Stream<G, StreamingGraphTuple>>, output_label: String) -> Stream<G, StarTuple> {
+        assert!(!streams.is_empty(), "Star join requires at least one stream");
+        let arity = streams.len();
+        let indexed_streams: Vec<Stream<G, (usize, StreamingGraphTuple)>> = streams.into_iter().enumerate()
+            .map(|(index, stream)| stream.map(move |sgt| (index, sgt)))
+            .collect();
+        let mut vector = Vec::new();
+
+        // stash to collect tuples of a timestamp until progress notification
+        let mut stash: HashMap<G::Timestamp, Vec<(usize, StreamingGraphTuple)>> = HashMap::new();
+        // interval of the tuples of each stream by center and leaf, keep max expiry for each value equivalent tuple
+        let mut indexes: Vec<HashMap<VertexType, HashMap<VertexType, HalfOpenTimeInterval>>> = vec![HashMap::new(); arity];
+
+        // all tuples of a center are joined by the same worker
+        let exchange = Exchange::new(|(_, sgt): &(usize, StreamingGraphTuple)| sgt.source);
+
+        self.concatenate(indexed_streams).unary_notify(exchange, "StarJoin", vec![], move |input, output, notificator| {
+            while let Some((time, data)) = input.next() {
+                data.swap(&mut vector);
+                stash.entry(time.time().clone()).or_insert_with(Vec::new).extend(vector.drain(..).filter(|(_, sgt)| sgt.append));
+                notificator.notify_at(time.retain());
+            }
+
+            notificator.for_each(|time, _, _| {
+                let event_time = time.time().event_time();
+                for index in indexes.iter_mut() {
+                    index.values_mut().for_each(|leaves| leaves.retain(|_, interval| interval.end > event_time));
+                    index.retain(|_, leaves| !leaves.is_empty());
+                }
+
+                let mut results = Vec::new();
+                // tuples are inserted one at a time, so that each combination is joined when its last tuple is inserted
+                for (stream, sgt) in stash.remove(time.time()).unwrap_or_default() {
+                    let current = indexes[stream].entry(sgt.source).or_insert_with(HashMap::new).entry(sgt.target).or_insert(HalfOpenTimeInterval::ZERO);
+                    if current.end >= sgt.interval.end {
+                        continue;
+                    }
+                    *current = sgt.interval;
+
+                    let mut combinations = vec![(Vec::with_capacity(arity), HalfOpenTimeInterval::new(0, u64::MAX))];
+                    for (other, index) in indexes.iter().enumerate() {
+                        let leaves: Vec<(VertexType, HalfOpenTimeInterval)> = if other == stream {
+                            vec![(sgt.target, sgt.interval)]
+                        } else {
+                            index.get(&sgt.source).map_or_else(Vec::new, |leaves| leaves.iter().map(|(leaf, interval)| (*leaf, *interval)).collect())
+                        };
+                        combinations = combinations.into_iter()
+                            .flat_map(|(prefix, prefix_interval)| leaves.iter().filter_map(move |(leaf, interval)| {
+                                let intersection = HalfOpenTimeInterval::intersect(&prefix_interval, interval);
+                                if intersection.start >= intersection.end {
+                                    return None;
+                                }
+                                let mut combination = prefix.clone();
+                                combination.push(*leaf);
+                                Some((combination, intersection))
+                            }))
+                            .collect();
+                    }
+
+                    results.extend(combinations.into_iter().map(|(leaves, interval)| StarTuple::new(sgt.source, leaves, output_label.clone(), interval)));
+                }
+
+                output.session(&time).give_iterator(results.into_iter());
+            });
+        })
+    }
+}
@@ -13,6 +13,8 @@ pub struct TreeNode {
     timestamp: HalfOpenTimeInterval,
     incoming_edge_ts: HalfOpenTimeInterval,
     parent: Option<VertexStatePair>,
+    // length of the path from the root when the node is attached or re-parented
+    depth: usize,
     children: HashSet<VertexStatePair, BuildHasherDefault<FxHasher>>,
 }
 
@@ -20,10 +22,10 @@ impl TreeNode {
     pub fn new(vertex: u64, state: u8, timestamp: HalfOpenTimeInterval, incoming_edge_ts: HalfOpenTimeInterval, parent: Option<VertexStatePair>) -> Self {
         match parent {
             Some(parent_node) => {
-                Self { node: (vertex, state), timestamp, incoming_edge_ts, parent: Some(parent_node), children: HashSet::with_hasher(BuildHasherDefault::<FxHasher>::default()) }
+                Self { node: (vertex, state), timestamp, incoming_edge_ts, parent: Some(parent_node), depth: 0, children: HashSet::with_hasher(BuildHasherDefault::<FxHasher>::default()) }
             }
             None => {
-                Self { node: (vertex, state), timestamp, incoming_edge_ts, parent: None, children: HashSet::with_hasher(BuildHasherDefault::<FxHasher>::default()) }
+                Self { node: (vertex, state), timestamp, incoming_edge_ts, parent: None, depth: 0, children: HashSet::with_hasher(BuildHasherDefault::<FxHasher>::default()) }
             }
         }
     }
@@ -67,6 +69,15 @@ impl TreeNode {
         self.parent
     }
 
+    /// returns the length of the path from the root, the root has depth 0
+    pub fn get_depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn set_depth(&mut self, depth: usize) {
+        self.depth = depth;
+    }
+
     pub fn get_incoming_edge_ts(&self) -> HalfOpenTimeInterval {
         self.incoming_edge_ts
     }
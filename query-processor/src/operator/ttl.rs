@@ -0,0 +1,75 @@
+extern crate timely;
+
+use std::cmp::{max, min};
+use std::collections::HashMap;
+
+use timely::dataflow::{Scope, Stream};
+use timely::dataflow::channels::pact::Exchange;
+use timely::dataflow::operators::generic::operator::Operator;
+
+use crate::input::tuple::StreamingGraphTuple;
+use crate::util::types::{EventTime, HalfOpenTimeInterval, VertexType};
+
+/// Limits how long results are advertised independently of the window size, i.e., the validity interval of each result
+/// is rewritten to `[start, min(end, start + ttl))`
+/// Retractions, i.e., tuples that are not appends, are clamped to the advertisement of the result they retract,
+/// and retractions of results whose advertisement has already ended are dropped, as consumers have expired them already
+pub trait ResultTTL<G: Scope> where G::Timestamp: EventTime {
+    /// panics if `ttl` is zero
+    fn result_ttl(&self, ttl: u64) -> Stream<G, StreamingGraphTuple>;
+}
+
+impl<G: Scope> ResultTTL<G> for Stream<G, StreamingGraphTuple> where G::Timestamp: EventTime {
+    fn result_ttl(&self, ttl: u64) -> Stream<G, StreamingGraphTuple> {
+        assert!(ttl > 0, "Result TTL has to be positive");
+        let mut vector = Vec::new();
+
+        // stash to collect tuples of a timestamp until progress notification
+        let mut stash: HashMap<G::Timestamp, Vec<StreamingGraphTuple>> = HashMap::new();
+        // end of the advertisement of each result that has not expired yet
+        let mut advertised: HashMap<(VertexType, VertexType, String), u64> = HashMap::new();
+
+        // a result and its retractions are processed by the same worker
+        let exchange = Exchange::new(|sgt: &StreamingGraphTuple| sgt.source);
+
+        self.unary_notify(exchange, "ResultTTL", vec![], move |input, output, notificator| {
+            while let Some((time, data)) = input.next() {
+                data.swap(&mut vector);
+                stash.entry(time.time().clone()).or_insert_with(Vec::new).extend(vector.drain(..));
+                notificator.notify_at(time.retain());
+            }
+
+            // timestamps are processed in order, so that retractions find the advertisement of earlier results
+            notificator.for_each(|time, _, _| {
+                if let Some(batch) = stash.remove(time.time()) {
+                    // results of a timestamp are advertised before its retractions are applied
+                    let (appends, retractions): (Vec<_>, Vec<_>) = batch.into_iter().partition(|sgt| sgt.append);
+                    let mut session = output.session(&time);
+
+                    for mut sgt in appends {
+                        let end = min(sgt.interval.end, sgt.interval.start.saturating_add(ttl));
+                        sgt.interval = HalfOpenTimeInterval::new(sgt.interval.start, end);
+                        let current = advertised.entry((sgt.source, sgt.target, sgt.label.clone())).or_insert(end);
+                        *current = max(*current, end);
+                        session.give(sgt);
+                    }
+
+                    for mut sgt in retractions {
+                        let key = (sgt.source, sgt.target, sgt.label.clone());
+                        if let Some(end) = advertised.get(&key).cloned() {
+                            if sgt.interval.start < end {
+                                advertised.remove(&key);
+                                sgt.interval = HalfOpenTimeInterval::new(sgt.interval.start, min(sgt.interval.end, end));
+                                session.give(sgt);
+                            }
+                        }
+                    }
+                }
+
+                // results whose advertisement ended by the completed timestamp have expired downstream
+                let event_time = time.time().event_time();
+                advertised.retain(|_, end| *end > event_time);
+            });
+        })
+    }
+}
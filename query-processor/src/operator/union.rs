@@ -0,0 +1,23 @@
+extern crate timely;
+
+use timely::dataflow::{Scope, Stream};
+use timely::dataflow::operators::{Concatenate, Map};
+
+use crate::input::tuple::{StreamingGraphTuple, TaggedTuple};
+
+/// Merges result streams of several standing queries into a single stream,
+/// where each tuple is tagged with the query it originates from and keeps its validity interval,
+/// so that a single pipeline can serve multiple queries to one consumer
+pub trait UnionWithTag<G: Scope> {
+    fn union_with_tag<I: IntoIterator<Item=(String, Stream<G, StreamingGraphTuple>)>>(&self, streams: I) -> Stream<G, TaggedTuple>;
+}
+
+impl<G: Scope> UnionWithTag<G> for G {
+    fn union_with_tag<I: IntoIterator<Item=(String, Stream<G, StreamingGraphTuple>)>>(&self, streams: I) -> Stream<G, TaggedTuple> {
+        let tagged_streams: Vec<Stream<G, TaggedTuple>> = streams.into_iter()
+            .map(|(tag, stream)| stream.map(move |sgt| TaggedTuple::new(tag.clone(), sgt)))
+            .collect();
+
+        self.concatenate(tagged_streams)
+    }
+}
@@ -6,8 +6,27 @@ use timely::dataflow::channels::pact::Pipeline;
 use timely::dataflow::operators::generic::operator::Operator;
 
 use self::super::super::input::{SGE, SGT, StreamingGraphEdge};
-use self::super::super::input::tuple::StreamingGraphTuple;
-use self::super::super::util::types::HalfOpenTimeInterval;
+use self::super::super::input::tuple::{SlideIds, StreamingGraphTuple};
+use self::super::super::util::types::{HalfOpenTimeInterval, window_ids};
+
+/// Window semantics shared by SGA and DD pipelines, which define when an edge expires
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum WindowSemantics {
+    /// an edge is valid for `window_size` after its timestamp
+    Sliding { window_size: u64 },
+    /// an edge is valid until the end of the `window_size` aligned period its timestamp is in
+    Tumbling { window_size: u64 },
+}
+
+impl WindowSemantics {
+    /// expiry timestamp of an edge with the given timestamp, the edge is valid in `[timestamp, expiry)`
+    pub fn expiry(&self, timestamp: u64) -> u64 {
+        match self {
+            WindowSemantics::Sliding { window_size } => timestamp + window_size,
+            WindowSemantics::Tumbling { window_size } => (timestamp / window_size + 1) * window_size,
+        }
+    }
+}
 
 /// `WSCAN` operator bsaed on PVLDB Submission
 /// It consumes a stream of StreamingGraphEdge's and produces a stream of StreamingGraphTuple's
@@ -16,22 +35,36 @@ pub trait SlidingWindow<G: Scope, D: Data + SGE, D2: Data + SGT<HalfOpenTimeInte
     /// Produces a streaming graph tuple from given inut graph edge
     /// and adjusts its validity interval based on given window_size parameters
     fn sliding_window(&self, window_size: u64) -> Stream<G, D2>;
+
+    /// Produces a streaming graph tuple from given input graph edge
+    /// and adjusts its validity interval based on given window semantics
+    fn windowed(&self, semantics: WindowSemantics) -> Stream<G, D2>;
 }
 
 
 impl<G: Scope> SlidingWindow<G, StreamingGraphEdge, StreamingGraphTuple> for Stream<G, StreamingGraphEdge> {
     /// Adjust the validity interval of te given input
     fn sliding_window(&self, window_size: u64) -> Stream<G, StreamingGraphTuple> {
+        self.windowed(WindowSemantics::Sliding { window_size })
+    }
+
+    fn windowed(&self, semantics: WindowSemantics) -> Stream<G, StreamingGraphTuple> {
         let mut vector = Vec::new();
+        // tuples are stamped with the id of their slide if slide ids are enabled
+        let slide_size = window_ids().map(|(_, slide_size)| slide_size);
         self.unary(Pipeline, "SlidingWindow", move |_, _| move |input, output| {
             input.for_each(|time, data| {
                 data.swap(&mut vector);
                 output.session(&time)
                     .give_iterator(vector.drain(..)
-                        .map(|input_edge|
-                            StreamingGraphTuple::from_edge(&input_edge,
-                                                           HalfOpenTimeInterval::new(input_edge.get_timestamp(), input_edge.get_timestamp() + window_size))
-                        ));
+                        .map(|input_edge| {
+                            let mut sgt = StreamingGraphTuple::from_edge(&input_edge,
+                                                                         HalfOpenTimeInterval::new(input_edge.get_timestamp(), semantics.expiry(input_edge.get_timestamp())));
+                            if let Some(slide_size) = slide_size {
+                                sgt.slide_ids = SlideIds::of_slide(input_edge.get_timestamp() / slide_size);
+                            }
+                            sgt
+                        }));
             });
         })
     }
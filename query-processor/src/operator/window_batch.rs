@@ -0,0 +1,50 @@
+extern crate timely;
+
+use std::collections::HashMap;
+
+use timely::dataflow::{Scope, Stream};
+use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::operators::generic::operator::Operator;
+
+use crate::input::tuple::StreamingGraphTuple;
+use crate::util::types::EventTime;
+
+/// Batches results of a stream per window and emits each batch at once when the window closes
+/// Each emitted sgt carries the id of the window, i.e., the event time of the completed timestamp, in its `window_id` field
+/// so that downstream consumers can detect window boundaries deterministically
+pub trait WindowBatch<G: Scope> where G::Timestamp: EventTime {
+    /// Emits tuples of each window as a single batch once the window is complete
+    /// tuples in a batch are sorted if `sorted` is set, otherwise they are emitted in arrival order
+    fn window_batch(&self, sorted: bool) -> Stream<G, StreamingGraphTuple>;
+}
+
+impl<G: Scope> WindowBatch<G> for Stream<G, StreamingGraphTuple> where G::Timestamp: EventTime {
+    fn window_batch(&self, sorted: bool) -> Stream<G, StreamingGraphTuple> {
+        let mut vector = Vec::new();
+
+        // stash to collect tuples of a window until progress notification
+        let mut stash: HashMap<G::Timestamp, Vec<StreamingGraphTuple>> = HashMap::new();
+
+        self.unary_notify(Pipeline, "WindowBatch", vec![], move |input, output, notificator| {
+            while let Some((time, data)) = input.next() {
+                data.swap(&mut vector);
+                stash.entry(time.time().clone()).or_insert_with(Vec::new).extend(vector.drain(..));
+                notificator.notify_at(time.retain());
+            }
+
+            // emit the entire batch once the window is complete
+            notificator.for_each(|time, _, _| {
+                if let Some(mut batch) = stash.remove(time.time()) {
+                    let window_id = time.time().event_time();
+                    if sorted {
+                        batch.sort();
+                    }
+                    output.session(&time).give_iterator(batch.into_iter().map(|mut sgt| {
+                        sgt.window_id = window_id;
+                        sgt
+                    }));
+                }
+            });
+        })
+    }
+}
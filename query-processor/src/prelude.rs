@@ -0,0 +1,31 @@
+//! Stable user-facing surface of the crate
+//!
+//! `use sgraffito_query::prelude::*;` brings input sources, streaming graph operators, tuple types,
+//! query libraries and the harness run API into scope.
+pub use crate::input::{GraphEdge, InputFileReader, InputPartitioner, InputStreamKind, LineFileReader, Partitioning, Punctuate, SGE, SGT, StreamEvent, StreamingGraphEdge, VertexDictionary, VertexEncoding};
+pub use crate::input::tuple::{GroupedResult, ResultLabel, StarTuple, StreamingGraphTuple, TaggedTuple};
+pub use crate::operator::{EndpointSets, SelfLoops};
+pub use crate::operator::alerts::ChangeAlerts;
+pub use crate::operator::degree::{DegreeAction, DegreeFilter, DegreeThresholds};
+pub use crate::operator::group::GroupBySource;
+pub use crate::operator::hash_join::{HashJoinAttributePair, JoinLabel, SymmetricHashJoin};
+pub use crate::operator::neighborhood::KHopNeighborhood;
+pub use crate::operator::rpq::{evaluate_rpq, RegularPathQuery};
+pub use crate::operator::sequence::TemporalSequence;
+pub use crate::operator::snapshot::{PublishSnapshot, Snapshot, SnapshotCell};
+pub use crate::operator::star_join::StarJoin;
+pub use crate::operator::ttl::ResultTTL;
+pub use crate::operator::union::UnionWithTag;
+pub use crate::operator::window::{SlidingWindow, WindowSemantics};
+pub use crate::operator::window_batch::WindowBatch;
+pub use crate::query::parser::RPQParser;
+pub use crate::query::query_library::{RPQLibrary, SGAQueryLibrary};
+#[cfg(feature = "dd")]
+pub use crate::query::query_library::DDQueryLibrary;
+#[cfg(feature = "dd")]
+pub use crate::dd::{reachability, transitive_closure, Direction, SharedArrangements, WindowedInput};
+pub use crate::sink::{ResultSink, SinkTo};
+pub use crate::harness::{run_sga_query, RunControl, WindowResults};
+#[cfg(feature = "dd")]
+pub use crate::harness::run_dd_query;
+pub use crate::util::types::{EventTime, HalfOpenInterval, HalfOpenTimeInterval, VertexType};
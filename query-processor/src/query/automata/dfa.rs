@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 /// DFA implementation where each transition is deterministic, i.e., there is at most one target node for each transition
 #[derive(Debug, Clone)]
@@ -9,6 +9,8 @@ pub struct DFA {
     forward_transitions: Vec<Vec<(String, u8)>>,
     backward_transitions: Vec<Vec<(String, u8)>>,
     pub alphabet: HashSet<String>,
+    // output labels of final states, e.g., of a combined automaton for several queries
+    final_tags: HashMap<u8, String>,
 }
 
 impl DFA {
@@ -20,9 +22,36 @@ impl DFA {
             forward_transitions: vec![Vec::new(); num_states as usize],
             backward_transitions: vec![Vec::new(); num_states as usize],
             alphabet: HashSet::new(),
+            final_tags: HashMap::new(),
         }
     }
 
+    /// adds a new state without transitions and returns it, e.g., for automata that are built incrementally
+    /// panics if the automaton already has the max # of states
+    pub fn add_state(&mut self, is_final: bool) -> u8 {
+        assert!(self.num_states < u8::MAX, "DFA cannot have more than {} states", u8::MAX);
+        let state = self.num_states;
+        self.num_states += 1;
+        self.forward_transitions.push(Vec::new());
+        self.backward_transitions.push(Vec::new());
+        if is_final {
+            self.final_states.insert(state);
+        }
+        state
+    }
+
+    /// tags the given final state, so that results reaching it carry the tag as their label
+    /// panics if the state is not a final state
+    pub fn set_final_tag(&mut self, state: u8, tag: String) {
+        assert!(self.is_final_state(state), "Only final states can be tagged, {} is not a final state", state);
+        self.final_tags.insert(state, tag);
+    }
+
+    /// returns the tag of the given final state, if it is tagged
+    pub fn final_tag(&self, state: u8) -> Option<&str> {
+        self.final_tags.get(&state).map(|tag| tag.as_str())
+    }
+
 
     /// Updates the transition graph of the automata
     pub fn add_transition(&mut self, source_state: u8, target_state: u8, label: String) {
@@ -49,17 +78,32 @@ impl DFA {
 
     /// Given a label in the alphabet, return an iterator of state-pairs that corresponds to given label
     pub fn get_transitions(&self, label: &str) -> Vec<(u8, u8)> {
-        self.transitions.get(label).iter().flat_map(|transition| transition.iter()).cloned().collect()
+        self.iter_transitions(label).collect()
     }
 
     /// Given a state, retrieve all label-state pairs (transitions) that originates from the given state
     pub fn get_outgoing_transitions(&self, state: u8) -> Vec<(String, u8)> {
-        self.forward_transitions[state as usize].iter().cloned().collect()
+        self.iter_outgoing_transitions(state).map(|(label, target)| (label.to_string(), target)).collect()
     }
 
     /// Retrieve all label-state pairs (transitions) that leads to given state
     pub fn get_incoming_transitions(&self, state: u8) -> Vec<(String, u8)> {
-        self.backward_transitions[state as usize].iter().cloned().collect()
+        self.iter_incoming_transitions(state).map(|(label, source)| (label.to_string(), source)).collect()
+    }
+
+    /// iterates over state-pairs of the transitions with the given label without collecting them
+    pub fn iter_transitions<'a>(&'a self, label: &str) -> impl Iterator<Item=(u8, u8)> + 'a {
+        self.transitions.get(label).into_iter().flat_map(|transitions| transitions.iter().cloned())
+    }
+
+    /// iterates over label-state pairs of the transitions that originate from the given state, borrowing labels from the automaton
+    pub fn iter_outgoing_transitions(&self, state: u8) -> impl Iterator<Item=(&str, u8)> + '_ {
+        self.forward_transitions[state as usize].iter().map(|(label, target)| (label.as_str(), *target))
+    }
+
+    /// iterates over label-state pairs of the transitions that lead to the given state, borrowing labels from the automaton
+    pub fn iter_incoming_transitions(&self, state: u8) -> impl Iterator<Item=(&str, u8)> + '_ {
+        self.backward_transitions[state as usize].iter().map(|(label, source)| (label.as_str(), *source))
     }
 
     pub fn state_move(&self, state: u8, label: &str) -> Option<u8> {
@@ -83,6 +127,73 @@ impl DFA {
         self.alphabet.get(&label)
     }
 
+    /// projection of the automaton onto the given labels, e.g., the labels registered in the stream, which drops transitions with other labels
+    /// together with states that become unreachable from the start state or cannot reach a final state anymore
+    /// States are renumbered in the order they are reached from the start state, which remains 0 even if the projection accepts nothing
+    pub fn project(&self, labels: &HashSet<String>) -> DFA {
+        self.prune(|label| labels.contains(label))
+    }
+
+    /// removes dead states, i.e., states from which no final state is reachable, and states that are unreachable from the start state,
+    /// so that they are not explored in the product graph. States are renumbered as in `project`
+    pub fn trim(&self) -> DFA {
+        self.prune(|_| true)
+    }
+
+    /// automaton with the transitions whose label is kept, without the states that are unreachable or dead over them
+    fn prune<F: Fn(&str) -> bool>(&self, keep: F) -> DFA {
+        let keep = &keep;
+        let kept_outgoing = |state: u8| self.iter_outgoing_transitions(state).filter(move |(label, _)| keep(*label));
+
+        // states reachable from the start state in the order they are reached
+        let mut reachable = vec![false; self.num_states as usize];
+        let mut order = vec![0];
+        let mut queue = VecDeque::from(vec![0]);
+        reachable[0] = true;
+        while let Some(state) = queue.pop_front() {
+            for (_, target) in kept_outgoing(state) {
+                if !reachable[target as usize] {
+                    reachable[target as usize] = true;
+                    order.push(target);
+                    queue.push_back(target);
+                }
+            }
+        }
+
+        // reachable states that can reach a final state
+        let mut useful = vec![false; self.num_states as usize];
+        let mut stack: Vec<u8> = order.iter().cloned().filter(|state| self.is_final_state(*state)).collect();
+        stack.iter().for_each(|state| useful[*state as usize] = true);
+        while let Some(state) = stack.pop() {
+            for (label, source) in self.iter_incoming_transitions(state) {
+                if keep(label) && reachable[source as usize] && !useful[source as usize] {
+                    useful[source as usize] = true;
+                    stack.push(source);
+                }
+            }
+        }
+
+        let mut renumbering = vec![None; self.num_states as usize];
+        let kept_states: Vec<u8> = order.into_iter().filter(|state| *state == 0 || useful[*state as usize]).collect();
+        kept_states.iter().enumerate().for_each(|(new_state, state)| renumbering[*state as usize] = Some(new_state as u8));
+
+        let final_states = kept_states.iter().filter(|state| self.is_final_state(**state)).map(|state| renumbering[*state as usize].unwrap()).collect();
+        let mut projection = DFA::new(kept_states.len() as u8, final_states);
+        for state in kept_states.iter() {
+            let source = renumbering[*state as usize].unwrap();
+            for (label, target) in kept_outgoing(*state) {
+                if let Some(target) = renumbering[target as usize].filter(|_| useful[target as usize]) {
+                    projection.add_transition(source, target, label.to_string());
+                }
+            }
+            if let Some(tag) = self.final_tag(*state) {
+                projection.set_final_tag(source, tag.to_string());
+            }
+        }
+
+        projection
+    }
+
     /// Returns true if given word, i.e, a vector of alphabet characters
     /// panics if given word has characters that are not part of the alphabet
     pub fn accept(&self, word: Vec<&str>) -> bool {
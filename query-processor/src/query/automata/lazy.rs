@@ -0,0 +1,107 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::iter::FromIterator;
+
+use crate::query::automata::dfa::DFA;
+use crate::query::automata::nfa::NFA;
+
+/// DFA that is determinized lazily from an NFA, as an alternative to the upfront subset construction of `determinize`
+/// that can blow up for large alphabets
+/// Subset-states are materialized only for labels that are observed in the stream: observing a new label materializes
+/// its transitions from all states, and each subset-state that is reached for the first time is materialized over all observed labels.
+/// Materialized states and transitions are cached, so that the DFA over the observed labels only grows and states keep their numbers.
+/// The DFA is not minimized, as minimization would renumber states that are already in use
+#[derive(Debug, Clone)]
+pub struct LazyDFA {
+    nfa: NFA,
+    dfa: DFA,
+    // subset of NFA states of each DFA state, indexed by the DFA state
+    subsets: Vec<BTreeSet<u8>>,
+    subset_ids: HashMap<BTreeSet<u8>, u8>,
+    observed_labels: Vec<String>,
+}
+
+impl LazyDFA {
+    /// creates a lazy DFA that only has the start state, i.e., the epsilon-closure of the NFA start state
+    pub fn new(nfa: NFA) -> Self {
+        let start_subset = BTreeSet::from_iter(nfa.get_epsilon_closure(0).into_iter());
+        let mut automaton = Self {
+            dfa: DFA::new(0, Default::default()),
+            nfa,
+            subsets: Vec::new(),
+            subset_ids: HashMap::new(),
+            observed_labels: Vec::new(),
+        };
+        automaton.add_subset(start_subset);
+        automaton
+    }
+
+    /// DFA over the labels observed so far
+    pub fn dfa(&self) -> &DFA {
+        &self.dfa
+    }
+
+    /// returns true if the label is in the alphabet of the NFA, i.e., it may be observed
+    pub fn contains_label(&self, label: &str) -> bool {
+        self.nfa.contains_label(label)
+    }
+
+    /// returns true if the label is already observed
+    pub fn is_observed(&self, label: &str) -> bool {
+        self.observed_labels.iter().any(|observed| observed == label)
+    }
+
+    /// materializes the transitions of the given label, and returns true if the label is new and in the alphabet of the NFA
+    /// Observed labels are part of the alphabet of the DFA even if no materialized state has a transition with them yet
+    pub fn observe(&mut self, label: &str) -> bool {
+        if !self.contains_label(label) || self.is_observed(label) {
+            return false;
+        }
+        self.observed_labels.push(label.to_string());
+        self.dfa.alphabet.insert(label.to_string());
+
+        // existing states are materialized for the new label only, and new states for all observed labels
+        let mut queue: VecDeque<(u8, bool)> = (0..self.dfa.num_states).map(|state| (state, false)).collect();
+        while let Some((state, is_new)) = queue.pop_front() {
+            let labels: Vec<String> = if is_new { self.observed_labels.clone() } else { vec![label.to_string()] };
+            for label in labels {
+                if let Some((target, is_new_target)) = self.materialize(state, &label) {
+                    if is_new_target {
+                        queue.push_back((target, true));
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    /// materializes the transition of the state with the given label, and returns its target
+    /// and whether the target is a new state, or None if the state has no transition with the label
+    fn materialize(&mut self, state: u8, label: &str) -> Option<(u8, bool)> {
+        let mut target_subset = BTreeSet::new();
+        for nfa_state in self.subsets[state as usize].iter() {
+            if let Some(targets) = self.nfa.state_move(*nfa_state, label) {
+                targets.iter().for_each(|target| target_subset.extend(self.nfa.get_epsilon_closure(*target)));
+            }
+        }
+        if target_subset.is_empty() {
+            return None;
+        }
+
+        let (target, is_new) = match self.subset_ids.get(&target_subset) {
+            Some(target) => (*target, false),
+            None => (self.add_subset(target_subset), true),
+        };
+        self.dfa.add_transition(state, target, label.to_string());
+        Some((target, is_new))
+    }
+
+    /// adds a DFA state for the given subset of NFA states, which is final if the subset has a final NFA state
+    fn add_subset(&mut self, subset: BTreeSet<u8>) -> u8 {
+        let is_final = subset.iter().any(|state| self.nfa.is_final_state(*state));
+        let state = self.dfa.add_state(is_final);
+        self.subset_ids.insert(subset.clone(), state);
+        self.subsets.push(subset);
+        state
+    }
+}
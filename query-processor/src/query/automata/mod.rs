@@ -1,5 +1,6 @@
 use std::cmp::{max, min};
-use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::convert::TryFrom;
 use std::iter::FromIterator;
 
 use itertools::Itertools;
@@ -8,6 +9,8 @@ use crate::query::automata::{dfa::DFA, nfa::NFA};
 
 pub mod nfa;
 pub mod dfa;
+pub mod lazy;
+pub mod regex;
 
 /// A set of helper functions to build NFA, used for NFA construction from a given regular expression
 /// based on the Thompson's construction algorithm
@@ -141,13 +144,14 @@ pub fn kleene_plus(input: NFA) -> NFA {
 /// https://en.wikipedia.org/wiki/Powerset_construction
 pub fn determinize(input: NFA) -> DFA {
     // ste of states in the new DFA, these are constructed from
-    let mut dfa_states = HashSet::new();
+    // ordered so that the resulting state numbering does not depend on hash iteration order
+    let mut dfa_states = BTreeSet::new();
     //create a start state by taking e-closure of the original start state
     let start_state = BTreeSet::from_iter(input.get_epsilon_closure(0).into_iter());
     dfa_states.insert(start_state.clone());
 
     // transition matrix of the final DFA
-    let mut transitions: HashMap<BTreeSet<u8>, HashMap<String, BTreeSet<u8>>> = HashMap::new();
+    let mut transitions: BTreeMap<BTreeSet<u8>, BTreeMap<String, BTreeSet<u8>>> = BTreeMap::new();
 
 
     let mut state_queue = VecDeque::new();
@@ -156,11 +160,11 @@ pub fn determinize(input: NFA) -> DFA {
     // while there are new more states to be processed
     while let Some(next_subset) = state_queue.pop_front() {
         // transitions for this state
-        let state_transitions = transitions.entry(next_subset.clone()).or_insert(HashMap::new());
+        let state_transitions = transitions.entry(next_subset.clone()).or_insert(BTreeMap::new());
         // apply move for each possible input symbol
         next_subset.iter().for_each(|state| {
-            input.get_outgoing_transitions(*state).iter().cloned().for_each(|(label, target_states)| {
-                let reachable_states = state_transitions.entry(label).or_insert(BTreeSet::new());
+            input.iter_outgoing_transitions(*state).for_each(|(label, target_states)| {
+                let reachable_states = state_transitions.entry(label.to_string()).or_insert(BTreeSet::new());
                 // update reachable states for given transition based on epsilon closure
                 target_states.iter().for_each(|target_state| input.get_epsilon_closure(*target_state).iter().for_each(|t| {
                     reachable_states.insert(*t);
@@ -227,13 +231,18 @@ pub fn determinize(input: NFA) -> DFA {
 /// minimizes the given DFA using Hopcroft's algorithm (https://en.wikipedia.org/wiki/DFA_minimization)
 /// It relies on  equivalence classes of the Myhill–Nerode equivalence relation
 /// it starts with two coarse partitions of final and non-final states and refines partitions based on transitions
-/// until no further refinement is possible
+/// until no further refinement is possible, final states with distinct tags start in distinct partitions so that tags are preserved
 pub fn minimize(input: DFA) -> DFA {
     // create initial partitions based on final and non-final states
     let final_states = BTreeSet::from_iter(input.final_states.iter().cloned());
     let non_final_states = BTreeSet::from_iter((0..input.num_states).filter(|state| !final_states.contains(state)));
 
-    let mut next_partitions = vec![non_final_states, final_states.clone()];
+    let mut tagged_final_states: BTreeMap<Option<&str>, BTreeSet<u8>> = BTreeMap::new();
+    for state in &final_states {
+        tagged_final_states.entry(input.final_tag(*state)).or_insert_with(BTreeSet::new).insert(*state);
+    }
+    let mut next_partitions = vec![non_final_states];
+    next_partitions.extend(tagged_final_states.into_iter().map(|(_, states)| states));
     let mut partitions = Vec::new();
 
     // iterate over no more partition can be generated
@@ -306,10 +315,19 @@ pub fn minimize(input: DFA) -> DFA {
 
     //create resulting automata
     let mut result_automata = DFA::new(next_state_no, minimized_dfa_final_states);
+    for (state, mapped_state) in &state_mapping {
+        if let Some(tag) = input.final_tag(*state) {
+            result_automata.set_final_tag(*mapped_state, tag.to_string());
+        }
+    }
 
-    // move transitions to new automata
-    for label in &input.alphabet {
-        for (source_state, target_state) in input.get_transitions(label) {
+    // move transitions to new automata, labels are sorted for a deterministic transition order
+    let mut alphabet: Vec<&String> = input.alphabet.iter().collect();
+    alphabet.sort();
+    for label in alphabet {
+        let mut label_transitions: Vec<(u8, u8)> = input.iter_transitions(label).collect();
+        label_transitions.sort();
+        for (source_state, target_state) in label_transitions {
             let source_mapping = state_mapping.get(&source_state).unwrap();
             let target_mapping = state_mapping.get(&target_state).unwrap();
             result_automata.add_transition(*source_mapping, *target_mapping, label.clone());
@@ -319,6 +337,54 @@ pub fn minimize(input: DFA) -> DFA {
     result_automata
 }
 
+/// specializes the given DFA for a known prefix of its words, e.g., a selective first predicate that is evaluated separately
+/// Matches of the prefix are represented by a single transition with the `anchor_label` from the start state
+/// into the state reached after the prefix, and only states reachable from there are kept
+/// returns None if the DFA does not accept any word that starts with the prefix, or if the specialized DFA has more than `u8::MAX` states
+pub fn specialize(input: &DFA, prefix: &[&str], anchor_label: &str) -> Option<DFA> {
+    let mut anchor_state = 0;
+    for label in prefix {
+        anchor_state = input.state_move(anchor_state, label)?;
+    }
+
+    // number reachable states in BFS order after the new start state, labels are sorted for a deterministic numbering
+    let mut state_mapping = BTreeMap::new();
+    state_mapping.insert(anchor_state, 1);
+    let mut transitions = Vec::new();
+    let mut state_queue = VecDeque::new();
+    state_queue.push_back(anchor_state);
+    while let Some(state) = state_queue.pop_front() {
+        let mut outgoing_transitions: Vec<(&str, u8)> = input.iter_outgoing_transitions(state).collect();
+        outgoing_transitions.sort();
+        for (label, target_state) in outgoing_transitions {
+            if !state_mapping.contains_key(&target_state) {
+                let mapped_state = u8::try_from(state_mapping.len() + 1).ok()?;
+                state_mapping.insert(target_state, mapped_state);
+                state_queue.push_back(target_state);
+            }
+            transitions.push((state, label, target_state));
+        }
+    }
+
+    let final_states = state_mapping.iter()
+        .filter(|(state, _)| input.is_final_state(**state))
+        .map(|(_, mapped_state)| *mapped_state)
+        .collect();
+
+    let mut result_automata = DFA::new(u8::try_from(state_mapping.len() + 1).ok()?, final_states);
+    for (state, mapped_state) in &state_mapping {
+        if let Some(tag) = input.final_tag(*state) {
+            result_automata.set_final_tag(*mapped_state, tag.to_string());
+        }
+    }
+    result_automata.add_transition(0, 1, anchor_label.to_string());
+    for (source_state, label, target_state) in transitions {
+        result_automata.add_transition(state_mapping[&source_state], state_mapping[&target_state], label.to_string());
+    }
+
+    Some(result_automata)
+}
+
 /// helper function to check equivelance classes during DFA minimization
 /// automata: the original DFA
 /// partitions: a partitioning of DFA states, where each partition is a subset of the original DFA
@@ -365,8 +431,8 @@ fn move_transitions(source: &NFA, target: &mut NFA, offset: u8) {
 
     // carry over all labeled transitions
     for state in 0..source.num_states {
-        source.get_outgoing_transitions(state)
-            .iter().flat_map(move |(label, target_states)|
+        source.iter_outgoing_transitions(state)
+            .flat_map(move |(label, target_states)|
             target_states.iter().map(move |target_state| (label, *target_state))
         ).for_each(|(label, target_sate)|
             target.add_transition(state + offset, target_sate + offset, label.to_string())
@@ -375,9 +441,9 @@ fn move_transitions(source: &NFA, target: &mut NFA, offset: u8) {
 
     //carry over all epsilon transitions
     for state in 0..source.num_states {
-        source.get_epsilon_transitions(state).iter()
+        source.iter_epsilon_transitions(state)
             .for_each(|target_state| {
-                target.add_epsilon_transition(state + offset, *target_state + offset)
+                target.add_epsilon_transition(state + offset, target_state + offset)
             });
     }
 }
@@ -388,8 +454,9 @@ mod tests {
     use std::collections::HashSet;
     use std::iter::FromIterator;
 
-    use crate::query::automata::{alternation, concatenation, determinize, kleene_plus, kleene_star, minimize, transition};
+    use crate::query::automata::{alternation, concatenation, determinize, kleene_plus, kleene_star, minimize, specialize, transition};
     use crate::query::automata::dfa::DFA;
+    use crate::query::automata::regex::RegexTree;
 
     #[test]
     fn test_transition() {
@@ -576,4 +643,151 @@ mod tests {
 
         assert!(minimized.num_states <= dfa_states);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_minimize_tagged() {
+        // a | b, where final states of `a` and `b` are tagged with distinct output labels
+        let mut dfa = DFA::new(3, HashSet::<u8>::from_iter(vec![1, 2].into_iter()));
+        dfa.add_transition(0, 1, "a".to_string());
+        dfa.add_transition(0, 2, "b".to_string());
+        assert_eq!(minimize(dfa.clone()).num_states, 2);
+
+        dfa.set_final_tag(1, "qa".to_string());
+        dfa.set_final_tag(2, "qb".to_string());
+        let minimized = minimize(dfa);
+        assert_eq!(minimized.num_states, 3);
+
+        let state_a = minimized.state_move(0, "a").unwrap();
+        let state_b = minimized.state_move(0, "b").unwrap();
+        assert_eq!(minimized.final_tag(state_a), Some("qa"));
+        assert_eq!(minimized.final_tag(state_b), Some("qb"));
+    }
+
+    #[test]
+    fn test_glushkov() {
+        let label = |l: &str| Box::new(RegexTree::Label(l.to_string()));
+        // (a/b)* | a+/c
+        let tree = RegexTree::Alternation(
+            Box::new(RegexTree::KleeneStar(Box::new(RegexTree::Concatenation(label("a"), label("b"))))),
+            Box::new(RegexTree::Concatenation(Box::new(RegexTree::KleenePlus(label("a"))), label("c"))),
+        );
+
+        let glushkov = tree.glushkov();
+        assert_eq!(glushkov.num_states as usize, tree.label_count() + 1);
+        assert!((0..glushkov.num_states).all(|state| glushkov.get_epsilon_transitions(state).is_empty()));
+
+        let dfa = minimize(determinize(glushkov));
+        let thompson = minimize(determinize(tree.thompson()));
+        assert_eq!(dfa.num_states, thompson.num_states);
+        for word in vec![vec![], vec!["a", "b"], vec!["a", "b", "a", "b"], vec!["a", "c"], vec!["a", "a", "c"]] {
+            assert!(dfa.accept(word));
+        }
+        for word in vec![vec!["a"], vec!["c"], vec!["a", "b", "c"], vec!["b", "a"]] {
+            assert!(!dfa.accept(word));
+        }
+    }
+
+    #[test]
+    fn test_specialize() {
+        let a = transition("a".to_string());
+        let b = transition("b".to_string());
+        let c = transition("c".to_string());
+        // a/b/c*
+        let dfa = minimize(determinize(concatenation(concatenation(a, b), kleene_star(c))));
+
+        let specialized = specialize(&dfa, &["a", "b"], "ab").unwrap();
+        assert!(specialized.accept(vec!["ab"]));
+        assert!(specialized.accept(vec!["ab", "c", "c"]));
+        assert!(!specialized.contains_label("a"));
+
+        assert!(specialize(&dfa, &["b"], "b").is_none());
+    }
+
+    #[test]
+    fn test_project() {
+        let a = transition("a".to_string());
+        let b = transition("b".to_string());
+        let c = transition("c".to_string());
+        // a/b* | c/a
+        let dfa = minimize(determinize(alternation(concatenation(a.clone(), kleene_star(b)), concatenation(c, a))));
+
+        // the c/a branch is unreachable without c transitions
+        let projected = dfa.project(&["a", "b"].iter().map(|label| label.to_string()).collect());
+        assert_eq!(projected.num_states, 2);
+        assert!(projected.accept(vec!["a", "b", "b"]));
+        assert!(!projected.contains_label("c"));
+
+        // no final state is reachable without a transitions, so only the start state remains
+        let projected = dfa.project(&["c"].iter().map(|label| label.to_string()).collect());
+        assert_eq!(projected.num_states, 1);
+        assert!(projected.alphabet.is_empty());
+        assert!(projected.final_states.is_empty());
+    }
+
+    #[test]
+    fn test_trim() {
+        // b leads to a dead state with a c loop, and state 3 is unreachable
+        let mut dfa = DFA::new(4, HashSet::from_iter(vec![1, 3]));
+        dfa.add_transition(0, 1, "a".to_string());
+        dfa.add_transition(0, 2, "b".to_string());
+        dfa.add_transition(2, 2, "c".to_string());
+        dfa.add_transition(3, 1, "a".to_string());
+
+        let trimmed = dfa.trim();
+        assert_eq!(trimmed.num_states, 2);
+        assert_eq!(trimmed.get_outgoing_transitions(0), vec![("a".to_string(), 1)]);
+        assert!(trimmed.is_final_state(1));
+        assert!(!trimmed.contains_label("b") && !trimmed.contains_label("c"));
+    }
+
+    #[test]
+    fn test_iterating_transitions() {
+        let mut dfa = DFA::new(2, HashSet::from_iter(vec![1]));
+        dfa.add_transition(0, 1, "a".to_string());
+        dfa.add_transition(1, 1, "b".to_string());
+        assert_eq!(dfa.iter_outgoing_transitions(1).collect::<Vec<_>>(), vec![("b", 1)]);
+        assert_eq!(dfa.iter_incoming_transitions(1).collect::<Vec<_>>(), vec![("a", 0), ("b", 1)]);
+        assert_eq!(dfa.iter_transitions("a").collect::<Vec<_>>(), vec![(0, 1)]);
+        assert_eq!(dfa.iter_transitions("c").count(), 0);
+
+        // iterators borrow the transitions that accessors collect
+        let nfa = concatenation(kleene_star(alternation(transition("a".to_string()), transition("b".to_string()))), transition("c".to_string()));
+        let dfa = minimize(determinize(nfa.clone()));
+        for label in nfa.alphabet.iter() {
+            let mut transitions: Vec<(u8, u8)> = nfa.iter_transitions(label).collect();
+            let mut collected = nfa.get_transitions(label);
+            transitions.sort();
+            collected.sort();
+            assert_eq!(transitions, collected);
+
+            let mut transitions: Vec<(u8, u8)> = dfa.iter_transitions(label).collect();
+            let mut collected = dfa.get_transitions(label);
+            transitions.sort();
+            collected.sort();
+            assert_eq!(transitions, collected);
+        }
+        for state in 0..nfa.num_states {
+            assert_eq!(nfa.iter_epsilon_transitions(state).collect::<Vec<_>>(), nfa.get_epsilon_transitions(state));
+            assert_eq!(nfa.iter_outgoing_transitions(state).map(|(label, targets)| (label.to_string(), targets.to_vec())).collect::<Vec<_>>(), nfa.get_outgoing_transitions(state));
+            assert_eq!(nfa.iter_incoming_transitions(state).map(|(label, sources)| (label.to_string(), sources.to_vec())).collect::<Vec<_>>(), nfa.get_incoming_transitions(state));
+        }
+        for state in 0..dfa.num_states {
+            assert_eq!(dfa.iter_outgoing_transitions(state).map(|(label, target)| (label.to_string(), target)).collect::<Vec<_>>(), dfa.get_outgoing_transitions(state));
+            assert_eq!(dfa.iter_incoming_transitions(state).map(|(label, source)| (label.to_string(), source)).collect::<Vec<_>>(), dfa.get_incoming_transitions(state));
+        }
+    }
+
+    #[test]
+    fn test_specialize_bounds_states() {
+        // a chain of the max # of states, whose specialization adds a start state
+        let mut chain = DFA::new(u8::MAX, HashSet::from_iter(vec![u8::MAX - 1]));
+        for state in 0..u8::MAX - 1 {
+            chain.add_transition(state, state + 1, "x".to_string());
+        }
+        assert!(specialize(&chain, &[], "anchor").is_none());
+
+        let specialized = specialize(&chain, &["x"], "anchor").unwrap();
+        assert_eq!(specialized.num_states, u8::MAX);
+        assert!(specialized.accept(std::iter::once("anchor").chain(std::iter::repeat("x").take(u8::MAX as usize - 2)).collect()));
+    }
+}
@@ -70,11 +70,11 @@ impl NFA {
 
     /// Given a label in the alphabet, return an iterator of state-pairs that corresponds to given label
     pub fn get_transitions(&self, label: &str) -> Vec<(u8, u8)> {
-        self.transitions.get(label).iter().flat_map(|transition| transition.iter()).cloned().collect()
+        self.iter_transitions(label).collect()
     }
 
     pub fn get_epsilon_transitions(&self, state: u8) -> Vec<u8> {
-        self.epsilon_transitions[state as usize].iter().cloned().collect()
+        self.iter_epsilon_transitions(state).collect()
     }
 
     /// Given a state, retrieve all label-state pairs (transitions) that originates from the given state
@@ -87,6 +87,26 @@ impl NFA {
         self.backward_transitions[state as usize].iter().cloned().collect()
     }
 
+    /// iterates over state-pairs of the transitions with the given label without collecting them
+    pub fn iter_transitions<'a>(&'a self, label: &str) -> impl Iterator<Item=(u8, u8)> + 'a {
+        self.transitions.get(label).into_iter().flat_map(|transitions| transitions.iter().cloned())
+    }
+
+    /// iterates over target states of epsilon transitions of the given state
+    pub fn iter_epsilon_transitions(&self, state: u8) -> impl Iterator<Item=u8> + '_ {
+        self.epsilon_transitions[state as usize].iter().cloned()
+    }
+
+    /// iterates over label-states pairs of the transitions that originate from the given state, borrowing them from the automaton
+    pub fn iter_outgoing_transitions(&self, state: u8) -> impl Iterator<Item=(&str, &[u8])> + '_ {
+        self.forward_transitions[state as usize].iter().map(|(label, targets)| (label.as_str(), targets.as_slice()))
+    }
+
+    /// iterates over label-states pairs of the transitions that lead to the given state, borrowing them from the automaton
+    pub fn iter_incoming_transitions(&self, state: u8) -> impl Iterator<Item=(&str, &[u8])> + '_ {
+        self.backward_transitions[state as usize].iter().map(|(label, sources)| (label.as_str(), sources.as_slice()))
+    }
+
     pub fn state_move(&self, state: u8, label: &str) -> Option<Vec<u8>> {
         self.forward_transitions[state as usize].iter()
             .find(|(l, _targets)| l == label)
@@ -163,8 +183,8 @@ impl NFA {
             e_closure.insert(current_state);
 
             // traverse its neighbours
-            self.get_epsilon_transitions(current_state).iter().filter(|neighbour| !e_closure.contains(neighbour))
-                .for_each(|neighbour| queue.push_back(*neighbour));
+            self.iter_epsilon_transitions(current_state).filter(|neighbour| !e_closure.contains(neighbour))
+                .for_each(|neighbour| queue.push_back(neighbour));
         }
 
         // return epsilon closure of the state
@@ -0,0 +1,132 @@
+use std::collections::{BTreeSet, HashSet};
+
+use crate::query::automata::{alternation, concatenation, kleene_plus, kleene_star, transition};
+use crate::query::automata::nfa::NFA;
+
+/// Construction algorithm to obtain an NFA from a regular expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NFAConstruction {
+    /// Thompson's construction, which introduces epsilon transitions for each operator
+    Thompson,
+    /// Glushkov's position automaton, which is epsilon-free and has one state per label occurrence plus the start state
+    Glushkov,
+}
+
+impl Default for NFAConstruction {
+    fn default() -> Self {
+        NFAConstruction::Thompson
+    }
+}
+
+/// Syntax tree of a regular expression over edge labels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegexTree {
+    Label(String),
+    Concatenation(Box<RegexTree>, Box<RegexTree>),
+    Alternation(Box<RegexTree>, Box<RegexTree>),
+    KleeneStar(Box<RegexTree>),
+    KleenePlus(Box<RegexTree>),
+}
+
+impl RegexTree {
+    /// constructs the NFA of the expression with the given algorithm
+    pub fn to_nfa(&self, construction: NFAConstruction) -> NFA {
+        match construction {
+            NFAConstruction::Thompson => self.thompson(),
+            NFAConstruction::Glushkov => self.glushkov(),
+        }
+    }
+
+    /// # of label occurrences in the expression, i.e., positions of the Glushkov automaton
+    pub fn label_count(&self) -> usize {
+        match self {
+            RegexTree::Label(_) => 1,
+            RegexTree::Concatenation(lhs, rhs) | RegexTree::Alternation(lhs, rhs) => lhs.label_count() + rhs.label_count(),
+            RegexTree::KleeneStar(inner) | RegexTree::KleenePlus(inner) => inner.label_count(),
+        }
+    }
+
+    /// NFA with epsilon transitions based on the Thompson's construction algorithm
+    pub fn thompson(&self) -> NFA {
+        match self {
+            RegexTree::Label(label) => transition(label.clone()),
+            RegexTree::Concatenation(lhs, rhs) => concatenation(lhs.thompson(), rhs.thompson()),
+            RegexTree::Alternation(lhs, rhs) => alternation(lhs.thompson(), rhs.thompson()),
+            RegexTree::KleeneStar(inner) => kleene_star(inner.thompson()),
+            RegexTree::KleenePlus(inner) => kleene_plus(inner.thompson()),
+        }
+    }
+
+    /// epsilon-free NFA based on the Glushkov's construction algorithm (https://en.wikipedia.org/wiki/Glushkov%27s_construction_algorithm)
+    /// Each label occurrence is a state, and a state has a transition to each position that can follow it
+    /// with the label of the target position
+    /// panics if the expression has more label occurrences than the states an NFA can have
+    pub fn glushkov(&self) -> NFA {
+        let label_count = self.label_count();
+        assert!(label_count < u8::MAX as usize, "Glushkov automaton requires {} states, at most {} are supported", label_count + 1, u8::MAX);
+
+        // positions are numbered from 1 as 0 is the start state
+        let mut positions = vec![String::new()];
+        let mut follow = vec![BTreeSet::new()];
+        let (nullable, first, last) = self.linearize(&mut positions, &mut follow);
+
+        let mut final_states: HashSet<u8> = last.iter().map(|position| *position as u8).collect();
+        if nullable {
+            final_states.insert(0);
+        }
+
+        let mut automata = NFA::new(positions.len() as u8, final_states);
+        for position in first {
+            automata.add_transition(0, position as u8, positions[position].clone());
+        }
+        for (source, targets) in follow.iter().enumerate() {
+            for target in targets {
+                automata.add_transition(source as u8, *target as u8, positions[*target].clone());
+            }
+        }
+
+        automata
+    }
+
+    /// assigns a position to each label occurrence and updates the follow set of each position
+    /// returns whether the expression accepts the empty word, and its first and last positions
+    fn linearize(&self, positions: &mut Vec<String>, follow: &mut Vec<BTreeSet<usize>>) -> (bool, BTreeSet<usize>, BTreeSet<usize>) {
+        match self {
+            RegexTree::Label(label) => {
+                let position = positions.len();
+                positions.push(label.clone());
+                follow.push(BTreeSet::new());
+                let singleton: BTreeSet<usize> = vec![position].into_iter().collect();
+                (false, singleton.clone(), singleton)
+            }
+            RegexTree::Concatenation(lhs, rhs) => {
+                let (lhs_nullable, mut lhs_first, lhs_last) = lhs.linearize(positions, follow);
+                let (rhs_nullable, rhs_first, mut rhs_last) = rhs.linearize(positions, follow);
+                for position in &lhs_last {
+                    follow[*position].extend(rhs_first.iter().cloned());
+                }
+                if lhs_nullable {
+                    lhs_first.extend(rhs_first);
+                }
+                if rhs_nullable {
+                    rhs_last.extend(lhs_last);
+                }
+                (lhs_nullable && rhs_nullable, lhs_first, rhs_last)
+            }
+            RegexTree::Alternation(lhs, rhs) => {
+                let (lhs_nullable, mut lhs_first, mut lhs_last) = lhs.linearize(positions, follow);
+                let (rhs_nullable, rhs_first, rhs_last) = rhs.linearize(positions, follow);
+                lhs_first.extend(rhs_first);
+                lhs_last.extend(rhs_last);
+                (lhs_nullable || rhs_nullable, lhs_first, lhs_last)
+            }
+            RegexTree::KleeneStar(inner) | RegexTree::KleenePlus(inner) => {
+                let (nullable, first, last) = inner.linearize(positions, follow);
+                for position in &last {
+                    follow[*position].extend(first.iter().cloned());
+                }
+                (nullable || matches!(self, RegexTree::KleeneStar(_)), first, last)
+            }
+        }
+    }
+}
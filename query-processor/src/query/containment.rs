@@ -0,0 +1,188 @@
+//! Containment-based sharing of RPQ evaluation among multiple queries
+//!
+//! If the language of a query is contained in the language of another query, every path that matches the former also matches
+//! the latter, so a single RPQ operator evaluates both over the product of their automata. Final states of the product are tagged
+//! with the queries whose automaton is in a final state, and results of contained queries are derived by filtering the results
+//! of the broader query by their tag, instead of maintaining spanning trees for each of them.
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use crate::input::tuple::ResultLabel;
+use crate::query::automata::dfa::DFA;
+use crate::query::automata::minimize;
+
+/// returns true if every word accepted by `narrower` is also accepted by `broader`
+pub fn is_contained(narrower: &DFA, broader: &DFA) -> bool {
+    // states of the product, where the state of `broader` is None once it has no transition for a prefix
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::new();
+    visited.insert((0, Some(0)));
+    queue.push_back((0, Some(0)));
+
+    while let Some((narrower_state, broader_state)) = queue.pop_front() {
+        if narrower.is_final_state(narrower_state) && !broader_state.map_or(false, |state| broader.is_final_state(state)) {
+            return false;
+        }
+        for (label, narrower_target) in narrower.iter_outgoing_transitions(narrower_state) {
+            let next = (narrower_target, broader_state.and_then(|state| broader.state_move(state, label)));
+            if visited.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+
+    true
+}
+
+/// Queries whose results are derived from a single RPQ operator over the product automaton of the broadest query of the group
+#[derive(Debug, Clone)]
+pub struct SharedEvaluation {
+    pub automaton: DFA,
+    /// output label of the broadest query, which all results of the operator belong to
+    pub output_label: String,
+    // output labels of the queries that results with the tag of a final state belong to
+    members: HashMap<String, Vec<String>>,
+}
+
+impl SharedEvaluation {
+    /// output labels of the queries that a result with the given tag belongs to, which is empty for unknown tags
+    pub fn output_labels(&self, tag: &str) -> &[String] {
+        self.members.get(tag).map(|labels| labels.as_slice()).unwrap_or(&[])
+    }
+
+    /// # of queries that are evaluated by the operator
+    pub fn len(&self) -> usize {
+        self.members.values().flatten().collect::<HashSet<_>>().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
+/// groups the given queries, i.e., automata with their output labels, so that each query whose language is contained in
+/// the language of another query is evaluated together with a broadest query that contains it
+/// Among queries with equivalent languages, the first one is the broadest. Groups follow the order of their broadest query
+pub fn share_contained(queries: Vec<(DFA, String)>) -> Vec<SharedEvaluation> {
+    let contained: Vec<Vec<bool>> = queries.iter()
+        .map(|(narrower, _)| queries.iter().map(|(broader, _)| is_contained(narrower, broader)).collect())
+        .collect();
+    // a query is the broadest of its group unless a strictly broader or an earlier equivalent query contains it
+    let is_broadest = |i: usize| (0..queries.len()).all(|j| j == i || !contained[i][j] || (contained[j][i] && j > i));
+    let broadest: Vec<usize> = (0..queries.len()).filter(|i| is_broadest(*i)).collect();
+
+    broadest.iter().map(|root| {
+        // remaining queries join the first broadest query that contains them
+        let members: Vec<usize> = (0..queries.len())
+            .filter(|i| !is_broadest(*i) && broadest.iter().find(|candidate| contained[*i][**candidate]) == Some(root))
+            .collect();
+        product(&queries[*root], members.iter().map(|i| &queries[*i]).collect())
+    }).collect()
+}
+
+/// product of the automaton of the broadest query with the automata of the queries it contains, whose final states are
+/// the final states of the broadest query, tagged by the queries whose automaton is in a final state
+fn product(broadest: &(DFA, String), contained: Vec<&(DFA, String)>) -> SharedEvaluation {
+    let (root, output_label) = broadest;
+    let start = (0, vec![Some(0); contained.len()]);
+    let mut states: HashMap<(u8, Vec<Option<u8>>), u8> = HashMap::new();
+    let mut ordered_states = vec![start.clone()];
+    let mut transitions = Vec::new();
+    states.insert(start.clone(), 0);
+    let mut queue = VecDeque::from(vec![start]);
+
+    while let Some((root_state, contained_states)) = queue.pop_front() {
+        let source = states[&(root_state, contained_states.clone())];
+        // contained queries are followed along the transitions of the broadest query, as their words are words of it
+        for (label, root_target) in root.iter_outgoing_transitions(root_state) {
+            let contained_targets: Vec<Option<u8>> = contained_states.iter().zip(contained.iter())
+                .map(|(state, (automaton, _))| state.and_then(|state| automaton.state_move(state, label)))
+                .collect();
+            let next = (root_target, contained_targets);
+            let target = match states.get(&next) {
+                Some(target) => *target,
+                None => {
+                    assert!(ordered_states.len() < u8::MAX as usize, "Product automaton of {} cannot have more than {} states", output_label, u8::MAX);
+                    let target = ordered_states.len() as u8;
+                    states.insert(next.clone(), target);
+                    ordered_states.push(next.clone());
+                    queue.push_back(next);
+                    target
+                }
+            };
+            transitions.push((source, target, label.to_string()));
+        }
+    }
+
+    let final_states = (0..ordered_states.len() as u8).filter(|state| root.is_final_state(ordered_states[*state as usize].0)).collect();
+    let mut automaton = DFA::new(ordered_states.len() as u8, final_states);
+    transitions.into_iter().for_each(|(source, target, label)| automaton.add_transition(source, target, label));
+
+    // each distinct set of queries that final states accept is a tag, so that minimization keeps them apart
+    let mut tags: HashMap<BTreeSet<usize>, String> = HashMap::new();
+    let mut members = HashMap::new();
+    for (state, (root_state, contained_states)) in ordered_states.iter().enumerate() {
+        if !root.is_final_state(*root_state) {
+            continue;
+        }
+        let accepting: BTreeSet<usize> = contained_states.iter().zip(contained.iter()).enumerate()
+            .filter(|(_, (state, (automaton, _)))| state.map_or(false, |state| automaton.is_final_state(state)))
+            .map(|(i, _)| i)
+            .collect();
+        let next_tag = tags.len();
+        let tag = tags.entry(accepting.clone()).or_insert_with(|| ResultLabel::stage(output_label, &format!("shared{}", next_tag)).to_string()).clone();
+        members.entry(tag.clone()).or_insert_with(|| {
+            let mut labels = vec![output_label.clone()];
+            labels.extend(accepting.iter().map(|i| contained[*i].1.clone()));
+            labels
+        });
+        automaton.set_final_tag(state as u8, tag);
+    }
+
+    SharedEvaluation { automaton: minimize(automaton), output_label: output_label.clone(), members }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::query::containment::{is_contained, share_contained};
+    use crate::query::parser::RPQParser;
+
+    #[test]
+    fn contained_languages() {
+        let parser = RPQParser::new();
+        let parse = |query: &str| parser.parse_rpq(query).unwrap();
+
+        assert!(is_contained(&parse("a/b"), &parse("a/b*")));
+        assert!(is_contained(&parse("a+"), &parse("a*")));
+        assert!(!is_contained(&parse("a*"), &parse("a+")));
+        assert!(!is_contained(&parse("a/c"), &parse("a/b*")));
+    }
+
+    #[test]
+    fn shared_evaluation() {
+        let parser = RPQParser::new();
+        let queries = vec!["a/b", "a/b*", "c", "a/b/b"].into_iter()
+            .enumerate()
+            .map(|(i, query)| (parser.parse_rpq(query).unwrap(), format!("q{}", i)))
+            .collect();
+
+        let shared = share_contained(queries);
+        // a/b and a/b/b are derived from a/b*
+        assert_eq!(shared.len(), 2);
+        assert_eq!(shared[0].output_label, "q1");
+        assert_eq!(shared[0].len(), 3);
+        assert_eq!(shared[1].output_label, "q2");
+
+        // results after a/b belong to q0 and q1, and results after a/b/b to q1 and q3
+        let automaton = &shared[0].automaton;
+        let ab = automaton.state_move(automaton.state_move(0, "a").unwrap(), "b").unwrap();
+        let abb = automaton.state_move(ab, "b").unwrap();
+        let labels = |state: u8| {
+            let mut labels = shared[0].output_labels(automaton.final_tag(state).unwrap()).to_vec();
+            labels.sort();
+            labels
+        };
+        assert_eq!(labels(ab), vec!["q0", "q1"]);
+        assert_eq!(labels(abb), vec!["q1", "q3"]);
+        assert_eq!(labels(automaton.state_move(abb, "b").unwrap()), vec!["q1"]);
+    }
+}
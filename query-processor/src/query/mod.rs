@@ -1,3 +1,5 @@
 pub mod automata;
+pub mod containment;
 pub mod query_library;
-pub mod parser;
\ No newline at end of file
+pub mod parser;
+pub mod plan;
\ No newline at end of file
@@ -1,143 +1,294 @@
+use std::fmt;
+
 use log::trace;
+use pest::error::{Error, ErrorVariant, InputLocation, LineColLocation};
 use pest::iterators::Pair;
-use pest::Parser;
+use pest::{Parser, Span};
 use pest_derive::Parser;
 
-use crate::query::{automata::dfa::DFA, automata::nfa::NFA};
-use crate::query::automata::{alternation, concatenation, determinize, kleene_plus, kleene_star, minimize, transition};
+use crate::input::labels::{canonical_label, unquote_label};
+use crate::query::automata::dfa::DFA;
+use crate::query::automata::lazy::LazyDFA;
+use crate::query::automata::{determinize, minimize};
+use crate::query::automata::regex::{NFAConstruction, RegexTree};
+use crate::util::types::VertexType;
+
+/// Endpoints of an anchored RPQ, e.g., `<42> knows+ ?x`, where a constant `<id>` fixes the vertex of an endpoint
+/// and a variable `?name` leaves it free. Constants are integer vertex identifiers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Endpoints {
+    pub source: Option<VertexType>,
+    pub target: Option<VertexType>,
+}
+
+impl Endpoints {
+    /// returns true if the source vertex matches the source constant, if any
+    pub fn matches_source(&self, source: VertexType) -> bool {
+        self.source.map_or(true, |constant| constant == source)
+    }
+
+    /// returns true if the target vertex matches the target constant, if any
+    pub fn matches_target(&self, target: VertexType) -> bool {
+        self.target.map_or(true, |constant| constant == target)
+    }
+
+    /// returns true if both endpoints match their constants, e.g., to filter join inputs and results
+    pub fn matches(&self, source: VertexType, target: VertexType) -> bool {
+        self.matches_source(source) && self.matches_target(target)
+    }
+
+    /// returns true if neither endpoint is fixed
+    pub fn is_unbound(&self) -> bool {
+        self.source.is_none() && self.target.is_none()
+    }
+}
+
+/// Error of parsing an RPQ, at the 1-based line and column of the offending token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    /// offending token, `None` at the end of the query
+    pub token: Option<String>,
+    pub message: String,
+}
+
+impl ParseError {
+    /// error at the start of the given span, where the span is the offending token
+    fn at(span: Span, message: String) -> Self {
+        let (line, column) = span.start_pos().line_col();
+        let token = Some(span.as_str().to_string()).filter(|token| !token.is_empty());
+        Self { line, column, token, message }
+    }
+
+    /// error of the grammar, where the offending token is the label or the symbol at the error position
+    fn from_pest(query_str: &str, error: Error<Rule>) -> Self {
+        let (line, column) = match error.line_col {
+            LineColLocation::Pos(position) | LineColLocation::Span(position, _) => position,
+        };
+        let position = match error.location {
+            InputLocation::Pos(position) | InputLocation::Span((position, _)) => position,
+        };
+
+        let rest = &query_str[position..];
+        let label: String = rest.chars().take_while(|c| !c.is_whitespace() && !"()|/^*+?".contains(*c)).collect();
+        let token = if label.is_empty() { rest.chars().next().map(|c| c.to_string()) } else { Some(label) };
+
+        let message = match error.variant {
+            ErrorVariant::ParsingError { positives, .. } if !positives.is_empty() => {
+                format!("expected {}", positives.iter().map(|rule| format!("{:?}", rule)).collect::<Vec<_>>().join(" or "))
+            }
+            ErrorVariant::ParsingError { .. } => "unexpected input".to_string(),
+            ErrorVariant::CustomError { message } => message,
+        };
+
+        Self { line, column, token, message }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.token {
+            Some(token) => write!(f, "{}:{}: {}, found `{}`", self.line, self.column, self.message, token),
+            None => write!(f, "{}:{}: {}, found end of query", self.line, self.column, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for String {
+    fn from(error: ParseError) -> Self {
+        error.to_string()
+    }
+}
 
 /// PEST based parser for Regular Path Queries
 /// It uses a subset of the SPARQL property path syntax to express RPQ, grammar is at `rpq.pest`
 
 #[derive(Parser)]
 #[grammar = "query/parser/rpq.pest"]
-pub struct RPQParser;
+pub struct RPQParser {
+    construction: NFAConstruction,
+    case_insensitive: bool,
+}
 
 impl RPQParser {
     pub fn new() -> Self {
-        Self {}
+        Self { construction: NFAConstruction::default(), case_insensitive: false }
     }
 
-    pub fn parse_rpq(&self, query_str: &str) -> Result<DFA, String> {
-        let parse_result = RPQParser::parse(Rule::RPQ, query_str).expect("RPQ Parser unsuccessfull").next().unwrap();
+    /// creates a parser that constructs NFAs with the given algorithm before determinization
+    pub fn with_construction(construction: NFAConstruction) -> Self {
+        Self { construction, case_insensitive: false }
+    }
 
-        let mut results = Vec::new();
+    /// lowercases predicates, so that labels of the automaton match edges canonicalized with `LabelMapping::case_insensitive`
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
 
-        for pair in parse_result.into_inner() {
-            let result = match pair.as_rule() {
-                Rule::Path => {
-                    let res = self.parse_path(pair);
-                    res.map(|nfa| minimize(determinize(nfa)))
-                }
-                r => {
-                    trace!("{:?}", pair);
-                    Err(format!("Rule {:?} is not recognized", r))
-                }
-            };
-            results.push(result)
-        }
+    pub fn parse_rpq(&self, query_str: &str) -> Result<DFA, ParseError> {
+        self.parse_regex(query_str).map(|tree| minimize(determinize(tree.to_nfa(self.construction)).trim()))
+    }
+
+    /// parses the given RPQ into a DFA that is determinized lazily for the labels observed in the stream, see `LazyDFA`
+    pub fn parse_lazy_rpq(&self, query_str: &str) -> Result<LazyDFA, ParseError> {
+        self.parse_regex(query_str).map(|tree| LazyDFA::new(tree.to_nfa(self.construction)))
+    }
+
+    /// parses the given RPQ into the syntax tree of its regular expression
+    pub fn parse_regex(&self, query_str: &str) -> Result<RegexTree, ParseError> {
+        let parse_result = RPQParser::parse(Rule::RPQ, query_str)
+            .map_err(|e| ParseError::from_pest(query_str, e))?
+            .next().unwrap();
+
+        // RPQ consists of a single Path besides EOI
+        let span = parse_result.as_span();
+        let path = parse_result.into_inner().find(|pair| pair.as_rule() == Rule::Path)
+            .ok_or_else(|| ParseError::at(span, "RPQ should include a path".to_string()))?;
+        self.parse_path(path)
+    }
+
+    /// parses an RPQ with constant or variable endpoints, e.g., `<42> knows+ ?x`
+    pub fn parse_anchored_rpq(&self, query_str: &str) -> Result<(Endpoints, DFA), ParseError> {
+        let parse_result = RPQParser::parse(Rule::AnchoredRPQ, query_str)
+            .map_err(|e| ParseError::from_pest(query_str, e))?
+            .next().unwrap();
+
+        let span = parse_result.as_span();
+        let mut inner = parse_result.into_inner();
+        let source = inner.next().ok_or_else(|| ParseError::at(span.clone(), "Anchored RPQ should start with an endpoint".to_string()))
+            .and_then(|pair| self.parse_endpoint(pair))?;
+        let tree = inner.next().ok_or_else(|| ParseError::at(span.clone(), "Anchored RPQ should include a path".to_string()))
+            .and_then(|pair| self.parse_path(pair))?;
+        let target = inner.next().ok_or_else(|| ParseError::at(span, "Anchored RPQ should end with an endpoint".to_string()))
+            .and_then(|pair| self.parse_endpoint(pair))?;
+
+        Ok((Endpoints { source, target }, minimize(determinize(tree.to_nfa(self.construction)))))
+    }
 
-        results.remove(0)
+    fn parse_endpoint(&self, pair: Pair<Rule>) -> Result<Option<VertexType>, ParseError> {
+        trace!("Endpoint: {:?}", pair);
+        let span = pair.as_span();
+        match pair.into_inner().next() {
+            Some(endpoint) if endpoint.as_rule() == Rule::Constant => {
+                let constant = endpoint.as_str().trim_start_matches('<').trim_end_matches('>');
+                constant.parse().map(Some).map_err(|e| ParseError::at(endpoint.as_span(), format!("Constant is not a valid vertex identifier: {}", e)))
+            }
+            Some(endpoint) if endpoint.as_rule() == Rule::Variable => Ok(None),
+            _ => Err(ParseError::at(span, "Endpoint is either a constant or a variable".to_string()))
+        }
     }
 
-    fn parse_primary(&self, pair: Pair<Rule>) -> Result<NFA, String> {
+    fn parse_primary(&self, pair: Pair<Rule>) -> Result<RegexTree, ParseError> {
         trace!("PathPrimary: {:?}", pair);
+        let span = pair.as_span();
         if let Some(primary) = pair.into_inner().next() {
             match primary.as_rule() {
-                Rule::predicate => {
-                    let label = primary.as_str().to_string();
-                    Ok(transition(label))
+                Rule::predicate | Rule::iri => {
+                    Ok(RegexTree::Label(canonical_label(primary.as_str(), self.case_insensitive)))
+                }
+                Rule::quoted => {
+                    unquote_label(primary.as_str())
+                        .map(|label| RegexTree::Label(canonical_label(&label, self.case_insensitive)))
+                        .map_err(|e| ParseError::at(primary.as_span(), e))
                 }
                 Rule::Path => {
                     self.parse_path(primary)
                 }
                 _ => {
-                    Err(format!("PathPrimary can consist of only Path or predicate {}", primary.as_str()).to_string())
+                    Err(ParseError::at(primary.as_span(), "PathPrimary can consist of only Path, IRI or predicate".to_string()))
                 }
             }
         } else {
-            Err(format!("PathPrimary is a Path or a predicate").to_string())
+            Err(ParseError::at(span, "PathPrimary is a Path or a predicate".to_string()))
         }
     }
 
-    fn parse_elt(&self, pair: Pair<Rule>) -> Result<NFA, String> {
-        trace!("PathElt: {:?}", pair);
-        if let Some(path_elt) = pair.into_inner().next() {
-            match path_elt.as_rule() {
-                Rule::PathElt => {
-                    let mut path_elt_iterator = path_elt.into_inner();
-
-                    if let Some(path_primary) = path_elt_iterator.next() {
-                        let primary = self.parse_primary(path_primary)?;
-
-                        if let Some(path_mod) = path_elt_iterator.next() {
-                            match path_mod.as_str() {
-                                "*" => {
-                                    Ok(kleene_star(primary))
-                                }
-                                "+" => {
-                                    Ok(kleene_plus(primary))
-                                }
-                                _ => {
-                                    Err("Bounded RPQ is not supported".to_string())
-                                }
-                            }
-                        } else {
-                            Ok(primary)
+    fn parse_elt(&self, pair: Pair<Rule>) -> Result<RegexTree, ParseError> {
+        trace!("PathEltOrInverse: {:?}", pair);
+        let span = pair.as_span();
+        match pair.as_rule() {
+            Rule::PathEltOrInverse => {
+                let path_elt = pair.into_inner().next().ok_or_else(|| ParseError::at(span, "PathEltOrInverse should consist of PathElt".to_string()))?;
+                self.parse_elt(path_elt)
+            }
+            Rule::InversePath => {
+                Err(ParseError::at(span, "Inverse paths are not supported".to_string()))
+            }
+            Rule::PathElt => {
+                let mut path_elt_iterator = pair.into_inner();
+                let path_primary = path_elt_iterator.next().ok_or_else(|| ParseError::at(span, "PathElt should include at least one path primary".to_string()))?;
+                // modifiers apply to the entire primary, i.e., a label or a parenthesized group
+                let primary = self.parse_primary(path_primary)?;
+
+                if let Some(path_mod) = path_elt_iterator.next() {
+                    match path_mod.as_str() {
+                        "*" => {
+                            Ok(RegexTree::KleeneStar(Box::new(primary)))
+                        }
+                        "+" => {
+                            Ok(RegexTree::KleenePlus(Box::new(primary)))
+                        }
+                        _ => {
+                            Err(ParseError::at(path_mod.as_span(), "Optional and bounded paths are not supported".to_string()))
                         }
-                    } else {
-                        Err("PathElt should include at least one path primary".to_string())
                     }
-                }
-                _ => {
-                    Err(format!("PathElt consist of PathAlternative {}", path_elt.as_str()).to_string())
+                } else {
+                    Ok(primary)
                 }
             }
-        } else {
-            Err("PathEltOrInverse should consist of PathElt".to_string())
+            _ => {
+                Err(ParseError::at(span, "PathElt consist of PathPrimary and an optional PathMod".to_string()))
+            }
         }
     }
 
-    fn parse_sequence(&self, pair: Pair<Rule>) -> Result<NFA, String> {
+    fn parse_sequence(&self, pair: Pair<Rule>) -> Result<RegexTree, ParseError> {
         trace!("PathSequence: {:?}", pair);
-        // obtain the first NFA, then use concat
+        let span = pair.as_span();
+        // obtain the first expression, then use concat
         let mut sequence_iterator = pair.into_inner();
 
         if let Some(first_seq) = sequence_iterator.next() {
             let mut first = self.parse_elt(first_seq)?;
 
-            while let Some(seq) = sequence_iterator.next() {
-                let seq_nfa = self.parse_elt(seq)?;
-                first = concatenation(first, seq_nfa)
+            for seq in sequence_iterator {
+                let seq_tree = self.parse_elt(seq)?;
+                first = RegexTree::Concatenation(Box::new(first), Box::new(seq_tree))
             }
 
             Ok(first)
         } else {
-            Err("Concatenation should have at least one element".to_string())
+            Err(ParseError::at(span, "Concatenation should have at least one element".to_string()))
         }
     }
 
-    fn parse_alternative(&self, pair: Pair<Rule>) -> Result<NFA, String> {
+    fn parse_alternative(&self, pair: Pair<Rule>) -> Result<RegexTree, ParseError> {
         trace!("PathAlternative: {:?}", pair);
-        // obtain the first NFA, then use alternation
+        let span = pair.as_span();
+        // obtain the first expression, then use alternation
         let mut alternation_iterator = pair.into_inner();
 
         if let Some(first_alternation) = alternation_iterator.next() {
             let mut first = self.parse_sequence(first_alternation)?;
 
-            while let Some(alt) = alternation_iterator.next() {
-                let alt_nfa = self.parse_sequence(alt)?;
-                first = alternation(first, alt_nfa)
+            for alt in alternation_iterator {
+                let alt_tree = self.parse_sequence(alt)?;
+                first = RegexTree::Alternation(Box::new(first), Box::new(alt_tree))
             }
 
             Ok(first)
         } else {
-            Err("Alternation should have at least one element".to_string())
+            Err(ParseError::at(span, "Alternation should have at least one element".to_string()))
         }
     }
 
-    fn parse_path(&self, pair: Pair<Rule>) -> Result<NFA, String> {
+    fn parse_path(&self, pair: Pair<Rule>) -> Result<RegexTree, ParseError> {
         trace!("Path: {:?}", pair);
+        let span = pair.as_span();
 
         if let Some(alternative_rule) = pair.into_inner().next() {
             match alternative_rule.as_rule() {
@@ -145,11 +296,11 @@ impl RPQParser {
                     self.parse_alternative(alternative_rule)
                 }
                 _ => {
-                    Err(format!("Path should consist of PathAlternative {}", alternative_rule.as_str()).to_string())
+                    Err(ParseError::at(alternative_rule.as_span(), "Path should consist of PathAlternative".to_string()))
                 }
             }
         } else {
-            Err("Path should consist of PathAlternative".to_string())
+            Err(ParseError::at(span, "Path should consist of PathAlternative".to_string()))
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,168 @@
+//! Programmatic RPQ plans whose transitions reference intermediate streams of a query plan directly
+//!
+//! Hybrid plans, e.g., `(a/b/c)+` that materializes `a/b` with a join, evaluate an RPQ over the edges of the input and
+//! the results of other operators. Instead of naming intermediate results and referring to their label in a generated
+//! query string, a `PathExpression` refers to each intermediate stream with a `Symbol::StreamRef` handle of a `PathPlan`.
+use std::collections::HashSet;
+use std::fmt;
+
+use timely::dataflow::{Scope, Stream};
+use timely::dataflow::operators::{Concatenate, Map};
+
+use crate::input::tuple::{ResultLabel, StreamingGraphTuple};
+use crate::operator::rpq::RegularPathQuery;
+use crate::query::automata::{determinize, minimize};
+use crate::query::automata::regex::RegexTree;
+use crate::util::types::EventTime;
+
+/// Symbol of a transition, either an edge label or the tuples of a stream registered in a `PathPlan`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Symbol {
+    Label(String),
+    StreamRef(usize),
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Symbol::Label(label) => write!(f, "{}", label),
+            Symbol::StreamRef(id) => write!(f, "${}", id),
+        }
+    }
+}
+
+/// Regular expression over symbols, built programmatically as `RegexTree` is built by the parser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathExpression {
+    Symbol(Symbol),
+    Concatenation(Box<PathExpression>, Box<PathExpression>),
+    Alternation(Box<PathExpression>, Box<PathExpression>),
+    KleeneStar(Box<PathExpression>),
+    KleenePlus(Box<PathExpression>),
+}
+
+impl PathExpression {
+    /// matches an edge with the given label
+    pub fn label(label: &str) -> Self {
+        PathExpression::Symbol(Symbol::Label(label.to_string()))
+    }
+
+    /// matches a tuple of the referenced stream
+    pub fn symbol(symbol: Symbol) -> Self {
+        PathExpression::Symbol(symbol)
+    }
+
+    /// `self/rhs`
+    pub fn then(self, rhs: PathExpression) -> Self {
+        PathExpression::Concatenation(Box::new(self), Box::new(rhs))
+    }
+
+    /// `self|rhs`
+    pub fn or(self, rhs: PathExpression) -> Self {
+        PathExpression::Alternation(Box::new(self), Box::new(rhs))
+    }
+
+    /// `self*`
+    pub fn star(self) -> Self {
+        PathExpression::KleeneStar(Box::new(self))
+    }
+
+    /// `self+`
+    pub fn plus(self) -> Self {
+        PathExpression::KleenePlus(Box::new(self))
+    }
+
+    /// regular expression where each stream reference is replaced by the label of its stream in `stream_labels`
+    /// returns an error if a reference is not registered
+    pub fn to_regex(&self, stream_labels: &[String]) -> Result<RegexTree, String> {
+        Ok(match self {
+            PathExpression::Symbol(Symbol::Label(label)) => RegexTree::Label(label.clone()),
+            PathExpression::Symbol(Symbol::StreamRef(id)) => {
+                let label = stream_labels.get(*id).ok_or_else(|| format!("Stream reference {} is not registered in the plan", id))?;
+                RegexTree::Label(label.clone())
+            }
+            PathExpression::Concatenation(lhs, rhs) => RegexTree::Concatenation(Box::new(lhs.to_regex(stream_labels)?), Box::new(rhs.to_regex(stream_labels)?)),
+            PathExpression::Alternation(lhs, rhs) => RegexTree::Alternation(Box::new(lhs.to_regex(stream_labels)?), Box::new(rhs.to_regex(stream_labels)?)),
+            PathExpression::KleeneStar(inner) => RegexTree::KleeneStar(Box::new(inner.to_regex(stream_labels)?)),
+            PathExpression::KleenePlus(inner) => RegexTree::KleenePlus(Box::new(inner.to_regex(stream_labels)?)),
+        })
+    }
+}
+
+impl fmt::Display for PathExpression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathExpression::Symbol(symbol) => write!(f, "{}", symbol),
+            PathExpression::Concatenation(lhs, rhs) => write!(f, "({}/{})", lhs, rhs),
+            PathExpression::Alternation(lhs, rhs) => write!(f, "({}|{})", lhs, rhs),
+            PathExpression::KleeneStar(inner) => write!(f, "{}*", inner),
+            PathExpression::KleenePlus(inner) => write!(f, "{}+", inner),
+        }
+    }
+}
+
+/// Inputs of an RPQ whose expression refers to edge labels of its edge streams and to its referenced streams
+pub struct PathPlan<G: Scope> {
+    edges: Vec<Stream<G, StreamingGraphTuple>>,
+    references: Vec<Stream<G, StreamingGraphTuple>>,
+    // labels that edge streams carry, if they are registered
+    edge_labels: Option<HashSet<String>>,
+}
+
+impl<G: Scope> PathPlan<G> where G::Timestamp: EventTime {
+    pub fn new() -> Self {
+        Self { edges: Vec::new(), references: Vec::new(), edge_labels: None }
+    }
+
+    /// adds a stream whose tuples are matched by `Symbol::Label` of their label
+    pub fn with_edges(mut self, stream: &Stream<G, StreamingGraphTuple>) -> Self {
+        self.edges.push(stream.clone());
+        self
+    }
+
+    /// registers labels that edge streams carry, so that the automaton is projected onto them and transitions
+    /// with labels that never occur in the stream are pruned before evaluation
+    pub fn with_edge_labels<I: IntoIterator<Item=String>>(mut self, labels: I) -> Self {
+        self.edge_labels.get_or_insert_with(HashSet::new).extend(labels);
+        self
+    }
+
+    /// registers a stream whose tuples are matched by the returned symbol regardless of their label
+    pub fn stream_ref(&mut self, stream: &Stream<G, StreamingGraphTuple>) -> Symbol {
+        self.references.push(stream.clone());
+        Symbol::StreamRef(self.references.len() - 1)
+    }
+
+    /// Incremental RPQ evaluation of the expression over the streams of the plan, results carry `output_label`
+    /// Tuples of referenced streams are relabelled with a stage label of the query that is private to the plan
+    /// The automaton is projected onto the registered edge labels and the labels of referenced streams, if edge labels are registered
+    /// panics if the expression refers to a stream that is not registered, or the plan has no streams
+    pub fn regular_path_query(self, expression: &PathExpression, output_label: String) -> Stream<G, StreamingGraphTuple> {
+        let stream_labels: Vec<String> = (0..self.references.len())
+            .map(|id| ResultLabel::stage(&output_label, &format!("ref{}", id)).to_string())
+            .collect();
+        let tree = expression.to_regex(&stream_labels).unwrap_or_else(|e| panic!("Path expression {} is not valid: {}", expression, e));
+        let mut automaton = minimize(determinize(tree.thompson()));
+        if let Some(mut labels) = self.edge_labels {
+            labels.extend(stream_labels.iter().cloned());
+            automaton = automaton.project(&labels);
+        }
+
+        let mut inputs = self.edges;
+        for (stream, label) in self.references.iter().zip(stream_labels) {
+            inputs.push(stream.map(move |mut sgt| {
+                sgt.label = label.clone();
+                sgt
+            }));
+        }
+        let scope = inputs.first().unwrap_or_else(|| panic!("Path expression {} has no input streams", expression)).scope();
+
+        scope.concatenate(inputs).tagged_regular_path_query(automaton, output_label)
+    }
+}
+
+impl<G: Scope> Default for PathPlan<G> where G::Timestamp: EventTime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
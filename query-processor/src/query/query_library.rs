@@ -1,19 +1,35 @@
 use std::collections::HashSet;
+#[cfg(feature = "dd")]
+use std::collections::{BTreeMap, HashMap};
 
+#[cfg(feature = "dd")]
 use differential_dataflow::{AsCollection, Collection};
+#[cfg(feature = "dd")]
 use differential_dataflow::operators::*;
 use log::trace;
 use timely::communication::allocator::Generic;
-use timely::dataflow::operators::{Concat, Partition, Filter, Inspect};
+use timely::dataflow::operators::{Concat, Concatenate, Partition, Filter, Inspect, Map};
 use timely::dataflow::scopes::Child;
+#[cfg(feature = "dd")]
+use timely::dataflow::Scope;
+#[cfg(feature = "dd")]
+use timely::dataflow::operators::generic::operator::empty;
 use timely::dataflow::Stream;
 use timely::worker::Worker;
 
+#[cfg(feature = "dd")]
+use crate::dd::{transitive_closure, transitive_closure_arranged, Direction, SharedArrangements};
 use crate::input::{GraphEdge, SGT, StreamingGraphEdge};
-use crate::input::tuple::StreamingGraphTuple;
+use crate::input::labels::quote_label;
+use crate::input::tuple::{ResultLabel, StreamingGraphTuple};
 use crate::operator::hash_join::{HashJoinAttributePair, SymmetricHashJoin};
 use crate::operator::rpq::RegularPathQuery;
+#[cfg(feature = "dd")]
+use crate::query::parser::RPQParser;
+use crate::query::plan::{PathExpression, PathPlan};
 use crate::util::types::HalfOpenTimeInterval;
+#[cfg(feature = "dd")]
+use crate::util::types::VertexType;
 
 use self::super::automata::nfa::NFA;
 
@@ -55,424 +71,572 @@ impl RPQLibrary {
     }
 }
 
+/// Relation of a DD path pattern over the edges of the predicate at the given index
+#[cfg(feature = "dd")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Relation {
+    /// edges of the predicate
+    Edges(usize),
+    /// transitive closure of the edges of the predicate, i.e., paths of one or more of its edges
+    Closure(usize),
+}
+
+#[cfg(feature = "dd")]
+impl Relation {
+    /// index of the predicate of the relation
+    pub fn predicate(&self) -> usize {
+        match self {
+            Relation::Edges(predicate) | Relation::Closure(predicate) => *predicate,
+        }
+    }
+}
+
+/// Declarative DD query over a list of relations, whose results are the (source, target) pairs of paths that concatenate
+/// the relations in order, e.g., `[Edges(0), Closure(1)]` for `a/b+`, or their transitive closure if `closure` is set,
+/// e.g., `(a/b/c)+`
+#[cfg(feature = "dd")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathPattern {
+    pub relations: Vec<Relation>,
+    pub closure: bool,
+}
+
+#[cfg(feature = "dd")]
+impl PathPattern {
+    pub fn new(relations: Vec<Relation>) -> Self {
+        Self { relations, closure: false }
+    }
+
+    /// pattern of the concatenation of the edges of each of the given # of predicates in order, e.g., `a/b/c/d`
+    pub fn concatenation(num_predicates: usize) -> Self {
+        Self::new((0..num_predicates).map(Relation::Edges).collect())
+    }
+
+    /// results are the transitive closure of the concatenation
+    pub fn with_closure(mut self) -> Self {
+        self.closure = true;
+        self
+    }
+
+    /// # of predicates the pattern refers to
+    pub fn num_predicates(&self) -> usize {
+        self.relations.iter().map(|relation| relation.predicate() + 1).max().unwrap_or(0)
+    }
+}
+
+/// partitions edges by their label into one collection of (source, target) pairs for each predicate, in the order of the predicates
+/// An edge belongs to every predicate with its label, e.g., to both relations of `a` in `a/b/c/a`,
+/// and edges whose label is not a predicate are dropped
+#[cfg(feature = "dd")]
+pub fn partition_by_label<G: Scope>(input: &Collection<G, StreamingGraphEdge, isize>, edge_predicates: &[String]) -> Vec<Collection<G, (VertexType, VertexType), isize>> {
+    let predicates = edge_predicates.to_vec();
+    input.inner
+        .flat_map(move |(sge, time, diff)| predicates.iter().enumerate()
+            .filter(|(_, predicate)| predicate.as_str() == sge.get_label())
+            .map(|(predicate, _)| (predicate, ((sge.get_source(), sge.get_target()), time.clone(), diff)))
+            .collect::<Vec<_>>())
+        .partition(edge_predicates.len() as u64, |(predicate, update)| (predicate as u64, update))
+        .into_iter()
+        .map(|stream| stream.as_collection())
+        .collect()
+}
+
 /// Pre-constructed DD dataflows for queries in SGA paper (Table 1)
+/// Queries read the edges of their predicates from `SharedArrangements`, so that queries of a dataflow share the arrangements of edges
+#[cfg(feature = "dd")]
 pub struct DDQueryLibrary;
 
+#[cfg(feature = "dd")]
 impl DDQueryLibrary {
-    pub fn hash_join<'a>(input: Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphEdge, isize>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
-        assert_eq!(edge_predicates.len(), 2);
-        // logical partitioning based on labels
-        let streams = input
-            .inner
-            .partition(2, move |sgt| {
-                if sgt.0.get_label() == &edge_predicates[0] {
-                    (0, sgt)
-                } else {
-                    (1, sgt)
-                }
-            });
-
-        let s1 = streams[0].as_collection();
-        let s2 = streams[1].as_collection();
-
-        // Create SGT from results with default interval
-        s1.map(|sgt| (sgt.target, sgt.source))
-            .join(&s2.map(|sgt| (sgt.source, sgt.target)))
-            .map(|(_key, (s1, t2))| (s1, t2)).distinct()
-            .map(move |(s1, t2)| StreamingGraphTuple::new(s1, t2, output_label.clone(), HalfOpenTimeInterval::ZERO))
-    }
-
-    pub fn query1<'a>(input: Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphEdge, isize>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
-        assert_eq!(edge_predicates.len(), 1);
-        let edges = input.map(|sge| (sge.get_source(), sge.get_target()));
-        let reachability = edges
-            .iterate(|transitive| {
-                let edges = edges.enter(&transitive.scope());
-                transitive
-                    .map(|(s, t)| (t, s))
-                    .join(&edges)
-                    .map(|(_key, (s1, t2))| (s1, t2))
-                    .concat(&edges)
-                    .distinct()
-            });
-
-        // construct sgts from reachable pairs
-        reachability.map(move |(s, t)| StreamingGraphTuple::new(s, t, output_label.clone(), HalfOpenTimeInterval::ZERO))
-    }
-
-    pub fn query2<'a>(input: Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphEdge, isize>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
-        assert_eq!(edge_predicates.len(), 2);
-        // logical partitioning based on labels
-        let streams = input
-            .inner
-            .partition(2, move |sgt| {
-                if sgt.0.get_label() == &edge_predicates[0] {
-                    (0, sgt)
-                } else {
-                    (1, sgt)
-                }
-            });
-
-        let s0 = streams[0].as_collection().map(|sgt| (sgt.get_target(), sgt.get_source()));
-        let s1 = streams[1].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-
-        let s1_closure = s1
-            .iterate(|transitive| {
-                let s1 = s1.enter(&transitive.scope());
-                transitive
-                    .map(|(s, t)| (t, s))
-                    .join(&s1)
-                    .map(|(_key, (s1, t2))| (s1, t2))
-                    .concat(&s1)
-                    .distinct()
-            });
-
-        let results = s0
-            .join(&s1_closure)
-            .map(|(_key, (s1, t2))| (s1, t2)).distinct();
+    /// names of the queries that `by_name` constructs
+    pub const QUERY_NAMES: &'static [&'static str] = &[
+        "join", "query1", "query2", "query2-a", "query3", "query3-a", "query4", "query4-a",
+        "query5", "query6", "query6-cq", "query7", "query7-cq", "query8", "path",
+    ];
+
+    /// returns true if `by_name` constructs a query with the given name
+    pub fn is_defined(query_name: &str) -> bool {
+        Self::QUERY_NAMES.contains(&query_name)
+    }
 
-        // construct sgts for reachable pairs
-        results.map(move |(s, t)| StreamingGraphTuple::new(s, t, output_label.clone(), HalfOpenTimeInterval::ZERO))
+    /// constructs the dataflow for the query with the given name from Table 1,
+    /// or `path` for the concatenation of any # of predicates in order, e.g., `a/b/c/d`
+    /// Queries with the `-a` suffix are evaluated over the automaton of their RPQ as the SGA queries of the same name
+    /// returns `None` if there is no query with the given name
+    pub fn by_name<'a>(query_name: &str, input: Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphEdge, isize>, edge_predicates: Vec<String>) -> Option<Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize>> {
+        let mut shared = SharedArrangements::new(&input, &edge_predicates);
+        Self::by_name_shared(query_name, &mut shared, edge_predicates)
     }
 
-    pub fn query3<'a>(input: Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphEdge, isize>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
-        assert_eq!(edge_predicates.len(), 3);
-        // logical partitioning based on labels
-        let streams = input
-            .inner
-            .partition(3, move |sgt| {
-                if sgt.0.get_label() == &edge_predicates[0] {
-                    (0, sgt)
-                } else if sgt.0.get_label() == &edge_predicates[1] {
-                    (1, sgt)
-                } else {
-                    (2, sgt)
-                }
-            });
-
-        let s0 = streams[0].as_collection().map(|sgt| (sgt.get_target(), sgt.get_source()));
-        let s1 = streams[1].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-        let s2 = streams[2].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-
-        let s1_closure = s1
-            .iterate(|transitive| {
-                let s1 = s1.enter(&transitive.scope());
-                transitive
-                    .map(|(s, t)| (t, s))
-                    .join(&s1)
-                    .map(|(_key, (s1, t2))| (s1, t2))
-                    .concat(&s1)
-                    .distinct()
-            });
-
-        let s2_closure = s2
-            .iterate(|transitive| {
-                let s2 = s2.enter(&transitive.scope());
-                transitive
-                    .map(|(s, t)| (t, s))
-                    .join(&s2)
-                    .map(|(_key, (s1, t2))| (s1, t2))
-                    .concat(&s2)
-                    .distinct()
-            });
-
-        let results = s0
-            .join(&s1_closure)
-            .map(|(_key, (s1, t2))| (t2, s1)).distinct()
-            .join(&s2_closure)
-            .map(|(_key, (s1, t3))| (s1, t3)).distinct();
+    /// constructs the dataflow for the query with the given name as `by_name` over the edges of shared arrangements,
+    /// e.g., to run several queries in a single dataflow
+    pub fn by_name_shared<'a>(query_name: &str, shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, edge_predicates: Vec<String>) -> Option<Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize>> {
+        let result = match query_name {
+            "join" => {
+                Self::hash_join(shared, edge_predicates, "join".to_string())
+            }
+            "query1" => {
+                Self::query1(shared, edge_predicates, "q1".to_string())
+            }
+            "query2" => {
+                Self::query2(shared, edge_predicates, "q2".to_string())
+            }
+            "query2-a" => {
+                Self::rpq(shared, &format!("{}/{}*", quote_label(&edge_predicates[0]), quote_label(&edge_predicates[1])), "q2".to_string())
+            }
+            "query3" => {
+                Self::query3(shared, edge_predicates, "q3".to_string())
+            }
+            "query3-a" => {
+                Self::rpq(shared, &format!("{}/{}*/{}*", quote_label(&edge_predicates[0]), quote_label(&edge_predicates[1]), quote_label(&edge_predicates[2])), "q3".to_string())
+            }
+            "query4" => {
+                Self::query4(shared, edge_predicates, "q4".to_string())
+            }
+            "query4-a" => {
+                Self::rpq(shared, &format!("({}/{}/{})+", quote_label(&edge_predicates[0]), quote_label(&edge_predicates[1]), quote_label(&edge_predicates[2])), "q4".to_string())
+            }
+            "query5" => {
+                Self::query5(shared, edge_predicates, "q5".to_string())
+            }
+            "query6" => {
+                Self::query6(shared, edge_predicates, "q6".to_string())
+            }
+            "query6-cq" => {
+                Self::query6_cq(shared, edge_predicates, "q6".to_string())
+            }
+            "query7" => {
+                Self::query7(shared, edge_predicates, "q7".to_string())
+            }
+            "query7-cq" => {
+                Self::query7_cq(shared, edge_predicates, "q7".to_string())
+            }
+            "query8" => {
+                Self::query8(shared, edge_predicates, "q8".to_string())
+            }
+            "path" => {
+                let pattern = PathPattern::concatenation(edge_predicates.len());
+                Self::path_pattern(shared, edge_predicates, &pattern, "path".to_string())
+            }
+            _ => {
+                return None;
+            }
+        };
+
+        Some(result)
+    }
 
+    /// constructs the dataflow of the given pattern, where the edges of each relation have the label of its predicate
+    /// Relations are concatenated from left to right, where the pairs of each prefix are joined with the next relation on their target
+    pub fn path_pattern<'a>(shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, edge_predicates: Vec<String>, pattern: &PathPattern, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
+        assert_eq!(edge_predicates.len(), pattern.num_predicates(), "# of predicates does not match the relations of the pattern");
+
+        let distinct = shared.distinct();
+        let mut relations = pattern.relations.iter();
+        let first = match relations.next().expect("Path pattern has no relation") {
+            Relation::Edges(predicate) => shared.edges(&edge_predicates[*predicate]),
+            Relation::Closure(predicate) => transitive_closure_arranged(&shared.by_source(&edge_predicates[*predicate])),
+        };
+        // the last join produces the results unless they are repeated
+        let last_join = if pattern.closure { None } else { pattern.relations.len().checked_sub(2) };
+        let concatenation = relations.enumerate().fold(first, |prefix, (join, relation)| {
+            let prefix = prefix.map(|(s, t)| (t, s));
+            let pairs = match relation {
+                // edges are joined with their shared arrangement
+                Relation::Edges(predicate) => prefix.join_core(&shared.by_source(&edge_predicates[*predicate]), |_key, s1, t2| Some((*s1, *t2))),
+                Relation::Closure(predicate) => prefix.join(&transitive_closure_arranged(&shared.by_source(&edge_predicates[*predicate]))).map(|(_key, (s1, t2))| (s1, t2)),
+            };
+            if Some(join) == last_join { distinct.results(&pairs) } else { distinct.intermediate(&pairs) }
+        });
+
+        // obtain transitive closure over the concatenation if it is repeated
+        let results = if pattern.closure { transitive_closure(&concatenation, Direction::Forward) } else { concatenation };
 
         // construct sgts for reachable pairs
         results.map(move |(s, t)| StreamingGraphTuple::new(s, t, output_label.clone(), HalfOpenTimeInterval::ZERO))
     }
 
-    pub fn query4<'a>(input: Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphEdge, isize>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
-        assert_eq!(edge_predicates.len(), 3);
-        // logical partitioning based on labels
-        let streams = input
-            .inner
-            .partition(3, move |sgt| {
-                if sgt.0.get_label() == &edge_predicates[0] {
-                    (0, sgt)
-                } else if sgt.0.get_label() == &edge_predicates[1] {
-                    (1, sgt)
-                } else {
-                    (2, sgt)
-                }
-            });
-
-        let s0 = streams[0].as_collection().map(|sgt| (sgt.get_target(), sgt.get_source()));
-        let s1 = streams[1].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-        let s2 = streams[2].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-
-        let cq = s0
-            .join(&s1)
-            .map(|(_key, (s1, t2))| (t2, s1)).distinct()
-            .join(&s2)
-            .map(|(_key, (s1, t3))| (s1, t3)).distinct();
+    /// constructs the dataflow that evaluates the RPQ over the product of the graph and the minimal automaton of the query,
+    /// i.e., the (source, target) pairs of paths of at least one edge whose labels form a word of the query
+    /// Reachable (vertex, (source, state)) triples are arranged by vertex once per round, and joined with the shared arrangement
+    /// of the edges of each label of the automaton, so that each round only joins the changes of reachability with the edges
+    /// panics if the RPQ cannot be parsed, or one of its labels is not arranged by `shared`
+    pub fn rpq<'a>(shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, query: &str, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
+        let automaton = RPQParser::new().parse_rpq(query).unwrap_or_else(|e| panic!("CANNOT create DFA from given RPQ {}: {}", query, e)).trim();
+
+        // target state of the transition of each source state for each label
+        let mut transitions: BTreeMap<String, HashMap<u8, u8>> = BTreeMap::new();
+        for state in 0..automaton.num_states {
+            for (label, target) in automaton.iter_outgoing_transitions(state) {
+                transitions.entry(label.to_string()).or_insert_with(HashMap::new).insert(state, target);
+            }
+        }
+
+        // paths of a single edge from the start state
+        let scope = shared.scope();
+        let seeds = transitions.iter()
+            .filter_map(|(label, label_transitions)| label_transitions.get(&0).map(|target| (label, *target)))
+            .fold(empty(&scope).as_collection(), |seeds, (label, target)| seeds.concat(&shared.edges(label).map(move |(s, t)| (t, (s, target)))));
+        let arrangements: Vec<_> = transitions.into_iter().map(|(label, label_transitions)| (shared.by_source(&label), label_transitions)).collect();
+
+        let reachability = seeds.iterate(|reach| {
+            let scope = reach.scope();
+            let reach = reach.arrange_by_key();
+            arrangements.iter()
+                .map(|(edges, label_transitions)| {
+                    let label_transitions = label_transitions.clone();
+                    reach.join_core(&edges.enter(&scope), move |_vertex, (source, state), target| label_transitions.get(state).map(|next| (*target, (*source, *next))))
+                })
+                .fold(seeds.enter(&scope), |derived, step| derived.concat(&step))
+                .distinct()
+        });
 
-        // obtain transitive closure over the subgraph pattern
-        let results = cq
-            .iterate(|transitive| {
-                let cq = cq.enter(&transitive.scope());
-                transitive
-                    .map(|(s, t)| (t, s))
-                    .join(&cq)
-                    .map(|(_key, (s1, t2))| (s1, t2))
-                    .concat(&cq)
-                    .distinct()
-            });
+        let results = shared.distinct().results(&reachability
+            .filter(move |(_vertex, (_source, state))| automaton.is_final_state(*state))
+            .map(|(vertex, (source, _state))| (source, vertex)));
 
         // construct sgts for reachable pairs
         results.map(move |(s, t)| StreamingGraphTuple::new(s, t, output_label.clone(), HalfOpenTimeInterval::ZERO))
     }
 
-    pub fn query5<'a>(input: Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphEdge, isize>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
+    pub fn hash_join<'a>(shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
+        assert_eq!(edge_predicates.len(), 2);
+        Self::path_pattern(shared, edge_predicates, &PathPattern::concatenation(2), output_label)
+    }
+
+    pub fn query1<'a>(shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
+        assert_eq!(edge_predicates.len(), 1);
+        Self::path_pattern(shared, edge_predicates, &PathPattern::new(vec![Relation::Closure(0)]), output_label)
+    }
+
+    pub fn query2<'a>(shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
+        assert_eq!(edge_predicates.len(), 2);
+        Self::path_pattern(shared, edge_predicates, &PathPattern::new(vec![Relation::Edges(0), Relation::Closure(1)]), output_label)
+    }
+
+    pub fn query3<'a>(shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
         assert_eq!(edge_predicates.len(), 3);
-        // logical partitioning based on labels
-        let streams = input
-            .inner
-            .partition(3, move |sgt| {
-                if sgt.0.get_label() == &edge_predicates[0] {
-                    (0, sgt)
-                } else if sgt.0.get_label() == &edge_predicates[1] {
-                    (1, sgt)
-                } else {
-                    (2, sgt)
-                }
-            });
-
-        let stream0 = streams[0].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-        let stream1 = streams[1].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-        let stream2 = streams[2].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-
-        let results = stream1
-            .map(|(s1, t1)| (t1, s1))
-            .join(&stream0)
-            .map(|(_key, (s1, t0))| (t0, s1)).distinct()
-            .join(&stream1.map(|(s3, t3)| (t3, s3)))
-            .map(|(_key, (s1, s3))| (s1, s3)).distinct()
-            .join(&stream2.map(|(s2, t2)| (t2, s2)))
-            .filter(|(_key, (s3, t2))| s3 == t2)
-            .map(|(key, (s3, _t2))| (key, s3)).distinct();
+        Self::path_pattern(shared, edge_predicates, &PathPattern::new(vec![Relation::Edges(0), Relation::Closure(1), Relation::Closure(2)]), output_label)
+    }
+
+    pub fn query4<'a>(shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
+        assert_eq!(edge_predicates.len(), 3);
+        Self::path_pattern(shared, edge_predicates, &PathPattern::concatenation(3).with_closure(), output_label)
+    }
 
+    pub fn query5<'a>(shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
+        assert_eq!(edge_predicates.len(), 3);
+        let distinct = shared.distinct();
+
+        let first = shared.edges(&edge_predicates[1])
+            .map(|(s1, t1)| (t1, s1))
+            .join_core(&shared.by_source(&edge_predicates[0]), |_key, s1, t0| Some((*t0, *s1)));
+        let second = distinct.intermediate(&first)
+            .join_core(&shared.by_target(&edge_predicates[1]), |_key, s1, s3| Some((*s1, *s3)));
+        let results = distinct.results(&distinct.intermediate(&second)
+            .join_core(&shared.by_target(&edge_predicates[2]), |key, s3, t2| if s3 == t2 { Some((*key, *s3)) } else { None }));
 
         // construct sgts for reachable pairs
         results.map(move |(s, t)| StreamingGraphTuple::new(s, t, output_label.clone(), HalfOpenTimeInterval::ZERO))
     }
 
-    pub fn query6<'a>(input: Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphEdge, isize>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
+    pub fn query6<'a>(shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
         assert_eq!(edge_predicates.len(), 3);
-        // logical partitioning based on labels
-        let streams = input
-            .inner
-            .partition(3, move |sgt| {
-                if sgt.0.get_label() == &edge_predicates[0] {
-                    (0, sgt)
-                } else if sgt.0.get_label() == &edge_predicates[1] {
-                    (1, sgt)
-                } else {
-                    (2, sgt)
-                }
-            });
-
-        let s0 = streams[0].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-        let s1 = streams[1].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-        let s2 = streams[2].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
 
-        //get transitive closure of the first stream
-        // get transive closure of knows
-        let closure1 = s0
-            .iterate(|transitive| {
-                let edges = s0.enter(&transitive.scope());
-                transitive
-                    .map(|(s, t)| (t, s))
-                    .join(&edges)
-                    .map(|(_key, (s1, t2))| (s1, t2))
-                    .concat(&edges)
-                    .distinct()
-            });
-
-        let results = s2
-            .join(&s1.map(|(s, t)| (t, s)))
-            .map(|(_key, (hc_t, l_s))| (l_s, hc_t)).distinct()
-            .join(&closure1)
-            .filter(|(_key, (hc_t, k_t))| hc_t == k_t)
-            .map(|(k_s, (_, k_t))| (k_s, k_t)).distinct();
+        let results = shared.distinct().results(&Self::closure_triangle(shared, &edge_predicates));
 
         // construct sgts for reachable pairs
         results.map(move |(s, t)| StreamingGraphTuple::new(s, t, output_label.clone(), HalfOpenTimeInterval::ZERO))
     }
 
-    pub fn query6_cq<'a>(input: Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphEdge, isize>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
+    pub fn query6_cq<'a>(shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
         assert_eq!(edge_predicates.len(), 3);
-        // logical partitioning based on labels
-        let streams = input
-            .inner
-            .partition(3, move |sgt| {
-                if sgt.0.get_label() == &edge_predicates[0] {
-                    (0, sgt)
-                } else if sgt.0.get_label() == &edge_predicates[1] {
-                    (1, sgt)
-                } else {
-                    (2, sgt)
-                }
-            });
-
-        let s0 = streams[0].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-        let s1 = streams[1].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-        let s2 = streams[2].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-
-        let results = s2
-            .join(&s1.map(|(s, t)| (t, s)))
-            .map(|(_key, (hc_t, l_s))| (l_s, hc_t)).distinct()
-            .join(&s0)
-            .filter(|(_key, (hc_t, k_t))| hc_t == k_t)
-            .map(|(k_s, (_, k_t))| (k_s, k_t)).distinct();
+
+        let results = shared.distinct().results(&Self::triangle(shared, &edge_predicates));
 
         // construct sgts for reachable pairs
         results.map(move |(s, t)| StreamingGraphTuple::new(s, t, output_label.clone(), HalfOpenTimeInterval::ZERO))
     }
 
-    pub fn query7<'a>(input: Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphEdge, isize>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
+    pub fn query7<'a>(shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
         assert_eq!(edge_predicates.len(), 3);
-        // logical partitioning based on labels
-        let streams = input
-            .inner
-            .partition(3, move |sgt| {
-                if sgt.0.get_label() == &edge_predicates[0] {
-                    (0, sgt)
-                } else if sgt.0.get_label() == &edge_predicates[1] {
-                    (1, sgt)
-                } else {
-                    (2, sgt)
-                }
-            });
-
-        let s0 = streams[0].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-        let s1 = streams[1].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-        let s2 = streams[2].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
 
-        //get transitive closure of the first stream
-        // get transive closure of knows
-        let closure1 = s0
-            .iterate(|transitive| {
-                let edges = s0.enter(&transitive.scope());
-                transitive
-                    .map(|(s, t)| (t, s))
-                    .join(&edges)
-                    .map(|(_key, (s1, t2))| (s1, t2))
-                    .concat(&edges)
-                    .distinct()
-            });
-
-        let cq = s2
-            .join(&s1.map(|(s, t)| (t, s)))
-            .map(|(_key, (hc_t, l_s))| (l_s, hc_t)).distinct()
-            .join(&closure1)
-            .filter(|(_key, (hc_t, k_t))| hc_t == k_t)
-            .map(|(k_s, (_, k_t))| (k_s, k_t)).distinct();
+        let cq = shared.distinct().intermediate(&Self::closure_triangle(shared, &edge_predicates));
 
         // obtain transitive closure over the subgraph pattern
-        let t = cq
-            .iterate(|transitive| {
-                let cq = cq.enter(&transitive.scope());
-                transitive
-                    .map(|(s, t)| (t, s))
-                    .join(&cq)
-                    .map(|(_key, (s1, t2))| (s1, t2))
-                    .concat(&cq)
-                    .distinct()
-            });
+        let t = transitive_closure(&cq, Direction::Forward);
 
         // join with last `c` edge
-        let results = t.map(|(s, t)| (t, s))
-            .join(&s2.map(|(s, t)| (t, s)))
-            .map(|(_key, (t_s, s2_s))| (t_s, s2_s)).distinct();
+        let results = shared.distinct().results(&t.map(|(s, t)| (t, s))
+            .join_core(&shared.by_target(&edge_predicates[2]), |_key, t_s, s2_s| Some((*t_s, *s2_s))));
 
         // construct sgts for reachable pairs
         results.map(move |(s, t)| StreamingGraphTuple::new(s, t, output_label.clone(), HalfOpenTimeInterval::ZERO))
     }
 
-    pub fn query7_cq<'a>(input: Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphEdge, isize>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
+    pub fn query7_cq<'a>(shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
         assert_eq!(edge_predicates.len(), 3);
-        // logical partitioning based on labels
-        let streams = input
-            .inner
-            .partition(3, move |sgt| {
-                if sgt.0.get_label() == &edge_predicates[0] {
-                    (0, sgt)
-                } else if sgt.0.get_label() == &edge_predicates[1] {
-                    (1, sgt)
-                } else {
-                    (2, sgt)
-                }
-            });
-
-        let s0 = streams[0].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-        let s1 = streams[1].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-        let s2 = streams[2].as_collection().map(|sgt| (sgt.get_source(), sgt.get_target()));
-
-        let cq = s2
-            .join(&s1.map(|(s, t)| (t, s)))
-            .map(|(_key, (hc_t, l_s))| (l_s, hc_t)).distinct()
-            .join(&s0)
-            .filter(|(_key, (hc_t, k_t))| hc_t == k_t)
-            .map(|(k_s, (_, k_t))| (k_s, k_t)).distinct();
+
+        let cq = shared.distinct().intermediate(&Self::triangle(shared, &edge_predicates));
 
         // obtain transitive closure over the subgraph pattern
-        let t = cq
-            .iterate(|transitive| {
-                let cq = cq.enter(&transitive.scope());
-                transitive
-                    .map(|(s, t)| (t, s))
-                    .join(&cq)
-                    .map(|(_key, (s1, t2))| (s1, t2))
-                    .concat(&cq)
-                    .distinct()
-            });
+        let t = transitive_closure(&cq, Direction::Forward);
 
         // join with last `c` edge
-        let results = t.map(|(s, t)| (t, s))
-            .join(&s2.map(|(s, t)| (t, s)))
-            .map(|(_key, (t_s, s2_s))| (t_s, s2_s)).distinct();
+        let results = shared.distinct().results(&t.map(|(s, t)| (t, s))
+            .join_core(&shared.by_target(&edge_predicates[2]), |_key, t_s, s2_s| Some((*t_s, *s2_s))));
 
         // construct sgts for reachable pairs
         results.map(move |(s, t)| StreamingGraphTuple::new(s, t, output_label.clone(), HalfOpenTimeInterval::ZERO))
     }
 
-    pub fn query8<'a>(input: Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphEdge, isize>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
+    pub fn query8<'a>(shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, edge_predicates: Vec<String>, output_label: String) -> Collection<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple, isize> {
         assert_eq!(edge_predicates.len(), 1);
 
-        let stream = input.map(|sge| (sge.get_target(), sge.get_source()));
-
-        let cq = stream
-            .join(&stream)
-            .filter(|(_key, (s0, s1))| s0 != s1)
-            .map(|(_key, (s0, s1))| (s0, s1)).distinct();
+        let cq = shared.distinct().intermediate(&shared.edges(&edge_predicates[0])
+            .map(|(s, t)| (t, s))
+            .join_core(&shared.by_target(&edge_predicates[0]), |_key, s0, s1| if s0 != s1 { Some((*s0, *s1)) } else { None }));
 
         // obtain transitive closure over the subgraph pattern
-        let results = cq
-            .iterate(|transitive| {
-                let cq = cq.enter(&transitive.scope());
-                transitive
-                    .map(|(s, t)| (t, s))
-                    .join(&cq)
-                    .map(|(_key, (s1, t2))| (s1, t2))
-                    .concat(&cq)
-                    .distinct()
-            });
+        let results = transitive_closure(&cq, Direction::Forward);
+
         // construct sgts for reachable pairs
         results.map(move |(s, t)| StreamingGraphTuple::new(s, t, output_label.clone(), HalfOpenTimeInterval::ZERO))
     }
+
+    /// (source, target) pairs of the first edges of triangles of the edges of the first predicate, closed by a path of
+    /// an edge of the second predicate and an edge of the third predicate from the source to the target
+    /// Pairs of the last join are deduplicated by callers, as it produces the results of `query6-cq`
+    fn triangle<'a>(shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, edge_predicates: &[String]) -> Collection<Child<'a, Worker<Generic>, u64>, (VertexType, VertexType), isize> {
+        let paths = shared.edges(&edge_predicates[2])
+            .join_core(&shared.by_target(&edge_predicates[1]), |_key, hc_t, l_s| Some((*l_s, *hc_t)));
+        shared.distinct().intermediate(&paths)
+            .join_core(&shared.by_source(&edge_predicates[0]), |k_s, hc_t, k_t| if hc_t == k_t { Some((*k_s, *k_t)) } else { None })
+    }
+
+    /// pairs of `triangle` where the first edge is replaced by a path of one or more edges of the first predicate,
+    /// which are deduplicated by callers as in `triangle`
+    fn closure_triangle<'a>(shared: &mut SharedArrangements<Child<'a, Worker<Generic>, u64>>, edge_predicates: &[String]) -> Collection<Child<'a, Worker<Generic>, u64>, (VertexType, VertexType), isize> {
+        //get transitive closure of the first stream
+        // get transive closure of knows
+        let closure1 = transitive_closure_arranged(&shared.by_source(&edge_predicates[0]));
+
+        let paths = shared.edges(&edge_predicates[2])
+            .join_core(&shared.by_target(&edge_predicates[1]), |_key, hc_t, l_s| Some((*l_s, *hc_t)));
+        shared.distinct().intermediate(&paths)
+            .join(&closure1)
+            .filter(|(_key, (hc_t, k_t))| hc_t == k_t)
+            .map(|(k_s, (_, k_t))| (k_s, k_t))
+    }
 }
 
 
 /// Pre-constructed SGA dataflows for queries in SGA paper (Table1)
+/// and path-style shortcuts of LDBC SNB Interactive queries, named `ldbc-<query>`
 pub struct SGAQueryLibrary;
 
 impl SGAQueryLibrary {
+    /// constructs the dataflow for the query with the given name from Table 1 or the LDBC SNB shortcuts
+    /// returns `None` if there is no query with the given name
+    pub fn by_name<'a>(query_name: &str, input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>) -> Option<Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>> {
+        let result = match query_name {
+            "join" => {
+                Self::hash_join(input, edge_predicates, "join".to_string())
+            }
+            "query1" => {
+                Self::query1(input, edge_predicates, "q1".to_string())
+            }
+            "query2" => {
+                Self::query2(input, edge_predicates, "q2".to_string())
+            }
+            "query2-a" => {
+                Self::query2_a(input, edge_predicates, "q2".to_string())
+            }
+            "query3" => {
+                Self::query3(input, edge_predicates, "q3".to_string())
+            }
+            "query3-a" => {
+                Self::query3_a(input, edge_predicates, "q3".to_string())
+            }
+            "query4" => {
+                Self::query4(input, edge_predicates, "q4".to_string())
+            }
+            "query4-a" => {
+                Self::query4_a(input, edge_predicates, "q4".to_string())
+            }
+            "query4-pc1" => {
+                Self::query4_pc1(input, edge_predicates, "q4".to_string())
+            }
+            "query4-pc2" => {
+                Self::query4_pc2(input, edge_predicates, "q4".to_string())
+            }
+            "query5" => {
+                Self::query5(input, edge_predicates, "q5".to_string())
+            }
+            "query6" => {
+                Self::query6(input, edge_predicates, "q6".to_string())
+            }
+            "query6-cq" => {
+                Self::query6_cq(input, edge_predicates, "q6".to_string())
+            }
+            "query7" => {
+                Self::query7(input, edge_predicates, "q7".to_string())
+            }
+            "query7-cq" => {
+                Self::query7_cq(input, edge_predicates, "q7".to_string())
+            }
+            "query8" => {
+                Self::query8(input, edge_predicates, "q8".to_string())
+            }
+            "ldbc-ic1" => {
+                Self::ldbc_ic1(input, edge_predicates, "ic1".to_string())
+            }
+            "ldbc-ic2" => {
+                Self::ldbc_ic2(input, edge_predicates, "ic2".to_string())
+            }
+            "ldbc-ic9" => {
+                Self::ldbc_ic9(input, edge_predicates, "ic9".to_string())
+            }
+            "ldbc-ic13" => {
+                Self::ldbc_ic13(input, edge_predicates, "ic13".to_string())
+            }
+            "ldbc-is2" => {
+                Self::ldbc_is2(input, edge_predicates, "is2".to_string())
+            }
+            "ldbc-is6" => {
+                Self::ldbc_is6(input, edge_predicates, "is6".to_string())
+            }
+            "ldbc-is7" => {
+                Self::ldbc_is7(input, edge_predicates, "is7".to_string())
+            }
+            _ => {
+                return None;
+            }
+        };
+
+        Some(result)
+    }
+
+    /// constructs the dataflow for the query with the given name over multiple physical streams, one stream per edge predicate
+    /// tuples of the i-th stream are relabeled with the i-th edge predicate, so each stream is bound to a single relation of the query
+    /// streams progress independently, and results at a time are produced once all streams have passed that time
+    /// returns `None` if there is no query with the given name
+    pub fn by_streams<'a>(query_name: &str, inputs: Vec<Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>>, edge_predicates: Vec<String>) -> Option<Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>> {
+        assert_eq!(inputs.len(), edge_predicates.len(), "Each edge predicate requires its own input stream");
+        let scope = inputs.first().expect("At least one input stream is required").scope();
+
+        let relabeled_inputs: Vec<_> = inputs.iter().zip(edge_predicates.iter().cloned())
+            .map(|(input, predicate)| input.map(move |mut sgt| {
+                sgt.label = predicate.clone();
+                sgt
+            }))
+            .collect();
+
+        Self::by_name(query_name, scope.concatenate(relabeled_inputs), edge_predicates)
+    }
+
+    /// returns labels consumed by the query with the given name, i.e., labels of edges that can contribute to its results
+    /// returns `None` if there is no query with the given name
+    pub fn alphabet(query_name: &str, edge_predicates: &[String]) -> Option<HashSet<String>> {
+        // all queries of the library consume exactly their edge predicates
+        Self::explain(query_name, edge_predicates).map(|_| edge_predicates.iter().cloned().collect())
+    }
+
+    /// describes the plan of the query with the given name, one operator per line in evaluation order
+    /// each line is of the form `label = Operator[parameters](inputs)`, where inputs are edge predicates or labels of previous lines
+    /// returns `None` if there is no query with the given name
+    pub fn explain(query_name: &str, edge_predicates: &[String]) -> Option<String> {
+        let p = |i: usize| edge_predicates.get(i).cloned().unwrap_or_else(|| format!("${}", i));
+        let (a, b, c) = (p(0), p(1), p(2));
+        // labels as they are written in RPQs
+        let (qa, qb, qc) = (quote_label(&a), quote_label(&b), quote_label(&c));
+
+        let plan = match query_name {
+            "join" => vec![explain_join("join", &a, &b, "TS", "ST")],
+            "query1" => vec![explain_rpq("q1", &format!("{}*", qa), &[&a])],
+            "query2" => vec![
+                explain_rpq("cq", &format!("{}*", qb), &[&b]),
+                explain_join("q2", &a, "cq", "TS", "ST"),
+            ],
+            "query2-a" => vec![explain_rpq("q2", &format!("{}/{}*", qa, qb), &[&a, &b])],
+            "query3" => vec![
+                explain_rpq("cq1", &format!("{}*", qb), &[&b]),
+                explain_rpq("cq2", &format!("{}*", qc), &[&c]),
+                explain_join("j1", &a, "cq1", "TS", "ST"),
+                explain_join("q3", "j1", "cq2", "TS", "ST"),
+            ],
+            "query3-a" => vec![explain_rpq("q3", &format!("{}/{}*/{}*", qa, qb, qc), &[&a, &b, &c])],
+            "query4" => vec![
+                explain_join("j1", &a, &b, "TS", "ST"),
+                explain_join("cq", "j1", &c, "TS", "ST"),
+                explain_rpq("q4", "cq*", &["cq"]),
+            ],
+            "query4-a" => vec![explain_rpq("q4", &format!("({}/{}/{})+", qa, qb, qc), &[&a, &b, &c])],
+            "query4-pc1" => vec![
+                explain_join("cq", &a, &b, "TS", "ST"),
+                explain_rpq("q4", &format!("(cq/{})+", qc), &[&c, "cq"]),
+            ],
+            "query4-pc2" => vec![
+                explain_join("cq", &b, &c, "TS", "ST"),
+                explain_rpq("q4", &format!("({}/cq)+", qa), &[&a, "cq"]),
+            ],
+            "query5" => vec![
+                explain_join("j1", &b, &a, "TS", "ST"),
+                explain_join("j2", "j1", &b, "TT", "SS"),
+                explain_join_tuple("q5", "j2", &c, true, false),
+            ],
+            "query6" => vec![
+                explain_rpq("closure", &format!("{}*", qa), &[&a]),
+                explain_join("j1", &c, &b, "ST", "TS"),
+                explain_join_tuple("q6", "j1", "closure", true, true),
+            ],
+            "query6-cq" => vec![
+                explain_join("j1", &c, &b, "ST", "TS"),
+                explain_join_tuple("q6", "j1", &a, true, true),
+            ],
+            "query7" => vec![
+                explain_rpq("closure", &format!("{}*", qa), &[&a]),
+                explain_join("j1", &c, &b, "ST", "TS"),
+                explain_join_tuple("cq", "j1", "closure", true, true),
+                explain_rpq("r", "cq*", &["cq"]),
+                explain_join("q7", "r", &c, "TT", "SS"),
+            ],
+            "query7-cq" => vec![
+                explain_join("j1", &c, &b, "ST", "TS"),
+                explain_join_tuple("cq", "j1", &a, true, true),
+                explain_rpq("r", "cq*", &["cq"]),
+                explain_join("q7", "r", &c, "TT", "SS"),
+            ],
+            "query8" => vec![
+                explain_join("cq", &a, &a, "TT", "SS"),
+                "cq = Filter[source != target](cq)".to_string(),
+                explain_rpq("q8", "cq*", &["cq"]),
+            ],
+            "ldbc-ic1" => vec![explain_rpq("ic1", &format!("{0}|{0}/{0}|{0}/{0}/{0}", qa), &[&a])],
+            "ldbc-ic2" => vec![explain_join("ic2", &a, &b, "TT", "SS")],
+            "ldbc-ic9" => vec![
+                explain_rpq("f", &format!("{0}|{0}/{0}", qa), &[&a]),
+                explain_join("ic9", "f", &b, "TT", "SS"),
+            ],
+            "ldbc-ic13" => vec![explain_rpq("ic13", &format!("{}+", qa), &[&a])],
+            "ldbc-is2" => vec![
+                explain_rpq("r", &format!("{}+", qb), &[&b]),
+                explain_join("is2", &a, "r", "SS", "TT"),
+            ],
+            "ldbc-is6" => vec![
+                explain_rpq("r", &format!("{}+", qa), &[&a]),
+                explain_join("j1", "r", &b, "TT", "SS"),
+                format!("is6 = Union(j1, Reverse({}))", b),
+            ],
+            "ldbc-is7" => vec![explain_join("is7", &a, &b, "SS", "TT")],
+            _ => {
+                return None;
+            }
+        };
+
+        Some(plan.join("\n"))
+    }
+
     pub fn hash_join<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
         assert_eq!(edge_predicates.len(), 2);
         // logical partitioning based on labels
@@ -496,7 +660,7 @@ impl SGAQueryLibrary {
     pub fn query1<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
         assert_eq!(edge_predicates.len(), 1);
         // create RPQ string
-        let mut query_string = String::from(&edge_predicates[0]);
+        let mut query_string = quote_label(&edge_predicates[0]);
         query_string.push_str("*");
 
         input.regular_path_query(&query_string, output_label)
@@ -519,7 +683,7 @@ impl SGAQueryLibrary {
         let mut query_string = String::from(&query_predicates[1]);
         query_string.push_str("*");
 
-        let closure = streams[1].regular_path_query(&query_string, "cq".to_string());
+        let closure = streams[1].regular_path_query(&query_string, stage_label(&output_label, "cq"));
 
         streams[0].hash_join(&closure, HashJoinAttributePair::TS, HashJoinAttributePair::ST, output_label)
     }
@@ -527,9 +691,9 @@ impl SGAQueryLibrary {
     pub fn query2_a<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
         assert_eq!(edge_predicates.len(), 2);
         // create RPQ string
-        let mut query_string = String::from(&edge_predicates[0]);
+        let mut query_string = quote_label(&edge_predicates[0]);
         query_string.push_str("/");
-        query_string.push_str(&edge_predicates[1]);
+        query_string.push_str(&quote_label(&edge_predicates[1]));
         query_string.push_str("*");
 
         input.regular_path_query(&query_string, output_label)
@@ -549,27 +713,27 @@ impl SGAQueryLibrary {
             }
         });
 
-        let mut query_string1 = String::from(&edge_predicates[1]);
+        let mut query_string1 = quote_label(&edge_predicates[1]);
         query_string1.push_str("*");
-        let mut query_string2 = String::from(&edge_predicates[2]);
+        let mut query_string2 = quote_label(&edge_predicates[2]);
         query_string2.push_str("*");
 
-        let closure1 = streams[1].regular_path_query(&query_string1, "cq1".to_string());
-        let closure2 = streams[2].regular_path_query(&query_string2, "cq2".to_string());
+        let closure1 = streams[1].regular_path_query(&query_string1, stage_label(&output_label, "cq1"));
+        let closure2 = streams[2].regular_path_query(&query_string2, stage_label(&output_label, "cq2"));
 
         streams[0]
-            .hash_join(&closure1, HashJoinAttributePair::TS, HashJoinAttributePair::ST, "j1".to_string())
+            .hash_join(&closure1, HashJoinAttributePair::TS, HashJoinAttributePair::ST, stage_label(&output_label, "j1"))
             .hash_join(&closure2, HashJoinAttributePair::TS, HashJoinAttributePair::ST, output_label)
     }
 
     pub fn query3_a<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
         assert_eq!(edge_predicates.len(), 3);
         // create RPQ string
-        let mut query_string = String::from(&edge_predicates[0]);
+        let mut query_string = quote_label(&edge_predicates[0]);
         query_string.push_str("/");
-        query_string.push_str(&edge_predicates[1]);
+        query_string.push_str(&quote_label(&edge_predicates[1]));
         query_string.push_str("*/");
-        query_string.push_str(&edge_predicates[2]);
+        query_string.push_str(&quote_label(&edge_predicates[2]));
         query_string.push_str("*");
 
         input.regular_path_query(&query_string, output_label)
@@ -580,11 +744,11 @@ impl SGAQueryLibrary {
         assert_eq!(edge_predicates.len(), 3);
         // create RPQ string
         let mut query_string = String::from("(");
-        query_string.push_str(&edge_predicates[0]);
+        query_string.push_str(&quote_label(&edge_predicates[0]));
         query_string.push_str("/");
-        query_string.push_str(&edge_predicates[1]);
+        query_string.push_str(&quote_label(&edge_predicates[1]));
         query_string.push_str("/");
-        query_string.push_str(&edge_predicates[2]);
+        query_string.push_str(&quote_label(&edge_predicates[2]));
         query_string.push_str(")+");
 
         input.regular_path_query(&query_string, output_label)
@@ -606,14 +770,14 @@ impl SGAQueryLibrary {
         });
 
         let cq = streams[0]
-            .hash_join(&streams[1], HashJoinAttributePair::TS, HashJoinAttributePair::ST, "cq".to_string());
+            .hash_join(&streams[1], HashJoinAttributePair::TS, HashJoinAttributePair::ST, stage_label(&output_label, "cq"));
 
-        // create RPQ string
-        let mut query_string = String::from("(cq/");
-        query_string.push_str(&edge_predicate3);
-        query_string.push_str(")+");
+        // (cq/c)+ where cq refers to the join results
+        let mut plan = PathPlan::new().with_edges(&streams[2]);
+        let cq = plan.stream_ref(&cq);
+        let expression = PathExpression::symbol(cq).then(PathExpression::label(&edge_predicate3)).plus();
 
-        streams[2].concat(&cq).regular_path_query(&query_string, output_label)
+        plan.regular_path_query(&expression, output_label)
     }
 
     /// RPQ (a/b/c)+ hybrid evaluation that first materializes b/c join, then joins with a
@@ -632,14 +796,14 @@ impl SGAQueryLibrary {
         });
 
         let cq = streams[1]
-            .hash_join(&streams[2], HashJoinAttributePair::TS, HashJoinAttributePair::ST, "cq".to_string());
+            .hash_join(&streams[2], HashJoinAttributePair::TS, HashJoinAttributePair::ST, stage_label(&output_label, "cq"));
 
-        // create RPQ string
-        let mut query_string = String::from("(");
-        query_string.push_str(&edge_predicate1);
-        query_string.push_str("/cq)+");
+        // (a/cq)+ where cq refers to the join results
+        let mut plan = PathPlan::new().with_edges(&streams[0]);
+        let cq = plan.stream_ref(&cq);
+        let expression = PathExpression::label(&edge_predicate1).then(PathExpression::symbol(cq)).plus();
 
-        streams[0].concat(&cq).regular_path_query(&query_string, output_label)
+        plan.regular_path_query(&expression, output_label)
     }
 
     /// RPQ (a/b/c)+ join based evaluation that first materializes a/b/c join
@@ -658,13 +822,14 @@ impl SGAQueryLibrary {
         });
 
         let cq = streams[0]
-            .hash_join(&streams[1], HashJoinAttributePair::TS, HashJoinAttributePair::ST, "j1".to_string())
-            .hash_join(&streams[2], HashJoinAttributePair::TS, HashJoinAttributePair::ST, "cq".to_string());
+            .hash_join(&streams[1], HashJoinAttributePair::TS, HashJoinAttributePair::ST, stage_label(&output_label, "j1"))
+            .hash_join(&streams[2], HashJoinAttributePair::TS, HashJoinAttributePair::ST, stage_label(&output_label, "cq"));
 
-        // create RPQ string
-        let query_string = String::from("cq*");
+        // cq* where cq refers to the join results
+        let mut plan = PathPlan::new();
+        let cq = plan.stream_ref(&cq);
 
-        cq.regular_path_query(&query_string, output_label)
+        plan.regular_path_query(&PathExpression::symbol(cq).star(), output_label)
     }
 
     pub fn query5<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
@@ -684,15 +849,15 @@ impl SGAQueryLibrary {
         });
 
         streams[1]
-            .hash_join(&streams[0], HashJoinAttributePair::TS, HashJoinAttributePair::ST, "j1".to_string())
-            .hash_join(&streams[1], HashJoinAttributePair::TT, HashJoinAttributePair::SS, "j2".to_string())
+            .hash_join(&streams[0], HashJoinAttributePair::TS, HashJoinAttributePair::ST, stage_label(&output_label, "j1"))
+            .hash_join(&streams[1], HashJoinAttributePair::TT, HashJoinAttributePair::SS, stage_label(&output_label, "j2"))
             .hash_join_tuple(&streams[2], true, false, output_label)
     }
 
     pub fn query6<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
         assert_eq!(edge_predicates.len(), 3);
         // obtain closure of the first predicate
-        let mut query_string = String::from(&edge_predicates[0]);
+        let mut query_string = quote_label(&edge_predicates[0]);
         query_string.push_str("*");
 
         // split streams based on edge predicates
@@ -706,16 +871,16 @@ impl SGAQueryLibrary {
             }
         });
 
-        let closure = streams[0].regular_path_query(&query_string, "c".to_string());
+        let closure = streams[0].regular_path_query(&query_string, stage_label(&output_label, "closure"));
         streams[2]
-            .hash_join(&streams[1], HashJoinAttributePair::ST, HashJoinAttributePair::TS, "j1".to_string())
+            .hash_join(&streams[1], HashJoinAttributePair::ST, HashJoinAttributePair::TS, stage_label(&output_label, "j1"))
             .hash_join_tuple(&closure, true, true, output_label)
     }
 
     pub fn query6_cq<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
         assert_eq!(edge_predicates.len(), 3);
         // obtain closure of the first predicate
-        let mut query_string = String::from(&edge_predicates[0]);
+        let mut query_string = quote_label(&edge_predicates[0]);
         query_string.push_str("*");
 
         // split streams based on edge predicates
@@ -730,14 +895,14 @@ impl SGAQueryLibrary {
         });
 
         streams[2]
-            .hash_join(&streams[1], HashJoinAttributePair::ST, HashJoinAttributePair::TS, "j1".to_string())
+            .hash_join(&streams[1], HashJoinAttributePair::ST, HashJoinAttributePair::TS, stage_label(&output_label, "j1"))
             .hash_join_tuple(&streams[0], true, true, output_label)
     }
 
     pub fn query7<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
         assert_eq!(edge_predicates.len(), 3);
         // obtain closure of the first predicate
-        let mut query_string = String::from(&edge_predicates[0]);
+        let mut query_string = quote_label(&edge_predicates[0]);
         query_string.push_str("*");
 
         // split streams based on edge predicates
@@ -751,18 +916,18 @@ impl SGAQueryLibrary {
             }
         });
 
-        let closure = streams[0].regular_path_query(&query_string, "c".to_string());
+        let closure = streams[0].regular_path_query(&query_string, stage_label(&output_label, "closure"));
         streams[2]
-            .hash_join(&streams[1], HashJoinAttributePair::ST, HashJoinAttributePair::TS, "j1".to_string())
-            .hash_join_tuple(&closure, true, true, "cq".to_string())
-            .regular_path_query("cq*", "r".to_string())
+            .hash_join(&streams[1], HashJoinAttributePair::ST, HashJoinAttributePair::TS, stage_label(&output_label, "j1"))
+            .hash_join_tuple(&closure, true, true, stage_label(&output_label, "cq"))
+            .regular_path_query(&format!("{}*", quote_label(&stage_label(&output_label, "cq"))), stage_label(&output_label, "r"))
             .hash_join(&streams[2], HashJoinAttributePair::TT, HashJoinAttributePair::SS, output_label)
     }
 
     pub fn query7_cq<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
         assert_eq!(edge_predicates.len(), 3);
         // obtain closure of the first predicate
-        let mut query_string = String::from(&edge_predicates[0]);
+        let mut query_string = quote_label(&edge_predicates[0]);
         query_string.push_str("*");
 
         // split streams based on edge predicates
@@ -777,9 +942,9 @@ impl SGAQueryLibrary {
         });
 
         streams[2]
-            .hash_join(&streams[1], HashJoinAttributePair::ST, HashJoinAttributePair::TS, "j1".to_string())
-            .hash_join_tuple(&streams[0], true, true, "cq".to_string())
-            .regular_path_query("cq*", "r".to_string())
+            .hash_join(&streams[1], HashJoinAttributePair::ST, HashJoinAttributePair::TS, stage_label(&output_label, "j1"))
+            .hash_join_tuple(&streams[0], true, true, stage_label(&output_label, "cq"))
+            .regular_path_query(&format!("{}*", quote_label(&stage_label(&output_label, "cq"))), stage_label(&output_label, "r"))
             .hash_join(&streams[2], HashJoinAttributePair::TT, HashJoinAttributePair::SS, output_label)
     }
 
@@ -788,9 +953,157 @@ impl SGAQueryLibrary {
         // obtain closure of the first predicate
 
         input
-            .hash_join(&input, HashJoinAttributePair::TT, HashJoinAttributePair::SS, "cq".to_string())
+            .hash_join(&input, HashJoinAttributePair::TT, HashJoinAttributePair::SS, stage_label(&output_label, "cq"))
             .filter(|sgt| sgt.get_source() != sgt.get_target())
             .inspect(|sgt| trace!("CQ: {:?}", sgt))
-            .regular_path_query("cq*", output_label)
+            .regular_path_query(&format!("{}*", quote_label(&stage_label(&output_label, "cq"))), output_label)
     }
-}
\ No newline at end of file
+
+    /// LDBC SNB IC1 shortcut: persons within 3 hops of each person, i.e., RPQ knows|knows/knows|knows/knows/knows
+    /// predicates: knows
+    pub fn ldbc_ic1<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
+        assert_eq!(edge_predicates.len(), 1);
+        let query_string = format!("{0}|{0}/{0}|{0}/{0}/{0}", quote_label(&edge_predicates[0]));
+
+        input.regular_path_query(&query_string, output_label)
+    }
+
+    /// LDBC SNB IC2 shortcut: messages created by friends of each person as (person, message) pairs
+    /// predicates: knows, hasCreator
+    pub fn ldbc_ic2<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
+        assert_eq!(edge_predicates.len(), 2);
+        // split streams based on edge predicates
+        let streams = input.partition(2, move |sgt| {
+            if sgt.get_label() == &edge_predicates[0] {
+                (0, sgt)
+            } else {
+                (1, sgt)
+            }
+        });
+
+        streams[0].hash_join(&streams[1], HashJoinAttributePair::TT, HashJoinAttributePair::SS, output_label)
+    }
+
+    /// LDBC SNB IC9 shortcut: messages created by friends and friends of friends of each person as (person, message) pairs
+    /// predicates: knows, hasCreator
+    pub fn ldbc_ic9<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
+        assert_eq!(edge_predicates.len(), 2);
+        let query_string = format!("{0}|{0}/{0}", quote_label(&edge_predicates[0]));
+
+        // split streams based on edge predicates
+        let streams = input.partition(2, move |sgt| {
+            if sgt.get_label() == &edge_predicates[0] {
+                (0, sgt)
+            } else {
+                (1, sgt)
+            }
+        });
+
+        streams[0]
+            .regular_path_query(&query_string, stage_label(&output_label, "f"))
+            .hash_join(&streams[1], HashJoinAttributePair::TT, HashJoinAttributePair::SS, output_label)
+    }
+
+    /// LDBC SNB IC13 shortcut: pairs of persons that are connected through a path of knows edges
+    /// predicates: knows
+    pub fn ldbc_ic13<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
+        assert_eq!(edge_predicates.len(), 1);
+        let query_string = format!("{}+", quote_label(&edge_predicates[0]));
+
+        input.regular_path_query(&query_string, output_label)
+    }
+
+    /// LDBC SNB IS2 shortcut: original posts of the replies created by each person as (person, post) pairs
+    /// predicates: hasCreator, replyOf
+    pub fn ldbc_is2<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
+        assert_eq!(edge_predicates.len(), 2);
+        let query_string = format!("{}+", quote_label(&edge_predicates[1]));
+
+        // split streams based on edge predicates
+        let streams = input.partition(2, move |sgt| {
+            if sgt.get_label() == &edge_predicates[0] {
+                (0, sgt)
+            } else {
+                (1, sgt)
+            }
+        });
+
+        let replies = streams[1].regular_path_query(&query_string, stage_label(&output_label, "r"));
+        streams[0].hash_join(&replies, HashJoinAttributePair::SS, HashJoinAttributePair::TT, output_label)
+    }
+
+    /// LDBC SNB IS6 shortcut: forum of each message as (message, forum) pairs, where replies belong to the forum of their original post
+    /// predicates: replyOf, containerOf
+    pub fn ldbc_is6<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
+        assert_eq!(edge_predicates.len(), 2);
+        let query_string = format!("{}+", quote_label(&edge_predicates[0]));
+
+        // split streams based on edge predicates
+        let streams = input.partition(2, move |sgt| {
+            if sgt.get_label() == &edge_predicates[0] {
+                (0, sgt)
+            } else {
+                (1, sgt)
+            }
+        });
+
+        // posts are directly contained by their forum
+        let post_label = output_label.clone();
+        let posts = streams[1].map(move |mut sgt| {
+            std::mem::swap(&mut sgt.source, &mut sgt.target);
+            sgt.label = post_label.clone();
+            sgt
+        });
+
+        streams[0]
+            .regular_path_query(&query_string, stage_label(&output_label, "r"))
+            .hash_join(&streams[1], HashJoinAttributePair::TT, HashJoinAttributePair::SS, output_label)
+            .concat(&posts)
+    }
+
+    /// LDBC SNB IS7 shortcut: authors of the direct replies of each message as (message, author) pairs
+    /// predicates: replyOf, hasCreator
+    pub fn ldbc_is7<'a>(input: Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple>, edge_predicates: Vec<String>, output_label: String) -> Stream<Child<'a, Worker<Generic>, u64>, StreamingGraphTuple> {
+        assert_eq!(edge_predicates.len(), 2);
+        // split streams based on edge predicates
+        let streams = input.partition(2, move |sgt| {
+            if sgt.get_label() == &edge_predicates[0] {
+                (0, sgt)
+            } else {
+                (1, sgt)
+            }
+        });
+
+        streams[0].hash_join(&streams[1], HashJoinAttributePair::SS, HashJoinAttributePair::TT, output_label)
+    }
+}
+
+/// describes an RPQ operator for `SGAQueryLibrary::explain`
+fn explain_rpq(label: &str, query_string: &str, inputs: &[&str]) -> String {
+    format!("{} = RPQ[{}]({})", label, query_string, inputs.join(", "))
+}
+
+/// describes a hash join operator for `SGAQueryLibrary::explain`, attributes are given as in `HashJoinAttributePair`
+fn explain_join(label: &str, lhs: &str, rhs: &str, join_predicate: &str, join_output: &str) -> String {
+    let attribute = |name: char| if name == 'S' { "source" } else { "target" };
+    let predicate: Vec<char> = join_predicate.chars().collect();
+    let output: Vec<char> = join_output.chars().collect();
+
+    format!("{} = HashJoin[{}.{} = {}.{} -> ({}.{}, {}.{})]({}, {})", label,
+            lhs, attribute(predicate[0]), rhs, attribute(predicate[1]),
+            lhs, attribute(output[0]), rhs, attribute(output[1]),
+            lhs, rhs)
+}
+
+/// describes a tuple based hash join operator for `SGAQueryLibrary::explain`
+fn explain_join_tuple(label: &str, lhs: &str, rhs: &str, rhs_reverse: bool, output_reverse: bool) -> String {
+    let rhs_tuple = if rhs_reverse { format!("({0}.target, {0}.source)", rhs) } else { format!("({0}.source, {0}.target)", rhs) };
+    let output = if output_reverse { format!("({0}.target, {0}.source)", lhs) } else { format!("({0}.source, {0}.target)", lhs) };
+
+    format!("{0} = HashJoinTuple[({3}.source, {3}.target) = {1} -> {2}]({3}, {4})", label, rhs_tuple, output, lhs, rhs)
+}
+
+/// label of intermediate results of the given stage of a query, namespaced by the output label of the query
+fn stage_label(output_label: &str, stage: &str) -> String {
+    ResultLabel::stage(output_label, stage).to_string()
+}
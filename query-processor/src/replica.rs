@@ -0,0 +1,164 @@
+//! Read-only replica of the window state of an RPQ operator, loaded from its state file of an anomaly dump, see `util::anomaly`
+//!
+//! A replica does not consume any stream. It serves batch RPQ evaluations over the captured graph, optionally restricted to
+//! the edges that are valid at a given time, as result views that look up the targets each source reaches,
+//! e.g., for offline analysis of captured window states. Spanning trees are captured as summaries of their size and expiry,
+//! so results are derived from the graph with a `StaticGraph` instead of from the trees.
+//! The graph only has the edges of the labels of the dumped query, so a replica evaluates only RPQs over these labels,
+//! e.g., the dumped query itself, and rejects RPQs with other labels instead of returning partial results.
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::Path;
+
+use crate::graph::{ExpiredEdge, StaticGraph};
+use crate::query::parser::RPQParser;
+use crate::util::types::{HalfOpenTimeInterval, VertexType};
+
+/// size and min expiry of a spanning tree of the captured state
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeSummary {
+    pub root: VertexType,
+    pub nodes: usize,
+    pub min_expiry: u64,
+}
+
+/// Graph and spanning tree summaries of an RPQ operator, as `(source, label, target, interval)` edges
+#[derive(Clone, Debug, Default)]
+pub struct Replica {
+    // labels that the graph has edges of, None if it has edges of all labels, e.g., after a hot swap
+    labels: Option<BTreeSet<String>>,
+    edges: Vec<ExpiredEdge>,
+    trees: Vec<TreeSummary>,
+}
+
+impl Replica {
+    /// loads the state file of an RPQ operator, i.e., `<dump directory>/<output label>-<worker>.txt`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| format!("Cannot read state file {}: {}", path.display(), e))?;
+        Self::parse(&content).map_err(|e| format!("Invalid state file {}: {}", path.display(), e))
+    }
+
+    fn parse(content: &str) -> Result<Self, String> {
+        let mut replica = Self { labels: Some(BTreeSet::new()), ..Self::default() };
+        let mut has_labels = false;
+        let mut section = "";
+        for (line_number, line) in content.lines().enumerate() {
+            let invalid = || format!("line {} is not a valid {} entry: {}", line_number + 1, section, line);
+            if let Some(header) = line.strip_prefix("# ") {
+                section = header.split(':').next().unwrap_or("");
+                if section == "labels" {
+                    has_labels = true;
+                    if header == "labels: all" {
+                        replica.labels = None;
+                    }
+                }
+                continue;
+            }
+            match section {
+                "labels" => {
+                    replica.labels.as_mut().ok_or_else(invalid)?.insert(line.to_string());
+                }
+                "graph" => {
+                    let mut fields = line.splitn(4, ' ');
+                    let source = fields.next().and_then(|field| field.parse::<VertexType>().ok()).ok_or_else(invalid)?;
+                    let label = fields.next().ok_or_else(invalid)?.to_string();
+                    let target = fields.next().and_then(|field| field.parse::<VertexType>().ok()).ok_or_else(invalid)?;
+                    let interval = fields.next().and_then(parse_interval).ok_or_else(invalid)?;
+                    replica.edges.push((source, label, target, interval));
+                }
+                "delta" => {
+                    // tree <root>: <nodes> nodes, min expiry <expiry>
+                    let summary = line.strip_prefix("tree ")
+                        .and_then(|summary| summary.split_once(": "))
+                        .and_then(|(root, summary)| summary.split_once(" nodes, min expiry ").map(|(nodes, expiry)| (root, nodes, expiry)))
+                        .and_then(|(root, nodes, expiry)| Some(TreeSummary { root: root.parse().ok()?, nodes: nodes.parse().ok()?, min_expiry: expiry.parse().ok()? }))
+                        .ok_or_else(invalid)?;
+                    replica.trees.push(summary);
+                }
+                // stashed tuples are not part of the window yet
+                "stash" => {}
+                _ => return Err(invalid()),
+            }
+        }
+        if !has_labels {
+            return Err("labels of the dumped query are missing".to_string());
+        }
+        Ok(replica)
+    }
+
+    /// edges of the captured graph
+    pub fn edges(&self) -> &[ExpiredEdge] {
+        &self.edges
+    }
+
+    /// spanning trees of the captured state
+    pub fn trees(&self) -> &[TreeSummary] {
+        &self.trees
+    }
+
+    /// labels that the captured graph has edges of, or None if it has edges of all labels
+    pub fn labels(&self) -> Option<&BTreeSet<String>> {
+        self.labels.as_ref()
+    }
+
+    /// evaluates the RPQ over the captured graph, or over its edges that are valid at the given time if it is set
+    /// returns an error if the RPQ has a label that the graph does not capture, i.e., a label outside the dumped query
+    pub fn evaluate(&self, query: &str, at: Option<u64>) -> Result<ResultView, String> {
+        let automaton = RPQParser::new().parse_rpq(query).map_err(|e| format!("CANNOT create DFA from given RPQ {}: {}", query, e))?;
+        if let Some(labels) = &self.labels {
+            let mut missing: Vec<&String> = automaton.alphabet.iter().filter(|label| !labels.contains(*label)).collect();
+            if !missing.is_empty() {
+                missing.sort();
+                return Err(format!("RPQ {} has labels {:?} that the dumped query does not have, the replica only has edges of {:?}", query, missing, labels));
+            }
+        }
+        let graph = StaticGraph::from_edges(self.edges.iter()
+            .filter(|(_, _, _, interval)| at.map_or(true, |time| interval.start <= time && time < interval.end))
+            .map(|(source, label, target, _)| (*source, label.as_str(), *target)));
+
+        let mut results: BTreeMap<VertexType, Vec<VertexType>> = BTreeMap::new();
+        for (source, target) in graph.reachability(&automaton) {
+            results.entry(source).or_insert_with(Vec::new).push(target);
+        }
+        results.values_mut().for_each(|targets| targets.sort_unstable());
+        Ok(ResultView { results })
+    }
+}
+
+/// parses an interval as it is displayed, i.e., `[<start>, <end>)`
+fn parse_interval(interval: &str) -> Option<HalfOpenTimeInterval> {
+    let (start, end) = interval.strip_prefix('[')?.strip_suffix(')')?.split_once(", ")?;
+    Some(HalfOpenTimeInterval::new(start.parse().ok()?, end.parse().ok()?))
+}
+
+/// Results of an RPQ over a replica, indexed by their source
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ResultView {
+    results: BTreeMap<VertexType, Vec<VertexType>>,
+}
+
+impl ResultView {
+    /// sorted targets that the given source reaches
+    pub fn targets(&self, source: VertexType) -> &[VertexType] {
+        self.results.get(&source).map_or(&[][..], |targets| targets.as_slice())
+    }
+
+    pub fn contains(&self, source: VertexType, target: VertexType) -> bool {
+        self.targets(source).binary_search(&target).is_ok()
+    }
+
+    /// all results as sorted (source, target) pairs
+    pub fn pairs(&self) -> impl Iterator<Item=(VertexType, VertexType)> + '_ {
+        self.results.iter().flat_map(|(source, targets)| targets.iter().map(move |target| (*source, *target)))
+    }
+
+    /// # of results
+    pub fn len(&self) -> usize {
+        self.results.values().map(|targets| targets.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+}
@@ -0,0 +1,121 @@
+//! Sink that counts results of each window per query and records them into the metrics pipeline
+//!
+//! For each window, i.e., each `slide_size` interval of result times, the # of results of each query (by output label)
+//! is recorded as `result-count-<label>`, and optionally appended to a summary file as `window_start,label,results`.
+//! Only positive tuples are counted, and queries that produced results in earlier windows are reported with 0 results
+//! in windows without any of their results.
+//! With anomaly detection, a dump of operator state is requested once counts deviate from the previous window, see `util::anomaly`.
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use log::{info, warn};
+use metrics_runtime::Sink;
+
+use crate::input::tuple::StreamingGraphTuple;
+use crate::sink::ResultSink;
+use crate::util::anomaly::{self, AnomalyDetector};
+use crate::util::metrics::summary::RunSummary;
+
+/// environment variable that sets the directory of per-worker summary files of result counts in runners
+pub const RESULT_COUNT_DIRECTORY_VARIABLE: &str = "SGRAFFITO_RESULT_COUNT_DIRECTORY";
+
+/// label of the output label of the query a result count belongs to
+pub const OUTPUT_LABEL: &str = "output";
+
+pub struct CardinalitySink {
+    slide_size: u64,
+    sink: Sink,
+    summary: Option<BufWriter<File>>,
+    run_summary: Option<RunSummary>,
+    anomaly_detector: Option<AnomalyDetector>,
+    // window that is being counted and the # of results of each query in the window
+    window: Option<u64>,
+    counts: BTreeMap<String, u64>,
+}
+
+impl CardinalitySink {
+    pub fn new(slide_size: u64, sink: Sink) -> Self {
+        assert!(slide_size > 0, "Slide size of the cardinality sink must be positive");
+        Self { slide_size, sink, summary: None, run_summary: None, anomaly_detector: None, window: None, counts: BTreeMap::new() }
+    }
+
+    /// additionally writes counts of each window into the given summary file
+    pub fn with_summary(mut self, path: &str) -> Result<Self, String> {
+        let mut summary = BufWriter::new(File::create(path).map_err(|e| format!("Cannot create result count summary {}: {}", path, e))?);
+        writeln!(summary, "window_start,label,results").map_err(|e| format!("Cannot write result count summary {}: {}", path, e))?;
+        self.summary = Some(summary);
+        Ok(self)
+    }
+
+    /// additionally adds counts of each window to the total # of results of the run
+    pub fn with_run_summary(mut self, run_summary: RunSummary) -> Self {
+        self.run_summary = Some(run_summary);
+        self
+    }
+
+    /// additionally requests a dump of operator state once the counts of a window deviate from the previous window by more than
+    /// the given relative change, where dumps have to be enabled with `anomaly::enable`
+    pub fn with_anomaly_detection(mut self, threshold: f64) -> Self {
+        self.anomaly_detector = Some(AnomalyDetector::new(threshold));
+        self
+    }
+
+    /// records counts of the current window and starts the next one
+    fn record_window(&mut self) {
+        let window = match self.window.take() {
+            Some(window) => window,
+            None => return,
+        };
+
+        let start = window * self.slide_size;
+        let counts = &self.counts;
+        if let Some(reason) = self.anomaly_detector.as_mut().and_then(|detector| detector.observe(counts)) {
+            match anomaly::request_dump(start, &reason) {
+                Ok(directory) => info!("Result counts of window {} deviate ({}), state is dumped into {}", start, reason, directory.display()),
+                Err(e) => warn!("Cannot dump state of window {}: {}", start, e),
+            }
+        }
+        for (label, count) in self.counts.iter_mut() {
+            self.sink.record_value_with_labels("result-count", *count, &[(OUTPUT_LABEL, label.clone())][..]);
+            if let Some(summary) = self.summary.as_mut() {
+                if let Err(e) = writeln!(summary, "{},{},{}", start, label, count) {
+                    warn!("Cannot write result count summary: {}", e);
+                }
+            }
+            if let Some(run_summary) = &self.run_summary {
+                run_summary.add_results(*count);
+            }
+            *count = 0;
+        }
+    }
+}
+
+impl ResultSink for CardinalitySink {
+    fn write(&mut self, time: u64, tuples: &[StreamingGraphTuple]) {
+        let window = time / self.slide_size;
+        if self.window != Some(window) {
+            self.record_window();
+            self.window = Some(window);
+        }
+
+        for sgt in tuples.iter().filter(|sgt| sgt.append) {
+            match self.counts.get_mut(&sgt.label) {
+                Some(count) => *count += 1,
+                None => { self.counts.insert(sgt.label.clone(), 1); }
+            }
+        }
+    }
+}
+
+impl Drop for CardinalitySink {
+    /// the last window is recorded once the stream ends
+    fn drop(&mut self) {
+        self.record_window();
+        if let Some(summary) = self.summary.as_mut() {
+            if let Err(e) = summary.flush() {
+                warn!("Cannot flush result count summary: {}", e);
+            }
+        }
+    }
+}
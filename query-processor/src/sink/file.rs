@@ -0,0 +1,117 @@
+//! File sink with exactly-once semantics through epoch-based commits
+//!
+//! Results of each time are buffered until the input frontier passes the time, and are then committed atomically
+//! into their own file `<directory>/<worker>/epoch-<time>.csv`, which is first written to a temporary file and then renamed.
+//! A restarted run that replays its sources from a checkpoint skips the times that are already committed,
+//! so that results are neither duplicated nor lost.
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use log::info;
+use timely::dataflow::{Scope, Stream};
+use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::operators::generic::operator::Operator;
+
+use crate::input::tuple::StreamingGraphTuple;
+
+/// environment variable that sets the directory results are committed to in runners
+pub const RESULT_DIRECTORY_VARIABLE: &str = "SGRAFFITO_RESULT_DIRECTORY";
+
+const EPOCH_FILE_PREFIX: &str = "epoch-";
+const EPOCH_FILE_EXTENSION: &str = "csv";
+
+/// Directory of committed epochs of a single worker
+pub struct EpochFileSink {
+    directory: PathBuf,
+    last_committed: Option<u64>,
+}
+
+impl EpochFileSink {
+    /// opens the epoch directory of the given worker, and recovers the latest committed epoch from a previous run
+    pub fn open<P: AsRef<Path>>(directory: P, worker_index: usize) -> Result<Self, String> {
+        let directory = directory.as_ref().join(worker_index.to_string());
+        fs::create_dir_all(&directory).map_err(|e| format!("Cannot create sink directory {:?}: {}", directory, e))?;
+
+        let entries = fs::read_dir(&directory).map_err(|e| format!("Cannot read sink directory {:?}: {}", directory, e))?;
+        let last_committed = entries.filter_map(Result::ok)
+            .filter_map(|entry| parse_epoch(&entry.file_name().to_string_lossy()))
+            .max();
+        if let Some(epoch) = last_committed {
+            info!("Results up to epoch {} are already committed to {:?}", epoch, directory);
+        }
+
+        Ok(Self { directory, last_committed })
+    }
+
+    /// the latest committed epoch, results of earlier epochs are not committed again
+    pub fn last_committed(&self) -> Option<u64> {
+        self.last_committed
+    }
+
+    /// atomically commits results of the given epoch, unless the epoch is already committed
+    /// returns false if the epoch is skipped
+    pub fn commit(&mut self, epoch: u64, tuples: &[StreamingGraphTuple]) -> Result<bool, String> {
+        if self.last_committed.map_or(false, |committed| epoch <= committed) {
+            return Ok(false);
+        }
+
+        let epoch_file = self.directory.join(format!("{}{:020}.{}", EPOCH_FILE_PREFIX, epoch, EPOCH_FILE_EXTENSION));
+        let temporary_file = epoch_file.with_extension("tmp");
+        {
+            let file = File::create(&temporary_file).map_err(|e| format!("Cannot create {:?}: {}", temporary_file, e))?;
+            let mut writer = BufWriter::new(file);
+            for sgt in tuples {
+                // edge ids of the result in provenance mode are the last column as `;` separated ids, which is empty otherwise
+                let provenance: Vec<String> = sgt.provenance.ids().iter().map(|edge_id| edge_id.to_string()).collect();
+                writeln!(writer, "{},{},{},{},{},{}", sgt.label, sgt.source, sgt.target, sgt.interval.start, sgt.interval.end, provenance.join(";"))
+                    .map_err(|e| format!("Cannot write {:?}: {}", temporary_file, e))?;
+            }
+            let file = writer.into_inner().map_err(|e| format!("Cannot write {:?}: {}", temporary_file, e))?;
+            file.sync_all().map_err(|e| format!("Cannot sync {:?}: {}", temporary_file, e))?;
+        }
+        fs::rename(&temporary_file, &epoch_file).map_err(|e| format!("Cannot commit {:?}: {}", epoch_file, e))?;
+
+        self.last_committed = Some(epoch);
+        Ok(true)
+    }
+}
+
+/// returns the epoch of a committed epoch file name
+fn parse_epoch(file_name: &str) -> Option<u64> {
+    file_name.strip_prefix(EPOCH_FILE_PREFIX)?
+        .strip_suffix(&format!(".{}", EPOCH_FILE_EXTENSION))?
+        .parse().ok()
+}
+
+/// Commits results of each time into an `EpochFileSink` once the output frontier passes the time
+pub trait CommitTo<G: Scope<Timestamp=u64>> {
+    fn commit_to(&self, sink: EpochFileSink);
+}
+
+impl<G: Scope<Timestamp=u64>> CommitTo<G> for Stream<G, StreamingGraphTuple> {
+    fn commit_to(&self, mut sink: EpochFileSink) {
+        let mut vector = Vec::new();
+
+        // results buffered until their time is completed
+        let mut stash: BTreeMap<u64, Vec<StreamingGraphTuple>> = BTreeMap::new();
+
+        self.sink(Pipeline, "EpochCommit", move |input| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                stash.entry(*time.time()).or_insert_with(Vec::new).extend(vector.drain(..));
+            });
+
+            // completed times are committed in order
+            while let Some(epoch) = stash.keys().next().cloned() {
+                if input.frontier().less_equal(&epoch) {
+                    break;
+                }
+                let mut tuples = stash.remove(&epoch).unwrap();
+                tuples.sort();
+                sink.commit(epoch, &tuples).unwrap_or_else(|e| panic!("{}", e));
+            }
+        });
+    }
+}
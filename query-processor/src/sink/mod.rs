@@ -0,0 +1,60 @@
+//! Sinks that deliver result tuples of a query to external consumers
+extern crate timely;
+
+use timely::dataflow::{Scope, Stream};
+use timely::dataflow::channels::pact::Pipeline;
+use timely::dataflow::operators::generic::operator::Operator;
+
+use crate::input::tuple::{StreamingGraphTuple, TaggedTuple};
+
+#[cfg(feature = "metrics")]
+pub mod cardinality;
+pub mod file;
+pub mod sampling;
+#[cfg(feature = "websocket")]
+pub mod websocket;
+
+/// Destination of result tuples, each worker writes its own results into its own instance
+pub trait ResultSink: 'static {
+    /// consumes a batch of tuples produced at the given time
+    fn write(&mut self, time: u64, tuples: &[StreamingGraphTuple]);
+
+    /// consumes a batch of tagged tuples produced at the given time, tags are dropped unless the sink handles them
+    fn write_tagged(&mut self, time: u64, tuples: &[TaggedTuple]) {
+        let untagged: Vec<StreamingGraphTuple> = tuples.iter().map(|tagged| tagged.tuple.clone()).collect();
+        self.write(time, &untagged);
+    }
+}
+
+/// Attaches a sink to a stream of results
+pub trait SinkTo<G: Scope<Timestamp=u64>> {
+    fn sink_to<S: ResultSink>(&self, sink: S);
+}
+
+impl<G: Scope<Timestamp=u64>> SinkTo<G> for Stream<G, StreamingGraphTuple> {
+    fn sink_to<S: ResultSink>(&self, mut sink: S) {
+        let mut vector = Vec::new();
+
+        self.sink(Pipeline, "ResultSink", move |input| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                sink.write(*time.time(), &vector);
+                vector.clear();
+            });
+        });
+    }
+}
+
+impl<G: Scope<Timestamp=u64>> SinkTo<G> for Stream<G, TaggedTuple> {
+    fn sink_to<S: ResultSink>(&self, mut sink: S) {
+        let mut vector = Vec::new();
+
+        self.sink(Pipeline, "TaggedResultSink", move |input| {
+            input.for_each(|time, data| {
+                data.swap(&mut vector);
+                sink.write_tagged(*time.time(), &vector);
+                vector.clear();
+            });
+        });
+    }
+}
@@ -0,0 +1,110 @@
+//! Sink that reservoir-samples results of each window to sanity-check large result sets without writing them out
+//!
+//! For each window, i.e., each `slide_size` interval of result times, it prints the total # of results
+//! and a uniform sample of at most `sample_size` results with resolved vertex identifiers and their validity intervals.
+use std::io::{self, Write};
+
+use crate::input::VertexDictionary;
+use crate::input::tuple::StreamingGraphTuple;
+use crate::sink::ResultSink;
+
+/// environment variable that sets the # of sampled results per window in runners
+pub const SAMPLE_SIZE_VARIABLE: &str = "SGRAFFITO_SAMPLE";
+
+pub struct SamplingSink {
+    sample_size: usize,
+    slide_size: u64,
+    dictionary: VertexDictionary,
+    output: Box<dyn Write>,
+    // window that is being sampled, its sample and the # of results seen in the window
+    window: Option<u64>,
+    sample: Vec<StreamingGraphTuple>,
+    count: u64,
+    // state of the xorshift generator, seeded for reproducible samples
+    random_state: u64,
+}
+
+impl SamplingSink {
+    /// creates a sink that prints samples to the standard output
+    pub fn new(sample_size: usize, slide_size: u64, dictionary: VertexDictionary) -> Self {
+        Self::with_output(sample_size, slide_size, dictionary, Box::new(io::stdout()))
+    }
+
+    pub fn with_output(sample_size: usize, slide_size: u64, dictionary: VertexDictionary, output: Box<dyn Write>) -> Self {
+        assert!(slide_size > 0, "Slide size of the sampling sink must be positive");
+        Self {
+            sample_size,
+            slide_size,
+            dictionary,
+            output,
+            window: None,
+            sample: Vec::with_capacity(sample_size),
+            count: 0,
+            random_state: 0x9E37_79B9_7F4A_7C15,
+        }
+    }
+
+    /// returns a uniformly distributed integer in `[0, bound)`
+    fn next_random(&mut self, bound: u64) -> u64 {
+        self.random_state ^= self.random_state << 13;
+        self.random_state ^= self.random_state >> 7;
+        self.random_state ^= self.random_state << 17;
+        self.random_state % bound
+    }
+
+    /// adds the tuple to the reservoir of the current window
+    fn offer(&mut self, sgt: &StreamingGraphTuple) {
+        self.count += 1;
+        if self.sample.len() < self.sample_size {
+            self.sample.push(sgt.clone());
+        } else {
+            let position = self.next_random(self.count) as usize;
+            if position < self.sample_size {
+                self.sample[position] = sgt.clone();
+            }
+        }
+    }
+
+    /// prints the sample of the current window and starts the next one
+    fn print_window(&mut self) {
+        let window = match self.window.take() {
+            Some(window) => window,
+            None => return,
+        };
+
+        let start = window * self.slide_size;
+        let mut sample = std::mem::take(&mut self.sample);
+        sample.sort_by_key(|sgt| (sgt.interval.start, sgt.source, sgt.target));
+
+        let mut lines = vec![format!("window [{}, {}): {} results, showing {}", start, start + self.slide_size, self.count, sample.len())];
+        for sgt in sample {
+            lines.push(format!("  {}  {} -> {}  {}", sgt.label, self.dictionary.resolve(sgt.source), self.dictionary.resolve(sgt.target), sgt.interval));
+        }
+        if let Err(e) = writeln!(self.output, "{}", lines.join("\n")) {
+            log::warn!("Cannot print result sample: {}", e);
+        }
+
+        self.count = 0;
+    }
+}
+
+impl ResultSink for SamplingSink {
+    fn write(&mut self, time: u64, tuples: &[StreamingGraphTuple]) {
+        let window = time / self.slide_size;
+        if self.window != Some(window) {
+            self.print_window();
+            self.window = Some(window);
+        }
+
+        for sgt in tuples {
+            self.offer(sgt);
+        }
+    }
+}
+
+impl Drop for SamplingSink {
+    /// the last window is printed once the stream ends
+    fn drop(&mut self) {
+        self.print_window();
+    }
+}
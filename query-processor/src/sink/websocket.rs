@@ -0,0 +1,142 @@
+//! WebSocket server sink that streams result tuples to subscribed clients as JSON
+//!
+//! Clients subscribe to results of particular queries by their output labels, e.g., `ws://host:port/?labels=q1,q2`,
+//! or to all results if no label is given. Each tuple is sent as a single text message of the form
+//! `{"label": "q1", "source": "alice", "target": "bob", "start": 10, "end": 20, "time": 10}`,
+//! where vertex identifiers are resolved through the given `VertexDictionary`.
+//! Tagged results, see `UnionWithTag`, additionally carry their `tag`, which subscriptions filter on instead of the label.
+use std::collections::HashSet;
+use std::net::{TcpListener, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{channel, Sender};
+use std::thread;
+
+use log::{info, warn};
+use tungstenite::{accept_hdr, Message};
+use tungstenite::handshake::server::{Request, Response};
+
+use crate::input::VertexDictionary;
+use crate::input::tuple::{StreamingGraphTuple, TaggedTuple};
+use crate::sink::ResultSink;
+
+/// environment variable that sets the address of the WebSocket sink in runners, e.g., `127.0.0.1:9001`
+pub const WEBSOCKET_ADDRESS_VARIABLE: &str = "SGRAFFITO_WEBSOCKET_ADDRESS";
+
+struct Subscriber {
+    // output labels the subscriber is interested in, all results if `None`
+    labels: Option<HashSet<String>>,
+    sender: Sender<String>,
+}
+
+impl Subscriber {
+    fn is_subscribed(&self, label: &str) -> bool {
+        self.labels.as_ref().map_or(true, |labels| labels.contains(label))
+    }
+}
+
+/// Sink that broadcasts results to WebSocket clients, cloned sinks share the same server and subscribers
+#[derive(Clone)]
+pub struct WebSocketSink {
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+    dictionary: VertexDictionary,
+}
+
+impl WebSocketSink {
+    /// starts a WebSocket server at the given address that accepts subscriptions on a background thread
+    pub fn bind<A: ToSocketAddrs>(address: A, dictionary: VertexDictionary) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let subscribers: Arc<Mutex<Vec<Subscriber>>> = Arc::new(Mutex::new(Vec::new()));
+
+        let server_subscribers = subscribers.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().filter_map(Result::ok) {
+                let subscribers = server_subscribers.clone();
+                thread::spawn(move || {
+                    let mut labels = None;
+                    let mut websocket = match accept_hdr(stream, |request: &Request, response: Response| {
+                        labels = parse_labels(request.uri().query());
+                        Ok(response)
+                    }) {
+                        Ok(websocket) => websocket,
+                        Err(e) => {
+                            warn!("WebSocket handshake has failed: {}", e);
+                            return;
+                        }
+                    };
+                    info!("New subscriber for labels {:?}", labels);
+
+                    let (sender, receiver) = channel();
+                    subscribers.lock().unwrap().push(Subscriber { labels, sender });
+
+                    // forward results until the client disconnects, its sender is then dropped on the next write
+                    for message in receiver {
+                        if websocket.write_message(Message::Text(message)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok(Self { subscribers, dictionary })
+    }
+
+    /// returns the dictionary used to resolve vertex identifiers, which readers should record vertices into
+    pub fn dictionary(&self) -> VertexDictionary {
+        self.dictionary.clone()
+    }
+}
+
+impl ResultSink for WebSocketSink {
+    fn write(&mut self, time: u64, tuples: &[StreamingGraphTuple]) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        if subscribers.is_empty() {
+            return;
+        }
+
+        for sgt in tuples {
+            let message = serde_json::json!({
+                "label": sgt.label,
+                "source": self.dictionary.resolve(sgt.source),
+                "target": self.dictionary.resolve(sgt.target),
+                "start": sgt.interval.start,
+                "end": sgt.interval.end,
+                "time": time,
+                "provenance": sgt.provenance.ids(),
+            }).to_string();
+
+            // subscribers whose connection is closed are removed
+            subscribers.retain(|subscriber| !subscriber.is_subscribed(&sgt.label) || subscriber.sender.send(message.clone()).is_ok());
+        }
+    }
+
+    fn write_tagged(&mut self, time: u64, tuples: &[TaggedTuple]) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        if subscribers.is_empty() {
+            return;
+        }
+
+        for TaggedTuple { tag, tuple: sgt } in tuples {
+            let message = serde_json::json!({
+                "tag": tag,
+                "label": sgt.label,
+                "source": self.dictionary.resolve(sgt.source),
+                "target": self.dictionary.resolve(sgt.target),
+                "start": sgt.interval.start,
+                "end": sgt.interval.end,
+                "time": time,
+                "provenance": sgt.provenance.ids(),
+            }).to_string();
+
+            subscribers.retain(|subscriber| !subscriber.is_subscribed(tag) || subscriber.sender.send(message.clone()).is_ok());
+        }
+    }
+}
+
+/// parses the `labels` parameter of a subscription query string
+fn parse_labels(query: Option<&str>) -> Option<HashSet<String>> {
+    query?.split('&')
+        .filter_map(|parameter| parameter.strip_prefix("labels="))
+        .map(|labels| labels.split(',').filter(|label| !label.is_empty()).map(|label| label.to_string()).collect())
+        .next()
+}
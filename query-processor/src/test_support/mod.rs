@@ -0,0 +1,101 @@
+//! Helpers to test operators and RPQs of the crate over small inputs in a single-worker dataflow
+//!
+//! The module is compiled only for tests of the crate and with the `test-support` feature, which integration tests,
+//! benchmarks and downstream test suites enable, so that test helpers are not part of the library otherwise.
+//!
+//! `run_operator` sends each input at its own epoch into a stream, applies an operator to the stream
+//! and collects the output of each epoch, e.g., to reproduce a bug report with a handful of tuples:
+//!
+//! ```ignore
+//! let outputs = run_operator(vec![(1, sgt(1, 2, "a", 1, 10))], |stream| stream.result_ttl(5));
+//! assert_eq!(outputs[&1], vec![sgt(1, 2, "a", 1, 6)]);
+//! ```
+//!
+//! `run_rpq` runs an RPQ end-to-end over windows of edges as the harness runs queries of the query library,
+//! and `model` generates random streams and RPQs with their expected results.
+extern crate timely;
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use timely::Data;
+use timely::communication::allocator::Thread;
+use timely::dataflow::{InputHandle, Stream};
+use timely::dataflow::operators::{Input, Inspect, Probe};
+use timely::dataflow::scopes::Child;
+use timely::worker::Worker;
+
+use crate::harness::{run_sga_dataflow, RunControl, window_times, WindowResults};
+use crate::input::{SGT, StreamingGraphEdge};
+use crate::input::tuple::StreamingGraphTuple;
+use crate::operator::rpq::RegularPathQuery;
+use crate::util::types::{HalfOpenTimeInterval, VertexType};
+
+pub mod model;
+
+/// Scope of the dataflow that operators under test are constructed in
+pub type TestScope<'a> = Child<'a, Worker<Thread>, u64>;
+
+/// Outputs of an operator in arrival order, keyed by the epoch they are produced at
+pub type EpochOutputs<D> = BTreeMap<u64, Vec<D>>;
+
+/// runs the operator over the given (epoch, input) pairs in a single worker, and returns its outputs per epoch
+/// Inputs are sent in the order of their epochs, where inputs of the same epoch keep their order,
+/// and the dataflow catches up to each epoch before inputs of a later epoch are sent
+pub fn run_operator<I, O, F>(mut inputs: Vec<(u64, I)>, operator: F) -> EpochOutputs<O>
+    where
+        I: Data + Send,
+        O: Data + Send,
+        F: for<'a> FnOnce(&Stream<TestScope<'a>, I>) -> Stream<TestScope<'a>, O> + Send + Sync + 'static {
+    inputs.sort_by_key(|(epoch, _)| *epoch);
+
+    timely::execute_directly(move |worker| {
+        let outputs = Rc::new(RefCell::new(BTreeMap::new()));
+        let operator_outputs = outputs.clone();
+        let mut input = InputHandle::new();
+
+        let probe = worker.dataflow::<u64, _, _>(|scope| {
+            operator(&scope.input_from(&mut input))
+                .inspect_batch(move |epoch, data| operator_outputs.borrow_mut().entry(*epoch).or_insert_with(Vec::new).extend_from_slice(data))
+                .probe()
+        });
+
+        for (epoch, data) in inputs {
+            if epoch > *input.time() {
+                input.advance_to(epoch);
+                worker.step_while(|| probe.less_than(input.time()));
+            }
+            input.send(data);
+        }
+        input.close();
+        worker.step_while(|| !probe.done());
+
+        outputs.replace(BTreeMap::new())
+    })
+}
+
+/// creates an sgt that is an append of the result from `source` to `target` with the given label and validity interval
+pub fn sgt(source: VertexType, target: VertexType, label: &str, start: u64, end: u64) -> StreamingGraphTuple {
+    StreamingGraphTuple::new(source, target, label.to_string(), HalfOpenTimeInterval::new(start, end))
+}
+
+/// runs the given RPQ over all edges with the RPQ operator and returns the results valid at each window
+pub fn run_rpq(edges: Vec<StreamingGraphEdge>, window_size: u64, slide_size: u64, query_str: &str) -> WindowResults {
+    let query_str = query_str.to_string();
+    let windows = window_times(&edges, slide_size);
+
+    run_sga_dataflow(edges, windows, window_size, RunControl::new(), move |windowed_stream| {
+        windowed_stream.regular_path_query(&query_str, "rpq".to_string())
+    })
+}
+
+/// runs the given RPQ as `run_rpq` over a lazily determinized automaton, see `lazy_regular_path_query`
+pub fn run_lazy_rpq(edges: Vec<StreamingGraphEdge>, window_size: u64, slide_size: u64, query_str: &str) -> WindowResults {
+    let query_str = query_str.to_string();
+    let windows = window_times(&edges, slide_size);
+
+    run_sga_dataflow(edges, windows, window_size, RunControl::new(), move |windowed_stream| {
+        windowed_stream.lazy_regular_path_query(&query_str, "rpq".to_string())
+    })
+}
@@ -0,0 +1,100 @@
+//! Model-based testing of the RPQ operator against a naive snapshot evaluation
+//!
+//! `ModelGenerator` generates random small streams and RPQs from a seed, so that a failing case can be reproduced from its seed.
+//! `expected_rpq_results` is the ground truth of each window: it evaluates the RPQ from scratch over the snapshot graph of the window,
+//! i.e., edges with timestamps in `(T - window_size, T]`, with a BFS over the product of the snapshot graph and the automaton.
+//! Results are compared against `test_support::run_rpq`, which evaluates the same windows incrementally.
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+
+use crate::harness::{window_times, WindowResults};
+use crate::input::{GraphEdge, SGE, StreamingGraphEdge};
+use crate::query::automata::dfa::DFA;
+use crate::query::parser::RPQParser;
+use crate::util::types::{VertexStatePair, VertexType};
+
+/// Generator of random streams and RPQs, backed by a xorshift generator so that it does not depend on an external crate
+pub struct ModelGenerator {
+    state: u64,
+}
+
+impl ModelGenerator {
+    pub fn new(seed: u64) -> Self {
+        // the state of xorshift must not be zero
+        Self { state: seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1 }
+    }
+
+    /// returns a random number in `[0, bound)`
+    pub fn next_below(&mut self, bound: u64) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state % bound
+    }
+
+    /// returns `count` edges over vertices `[0, vertices)` with the given labels, ordered by their timestamps
+    /// timestamps start at 1 and advance by at most `max_gap` between consecutive edges
+    pub fn edges(&mut self, count: usize, vertices: u64, labels: &[&str], max_gap: u64) -> Vec<StreamingGraphEdge> {
+        let mut timestamp = 1;
+        (0..count).map(|_| {
+            timestamp += self.next_below(max_gap + 1);
+            let label = labels[self.next_below(labels.len() as u64) as usize];
+            StreamingGraphEdge::new(self.next_below(vertices), self.next_below(vertices), label.to_string(), timestamp)
+        }).collect()
+    }
+
+    /// returns a random RPQ over the given labels with at most `depth` nested operators
+    pub fn rpq(&mut self, labels: &[&str], depth: usize) -> String {
+        if depth == 0 {
+            return labels[self.next_below(labels.len() as u64) as usize].to_string();
+        }
+        match self.next_below(6) {
+            0 => format!("{}/{}", self.rpq(labels, depth - 1), self.rpq(labels, depth - 1)),
+            1 => format!("({}|{})", self.rpq(labels, depth - 1), self.rpq(labels, depth - 1)),
+            2 => format!("({})*", self.rpq(labels, depth - 1)),
+            3 => format!("({})+", self.rpq(labels, depth - 1)),
+            _ => self.rpq(labels, depth - 1),
+        }
+    }
+}
+
+/// evaluates the RPQ over the snapshot graph of the given edges, and returns (source, target) pairs of paths of at least one edge
+pub fn snapshot_rpq<'a, I: IntoIterator<Item=&'a StreamingGraphEdge>>(edges: I, automaton: &DFA) -> BTreeSet<(VertexType, VertexType)> {
+    let mut adjacency: HashMap<VertexType, Vec<(&str, VertexType)>> = HashMap::new();
+    for sge in edges {
+        adjacency.entry(sge.get_source()).or_insert_with(Vec::new).push((sge.get_label(), sge.get_target()));
+    }
+
+    let mut results = BTreeSet::new();
+    for source in adjacency.keys() {
+        // the start node is visited only if a path leads back to it
+        let mut visited: HashSet<VertexStatePair> = HashSet::new();
+        let mut queue = VecDeque::new();
+        queue.push_back((*source, 0));
+
+        while let Some((vertex, state)) = queue.pop_front() {
+            for (label, target) in adjacency.get(&vertex).into_iter().flatten() {
+                if let Some(target_state) = automaton.state_move(state, label) {
+                    if visited.insert((*target, target_state)) {
+                        if automaton.is_final_state(target_state) {
+                            results.insert((*source, *target));
+                        }
+                        queue.push_back((*target, target_state));
+                    }
+                }
+            }
+        }
+    }
+
+    results
+}
+
+/// returns the ground truth of the RPQ for each window of the given edges, windows are evaluated as in `harness`
+/// panics if the RPQ is not valid
+pub fn expected_rpq_results(edges: &[StreamingGraphEdge], window_size: u64, slide_size: u64, query_str: &str) -> WindowResults {
+    let automaton = RPQParser::new().parse_rpq(query_str).unwrap_or_else(|e| panic!("RPQ {} is not valid: {}", query_str, e));
+
+    window_times(edges, slide_size).into_iter().map(|window_time| {
+        let snapshot = edges.iter().filter(|sge| sge.timestamp <= window_time && sge.timestamp + window_size > window_time);
+        (window_time, snapshot_rpq(snapshot, &automaton))
+    }).collect()
+}
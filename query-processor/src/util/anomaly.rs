@@ -0,0 +1,139 @@
+//! Process-wide debugging dumps of operator state once result counts of consecutive windows deviate beyond a threshold
+//!
+//! The cardinality sink compares the # of results of each query with the previous window through an `AnomalyDetector`,
+//! and requests a dump on a deviation. A dump is a directory `<directory>/window-<start>` with the reason of the request and
+//! the gauges at the time of the request, i.e., state sizes of operators, to which each RPQ operator adds the labels of its query, a snapshot of its graph,
+//! a summary of its spanning trees and the sizes of its stash the next time it is scheduled.
+//! As gauges, operators check for requests only if dumps are enabled, so that there is no overhead otherwise.
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use once_cell::sync::Lazy;
+
+use crate::util::gauges;
+
+/// environment variable that sets the directory of anomaly dumps in runners, which enables them
+pub const ANOMALY_DIRECTORY_VARIABLE: &str = "SGRAFFITO_ANOMALY_DIRECTORY";
+
+/// environment variable for the relative change of result counts between consecutive windows that is an anomaly, e.g., `0.5`
+pub const ANOMALY_THRESHOLD_VARIABLE: &str = "SGRAFFITO_ANOMALY_THRESHOLD";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static DIRECTORY: Lazy<Mutex<PathBuf>> = Lazy::new(|| Mutex::new(PathBuf::new()));
+
+// # of dumps requested so far and the directory of the latest one, operators dump their state once for each request
+static REQUESTS: AtomicU64 = AtomicU64::new(0);
+static LATEST_DUMP: Lazy<Mutex<PathBuf>> = Lazy::new(|| Mutex::new(PathBuf::new()));
+
+/// enables dumps into the given directory, it has to be enabled before RPQ operators are constructed
+pub fn enable(directory: &str) {
+    *DIRECTORY.lock().unwrap() = PathBuf::from(directory);
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// returns true if dumps are enabled
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// creates the dump of the window with the given start, writes the reason and the current gauges into it,
+/// and requests operators to add their state, returns the directory of the dump
+/// Reasons of repeated requests for the same window, e.g., by several workers, are appended
+pub fn request_dump(window_start: u64, reason: &str) -> Result<PathBuf, String> {
+    let directory = DIRECTORY.lock().unwrap().join(format!("window-{}", window_start));
+    fs::create_dir_all(&directory).map_err(|e| format!("Cannot create anomaly dump {}: {}", directory.display(), e))?;
+
+    let reason_path = directory.join("reason.txt");
+    OpenOptions::new().create(true).append(true).open(&reason_path)
+        .and_then(|mut file| writeln!(file, "{}", reason))
+        .map_err(|e| format!("Cannot write anomaly dump {}: {}", reason_path.display(), e))?;
+
+    let gauges_path = directory.join("gauges.json");
+    let content = serde_json::to_string_pretty(&gauges::snapshot()).map_err(|e| format!("Cannot serialize gauges: {}", e))?;
+    fs::write(&gauges_path, content).map_err(|e| format!("Cannot write anomaly dump {}: {}", gauges_path.display(), e))?;
+
+    *LATEST_DUMP.lock().unwrap() = directory.clone();
+    REQUESTS.fetch_add(1, Ordering::SeqCst);
+    Ok(directory)
+}
+
+/// returns the directory of the latest dump if it is requested after the request the caller has dumped its state for,
+/// and marks it as dumped
+pub fn pending_dump(dumped_requests: &mut u64) -> Option<PathBuf> {
+    let requests = REQUESTS.load(Ordering::SeqCst);
+    if requests == *dumped_requests {
+        return None;
+    }
+    *dumped_requests = requests;
+    Some(LATEST_DUMP.lock().unwrap().clone())
+}
+
+/// Detector of deviations of result counts of each query between consecutive windows
+#[derive(Clone, Debug)]
+pub struct AnomalyDetector {
+    threshold: f64,
+    // counts of the previous window, None before the first window
+    previous: Option<BTreeMap<String, u64>>,
+}
+
+impl AnomalyDetector {
+    /// `threshold` is the relative change of the # of results of a query that is an anomaly, e.g., 0.5 for a change of 50%
+    pub fn new(threshold: f64) -> Self {
+        assert!(threshold > 0.0, "Anomaly threshold must be positive, found {}", threshold);
+        Self { threshold, previous: None }
+    }
+
+    /// compares the counts of a window with the counts of the previous window, and returns the deviations
+    /// as `<label>: <previous> -> <count>` if a query deviates beyond the threshold
+    /// Queries without results in the previous window, including queries that produce their first results, deviate by their # of results,
+    /// whereas the first window has nothing to be compared with
+    pub fn observe(&mut self, counts: &BTreeMap<String, u64>) -> Option<String> {
+        let previous_counts = self.previous.replace(counts.clone())?;
+        let deviations: Vec<String> = counts.iter()
+            .map(|(label, count)| (label, previous_counts.get(label).cloned().unwrap_or(0), *count))
+            .filter(|(_, previous, count)| (*count as f64 - *previous as f64).abs() / (*previous).max(1) as f64 > self.threshold)
+            .map(|(label, previous, count)| format!("{}: {} -> {}", label, previous, count))
+            .collect();
+
+        if deviations.is_empty() {
+            None
+        } else {
+            Some(deviations.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::util::anomaly::AnomalyDetector;
+
+    #[test]
+    fn deviating_counts() {
+        let counts = |q1: u64, q2: u64| vec![("q1".to_string(), q1), ("q2".to_string(), q2)].into_iter().collect::<BTreeMap<_, _>>();
+        let mut detector = AnomalyDetector::new(0.5);
+
+        assert_eq!(detector.observe(&counts(10, 0)), None);
+        assert_eq!(detector.observe(&counts(14, 0)), None);
+        assert_eq!(detector.observe(&counts(4, 0)), Some("q1: 14 -> 4".to_string()));
+        assert_eq!(detector.observe(&counts(5, 3)), Some("q2: 0 -> 3".to_string()));
+    }
+
+    #[test]
+    fn first_results_of_a_query_deviate() {
+        let mut detector = AnomalyDetector::new(0.5);
+        let mut counts: BTreeMap<String, u64> = vec![("q1".to_string(), 10)].into_iter().collect();
+        assert_eq!(detector.observe(&counts), None);
+
+        // q2 has no count in the previous window, i.e., it had no results
+        counts.insert("q2".to_string(), 4);
+        assert_eq!(detector.observe(&counts), Some("q2: 0 -> 4".to_string()));
+        assert_eq!(detector.observe(&counts), None);
+    }
+}
@@ -0,0 +1,183 @@
+//! Sources of processing time for latency measurements and metric reports
+//!
+//! Components that measure processing time, i.e., the `ReplayDriver`, runners, harness runs and the CSV metric reporter, read it from a `Clock`,
+//! which is the `SystemClock` by default, so that deterministic tests and simulations can control time with a `ManualClock`.
+//! Simulations replay sources on a `VirtualClock`, which follows the processing clock only while slides are processed.
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Source of processing time
+pub trait Clock: Debug + Send + Sync {
+    /// returns the time since an origin that is fixed for the clock, which never decreases
+    fn now(&self) -> Duration;
+
+    /// returns the wall-clock time since the UNIX epoch, e.g., for timestamps of metric reports
+    fn unix_time(&self) -> Duration;
+
+    /// returns `now` in nanoseconds, e.g., for timings of metric sinks
+    fn nanos(&self) -> u64 {
+        self.now().as_nanos() as u64
+    }
+}
+
+/// returns a new system clock, whose origin is the time of the call, for components that are not given another clock
+pub fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock::new())
+}
+
+/// Clock of the system, where `now` is the time since the clock is created
+#[derive(Clone, Debug)]
+pub struct SystemClock {
+    origin: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self { origin: Instant::now() }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self { Self::new() }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+
+    fn unix_time(&self) -> Duration {
+        SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default()
+    }
+}
+
+/// Clock that advances only when it is told to, shared among its clones
+/// Its origin is the UNIX epoch, i.e., `now` and `unix_time` are the same
+#[derive(Clone, Debug, Default)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// creates a clock at its origin
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// advances the clock by the given duration
+    pub fn advance(&self, duration: Duration) {
+        self.nanos.fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    /// advances the clock to the given time since its origin, unless it is already past it
+    pub fn advance_to(&self, time: Duration) {
+        self.nanos.fetch_max(time.as_nanos() as u64, Ordering::SeqCst);
+    }
+
+    /// sets the clock to the given time since its origin
+    /// panics if the time is before the current time of the clock
+    pub fn set(&self, time: Duration) {
+        let nanos = time.as_nanos() as u64;
+        let previous = self.nanos.fetch_max(nanos, Ordering::SeqCst);
+        assert!(previous <= nanos, "Manual clock cannot go back from {:?} to {:?}", Duration::from_nanos(previous), time);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+
+    fn unix_time(&self) -> Duration {
+        self.now()
+    }
+}
+
+/// Virtual clock of a simulation, see `ingest::Simulation`, shared among its clones
+/// It advances only when it is told to, except while processing is measured, where it advances with the processing clock,
+/// so that the processing time of slides adds to the virtual time of a replay
+/// Its origin is the UNIX epoch, i.e., `now` and `unix_time` are the same
+#[derive(Clone, Debug)]
+pub struct VirtualClock {
+    virtual_time: ManualClock,
+    processing_clock: Arc<dyn Clock>,
+    // time of the processing clock when the running measurement has started
+    processing_since: Arc<Mutex<Option<Duration>>>,
+}
+
+impl VirtualClock {
+    /// creates a clock at its origin that measures processing time with the given clock
+    pub fn new(processing_clock: Arc<dyn Clock>) -> Self {
+        Self { virtual_time: ManualClock::new(), processing_clock, processing_since: Arc::new(Mutex::new(None)) }
+    }
+
+    /// advances the clock by the given duration, e.g., a synthetic processing delay
+    pub fn advance(&self, duration: Duration) {
+        self.virtual_time.advance(duration);
+    }
+
+    /// advances the clock to the given time since its origin, unless it is already past it
+    pub fn advance_to(&self, time: Duration) {
+        self.virtual_time.advance_to(time);
+    }
+
+    /// starts to advance with the processing clock, e.g., while a slide is processed
+    pub fn start_processing(&self) {
+        self.processing_since.lock().unwrap().get_or_insert_with(|| self.processing_clock.now());
+    }
+
+    /// stops to advance with the processing clock, where the processing time since `start_processing` is added to the clock
+    pub fn stop_processing(&self) {
+        if let Some(since) = self.processing_since.lock().unwrap().take() {
+            self.virtual_time.advance(self.processing_clock.now() - since);
+        }
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        let processing = self.processing_since.lock().unwrap().map_or(Duration::default(), |since| self.processing_clock.now() - since);
+        self.virtual_time.now() + processing
+    }
+
+    fn unix_time(&self) -> Duration {
+        self.now()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::util::clock::{Clock, ManualClock, VirtualClock};
+
+    #[test]
+    fn manual_clock_is_shared_among_clones() {
+        let clock = ManualClock::new();
+        let shared = clock.clone();
+        clock.advance(Duration::from_millis(5));
+        shared.set(Duration::from_millis(8));
+        assert_eq!(clock.now(), Duration::from_millis(8));
+        assert_eq!(clock.nanos(), 8_000_000);
+    }
+
+    #[test]
+    fn virtual_clock_follows_processing_time() {
+        let processing_clock = ManualClock::new();
+        let clock = VirtualClock::new(Arc::new(processing_clock.clone()));
+        clock.advance_to(Duration::from_millis(10));
+        // processing time is ignored outside of measurements
+        processing_clock.advance(Duration::from_millis(100));
+        assert_eq!(clock.now(), Duration::from_millis(10));
+
+        clock.start_processing();
+        processing_clock.advance(Duration::from_millis(3));
+        assert_eq!(clock.now(), Duration::from_millis(13));
+        clock.stop_processing();
+        processing_clock.advance(Duration::from_millis(100));
+        assert_eq!(clock.now(), Duration::from_millis(13));
+    }
+}
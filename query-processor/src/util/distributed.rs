@@ -0,0 +1,86 @@
+//! Command-line options of runners for multi-worker and multi-process executions
+//!
+//! Options follow the predicates of a runner and are translated into Timely configuration arguments:
+//! - `-w, --workers <n>`: # of worker threads per process
+//! - `-n, --processes <n>`: # of processes
+//! - `-p, --process <i>`: index of this process
+//! - `-h, --hostfile <file>`: file with one `host:port` per process
+//! - `--partition <line|hash|range>`: partitioning of the input among workers, see `Partitioning`
+//! - `--vertices <start..end|file>`: restriction of the input to the subgraph induced by the given vertices, see `VertexFilter`
+use crate::input::Partitioning;
+
+#[derive(Clone, Debug)]
+pub struct DistributedOptions {
+    pub workers: usize,
+    pub processes: usize,
+    pub process_index: usize,
+    pub hostfile: Option<String>,
+    pub partitioning: Partitioning,
+    pub vertex_filter: Option<String>,
+}
+
+impl Default for DistributedOptions {
+    fn default() -> Self {
+        Self {
+            workers: 1,
+            processes: 1,
+            process_index: 0,
+            hostfile: None,
+            partitioning: Partitioning::RoundRobin,
+            vertex_filter: None,
+        }
+    }
+}
+
+impl DistributedOptions {
+    /// parses and validates options from the remaining command-line arguments
+    pub fn from_args<I: Iterator<Item=String>>(mut args: I) -> Result<Self, String> {
+        let mut options = Self::default();
+
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("Missing value for {}", flag));
+            match flag.as_str() {
+                "-w" | "--workers" => options.workers = parse_count(&flag, &value()?)?,
+                "-n" | "--processes" => options.processes = parse_count(&flag, &value()?)?,
+                "-p" | "--process" => options.process_index = value()?.parse().map_err(|_| format!("Invalid process index for {}", flag))?,
+                "-h" | "--hostfile" => options.hostfile = Some(value()?),
+                "--partition" => {
+                    let name = value()?;
+                    options.partitioning = Partitioning::from_name(&name).ok_or_else(|| format!("Partitioning {} is not valid, use `line`, `hash` or `range`", name))?
+                }
+                "--vertices" => options.vertex_filter = Some(value()?),
+                _ => return Err(format!("Unknown option {}", flag))
+            }
+        }
+
+        if options.process_index >= options.processes {
+            return Err(format!("Process index {} must be smaller than the number of processes {}", options.process_index, options.processes));
+        }
+        if options.processes > 1 && options.hostfile.is_none() {
+            return Err("A hostfile is required to run multiple processes".to_string());
+        }
+
+        Ok(options)
+    }
+
+    /// returns the arguments to configure Timely execution, i.e., for `timely::execute_from_args`
+    pub fn timely_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "-w".to_string(), self.workers.to_string(),
+            "-n".to_string(), self.processes.to_string(),
+            "-p".to_string(), self.process_index.to_string(),
+        ];
+        if let Some(hostfile) = &self.hostfile {
+            args.push("-h".to_string());
+            args.push(hostfile.clone());
+        }
+        args
+    }
+}
+
+fn parse_count(flag: &str, value: &str) -> Result<usize, String> {
+    match value.parse() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!("{} requires a positive integer, found {}", flag, value))
+    }
+}
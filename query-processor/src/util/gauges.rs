@@ -0,0 +1,85 @@
+//! Process-wide registry of state-size gauges, e.g., # of spanning trees of each RPQ operator, and of the lag of operator inputs
+//!
+//! Operators record gauges only if the registry is enabled, so that there is no overhead unless
+//! a consumer, i.e., the admin endpoint, anomaly dumps or peak state sizes of the run summary, reads them.
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use once_cell::sync::Lazy;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static GAUGES: Lazy<Mutex<BTreeMap<String, u64>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// enables recording of gauges, operators constructed before it is enabled still record
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// returns true if gauges are recorded
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// sets the current value of the gauge with the given name
+pub fn set(name: String, value: u64) {
+    GAUGES.lock().unwrap().insert(name, value);
+}
+
+/// returns the current value of all gauges
+pub fn snapshot() -> BTreeMap<String, u64> {
+    GAUGES.lock().unwrap().clone()
+}
+
+/// Gauges of an input of an operator that make stragglers and backpressure in multi-stage plans visible,
+/// i.e., `<name>.frontier-lag.<worker>`, the latest event time the input received minus the min event time of its frontier,
+/// and `<name>.pending.<worker>`, the # of tuples that are stashed until their time is complete
+#[derive(Clone, Debug)]
+pub struct InputLag {
+    lag_gauge: String,
+    pending_gauge: String,
+    latest_event_time: u64,
+}
+
+impl InputLag {
+    /// `name` identifies the input, e.g., the output label of an operator with a single input
+    pub fn new(name: &str, worker_index: usize) -> Self {
+        Self {
+            lag_gauge: format!("{}.frontier-lag.{}", name, worker_index),
+            pending_gauge: format!("{}.pending.{}", name, worker_index),
+            latest_event_time: 0,
+        }
+    }
+
+    /// observes the event time of received input
+    pub fn observe(&mut self, event_time: u64) {
+        self.latest_event_time = self.latest_event_time.max(event_time);
+    }
+
+    /// sets the gauges for the min event time of the input frontier, which is None once the input is closed and has no lag,
+    /// and the # of pending tuples
+    pub fn record(&self, frontier: Option<u64>, pending: usize) {
+        let lag = frontier.map_or(0, |frontier| self.latest_event_time.saturating_sub(frontier));
+        set(self.lag_gauge.clone(), lag);
+        set(self.pending_gauge.clone(), pending as u64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::gauges::{self, InputLag};
+
+    #[test]
+    fn input_lag() {
+        let mut lag = InputLag::new("test-input", 0);
+        lag.observe(30);
+        lag.observe(20);
+        lag.record(Some(12), 4);
+        assert_eq!(gauges::snapshot()["test-input.frontier-lag.0"], 18);
+        assert_eq!(gauges::snapshot()["test-input.pending.0"], 4);
+
+        lag.record(None, 0);
+        assert_eq!(gauges::snapshot()["test-input.frontier-lag.0"], 0);
+    }
+}
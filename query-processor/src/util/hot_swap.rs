@@ -0,0 +1,94 @@
+//! Process-wide hot-swaps of the automata of running RPQ operators, e.g., after a query is edited slightly
+//!
+//! A swap replaces the automaton of the RPQ operator with the given output label before it processes the first event time
+//! at or after the time of the swap. The operator keeps its graph, i.e., the window content, and rebuilds its spanning trees
+//! from the retained edges, where results of the window are emitted again.
+//! Once a swap is requested, operators retain edges of all labels, including labels that their automaton does not have,
+//! so that a swap requested before the dataflow is constructed loses no edges. Otherwise, edges of labels that only the
+//! new automaton has are missed if they arrived before the request, and windows are warming until the tuples that arrived
+//! until then expire, see `is_warming` and `Slide::is_warming`.
+//! Each worker runs its own instance of the operator, so a swap is applied by every worker that reaches its time,
+//! and the warming windows of a swap span those of all workers.
+//! As dumps, operators check for swaps only once a swap is requested, so that there is no overhead otherwise.
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use once_cell::sync::Lazy;
+
+use crate::query::automata::dfa::DFA;
+use crate::query::parser::RPQParser;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// swaps of each operator by its output label
+static SWAPS: Lazy<Mutex<HashMap<String, Swap>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Clone, Debug, Default)]
+struct Swap {
+    // automaton that is swapped in at the given event time
+    pending: Option<(u64, DFA)>,
+    // indices of the workers that have applied the pending swap
+    applied: HashSet<usize>,
+    // event times of the windows that are warming after the last swap, i.e., `[swap, warming until)`
+    warming: Option<(u64, u64)>,
+    // true if the warming windows belong to the pending swap, i.e., the ranges of workers are merged
+    warming_pending: bool,
+}
+
+/// requests the RPQ operator with the given output label to evaluate the given query from the given event time on,
+/// which replaces a pending swap of the operator
+pub fn request_swap(output_label: &str, event_time: u64, query_str: &str) -> Result<(), String> {
+    let automaton = RPQParser::new().parse_rpq(query_str).map_err(|e| format!("Cannot swap {} to RPQ {}: {}", output_label, query_str, e))?;
+    let mut swaps = SWAPS.lock().unwrap();
+    let swap = swaps.entry(output_label.to_string()).or_default();
+    swap.pending = Some((event_time, automaton));
+    swap.applied.clear();
+    swap.warming_pending = false;
+    ENABLED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// returns true if any swap is requested
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// returns the automaton of the swap of the operator with the given output label if it is due at the given event time
+/// and the given worker has not applied it yet, which marks it as applied by the worker
+pub fn due_swap(output_label: &str, worker_index: usize, event_time: u64) -> Option<DFA> {
+    let mut swaps = SWAPS.lock().unwrap();
+    let swap = swaps.get_mut(output_label)?;
+    match &swap.pending {
+        Some((swap_time, automaton)) if *swap_time <= event_time && !swap.applied.contains(&worker_index) => {
+            let automaton = automaton.clone();
+            swap.applied.insert(worker_index);
+            Some(automaton)
+        }
+        _ => None,
+    }
+}
+
+/// marks windows of the operator with the given output label as warming from the event time of a swap until the given event time,
+/// i.e., none if it is not after the swap
+/// Workers apply a swap at the first event time they process after it, so the ranges of workers of the same swap are merged
+pub fn set_warming(output_label: &str, swap_time: u64, warming_until: u64) {
+    let mut swaps = SWAPS.lock().unwrap();
+    let swap = swaps.entry(output_label.to_string()).or_default();
+    swap.warming = match swap.warming {
+        Some((start, until)) if swap.warming_pending => Some((start.min(swap_time), until.max(warming_until))),
+        _ => Some((swap_time, warming_until)),
+    };
+    swap.warming_pending = true;
+}
+
+/// returns true if the window at the given event time of the operator with the given output label is warming after a swap,
+/// i.e., it may miss results over edges that arrived before the swap
+pub fn is_warming(output_label: &str, event_time: u64) -> bool {
+    is_enabled() && SWAPS.lock().unwrap().get(output_label).and_then(|swap| swap.warming).map_or(false, |(swap_time, until)| swap_time <= event_time && event_time < until)
+}
+
+/// returns true if the window at the given event time of any operator is warming after a swap
+pub fn is_any_warming(event_time: u64) -> bool {
+    is_enabled() && SWAPS.lock().unwrap().values().filter_map(|swap| swap.warming).any(|(swap_time, until)| swap_time <= event_time && event_time < until)
+}
@@ -0,0 +1,39 @@
+//! Samples of the memory usage of the process, to validate state-size estimates of operators against actual process memory
+//!
+//! With the `jemalloc` feature, samples include the # of bytes allocated by the application besides the resident memory of jemalloc,
+//! which requires the binary to install jemalloc as its global allocator, as the runners do. The library does not install it,
+//! so that applications embedding it keep their own allocator. Otherwise, only the resident set size is sampled from `/proc/self/status`.
+
+/// environment variable that enables memory samples in runners
+pub const MEMORY_STATS_VARIABLE: &str = "SGRAFFITO_MEMORY_STATS";
+
+/// Memory usage of the process in bytes
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MemoryStats {
+    pub resident: u64,
+    /// bytes allocated by the application, available only with jemalloc
+    pub allocated: Option<u64>,
+}
+
+/// samples the current memory usage, or returns `None` if it is not available
+#[cfg(feature = "jemalloc")]
+pub fn sample() -> Option<MemoryStats> {
+    use jemalloc_ctl::{epoch, stats};
+
+    // statistics are cached by jemalloc until the epoch is advanced
+    epoch::advance().ok()?;
+    let resident = stats::resident::read().ok()?;
+    let allocated = stats::allocated::read().ok()?;
+    Some(MemoryStats { resident: resident as u64, allocated: Some(allocated as u64) })
+}
+
+/// samples the current memory usage, or returns `None` if it is not available
+#[cfg(not(feature = "jemalloc"))]
+pub fn sample() -> Option<MemoryStats> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let resident_kb: u64 = status.lines()
+        .find(|line| line.starts_with("VmRSS:"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|value| value.parse().ok())?;
+    Some(MemoryStats { resident: resident_kb * 1024, allocated: None })
+}
@@ -1,22 +1,78 @@
-use std::collections::BTreeMap;
-use std::time::SystemTime;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
 
 use hdrhistogram::Histogram;
 use metrics_core::{Builder, Drain, Key, Observer};
 use metrics_util::{parse_quantiles, Quantile};
 
+use crate::util::clock::{Clock, system_clock};
+use crate::util::metrics::{QUERY_LABEL, WORKER_LABEL};
+
 pub enum MetricValue {
     Unsigned(u64),
     Signed(i64),
     Hist(Histogram<u64>),
 }
 
+/// Environment variable for the histogram mode of runners, see `HistogramMode::from_name`
+pub const HISTOGRAM_MODE_VARIABLE: &str = "SGRAFFITO_HISTOGRAM_MODE";
+
+/// Interval of values that percentiles of histograms are reported over
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HistogramMode {
+    /// all values since the start, i.e., warm-up values are mixed with the steady state
+    Cumulative,
+    /// values of each reporting period, histograms are reset once they are reported
+    Interval,
+    /// values of the given # of most recent reporting periods
+    Rolling { periods: usize },
+}
+
+impl HistogramMode {
+    /// parses `cumulative`, `interval` or `rolling:<periods>`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cumulative" => Some(HistogramMode::Cumulative),
+            "interval" => Some(HistogramMode::Interval),
+            _ => match name.strip_prefix("rolling:").map(|periods| periods.parse::<usize>()) {
+                Some(Ok(periods)) if periods > 0 => Some(HistogramMode::Rolling { periods }),
+                _ => None,
+            }
+        }
+    }
+}
+
 /// Custom CSV-Based metric reporting
-pub struct CSVBuilder {}
+///
+/// Values of labels are appended to metric names, i.e., file names, except for column labels (`worker` and `query` by default),
+/// whose values are reported as columns so that series of different workers and queries share a file.
+pub struct CSVBuilder {
+    histogram_mode: HistogramMode,
+    column_labels: Vec<String>,
+    clock: Arc<dyn Clock>,
+}
 
 impl CSVBuilder {
+    /// builder of observers that report percentiles over all values since the start, as `scripts/result-aggregator.py` expects
     pub fn new() -> Self {
-        Self {}
+        Self { histogram_mode: HistogramMode::Cumulative, column_labels: vec![WORKER_LABEL.to_string(), QUERY_LABEL.to_string()], clock: system_clock() }
+    }
+
+    pub fn with_histogram_mode(mut self, histogram_mode: HistogramMode) -> Self {
+        self.histogram_mode = histogram_mode;
+        self
+    }
+
+    /// labels whose values are reported as columns instead of being appended to metric names
+    pub fn with_column_labels(mut self, column_labels: Vec<String>) -> Self {
+        self.column_labels = column_labels;
+        self
+    }
+
+    /// clock that the timestamp column of reports is read from
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
     }
 }
 
@@ -27,6 +83,10 @@ impl Builder for CSVBuilder {
         CSVObserver {
             quantiles: parse_quantiles(&[0.25, 0.5, 0.75, 0.9, 0.99, 0.999]),
             content: BTreeMap::new(),
+            histogram_mode: self.histogram_mode,
+            recent_periods: BTreeMap::new(),
+            column_labels: self.column_labels.clone(),
+            clock: self.clock.clone(),
         }
     }
 }
@@ -35,24 +95,78 @@ impl Default for CSVBuilder {
     fn default() -> Self { Self::new() }
 }
 
+/// Series of a metric, i.e., the name of its metric file and values of column labels
+type SeriesKey = (String, Vec<String>);
+
 pub struct CSVObserver {
     pub(crate) quantiles: Vec<Quantile>,
-    pub(crate) content: BTreeMap<String, MetricValue>,
+    pub(crate) content: BTreeMap<SeriesKey, MetricValue>,
+    histogram_mode: HistogramMode,
+    // histograms of the most recent reporting periods of each series in the rolling mode
+    recent_periods: BTreeMap<SeriesKey, VecDeque<Histogram<u64>>>,
+    column_labels: Vec<String>,
+    clock: Arc<dyn Clock>,
+}
+
+impl CSVObserver {
+    /// histogram to report for the given metric, values of the current reporting period are reset unless histograms are cumulative
+    fn reported_histogram(&mut self, key: &SeriesKey, histogram: &mut Histogram<u64>) -> Histogram<u64> {
+        let reported = match self.histogram_mode {
+            HistogramMode::Cumulative => return histogram.clone(),
+            HistogramMode::Interval => histogram.clone(),
+            HistogramMode::Rolling { periods } => {
+                let recent_periods = self.recent_periods.entry(key.clone()).or_insert_with(VecDeque::new);
+                recent_periods.push_back(histogram.clone());
+                if recent_periods.len() > periods {
+                    recent_periods.pop_front();
+                }
+
+                let mut merged = histogram.clone();
+                merged.reset();
+                for period in recent_periods.iter() {
+                    merged.add(period).expect("failed to merge histograms");
+                }
+                merged
+            }
+        };
+        histogram.reset();
+        reported
+    }
+
+    /// series of the given key, values of labels that are not column labels are appended to the metric name
+    /// so that metrics with different labels, i.e., recorded in different phases, are reported separately
+    fn series_key(&self, key: &Key) -> SeriesKey {
+        let mut name = key.name().to_string();
+        let mut column_values = vec![String::new(); self.column_labels.len()];
+        for label in key.labels() {
+            match self.column_labels.iter().position(|column_label| column_label == label.key()) {
+                Some(column) => column_values[column] = label.value().to_string(),
+                None => {
+                    name.push('-');
+                    name.push_str(label.value());
+                }
+            }
+        }
+        (name, column_values)
+    }
 }
 
 impl Observer for CSVObserver {
     fn observe_counter(&mut self, key: Key, value: u64) {
-        *self.content.entry(key.name().to_string()).or_insert(MetricValue::Unsigned(value)) = MetricValue::Unsigned(value);
+        let series_key = self.series_key(&key);
+        *self.content.entry(series_key).or_insert(MetricValue::Unsigned(value)) = MetricValue::Unsigned(value);
     }
 
     fn observe_gauge(&mut self, key: Key, value: i64) {
-        *self.content.entry(key.name().to_string()).or_insert(MetricValue::Signed(value)) = MetricValue::Signed(value);
+        let series_key = self.series_key(&key);
+        *self.content.entry(series_key).or_insert(MetricValue::Signed(value)) = MetricValue::Signed(value);
     }
 
     fn observe_histogram(&mut self, key: Key, values: &[u64]) {
+        let series_key = self.series_key(&key);
         let entry = self
             .content
-            .entry(key.name().to_string())
+            .entry(series_key)
             .or_insert_with(|| MetricValue::Hist(Histogram::<u64>::new(4).expect("failed to create histogram")));
 
         if let MetricValue::Hist(hist) = entry {
@@ -71,35 +185,43 @@ impl Drain<Vec<(String, Vec<String>, Vec<String>)>> for CSVObserver {
         let mut measurements = Vec::new();
 
         // report all measurements
-        for (key, value) in self.content.iter() {
+        let mut content = std::mem::take(&mut self.content);
+        for (key, value) in content.iter_mut() {
             let mut headers = Vec::new();
             let mut values = Vec::new();
 
             match value {
                 MetricValue::Hist(val) => {
-                    let hist_pairs = hist_to_values(val, &self.quantiles);
+                    let reported = self.reported_histogram(key, val);
+                    // periods without any value are not reported
+                    if reported.is_empty() {
+                        continue;
+                    }
+                    let hist_pairs = hist_to_values(&reported, &self.quantiles);
                     for (hist_label, hist_value) in hist_pairs {
                         headers.push(hist_label);
                         values.push(hist_value.to_string());
                     }
                 }
                 MetricValue::Unsigned(val) => {
-                    headers.push(key.to_string());
+                    headers.push(key.0.clone());
                     values.push(val.to_string());
                 }
                 MetricValue::Signed(val) => {
-                    headers.push(key.to_string());
+                    headers.push(key.0.clone());
                     values.push(val.to_string());
                 }
             }
 
             if !headers.is_empty() && !values.is_empty() {
-                // insert system time as the first column
-                headers.insert(0, "timestamp".to_string());
-                values.insert(0, SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs().to_string());
-                measurements.push((key.to_string(), headers, values))
+                // insert system time as the first column, followed by column labels
+                let (name, column_values) = key;
+                headers.splice(0..0, std::iter::once("timestamp".to_string()).chain(self.column_labels.iter().cloned()));
+                values.splice(0..0, std::iter::once(self.clock.unix_time().as_secs().to_string()).chain(column_values.iter().cloned()));
+                measurements.push((name.clone(), headers, values))
             }
         }
+        self.content = content;
 
         // return measurements
         measurements
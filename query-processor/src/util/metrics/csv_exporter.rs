@@ -9,12 +9,16 @@
 //! configured interval.
 //! - Using `async_run` will return a future that can be awaited on, mimicing the behavior of
 //! `run`.
+//! - Using `spawn` will run the exporter on a background thread, whose handle flushes on demand,
+//! e.g., at the end of stream, and stops the exporter once the run is over.
 #![deny(missing_docs)]
 
 use std::{thread, time::Duration};
 use std::collections::HashMap;
 use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use csv::Writer;
 use log::trace;
@@ -70,6 +74,55 @@ impl<C, B> CSVExporter<C, B>
         }
     }
 
+    /// Runs this exporter on the current thread until `stop` is set, logging output at the interval
+    /// given on construction and once more after `stop` is set so that final measurements are flushed.
+    ///
+    /// The thread running the exporter can be unparked to flush without waiting for the interval,
+    /// e.g., at the end of stream.
+    pub fn run_until(&mut self, stop: &AtomicBool) {
+        while !stop.load(Ordering::SeqCst) {
+            thread::park_timeout(self.interval);
+
+            self.flush_now();
+        }
+        self.flush_now();
+    }
+
+    /// Runs this exporter on a background thread, logging output at the interval given on construction,
+    /// until the returned handle is stopped.
+    pub fn spawn(self) -> ExporterThread<C, B>
+        where
+            C: Send + 'static,
+            B: 'static,
+            B::Output: Send,
+    {
+        let exporter = Arc::new(Mutex::new(self));
+        let stop = Arc::new(AtomicBool::new(false));
+        let interval = exporter.lock().unwrap().interval;
+
+        let thread_exporter = exporter.clone();
+        let thread_stop = stop.clone();
+        let thread = thread::spawn(move || {
+            while !thread_stop.load(Ordering::SeqCst) {
+                thread::park_timeout(interval);
+
+                thread_exporter.lock().unwrap().flush_now();
+            }
+        });
+
+        ExporterThread { exporter, stop, thread }
+    }
+
+    /// Captures a snapshot and writes it right away, regardless of the interval.
+    pub fn flush_now(&mut self) {
+        self.turn();
+        for writer in self.metric_writer.values_mut() {
+            if let Err(e) = writer.flush() {
+                eprintln!("Error during flushing metric values {:?}", e);
+            }
+        }
+    }
+
     /// Run this exporter, logging output only once.
     pub fn turn(&mut self) {
         self.controller.observe(&mut self.observer);
@@ -111,3 +164,62 @@ impl<C, B> CSVExporter<C, B>
         }
     }
 }
+
+/// Handle of an exporter that runs on a background thread, see [`CSVExporter::spawn`]
+pub struct ExporterThread<C, B>
+    where
+        B: Builder,
+{
+    exporter: Arc<Mutex<CSVExporter<C, B>>>,
+    stop: Arc<AtomicBool>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl<C, B> ExporterThread<C, B>
+    where
+        B: Builder,
+        B::Output: Drain<Vec<(String, Vec<String>, Vec<String>)>> + Observer,
+        C: Observe,
+{
+    /// Returns a handle that flushes the exporter from other threads, e.g., workers at the end of stream.
+    pub fn flusher(&self) -> ExporterFlusher<C, B> {
+        ExporterFlusher { exporter: self.exporter.clone() }
+    }
+
+    /// Stops the exporter and writes its final measurements.
+    pub fn stop(self) {
+        self.stop.store(true, Ordering::SeqCst);
+        self.thread.thread().unpark();
+        self.thread.join().expect("Metric logger has failed");
+        self.exporter.lock().unwrap().flush_now();
+    }
+}
+
+/// Flushes an exporter that runs on a background thread without waiting for its interval
+pub struct ExporterFlusher<C, B>
+    where
+        B: Builder,
+{
+    exporter: Arc<Mutex<CSVExporter<C, B>>>,
+}
+
+impl<C, B> Clone for ExporterFlusher<C, B>
+    where
+        B: Builder,
+{
+    fn clone(&self) -> Self {
+        ExporterFlusher { exporter: self.exporter.clone() }
+    }
+}
+
+impl<C, B> ExporterFlusher<C, B>
+    where
+        B: Builder,
+        B::Output: Drain<Vec<(String, Vec<String>, Vec<String>)>> + Observer,
+        C: Observe,
+{
+    /// Captures a snapshot and writes it right away, see [`CSVExporter::flush_now`].
+    pub fn flush_now(&self) {
+        self.exporter.lock().unwrap().flush_now();
+    }
+}
@@ -1,2 +1,8 @@
 pub mod csv_exporter;
 pub mod csv_builder;
+pub mod summary;
+
+/// label of the worker a metric is recorded by
+pub const WORKER_LABEL: &str = "worker";
+/// label of the query a metric belongs to
+pub const QUERY_LABEL: &str = "query";
@@ -0,0 +1,77 @@
+//! Consolidated summary of a run, which is written once at the end of the run
+//!
+//! Metrics are exported once per reporting period, so that short runs may end before their measurements are reported.
+//! `RunSummary` is shared by workers of a process and is written into `summary.csv` in the reporting directory
+//! as `metric,value` rows with the total # of edges, throughput, total # of results and peak state sizes of operators.
+//! Peak state sizes are read from `util::gauges`, so they are summarized only if gauges are recorded, see `SUMMARY_STATE_VARIABLE`.
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// name of the summary file in the reporting directory
+pub const SUMMARY_FILE: &str = "summary.csv";
+
+/// environment variable that enables gauges in runners, so that peak state sizes of operators are summarized
+pub const SUMMARY_STATE_VARIABLE: &str = "SGRAFFITO_SUMMARY_STATE";
+
+#[derive(Clone, Debug, Default)]
+struct SummaryState {
+    edges: u64,
+    results: Option<u64>,
+    peak_state: BTreeMap<String, u64>,
+}
+
+/// Totals of a run, clones share the same totals
+#[derive(Clone, Debug, Default)]
+pub struct RunSummary {
+    state: Arc<Mutex<SummaryState>>,
+}
+
+impl RunSummary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// adds edges that are sent into the dataflow
+    pub fn add_edges(&self, edges: u64) {
+        self.state.lock().unwrap().edges += edges;
+    }
+
+    /// adds results of the query, results are reported only if they are counted
+    pub fn add_results(&self, results: u64) {
+        *self.state.lock().unwrap().results.get_or_insert(0) += results;
+    }
+
+    /// keeps the peak of each of the given state sizes, e.g., a snapshot of `util::gauges`
+    pub fn observe_state(&self, state_sizes: &BTreeMap<String, u64>) {
+        let mut state = self.state.lock().unwrap();
+        for (name, size) in state_sizes {
+            let peak = state.peak_state.entry(name.clone()).or_insert(0);
+            *peak = (*peak).max(*size);
+        }
+    }
+
+    /// writes the summary of a run that took `elapsed` into the summary file of the given reporting directory
+    pub fn write<P: AsRef<Path>>(&self, reporting_dir: P, elapsed: Duration) -> Result<(), String> {
+        let path = reporting_dir.as_ref().join(SUMMARY_FILE);
+        let state = self.state.lock().unwrap();
+
+        let mut rows = vec![
+            ("edges".to_string(), state.edges.to_string()),
+            ("total-time-ms".to_string(), elapsed.as_millis().to_string()),
+            ("throughput".to_string(), format!("{:.2}", state.edges as f64 / elapsed.as_secs_f64().max(f64::EPSILON))),
+        ];
+        if let Some(results) = state.results {
+            rows.push(("results".to_string(), results.to_string()));
+        }
+        rows.extend(state.peak_state.iter().map(|(name, size)| (format!("peak-{}", name), size.to_string())));
+
+        let mut writer = csv::Writer::from_path(&path).map_err(|e| format!("Cannot create summary file {:?}: {}", path, e))?;
+        writer.write_record(&["metric", "value"]).map_err(|e| format!("Cannot write summary file {:?}: {}", path, e))?;
+        for (metric, value) in rows {
+            writer.write_record(&[metric, value]).map_err(|e| format!("Cannot write summary file {:?}: {}", path, e))?;
+        }
+        writer.flush().map_err(|e| format!("Cannot write summary file {:?}: {}", path, e))
+    }
+}
@@ -1,2 +1,12 @@
+pub mod anomaly;
+pub mod clock;
+pub mod distributed;
+pub mod gauges;
+pub mod hot_swap;
+pub mod memory;
+#[cfg(feature = "metrics")]
 pub mod metrics;
+pub mod phase;
+pub mod shutdown;
+pub mod state_counters;
 pub mod types;
\ No newline at end of file
@@ -0,0 +1,83 @@
+//! Warm-up, measurement and cool-down phases of a run
+//!
+//! Phases are defined over the stream time, i.e., edge timestamps, relative to the first timestamp of the input.
+//! A run consists of a warm-up phase, an optional bounded measurement phase and a cool-down phase,
+//! after which the input is no longer consumed.
+use std::env;
+
+/// environment variables to configure phase durations in runners, in the unit of edge timestamps
+pub const WARM_UP_VARIABLE: &str = "SGRAFFITO_WARMUP";
+pub const MEASUREMENT_VARIABLE: &str = "SGRAFFITO_MEASUREMENT";
+pub const COOL_DOWN_VARIABLE: &str = "SGRAFFITO_COOLDOWN";
+
+/// label attached to exported metrics to annotate the phase they are recorded in
+pub const PHASE_LABEL: &str = "phase";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RunPhase {
+    WarmUp,
+    Measurement,
+    CoolDown,
+    Finished,
+}
+
+impl RunPhase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RunPhase::WarmUp => "warmup",
+            RunPhase::Measurement => "measurement",
+            RunPhase::CoolDown => "cooldown",
+            RunPhase::Finished => "finished",
+        }
+    }
+}
+
+/// Phase boundaries of a run over the stream time
+#[derive(Copy, Clone, Debug)]
+pub struct PhaseSchedule {
+    measurement_start: u64,
+    measurement_end: Option<u64>,
+    cool_down_end: Option<u64>,
+}
+
+impl PhaseSchedule {
+    /// `measurement` is the duration of the measurement phase, unbounded if it is `None`
+    /// in which case the cool-down phase never starts
+    pub fn new(start_timestamp: u64, warm_up: u64, measurement: Option<u64>, cool_down: u64) -> Self {
+        let measurement_start = start_timestamp + warm_up;
+        let measurement_end = measurement.map(|duration| measurement_start + duration);
+
+        Self {
+            measurement_start,
+            measurement_end,
+            cool_down_end: measurement_end.map(|end| end + cool_down),
+        }
+    }
+
+    /// creates a schedule from `SGRAFFITO_WARMUP`, `SGRAFFITO_MEASUREMENT` and `SGRAFFITO_COOLDOWN` environment variables
+    /// warm-up defaults to `default_warm_up`, measurement is unbounded and cool-down is 0 if they are not set
+    pub fn from_env(start_timestamp: u64, default_warm_up: u64) -> Self {
+        let warm_up = read_duration(WARM_UP_VARIABLE).unwrap_or(default_warm_up);
+        let measurement = read_duration(MEASUREMENT_VARIABLE);
+        let cool_down = read_duration(COOL_DOWN_VARIABLE).unwrap_or(0);
+
+        Self::new(start_timestamp, warm_up, measurement, cool_down)
+    }
+
+    /// returns the phase of the given stream time
+    pub fn phase_at(&self, timestamp: u64) -> RunPhase {
+        if timestamp < self.measurement_start {
+            RunPhase::WarmUp
+        } else if self.measurement_end.map_or(true, |end| timestamp < end) {
+            RunPhase::Measurement
+        } else if self.cool_down_end.map_or(true, |end| timestamp < end) {
+            RunPhase::CoolDown
+        } else {
+            RunPhase::Finished
+        }
+    }
+}
+
+fn read_duration(variable: &str) -> Option<u64> {
+    env::var(variable).ok().map(|value| value.parse().unwrap_or_else(|_| panic!("{} must be a non-negative integer, found {}", variable, value)))
+}
@@ -0,0 +1,31 @@
+//! Graceful shutdown on SIGINT/SIGTERM
+//!
+//! The first signal requests a shutdown, upon which sources emit an end-of-stream marker so that runners close
+//! their inputs, drain the dataflow and flush metrics. A second signal terminates the process immediately.
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use log::warn;
+
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// installs a handler for SIGINT and SIGTERM that requests a graceful shutdown
+pub fn install_signal_handler() {
+    ctrlc::set_handler(|| {
+        if SHUTDOWN_REQUESTED.swap(true, Ordering::SeqCst) {
+            warn!("Second termination signal is received, exiting immediately");
+            process::exit(130);
+        }
+        warn!("Termination signal is received, shutting down after the end of stream is processed");
+    }).expect("Cannot install signal handler");
+}
+
+/// requests a graceful shutdown, sources emit end-of-stream once it is requested
+pub fn request_shutdown() {
+    SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// returns true if a graceful shutdown is requested
+pub fn is_shutdown_requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::SeqCst)
+}
@@ -0,0 +1,68 @@
+//! Process-wide registry of automaton state and transition visit counters of RPQ operators
+//!
+//! Each time an RPQ operator adds or updates a spanning tree node, it traverses a transition of its automaton into the
+//! state of the node. Counters are collected per window, i.e., between consecutive calls to `complete_window`,
+//! to guide query rewriting and planner heuristics. As gauges, operators count visits only if the registry is enabled,
+//! so that there is no overhead otherwise.
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::util::types::StateType;
+
+/// environment variable that enables state visit counters in runners
+pub const STATE_COUNTERS_VARIABLE: &str = "SGRAFFITO_STATE_COUNTERS";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static COUNTERS: Lazy<Mutex<StateCounters>> = Lazy::new(|| Mutex::new(StateCounters::default()));
+
+/// Visit counts keyed by `<output label>.state.<state>` and `<output label>.transition.<source state>-<target state>`
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct StateCounters {
+    /// counts of the window in progress
+    #[serde(skip)]
+    current: BTreeMap<String, u64>,
+    /// counts of the latest completed window
+    pub last_window: BTreeMap<String, u64>,
+    /// counts since the start of the run
+    pub total: BTreeMap<String, u64>,
+}
+
+/// enables visit counters, it has to be enabled before RPQ operators are constructed
+pub fn enable() {
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// returns true if visits are counted
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// adds transition visits of the operator with the given output label to the window in progress
+pub fn add<I: IntoIterator<Item=((StateType, StateType), u64)>>(output_label: &str, transition_visits: I) {
+    let mut counters = COUNTERS.lock().unwrap();
+    for ((source_state, target_state), count) in transition_visits {
+        *counters.current.entry(format!("{}.transition.{}-{}", output_label, source_state, target_state)).or_insert(0) += count;
+        *counters.current.entry(format!("{}.state.{}", output_label, target_state)).or_insert(0) += count;
+    }
+}
+
+/// completes the window in progress and returns its counts
+pub fn complete_window() -> BTreeMap<String, u64> {
+    let mut counters = COUNTERS.lock().unwrap();
+    let window = std::mem::take(&mut counters.current);
+    for (name, count) in window.iter() {
+        *counters.total.entry(name.clone()).or_insert(0) += count;
+    }
+    counters.last_window = window.clone();
+    window
+}
+
+/// returns counts of the latest completed window and the totals
+pub fn snapshot() -> StateCounters {
+    COUNTERS.lock().unwrap().clone()
+}
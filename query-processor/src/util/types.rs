@@ -2,12 +2,17 @@ extern crate abomonation;
 extern crate abomonation_derive;
 extern crate strum;
 extern crate strum_macros;
+extern crate timely;
 
 use std::cmp::{max, min};
 use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
 
 use abomonation_derive::Abomonation;
+use serde::{Deserialize, Serialize};
 use strum_macros::EnumString;
+use timely::order::TotalOrder;
+use timely::progress::Timestamp;
 
 /// custom type definitions
 pub type VertexType = u64;
@@ -16,6 +21,261 @@ pub type VertexStatePair = (VertexType, StateType);
 
 /// constants
 pub const REPORTING_PERIOD_MILLISECONDS: u64 = 5000;
+/// # of operator invocations between two compactions of join indexes
+pub const JOIN_COMPACTION_PERIOD: u64 = 64;
+
+/// environment variable that enables deterministic execution mode in runners
+pub const DETERMINISTIC_VARIABLE: &str = "SGRAFFITO_DETERMINISTIC";
+
+/// Deterministic execution mode, see `set_deterministic_mode`
+static DETERMINISTIC_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables deterministic execution mode
+/// In deterministic mode, operators process stashed tuples and candidate trees in a sorted order
+/// so that repeated runs over the same input produce results in the same order, at the expense of performance
+/// RPQ and hash join operators read it once when they are added to a dataflow, so operators added earlier keep their mode
+pub fn set_deterministic_mode(enabled: bool) {
+    DETERMINISTIC_MODE.store(enabled, Ordering::SeqCst);
+}
+
+/// returns true if deterministic execution mode is enabled
+pub fn is_deterministic_mode() -> bool {
+    DETERMINISTIC_MODE.load(Ordering::SeqCst)
+}
+
+/// environment variable that enables provenance mode in runners
+pub const PROVENANCE_VARIABLE: &str = "SGRAFFITO_PROVENANCE";
+
+/// Provenance mode, see `set_provenance_mode`
+static PROVENANCE_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables provenance mode
+/// In provenance mode, tuples of edges carry the id of their edge, and join results carry the ids of all base edges they are derived from,
+/// so that consumers can explain composite results at the expense of larger tuples and join state
+/// It is read each time an edge is converted into a tuple, i.e., by `SGT::from_edge` of the window operator,
+/// so it has to be set before edges are fed to dataflows, and tuples of earlier edges carry no provenance
+pub fn set_provenance_mode(enabled: bool) {
+    PROVENANCE_MODE.store(enabled, Ordering::SeqCst);
+}
+
+/// returns true if provenance mode is enabled
+pub fn is_provenance_mode() -> bool {
+    PROVENANCE_MODE.load(Ordering::SeqCst)
+}
+
+/// environment variable that enables slide ids in runners
+pub const WINDOW_IDS_VARIABLE: &str = "SGRAFFITO_WINDOW_IDS";
+
+/// Window size and slide size of slide ids, see `set_window_ids`, 0 if they are disabled
+static WINDOW_ID_SIZE: AtomicU64 = AtomicU64::new(0);
+static WINDOW_ID_SLIDE: AtomicU64 = AtomicU64::new(0);
+
+/// Enables slide ids with the given window size and slide size of a sliding window, or disables them if `None`
+/// The window operator stamps each tuple with the id of the slide its timestamp is in, i.e., `timestamp / slide_size`,
+/// and results carry the ids of the earliest and the latest slide of the edges they are derived from (`SlideIds`),
+/// i.e., joins take the min and the max of both sides, and RPQ operators derive them from the interval of each path,
+/// so that sinks group results by slide exactly without re-deriving slides from intervals
+/// The window and RPQ operators read it when they are constructed, so it has to be set before dataflows are constructed
+/// panics if the window size or the slide size is 0
+pub fn set_window_ids(window: Option<(u64, u64)>) {
+    if let Some((window_size, slide_size)) = window {
+        assert!(window_size > 0 && slide_size > 0, "Window size and slide size of slide ids must be positive, found {} and {}", window_size, slide_size);
+    }
+    let (window_size, slide_size) = window.unwrap_or((0, 0));
+    WINDOW_ID_SIZE.store(window_size, Ordering::SeqCst);
+    WINDOW_ID_SLIDE.store(slide_size, Ordering::SeqCst);
+}
+
+/// returns the window size and the slide size of slide ids if they are enabled
+pub fn window_ids() -> Option<(u64, u64)> {
+    match (WINDOW_ID_SIZE.load(Ordering::SeqCst), WINDOW_ID_SLIDE.load(Ordering::SeqCst)) {
+        (_, 0) => None,
+        window => Some(window),
+    }
+}
+
+/// environment variable that sets the slack of the reordering buffer of RPQ operators in runners
+pub const REORDERING_SLACK_VARIABLE: &str = "SGRAFFITO_REORDERING_SLACK";
+
+/// Slack of the reordering buffer of RPQ operators, see `set_reordering_slack`, which is read only if it is enabled
+static REORDERING_SLACK: AtomicU64 = AtomicU64::new(0);
+static REORDERING_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Sets the slack of the reordering buffer that RPQ operators use to tolerate out-of-order inputs, or disables it if `None`
+/// `regular_path_query` and its variants read it when they are called, whereas `regular_path_query_with_slack` takes its own slack
+pub fn set_reordering_slack(slack: Option<u64>) {
+    REORDERING_SLACK.store(slack.unwrap_or(0), Ordering::SeqCst);
+    REORDERING_ENABLED.store(slack.is_some(), Ordering::SeqCst);
+}
+
+/// returns the slack of the reordering buffer if it is enabled
+pub fn reordering_slack() -> Option<u64> {
+    if REORDERING_ENABLED.load(Ordering::SeqCst) {
+        Some(REORDERING_SLACK.load(Ordering::SeqCst))
+    } else {
+        None
+    }
+}
+
+/// Order in which nodes are evicted from spanning trees that exceed the node cap, see `set_tree_node_cap`
+#[derive(EnumString, Debug, PartialEq, Eq, Copy, Clone)]
+pub enum EvictionPolicy {
+    /// nodes with the lowest expiry timestamp first, i.e., nodes that would expire first anyway
+    LowestExpiry,
+    /// nodes that are not added, re-parented or expanded for the longest time first
+    LeastRecentlyExtended,
+}
+
+/// environment variable that sets the node cap of spanning trees of RPQ operators in runners
+pub const TREE_NODE_CAP_VARIABLE: &str = "SGRAFFITO_TREE_NODE_CAP";
+/// environment variable that sets the `EvictionPolicy` of the node cap in runners, `LowestExpiry` by default
+pub const TREE_EVICTION_POLICY_VARIABLE: &str = "SGRAFFITO_TREE_EVICTION_POLICY";
+
+/// Max # of nodes of each spanning tree of RPQ operators, see `set_tree_node_cap`, `u64::MAX` if it is disabled
+static TREE_NODE_CAP: AtomicU64 = AtomicU64::new(u64::MAX);
+/// `EvictionPolicy` of the node cap, as its index in the declaration order
+static TREE_EVICTION_POLICY: AtomicU8 = AtomicU8::new(0);
+
+/// Sets the max # of nodes of each spanning tree that RPQ operators maintain, or disables the cap if `None`
+/// Once a tree exceeds the cap, nodes are evicted along with their subtrees in the order of `policy`, so that trees of super-node roots
+/// degrade gracefully into partial trees, i.e., results through evicted nodes are missed, instead of growing unboundedly
+/// Each RPQ operator reads the cap and the policy once when it is added to a dataflow and applies them to all of its trees
+pub fn set_tree_node_cap(max_nodes: Option<usize>, policy: EvictionPolicy) {
+    assert!(max_nodes != Some(0), "Node cap of spanning trees must be positive");
+    TREE_NODE_CAP.store(max_nodes.map_or(u64::MAX, |max_nodes| max_nodes as u64), Ordering::SeqCst);
+    TREE_EVICTION_POLICY.store(policy as u8, Ordering::SeqCst);
+}
+
+/// returns the node cap of spanning trees and its eviction policy if it is enabled
+pub fn tree_node_cap() -> Option<(usize, EvictionPolicy)> {
+    let policy = match TREE_EVICTION_POLICY.load(Ordering::SeqCst) {
+        0 => EvictionPolicy::LowestExpiry,
+        _ => EvictionPolicy::LeastRecentlyExtended,
+    };
+    match TREE_NODE_CAP.load(Ordering::SeqCst) {
+        u64::MAX => None,
+        max_nodes => Some((max_nodes as usize, policy)),
+    }
+}
+
+/// environment variable that enables Bloom filters over join indexes with the given false-positive rate in runners
+pub const JOIN_BLOOM_FPR_VARIABLE: &str = "SGRAFFITO_JOIN_BLOOM_FPR";
+
+/// False-positive rate of Bloom filters that guard probes of join indexes, see `set_join_bloom_filter`, as bits of an `f64`, 0 if disabled
+static JOIN_BLOOM_FILTER_RATE: AtomicU64 = AtomicU64::new(0);
+
+/// Enables Bloom filters over the keys of each join index of hash join operators with the given false-positive rate, or disables them if `None`
+/// Probes for keys that the filter of the other index rules out skip the index lookup, which pays off for sparse joins where most probes find no match
+/// Hash join operators read the rate when they are added to a dataflow and size the filters of their indexes with it
+pub fn set_join_bloom_filter(false_positive_rate: Option<f64>) {
+    if let Some(rate) = false_positive_rate {
+        assert!(rate > 0.0 && rate < 1.0, "False-positive rate of join filters must be in (0, 1), found {}", rate);
+    }
+    JOIN_BLOOM_FILTER_RATE.store(false_positive_rate.map_or(0, f64::to_bits), Ordering::SeqCst);
+}
+
+/// returns the false-positive rate of Bloom filters of join indexes if they are enabled
+pub fn join_bloom_filter() -> Option<f64> {
+    match JOIN_BLOOM_FILTER_RATE.load(Ordering::SeqCst) {
+        0 => None,
+        bits => Some(f64::from_bits(bits)),
+    }
+}
+
+/// environment variable that sets the # of threads that expire spanning trees in parallel in runners
+pub const DELTA_THREADS_VARIABLE: &str = "SGRAFFITO_DELTA_THREADS";
+
+/// # of threads that expire spanning trees of RPQ operators in parallel, see `set_delta_threads`, `u64::MAX` if it is disabled
+static DELTA_THREADS: AtomicU64 = AtomicU64::new(u64::MAX);
+
+/// Sets the # of threads of the pool that expires spanning trees of RPQ operators in parallel, or disables it if `None`
+/// The pool is shared by all RPQ operators of the process
+/// It takes effect only if the crate is built with the `parallel-delta` feature, and pays off for windows that expire many trees at once
+/// as it parallelizes expiry only, whereas trees are expanded by new edges sequentially
+/// RPQ operators read it when they are added to a dataflow, and the first operator that reads a new # of threads creates the pool
+pub fn set_delta_threads(threads: Option<usize>) {
+    assert!(threads != Some(0), "# of delta threads must be positive");
+    DELTA_THREADS.store(threads.map_or(u64::MAX, |threads| threads as u64), Ordering::SeqCst);
+}
+
+/// returns the # of threads that expire spanning trees in parallel if it is enabled
+pub fn delta_threads() -> Option<usize> {
+    match DELTA_THREADS.load(Ordering::SeqCst) {
+        u64::MAX => None,
+        threads => Some(threads as usize),
+    }
+}
+
+/// configures the modes and the operator settings of this module from their environment variables, as the SGA runners do,
+/// where slide ids are enabled for a sliding window of the given window size and slide size
+/// It has to be called before dataflows are constructed, and panics if a variable holds a malformed value
+pub fn configure_from_env(window_size: u64, slide_size: u64) {
+    if std::env::var(DETERMINISTIC_VARIABLE).is_ok() {
+        set_deterministic_mode(true);
+    }
+
+    if let Ok(slack) = std::env::var(REORDERING_SLACK_VARIABLE) {
+        set_reordering_slack(Some(slack.parse().unwrap_or_else(|_| panic!("{} must be a non-negative integer, found {}", REORDERING_SLACK_VARIABLE, slack))));
+    }
+
+    if let Ok(max_nodes) = std::env::var(TREE_NODE_CAP_VARIABLE) {
+        let max_nodes = max_nodes.parse().unwrap_or_else(|_| panic!("{} must be a positive integer, found {}", TREE_NODE_CAP_VARIABLE, max_nodes));
+        let policy = std::env::var(TREE_EVICTION_POLICY_VARIABLE).map_or(EvictionPolicy::LowestExpiry, |policy| {
+            policy.parse().unwrap_or_else(|_| panic!("{} must be LowestExpiry or LeastRecentlyExtended, found {}", TREE_EVICTION_POLICY_VARIABLE, policy))
+        });
+        set_tree_node_cap(Some(max_nodes), policy);
+    }
+
+    if let Ok(rate) = std::env::var(JOIN_BLOOM_FPR_VARIABLE) {
+        let rate = rate.parse().unwrap_or_else(|_| panic!("{} must be a rate in (0, 1), found {}", JOIN_BLOOM_FPR_VARIABLE, rate));
+        set_join_bloom_filter(Some(rate));
+    }
+
+    if let Ok(threads) = std::env::var(DELTA_THREADS_VARIABLE) {
+        let threads = threads.parse().unwrap_or_else(|_| panic!("{} must be a positive integer, found {}", DELTA_THREADS_VARIABLE, threads));
+        set_delta_threads(Some(threads));
+    }
+
+    if std::env::var(PROVENANCE_VARIABLE).is_ok() {
+        set_provenance_mode(true);
+    }
+
+    if std::env::var(WINDOW_IDS_VARIABLE).is_ok() {
+        set_window_ids(Some((window_size, slide_size)));
+    }
+}
+
+/// Timestamps of dataflows that SGA operators can run in, i.e., totally ordered timestamps that map onto event times
+/// Validity intervals are always in event time, i.e., edge timestamps, whereas dataflow timestamps can be finer grained,
+/// e.g., `(epoch, sequence)` pairs to track progress of sub-epochs, or timestamps of nested scopes
+pub trait EventTime: Timestamp + TotalOrder {
+    /// returns the event time this timestamp belongs to
+    fn event_time(&self) -> u64;
+    /// returns the latest timestamp of the given event time, so that comparing it against a frontier
+    /// tells whether the frontier can still produce data at or before that event time
+    fn upper_bound(event_time: u64) -> Self;
+}
+
+impl EventTime for u64 {
+    fn event_time(&self) -> u64 {
+        *self
+    }
+
+    fn upper_bound(event_time: u64) -> Self {
+        event_time
+    }
+}
+
+/// `(epoch, sequence)` timestamps ordered lexicographically, where the epoch is the event time
+impl EventTime for (u64, u64) {
+    fn event_time(&self) -> u64 {
+        self.0
+    }
+
+    fn upper_bound(event_time: u64) -> Self {
+        (event_time, u64::MAX)
+    }
+}
 
 /// Operation mode for SGA operators
 #[derive(EnumString, PartialEq, Copy, Clone)]
@@ -36,7 +296,7 @@ pub trait HalfOpenInterval {
 }
 
 /// Half-open time interval that is used to represent validity intervals
-#[derive(Copy, Clone, PartialEq, Abomonation, Debug, Hash, Eq)]
+#[derive(Copy, Clone, PartialEq, Abomonation, Debug, Hash, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct HalfOpenTimeInterval {
     pub start: u64,
     pub end: u64,
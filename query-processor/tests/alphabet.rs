@@ -0,0 +1,18 @@
+use sgraffito_query::Alphabet;
+
+#[test]
+fn frozen_alphabet() {
+    let mut alphabet = Alphabet::from_labels(vec!["a", "b"]);
+    assert_eq!(alphabet.get_or_insert("b"), Some(1));
+    assert_eq!(alphabet.get_or_insert("c"), Some(2));
+
+    alphabet.freeze();
+    assert_eq!(alphabet.get_or_insert("d"), None);
+    assert_eq!(alphabet.get_or_insert("a"), Some(0));
+    assert!(!alphabet.contains("d"));
+
+    let shared = alphabet.into_shared();
+    assert_eq!(shared.get("c"), Some(2));
+    assert_eq!(shared.resolve(1), "b");
+    assert_eq!(shared.iter().map(|(_, label)| label).collect::<Vec<_>>(), vec!["a", "b", "c"]);
+}
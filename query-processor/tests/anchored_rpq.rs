@@ -0,0 +1,74 @@
+//! Tests that constant endpoints of RPQs are parsed and filter results of RPQ and join operators
+use timely::dataflow::operators::Filter;
+
+use sgraffito_query::operator::SelfLoops;
+use sgraffito_query::operator::hash_join::{HashJoinAttributePair, JoinLabel, SymmetricHashJoin};
+use sgraffito_query::operator::rpq::RegularPathQuery;
+use sgraffito_query::query::parser::{Endpoints, RPQParser};
+use sgraffito_query::test_support::{run_operator, sgt};
+
+#[test]
+fn constant_endpoints() {
+    let parser = RPQParser::new();
+
+    let (endpoints, dfa) = parser.parse_anchored_rpq("<42> knows+ ?x").unwrap();
+    assert_eq!(endpoints, Endpoints { source: Some(42), target: None });
+    assert!(dfa.accept(vec!["knows", "knows"]));
+    assert!(endpoints.matches(42, 7));
+    assert!(!endpoints.matches(7, 42));
+
+    let (endpoints, _) = parser.parse_anchored_rpq("?x (a/b)* <7>").unwrap();
+    assert_eq!(endpoints, Endpoints { source: None, target: Some(7) });
+
+    assert!(parser.parse_anchored_rpq("<x> a ?y").is_err());
+}
+
+#[test]
+fn rpqs_filter_constant_endpoints() {
+    let inputs = vec![
+        (1, sgt(42, 1, "knows", 1, 10)),
+        (1, sgt(7, 1, "knows", 1, 10)),
+        (2, sgt(1, 2, "knows", 2, 10)),
+    ];
+    let rpq = |query_str: &'static str| -> Vec<(u64, u64)> {
+        let mut results: Vec<_> = run_operator(inputs.clone(), move |stream| stream.anchored_regular_path_query(query_str, "q".to_string()))
+            .into_iter().flat_map(|(_, tuples)| tuples).map(|sgt| (sgt.source, sgt.target)).collect();
+        results.sort();
+        results
+    };
+
+    assert_eq!(rpq("?x knows+ ?y"), vec![(1, 2), (7, 1), (7, 2), (42, 1), (42, 2)]);
+    assert_eq!(rpq("<42> knows+ ?y"), vec![(42, 1), (42, 2)]);
+    assert_eq!(rpq("?x knows+ <2>"), vec![(1, 2), (7, 2), (42, 2)]);
+    assert_eq!(rpq("<7> knows+ <1>"), vec![(7, 1)]);
+}
+
+#[test]
+fn joins_filter_constant_endpoints() {
+    let inputs = vec![
+        (1, sgt(1, 2, "a", 1, 10)),
+        (1, sgt(5, 2, "a", 1, 10)),
+        (2, sgt(2, 3, "b", 2, 8)),
+        (2, sgt(2, 4, "b", 2, 8)),
+        (2, sgt(1, 2, "b", 2, 8)),
+    ];
+    let join = |endpoints: Endpoints, tuple_join: bool| -> Vec<(u64, u64)> {
+        let mut results: Vec<_> = run_operator(inputs.clone(), move |stream| {
+            let lhs = stream.filter(|sgt| sgt.label == "a");
+            let rhs = stream.filter(|sgt| sgt.label == "b");
+            if tuple_join {
+                lhs.hash_join_tuple_with_endpoints(&rhs, false, false, "q".to_string(), SelfLoops::Keep, JoinLabel::Output, endpoints)
+            } else {
+                lhs.hash_join_with_endpoints(&rhs, HashJoinAttributePair::TS, HashJoinAttributePair::ST, "q".to_string(), SelfLoops::Keep, JoinLabel::Output, endpoints)
+            }
+        }).into_iter().flat_map(|(_, tuples)| tuples).map(|sgt| (sgt.source, sgt.target)).collect();
+        results.sort();
+        results
+    };
+
+    assert_eq!(join(Endpoints::default(), false), vec![(1, 3), (1, 4), (5, 3), (5, 4)]);
+    assert_eq!(join(Endpoints { source: Some(1), target: None }, false), vec![(1, 3), (1, 4)]);
+    assert_eq!(join(Endpoints { source: Some(1), target: Some(4) }, false), vec![(1, 4)]);
+    assert_eq!(join(Endpoints::default(), true), vec![(1, 2)]);
+    assert_eq!(join(Endpoints { source: Some(5), target: None }, true), vec![]);
+}
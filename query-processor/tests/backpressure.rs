@@ -0,0 +1,60 @@
+//! Tests that the replay driver throttles the input until the dataflow catches up once too many edges are in flight
+use std::sync::Arc;
+use std::time::Duration;
+
+use sgraffito_query::ingest::{Backpressure, ReplayDriver, ReplayHandler, Slide};
+use sgraffito_query::input::{InputPartitioner, Partitioning, StreamingGraphEdge};
+use sgraffito_query::util::clock::ManualClock;
+use sgraffito_query::util::phase::PhaseSchedule;
+
+/// records the calls of the driver, where catching up takes 5ms of a manual clock
+struct Recorder {
+    clock: ManualClock,
+    calls: Vec<String>,
+    backpressure: Vec<Option<Backpressure>>,
+}
+
+impl ReplayHandler for Recorder {
+    fn send(&mut self, sge: StreamingGraphEdge) {
+        self.calls.push(format!("send {}", sge.timestamp));
+    }
+
+    fn slide(&mut self, slide: &Slide) {
+        self.calls.push(format!("slide {}", slide.time));
+        self.backpressure.push(slide.backpressure);
+    }
+
+    fn catch_up(&mut self, time: u64) {
+        self.clock.advance(Duration::from_millis(5));
+        self.calls.push(format!("catch up {}", time));
+    }
+}
+
+fn replay(max_in_flight: Option<u64>) -> Recorder {
+    let edges = vec![1, 2, 3, 4, 5, 11].into_iter().map(|ts| StreamingGraphEdge::new(1, 2, "a".to_string(), ts));
+    let predicates = vec!["a".to_string()];
+
+    let clock = ManualClock::new();
+    let mut handler = Recorder { clock: clock.clone(), calls: Vec::new(), backpressure: Vec::new() };
+    ReplayDriver::new(1, 10, PhaseSchedule::new(1, 0, None, 0), &predicates, InputPartitioner::new(Partitioning::RoundRobin, 0, 1))
+        .with_backpressure(max_in_flight)
+        .with_clock(Arc::new(clock))
+        .run(edges, &mut handler);
+    handler
+}
+
+#[test]
+fn input_is_throttled_after_max_in_flight_edges() {
+    let handler = replay(Some(2));
+    assert_eq!(handler.calls, vec![
+        "send 1", "send 2", "catch up 3", "send 3", "send 4", "catch up 5", "send 5", "slide 11", "send 11",
+    ]);
+    assert_eq!(handler.backpressure, vec![Some(Backpressure { throttles: 2, stalled: Duration::from_millis(10) })]);
+}
+
+#[test]
+fn input_is_not_throttled_without_backpressure() {
+    let handler = replay(None);
+    assert!(handler.calls.iter().all(|call| !call.starts_with("catch up")));
+    assert_eq!(handler.backpressure, vec![None]);
+}
@@ -0,0 +1,38 @@
+//! Tests that benchmark scenarios are validated when they are loaded
+#![cfg(all(feature = "dd", feature = "metrics"))]
+use sgraffito_query::bench::Scenario;
+
+const SCENARIO: &str = r#"
+name = "validation"
+dataset = "tests/data/graph.txt"
+input_type = "it"
+engines = ["@engines"]
+workers = [1]
+report = "/tmp/validation.csv"
+
+[[queries]]
+name = "@query"
+predicates = ["a", "b"]
+
+[[windows]]
+window_size = 10
+slide_size = 5
+"#;
+
+fn load(engine: &str, query_name: &str) -> Result<Scenario, String> {
+    let path = std::env::temp_dir().join(format!("sgraffito-scenario-{}-{}-{}.toml", std::process::id(), engine, query_name));
+    std::fs::write(&path, SCENARIO.replace("@engines", engine).replace("@query", query_name)).unwrap();
+    let scenario = Scenario::from_file(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    scenario
+}
+
+#[test]
+fn unknown_queries_are_rejected() {
+    assert!(load("sga", "query2").is_ok());
+    assert!(load("dd", "query2").is_ok());
+    // LDBC queries are defined only in the SGA library
+    assert!(load("sga", "ldbc-is2").is_ok());
+    assert!(load("dd", "ldbc-is2").unwrap_err().contains("ldbc-is2"));
+    assert!(load("sga", "query9").unwrap_err().contains("query9"));
+}
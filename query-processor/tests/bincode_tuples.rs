@@ -0,0 +1,31 @@
+//! Round trips of tuple types through serde and bincode, which exchange tuples between workers with the `bincode` feature
+#![cfg(feature = "bincode")]
+use std::fmt::Debug;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use sgraffito_query::input::SGT;
+use sgraffito_query::input::tuple::{GroupedResult, Provenance, SlideIds, StarTuple, StreamingGraphTuple, TaggedTuple};
+use sgraffito_query::util::types::HalfOpenTimeInterval;
+
+fn round_trip<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: T) {
+    let bytes = bincode::serialize(&value).unwrap();
+    assert_eq!(bincode::deserialize::<T>(&bytes).unwrap(), value);
+}
+
+#[test]
+fn tuples_survive_round_trips() {
+    let mut tuple = StreamingGraphTuple::new(1, 2, "q".to_string(), HalfOpenTimeInterval::new(3, 8));
+    round_trip(tuple.clone());
+
+    // annotations are boxed or optional, so they are checked with and without values
+    tuple.window_id = 5;
+    tuple.slide_ids = SlideIds { first: 1, last: 2 };
+    tuple.provenance = Provenance::of_edge(7).merge(&Provenance::of_edge(4));
+    round_trip(tuple.clone());
+    round_trip(TaggedTuple::new("q1".to_string(), tuple));
+
+    round_trip(StarTuple::new(1, vec![2, 3, 4], "star".to_string(), HalfOpenTimeInterval::new(3, 8)));
+    round_trip(GroupedResult { source: 1, targets: vec![2, 5], label: "q".to_string(), interval: HalfOpenTimeInterval::new(3, 8), append: false, window_id: 4 });
+}
@@ -0,0 +1,32 @@
+//! Tests that change alerts report only results that appear or disappear between consecutive windows
+use sgraffito_query::input::tuple::StreamingGraphTuple;
+use sgraffito_query::operator::alerts::ChangeAlerts;
+use sgraffito_query::test_support::{run_operator, sgt};
+
+fn alert(source: u64, start: u64, end: u64, rising: bool, window: u64) -> StreamingGraphTuple {
+    let mut sgt = sgt(source, source + 1, "q", start, end);
+    sgt.append = rising;
+    sgt.window_id = window;
+    sgt
+}
+
+#[test]
+fn rising_and_falling_results() {
+    let inputs = vec![
+        (1, sgt(1, 2, "q", 1, 5)),
+        (3, sgt(2, 3, "q", 3, 9)),
+        // a result that is already valid does not rise again
+        (3, sgt(1, 2, "q", 3, 5)),
+        (10, sgt(4, 5, "q", 10, 12)),
+    ];
+    let outputs = run_operator(inputs, |stream| stream.change_alerts(2));
+
+    assert_eq!(outputs[&2], vec![alert(1, 1, 5, true, 2)]);
+    assert_eq!(outputs[&4], vec![alert(2, 3, 9, true, 4)]);
+    assert_eq!(outputs[&6], vec![alert(1, 1, 5, false, 6)]);
+    assert!(!outputs.contains_key(&8));
+    let mut alerts = outputs[&10].clone();
+    alerts.sort_by_key(|sgt| sgt.source);
+    assert_eq!(alerts, vec![alert(2, 3, 9, false, 10), alert(4, 10, 12, true, 10)]);
+    assert_eq!(outputs.len(), 4);
+}
@@ -0,0 +1,32 @@
+use sgraffito_query::input::{GraphEdge, SGE};
+use sgraffito_query::input::datasets::{parse_datetime, DatasetReader, DatasetSchema};
+
+#[test]
+fn datetimes_are_converted_to_unix_timestamps() {
+    assert_eq!(parse_datetime("1970-01-01 00:00:00"), Some(0));
+    assert_eq!(parse_datetime("2013-12-31 16:39:58"), Some(1_388_507_998));
+    assert_eq!(parse_datetime("2016-02-29 00:00:01"), Some(1_456_704_001));
+    assert_eq!(parse_datetime("2016-13-01 00:00:00"), None);
+}
+
+#[test]
+fn presets_map_columns_and_time_units() {
+    let directory = std::env::temp_dir();
+
+    let snap_file = directory.join(format!("sgraffito-sx-stackoverflow-{}.txt", std::process::id()));
+    std::fs::write(&snap_file, "# comment\n9 8 1217567877\n1 2 1217573801\n").unwrap();
+    let edges: Vec<_> = DatasetReader::open(snap_file.to_str().unwrap(), DatasetSchema::preset("sx-stackoverflow-a2q").unwrap()).unwrap().collect();
+    assert_eq!(edges.len(), 2);
+    assert_eq!((edges[0].get_source(), edges[0].get_target(), edges[0].get_label(), edges[0].get_timestamp()), (9, 8, "a2q", 1_217_567_877));
+    std::fs::remove_file(&snap_file).unwrap();
+
+    let reddit_file = directory.join(format!("sgraffito-reddit-hyperlinks-{}.tsv", std::process::id()));
+    std::fs::write(&reddit_file, "SOURCE_SUBREDDIT\tTARGET_SUBREDDIT\tPOST_ID\tTIMESTAMP\tLINK_SENTIMENT\tPROPERTIES\n\
+        leagueoflegends\tteamredditteams\t1u4nrps\t2013-12-31 16:39:58\t1\t345.0\n").unwrap();
+    let reader = DatasetReader::open(reddit_file.to_str().unwrap(), DatasetSchema::preset("reddit-hyperlinks").unwrap()).unwrap();
+    assert_eq!(reader.get_start_timestamp(), 1_388_507_998);
+    let edges: Vec<_> = reader.collect();
+    assert_eq!(edges.len(), 1);
+    assert_eq!(edges[0].get_label(), "hyperlink");
+    std::fs::remove_file(&reddit_file).unwrap();
+}
@@ -0,0 +1,36 @@
+//! Tests that duplicate input edges are dropped within their horizon
+use sgraffito_query::input::{SGE, StreamingGraphEdge};
+use sgraffito_query::input::dedup::{DedupMode, EdgeDeduplicator};
+
+fn edge(source: u64, target: u64, timestamp: u64) -> StreamingGraphEdge {
+    StreamingGraphEdge::new(source, target, "a".to_string(), timestamp)
+}
+
+#[test]
+fn exact_duplicates() {
+    let mut deduplicator = EdgeDeduplicator::new(DedupMode::Exact);
+
+    assert!(!deduplicator.is_duplicate(&edge(1, 2, 1)));
+    assert!(deduplicator.is_duplicate(&edge(1, 2, 1)));
+    assert!(!deduplicator.is_duplicate(&edge(2, 1, 1)));
+    // the same edge at a later timestamp is not an exact duplicate
+    assert!(!deduplicator.is_duplicate(&edge(1, 2, 2)));
+
+    assert_eq!(deduplicator.take_dropped(), 1);
+    assert_eq!(deduplicator.take_dropped(), 0);
+    assert_eq!(deduplicator.len(), 1);
+}
+
+#[test]
+fn duplicates_within_horizon() {
+    let mut deduplicator = EdgeDeduplicator::new(DedupMode::Horizon(3));
+
+    assert!(!deduplicator.is_duplicate(&edge(1, 2, 1)));
+    assert!(deduplicator.is_duplicate(&edge(1, 2, 2)));
+    assert!(deduplicator.is_duplicate(&edge(1, 2, 3)));
+    // horizon is measured from the last edge that is not dropped
+    assert!(!deduplicator.is_duplicate(&edge(1, 2, 4)));
+    assert!(deduplicator.is_duplicate(&edge(1, 2, 6)));
+
+    assert_eq!(deduplicator.take_dropped(), 3);
+}
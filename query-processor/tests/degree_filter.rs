@@ -0,0 +1,33 @@
+//! Tests that edges incident to vertices whose windowed degree is outside the thresholds are dropped or flagged
+use sgraffito_query::input::tuple::StreamingGraphTuple;
+use sgraffito_query::operator::degree::{DegreeAction, DegreeFilter, DegreeThresholds};
+use sgraffito_query::test_support::{run_operator, sgt};
+
+fn inputs() -> Vec<(u64, StreamingGraphTuple)> {
+    vec![
+        (1, sgt(1, 2, "a", 1, 5)),
+        (2, sgt(1, 3, "a", 2, 6)),
+        // vertex 1 reaches degree 3
+        (3, sgt(4, 1, "b", 3, 7)),
+        // the first edge of vertex 1 has expired
+        (5, sgt(1, 5, "a", 5, 9)),
+    ]
+}
+
+#[test]
+fn drop_edges_of_super_nodes() {
+    let outputs = run_operator(inputs(), |stream| stream.degree_filter(DegreeThresholds::at_most(2), DegreeAction::Drop));
+
+    assert_eq!(outputs[&1], vec![sgt(1, 2, "a", 1, 5)]);
+    assert_eq!(outputs[&2], vec![sgt(1, 3, "a", 2, 6)]);
+    assert!(!outputs.contains_key(&3));
+    assert!(!outputs.contains_key(&5));
+}
+
+#[test]
+fn flag_edges_of_super_nodes() {
+    let outputs = run_operator(inputs(), |stream| stream.degree_filter(DegreeThresholds::at_most(2), DegreeAction::from_name("flag:-hub").unwrap()));
+
+    assert_eq!(outputs[&3], vec![sgt(4, 1, "b-hub", 3, 7)]);
+    assert_eq!(outputs[&5], vec![sgt(1, 5, "a-hub", 5, 9)]);
+}
@@ -0,0 +1,28 @@
+//! Tests that operators emit results in the same order in deterministic mode regardless of the arrival order of their inputs
+use sgraffito_query::input::tuple::StreamingGraphTuple;
+use sgraffito_query::operator::rpq::RegularPathQuery;
+use sgraffito_query::test_support::{run_operator, sgt};
+use sgraffito_query::util::types::set_deterministic_mode;
+
+/// results of `a+` in arrival order, where all edges arrive at the same epoch
+fn rpq_results(edges: &[(u64, u64)]) -> Vec<StreamingGraphTuple> {
+    let inputs = edges.iter().map(|(source, target)| (1, sgt(*source, *target, "a", 1, 10))).collect();
+    run_operator(inputs, |stream| stream.regular_path_query("a+", "q".to_string())).remove(&1).unwrap()
+}
+
+#[test]
+fn results_follow_a_sorted_order() {
+    // operators read the mode when they are constructed
+    set_deterministic_mode(true);
+
+    let edges = vec![(3, 4), (1, 2), (2, 4), (1, 3), (2, 3), (4, 1)];
+    let results = rpq_results(&edges);
+    assert_eq!(results.len(), 16);
+
+    let mut reversed_edges = edges.clone();
+    reversed_edges.reverse();
+    assert_eq!(rpq_results(&reversed_edges), results);
+    let mut sorted_edges = edges;
+    sorted_edges.sort();
+    assert_eq!(rpq_results(&sorted_edges), results);
+}
@@ -0,0 +1,21 @@
+//! Tests that vertex dictionaries reloaded from their file keep the vertices of identifiers across runs
+use sgraffito_query::input::VertexDictionary;
+
+#[test]
+fn persisted_dictionary_keeps_vertices() {
+    let path = std::env::temp_dir().join(format!("sgraffito-dictionary-{}.txt", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    let dictionary = VertexDictionary::load_or_new(path).unwrap();
+    assert_eq!(dictionary.get_or_assign("alice"), 0);
+    assert_eq!(dictionary.get_or_assign("bob smith"), 1);
+    dictionary.insert(42, "carol");
+    dictionary.save(path).unwrap();
+
+    let reloaded = VertexDictionary::load_or_new(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(reloaded.get_or_assign("bob smith"), 1);
+    assert_eq!(reloaded.resolve(42), "carol");
+    // dense vertices follow the reloaded ones
+    assert_eq!(reloaded.get_or_assign("dave"), 2);
+}
@@ -0,0 +1,53 @@
+//! Tests that RPQs between vertex sets root trees only at the source set and report only targets in the target set
+use std::collections::HashSet;
+use std::fs;
+
+use sgraffito_query::input::{VertexDictionary, VertexEncoding, VertexIds};
+use sgraffito_query::input::vertex_filter::VertexFilter;
+use sgraffito_query::operator::EndpointSets;
+use sgraffito_query::operator::rpq::RegularPathQuery;
+use sgraffito_query::test_support::{run_operator, sgt};
+
+#[test]
+fn results_between_vertex_sets() {
+    let directory = std::env::temp_dir().join(format!("sgraffito-endpoints-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let (sources, targets) = (directory.join("sources.txt"), directory.join("targets.txt"));
+    fs::write(&sources, "1\n5\n").unwrap();
+    fs::write(&targets, "3\n4\n").unwrap();
+    let endpoints = EndpointSets::from_files(sources.to_str().unwrap(), targets.to_str().unwrap(), &VertexIds::new(true)).unwrap();
+    assert!(endpoints.matches_source(5) && !endpoints.matches_source(2));
+    assert!(endpoints.matches_target(4) && !endpoints.matches_target(1));
+    fs::remove_dir_all(&directory).unwrap();
+
+    let inputs = vec![
+        (1, sgt(1, 2, "a", 1, 10)),
+        (1, sgt(2, 3, "a", 1, 10)),
+        (2, sgt(3, 4, "a", 2, 10)),
+        (2, sgt(5, 1, "a", 2, 10)),
+    ];
+    let endpoints = EndpointSets::new(VertexFilter::Set(vec![1, 5].into_iter().collect()), VertexFilter::Set(vec![3, 4].into_iter().collect()));
+    let outputs = run_operator(inputs, move |stream| stream.regular_path_query_between("a+", endpoints, "q".to_string()));
+
+    let pairs: HashSet<(u64, u64)> = outputs.values().flatten().map(|sgt| (sgt.source, sgt.target)).collect();
+    assert_eq!(pairs, vec![(1, 3), (1, 4), (5, 3), (5, 4)].into_iter().collect());
+}
+
+#[test]
+fn endpoint_files_with_dense_identifiers() {
+    let directory = std::env::temp_dir().join(format!("sgraffito-dense-endpoints-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let (sources, targets) = (directory.join("sources.txt"), directory.join("targets.txt"));
+    fs::write(&sources, "alice\n").unwrap();
+    fs::write(&targets, "carol\n").unwrap();
+
+    // vertices that the reader assigns afterwards share the dense identifiers of the endpoint files
+    let dictionary = VertexDictionary::new();
+    let vertex_ids = VertexIds::new(false).with_vertex_encoding(VertexEncoding::Dense, dictionary.clone());
+    let endpoints = EndpointSets::from_files(sources.to_str().unwrap(), targets.to_str().unwrap(), &vertex_ids).unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert!(endpoints.matches_source(dictionary.get_or_assign("alice")));
+    assert!(!endpoints.matches_source(dictionary.get_or_assign("bob")));
+    assert!(endpoints.matches_target(dictionary.get_or_assign("carol")));
+}
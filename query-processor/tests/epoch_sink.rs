@@ -0,0 +1,25 @@
+use sgraffito_query::input::tuple::StreamingGraphTuple;
+use sgraffito_query::sink::file::EpochFileSink;
+use sgraffito_query::util::types::HalfOpenTimeInterval;
+
+#[test]
+fn committed_epochs_are_skipped_after_restart() {
+    let directory = std::env::temp_dir().join(format!("sgraffito-epoch-sink-{}", std::process::id()));
+    let tuples = vec![StreamingGraphTuple::new(1, 2, "q".to_string(), HalfOpenTimeInterval::new(3, 8))];
+
+    let mut sink = EpochFileSink::open(&directory, 0).unwrap();
+    assert_eq!(sink.last_committed(), None);
+    assert!(sink.commit(3, &tuples).unwrap());
+    assert!(sink.commit(5, &tuples).unwrap());
+
+    // a restarted run replays from an earlier checkpoint
+    let mut sink = EpochFileSink::open(&directory, 0).unwrap();
+    assert_eq!(sink.last_committed(), Some(5));
+    assert!(!sink.commit(5, &tuples).unwrap());
+    assert!(sink.commit(6, &tuples).unwrap());
+
+    let committed = std::fs::read_to_string(directory.join("0").join(format!("epoch-{:020}.csv", 3))).unwrap();
+    assert_eq!(committed, "q,1,2,3,8,\n");
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
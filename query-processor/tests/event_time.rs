@@ -0,0 +1,77 @@
+//! Tests that SGA operators produce the same results in dataflows with `(epoch, sequence)` timestamps
+//! as in dataflows with `u64` timestamps
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use timely::Configuration;
+use timely::dataflow::{InputHandle, ProbeHandle, Scope};
+use timely::dataflow::operators::{Concat, Input, Inspect, Probe};
+
+use sgraffito_query::harness::read_edges;
+use sgraffito_query::input::StreamingGraphEdge;
+use sgraffito_query::operator::hash_join::{HashJoinAttributePair, SymmetricHashJoin};
+use sgraffito_query::operator::rpq::RegularPathQuery;
+use sgraffito_query::operator::window::SlidingWindow;
+use sgraffito_query::util::types::{EventTime, HalfOpenTimeInterval};
+
+const GRAPH_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/tiny-graph.txt");
+const WINDOW: u64 = 8;
+
+type Results = BTreeSet<(u64, u64, String, HalfOpenTimeInterval)>;
+
+/// runs `a*` and the `a/b` join over the tiny graph, where the i-th edge with timestamp `ts` is sent at `timestamp(ts, i)`
+fn run<T: EventTime, F: Fn(u64, u64) -> T + Send + Sync + 'static>(timestamp: F) -> Results {
+    let edges = read_edges(GRAPH_FILE, true, true);
+
+    let guards = timely::execute(Configuration::Thread, move |worker| {
+        let mut input: InputHandle<T, StreamingGraphEdge> = InputHandle::new();
+        let mut probe = ProbeHandle::new();
+        let results = Rc::new(RefCell::new(BTreeSet::new()));
+
+        let result_sink = results.clone();
+        worker.dataflow::<T, _, _>(|scope| {
+            let windowed_stream = scope.input_from(&mut input).sliding_window(WINDOW);
+            let a = windowed_stream.filter_label("a");
+            let b = windowed_stream.filter_label("b");
+
+            let closure = a.regular_path_query("a*", "rpq".to_string());
+            let join = a.hash_join(&b, HashJoinAttributePair::TS, HashJoinAttributePair::ST, "join".to_string());
+
+            closure.concat(&join)
+                .inspect(move |sgt| { result_sink.borrow_mut().insert((sgt.source, sgt.target, sgt.label.clone(), sgt.interval)); })
+                .probe_with(&mut probe);
+        });
+
+        for (sequence, sge) in edges.iter().cloned().enumerate() {
+            input.advance_to(timestamp(sge.timestamp, sequence as u64));
+            input.send(sge);
+        }
+        input.close();
+        worker.step_while(|| !probe.done());
+
+        results.replace(BTreeSet::new())
+    }).expect("Cannot execute the dataflow");
+
+    guards.join().into_iter().next().unwrap().expect("Dataflow has failed")
+}
+
+trait FilterLabel {
+    fn filter_label(&self, label: &'static str) -> Self;
+}
+
+impl<G: Scope> FilterLabel for timely::dataflow::Stream<G, sgraffito_query::input::tuple::StreamingGraphTuple> {
+    fn filter_label(&self, label: &'static str) -> Self {
+        use timely::dataflow::operators::Filter;
+        self.filter(move |sgt| sgt.label == label)
+    }
+}
+
+#[test]
+fn sequence_timestamps_match_epochs() {
+    let epoch_results = run(|timestamp, _| timestamp);
+    let sequence_results = run(|timestamp, sequence| (timestamp, sequence));
+
+    assert!(!epoch_results.is_empty());
+    assert_eq!(epoch_results, sequence_results);
+}
@@ -0,0 +1,25 @@
+//! Tests that results of a window are grouped by their source with the list of their targets
+use sgraffito_query::input::tuple::GroupedResult;
+use sgraffito_query::operator::group::GroupBySource;
+use sgraffito_query::test_support::{run_operator, sgt};
+use sgraffito_query::util::types::HalfOpenTimeInterval;
+
+#[test]
+fn targets_grouped_by_source() {
+    let mut retraction = sgt(1, 3, "q", 2, 10);
+    retraction.append = false;
+    let inputs = vec![
+        (1, sgt(1, 3, "q", 1, 10)),
+        (1, sgt(1, 2, "q", 1, 10)),
+        (1, sgt(2, 3, "q", 1, 10)),
+        (1, sgt(1, 4, "q", 1, 5)),
+        (2, retraction),
+    ];
+    let outputs = run_operator(inputs, |stream| stream.group_by_source());
+
+    let group = |source: u64, targets: Vec<u64>, start: u64, end: u64, append: bool, window_id: u64| {
+        GroupedResult { source, targets, label: "q".to_string(), interval: HalfOpenTimeInterval::new(start, end), append, window_id }
+    };
+    assert_eq!(outputs[&1], vec![group(1, vec![4], 1, 5, true, 1), group(1, vec![2, 3], 1, 10, true, 1), group(2, vec![3], 1, 10, true, 1)]);
+    assert_eq!(outputs[&2], vec![group(1, vec![3], 2, 10, false, 2)]);
+}
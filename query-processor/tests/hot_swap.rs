@@ -0,0 +1,119 @@
+//! Tests that the RPQ operator swaps its automaton without losing the window content
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use timely::Configuration;
+use timely::dataflow::{InputHandle, ProbeHandle};
+use timely::dataflow::operators::{Input, Inspect, Probe};
+
+use sgraffito_query::input::{SGE, StreamingGraphEdge};
+use sgraffito_query::operator::rpq::RegularPathQuery;
+use sgraffito_query::operator::window::SlidingWindow;
+use sgraffito_query::util::hot_swap;
+
+const WINDOW: u64 = 100;
+
+/// a swap of the operator to the given query at the given event time, which is requested before the edge of the given timestamp is sent
+type LateSwap = (u64, u64, &'static str);
+
+/// runs the query over the given (source, label, target, timestamp) edges, returning endpoints of results
+fn run(query: &'static str, output_label: &'static str, edges: Vec<(u64, &'static str, u64, u64)>, late_swap: Option<LateSwap>) -> BTreeSet<(u64, u64)> {
+    run_on(Configuration::Thread, query, output_label, edges, late_swap).remove(0)
+}
+
+/// runs the query as `run` with the given configuration, where the first worker reads the edges, returning endpoints of results of each worker
+fn run_on(configuration: Configuration, query: &'static str, output_label: &'static str, edges: Vec<(u64, &'static str, u64, u64)>, late_swap: Option<LateSwap>) -> Vec<BTreeSet<(u64, u64)>> {
+    let guards = timely::execute(configuration, move |worker| {
+        let reads_input = worker.index() == 0;
+        let mut input: InputHandle<u64, StreamingGraphEdge> = InputHandle::new();
+        let mut probe = ProbeHandle::new();
+        let results = Rc::new(RefCell::new(BTreeSet::new()));
+
+        let result_sink = results.clone();
+        worker.dataflow::<u64, _, _>(|scope| {
+            scope.input_from(&mut input).sliding_window(WINDOW)
+                .regular_path_query(query, output_label.to_string())
+                .inspect(move |sgt| { result_sink.borrow_mut().insert((sgt.source, sgt.target)); })
+                .probe_with(&mut probe);
+        });
+
+        for (source, label, target, timestamp) in edges.iter() {
+            if let Some((request_time, swap_time, swap_query)) = late_swap {
+                if reads_input && request_time == *timestamp {
+                    hot_swap::request_swap(output_label, swap_time, swap_query).unwrap();
+                }
+            }
+            input.advance_to(*timestamp);
+            if reads_input {
+                input.send(StreamingGraphEdge::new(*source, *target, label.to_string(), *timestamp));
+            }
+            worker.step_while(|| probe.less_than(input.time()));
+        }
+        input.close();
+        worker.step_while(|| !probe.done());
+
+        results.replace(BTreeSet::new())
+    }).expect("Cannot execute the dataflow");
+
+    guards.join().into_iter().map(|results| results.expect("Dataflow has failed")).collect()
+}
+
+/// swaps are global, so that a swap that is requested while the dataflow runs is checked before any swap is requested upfront
+#[test]
+fn swapped_automaton_keeps_window() {
+    // edges of `b` are dropped until the swap is requested at 5, so that windows are warming until the edges before it expire
+    let edges = vec![(1, "b", 2, 1), (2, "a", 3, 2), (3, "b", 4, 5), (4, "a", 5, 10)];
+    let expected: BTreeSet<(u64, u64)> = vec![(2, 3), (3, 4), (4, 5)].into_iter().collect();
+    assert_eq!(run("a", "late", edges, Some((5, 10, "a|b"))), expected);
+    assert!(!hot_swap::is_warming("late", 5));
+    assert!(hot_swap::is_warming("late", 10) && hot_swap::is_warming("late", WINDOW));
+    assert!(hot_swap::is_any_warming(50));
+    assert!(!hot_swap::is_warming("late", 1 + WINDOW));
+
+    // edges of all labels are retained once a swap is requested, so that trees are rebuilt over the entire window at the swap
+    hot_swap::request_swap("swapped", 10, "a|b").unwrap();
+    let edges = vec![(1, "a", 2, 1), (2, "b", 3, 2), (3, "b", 4, 10), (4, "a", 5, 11), (5, "c", 6, 12)];
+    let expected: BTreeSet<(u64, u64)> = vec![(1, 2), (2, 3), (3, 4), (4, 5)].into_iter().collect();
+    assert_eq!(run("a", "swapped", edges, None), expected);
+
+    // no edge is missed, so that windows are not warming
+    assert!(!hot_swap::is_warming("swapped", 50));
+    assert!(!hot_swap::is_warming("unswapped", 50));
+}
+
+#[test]
+fn every_worker_swaps_its_automaton() {
+    hot_swap::request_swap("parallel", 10, "a|b").unwrap();
+    let edges = vec![(1, "a", 2, 1), (2, "b", 3, 2), (3, "b", 4, 10), (4, "a", 5, 11)];
+    let expected: BTreeSet<(u64, u64)> = vec![(1, 2), (2, 3), (3, 4), (4, 5)].into_iter().collect();
+
+    // workers that evaluate the query return results of the new automaton only
+    let results = run_on(Configuration::Process(2), "a", "parallel", edges, None);
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().any(|worker_results| !worker_results.is_empty()));
+    assert!(results.iter().filter(|worker_results| !worker_results.is_empty()).all(|worker_results| *worker_results == expected));
+}
+
+#[test]
+fn swaps_are_applied_once_per_worker() {
+    hot_swap::request_swap("workers", 10, "a|b").unwrap();
+    assert!(hot_swap::due_swap("workers", 0, 5).is_none());
+    assert!(hot_swap::due_swap("workers", 0, 10).is_some());
+    assert!(hot_swap::due_swap("workers", 0, 11).is_none());
+    // other workers apply the same swap
+    assert!(hot_swap::due_swap("workers", 1, 12).is_some());
+    assert!(hot_swap::due_swap("workers", 1, 13).is_none());
+
+    // warming windows of workers are merged
+    hot_swap::set_warming("workers", 10, 50);
+    hot_swap::set_warming("workers", 12, 40);
+    assert!(hot_swap::is_warming("workers", 10) && hot_swap::is_warming("workers", 49));
+    assert!(!hot_swap::is_warming("workers", 50));
+}
+
+#[test]
+fn invalid_swaps() {
+    assert!(hot_swap::request_swap("invalid", 10, "a|").is_err());
+    assert!(hot_swap::due_swap("invalid", 0, 10).is_none());
+}
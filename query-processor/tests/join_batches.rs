@@ -0,0 +1,57 @@
+//! Tests that emitting the results of each probed key as a batch preserves results of hash joins
+use std::collections::HashSet;
+
+use timely::dataflow::operators::Filter;
+
+use sgraffito_query::input::tuple::StreamingGraphTuple;
+use sgraffito_query::operator::hash_join::{HashJoinAttributePair, SymmetricHashJoin};
+use sgraffito_query::test_support::{run_operator, sgt};
+use sgraffito_query::test_support::model::ModelGenerator;
+
+const WINDOW: u64 = 7;
+
+/// distinct random `a` and `b` tuples over a few vertices, so that keys are probed by many tuples,
+/// where tuples start at odd timestamps and are valid for an odd window, so that no tuple starts when another one ends
+fn random_tuples(seed: u64) -> Vec<(u64, StreamingGraphTuple)> {
+    let mut generator = ModelGenerator::new(seed);
+    let mut edges = HashSet::new();
+    (0..60).filter_map(|i| {
+        let label = if generator.next_below(2) == 0 { "a" } else { "b" };
+        let (source, target) = (generator.next_below(5), generator.next_below(5));
+        let start = 2 * i + 1;
+        if edges.insert((label, source, target)) { Some((start, sgt(source, target, label, start, start + WINDOW))) } else { None }
+    }).collect()
+}
+
+/// results of `a/b` from a nested loop over all pairs of tuples, at the epoch of the later tuple of each pair
+fn nested_loop_join(tuples: &[(u64, StreamingGraphTuple)]) -> Vec<(u64, StreamingGraphTuple)> {
+    let mut results = Vec::new();
+    for (_, lhs) in tuples.iter().filter(|(_, tuple)| tuple.label == "a") {
+        for (_, rhs) in tuples.iter().filter(|(_, tuple)| tuple.label == "b" && tuple.source == lhs.target) {
+            let (start, end) = (lhs.interval.start.max(rhs.interval.start), lhs.interval.end.min(rhs.interval.end));
+            if start < end {
+                results.push((start, sgt(lhs.source, rhs.target, "q", start, end)));
+            }
+        }
+    }
+    results.sort();
+    results
+}
+
+#[test]
+fn batched_results_match_a_nested_loop_join() {
+    for seed in 0..10 {
+        let tuples = random_tuples(seed);
+        let expected = nested_loop_join(&tuples);
+
+        let mut results: Vec<(u64, StreamingGraphTuple)> = run_operator(tuples, |stream| {
+            let lhs = stream.filter(|sgt| sgt.label == "a");
+            let rhs = stream.filter(|sgt| sgt.label == "b");
+            lhs.hash_join(&rhs, HashJoinAttributePair::TS, HashJoinAttributePair::ST, "q".to_string())
+        }).into_iter().flat_map(|(epoch, tuples)| tuples.into_iter().map(move |tuple| (epoch, tuple))).collect();
+        results.sort();
+
+        assert!(!expected.is_empty());
+        assert_eq!(results, expected, "results of seed {} differ", seed);
+    }
+}
@@ -0,0 +1,47 @@
+//! Tests that Bloom filters over join indexes skip probes of absent keys without changing join results
+use timely::dataflow::operators::Filter;
+
+use sgraffito_query::operator::hash_join::{HashJoinAttributePair, SymmetricHashJoin};
+use sgraffito_query::test_support::{run_operator, sgt};
+use sgraffito_query::util::types::set_join_bloom_filter;
+
+/// (source, target, start, end) of results of the join of `a` and `b` tuples, sorted
+fn join_results(tuple_join: bool) -> Vec<(u64, u64, u64, u64)> {
+    // many keys are present in one index only, so that their probes are answered by the filter of the other index
+    let mut inputs = Vec::new();
+    for i in 0..50 {
+        inputs.push((1 + i % 5, sgt(i, 100 + i, "a", 1 + i % 5, 20)));
+        inputs.push((1 + i % 5, sgt(100 + 2 * i, 300 + i, "b", 1 + i % 5, 15)));
+    }
+    // pairs that match in both joins
+    inputs.push((6, sgt(7, 107, "b", 6, 15)));
+    inputs.push((6, sgt(20, 120, "b", 6, 15)));
+
+    let mut results: Vec<(u64, u64, u64, u64)> = run_operator(inputs, move |stream| {
+        let lhs = stream.filter(|sgt| sgt.label == "a");
+        let rhs = stream.filter(|sgt| sgt.label == "b");
+        if tuple_join {
+            lhs.hash_join_tuple(&rhs, false, false, "q".to_string())
+        } else {
+            lhs.hash_join(&rhs, HashJoinAttributePair::TS, HashJoinAttributePair::ST, "q".to_string())
+        }
+    }).values().flatten().map(|result| (result.source, result.target, result.interval.start, result.interval.end)).collect();
+    results.sort();
+    results
+}
+
+/// operators read the filter rate when they are constructed, and runs with and without filters share the rate,
+/// so that they are compared in a single test
+#[test]
+fn filtered_joins_match_unfiltered_joins() {
+    set_join_bloom_filter(None);
+    let hash_join = join_results(false);
+    let tuple_join = join_results(true);
+    assert!(!hash_join.is_empty());
+    assert_eq!(tuple_join, vec![(7, 107, 6, 15), (20, 120, 6, 15)]);
+
+    set_join_bloom_filter(Some(0.01));
+    assert_eq!(join_results(false), hash_join);
+    assert_eq!(join_results(true), tuple_join);
+    set_join_bloom_filter(None);
+}
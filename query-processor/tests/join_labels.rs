@@ -0,0 +1,59 @@
+//! Tests that join results are labeled by the labels of the tuples they are derived from
+use std::sync::Arc;
+
+use timely::dataflow::operators::Filter;
+
+use sgraffito_query::input::tuple::StreamingGraphTuple;
+use sgraffito_query::operator::SelfLoops;
+use sgraffito_query::operator::hash_join::{HashJoinAttributePair, JoinLabel, SymmetricHashJoin};
+use sgraffito_query::test_support::{run_operator, sgt};
+
+fn join_with_label(result_label: JoinLabel) -> Vec<(u64, Vec<StreamingGraphTuple>)> {
+    let inputs = vec![
+        (1, sgt(1, 2, "a", 1, 10)),
+        (2, sgt(2, 3, "b", 2, 8)),
+    ];
+    run_operator(inputs, move |stream| {
+        let lhs = stream.filter(|sgt| sgt.label == "a");
+        let rhs = stream.filter(|sgt| sgt.label == "b");
+        lhs.hash_join_with_label(&rhs, HashJoinAttributePair::TS, HashJoinAttributePair::ST, "q".to_string(), SelfLoops::Keep, result_label)
+    }).into_iter().collect()
+}
+
+#[test]
+fn pass_through_labels() {
+    assert_eq!(join_with_label(JoinLabel::Output), vec![(2, vec![sgt(1, 3, "q", 2, 8)])]);
+    assert_eq!(join_with_label(JoinLabel::Lhs), vec![(2, vec![sgt(1, 3, "a", 2, 8)])]);
+    assert_eq!(join_with_label(JoinLabel::Rhs), vec![(2, vec![sgt(1, 3, "b", 2, 8)])]);
+    assert_eq!(join_with_label(JoinLabel::Concatenated("/".to_string())), vec![(2, vec![sgt(1, 3, "a/b", 2, 8)])]);
+    assert_eq!(join_with_label(JoinLabel::Custom(Arc::new(|lhs, rhs| format!("{}-then-{}", lhs, rhs)))), vec![(2, vec![sgt(1, 3, "a-then-b", 2, 8)])]);
+}
+
+#[test]
+fn labels_of_the_same_endpoints_are_kept_apart() {
+    // lhs tuples of both labels share their endpoints, so each of them derives its own result
+    let inputs = vec![
+        (1, sgt(1, 2, "a", 1, 10)),
+        (1, sgt(1, 2, "c", 1, 10)),
+        (2, sgt(2, 3, "b", 2, 8)),
+        (2, sgt(1, 2, "b", 2, 8)),
+    ];
+    let results = |tuple_join: bool, result_label: JoinLabel| -> Vec<StreamingGraphTuple> {
+        let mut results: Vec<_> = run_operator(inputs.clone(), move |stream| {
+            let lhs = stream.filter(|sgt| sgt.label != "b");
+            let rhs = stream.filter(|sgt| sgt.label == "b");
+            if tuple_join {
+                lhs.hash_join_tuple_with_label(&rhs, false, false, "q".to_string(), SelfLoops::Keep, result_label)
+            } else {
+                lhs.hash_join_with_label(&rhs, HashJoinAttributePair::TS, HashJoinAttributePair::ST, "q".to_string(), SelfLoops::Keep, result_label)
+            }
+        }).into_iter().flat_map(|(_, tuples)| tuples).collect();
+        results.sort_by(|x, y| x.label.cmp(&y.label));
+        results
+    };
+
+    assert_eq!(results(false, JoinLabel::Lhs), vec![sgt(1, 3, "a", 2, 8), sgt(1, 3, "c", 2, 8)]);
+    assert_eq!(results(true, JoinLabel::Lhs), vec![sgt(1, 2, "a", 2, 8), sgt(1, 2, "c", 2, 8)]);
+    assert_eq!(results(false, JoinLabel::Output), vec![sgt(1, 3, "q", 2, 8)]);
+    assert_eq!(results(true, JoinLabel::Output), vec![sgt(1, 2, "q", 2, 8)]);
+}
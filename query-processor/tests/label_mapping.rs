@@ -0,0 +1,35 @@
+//! Tests that label mappings rewrite labels of edges and drop edges with labels that the query does not use
+use sgraffito_query::input::{SGE, StreamingGraphEdge};
+use sgraffito_query::input::labels::LabelMapping;
+
+fn apply(mapping: &LabelMapping, label: &str) -> Option<String> {
+    let mut edge = StreamingGraphEdge::new(1, 2, label.to_string(), 1);
+    if mapping.apply(&mut edge) { Some(edge.label) } else { None }
+}
+
+#[test]
+fn unused_labels_are_dropped() {
+    let alphabet = vec!["likes".to_string(), "knows".to_string()].into_iter().collect();
+    let mapping = LabelMapping::new().alias("likes_post", "likes").drop_unused(true).with_alphabet(alphabet);
+
+    // labels are matched against the alphabet after they are rewritten
+    assert_eq!(apply(&mapping, "likes_post"), Some("likes".to_string()));
+    assert_eq!(apply(&mapping, "knows"), Some("knows".to_string()));
+    assert_eq!(apply(&mapping, "follows"), None);
+
+    // unused labels are kept unless they are dropped, or until the alphabet is set
+    let alphabet = vec!["likes".to_string()].into_iter().collect();
+    assert_eq!(apply(&LabelMapping::new().with_alphabet(alphabet), "follows"), Some("follows".to_string()));
+    assert_eq!(apply(&LabelMapping::new().drop_unused(true), "follows"), Some("follows".to_string()));
+}
+
+#[test]
+fn entries_are_canonicalized_as_edge_labels() {
+    let alphabet = vec!["Likes".to_string()].into_iter().collect();
+    let mapping = LabelMapping::new().alias("<http://example.org/Likes_Post>", "LIKES").case_insensitive(true)
+        .drop_unused(true).with_alphabet(alphabet);
+
+    assert_eq!(apply(&mapping, "<http://example.org/likes_post>"), Some("likes".to_string()));
+    assert_eq!(apply(&mapping, "LIKES"), Some("likes".to_string()));
+    assert_eq!(apply(&mapping, "knows"), None);
+}
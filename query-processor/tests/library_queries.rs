@@ -0,0 +1,473 @@
+//! End-to-end tests that run queries of the query library over miniature datasets in `tests/data`
+//! and compare results of each window against the expected result sets
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use sgraffito_query::harness::{read_edges, run_sga_query, run_sga_query_with_control, RunControl, WindowResults};
+use sgraffito_query::operator::rpq::evaluate_rpq;
+use sgraffito_query::query::query_library::SGAQueryLibrary;
+use sgraffito_query::util::clock::{Clock, ManualClock};
+#[cfg(feature = "dd")]
+use sgraffito_query::dd::DistinctGranularity;
+#[cfg(feature = "dd")]
+use sgraffito_query::harness::{run_dd_query, run_dd_query_with_distinct};
+
+const GRAPH_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/tiny-graph.txt");
+const PATTERN_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/tiny-patterns.txt");
+
+const GRAPH_WINDOW: u64 = 8;
+const PATTERN_WINDOW: u64 = 6;
+const SLIDE: u64 = 2;
+
+/// builds the expected results from (window, pairs) tuples
+fn expected(windows: Vec<(u64, Vec<(u64, u64)>)>) -> WindowResults {
+    windows.into_iter().map(|(window, pairs)| (window, pairs.into_iter().collect())).collect()
+}
+
+fn predicates(labels: &[&str]) -> Vec<String> {
+    labels.iter().map(|label| label.to_string()).collect()
+}
+
+fn check_sga(input_file: &str, window_size: u64, query_name: &str, labels: &[&str], expected_results: &WindowResults) {
+    let edges = read_edges(input_file, true, true);
+    let results = run_sga_query(edges, window_size, SLIDE, query_name, predicates(labels));
+    assert_eq!(&results, expected_results, "SGA results of {} do not match", query_name);
+}
+
+#[cfg(feature = "dd")]
+fn check_dd(input_file: &str, window_size: u64, query_name: &str, labels: &[&str], expected_results: &WindowResults) {
+    let edges = read_edges(input_file, true, true);
+    let results = run_dd_query(edges, window_size, SLIDE, query_name, predicates(labels));
+    assert_eq!(&results, expected_results, "DD results of {} do not match", query_name);
+}
+
+/// DD results are not checked unless the `dd` feature is enabled
+#[cfg(not(feature = "dd"))]
+fn check_dd(_input_file: &str, _window_size: u64, _query_name: &str, _labels: &[&str], _expected_results: &WindowResults) {}
+
+#[test]
+fn join() {
+    let expected_results = expected(vec![
+        (3, vec![(1, 3)]),
+        (5, vec![(1, 3)]),
+        (7, vec![(1, 3), (2, 4), (5, 4)]),
+        (9, vec![(2, 4), (5, 4)]),
+        (11, vec![(2, 4), (4, 4), (5, 4)]),
+        (13, vec![(4, 4), (5, 4)]),
+        (15, vec![(1, 2)]),
+    ]);
+
+    check_sga(GRAPH_FILE, GRAPH_WINDOW, "join", &["a", "b"], &expected_results);
+    check_dd(GRAPH_FILE, GRAPH_WINDOW, "join", &["a", "b"], &expected_results);
+}
+
+#[test]
+fn query1() {
+    let expected_results = expected(vec![
+        (3, vec![(1, 2)]),
+        (5, vec![(1, 2), (1, 3), (2, 3)]),
+        (7, vec![(1, 2), (1, 3), (2, 3), (5, 3)]),
+        (9, vec![(2, 3), (5, 3)]),
+        (11, vec![(1, 3), (1, 4), (2, 3), (4, 3), (5, 3)]),
+        (13, vec![(1, 3), (1, 4), (4, 3), (5, 3)]),
+        (15, vec![(1, 1), (1, 3), (1, 4), (3, 1), (3, 3), (3, 4), (4, 1), (4, 3), (4, 4)]),
+    ]);
+
+    check_sga(GRAPH_FILE, GRAPH_WINDOW, "query1", &["a"], &expected_results);
+    check_dd(GRAPH_FILE, GRAPH_WINDOW, "query1", &["a"], &expected_results);
+}
+
+#[test]
+fn query2() {
+    let expected_results = expected(vec![
+        (3, vec![(1, 3)]),
+        (5, vec![(1, 3)]),
+        (7, vec![(1, 3), (1, 4), (2, 4), (5, 4)]),
+        (9, vec![(2, 4), (5, 4)]),
+        (11, vec![(2, 4), (4, 4), (5, 4)]),
+        (13, vec![(4, 4), (5, 4)]),
+        (15, vec![(1, 2), (1, 6)]),
+    ]);
+
+    check_sga(GRAPH_FILE, GRAPH_WINDOW, "query2", &["a", "b"], &expected_results);
+    check_dd(GRAPH_FILE, GRAPH_WINDOW, "query2", &["a", "b"], &expected_results);
+}
+
+#[test]
+fn query2_a() {
+    let expected_results = expected(vec![
+        (3, vec![(1, 2), (1, 3)]),
+        (5, vec![(1, 2), (1, 3), (2, 3)]),
+        (7, vec![(1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (5, 3), (5, 4)]),
+        (9, vec![(2, 3), (2, 4), (5, 3), (5, 4)]),
+        (11, vec![(1, 4), (2, 3), (2, 4), (4, 3), (4, 4), (5, 3), (5, 4)]),
+        (13, vec![(1, 4), (4, 3), (4, 4), (5, 3), (5, 4)]),
+        (15, vec![(1, 2), (1, 4), (1, 6), (3, 1), (4, 3)]),
+    ]);
+
+    check_sga(GRAPH_FILE, GRAPH_WINDOW, "query2-a", &["a", "b"], &expected_results);
+}
+
+#[test]
+fn query3() {
+    let expected_results = expected(vec![
+        (3, vec![(1, 1)]),
+        (5, vec![(1, 1)]),
+        (7, vec![(1, 1)]),
+        (9, vec![(2, 2), (5, 2)]),
+        (11, vec![(2, 2), (4, 2), (5, 2)]),
+        (13, vec![(4, 2), (5, 2)]),
+        (15, vec![(1, 3)]),
+    ]);
+
+    check_sga(GRAPH_FILE, GRAPH_WINDOW, "query3", &["a", "b", "c"], &expected_results);
+    check_dd(GRAPH_FILE, GRAPH_WINDOW, "query3", &["a", "b", "c"], &expected_results);
+}
+
+#[test]
+fn query3_a() {
+    let expected_results = expected(vec![
+        (3, vec![(1, 1), (1, 2), (1, 3)]),
+        (5, vec![(1, 1), (1, 2), (1, 3), (2, 1), (2, 3)]),
+        (7, vec![(1, 1), (1, 2), (1, 3), (1, 4), (2, 1), (2, 3), (2, 4), (5, 1), (5, 3), (5, 4)]),
+        (9, vec![(2, 1), (2, 2), (2, 3), (2, 4), (5, 1), (5, 2), (5, 3), (5, 4)]),
+        (11, vec![(1, 2), (1, 4), (2, 2), (2, 3), (2, 4), (4, 2), (4, 3), (4, 4), (5, 2), (5, 3), (5, 4)]),
+        (13, vec![(1, 2), (1, 4), (4, 2), (4, 3), (4, 4), (5, 2), (5, 3), (5, 4)]),
+        (15, vec![(1, 2), (1, 3), (1, 4), (1, 6), (3, 1), (4, 3)]),
+    ]);
+
+    check_sga(GRAPH_FILE, GRAPH_WINDOW, "query3-a", &["a", "b", "c"], &expected_results);
+}
+
+#[test]
+fn query4() {
+    let expected_results = expected(vec![
+        (3, vec![(1, 1)]),
+        (5, vec![(1, 1)]),
+        (7, vec![(1, 1)]),
+        (9, vec![(2, 2), (5, 2)]),
+        (11, vec![(2, 2), (4, 2), (5, 2)]),
+        (13, vec![(4, 2), (5, 2)]),
+        (15, vec![]),
+    ]);
+
+    check_sga(GRAPH_FILE, GRAPH_WINDOW, "query4", &["a", "b", "c"], &expected_results);
+    check_sga(GRAPH_FILE, GRAPH_WINDOW, "query4-a", &["a", "b", "c"], &expected_results);
+    check_sga(GRAPH_FILE, GRAPH_WINDOW, "query4-pc1", &["a", "b", "c"], &expected_results);
+    check_sga(GRAPH_FILE, GRAPH_WINDOW, "query4-pc2", &["a", "b", "c"], &expected_results);
+    check_dd(GRAPH_FILE, GRAPH_WINDOW, "query4", &["a", "b", "c"], &expected_results);
+}
+
+#[test]
+fn query5() {
+    let expected_results = expected(vec![
+        (3, vec![]),
+        (5, vec![(1, 4)]),
+        (7, vec![]),
+        (9, vec![]),
+        (11, vec![]),
+    ]);
+
+    check_sga(PATTERN_FILE, PATTERN_WINDOW, "query5", &["a", "b", "c"], &expected_results);
+    check_dd(PATTERN_FILE, PATTERN_WINDOW, "query5", &["a", "b", "c"], &expected_results);
+}
+
+#[test]
+fn query6() {
+    let expected_results = expected(vec![
+        (3, vec![]),
+        (5, vec![]),
+        (7, vec![]),
+        (9, vec![(5, 7)]),
+        (11, vec![(6, 7)]),
+    ]);
+
+    check_sga(PATTERN_FILE, PATTERN_WINDOW, "query6", &["a", "b", "c"], &expected_results);
+    check_dd(PATTERN_FILE, PATTERN_WINDOW, "query6", &["a", "b", "c"], &expected_results);
+}
+
+#[test]
+fn query6_cq() {
+    let expected_results = expected(vec![
+        (3, vec![]),
+        (5, vec![]),
+        (7, vec![]),
+        (9, vec![]),
+        (11, vec![(6, 7)]),
+    ]);
+
+    check_sga(PATTERN_FILE, PATTERN_WINDOW, "query6-cq", &["a", "b", "c"], &expected_results);
+    check_dd(PATTERN_FILE, PATTERN_WINDOW, "query6-cq", &["a", "b", "c"], &expected_results);
+}
+
+#[test]
+fn query7() {
+    let expected_results = expected(vec![
+        (3, vec![]),
+        (5, vec![]),
+        (7, vec![]),
+        (9, vec![(5, 8)]),
+        (11, vec![(6, 8), (6, 9)]),
+    ]);
+
+    check_sga(PATTERN_FILE, PATTERN_WINDOW, "query7", &["a", "b", "c"], &expected_results);
+    check_dd(PATTERN_FILE, PATTERN_WINDOW, "query7", &["a", "b", "c"], &expected_results);
+}
+
+#[test]
+fn query7_cq() {
+    let expected_results = expected(vec![
+        (3, vec![]),
+        (5, vec![]),
+        (7, vec![]),
+        (9, vec![]),
+        (11, vec![(6, 8), (6, 9)]),
+    ]);
+
+    check_sga(PATTERN_FILE, PATTERN_WINDOW, "query7-cq", &["a", "b", "c"], &expected_results);
+    check_dd(PATTERN_FILE, PATTERN_WINDOW, "query7-cq", &["a", "b", "c"], &expected_results);
+}
+
+#[test]
+fn query8() {
+    let expected_results = expected(vec![
+        (3, vec![]),
+        (5, vec![]),
+        (7, vec![(2, 2), (2, 5), (5, 2), (5, 5)]),
+        (9, vec![(2, 2), (2, 5), (5, 2), (5, 5)]),
+        (11, vec![(2, 2), (2, 4), (2, 5), (4, 2), (4, 4), (4, 5), (5, 2), (5, 4), (5, 5)]),
+        (13, vec![(4, 4), (4, 5), (5, 4), (5, 5)]),
+        (15, vec![]),
+    ]);
+
+    check_sga(GRAPH_FILE, GRAPH_WINDOW, "query8", &["a"], &expected_results);
+    check_dd(GRAPH_FILE, GRAPH_WINDOW, "query8", &["a"], &expected_results);
+}
+
+#[test]
+fn single_step_run() {
+    let edges = read_edges(GRAPH_FILE, true, true);
+    let expected_results = run_sga_query(edges.clone(), GRAPH_WINDOW, SLIDE, "query1", predicates(&["a"]));
+
+    let control = RunControl::new_paused();
+    let run_control = control.clone();
+    let run = thread::spawn(move || run_sga_query_with_control(edges, GRAPH_WINDOW, SLIDE, "query1", predicates(&["a"]), run_control));
+
+    // a paused run completes exactly one window per step
+    for (step, (window_time, window_results)) in expected_results.iter().enumerate() {
+        control.step();
+        let snapshots = control.wait_for_windows(step + 1);
+        assert_eq!(snapshots.len(), step + 1);
+        assert_eq!(snapshots.get(window_time), Some(window_results));
+    }
+
+    control.resume();
+    assert_eq!(run.join().unwrap(), expected_results);
+}
+
+/// clock that advances by a millisecond whenever it is read
+#[derive(Debug, Default)]
+struct TickingClock(ManualClock);
+
+impl Clock for TickingClock {
+    fn now(&self) -> Duration {
+        self.0.advance(Duration::from_millis(1));
+        self.0.now()
+    }
+
+    fn unix_time(&self) -> Duration {
+        self.now()
+    }
+}
+
+#[test]
+fn window_latencies_follow_the_clock_of_the_run() {
+    let edges = read_edges(GRAPH_FILE, true, true);
+    let control = RunControl::new().with_clock(Arc::new(TickingClock::default()));
+    let results = run_sga_query_with_control(edges, GRAPH_WINDOW, SLIDE, "query1", predicates(&["a"]), control.clone());
+
+    // each window reads the clock when it starts and when it is completed
+    let latencies = control.latencies();
+    assert_eq!(latencies.keys().collect::<Vec<_>>(), results.keys().collect::<Vec<_>>());
+    assert!(latencies.values().all(|latency| *latency == Duration::from_millis(1)));
+}
+
+#[test]
+fn ldbc_ic2() {
+    let expected_results = expected(vec![
+        (3, vec![]),
+        (5, vec![(2, 2)]),
+        (7, vec![(2, 2), (5, 2)]),
+        (9, vec![(2, 2), (5, 2)]),
+        (11, vec![(1, 3)]),
+        (13, vec![(1, 3)]),
+        (15, vec![]),
+    ]);
+
+    check_sga(GRAPH_FILE, GRAPH_WINDOW, "ldbc-ic2", &["a", "b"], &expected_results);
+}
+
+/// evaluates a plan of `SGAQueryLibrary::explain` over the edges of a window, one line at a time
+fn evaluate_plan(plan: &str, window_edges: &[(u64, &str, u64)]) -> BTreeSet<(u64, u64)> {
+    let mut relations: BTreeMap<String, BTreeSet<(u64, u64)>> = BTreeMap::new();
+    for (source, label, target) in window_edges {
+        relations.entry(label.to_string()).or_default().insert((*source, *target));
+    }
+    let attribute = |pair: &(u64, u64), endpoint: &str| if endpoint.ends_with("source") { pair.0 } else { pair.1 };
+
+    let mut last = BTreeSet::new();
+    for line in plan.lines() {
+        let (label, operator) = line.split_once(" = ").unwrap();
+        let (parameters, inputs) = match operator.split_once('[') {
+            Some((_, rest)) => {
+                let (parameters, inputs) = rest.rsplit_once("](").unwrap();
+                (parameters, inputs.trim_end_matches(')'))
+            }
+            None => ("", operator.split_once('(').unwrap().1.trim_end_matches(')')),
+        };
+        let input = |name: &str| relations.get(name).cloned().unwrap_or_default();
+        let inputs: Vec<&str> = inputs.split(", ").collect();
+
+        let output: BTreeSet<(u64, u64)> = if operator.starts_with("RPQ[") {
+            let edges: Vec<(u64, String, u64)> = inputs.iter()
+                .flat_map(|name| input(*name).into_iter().map(move |(source, target)| (source, name.to_string(), target)))
+                .collect();
+            evaluate_rpq(edges.iter().map(|(source, label, target)| (*source, label.as_str(), *target)), parameters).into_iter().collect()
+        } else if operator.starts_with("HashJoin[") {
+            // lhs.<attribute> = rhs.<attribute> -> (lhs.<attribute>, rhs.<attribute>)
+            let (predicate, projection) = parameters.split_once(" -> ").unwrap();
+            let (lhs_key, rhs_key) = predicate.split_once(" = ").unwrap();
+            let (lhs_output, rhs_output) = projection.trim_matches(|c| c == '(' || c == ')').split_once(", ").unwrap();
+            let (lhs, rhs) = (input(inputs[0]), input(inputs[1]));
+            lhs.iter()
+                .flat_map(|l| rhs.iter().filter(move |r| attribute(l, lhs_key) == attribute(r, rhs_key)).map(move |r| (attribute(l, lhs_output), attribute(r, rhs_output))))
+                .collect()
+        } else if operator.starts_with("HashJoinTuple[") {
+            // (lhs.source, lhs.target) = (rhs.<first>, rhs.<second>) -> (lhs.<first>, lhs.<second>)
+            let (predicate, projection) = parameters.split_once(" -> ").unwrap();
+            let rhs_tuple = predicate.split_once(" = ").unwrap().1;
+            let rhs_reverse = rhs_tuple.starts_with(&format!("({}.target", inputs[1]));
+            let output_reverse = projection.starts_with(&format!("({}.target", inputs[0]));
+            let rhs: BTreeSet<(u64, u64)> = input(inputs[1]).into_iter().map(|(s, t)| if rhs_reverse { (t, s) } else { (s, t) }).collect();
+            input(inputs[0]).into_iter()
+                .filter(|pair| rhs.contains(pair))
+                .map(|(s, t)| if output_reverse { (t, s) } else { (s, t) })
+                .collect()
+        } else if operator.starts_with("Filter[") {
+            assert_eq!(parameters, "source != target", "unknown filter in {}", line);
+            input(inputs[0]).into_iter().filter(|(s, t)| s != t).collect()
+        } else if operator.starts_with("Union(") {
+            inputs.iter()
+                .flat_map(|name| match name.strip_prefix("Reverse(") {
+                    Some(name) => input(name.trim_end_matches(')')).into_iter().map(|(s, t)| (t, s)).collect::<Vec<_>>(),
+                    None => input(*name).into_iter().collect(),
+                })
+                .collect()
+        } else {
+            panic!("unknown operator in {}", line);
+        };
+
+        relations.insert(label.to_string(), output.clone());
+        last = output;
+    }
+    last
+}
+
+/// plans described by `explain` produce the results of the plans built by `by_name` in each window
+#[test]
+fn explain_matches_plans() {
+    let edges = read_edges(GRAPH_FILE, true, true);
+    let queries: &[(&str, &[&str])] = &[
+        ("join", &["a", "b"]), ("query1", &["a"]), ("query2", &["a", "b"]), ("query2-a", &["a", "b"]),
+        ("query3", &["a", "b", "c"]), ("query3-a", &["a", "b", "c"]), ("query4", &["a", "b", "c"]), ("query4-a", &["a", "b", "c"]),
+        ("query4-pc1", &["a", "b", "c"]), ("query4-pc2", &["a", "b", "c"]), ("query5", &["a", "b", "c"]), ("query6", &["a", "b", "c"]),
+        ("query6-cq", &["a", "b", "c"]), ("query7", &["a", "b", "c"]), ("query7-cq", &["a", "b", "c"]), ("query8", &["a"]),
+        ("ldbc-ic1", &["a"]), ("ldbc-ic2", &["a", "b"]), ("ldbc-ic9", &["a", "b"]), ("ldbc-ic13", &["a"]),
+        ("ldbc-is2", &["a", "b"]), ("ldbc-is6", &["a", "b"]), ("ldbc-is7", &["a", "b"]),
+    ];
+
+    for (query_name, labels) in queries {
+        let plan = SGAQueryLibrary::explain(query_name, &predicates(labels)).unwrap();
+        let results = run_sga_query(edges.clone(), GRAPH_WINDOW, SLIDE, query_name, predicates(labels));
+        assert!(!results.is_empty());
+        for (window_time, pairs) in results.iter() {
+            let window_edges: Vec<(u64, &str, u64)> = edges.iter()
+                .filter(|sge| labels.contains(&sge.label.as_str()))
+                .filter(|sge| sge.timestamp <= *window_time && sge.timestamp + GRAPH_WINDOW > *window_time)
+                .map(|sge| (sge.source, sge.label.as_str(), sge.target))
+                .collect();
+            assert_eq!(pairs, &evaluate_plan(&plan, &window_edges), "explain of {} does not match its plan at {}:\n{}", query_name, window_time, plan);
+        }
+    }
+}
+
+/// the DD path pattern of any # of predicates matches the RPQ of their concatenation in each window
+#[cfg(feature = "dd")]
+#[test]
+fn dd_path_of_four_predicates() {
+    let edges = read_edges(GRAPH_FILE, true, true);
+    let results = run_dd_query(edges.clone(), GRAPH_WINDOW, SLIDE, "path", predicates(&["a", "b", "c", "a"]));
+
+    assert!(results.values().any(|pairs| !pairs.is_empty()));
+    for (window_time, pairs) in results.iter() {
+        let window_edges = edges.iter()
+            .filter(|sge| sge.timestamp <= *window_time && sge.timestamp + GRAPH_WINDOW > *window_time)
+            .map(|sge| (sge.source, sge.label.as_str(), sge.target));
+        let expected_pairs = evaluate_rpq(window_edges, "a/b/c/a").into_iter().collect();
+        assert_eq!(pairs, &expected_pairs, "DD results of path do not match at {}", window_time);
+    }
+}
+
+/// the automaton-based DD RPQ matches the RPQ over each window
+#[cfg(feature = "dd")]
+#[test]
+fn dd_rpq_over_automaton() {
+    let edges = read_edges(GRAPH_FILE, true, true);
+    let results = run_dd_query(edges.clone(), GRAPH_WINDOW, SLIDE, "query3-a", predicates(&["a", "b", "c"]));
+
+    assert!(results.values().any(|pairs| !pairs.is_empty()));
+    for (window_time, pairs) in results.iter() {
+        let window_edges = edges.iter()
+            .filter(|sge| sge.timestamp <= *window_time && sge.timestamp + GRAPH_WINDOW > *window_time)
+            .map(|sge| (sge.source, sge.label.as_str(), sge.target));
+        let expected_pairs = evaluate_rpq(window_edges, "a/b*/c*").into_iter().collect();
+        assert_eq!(pairs, &expected_pairs, "DD results of query3-a do not match at {}", window_time);
+    }
+}
+
+/// results of windows do not depend on where joins are deduplicated
+#[cfg(feature = "dd")]
+#[test]
+fn dd_distinct_granularity() {
+    let edges = read_edges(GRAPH_FILE, true, true);
+    for query_name in ["query3", "query5"].iter() {
+        let expected_results = run_dd_query(edges.clone(), GRAPH_WINDOW, SLIDE, query_name, predicates(&["a", "b", "c"]));
+        for distinct in [DistinctGranularity::FinalOnly, DistinctGranularity::Disabled].iter() {
+            let results = run_dd_query_with_distinct(edges.clone(), GRAPH_WINDOW, SLIDE, query_name, predicates(&["a", "b", "c"]), *distinct);
+            assert_eq!(results, expected_results, "DD results of {} with {:?} do not match", query_name, distinct);
+        }
+    }
+}
+
+/// queries constructed with the same shared arrangements arrange the edges of a label once
+#[cfg(feature = "dd")]
+#[test]
+fn dd_queries_share_arrangements() {
+    use sgraffito_query::dd::{SharedArrangements, WindowedInput};
+    use sgraffito_query::query::query_library::DDQueryLibrary;
+
+    let num_arrangements = timely::execute(timely::Configuration::Thread, |worker| {
+        let mut input = WindowedInput::sliding(GRAPH_WINDOW);
+        worker.dataflow::<u64, _, _>(|scope| {
+            let mut shared = SharedArrangements::new(&input.to_collection(scope), &predicates(&["a", "b"]));
+            DDQueryLibrary::by_name_shared("join", &mut shared, predicates(&["a", "b"])).unwrap();
+            DDQueryLibrary::by_name_shared("query2", &mut shared, predicates(&["a", "b"])).unwrap();
+            shared.num_arrangements()
+        })
+    }).unwrap().join().into_iter().map(|result| result.unwrap()).collect::<Vec<_>>();
+
+    // both queries join paths with the edges of `b` arranged by their source
+    assert_eq!(num_arrangements, vec![1]);
+}
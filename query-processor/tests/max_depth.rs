@@ -0,0 +1,73 @@
+//! Tests that the RPQ operator bounds the length of paths by the max depth
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use timely::Configuration;
+use timely::dataflow::{InputHandle, ProbeHandle};
+use timely::dataflow::operators::{Input, Inspect, Probe};
+
+use sgraffito_query::input::{SGE, StreamingGraphEdge};
+use sgraffito_query::operator::rpq::RegularPathQuery;
+use sgraffito_query::operator::window::SlidingWindow;
+
+const WINDOW: u64 = 100;
+
+/// runs `a+` over a chain 1 -> 2 -> 3 -> 4 -> 5, returning endpoints of results
+fn run(max_depth: Option<usize>) -> BTreeSet<(u64, u64)> {
+    // edges arrive in reverse order, so that paths are completed by their first edge
+    run_edges((1..5).rev().map(|source| (source, source + 1)).collect(), max_depth)
+}
+
+/// runs `a+` over the given edges, where each edge arrives at its own timestamp, returning endpoints of results
+fn run_edges(edges: Vec<(u64, u64)>, max_depth: Option<usize>) -> BTreeSet<(u64, u64)> {
+    let guards = timely::execute(Configuration::Thread, move |worker| {
+        let mut input: InputHandle<u64, StreamingGraphEdge> = InputHandle::new();
+        let mut probe = ProbeHandle::new();
+        let results = Rc::new(RefCell::new(BTreeSet::new()));
+
+        let result_sink = results.clone();
+        worker.dataflow::<u64, _, _>(|scope| {
+            let window = scope.input_from(&mut input).sliding_window(WINDOW);
+            let results = match max_depth {
+                Some(max_depth) => window.regular_path_query_with_max_depth("a+", "rpq".to_string(), max_depth),
+                None => window.regular_path_query("a+", "rpq".to_string()),
+            };
+            results
+                .inspect(move |sgt| { result_sink.borrow_mut().insert((sgt.source, sgt.target)); })
+                .probe_with(&mut probe);
+        });
+
+        for (timestamp, (source, target)) in edges.iter().enumerate() {
+            input.advance_to(timestamp as u64 + 1);
+            input.send(StreamingGraphEdge::new(*source, *target, "a".to_string(), timestamp as u64 + 1));
+            worker.step();
+        }
+        input.close();
+        worker.step_while(|| !probe.done());
+
+        results.replace(BTreeSet::new())
+    }).expect("Cannot execute the dataflow");
+
+    guards.join().into_iter().next().unwrap().expect("Dataflow has failed")
+}
+
+#[test]
+fn bounded_paths() {
+    let all_paths = run(None);
+    assert_eq!(all_paths.len(), 10);
+
+    let bounded_paths = run(Some(2));
+    let expected: BTreeSet<(u64, u64)> = all_paths.into_iter().filter(|(source, target)| target - source <= 2).collect();
+    assert_eq!(bounded_paths, expected);
+    assert_eq!(run(Some(1)).len(), 4);
+}
+
+/// descendants of a node that moves onto a shorter path can be extended up to the max depth
+#[test]
+fn shorter_path_after_longer_path() {
+    // 1 -> 2 -> 3 -> 4 is built first, then the shortcut 1 -> 3 re-parents 3 without extending the older edge to 4
+    let results = run_edges(vec![(3, 4), (2, 3), (1, 2), (1, 3), (4, 5)], Some(3));
+    assert!(results.contains(&(1, 4)));
+    assert!(results.contains(&(1, 5)), "1 -> 3 -> 4 -> 5 is within the max depth");
+}
@@ -0,0 +1,45 @@
+//! Tests that multiple input streams are read with aligned frontiers
+use sgraffito_query::input::{SGE, StreamingGraphEdge};
+use sgraffito_query::input::multi_stream::{AlignedStreams, FrontierAligner};
+
+fn stream(label: &str, timestamps: &[u64]) -> Vec<StreamingGraphEdge> {
+    timestamps.iter().map(|ts| StreamingGraphEdge::new(1, 2, label.to_string(), *ts)).collect()
+}
+
+#[test]
+fn lagging_stream_is_read_first() {
+    let streams = vec![stream("a", &[1, 2, 10, 11]), stream("b", &[3, 4, 5])];
+    let mut aligned = AlignedStreams::new(streams.into_iter().map(|edges| edges.into_iter()).collect());
+
+    let mut order = Vec::new();
+    while let Some((stream, sge)) = aligned.next() {
+        order.push((stream, sge.timestamp, aligned.aligner().aligned_frontier()));
+    }
+
+    assert_eq!(order, vec![
+        (0, 1, Some(0)),
+        (1, 3, Some(1)),
+        (0, 2, Some(2)),
+        (0, 10, Some(3)),
+        (1, 4, Some(4)),
+        (1, 5, Some(5)),
+        // stream b is exhausted and no longer holds back the frontier
+        (0, 11, Some(11)),
+    ]);
+    assert_eq!(aligned.aligner().aligned_frontier(), None);
+}
+
+#[test]
+fn watermarks_never_regress() {
+    let mut aligner = FrontierAligner::new(2);
+    aligner.advance(0, 5);
+    aligner.advance(0, 3);
+    aligner.advance(1, 4);
+
+    assert_eq!(aligner.watermark(0), Some(5));
+    assert_eq!(aligner.aligned_frontier(), Some(4));
+    assert_eq!(aligner.lagging_stream(), Some(1));
+
+    aligner.close(1);
+    assert_eq!(aligner.aligned_frontier(), Some(5));
+}
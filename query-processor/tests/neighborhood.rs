@@ -0,0 +1,47 @@
+//! Tests that the k-hop neighborhood contains the edges within k hops of the seeds with the interval of their longest-lived path
+use std::collections::HashSet;
+
+use sgraffito_query::operator::neighborhood::KHopNeighborhood;
+use sgraffito_query::test_support::{run_operator, sgt};
+
+#[test]
+fn edges_within_k_hops_of_seeds() {
+    let inputs = vec![
+        (1, sgt(1, 2, "a", 1, 10)),
+        // labels outside the set are not traversed
+        (1, sgt(1, 5, "c", 1, 10)),
+        (2, sgt(2, 3, "b", 2, 6)),
+        // beyond k hops of the seed
+        (3, sgt(3, 4, "a", 3, 12)),
+        // a longer-lived path to 3 extends its outgoing edge
+        (4, sgt(1, 3, "a", 4, 14)),
+    ];
+    let seeds: HashSet<u64> = vec![1].into_iter().collect();
+    let labels: HashSet<String> = vec!["a".to_string(), "b".to_string()].into_iter().collect();
+    let outputs = run_operator(inputs, move |stream| stream.k_hop_neighborhood(seeds, labels, 2));
+
+    assert_eq!(outputs[&1], vec![sgt(1, 2, "a", 1, 10)]);
+    assert_eq!(outputs[&2], vec![sgt(2, 3, "b", 2, 6)]);
+    assert!(!outputs.contains_key(&3));
+    assert_eq!(outputs[&4], vec![sgt(1, 3, "a", 4, 14), sgt(3, 4, "a", 4, 12)]);
+}
+
+#[test]
+fn expired_paths_leave_the_neighborhood() {
+    let inputs = vec![
+        (1, sgt(1, 2, "a", 1, 5)),
+        (2, sgt(2, 3, "a", 2, 10)),
+        // the path to 2 has expired, so that 3 is not reached
+        (6, sgt(3, 4, "a", 6, 12)),
+        // a new path to 2 brings its outgoing edge back into the neighborhood
+        (7, sgt(1, 2, "a", 7, 20)),
+    ];
+    let seeds: HashSet<u64> = vec![1].into_iter().collect();
+    let labels: HashSet<String> = vec!["a".to_string()].into_iter().collect();
+    let outputs = run_operator(inputs, move |stream| stream.k_hop_neighborhood(seeds, labels, 2));
+
+    assert_eq!(outputs[&1], vec![sgt(1, 2, "a", 1, 5)]);
+    assert_eq!(outputs[&2], vec![sgt(2, 3, "a", 2, 5)]);
+    assert!(!outputs.contains_key(&6));
+    assert_eq!(outputs[&7], vec![sgt(1, 2, "a", 7, 20), sgt(2, 3, "a", 7, 10)]);
+}
@@ -0,0 +1,22 @@
+//! Tests that spanning trees expired in parallel by the delta pool produce the same results as sequential expiry
+#![cfg(feature = "parallel-delta")]
+use sgraffito_query::test_support::run_rpq;
+use sgraffito_query::input::{SGE, StreamingGraphEdge};
+use sgraffito_query::util::types::set_delta_threads;
+
+#[test]
+fn parallel_expiry_equals_sequential_expiry() {
+    // many trees over a dense graph, where each window expires several trees at once
+    let edges: Vec<StreamingGraphEdge> = (0..400u64)
+        .map(|i| StreamingGraphEdge::new((i * 7) % 40, (i * 13 + 1) % 40, if i % 3 == 0 { "b" } else { "a" }.to_string(), 1 + i / 4))
+        .collect();
+
+    set_delta_threads(None);
+    let sequential = run_rpq(edges.clone(), 10, 2, "a/b*");
+    set_delta_threads(Some(3));
+    let parallel = run_rpq(edges, 10, 2, "a/b*");
+    set_delta_threads(None);
+
+    assert!(sequential.values().any(|results| !results.is_empty()));
+    assert_eq!(parallel, sequential);
+}
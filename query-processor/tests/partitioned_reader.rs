@@ -0,0 +1,25 @@
+//! Tests that partitioned file readers read every edge of the input exactly once
+use sgraffito_query::input::{InputFileReader, LineFileReader, StreamingGraphEdge};
+
+const GRAPH_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/tiny-graph.txt");
+
+#[test]
+fn byte_ranges_cover_input() {
+    let edges: Vec<StreamingGraphEdge> = LineFileReader::open(GRAPH_FILE, true, true).unwrap().collect();
+
+    for peers in 1..=edges.len() + 2 {
+        let mut partitioned_edges = Vec::new();
+        for index in 0..peers {
+            let reader = LineFileReader::open_partition(GRAPH_FILE, true, true, index, peers).unwrap();
+            assert_eq!(reader.get_start_timestamp(), edges[0].timestamp);
+            partitioned_edges.extend(reader);
+        }
+
+        assert_eq!(partitioned_edges, edges, "partitions of {} workers do not cover the input", peers);
+    }
+}
+
+#[test]
+fn untimestamped_input_cannot_be_partitioned() {
+    assert!(LineFileReader::open_partition(GRAPH_FILE, false, true, 0, 2).is_err());
+}
@@ -0,0 +1,31 @@
+//! Tests that RPQs specialized for a known prefix start their paths only at upstream matches of the prefix
+use std::collections::BTreeMap;
+
+use sgraffito_query::operator::rpq::RegularPathQuery;
+use sgraffito_query::test_support::{run_operator, sgt};
+
+#[test]
+fn paths_start_after_the_anchor() {
+    let inputs = vec![
+        // edges of the prefix are not expanded, and paths do not start at `c` edges
+        (1, sgt(1, 2, "a", 1, 10)),
+        (1, sgt(2, 3, "b", 1, 10)),
+        (1, sgt(7, 3, "c", 1, 10)),
+        // an upstream match of `a/b` from 1 to 3
+        (2, sgt(1, 3, "ab", 2, 10)),
+        (3, sgt(3, 4, "c", 3, 10)),
+        (4, sgt(4, 5, "c", 4, 10)),
+    ];
+    let outputs = run_operator(inputs, |stream| stream.regular_path_query_after_prefix("a/b/c+", &["a", "b"], "ab", "q".to_string()));
+
+    let pairs: BTreeMap<u64, Vec<(u64, u64)>> = outputs.into_iter()
+        .map(|(epoch, results)| (epoch, results.into_iter().map(|result| (result.source, result.target)).collect()))
+        .collect();
+    assert_eq!(pairs, vec![(3, vec![(1, 4)]), (4, vec![(1, 5)])].into_iter().collect());
+}
+
+#[test]
+#[should_panic(expected = "has no match starting with")]
+fn prefixes_without_matches() {
+    run_operator(vec![(1, sgt(1, 2, "ab", 1, 10))], |stream| stream.regular_path_query_after_prefix("a/b/c+", &["c"], "ab", "q".to_string()));
+}
@@ -0,0 +1,38 @@
+//! Tests that tuples carry the ids of the base edges they are derived from in provenance mode
+use timely::dataflow::operators::Filter;
+
+use sgraffito_query::input::{SGE, SGT, StreamingGraphEdge};
+use sgraffito_query::input::tuple::{Annotations, Provenance, StreamingGraphTuple};
+use sgraffito_query::operator::hash_join::{HashJoinAttributePair, SymmetricHashJoin};
+use sgraffito_query::test_support::{run_operator, sgt};
+use sgraffito_query::util::types::{HalfOpenTimeInterval, set_provenance_mode};
+
+fn with_edge(tuple: StreamingGraphTuple, edge_id: u64) -> StreamingGraphTuple {
+    tuple.with_annotations(Annotations { provenance: Provenance::of_edge(edge_id), ..Annotations::default() })
+}
+
+#[test]
+fn joins_merge_edge_ids() {
+    let edge = StreamingGraphEdge::new(1, 2, "a".to_string(), 1);
+    let interval = HalfOpenTimeInterval::new(1, 10);
+    assert!(StreamingGraphTuple::from_edge(&edge, interval).provenance.is_empty());
+    set_provenance_mode(true);
+    assert_eq!(StreamingGraphTuple::from_edge(&edge, interval).provenance.ids(), &[edge.edge_id()]);
+    set_provenance_mode(false);
+
+    let inputs = vec![
+        (1, with_edge(sgt(1, 2, "a", 1, 10), 7)),
+        (2, with_edge(sgt(2, 3, "b", 2, 8), 3)),
+        (2, with_edge(sgt(3, 4, "c", 2, 8), 5)),
+    ];
+    let results: Vec<StreamingGraphTuple> = run_operator(inputs, |stream| {
+        let ab = stream.filter(|sgt| sgt.label == "a")
+            .hash_join(&stream.filter(|sgt| sgt.label == "b"), HashJoinAttributePair::TS, HashJoinAttributePair::ST, "ab".to_string());
+        ab.hash_join(&stream.filter(|sgt| sgt.label == "c"), HashJoinAttributePair::TS, HashJoinAttributePair::ST, "q".to_string())
+    }).into_iter().flat_map(|(_, tuples)| tuples).collect();
+
+    assert_eq!(results.len(), 1);
+    assert_eq!((results[0].source, results[0].target), (1, 4));
+    // ids of all base edges of the result, sorted
+    assert_eq!(results[0].provenance.ids(), &[3, 5, 7]);
+}
@@ -0,0 +1,20 @@
+//! Tests that counts of reachable targets follow the spanning trees of each source as results are added and expire
+use sgraffito_query::operator::rpq::RegularPathQuery;
+use sgraffito_query::test_support::{run_operator, sgt};
+use sgraffito_query::util::types::HalfOpenTimeInterval;
+
+#[test]
+fn counts_follow_expansion_and_expiry() {
+    let inputs = vec![
+        (1, sgt(1, 2, "a", 1, 10)),
+        (2, sgt(2, 3, "a", 2, 6)),
+        (6, sgt(7, 8, "a", 6, 20)),
+    ];
+    let outputs = run_operator(inputs, |stream| stream.reachable_target_counts("a+", "q".to_string()));
+
+    assert_eq!(outputs[&1], vec![(1, 1, HalfOpenTimeInterval::new(1, 10))]);
+    // 1 reaches 3 through the shorter-lived edge, which bounds its count
+    assert_eq!(outputs[&2], vec![(1, 2, HalfOpenTimeInterval::new(2, 6)), (2, 1, HalfOpenTimeInterval::new(2, 6))]);
+    // 3 has expired, so that 1 reaches only 2, and the tree of 2 is removed without a count
+    assert_eq!(outputs[&6], vec![(1, 1, HalfOpenTimeInterval::new(6, 10)), (7, 1, HalfOpenTimeInterval::new(6, 20))]);
+}
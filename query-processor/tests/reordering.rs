@@ -0,0 +1,79 @@
+//! Tests that the reordering buffer of the RPQ operator tolerates out-of-order inputs within its slack
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::rc::Rc;
+
+use timely::Configuration;
+use timely::dataflow::{InputHandle, ProbeHandle};
+use timely::dataflow::operators::{Filter, Input, Inspect, Probe};
+
+use sgraffito_query::harness::read_edges;
+use sgraffito_query::input::StreamingGraphEdge;
+use sgraffito_query::operator::rpq::RegularPathQuery;
+use sgraffito_query::operator::window::SlidingWindow;
+use sgraffito_query::util::types::{HalfOpenTimeInterval, reordering_slack, set_reordering_slack};
+
+const GRAPH_FILE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/tiny-graph.txt");
+const WINDOW: u64 = 8;
+const SLACK: u64 = 2;
+
+/// runs `a*` over the given edges with a reordering buffer of the given slack, where each edge is sent at the max timestamp seen so far
+fn run(edges: Vec<StreamingGraphEdge>, slack: u64) -> BTreeSet<(u64, u64, HalfOpenTimeInterval)> {
+    let guards = timely::execute(Configuration::Thread, move |worker| {
+        let mut input: InputHandle<u64, StreamingGraphEdge> = InputHandle::new();
+        let mut probe = ProbeHandle::new();
+        let results = Rc::new(RefCell::new(BTreeSet::new()));
+
+        let result_sink = results.clone();
+        worker.dataflow::<u64, _, _>(|scope| {
+            scope.input_from(&mut input)
+                .sliding_window(WINDOW)
+                .filter(|sgt| sgt.label == "a")
+                .regular_path_query_with_slack("a*", "rpq".to_string(), slack)
+                .inspect(move |sgt| { result_sink.borrow_mut().insert((sgt.source, sgt.target, sgt.interval)); })
+                .probe_with(&mut probe);
+        });
+
+        for sge in edges.iter().cloned() {
+            if sge.timestamp > *input.time() {
+                input.advance_to(sge.timestamp);
+            }
+            input.send(sge);
+            worker.step();
+        }
+        input.close();
+        worker.step_while(|| !probe.done());
+
+        results.replace(BTreeSet::new())
+    }).expect("Cannot execute the dataflow");
+
+    guards.join().into_iter().next().unwrap().expect("Dataflow has failed")
+}
+
+#[test]
+fn disorder_within_slack() {
+    let edges = read_edges(GRAPH_FILE, true, true);
+
+    // swap adjacent edges, so that each edge is at most one timestamp late
+    let mut shuffled_edges = edges.clone();
+    for pair in shuffled_edges.chunks_mut(2) {
+        pair.reverse();
+    }
+
+    let ordered_results = run(edges, SLACK);
+    assert!(!ordered_results.is_empty());
+    assert_eq!(ordered_results, run(shuffled_edges, SLACK));
+}
+
+#[test]
+fn max_slack() {
+    // the max slack is a valid slack rather than a sentinel of a disabled buffer
+    set_reordering_slack(Some(u64::MAX));
+    assert_eq!(reordering_slack(), Some(u64::MAX));
+    set_reordering_slack(None);
+    assert_eq!(reordering_slack(), None);
+
+    // deadlines saturate, so that every tuple is processed once the input is closed
+    let edges = read_edges(GRAPH_FILE, true, true);
+    assert_eq!(run(edges.clone(), SLACK), run(edges, u64::MAX));
+}
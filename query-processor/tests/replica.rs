@@ -0,0 +1,66 @@
+//! Tests that replicas load state files of RPQ operators from anomaly dumps and evaluate RPQs over the labels of the dumped query
+use std::fs;
+
+use sgraffito_query::replica::{Replica, TreeSummary};
+
+const STATE: &str = "# labels: 2 labels
+a
+b
+# graph: 3 vertices, 3 edges
+1 a 2 [0, 100)
+2 b 3 [50, 150)
+3 b 4 [120, 220)
+# delta: 1 spanning trees, 3 nodes
+tree 1: 3 nodes, min expiry 100
+# stash: 1 event times, 2 tuples
+130: 2 tuples
+";
+
+#[test]
+fn replica_serves_captured_state() {
+    let path = std::env::temp_dir().join(format!("sgraffito-replica-{}.txt", std::process::id()));
+    fs::write(&path, STATE).unwrap();
+    let replica = Replica::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(replica.edges().len(), 3);
+    assert_eq!(replica.trees(), &[TreeSummary { root: 1, nodes: 3, min_expiry: 100 }]);
+
+    let view = replica.evaluate("a/b*", None).unwrap();
+    assert_eq!(view.pairs().collect::<Vec<_>>(), vec![(1, 2), (1, 3), (1, 4)]);
+    assert_eq!(view.targets(1), &[2, 3, 4]);
+    assert!(view.targets(2).is_empty());
+
+    // only edges valid at the given time are evaluated
+    let view = replica.evaluate("a/b*", Some(60)).unwrap();
+    assert_eq!(view.pairs().collect::<Vec<_>>(), vec![(1, 2), (1, 3)]);
+    assert!(!view.contains(1, 4));
+    assert!(replica.evaluate("a/", None).is_err());
+
+    // the graph has no edges of other labels, so RPQs over them would miss results
+    assert!(replica.evaluate("a/c*", None).is_err());
+    assert!(replica.evaluate("b", None).is_ok());
+}
+
+#[test]
+fn replicas_of_all_labels() {
+    let path = std::env::temp_dir().join(format!("sgraffito-replica-all-{}.txt", std::process::id()));
+    fs::write(&path, "# labels: all\n# graph: 2 vertices, 1 edges\n1 c 2 [0, 100)\n").unwrap();
+    let replica = Replica::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(replica.labels(), None);
+    assert_eq!(replica.evaluate("c/d*", None).unwrap().pairs().collect::<Vec<_>>(), vec![(1, 2)]);
+}
+
+#[test]
+fn invalid_state_files() {
+    let path = std::env::temp_dir().join(format!("sgraffito-replica-invalid-{}.txt", std::process::id()));
+    fs::write(&path, "# labels: 1 labels\na\n# graph: 1 vertices, 1 edges\n1 a two [0, 100)\n").unwrap();
+    assert!(Replica::load(&path).is_err());
+    // state files without the labels of the dumped query are rejected
+    fs::write(&path, "# graph: 2 vertices, 1 edges\n1 a 2 [0, 100)\n").unwrap();
+    assert!(Replica::load(&path).is_err());
+    fs::remove_file(&path).unwrap();
+    assert!(Replica::load(&path).is_err());
+}
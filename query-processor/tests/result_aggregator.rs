@@ -0,0 +1,73 @@
+//! Tests that `scripts/result-aggregator.py` reads the metric files that `CSVBuilder` reports
+use std::collections::HashMap;
+use std::process::Command;
+use std::time::Duration;
+
+use metrics_runtime::Receiver;
+
+use sgraffito_query::util::metrics::{QUERY_LABEL, WORKER_LABEL};
+use sgraffito_query::util::metrics::csv_builder::CSVBuilder;
+use sgraffito_query::util::metrics::csv_exporter::CSVExporter;
+use sgraffito_query::util::phase::{PHASE_LABEL, RunPhase};
+
+#[test]
+fn aggregates_metrics_of_all_workers_in_the_measurement_phase() {
+    let results = std::env::temp_dir().join(format!("sgraffito-aggregator-{}", std::process::id()));
+    // reporting folder of a run as the test runner names it, i.e., `<exec>#<query>#<binding>#<window>#<slide>#<threads>`
+    let run = results.join("sga#query1#0#10#5#2");
+
+    let receiver = Receiver::builder().build().expect("failed to create receiver");
+    let mut exporter = CSVExporter::new(receiver.controller(), CSVBuilder::default(), run.to_str().unwrap(), Duration::from_secs(5));
+    for worker in 0..2u64 {
+        let mut sink = receiver.sink();
+        sink.add_default_labels(vec![(WORKER_LABEL, worker.to_string()), (QUERY_LABEL, "query1".to_string())]);
+        // slides of the warm-up phase are reported in their own files, which are not aggregated
+        let warm_up = [(PHASE_LABEL, RunPhase::WarmUp.as_str())];
+        sink.record_value_with_labels("batch-size", 1000, &warm_up[..]);
+        sink.record_value_with_labels("batch-latency", 100_000, &warm_up[..]);
+
+        let measurement = [(PHASE_LABEL, RunPhase::Measurement.as_str())];
+        for _slide in 0..4 {
+            sink.record_value_with_labels("batch-size", 10 * (worker + 1), &measurement[..]);
+            sink.record_value_with_labels("total-size", 100, &measurement[..]);
+            sink.record_value_with_labels("batch-latency", 1000 * (worker + 1), &measurement[..]);
+            sink.record_value_with_labels("total-latency", 5000, &measurement[..]);
+        }
+        sink.record_value("total-time", 8);
+    }
+    exporter.turn();
+    // the test runner samples the memory of the process
+    std::fs::write(run.join("memory.csv"), "max\n4096\n").unwrap();
+
+    let aggregated_file = results.join("aggregated.csv");
+    let script = concat!(env!("CARGO_MANIFEST_DIR"), "/scripts/result-aggregator.py");
+    match Command::new("python3").arg(script).arg(&results).arg(&aggregated_file).status() {
+        Ok(status) => assert!(status.success(), "Aggregator failed with {}", status),
+        Err(e) => {
+            eprintln!("Skipping the aggregator as python3 cannot be run: {}", e);
+            std::fs::remove_dir_all(&results).unwrap();
+            return;
+        }
+    }
+
+    let aggregated = std::fs::read_to_string(&aggregated_file).unwrap();
+    let mut lines = aggregated.lines();
+    let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+    let rows: Vec<HashMap<&str, &str>> = lines.map(|line| header.iter().cloned().zip(line.split(',')).collect()).collect();
+    assert_eq!(rows.len(), 1, "Run is not aggregated: {}", aggregated);
+
+    let row = &rows[0];
+    assert_eq!(row["query"], "query1");
+    assert_eq!(row["slide-count"], "4");
+    // sizes of workers add up, and latencies are those of the slowest worker
+    assert_eq!(row["processed-size-mean"], "30");
+    assert_eq!(row["total-size-mean"], "200");
+    assert_eq!(row["processed-mean"], "2000");
+    assert_eq!(row["processed-p99"], "2000");
+    assert_eq!(row["total-p99"], "5000");
+    assert_eq!(row["total-time"], "8");
+    assert_eq!(row["throughput"], "15.0");
+    assert_eq!(row["memory"], "4096");
+
+    std::fs::remove_dir_all(&results).unwrap();
+}
@@ -0,0 +1,31 @@
+use std::collections::BTreeMap;
+
+use sgraffito_query::diff::{diff_files, diff_outputs, write_window_outputs, WindowOutputs};
+use sgraffito_query::util::types::HalfOpenTimeInterval;
+
+#[test]
+fn diff_reports_missing_extra_and_interval_mismatches() {
+    let mut expected = WindowOutputs::new();
+    expected.insert(10, vec![((1, 2), Some(HalfOpenTimeInterval::new(3, 13))), ((1, 3), None)].into_iter().collect());
+    expected.insert(20, vec![((2, 3), Some(HalfOpenTimeInterval::new(15, 25)))].into_iter().collect());
+
+    let mut actual = WindowOutputs::new();
+    actual.insert(10, vec![((1, 2), Some(HalfOpenTimeInterval::new(4, 13))), ((1, 3), Some(HalfOpenTimeInterval::new(5, 15))), ((4, 5), None)].into_iter().collect());
+    actual.insert(20, vec![((2, 3), Some(HalfOpenTimeInterval::new(15, 25)))].into_iter().collect());
+    actual.insert(30, BTreeMap::new());
+
+    let diff = diff_outputs(&expected, &actual);
+    assert_eq!(diff.summary.windows, 3);
+    assert_eq!(diff.summary.differing_windows, 1);
+    assert_eq!(diff.summary.matching, 2);
+    assert_eq!(diff.summary.mismatches(), 2);
+    assert_eq!(diff.windows[0].extra, vec![(4, 5)]);
+    assert_eq!(diff.windows[0].interval_mismatches[0].actual, HalfOpenTimeInterval::new(4, 13));
+
+    // results survive a round trip through result files
+    let directory = std::env::temp_dir();
+    let expected_file = directory.join(format!("sgraffito-diff-expected-{}.csv", std::process::id())).to_string_lossy().into_owned();
+    write_window_outputs(&expected_file, &expected).unwrap();
+    assert!(diff_files(&expected_file, &expected_file).unwrap().is_identical());
+    std::fs::remove_file(&expected_file).unwrap();
+}
@@ -0,0 +1,39 @@
+//! Tests that structured result labels keep the string form of labels and namespace intermediate results by their query
+use timely::dataflow::operators::Concat;
+
+use sgraffito_query::input::tuple::ResultLabel;
+use sgraffito_query::operator::rpq::RegularPathQuery;
+use sgraffito_query::test_support::{run_operator, sgt};
+
+#[test]
+fn labels_round_trip_their_string_form() {
+    assert_eq!(ResultLabel::parse("q7"), ResultLabel::query("q7"));
+    assert_eq!(ResultLabel::parse("q7.cq"), ResultLabel::stage("q7", "cq"));
+    // the stage follows the first separator
+    assert_eq!(ResultLabel::parse("q7.cq.1"), ResultLabel::stage("q7", "cq.1"));
+
+    for label in &["q7", "q7.cq", "q7.cq.1", "free form"] {
+        assert_eq!(ResultLabel::parse(label).to_string(), *label);
+    }
+    assert_eq!(String::from(ResultLabel::stage("q7", "j1")), "q7.j1");
+    assert!(ResultLabel::parse("q7").is_final());
+    assert!(!ResultLabel::parse("q7.cq").is_final());
+}
+
+#[test]
+fn intermediate_results_of_queries_are_kept_apart() {
+    let inputs = vec![(1, sgt(1, 2, "a", 1, 10))];
+    // both queries have a `cq` stage, whose results are demultiplexed by their query
+    let outputs = run_operator(inputs, |stream| {
+        let q1 = stream.regular_path_query("a", ResultLabel::stage("q1", "cq").to_string());
+        let q2 = stream.regular_path_query("a+", ResultLabel::stage("q2", "cq").to_string());
+        q1.concat(&q2)
+    });
+
+    let mut queries: Vec<(String, Option<String>)> = outputs[&1].iter().map(|result| {
+        let label = result.result_label();
+        (label.query_id, label.stage)
+    }).collect();
+    queries.sort();
+    assert_eq!(queries, vec![("q1".to_string(), Some("cq".to_string())), ("q2".to_string(), Some("cq".to_string()))]);
+}
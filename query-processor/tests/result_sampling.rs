@@ -0,0 +1,46 @@
+//! Tests that the sampling sink prints the # of results of each window with a bounded sample of them
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::rc::Rc;
+
+use sgraffito_query::input::VertexDictionary;
+use sgraffito_query::sink::ResultSink;
+use sgraffito_query::sink::sampling::SamplingSink;
+use sgraffito_query::test_support::sgt;
+
+/// output that is shared with the test, as the sink owns its output
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn windows_are_sampled() {
+    let output = SharedOutput::default();
+    let dictionary = VertexDictionary::new();
+    dictionary.insert(1, "alice");
+
+    let mut sink = SamplingSink::with_output(2, 10, dictionary, Box::new(output.clone()));
+    sink.write(1, &[sgt(1, 2, "q", 1, 10), sgt(2, 3, "q", 1, 10)]);
+    sink.write(2, &[sgt(3, 4, "q", 2, 10)]);
+    // a window is printed once results of a later window arrive
+    assert!(output.0.borrow().is_empty());
+    sink.write(11, &[sgt(1, 2, "q", 11, 20)]);
+    // and the last window once the sink is dropped
+    drop(sink);
+
+    let printed = String::from_utf8(output.0.borrow().clone()).unwrap();
+    let lines: Vec<&str> = printed.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert_eq!(lines[0], "window [0, 10): 3 results, showing 2");
+    assert_eq!(lines[3], "window [10, 20): 1 results, showing 1");
+    assert_eq!(lines[4], "  q  alice -> 2  [11, 20)");
+}
@@ -0,0 +1,29 @@
+//! Tests that result TTLs bound the advertisement of results and their retractions
+use sgraffito_query::input::tuple::StreamingGraphTuple;
+use sgraffito_query::operator::ttl::ResultTTL;
+use sgraffito_query::test_support::{run_operator, sgt};
+
+fn retraction(source: u64, start: u64, end: u64) -> StreamingGraphTuple {
+    let mut sgt = sgt(source, source + 1, "q", start, end);
+    sgt.append = false;
+    sgt
+}
+
+#[test]
+fn ttl_clamps_results_and_retractions() {
+    let inputs = vec![
+        (2, sgt(1, 2, "q", 2, 20)),
+        (2, sgt(2, 3, "q", 2, 4)),
+        (5, retraction(1, 5, 20)),
+        (5, retraction(3, 5, 20)),
+        (8, retraction(2, 8, 20)),
+    ];
+    let outputs = run_operator(inputs, |stream| stream.result_ttl(5));
+
+    let mut appends = outputs[&2].clone();
+    appends.sort();
+    assert_eq!(appends, vec![sgt(1, 2, "q", 2, 7), sgt(2, 3, "q", 2, 4)]);
+    // the retraction of 1 ends with its advertisement, 3 is never advertised and 2 has already expired
+    assert_eq!(outputs[&5], vec![retraction(1, 5, 7)]);
+    assert!(!outputs.contains_key(&8));
+}
@@ -0,0 +1,50 @@
+//! Model-based tests that compare results of the RPQ operator with the naive snapshot evaluation of random streams and RPQs
+use sgraffito_query::input::GraphEdge;
+use sgraffito_query::operator::rpq::evaluate_rpq;
+use sgraffito_query::query::parser::RPQParser;
+use sgraffito_query::test_support::{run_lazy_rpq, run_rpq};
+use sgraffito_query::test_support::model::{expected_rpq_results, ModelGenerator, snapshot_rpq};
+
+const LABELS: [&str; 3] = ["a", "b", "c"];
+const WINDOW: u64 = 6;
+const SLIDE: u64 = 2;
+
+#[test]
+fn random_streams_and_queries() {
+    for seed in 0..40 {
+        let mut generator = ModelGenerator::new(seed);
+        let edges = generator.edges(30, 6, &LABELS, 2);
+        let query = generator.rpq(&LABELS, 3);
+
+        let expected_results = expected_rpq_results(&edges, WINDOW, SLIDE, &query);
+        let results = run_rpq(edges.clone(), WINDOW, SLIDE, &query);
+        assert_eq!(results, expected_results, "Results of {} do not match with seed {} over {:?}", query, seed, edges);
+    }
+}
+
+#[test]
+fn lazy_automata() {
+    for seed in 0..20 {
+        let mut generator = ModelGenerator::new(seed);
+        let edges = generator.edges(30, 6, &LABELS, 2);
+        let query = generator.rpq(&LABELS, 3);
+
+        let expected_results = expected_rpq_results(&edges, WINDOW, SLIDE, &query);
+        let results = run_lazy_rpq(edges.clone(), WINDOW, SLIDE, &query);
+        assert_eq!(results, expected_results, "Results of lazy {} do not match with seed {} over {:?}", query, seed, edges);
+    }
+}
+
+#[test]
+fn static_graphs() {
+    for seed in 0..20 {
+        let mut generator = ModelGenerator::new(seed);
+        let edges = generator.edges(20, 6, &LABELS, 0);
+        let query = generator.rpq(&LABELS, 3);
+
+        let automaton = RPQParser::new().parse_rpq(&query).unwrap();
+        let expected_results: Vec<_> = snapshot_rpq(&edges, &automaton).into_iter().collect();
+        let results = evaluate_rpq(edges.iter().map(|sge| (sge.get_source(), sge.get_label(), sge.get_target())), &query);
+        assert_eq!(results, expected_results, "Results of {} do not match with seed {} over {:?}", query, seed, edges);
+    }
+}
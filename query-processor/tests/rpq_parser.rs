@@ -0,0 +1,120 @@
+use sgraffito_query::harness::{read_edges, run_sga_query};
+use sgraffito_query::input::labels::{quote_label, split_fields};
+use sgraffito_query::query::automata::regex::RegexTree;
+use sgraffito_query::query::parser::RPQParser;
+
+fn label(label: &str) -> Box<RegexTree> {
+    Box::new(RegexTree::Label(label.to_string()))
+}
+
+#[test]
+fn nested_groups() {
+    let parser = RPQParser::new();
+
+    let tree = parser.parse_regex("((a|b)/c*)+").unwrap();
+    assert_eq!(tree, RegexTree::KleenePlus(Box::new(RegexTree::Concatenation(
+        Box::new(RegexTree::Alternation(label("a"), label("b"))),
+        Box::new(RegexTree::KleeneStar(label("c"))),
+    ))));
+
+    let dfa = parser.parse_rpq("((a|b)/c*)+").unwrap();
+    assert!(dfa.accept(vec!["a"]));
+    assert!(dfa.accept(vec!["b", "c", "c", "a"]));
+    assert!(!dfa.accept(vec!["c"]));
+    assert!(!dfa.accept(vec![]));
+
+    let dfa = parser.parse_rpq("(((a)+/(b|c))*)").unwrap();
+    assert!(dfa.accept(vec![]));
+    assert!(dfa.accept(vec!["a", "a", "c", "a", "b"]));
+    assert!(!dfa.accept(vec!["a", "a"]));
+}
+
+#[test]
+fn precedence() {
+    let parser = RPQParser::new();
+
+    // modifiers bind tighter than sequences, which bind tighter than alternations
+    assert_eq!(parser.parse_regex("a|b/c*").unwrap(), RegexTree::Alternation(
+        label("a"),
+        Box::new(RegexTree::Concatenation(label("b"), Box::new(RegexTree::KleeneStar(label("c"))))),
+    ));
+    assert_eq!(parser.parse_regex("(a|b)/c").unwrap(), RegexTree::Concatenation(
+        Box::new(RegexTree::Alternation(label("a"), label("b"))),
+        label("c"),
+    ));
+}
+
+#[test]
+fn invalid_paths() {
+    let parser = RPQParser::new();
+
+    assert!(parser.parse_regex("").is_err());
+    assert!(parser.parse_regex("()").is_err());
+    assert!(parser.parse_regex("(a|b").is_err());
+    assert!(parser.parse_regex("a//b").is_err());
+    assert!(parser.parse_regex("a?").is_err());
+    assert!(parser.parse_regex("^a/b").is_err());
+    assert!(parser.parse_regex("a^b").is_err());
+}
+
+#[test]
+fn iris_and_whitespace() {
+    let parser = RPQParser::new();
+
+    assert_eq!(parser.parse_regex("foaf:knows+").unwrap(), RegexTree::KleenePlus(label("foaf:knows")));
+    assert_eq!(parser.parse_regex("<http://xmlns.com/foaf/0.1/knows>").unwrap(), RegexTree::Label("http://xmlns.com/foaf/0.1/knows".to_string()));
+    assert_eq!(parser.parse_regex(" ( likes_post | replyOf )* / knows ").unwrap(), parser.parse_regex("(likes_post|replyOf)*/knows").unwrap());
+
+    let parser = RPQParser::new().case_insensitive(true);
+    assert_eq!(parser.parse_regex("Foaf:Knows").unwrap(), RegexTree::Label("foaf:knows".to_string()));
+}
+
+#[test]
+fn quoted_labels() {
+    let parser = RPQParser::new();
+
+    assert_eq!(split_fields("1 \"works at\" 2 10").unwrap(), vec!["1", "works at", "2", "10"]);
+    assert_eq!(split_fields("1 \"say \\\"hi\\\"\" 2").unwrap(), vec!["1", "say \"hi\"", "2"]);
+    assert!(split_fields("1 \"works at 2 10").is_err());
+
+    let text = "say \"hi\" \\ bye";
+    assert_eq!(split_fields(&format!("1 {} 2", quote_label(text))).unwrap()[1], text);
+    assert_eq!(parser.parse_regex(&format!("{}+/knows", quote_label(text))).unwrap(),
+        RegexTree::Concatenation(Box::new(RegexTree::KleenePlus(label(text))), label("knows")));
+    assert_eq!(quote_label("foaf:knows"), "foaf:knows");
+
+    // empty labels of input files are quoted as `""`, which RPQs accept as well
+    assert_eq!(split_fields(&format!("1 {} 2", quote_label(""))).unwrap(), vec!["1", "", "2"]);
+    assert_eq!(parser.parse_regex(&format!("{}/knows", quote_label(""))).unwrap(),
+        RegexTree::Concatenation(Box::new(label("")), label("knows")));
+}
+
+/// IRI labels of input files are read in the canonical form of RPQ predicates, and library queries quote their predicates
+#[test]
+fn labels_of_input_files_match_library_queries() {
+    let graph_file = std::env::temp_dir().join(format!("sgraffito-iri-labels-{}.txt", std::process::id()));
+    std::fs::write(&graph_file, "1 <http://xmlns.com/foaf/0.1/knows> 2 1\n2 \"works at\" 3 2\n3 \"works at\" 4 3\n1 a 2 4\n").unwrap();
+    let edges = read_edges(graph_file.to_str().unwrap(), true, true);
+    std::fs::remove_file(&graph_file).unwrap();
+    assert_eq!(edges[0].label, "http://xmlns.com/foaf/0.1/knows");
+
+    let results = run_sga_query(edges.clone(), 10, 1, "query1", vec!["http://xmlns.com/foaf/0.1/knows".to_string()]);
+    assert!(results.values().any(|pairs| pairs.contains(&(1, 2))));
+
+    let results = run_sga_query(edges, 10, 1, "ldbc-ic13", vec!["works at".to_string()]);
+    assert_eq!(results.get(&4).map(|pairs| pairs.iter().cloned().collect::<Vec<_>>()), Some(vec![(2, 3), (2, 4), (3, 4)]));
+}
+
+#[test]
+fn error_positions() {
+    let parser = RPQParser::new();
+
+    let error = parser.parse_regex("(a|)+").unwrap_err();
+    assert_eq!((error.line, error.column, error.token.as_deref()), (1, 4, Some(")")));
+
+    let error = parser.parse_regex("knows/a?").unwrap_err();
+    assert_eq!((error.line, error.column, error.token.as_deref()), (1, 8, Some("?")));
+
+    let error = parser.parse_regex("(knows").unwrap_err();
+    assert_eq!((error.column, error.token), (7, None));
+}
@@ -0,0 +1,88 @@
+//! Tests that final measurements are flushed on demand and that the summary of a run is written once at its end
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use metrics_runtime::Receiver;
+
+use sgraffito_query::util::metrics::csv_builder::CSVBuilder;
+use sgraffito_query::util::metrics::csv_exporter::CSVExporter;
+use sgraffito_query::util::metrics::summary::{RunSummary, SUMMARY_FILE};
+
+#[test]
+fn measurements_are_flushed_without_waiting_for_the_interval() {
+    let reporting_dir = std::env::temp_dir().join(format!("sgraffito-flush-now-{}", std::process::id()));
+
+    let receiver = Receiver::builder().build().expect("failed to create receiver");
+    let mut exporter = CSVExporter::new(receiver.controller(), CSVBuilder::default(), reporting_dir.to_str().unwrap(), Duration::from_secs(3600));
+    let mut sink = receiver.sink();
+    sink.update_gauge("vertices", 7);
+    exporter.flush_now();
+
+    let report = std::fs::read_to_string(reporting_dir.join("vertices.csv")).unwrap();
+    let lines: Vec<&str> = report.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], "timestamp,worker,query,vertices");
+    assert!(lines[1].ends_with(",,,7"));
+
+    std::fs::remove_dir_all(&reporting_dir).unwrap();
+}
+
+#[test]
+fn spawned_exporters_flush_on_demand_and_when_stopped() {
+    let reporting_dir = std::env::temp_dir().join(format!("sgraffito-spawned-exporter-{}", std::process::id()));
+
+    let receiver = Receiver::builder().build().expect("failed to create receiver");
+    let exporter = CSVExporter::new(receiver.controller(), CSVBuilder::default(), reporting_dir.to_str().unwrap(), Duration::from_secs(3600));
+    let exporter_thread = exporter.spawn();
+    let flusher = exporter_thread.flusher();
+
+    let mut sink = receiver.sink();
+    sink.update_gauge("edges", 3);
+    flusher.flush_now();
+    assert_eq!(std::fs::read_to_string(reporting_dir.join("edges.csv")).unwrap().lines().count(), 2);
+
+    // measurements recorded after the end of stream are written once the exporter stops
+    sink.update_gauge("edges", 5);
+    exporter_thread.stop();
+    let report = std::fs::read_to_string(reporting_dir.join("edges.csv")).unwrap();
+    assert!(report.lines().last().unwrap().ends_with(",,,5"));
+
+    std::fs::remove_dir_all(&reporting_dir).unwrap();
+}
+
+#[test]
+fn summary_consolidates_totals_of_workers() {
+    let reporting_dir = std::env::temp_dir().join(format!("sgraffito-summary-{}", std::process::id()));
+    std::fs::create_dir_all(&reporting_dir).unwrap();
+
+    // workers share the totals through clones of the summary
+    let summary = RunSummary::new();
+    let worker = summary.clone();
+    summary.add_edges(10);
+    worker.add_edges(20);
+    worker.add_results(4);
+    let state_sizes = |trees: u64| vec![("trees".to_string(), trees)].into_iter().collect::<BTreeMap<String, u64>>();
+    summary.observe_state(&state_sizes(5));
+    worker.observe_state(&state_sizes(3));
+    summary.write(&reporting_dir, Duration::from_secs(2)).unwrap();
+
+    let written = std::fs::read_to_string(reporting_dir.join(SUMMARY_FILE)).unwrap();
+    assert_eq!(written, "metric,value\nedges,30\ntotal-time-ms,2000\nthroughput,15.00\nresults,4\npeak-trees,5\n");
+
+    std::fs::remove_dir_all(&reporting_dir).unwrap();
+}
+
+#[test]
+fn results_are_summarized_only_if_they_are_counted() {
+    let reporting_dir = std::env::temp_dir().join(format!("sgraffito-summary-uncounted-{}", std::process::id()));
+    std::fs::create_dir_all(&reporting_dir).unwrap();
+
+    let summary = RunSummary::new();
+    summary.add_edges(1);
+    summary.write(&reporting_dir, Duration::from_secs(1)).unwrap();
+
+    let written = std::fs::read_to_string(reporting_dir.join(SUMMARY_FILE)).unwrap();
+    assert!(!written.contains("results"));
+
+    std::fs::remove_dir_all(&reporting_dir).unwrap();
+}
@@ -0,0 +1,40 @@
+//! Tests that RPQ and join operators drop or keep only results from a vertex to itself as their `SelfLoops` option tells
+use std::collections::BTreeSet;
+
+use timely::dataflow::operators::Filter;
+
+use sgraffito_query::operator::SelfLoops;
+use sgraffito_query::operator::hash_join::{HashJoinAttributePair, SymmetricHashJoin};
+use sgraffito_query::operator::rpq::RegularPathQuery;
+use sgraffito_query::test_support::{run_operator, sgt};
+
+/// (source, target) pairs of `a+` over the cycle 1 -> 2 -> 1
+fn rpq_pairs(self_loops: SelfLoops) -> BTreeSet<(u64, u64)> {
+    let inputs = vec![(1, sgt(1, 2, "a", 1, 10)), (2, sgt(2, 1, "a", 2, 10))];
+    run_operator(inputs, move |stream| stream.regular_path_query_with_loops("a+", "q".to_string(), self_loops))
+        .values().flatten().map(|result| (result.source, result.target)).collect()
+}
+
+/// (source, target) pairs of `a/b`, where the path of 1 returns to 1
+fn join_pairs(self_loops: SelfLoops) -> BTreeSet<(u64, u64)> {
+    let inputs = vec![(1, sgt(1, 2, "a", 1, 10)), (1, sgt(3, 2, "a", 1, 10)), (2, sgt(2, 1, "b", 2, 10))];
+    run_operator(inputs, move |stream| {
+        let lhs = stream.filter(|sgt| sgt.label == "a");
+        let rhs = stream.filter(|sgt| sgt.label == "b");
+        lhs.hash_join_with_loops(&rhs, HashJoinAttributePair::TS, HashJoinAttributePair::ST, "q".to_string(), self_loops)
+    }).values().flatten().map(|result| (result.source, result.target)).collect()
+}
+
+#[test]
+fn rpq_results_of_self_loops() {
+    assert_eq!(rpq_pairs(SelfLoops::Keep), vec![(1, 1), (1, 2), (2, 1), (2, 2)].into_iter().collect());
+    assert_eq!(rpq_pairs(SelfLoops::Drop), vec![(1, 2), (2, 1)].into_iter().collect());
+    assert_eq!(rpq_pairs(SelfLoops::Only), vec![(1, 1), (2, 2)].into_iter().collect());
+}
+
+#[test]
+fn join_results_of_self_loops() {
+    assert_eq!(join_pairs(SelfLoops::Keep), vec![(1, 1), (3, 1)].into_iter().collect());
+    assert_eq!(join_pairs(SelfLoops::Drop), vec![(3, 1)].into_iter().collect());
+    assert_eq!(join_pairs(SelfLoops::Only), vec![(1, 1)].into_iter().collect());
+}
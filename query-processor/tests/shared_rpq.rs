@@ -0,0 +1,53 @@
+//! Tests that queries evaluated together by containment have the same results as their separate evaluation
+use std::collections::BTreeMap;
+
+use timely::dataflow::operators::Concat;
+
+use sgraffito_query::input::tuple::StreamingGraphTuple;
+use sgraffito_query::operator::rpq::RegularPathQuery;
+use sgraffito_query::test_support::{run_operator, sgt};
+
+/// (label, source, target, expiry) of results per epoch, sorted within each epoch
+fn sorted_results(outputs: BTreeMap<u64, Vec<StreamingGraphTuple>>) -> BTreeMap<u64, Vec<(String, u64, u64, u64)>> {
+    outputs.into_iter().map(|(epoch, results)| {
+        let mut results: Vec<_> = results.into_iter().map(|result| (result.label, result.source, result.target, result.interval.end)).collect();
+        results.sort();
+        (epoch, results)
+    }).collect()
+}
+
+#[test]
+fn contained_queries_match_separate_evaluation() {
+    // the `b` loop at 2 reaches 2 both after `a/b` and after `a/b/b`, i.e., in final states of the product with different tags
+    let inputs = vec![
+        (1, sgt(1, 2, "a", 1, 10)),
+        (2, sgt(2, 2, "b", 2, 10)),
+        (3, sgt(2, 3, "b", 3, 10)),
+        (4, sgt(4, 5, "a", 4, 12)),
+        (5, sgt(5, 6, "b", 5, 12)),
+    ];
+
+    let shared = run_operator(inputs.clone(), |stream| stream.shared_regular_path_queries(&[("a/b*", "broad".to_string()), ("a/b", "narrow".to_string())]));
+    let separate = run_operator(inputs, |stream| {
+        let broad = stream.regular_path_query("a/b*", "broad".to_string());
+        let narrow = stream.regular_path_query("a/b", "narrow".to_string());
+        broad.concat(&narrow)
+    });
+
+    let separate = sorted_results(separate);
+    assert_eq!(separate[&2], vec![("narrow".to_string(), 1, 2, 10)]);
+    assert_eq!(sorted_results(shared), separate);
+}
+
+#[test]
+fn unrelated_queries_match_separate_evaluation() {
+    let inputs = vec![(1, sgt(1, 2, "a", 1, 10)), (2, sgt(2, 3, "b", 2, 10)), (3, sgt(3, 1, "c", 3, 10))];
+
+    let shared = run_operator(inputs.clone(), |stream| stream.shared_regular_path_queries(&[("a/b", "ab".to_string()), ("b/c", "bc".to_string())]));
+    let separate = run_operator(inputs, |stream| {
+        let ab = stream.regular_path_query("a/b", "ab".to_string());
+        let bc = stream.regular_path_query("b/c", "bc".to_string());
+        ab.concat(&bc)
+    });
+    assert_eq!(sorted_results(shared), sorted_results(separate));
+}
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use sgraffito_query::ingest::{ReplayDriver, ReplayHandler, Simulation, Slide};
+use sgraffito_query::input::{InputPartitioner, Partitioning, StreamingGraphEdge};
+use sgraffito_query::util::clock::{Clock, ManualClock};
+use sgraffito_query::util::phase::PhaseSchedule;
+
+/// records the virtual latency of each slide after processing it for the given time of the processing clock
+struct VirtualLatencies {
+    clock: Arc<dyn Clock>,
+    processing_clock: ManualClock,
+    processing_time: Duration,
+    latencies: Vec<(u64, Duration)>,
+}
+
+impl ReplayHandler for VirtualLatencies {
+    fn send(&mut self, _sge: StreamingGraphEdge) {}
+
+    fn slide(&mut self, slide: &Slide) {
+        self.processing_clock.advance(self.processing_time);
+        self.latencies.push((slide.time, self.clock.now() - slide.virtual_arrival.unwrap()));
+    }
+}
+
+fn replay(simulation: Simulation, processing_time: Duration) -> Vec<(u64, Duration)> {
+    let edges = (0..10).map(|i| StreamingGraphEdge::new(1, 2, "a".to_string(), 1 + i * 10));
+    let predicates = vec!["a".to_string()];
+
+    // processing time is measured with a manual clock, so that it is deterministic
+    let processing_clock = ManualClock::new();
+    let mut driver = ReplayDriver::new(1, 10, PhaseSchedule::new(1, 0, None, 0), &predicates, InputPartitioner::new(Partitioning::RoundRobin, 0, 1))
+        .with_clock(Arc::new(processing_clock.clone()))
+        .with_simulation(Some(simulation));
+    let mut handler = VirtualLatencies { clock: driver.clock(), processing_clock, processing_time, latencies: Vec::new() };
+    driver.run(edges, &mut handler);
+    handler.latencies
+}
+
+#[test]
+fn virtual_latency_follows_synthetic_delays() {
+    // slides are processed at their arrival without delays
+    let latencies = replay(Simulation::from_spec("1").unwrap(), Duration::default());
+    assert_eq!(latencies.len(), 9);
+    assert!(latencies.iter().all(|(_, latency)| *latency == Duration::default()));
+
+    // each slide of a single edge takes 15ms of virtual time, whereas slides arrive every 10ms
+    let latencies = replay(Simulation::from_spec("1,5,10000").unwrap(), Duration::default());
+    let expected: Vec<(u64, Duration)> = (1..10).map(|i| (1 + i * 10, Duration::from_millis(10 + i * 5))).collect();
+    assert_eq!(latencies, expected);
+}
+
+#[test]
+fn virtual_latency_includes_processing_time() {
+    // each slide takes 3ms of processing on top of its synthetic delay of 10ms, i.e., 13ms per slide arriving every 10ms
+    let latencies = replay(Simulation::from_spec("1,10").unwrap(), Duration::from_millis(3));
+    let expected: Vec<(u64, Duration)> = (1..10).map(|i| (1 + i * 10, Duration::from_millis(10 + i * 3))).collect();
+    assert_eq!(latencies, expected);
+}
+
+#[test]
+fn invalid_simulation_specs() {
+    assert_eq!(Simulation::from_spec("2,3").unwrap(), Simulation::new(Duration::from_millis(2)).with_delays(Duration::from_millis(3), Duration::default()));
+    assert!(Simulation::from_spec("").is_err());
+    assert!(Simulation::from_spec("1,2,3,4").is_err());
+    assert!(Simulation::from_spec("1,fast").is_err());
+}
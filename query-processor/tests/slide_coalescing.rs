@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use sgraffito_query::ingest::{ReplayDriver, ReplayHandler, Slide};
+use sgraffito_query::input::{InputPartitioner, Partitioning, StreamingGraphEdge};
+use sgraffito_query::util::clock::ManualClock;
+use sgraffito_query::util::phase::PhaseSchedule;
+
+/// handler whose first slide exceeds the budget of the driver
+#[derive(Default)]
+struct SlowStart {
+    slides: Vec<(u64, u64, u64)>,
+}
+
+impl ReplayHandler for SlowStart {
+    fn send(&mut self, _sge: StreamingGraphEdge) {}
+
+    fn slide(&mut self, slide: &Slide) {
+        if self.slides.is_empty() {
+            std::thread::sleep(Duration::from_millis(30));
+        }
+        self.slides.push((slide.time, slide.batch_size, slide.coalesced.unwrap()));
+    }
+}
+
+#[test]
+fn slides_are_coalesced_while_behind() {
+    let edges = (0..10).map(|i| StreamingGraphEdge::new(1, 2, "a".to_string(), 1 + i * 10));
+    let predicates = vec!["a".to_string()];
+
+    let mut handler = SlowStart::default();
+    ReplayDriver::new(1, 10, PhaseSchedule::new(1, 0, None, 0), &predicates, InputPartitioner::new(Partitioning::RoundRobin, 0, 1))
+        .with_slide_coalescing(Some(Duration::from_millis(10)))
+        .run(edges, &mut handler);
+
+    // the first slide exceeds its budget by at least 2 slides, which are processed with the next one
+    assert_eq!(handler.slides[0], (11, 1, 0));
+    assert!(handler.slides[1].2 >= 2);
+    assert_eq!(handler.slides[1].1, handler.slides[1].2 + 1);
+    // every slide is processed, either on its own or coalesced
+    assert_eq!(handler.slides.iter().map(|(_, _, coalesced)| coalesced + 1).sum::<u64>(), 9);
+    assert_eq!(handler.slides.last().unwrap().0, 91);
+}
+
+/// handler whose first slide takes 25ms of a manual clock
+struct SimulatedStart {
+    clock: ManualClock,
+    slides: Vec<(u64, u64, u64)>,
+}
+
+impl ReplayHandler for SimulatedStart {
+    fn send(&mut self, _sge: StreamingGraphEdge) {}
+
+    fn slide(&mut self, slide: &Slide) {
+        if self.slides.is_empty() {
+            self.clock.advance(Duration::from_millis(25));
+        }
+        self.slides.push((slide.time, slide.batch_size, slide.coalesced.unwrap()));
+    }
+}
+
+#[test]
+fn slides_are_coalesced_with_manual_clock() {
+    let edges = (0..10).map(|i| StreamingGraphEdge::new(1, 2, "a".to_string(), 1 + i * 10));
+    let predicates = vec!["a".to_string()];
+
+    let clock = ManualClock::new();
+    let mut handler = SimulatedStart { clock: clock.clone(), slides: Vec::new() };
+    ReplayDriver::new(1, 10, PhaseSchedule::new(1, 0, None, 0), &predicates, InputPartitioner::new(Partitioning::RoundRobin, 0, 1))
+        .with_slide_coalescing(Some(Duration::from_millis(10)))
+        .with_clock(std::sync::Arc::new(clock))
+        .run(edges, &mut handler);
+
+    // the first slide exceeds its budget by 15ms, which the next 2 slides recover
+    assert_eq!(handler.slides[..2], [(11, 1, 0), (41, 3, 2)]);
+    assert_eq!(handler.slides.len(), 7);
+}
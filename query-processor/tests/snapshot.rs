@@ -0,0 +1,23 @@
+//! Tests that snapshots of a stream hold the tuples that are valid at the latest completed time
+use sgraffito_query::operator::snapshot::{PublishSnapshot, SnapshotCell};
+use sgraffito_query::test_support::{run_operator, sgt};
+
+#[test]
+fn snapshots_hold_valid_tuples() {
+    let cell = SnapshotCell::new();
+    let publisher = cell.clone();
+    let inputs = vec![
+        (1, sgt(1, 2, "a", 1, 3)),
+        (2, sgt(2, 3, "a", 2, 10)),
+        (4, sgt(3, 4, "a", 4, 10)),
+    ];
+    run_operator(inputs, move |stream| {
+        stream.publish_snapshots(publisher);
+        stream.clone()
+    });
+
+    // the tuple of the first epoch expired before the last one
+    let snapshot = cell.load();
+    assert_eq!(snapshot.epoch, 4);
+    assert_eq!(snapshot.tuples, vec![sgt(2, 3, "a", 2, 10), sgt(3, 4, "a", 4, 10)]);
+}
@@ -0,0 +1,31 @@
+//! Tests that the star join emits a combination of overlapping tuples of all streams that share their source
+use timely::dataflow::operators::Filter;
+
+use sgraffito_query::input::tuple::StarTuple;
+use sgraffito_query::operator::star_join::StarJoin;
+use sgraffito_query::test_support::{run_operator, sgt};
+use sgraffito_query::util::types::HalfOpenTimeInterval;
+
+#[test]
+fn combinations_of_overlapping_tuples() {
+    let inputs = vec![
+        (1, sgt(1, 2, "a", 1, 10)),
+        (2, sgt(1, 3, "b", 2, 8)),
+        (3, sgt(1, 4, "b", 3, 12)),
+        // no b tuple has the same center
+        (4, sgt(5, 6, "a", 4, 9)),
+        // the b tuples have expired
+        (12, sgt(1, 7, "a", 12, 20)),
+    ];
+    let outputs = run_operator(inputs, |stream| {
+        let a = stream.filter(|sgt| sgt.label == "a");
+        let b = stream.filter(|sgt| sgt.label == "b");
+        stream.scope().star_join(vec![a, b], "star".to_string())
+    });
+
+    let star = |leaves: Vec<u64>, start: u64, end: u64| StarTuple::new(1, leaves, "star".to_string(), HalfOpenTimeInterval::new(start, end));
+    assert_eq!(outputs.into_iter().collect::<Vec<_>>(), vec![
+        (2, vec![star(vec![2, 3], 2, 8)]),
+        (3, vec![star(vec![2, 4], 3, 10)]),
+    ]);
+}
@@ -0,0 +1,32 @@
+//! Tests that RPQ operators count visits of automaton states and transitions per window once counters are enabled
+use std::collections::BTreeMap;
+
+use sgraffito_query::operator::rpq::RegularPathQuery;
+use sgraffito_query::test_support::{run_operator, sgt};
+use sgraffito_query::util::state_counters;
+
+#[test]
+fn visits_are_counted_per_window() {
+    // operators read the registry when they are constructed
+    state_counters::enable();
+
+    // 1 -a-> 2 -b-> 3 traverses 0 -a-> 1 -b-> 2 of the automaton of `a/b` once each
+    let inputs = vec![(1, sgt(1, 2, "a", 1, 10)), (2, sgt(2, 3, "b", 2, 10))];
+    let outputs = run_operator(inputs, |stream| stream.regular_path_query("a/b", "q".to_string()));
+    assert_eq!(outputs.values().flatten().count(), 1);
+
+    let expected: BTreeMap<String, u64> = vec![
+        ("q.state.1".to_string(), 1),
+        ("q.state.2".to_string(), 1),
+        ("q.transition.0-1".to_string(), 1),
+        ("q.transition.1-2".to_string(), 1),
+    ].into_iter().collect();
+    assert_eq!(state_counters::complete_window(), expected);
+    assert_eq!(state_counters::snapshot().last_window, expected);
+    assert_eq!(state_counters::snapshot().total, expected);
+
+    // a window without visits clears the latest window but keeps the totals
+    assert!(state_counters::complete_window().is_empty());
+    assert!(state_counters::snapshot().last_window.is_empty());
+    assert_eq!(state_counters::snapshot().total, expected);
+}
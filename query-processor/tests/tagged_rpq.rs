@@ -0,0 +1,36 @@
+//! Tests that results of RPQs over a combined automaton carry the tag of the final state they reach
+use std::collections::{BTreeMap, HashSet};
+use std::iter::FromIterator;
+
+use sgraffito_query::operator::rpq::RegularPathQuery;
+use sgraffito_query::query::automata::dfa::DFA;
+use sgraffito_query::test_support::{run_operator, sgt};
+
+#[test]
+fn results_carry_final_tags() {
+    // a | a/b | a/b/c, where final states of `a` and `a/b` are tagged and the one of `a/b/c` is not
+    let mut dfa = DFA::new(4, HashSet::<u8>::from_iter(vec![1, 2, 3].into_iter()));
+    dfa.add_transition(0, 1, "a".to_string());
+    dfa.add_transition(1, 2, "b".to_string());
+    dfa.add_transition(2, 3, "c".to_string());
+    dfa.set_final_tag(1, "qa".to_string());
+    dfa.set_final_tag(2, "qab".to_string());
+
+    let inputs = vec![
+        (1, sgt(1, 2, "a", 1, 10)),
+        (2, sgt(2, 3, "b", 2, 10)),
+        (3, sgt(3, 4, "c", 3, 10)),
+    ];
+    let outputs = run_operator(inputs, move |stream| stream.tagged_regular_path_query(dfa, "q".to_string()));
+
+    let results: BTreeMap<u64, Vec<(u64, u64, String)>> = outputs.into_iter()
+        .map(|(epoch, results)| (epoch, results.into_iter().map(|result| (result.source, result.target, result.label)).collect()))
+        .collect();
+    let expected = vec![
+        (1, vec![(1, 2, "qa".to_string())]),
+        (2, vec![(1, 3, "qab".to_string())]),
+        // untagged final states fall back to the output label
+        (3, vec![(1, 4, "q".to_string())]),
+    ];
+    assert_eq!(results, expected.into_iter().collect());
+}
@@ -0,0 +1,39 @@
+//! Tests that temporal sequences match only paths whose edges start in order and within the duration
+use sgraffito_query::operator::sequence::TemporalSequence;
+use sgraffito_query::test_support::{run_operator, sgt};
+use sgraffito_query::util::gauges;
+
+#[test]
+fn ordered_sequences_within_duration() {
+    let inputs = vec![
+        (1, sgt(1, 2, "a", 1, 20)),
+        (2, sgt(2, 3, "b", 2, 20)),
+        (3, sgt(3, 4, "c", 3, 20)),
+        // the b edge starts before the a edge it follows
+        (4, sgt(6, 7, "b", 4, 20)),
+        (5, sgt(5, 6, "a", 5, 20)),
+        (6, sgt(7, 8, "c", 6, 20)),
+        // the c edge starts later than the duration after the a edge
+        (10, sgt(10, 11, "a", 10, 30)),
+        (12, sgt(11, 12, "b", 12, 30)),
+        (17, sgt(12, 13, "c", 17, 30)),
+    ];
+    let outputs = run_operator(inputs, |stream| stream.temporal_sequence("a/b/c", 5, "seq".to_string()));
+
+    assert_eq!(outputs.into_iter().collect::<Vec<_>>(), vec![(3, vec![sgt(1, 4, "seq", 3, 20)])]);
+}
+
+#[test]
+fn one_partial_match_per_origin_state_and_vertex() {
+    gauges::enable();
+    let inputs = vec![
+        (1, sgt(1, 2, "a", 1, 20)),
+        // the self-loop leads the match from 1 to the same state at 2, which is not kept as it starts no earlier
+        (2, sgt(2, 2, "b", 2, 20)),
+        (3, sgt(2, 3, "c", 3, 20)),
+    ];
+    let outputs = run_operator(inputs, |stream| stream.temporal_sequence("a/b*/c", 10, "loop".to_string()));
+
+    assert_eq!(outputs.into_iter().collect::<Vec<_>>(), vec![(3, vec![sgt(1, 3, "loop", 3, 20)])]);
+    assert_eq!(gauges::snapshot()["loop.partial-matches.0"], 2);
+}
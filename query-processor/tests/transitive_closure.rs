@@ -0,0 +1,45 @@
+//! Transitive closure of DD dataflows in both directions over the edges of label sets
+#![cfg(feature = "dd")]
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use sgraffito_query::dd::{reachability, Direction, WindowedInput};
+use sgraffito_query::input::{SGE, StreamingGraphEdge};
+
+/// pairs of the reachability of the edges with the given labels over all edges
+fn reachable_pairs(edges: Vec<(u64, &'static str, u64)>, labels: Vec<String>, direction: Direction) -> Vec<(u64, u64)> {
+    timely::execute(timely::Configuration::Thread, move |worker| {
+        let mut input = WindowedInput::sliding(100);
+        let results = Rc::new(RefCell::new(BTreeMap::new()));
+        let result_sink = results.clone();
+
+        let probe = worker.dataflow::<u64, _, _>(|scope| {
+            reachability(&input.to_collection(scope), &labels, direction)
+                .inspect(move |(pair, _, diff)| *result_sink.borrow_mut().entry(*pair).or_insert(0) += *diff)
+                .probe()
+        });
+
+        for (source, label, target) in edges.iter() {
+            input.insert(StreamingGraphEdge::new(*source, *target, label.to_string(), 1));
+        }
+        input.advance_to(2);
+        input.flush();
+        worker.step_while(|| probe.less_than(input.time()));
+
+        let pairs: Vec<_> = results.borrow().iter().filter(|(_, count)| **count > 0).map(|(pair, _)| *pair).collect();
+        pairs
+    }).unwrap().join().into_iter().next().unwrap().unwrap()
+}
+
+#[test]
+fn closure_of_label_set() {
+    let edges = vec![(1, "a", 2), (2, "b", 3), (3, "c", 4), (3, "a", 1)];
+    let labels = vec!["a".to_string(), "b".to_string()];
+
+    let forward = reachable_pairs(edges.clone(), labels.clone(), Direction::Forward);
+    assert_eq!(forward, vec![(1, 1), (1, 2), (1, 3), (2, 1), (2, 2), (2, 3), (3, 1), (3, 2), (3, 3)]);
+
+    let backward = reachable_pairs(vec![(1, "a", 2), (2, "b", 3), (3, "c", 4)], labels, Direction::Backward);
+    assert_eq!(backward, vec![(2, 1), (3, 1), (3, 2)]);
+}
@@ -0,0 +1,26 @@
+//! Node caps of spanning trees of RPQ operators, which runners configure from environment variables once per process
+use sgraffito_query::input::StreamingGraphEdge;
+use sgraffito_query::test_support::run_rpq;
+use sgraffito_query::util::types::{configure_from_env, EvictionPolicy, tree_node_cap, TREE_EVICTION_POLICY_VARIABLE, TREE_NODE_CAP_VARIABLE};
+
+#[test]
+fn node_cap_from_environment_bounds_spanning_trees() {
+    // a path 1 -> 2 -> 3 -> 4 -> 5, with one edge per slide
+    let edges: Vec<StreamingGraphEdge> = (1..5).map(|i| StreamingGraphEdge::new(i, i + 1, "a".to_string(), i)).collect();
+    let uncapped = run_rpq(edges.clone(), 100, 1, "a+");
+    assert!(uncapped[&4].contains(&(1, 4)));
+
+    std::env::set_var(TREE_NODE_CAP_VARIABLE, "1");
+    std::env::set_var(TREE_EVICTION_POLICY_VARIABLE, "LeastRecentlyExtended");
+    configure_from_env(100, 1);
+    assert_eq!(tree_node_cap(), Some((1, EvictionPolicy::LeastRecentlyExtended)));
+
+    // the tree of 1 evicts 2 along with 3 once 3 is attached, so that the path to 4 is missed,
+    // whereas results emitted before the eviction remain valid
+    let capped = run_rpq(edges, 100, 1, "a+");
+    assert!(capped[&4].contains(&(1, 3)));
+    assert!(!capped[&4].contains(&(1, 4)));
+    for (window, results) in capped.iter() {
+        assert!(results.is_subset(&uncapped[window]), "window {} has results that are not in the uncapped run", window);
+    }
+}
@@ -0,0 +1,41 @@
+//! Tests that tumbling windows expire edges at the end of the aligned period their timestamp is in
+use std::collections::BTreeMap;
+
+use sgraffito_query::input::{SGE, StreamingGraphEdge};
+use sgraffito_query::input::tuple::StreamingGraphTuple;
+use sgraffito_query::operator::window::{SlidingWindow, WindowSemantics};
+use sgraffito_query::test_support::{EpochOutputs, run_operator};
+
+const WINDOW: u64 = 10;
+
+#[test]
+fn expiry_on_boundaries() {
+    let semantics = WindowSemantics::Tumbling { window_size: WINDOW };
+    assert_eq!(semantics.expiry(0), 10);
+    assert_eq!(semantics.expiry(9), 10);
+    assert_eq!(semantics.expiry(10), 20);
+    assert_eq!(semantics.expiry(11), 20);
+    assert_eq!(semantics.expiry(19), 20);
+    assert_eq!(semantics.expiry(20), 30);
+}
+
+#[test]
+fn tuples_around_boundaries() {
+    let inputs = vec![0, 9, 10, 11, 19, 20].into_iter()
+        .map(|timestamp| (timestamp, StreamingGraphEdge::new(timestamp, timestamp + 1, "a".to_string(), timestamp)))
+        .collect();
+    let outputs: EpochOutputs<StreamingGraphTuple> = run_operator(inputs, |stream| stream.windowed(WindowSemantics::Tumbling { window_size: WINDOW }));
+
+    let intervals: BTreeMap<u64, Vec<(u64, u64)>> = outputs.into_iter()
+        .map(|(epoch, tuples)| (epoch, tuples.into_iter().map(|tuple| (tuple.interval.start, tuple.interval.end)).collect()))
+        .collect();
+    let expected = vec![
+        (0, vec![(0, 10)]),
+        (9, vec![(9, 10)]),
+        (10, vec![(10, 20)]),
+        (11, vec![(11, 20)]),
+        (19, vec![(19, 20)]),
+        (20, vec![(20, 30)]),
+    ];
+    assert_eq!(intervals, expected.into_iter().collect());
+}
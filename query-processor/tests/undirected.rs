@@ -0,0 +1,37 @@
+use sgraffito_query::ingest::{ReplayDriver, ReplayHandler, Slide};
+use sgraffito_query::input::{InputPartitioner, Partitioning, StreamingGraphEdge};
+use sgraffito_query::input::labels::LabelMapping;
+use sgraffito_query::util::phase::PhaseSchedule;
+
+#[derive(Default)]
+struct Collect {
+    sent: Vec<(u64, u64, u64)>,
+}
+
+impl ReplayHandler for Collect {
+    fn send(&mut self, sge: StreamingGraphEdge) {
+        self.sent.push((sge.source, sge.target, sge.timestamp));
+    }
+
+    fn slide(&mut self, _slide: &Slide) {}
+}
+
+#[test]
+fn undirected_labels() {
+    let edges = vec![
+        StreamingGraphEdge::new(1, 2, "knows".to_string(), 1),
+        StreamingGraphEdge::new(2, 3, "likes".to_string(), 2),
+        StreamingGraphEdge::new(4, 4, "knows".to_string(), 3),
+    ];
+    let predicates = vec!["knows".to_string(), "likes".to_string()];
+    let label_mapping = LabelMapping::new().undirected("knows");
+
+    let mut handler = Collect::default();
+    let summary = ReplayDriver::new(1, 10, PhaseSchedule::new(1, 0, None, 0), &predicates, InputPartitioner::new(Partitioning::RoundRobin, 0, 1))
+        .with_label_mapping(Some(&label_mapping))
+        .run(edges.into_iter(), &mut handler);
+
+    // self-loops are sent once
+    assert_eq!(handler.sent, vec![(1, 2, 1), (2, 1, 1), (2, 3, 2), (4, 4, 3)]);
+    assert_eq!(summary.sent, 4);
+}
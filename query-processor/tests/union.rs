@@ -0,0 +1,42 @@
+use std::sync::{Arc, Mutex};
+
+use timely::dataflow::operators::ToStream;
+
+use sgraffito_query::input::tuple::{StreamingGraphTuple, TaggedTuple};
+use sgraffito_query::operator::union::UnionWithTag;
+use sgraffito_query::sink::{ResultSink, SinkTo};
+use sgraffito_query::util::types::HalfOpenTimeInterval;
+
+/// sink that keeps tagged tuples in memory
+struct CollectingSink {
+    tuples: Arc<Mutex<Vec<TaggedTuple>>>,
+}
+
+impl ResultSink for CollectingSink {
+    fn write(&mut self, _time: u64, _tuples: &[StreamingGraphTuple]) {
+        panic!("Tagged streams are written with their tags");
+    }
+
+    fn write_tagged(&mut self, _time: u64, tuples: &[TaggedTuple]) {
+        self.tuples.lock().unwrap().extend_from_slice(tuples);
+    }
+}
+
+#[test]
+fn union_preserves_tags_and_intervals() {
+    let tuples = Arc::new(Mutex::new(Vec::new()));
+    let sink_tuples = tuples.clone();
+
+    timely::example(move |scope| {
+        let q1 = vec![StreamingGraphTuple::new(1, 2, "q1".to_string(), HalfOpenTimeInterval::new(1, 5))].to_stream(scope);
+        let q2 = vec![StreamingGraphTuple::new(3, 4, "q2".to_string(), HalfOpenTimeInterval::new(2, 6))].to_stream(scope);
+
+        scope.union_with_tag(vec![("first".to_string(), q1), ("second".to_string(), q2)])
+            .sink_to(CollectingSink { tuples: sink_tuples });
+    });
+
+    let mut tuples = tuples.lock().unwrap().clone();
+    tuples.sort();
+    assert_eq!(tuples.iter().map(|tagged| tagged.tag.as_str()).collect::<Vec<_>>(), vec!["first", "second"]);
+    assert_eq!(tuples[1].tuple.interval, HalfOpenTimeInterval::new(2, 6));
+}
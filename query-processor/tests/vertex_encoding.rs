@@ -0,0 +1,48 @@
+//! Tests that string vertex identifiers are encoded as configured and that collisions of hashed identifiers are detected
+use sgraffito_query::input::{InputFileReader, LineFileReader, StreamingGraphEdge, VertexDictionary, VertexEncoding};
+
+/// reads a file of string identifiers with the given encoding
+fn read_edges(vertex_encoding: VertexEncoding, dictionary: &VertexDictionary) -> Vec<StreamingGraphEdge> {
+    let path = std::env::temp_dir().join(format!("sgraffito-vertex-encoding-{:?}-{}.txt", vertex_encoding, std::process::id()));
+    std::fs::write(&path, "alice knows bob 1\nbob knows carol 2\ncarol knows alice 3\n").unwrap();
+    let edges = LineFileReader::open(path.to_str().unwrap(), true, false).unwrap()
+        .with_vertex_encoding(vertex_encoding, dictionary.clone())
+        .collect();
+    std::fs::remove_file(&path).unwrap();
+    edges
+}
+
+#[test]
+fn checked_hashes_resolve_to_identifiers() {
+    let dictionary = VertexDictionary::new();
+    let edges = read_edges(VertexEncoding::CheckedHash, &dictionary);
+    let names: Vec<(String, String)> = edges.iter().map(|sge| (dictionary.resolve(sge.source), dictionary.resolve(sge.target))).collect();
+    assert_eq!(names, vec![
+        ("alice".to_string(), "bob".to_string()),
+        ("bob".to_string(), "carol".to_string()),
+        ("carol".to_string(), "alice".to_string()),
+    ]);
+    assert_eq!(dictionary.collisions(), 0);
+}
+
+#[test]
+fn dense_identifiers_follow_first_appearance() {
+    let dictionary = VertexDictionary::new();
+    let edges: Vec<(u64, u64)> = read_edges(VertexEncoding::Dense, &dictionary).iter().map(|sge| (sge.source, sge.target)).collect();
+    assert_eq!(edges, vec![(0, 1), (1, 2), (2, 0)]);
+    assert_eq!(dictionary.resolve(2), "carol");
+}
+
+#[test]
+fn collisions_are_counted_once_per_identifier() {
+    let dictionary = VertexDictionary::new();
+    dictionary.insert(7, "alice");
+    dictionary.insert(7, "alice");
+    assert_eq!(dictionary.collisions(), 0);
+
+    dictionary.insert(7, "bob");
+    dictionary.insert(7, "bob");
+    assert_eq!(dictionary.collisions(), 1);
+    // the vertex keeps its first identifier
+    assert_eq!(dictionary.resolve(7), "alice");
+}
@@ -0,0 +1,31 @@
+//! Tests that vertex files resolve dense string identifiers through the dictionary of the readers
+use std::fs;
+
+use sgraffito_query::input::{InputFileReader, LineFileReader, VertexDictionary, VertexEncoding, VertexIds};
+use sgraffito_query::input::vertex_filter::VertexFilter;
+
+#[test]
+fn dense_identifiers_match_the_reader() {
+    let directory = std::env::temp_dir().join(format!("sgraffito-vertex-filter-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let (vertices, input) = (directory.join("vertices.txt"), directory.join("input.txt"));
+    fs::write(&vertices, "bob\ncarol\n").unwrap();
+    fs::write(&input, "alice knows bob\nbob knows carol\ncarol knows alice\n").unwrap();
+
+    // the filter is read before the stream, so that its vertices are assigned first
+    let dictionary = VertexDictionary::new();
+    let vertex_ids = VertexIds::new(false).with_vertex_encoding(VertexEncoding::Dense, dictionary.clone());
+    let filter = VertexFilter::from_file(vertices.to_str().unwrap(), &vertex_ids).unwrap();
+    let reader = LineFileReader::open(input.to_str().unwrap(), false, false).unwrap()
+        .with_vertex_encoding(VertexEncoding::Dense, dictionary.clone());
+
+    let retained: Vec<(String, String)> = reader.filter(|edge| filter.retains(edge))
+        .map(|edge| (dictionary.resolve(edge.source), dictionary.resolve(edge.target)))
+        .collect();
+    fs::remove_dir_all(&directory).unwrap();
+    assert_eq!(retained, vec![("bob".to_string(), "carol".to_string())]);
+
+    // integer identifiers are parsed and ranges are rejected for string identifiers
+    assert!(VertexFilter::from_spec("1..4", &VertexIds::new(false)).is_err());
+    assert!(VertexFilter::from_spec("1..4", &VertexIds::new(true)).unwrap().contains(3));
+}
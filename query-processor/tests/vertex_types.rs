@@ -0,0 +1,39 @@
+use sgraffito_query::input::{StreamingGraphEdge, VertexDictionary, VertexEncoding, VertexIds};
+use sgraffito_query::input::vertex_types::{split_typed_label, typed_label, VertexTypes};
+
+#[test]
+fn typed_labels() {
+    let mut vertex_types = VertexTypes::new();
+    vertex_types.insert(1, "Person");
+    vertex_types.insert(2, "Person");
+    vertex_types.insert(3, "Organisation");
+
+    let mut edge = StreamingGraphEdge::new(1, 3, "knows".to_string(), 10);
+    vertex_types.apply(&mut edge);
+    assert_eq!(edge.label, "Person:knows:Organisation");
+    assert_eq!(edge.label, typed_label("Person", "knows", "Organisation"));
+
+    // labels of edges with an untyped endpoint are kept
+    let mut edge = StreamingGraphEdge::new(2, 4, "knows".to_string(), 10);
+    vertex_types.apply(&mut edge);
+    assert_eq!(edge.label, "knows");
+}
+
+#[test]
+fn typed_labels_split_at_the_outer_separators() {
+    // labels may contain the separator, e.g., prefixed names
+    let label = typed_label("Person", "foaf:knows", "Organisation");
+    assert_eq!(split_typed_label(&label), Some(("Person", "foaf:knows", "Organisation")));
+    assert_eq!(split_typed_label("knows"), None);
+
+    // types cannot contain the separator, and dense identifiers are resolved through the dictionary of the readers
+    let path = std::env::temp_dir().join(format!("sgraffito-vertex-types-{}.txt", std::process::id()));
+    let dictionary = VertexDictionary::new();
+    let vertex_ids = VertexIds::new(false).with_vertex_encoding(VertexEncoding::Dense, dictionary.clone());
+    std::fs::write(&path, "alice schema:Person\n").unwrap();
+    assert!(VertexTypes::from_file(path.to_str().unwrap(), &vertex_ids).is_err());
+    std::fs::write(&path, "alice Person\n").unwrap();
+    let vertex_types = VertexTypes::from_file(path.to_str().unwrap(), &vertex_ids).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(vertex_types.get(dictionary.get_or_assign("alice")), Some("Person"));
+}
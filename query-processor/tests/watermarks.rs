@@ -0,0 +1,62 @@
+//! Tests that watermarks advance event time during input gaps and trigger slides of the replay driver
+use std::thread;
+use std::time::Duration;
+
+use sgraffito_query::ingest::{ReplayDriver, ReplayHandler, Slide};
+use sgraffito_query::input::{InputPartitioner, Partitioning, StreamEvent, StreamingGraphEdge};
+use sgraffito_query::input::watermark::WatermarkSource;
+use sgraffito_query::util::phase::PhaseSchedule;
+
+#[test]
+fn watermarks_follow_wall_clock_during_gaps() {
+    // the second edge arrives after a gap of more than a second
+    let source = vec![(1, Duration::default()), (2, Duration::from_millis(1300))].into_iter().map(|(ts, gap)| {
+        thread::sleep(gap);
+        StreamingGraphEdge::new(1, 2, "a".to_string(), ts)
+    });
+    let events: Vec<StreamEvent> = WatermarkSource::spawn(source, Duration::from_millis(100)).collect();
+
+    assert_eq!(events.first(), Some(&StreamEvent::Edge(StreamingGraphEdge::new(1, 2, "a".to_string(), 1))));
+    assert_eq!(events[events.len() - 2..], [StreamEvent::Edge(StreamingGraphEdge::new(1, 2, "a".to_string(), 2)), StreamEvent::EndOfStream]);
+
+    let watermarks: Vec<u64> = events.iter().filter_map(|event| match event {
+        StreamEvent::Watermark(watermark) => Some(*watermark),
+        _ => None,
+    }).collect();
+    assert!(watermarks.len() > 1);
+    assert!(watermarks.windows(2).all(|pair| pair[0] <= pair[1]));
+    // event time advances by a second once the gap exceeds a second
+    assert_eq!(watermarks.first(), Some(&1));
+    assert_eq!(watermarks.last(), Some(&2));
+}
+
+/// records the times of slides
+#[derive(Default)]
+struct SlideTimes(Vec<u64>);
+
+impl ReplayHandler for SlideTimes {
+    fn send(&mut self, _sge: StreamingGraphEdge) {}
+
+    fn slide(&mut self, slide: &Slide) {
+        self.0.push(slide.time);
+    }
+}
+
+#[test]
+fn watermarks_trigger_aligned_slides() {
+    let events = vec![
+        StreamEvent::Edge(StreamingGraphEdge::new(1, 2, "a".to_string(), 1)),
+        StreamEvent::Watermark(25),
+        StreamEvent::Edge(StreamingGraphEdge::new(2, 3, "a".to_string(), 26)),
+        StreamEvent::EndOfStream,
+    ];
+    let predicates = vec!["a".to_string()];
+
+    let mut handler = SlideTimes::default();
+    let summary = ReplayDriver::new(1, 10, PhaseSchedule::new(1, 0, None, 0), &predicates, InputPartitioner::new(Partitioning::RoundRobin, 0, 1))
+        .run_events(events.into_iter(), &mut handler);
+
+    // the watermark slides to the last slide boundary it passes, so that the next edge is in the same slide
+    assert_eq!(handler.0, vec![21]);
+    assert_eq!(summary.sent, 2);
+}
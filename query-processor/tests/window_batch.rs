@@ -0,0 +1,32 @@
+//! Tests that results are emitted per window as a batch stamped with the id of the window
+use sgraffito_query::input::tuple::StreamingGraphTuple;
+use sgraffito_query::operator::window_batch::WindowBatch;
+use sgraffito_query::test_support::{run_operator, sgt};
+
+fn window_batches(sorted: bool) -> Vec<(u64, Vec<StreamingGraphTuple>)> {
+    let inputs = vec![
+        (1, sgt(3, 4, "q", 1, 10)),
+        (1, sgt(1, 2, "q", 1, 10)),
+        (4, sgt(2, 5, "q", 4, 10)),
+    ];
+    run_operator(inputs, move |stream| stream.window_batch(sorted)).into_iter().collect()
+}
+
+/// the given tuple as it is emitted in the window with the given id
+fn in_window(mut tuple: StreamingGraphTuple, window_id: u64) -> StreamingGraphTuple {
+    tuple.window_id = window_id;
+    tuple
+}
+
+#[test]
+fn batches_carry_their_window_id() {
+    assert_eq!(window_batches(false), vec![
+        (1, vec![in_window(sgt(3, 4, "q", 1, 10), 1), in_window(sgt(1, 2, "q", 1, 10), 1)]),
+        (4, vec![in_window(sgt(2, 5, "q", 4, 10), 4)]),
+    ]);
+}
+
+#[test]
+fn sorted_batches() {
+    assert_eq!(window_batches(true)[0], (1, vec![in_window(sgt(1, 2, "q", 1, 10), 1), in_window(sgt(3, 4, "q", 1, 10), 1)]));
+}
@@ -0,0 +1,52 @@
+//! Tests that tuples carry the ids of the earliest and the latest slide of their base edges
+use timely::dataflow::operators::Filter;
+
+use sgraffito_query::input::{SGE, StreamingGraphEdge};
+use sgraffito_query::input::tuple::{SlideIds, StreamingGraphTuple};
+use sgraffito_query::operator::hash_join::{HashJoinAttributePair, SymmetricHashJoin};
+use sgraffito_query::operator::rpq::RegularPathQuery;
+use sgraffito_query::operator::window::SlidingWindow;
+use sgraffito_query::test_support::{EpochOutputs, run_operator};
+use sgraffito_query::util::types::set_window_ids;
+
+const WINDOW: u64 = 20;
+const SLIDE: u64 = 5;
+
+/// edges 1 -a-> 2 -b-> 3 -a-> 4 in slides 0, 1 and 2, respectively
+fn edges() -> Vec<(u64, StreamingGraphEdge)> {
+    vec![
+        (1, StreamingGraphEdge::new(1, 2, "a".to_string(), 1)),
+        (7, StreamingGraphEdge::new(2, 3, "b".to_string(), 7)),
+        (12, StreamingGraphEdge::new(3, 4, "a".to_string(), 12)),
+    ]
+}
+
+fn flatten(outputs: EpochOutputs<StreamingGraphTuple>) -> Vec<StreamingGraphTuple> {
+    outputs.into_iter().flat_map(|(_, tuples)| tuples).collect()
+}
+
+#[test]
+fn slide_ids_compose_by_min_and_max() {
+    set_window_ids(Some((WINDOW, SLIDE)));
+
+    let tuples = flatten(run_operator(edges(), |stream| stream.sliding_window(WINDOW)));
+    let slides: Vec<_> = tuples.iter().map(|sgt| sgt.slide_ids).collect();
+    assert_eq!(slides, vec![SlideIds::of_slide(0), SlideIds::of_slide(1), SlideIds::of_slide(2)]);
+    // window ids of per-window batches are not touched
+    assert!(tuples.iter().all(|sgt| sgt.window_id == 0));
+
+    // joins take the min and the max of both sides
+    let joined = flatten(run_operator(edges(), |stream| {
+        let window = stream.sliding_window(WINDOW);
+        let ab = window.filter(|sgt| sgt.label == "a" && sgt.source == 1)
+            .hash_join(&window.filter(|sgt| sgt.label == "b"), HashJoinAttributePair::TS, HashJoinAttributePair::ST, "ab".to_string());
+        ab.hash_join(&window.filter(|sgt| sgt.label == "a" && sgt.source == 3), HashJoinAttributePair::TS, HashJoinAttributePair::ST, "aba".to_string())
+    }));
+    assert_eq!(joined.iter().map(|sgt| (sgt.source, sgt.target, sgt.slide_ids)).collect::<Vec<_>>(), vec![(1, 4, SlideIds { first: 0, last: 2 })]);
+
+    // RPQ results span the slides of the edges of their path
+    let paths = flatten(run_operator(edges(), |stream| stream.sliding_window(WINDOW).regular_path_query("a/b/a", "rpq".to_string())));
+    assert_eq!(paths.iter().map(|sgt| (sgt.source, sgt.target, sgt.slide_ids)).collect::<Vec<_>>(), vec![(1, 4, SlideIds { first: 0, last: 2 })]);
+
+    set_window_ids(None);
+}